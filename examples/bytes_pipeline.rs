@@ -0,0 +1,90 @@
+//! Throughput of `Bytes` frames moving through two-actor pipeline.
+//!
+//! Producer sends 64KiB frames to relay actor, relay forwards them to sink
+//! actor, each actor runs in its own arbiter. Frames share one buffer,
+//! so the numbers show cost of message passing rather than memory copies.
+//!
+//! Run with `cargo run --release --features messages --example bytes_pipeline`
+
+extern crate actix;
+extern crate bytes;
+extern crate futures;
+
+use std::time::Instant;
+use bytes::Bytes;
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::messages::Data;
+
+const FRAME_SIZE: usize = 64 * 1024;
+const FRAMES: usize = 100_000;
+
+struct Relay {
+    sink: SyncAddress<Sink>,
+}
+
+impl Actor for Relay {
+    type Context = Context<Self>;
+}
+
+impl Handler<Data> for Relay {
+    type Result = ();
+
+    fn handle(&mut self, msg: Data, _: &mut Context<Self>) {
+        self.sink.do_send(msg);
+    }
+}
+
+struct Sink {
+    frames: usize,
+    bytes: usize,
+    start: Instant,
+}
+
+impl Actor for Sink {
+    type Context = Context<Self>;
+}
+
+impl Handler<Data> for Sink {
+    type Result = ();
+
+    fn handle(&mut self, msg: Data, _: &mut Context<Self>) {
+        self.frames += 1;
+        self.bytes += msg.0.len();
+
+        if self.frames == FRAMES {
+            let elapsed = self.start.elapsed();
+            let secs = elapsed.as_secs() as f64 + f64::from(elapsed.subsec_nanos()) / 1e9;
+            println!("{} frames of {} bytes in {:.3}s: {:.0} frames/s, {:.1} MiB/s",
+                     self.frames, FRAME_SIZE, secs,
+                     self.frames as f64 / secs, self.bytes as f64 / secs / 1024.0 / 1024.0);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        }
+    }
+}
+
+fn main() {
+    let sys = System::new("bytes-pipeline");
+
+    let sink: SyncAddress<_> = Sink::build()
+        .on_arbiter(&Arbiter::new("sink"))
+        .start(Sink{frames: 0, bytes: 0, start: Instant::now()});
+    let relay: SyncAddress<_> = Relay::build()
+        .on_arbiter(&Arbiter::new("relay"))
+        .start(Relay{sink: sink});
+
+    let frame = Bytes::from(vec![0u8; FRAME_SIZE]);
+    Arbiter::handle().spawn(
+        future::loop_fn(0, move |i| {
+            // `send()` waits if relay's mailbox is full
+            relay.send(Data(frame.clone())).map(move |_| {
+                if i + 1 == FRAMES {
+                    future::Loop::Break(())
+                } else {
+                    future::Loop::Continue(i + 1)
+                }
+            })
+        }).map_err(|err| panic!("relay is gone: {}", err)));
+
+    sys.run();
+}
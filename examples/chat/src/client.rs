@@ -42,7 +42,7 @@ fn main() {
                             return
                         }
 
-                        addr.send(ClientCommand(cmd));
+                        addr.do_send(ClientCommand(cmd));
                     }
                 });
 
@@ -78,7 +78,7 @@ impl Actor for ChatClient {
         println!("Disconnected");
 
         // Stop application on disconnect
-        Arbiter::system().send(actix::msgs::SystemExit(0));
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
 
         true
     }
@@ -86,9 +86,8 @@ impl Actor for ChatClient {
 
 impl ChatClient {
     fn hb(&self, ctx: &mut Context<Self>) {
-        ctx.run_later(Duration::new(1, 0), |act, ctx| {
+        ctx.run_interval(Duration::new(1, 0), |act, _| {
             act.framed.send(codec::ChatRequest::Ping);
-            act.hb(ctx);
         });
     }
 }
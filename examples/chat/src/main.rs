@@ -54,8 +54,9 @@ impl Handler<TcpConnect> for Server {
         let server = self.chat.clone();
         let _: () = ChatSession::create(
             move |ctx| {
-                let (reader, writer) = FramedReader::wrap(msg.0.framed(ChatCodec));
-                ChatSession::add_stream(reader, ctx);
+                // session stops once peer closes connection
+                let (reader, writer) = FramedReader::wrap_linked(msg.0.framed(ChatCodec));
+                reader.start(ctx);
                 ChatSession::new(server, writer)
             });
     }
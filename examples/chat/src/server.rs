@@ -3,9 +3,9 @@
 //! room through `ChatServer`.
 
 use std::cell::RefCell;
-use std::collections::{HashMap, HashSet};
 use rand::{self, Rng, ThreadRng};
 use actix::prelude::*;
+use actix::utils::Rooms;
 
 use session;
 
@@ -42,40 +42,27 @@ pub struct Message {
     pub room: String,
 }
 
-/// List of available rooms
-pub struct ListRooms;
-
-impl ResponseType for ListRooms {
-    type Item = Vec<String>;
-    type Error = ();
-}
-
-/// Join room, if room does not exists create new one.
-#[derive(Message)]
-pub struct Join {
-    /// Client id
-    pub id: usize,
-    /// Room name
-    pub name: String,
+actor_facade! {
+    /// Chat server operations that are available to sessions
+    pub trait ChatServerApi for ChatServer {
+        /// List of available rooms
+        fn list_rooms() -> Vec<String> as ListRooms;
+        /// Join room, if room does not exists create new one.
+        fn join(id: usize, name: String) as Join;
+    }
 }
 
 /// `ChatServer` manages chat rooms and responsible for coordinating chat session.
 /// implementation is super primitive
 pub struct ChatServer {
-    sessions: HashMap<usize, Address<session::ChatSession>>,
-    rooms: HashMap<String, HashSet<usize>>,
+    rooms: Rooms<session::Message>,
     rng: RefCell<ThreadRng>,
 }
 
 impl Default for ChatServer {
     fn default() -> ChatServer {
-        // default room
-        let mut rooms = HashMap::new();
-        rooms.insert("Main".to_owned(), HashSet::new());
-
         ChatServer {
-            sessions: HashMap::new(),
-            rooms: rooms,
+            rooms: Rooms::new(),
             rng: RefCell::new(rand::thread_rng()),
         }
     }
@@ -83,16 +70,8 @@ impl Default for ChatServer {
 
 impl ChatServer {
     /// Send message to all users in the room
-    fn send_message(&self, room: &str, message: &str, skip_id: usize) {
-        if let Some(sessions) = self.rooms.get(room) {
-            for id in sessions {
-                if *id != skip_id {
-                    if let Some(addr) = self.sessions.get(id) {
-                        addr.send(session::Message(message.to_owned()))
-                    }
-                }
-            }
-        }
+    fn send_message(&mut self, room: &str, message: &str, skip_id: Option<usize>) {
+        self.rooms.broadcast(room, session::Message(message.to_owned()), skip_id);
     }
 }
 
@@ -113,14 +92,11 @@ impl Handler<Connect> for ChatServer {
         println!("Someone joined");
 
         // notify all users in same room
-        self.send_message(&"Main".to_owned(), "Someone joined", 0);
+        self.send_message("Main", "Someone joined", None);
 
-        // register session with random id
+        // register session with random id and auto join it to Main room
         let id = self.rng.borrow_mut().gen::<usize>();
-        self.sessions.insert(id, msg.addr);
-
-        // auto join session to Main room
-        self.rooms.get_mut(&"Main".to_owned()).unwrap().insert(id);
+        self.rooms.join("Main", id, msg.addr.into_subscriber());
 
         // send id back
         Ok(id)
@@ -134,20 +110,9 @@ impl Handler<Disconnect> for ChatServer {
     fn handle(&mut self, msg: Disconnect, _: &mut Context<Self>) {
         println!("Someone disconnected");
 
-        let mut rooms: Vec<String> = Vec::new();
-
-        // remove address
-        if self.sessions.remove(&msg.id).is_some() {
-            // remove session from all rooms
-            for (name, sessions) in &mut self.rooms {
-                if sessions.remove(&msg.id) {
-                    rooms.push(name.to_owned());
-                }
-            }
-        }
-        // send message to other users
-        for room in rooms {
-            self.send_message(&room, "Someone disconnected", 0);
+        // remove session from all rooms and send message to other users
+        for room in self.rooms.leave_all(msg.id) {
+            self.send_message(&room, "Someone disconnected", None);
         }
     }
 }
@@ -157,7 +122,7 @@ impl Handler<Message> for ChatServer {
     type Result = ();
 
     fn handle(&mut self, msg: Message, _: &mut Context<Self>) {
-        self.send_message(&msg.room, msg.msg.as_str(), msg.id);
+        self.send_message(&msg.room, msg.msg.as_str(), Some(msg.id));
     }
 }
 
@@ -166,13 +131,7 @@ impl Handler<ListRooms> for ChatServer {
     type Result = MessageResult<ListRooms>;
 
     fn handle(&mut self, _: ListRooms, _: &mut Context<Self>) -> Self::Result {
-        let mut rooms = Vec::new();
-
-        for key in self.rooms.keys() {
-            rooms.push(key.to_owned())
-        }
-
-        Ok(rooms)
+        Ok(self.rooms.rooms())
     }
 }
 
@@ -183,23 +142,17 @@ impl Handler<Join> for ChatServer {
 
     fn handle(&mut self, msg: Join, _: &mut Context<Self>) {
         let Join {id, name} = msg;
-        let mut rooms = Vec::new();
-
-        // remove session from all rooms
-        for (n, sessions) in &mut self.rooms {
-            if sessions.remove(&id) {
-                rooms.push(n.to_owned());
-            }
-        }
-        // send message to other users
-        for room in rooms {
-            self.send_message(&room, "Someone disconnected", 0);
+        let subscr = match self.rooms.subscriber(id) {
+            Some(subscr) => subscr,
+            None => return,
+        };
+
+        // remove session from all rooms and send message to other users
+        for room in self.rooms.leave_all(id) {
+            self.send_message(&room, "Someone disconnected", None);
         }
 
-        if self.rooms.get_mut(&name).is_none() {
-            self.rooms.insert(name.clone(), HashSet::new());
-        }
-        self.send_message(&name, "Someone connected", id);
-        self.rooms.get_mut(&name).unwrap().insert(id);
+        self.send_message(&name, "Someone connected", Some(id));
+        self.rooms.join(&name, id, subscr);
     }
 }
@@ -3,12 +3,14 @@
 use std::time::{Instant, Duration};
 use tokio_core::net::TcpStream;
 use actix::prelude::*;
+use actix::ConnectionClosed;
 
-use server::{self, ChatServer};
+use server::{self, ChatServer, ChatServerApi};
 use codec::{ChatRequest, ChatResponse, ChatCodec};
 
 
 /// Chat server sends this messages to session
+#[derive(Clone)]
 pub struct Message(pub String);
 
 impl ResponseType for Message {
@@ -37,22 +39,26 @@ impl Actor for ChatSession {
         // we'll start heartbeat process on session start.
         self.hb(ctx);
 
-        // register self in chat server. `AsyncContext::wait` register
+        // register self in chat server. `AsyncContext::wait_timeout` register
         // future within context, but context waits until this future resolves
-        // before processing any other events.
-        self.addr.call(self, server::Connect{addr: ctx.address()}).then(|res, act, ctx| {
-            match res {
-                Ok(Ok(res)) => act.id = res,
-                // something is wrong with chat server
-                _ => ctx.stop(),
-            }
-            actix::fut::ok(())
-        }).wait(ctx);
+        // before processing any other events. Session stops if chat server
+        // does not respond within 5 seconds.
+        let fut = self.addr.call_fut(server::Connect{addr: ctx.address()})
+            .into_actor(self)
+            .then(|res, act, ctx| {
+                match res {
+                    Ok(Ok(res)) => act.id = res,
+                    // something is wrong with chat server
+                    _ => ctx.stop(),
+                }
+                actix::fut::ok(())
+            });
+        ctx.wait_timeout(fut, Duration::new(5, 0), |_, ctx| ctx.stop());
     }
 
     fn stopping(&mut self, _: &mut Self::Context) -> bool {
         // notify chat server
-        self.addr.send(server::Disconnect{id: self.id});
+        self.addr.do_send(server::Disconnect{id: self.id});
         true
     }
 }
@@ -66,27 +72,29 @@ impl StreamHandler<ChatRequest, FramedError<ChatCodec>> for ChatSession {
             ChatRequest::List => {
                 // Send ListRooms message to chat server and wait for response
                 println!("List rooms");
-                self.addr.call(self, server::ListRooms).then(|res, act, _| {
-                    match res {
-                        Ok(Ok(rooms)) =>
-                            act.framed.send(ChatResponse::Rooms(rooms)),
-                        _ => println!("Something is wrong"),
-                    }
-                    actix::fut::ok(())
-                }).wait(ctx)
+                self.addr.list_rooms()
+                    .into_actor(self)
+                    .then(|res, act, _| {
+                        match res {
+                            Ok(Ok(rooms)) =>
+                                act.framed.send(ChatResponse::Rooms(rooms)),
+                            _ => println!("Something is wrong"),
+                        }
+                        actix::fut::ok(())
+                    }).wait(ctx)
                 // .wait(ctx) pauses all events in context,
                 // so actor wont receive any new messages until it get list of rooms back
             },
             ChatRequest::Join(name) => {
                 println!("Join to room: {}", name);
                 self.room = name.clone();
-                self.addr.send(server::Join{id: self.id, name: name.clone()});
+                self.addr.join(self.id, name.clone());
                 self.framed.send(ChatResponse::Joined(name));
             },
             ChatRequest::Message(message) => {
                 // send message to chat server
                 println!("Peer message: {}", message);
-                self.addr.send(
+                self.addr.do_send(
                     server::Message{id: self.id,
                                     msg: message, room:
                                     self.room.clone()})
@@ -98,6 +106,15 @@ impl StreamHandler<ChatRequest, FramedError<ChatCodec>> for ChatSession {
     }
 }
 
+/// Peer closed connection, pending responses are flushed at this point
+impl Handler<ConnectionClosed<ChatCodec>> for ChatSession {
+    type Result = ();
+
+    fn handle(&mut self, _: ConnectionClosed<ChatCodec>, _: &mut Self::Context) {
+        println!("Peer disconnected");
+    }
+}
+
 /// Handler for Message, chat server sends this message, we just send string to peer
 impl Handler<Message> for ChatSession {
     type Result = ();
@@ -124,21 +141,20 @@ impl ChatSession {
     ///
     /// also this method check heartbeats from client
     fn hb(&self, ctx: &mut actix::Context<Self>) {
-        ctx.run_later(Duration::new(1, 0), |act, ctx| {
+        ctx.run_interval(Duration::new(1, 0), |act, ctx| {
             // check client heartbeats
             if Instant::now().duration_since(act.hb) > Duration::new(10, 0) {
                 // heartbeat timed out
                 println!("Client heartbeat failed, disconnecting!");
 
                 // notify chat server
-                act.addr.send(server::Disconnect{id: act.id});
+                act.addr.do_send(server::Disconnect{id: act.id});
 
                 // stop actor
                 ctx.stop();
             }
 
             act.framed.send(ChatResponse::Ping);
-            act.hb(ctx);
         });
     }
 }
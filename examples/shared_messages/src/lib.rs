@@ -0,0 +1,30 @@
+//! Messages shared with peers that do not run actors
+//!
+//! Crate depends on actix without `runtime` feature, so it builds
+//! without tokio and futures.
+#![no_std]
+
+#[macro_use]
+extern crate actix;
+
+use actix::{MailboxError, MessageResult, ResponseType};
+
+/// Request current value of the counter
+#[derive(Message)]
+#[rtype(usize)]
+pub struct Get;
+
+/// Add value to the counter
+#[derive(Message)]
+pub struct Add(pub usize);
+
+/// Reset counter, fails if counter is locked
+pub struct Reset;
+
+impl ResponseType for Reset {
+    type Item = ();
+    type Error = MailboxError;
+}
+
+/// Result of the `Reset` request
+pub type ResetResult = MessageResult<Reset>;
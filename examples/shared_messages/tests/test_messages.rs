@@ -0,0 +1,20 @@
+extern crate actix;
+extern crate shared_messages;
+
+use actix::{MailboxError, ResponseType};
+use shared_messages::{Add, Get, Reset, ResetResult};
+
+fn response<M: ResponseType>(_: &M, item: M::Item) -> M::Item {
+    item
+}
+
+#[test]
+fn test_core_messages() {
+    assert_eq!(response(&Get, 10), 10);
+    response(&Add(1), ());
+
+    let res: ResetResult = Err(MailboxError::Closed);
+    assert_eq!(res, Err(MailboxError::Closed));
+    assert_eq!(format!("{}", MailboxError::Closed), "Mailbox has closed");
+    assert!(Reset.render().is_none());
+}
@@ -0,0 +1,55 @@
+//! Support of `panic = "abort"` builds, see `System::set_abort_handler()`
+use std::panic::{self, AssertUnwindSafe, PanicInfo};
+use std::sync::{Mutex, Once};
+use std::thread;
+
+use log;
+
+/// Handler called from panic hook
+type AbortHandler = Box<Fn(&PanicInfo) + Send + Sync>;
+
+static HANDLER: Mutex<Option<AbortHandler>> = Mutex::new(None);
+static HOOK: Once = Once::new();
+static WARN: Once = Once::new();
+
+/// Check if panics unwind
+pub(crate) fn unwind_supported() -> bool {
+    cfg!(panic = "unwind")
+}
+
+/// Check if panic recovery `feature` is disabled, warns once per process
+pub(crate) fn recovery_disabled(feature: &str) -> bool {
+    if unwind_supported() {
+        return false
+    }
+    WARN.call_once(|| warn!("Panics abort the process, {} and other panic \
+                             recovery features are disabled", feature));
+    true
+}
+
+/// Run `f` and catch its panic, without unwinding `f` runs as is
+pub(crate) fn catch_unwind<F, R>(feature: &str, f: F) -> thread::Result<R>
+    where F: FnOnce() -> R
+{
+    if recovery_disabled(feature) {
+        Ok(f())
+    } else {
+        panic::catch_unwind(AssertUnwindSafe(f))
+    }
+}
+
+/// Replace abort handler, panic hook is installed on first call
+pub(crate) fn set_handler(handler: AbortHandler) {
+    *HANDLER.lock().unwrap_or_else(|err| err.into_inner()) = Some(handler);
+
+    HOOK.call_once(|| {
+        let prev = panic::take_hook();
+        panic::set_hook(Box::new(move |info| {
+            prev(info);
+            if let Some(ref handler) = *HANDLER.lock().unwrap_or_else(|err| err.into_inner()) {
+                handler(info);
+            }
+            log::logger().flush();
+        }));
+    });
+}
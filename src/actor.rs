@@ -1,14 +1,22 @@
-use std::time::Duration;
-use futures::{future, Future, Stream};
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+use futures::{future, Future, Poll, Stream};
+use futures::unsync::oneshot::{Canceled, Receiver};
 
 use fut::ActorFuture;
 use arbiter::Arbiter;
 use address::{Address, SyncAddress, ActorAddress, ToEnvelope};
+use builder::ActorBuilder;
+use cache::ResponseCache;
+use pipe::MessagePipes;
+use redelivery::Redelivery;
 use context::Context;
+use mailbox::MailboxReceiver;
 use handler::{Handler, ResponseType};
-use contextitems::{ActorFutureItem, ActorMessageItem,
-                   ActorDelayedMessageItem, ActorMessageStreamItem};
-use utils::TimerFunc;
+use contextitems::{ActorFutureItem, ActorMessageItem, ActorDelayedMessageItem,
+                   ActorIntervalMessageItem, ActorMessageStreamItem, ActorWaitTimeoutItem};
+use utils::{TimerFunc, IntervalFunc};
 
 
 #[allow(unused_variables)]
@@ -36,6 +44,16 @@ use utils::TimerFunc;
 ///
 /// Actor starts in `Started` state, during this state `started` method get called.
 ///
+/// Startup sequence of an actor running in `Context<A>` is following:
+///
+/// * `Actor::started()` get called
+/// * wait futures created with `Context::wait()` in `started()` complete
+/// * messages sent with `Context::notify()` from `create()` closure,
+///   `started()` method or from handlers of such messages get handled
+///   in the order they were sent
+/// * mailbox messages get handled
+/// * streams and futures added during startup get polled first time
+///
 /// ## Running
 ///
 /// After Actor's method `started` get called, actor transitions to `Running` state.
@@ -51,6 +69,8 @@ use utils::TimerFunc;
 ///
 /// Actor could restore from `stopping` state to `running` state by creating new
 /// address or adding evented object, like future or stream, in `Actor::stopping` method.
+/// Messages sent to an address created in `Actor::stopping` method get delivered
+/// if this method returns `false`.
 ///
 /// If actor changed state to a `stopping` state because of `Context::stop()` get called
 /// then context immediately stops processing incoming messages and calls
@@ -62,6 +82,12 @@ use utils::TimerFunc;
 /// If actor does not modify execution context during stooping state actor state changes
 /// to `Stopped`. This state is considered final and at this point actor get dropped.
 ///
+/// ## Hibernation
+///
+/// If hibernation is enabled, actor goes to hibernation after configured period
+/// of inactivity, `Actor::hibernate` method get called. Actor wakes up on next
+/// incoming message, `Actor::unhibernate` method get called before message get handled.
+///
 pub trait Actor: Sized + 'static {
 
     /// Actor execution context type
@@ -85,6 +111,16 @@ pub trait Actor: Sized + 'static {
     /// after this call actor get dropped.
     fn stopped(&mut self, ctx: &mut Self::Context) {}
 
+    /// Method is called when actor goes to hibernation. Hibernation is
+    /// enabled with `ActorBuilder::hibernate_after()` method.
+    ///
+    /// Actor could release any auxiliary buffers at this point.
+    fn hibernate(&mut self, ctx: &mut Self::Context) {}
+
+    /// Method is called when hibernated actor wakes up, before
+    /// any new message get handled.
+    fn unhibernate(&mut self, ctx: &mut Self::Context) {}
+
     /// Start new asynchronous actor, returns address of newly created actor.
     ///
     /// # Examples
@@ -111,6 +147,28 @@ pub trait Actor: Sized + 'static {
         addr
     }
 
+    /// Start new asynchronous actor, returns address of newly created actor
+    /// and future that resolves after actor's startup completes.
+    ///
+    /// Startup completes after `Actor::started()` returns and futures
+    /// registered with `AsyncContext::wait()` during startup resolve.
+    /// Future fails if actor stops before that, i.e. `started()` calls
+    /// `ctx.stop()`.
+    ///
+    /// ```rust,ignore
+    /// let (addr, started) = MyActor.start_notified::<Address<_>>();
+    /// started.map(move |_| addr.do_send(Ping))
+    /// ```
+    fn start_notified<Addr>(self) -> (Addr, StartedFuture)
+        where Self: Actor<Context=Context<Self>> + ActorAddress<Self, Addr>
+    {
+        let mut ctx = Context::new(Some(self));
+        let addr =  <Self as ActorAddress<Self, Addr>>::get(&mut ctx);
+        let started = StartedFuture(ctx.start_waiter());
+        ctx.run(Arbiter::handle());
+        (addr, started)
+    }
+
     /// Start new asynchronous actor, returns address of newly created actor.
     fn start_default<Addr>() -> Addr
         where Self: Default + Actor<Context=Context<Self>> + ActorAddress<Self, Addr>
@@ -118,6 +176,31 @@ pub trait Actor: Sized + 'static {
         Self::default().start()
     }
 
+    /// Create actor builder. Builder allows to configure actor's context
+    /// before actor starts.
+    ///
+    /// # Examples
+    ///
+    /// ```rust
+    /// use actix::*;
+    ///
+    /// // initialize system
+    /// System::new("test");
+    ///
+    /// struct MyActor;
+    /// impl Actor for MyActor {
+    ///     type Context = Context<Self>;
+    /// }
+    ///
+    /// let addr: Address<_> = MyActor::build()
+    ///     .mailbox_capacity(32)
+    ///     .name("my-actor")
+    ///     .start(MyActor);
+    /// ```
+    fn build() -> ActorBuilder<Self> where Self: Actor<Context=Context<Self>> {
+        ActorBuilder::new()
+    }
+
     /// Use `create` method, if you need `Context` object during actor initialization.
     ///
     /// # Examples
@@ -152,6 +235,60 @@ pub trait Actor: Sized + 'static {
         });
         addr
     }
+
+    /// Same as `create()`, actor uses provided receiver as its mailbox.
+    ///
+    /// Use `MailboxReceiver::channel()` to create detached channel pair,
+    /// messages pushed to the sender half get handled by the actor.
+    fn create_with_receiver<Addr, F>(rx: MailboxReceiver<Self>, f: F) -> Addr
+        where Self: Actor<Context=Context<Self>> + ActorAddress<Self, Addr>,
+              F: FnOnce(&mut Context<Self>) -> Self + 'static
+    {
+        let mut ctx = Context::with_receiver(None, rx.into_inner());
+        let addr =  <Self as ActorAddress<Self, Addr>>::get(&mut ctx);
+
+        Arbiter::handle().spawn_fn(move || {
+            let act = f(&mut ctx);
+            ctx.set_actor(act);
+            ctx.run(Arbiter::handle());
+            future::ok(())
+        });
+        addr
+    }
+
+    /// Same as `create()`, returns future that resolves after actor's
+    /// startup completes, see `Actor::start_notified()`.
+    fn create_notified<Addr, F>(f: F) -> (Addr, StartedFuture)
+        where Self: Actor<Context=Context<Self>> + ActorAddress<Self, Addr>,
+              F: FnOnce(&mut Context<Self>) -> Self + 'static
+    {
+        let mut ctx = Context::new(None);
+        let addr =  <Self as ActorAddress<Self, Addr>>::get(&mut ctx);
+        let started = StartedFuture(ctx.start_waiter());
+
+        Arbiter::handle().spawn_fn(move || {
+            let act = f(&mut ctx);
+            ctx.set_actor(act);
+            ctx.run(Arbiter::handle());
+            future::ok(())
+        });
+        (addr, started)
+    }
+}
+
+/// Future that resolves after actor's startup completes,
+/// see `Actor::start_notified()`
+///
+/// Future resolves to `Canceled` error if actor stops during startup.
+pub struct StartedFuture(Receiver<()>);
+
+impl Future for StartedFuture {
+    type Item = ();
+    type Error = Canceled;
+
+    fn poll(&mut self) -> Poll<(), Canceled> {
+        self.0.poll()
+    }
 }
 
 #[allow(unused_variables)]
@@ -168,6 +305,21 @@ pub trait Supervised: Actor {
 
     /// Method called when supervisor restarting failed actor
     fn restarting(&mut self, ctx: &mut <Self as Actor>::Context) {}
+
+    /// Serialize actor state, called during graceful stop
+    ///
+    /// Snapshot is saved to `StateStore` configured with
+    /// `ActorBuilder::state_store()`. `None` means nothing to save.
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        None
+    }
+
+    /// Restore actor state from snapshot saved by previous process
+    ///
+    /// Method is called after actor is constructed, before
+    /// `Actor::started()`. It is not called if snapshot is missing
+    /// or corrupted, in that case actor starts fresh.
+    fn restore(&mut self, snapshot: Vec<u8>) {}
 }
 
 /// Actor execution state
@@ -222,6 +374,24 @@ pub trait AsyncContext<A>: ActorContext + ToEnvelope<A> where A: Actor<Context=S
     /// Return `Address` of the context
     fn local_address(&mut self) -> Address<A>;
 
+    #[doc(hidden)]
+    /// Return response cache of the context
+    fn response_cache(&self) -> Option<Rc<RefCell<ResponseCache>>> {
+        None
+    }
+
+    #[doc(hidden)]
+    /// Return message pipes of the context
+    fn message_pipes(&self) -> Option<Rc<RefCell<MessagePipes>>> {
+        None
+    }
+
+    #[doc(hidden)]
+    /// Return message types that are redelivered after actor failure
+    fn redelivery(&self) -> Option<Rc<RefCell<Redelivery>>> {
+        None
+    }
+
     /// Spawn async future into context. Returns handle of the item,
     /// could be used for cancelling execution.
     ///
@@ -229,11 +399,51 @@ pub trait AsyncContext<A>: ActorContext + ToEnvelope<A> where A: Actor<Context=S
     fn spawn<F>(&mut self, fut: F) -> SpawnHandle
         where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static;
 
+    #[doc(hidden)]
+    /// Spawn future that belongs to context machinery, i.e. stream, timer
+    /// or message response. Such future ignores limit of spawned futures.
+    fn spawn_internal<F>(&mut self, fut: F) -> SpawnHandle
+        where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static
+    {
+        self.spawn(fut)
+    }
+
     /// Spawn future into the context. Stop processing any of incoming events
     /// until this future resolves.
+    ///
+    /// Streams registered with `add_stream()` are not polled while
+    /// context is waiting.
     fn wait<F>(&mut self, fut: F)
         where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static;
 
+    /// Spawn future into the context and wait its completion, but no
+    /// longer than `timeout`.
+    ///
+    /// If deadline passes first, pending future is dropped, which cancels
+    /// its outstanding requests, and `on_timeout` closure gets called.
+    /// Context continues processing incoming events either way, closure
+    /// could stop actor if it can not continue without future's result.
+    fn wait_timeout<F, T>(&mut self, fut: F, timeout: Duration, on_timeout: T)
+        where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static,
+              T: FnOnce(&mut A, &mut A::Context) + 'static
+    {
+        self.wait(ActorWaitTimeoutItem::new(fut, timeout, on_timeout))
+    }
+
+    /// Spawn future into the context. Stop processing incoming messages
+    /// until this future resolves.
+    ///
+    /// Unlike `wait()`, already spawned futures, streams and timers continue
+    /// running. Futures registered with `wait()` take precedence, while a wait
+    /// future is pending, exclusive future is not polled either.
+    ///
+    /// Default implementation uses `wait()`.
+    fn spawn_exclusive<F>(&mut self, fut: F)
+        where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static
+    {
+        self.wait(fut)
+    }
+
     /// Check if context is paused (waiting for future completion or stopping)
     fn waiting(&self) -> bool;
 
@@ -259,7 +469,7 @@ pub trait AsyncContext<A>: ActorContext + ToEnvelope<A> where A: Actor<Context=S
     ///
     ///     fn handle(&mut self, msg: Result<Ping, io::Error>, ctx: &mut Context<MyActor>) {
     ///         println!("PING");
-    /// #       Arbiter::system().send(actix::msgs::SystemExit(0));
+    /// #       Arbiter::system().do_send(actix::msgs::SystemExit(0));
     ///     }
     /// }
     ///
@@ -285,7 +495,7 @@ pub trait AsyncContext<A>: ActorContext + ToEnvelope<A> where A: Actor<Context=S
         if self.state() == ActorState::Stopped {
             error!("Context::add_future called for stopped actor.");
         } else {
-            self.spawn(ActorFutureItem::new(fut));
+            self.spawn_internal(ActorFutureItem::new(fut));
         }
     }
 
@@ -307,7 +517,7 @@ pub trait AsyncContext<A>: ActorContext + ToEnvelope<A> where A: Actor<Context=S
     ///
     ///     fn handle(&mut self, msg: Ping, ctx: &mut Context<MyActor>) {
     ///         println!("PING");
-    /// #       Arbiter::system().send(actix::msgs::SystemExit(0));
+    /// #       Arbiter::system().do_send(actix::msgs::SystemExit(0));
     ///     }
     /// }
     ///
@@ -333,7 +543,7 @@ pub trait AsyncContext<A>: ActorContext + ToEnvelope<A> where A: Actor<Context=S
         if self.state() == ActorState::Stopped {
             error!("Context::add_message_stream called for stopped actor.");
         } else {
-            self.spawn(ActorMessageStreamItem::new(fut));
+            self.spawn_internal(ActorMessageStreamItem::new(fut));
         }
     }
 
@@ -344,7 +554,7 @@ pub trait AsyncContext<A>: ActorContext + ToEnvelope<A> where A: Actor<Context=S
         if self.state() == ActorState::Stopped {
             error!("Context::add_timeout called for stopped actor.");
         } else {
-            self.spawn(ActorMessageItem::new(msg));
+            self.spawn_internal(ActorMessageItem::new(msg));
         }
     }
 
@@ -358,16 +568,55 @@ pub trait AsyncContext<A>: ActorContext + ToEnvelope<A> where A: Actor<Context=S
             error!("Context::add_timeout called for stopped actor.");
             SpawnHandle::default()
         } else {
-            self.spawn(ActorDelayedMessageItem::new(msg, after))
+            self.spawn_internal(ActorDelayedMessageItem::new(msg, after))
+        }
+    }
+
+    /// Send message `msg` to self periodically, every `interval` period of time.
+    /// Returns spawn handle which could be used for cancellation.
+    /// Notifications stop if context's stop method get called.
+    fn notify_interval<M>(&mut self, msg: M, interval: Duration) -> SpawnHandle
+        where A: Handler<M>, M: ResponseType + Clone + 'static
+    {
+        if self.state() == ActorState::Stopped {
+            error!("Context::notify_interval called for stopped actor.");
+            SpawnHandle::default()
+        } else {
+            self.spawn_internal(ActorIntervalMessageItem::new(msg, interval))
         }
     }
 
     /// Execute closure after specified period of time within same Actor and Context.
+    /// Returns spawn handle, pass it to `cancel_future()` to cancel execution.
     /// Execution get cancelled if context's stop method get called.
     fn run_later<F>(&mut self, dur: Duration, f: F) -> SpawnHandle
         where F: FnOnce(&mut A, &mut A::Context) + 'static
     {
-        self.spawn(TimerFunc::new(dur, f))
+        self.spawn_internal(TimerFunc::new(dur, f))
+    }
+
+    /// Execute closure periodically, every `dur` starting from now.
+    ///
+    /// Ticks fire at exact multiples of `dur`, closure execution time does not
+    /// cause drift, see `run_interval_at()`. Returns spawn handle which could be
+    /// used for cancellation. Execution get cancelled if context's stop method get called.
+    fn run_interval<F>(&mut self, dur: Duration, f: F) -> SpawnHandle
+        where F: FnMut(&mut A, &mut A::Context) + 'static
+    {
+        self.run_interval_at(Instant::now() + dur, dur, f)
+    }
+
+    /// Execute closure periodically, ticks are aligned to `start` instant.
+    ///
+    /// First tick fires at `start`, or at next `start + N * every` instant
+    /// if `start` is in the past. Following ticks fire at exact multiples of
+    /// `every`, so closure execution time does not cause drift. Ticks missed
+    /// while closure runs are skipped. Returns spawn handle which could be used
+    /// for cancellation. Execution get cancelled if context's stop method get called.
+    fn run_interval_at<F>(&mut self, start: Instant, every: Duration, f: F) -> SpawnHandle
+        where F: FnMut(&mut A, &mut A::Context) + 'static
+    {
+        self.spawn_internal(IntervalFunc::new(start, every, f))
     }
 }
 
@@ -391,3 +640,10 @@ impl Default for SpawnHandle {
         SpawnHandle(0)
     }
 }
+
+/// Timer group token, see `Context::timer_group()`
+///
+/// All timers registered within a group could be cancelled
+/// with single `Context::cancel_group()` call.
+#[derive(Eq, PartialEq, Debug, Copy, Clone, Hash)]
+pub struct TimerGroup(pub(crate) usize);
@@ -0,0 +1,233 @@
+//! Dead letters service
+//!
+//! Message that is sent with `do_send()` to an actor with closed mailbox
+//! becomes dead letter, as well as message that is left in mailbox once actor
+//! stops and message quarantined by supervised actor,
+//! see `RestartPolicy::KeepMailbox`. `DeadLetters` system service keeps last dead letters
+//! in a ring buffer, they could be queried with `GetDeadLetters` message.
+//! To receive dead letters as they arrive, send `Subscribe` message to
+//! `DeadLetters` service.
+//!
+//! Payload of the message is rendered only if message type overrides
+//! `ResponseType::render()` method.
+//!
+//! ```rust,ignore
+//! let letters = DeadLetters::from_registry().call_fut(GetDeadLetters);
+//! ```
+use std;
+use std::any::{type_name, TypeId};
+use std::cell::Cell;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread::{self, ThreadId};
+use std::collections::VecDeque;
+use std::time::Instant;
+
+use prelude::*;
+
+/// Default number of dead letters kept by `DeadLetters` service
+pub const DEFAULT_CAPACITY: usize = 64;
+
+/// Reason why message became dead letter
+#[derive(Clone, Copy, Debug, PartialEq)]
+pub enum DeadLetterReason {
+    /// Actor's mailbox is closed
+    Closed,
+    /// Actor stopped before message was handled
+    Stopped,
+    /// Send future got dropped while it was waiting for mailbox room
+    Dropped,
+    /// Message handler failed on every delivery attempt,
+    /// see `RestartPolicy::KeepMailbox`
+    Poisoned {
+        attempts: u32,
+    },
+}
+
+/// Message that could not be delivered or handled
+#[derive(Clone, Debug)]
+pub struct DeadLetter {
+    /// Type name of the message
+    pub message_type: &'static str,
+    /// Type name of the actor message was sent to
+    pub actor_type: &'static str,
+    /// Thread message was sent from
+    pub sender: ThreadId,
+    /// Time when message was sent
+    pub at: Instant,
+    /// Rendered message payload, see `ResponseType::render()`
+    pub rendered: Option<String>,
+    /// Reason why message became dead letter
+    pub reason: DeadLetterReason,
+}
+
+impl ResponseType for DeadLetter {
+    type Item = ();
+    type Error = ();
+}
+
+impl DeadLetter {
+    fn new<A: Actor, M: ResponseType>(msg: &M, reason: DeadLetterReason, sender: ThreadId)
+                                      -> DeadLetter
+    {
+        DeadLetter {
+            message_type: type_name::<M>(),
+            actor_type: type_name::<A>(),
+            sender: sender,
+            at: Instant::now(),
+            rendered: msg.render(),
+            reason: reason,
+        }
+    }
+}
+
+thread_local!(static PUBLISHING: Cell<bool> = Cell::new(false));
+
+/// Resets publishing flag of current thread, even if publishing panics
+struct PublishingGuard;
+
+impl Drop for PublishingGuard {
+    fn drop(&mut self) {
+        PUBLISHING.with(|p| p.set(false));
+    }
+}
+
+static PUBLISHED: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of dead letters produced since process start,
+/// including dropped ones
+pub fn published() -> usize {
+    PUBLISHED.load(Ordering::Relaxed)
+}
+
+/// Publish dead letter to `DeadLetters` service.
+///
+/// Dead letter is dropped if current thread does not run arbiter.
+/// Dead letters produced while publishing are dropped as well, i.e.
+/// if system arbiter is gone and service could not be started.
+pub(crate) fn publish<A: Actor, M: ResponseType + 'static>(msg: &M) {
+    publish_with_reason::<A, M>(msg, DeadLetterReason::Closed)
+}
+
+/// Publish dead letter with specific reason, see `publish()`
+pub(crate) fn publish_with_reason<A, M>(msg: &M, reason: DeadLetterReason)
+    where A: Actor, M: ResponseType + 'static
+{
+    publish_from::<A, M>(msg, reason, thread::current().id())
+}
+
+/// Publish dead letter of message sent from `sender` thread, see `publish()`
+///
+/// Undelivered dead letters are dropped, they do not become dead letters again.
+pub(crate) fn publish_from<A, M>(msg: &M, reason: DeadLetterReason, sender: ThreadId)
+    where A: Actor, M: ResponseType + 'static
+{
+    if TypeId::of::<M>() == TypeId::of::<DeadLetter>() {
+        return
+    }
+    PUBLISHED.fetch_add(1, Ordering::Relaxed);
+    if Arbiter::try_system().is_none() || PUBLISHING.with(|p| p.replace(true)) {
+        return
+    }
+    let _guard = PublishingGuard;
+    let addr = Arbiter::system_registry().get::<DeadLetters>();
+    // subscriber's send does not produce dead letters
+    let _ = actix::Subscriber::send(&addr, DeadLetter::new::<A, M>(msg, reason, sender));
+}
+
+/// System service that keeps last dead letters
+pub struct DeadLetters {
+    letters: VecDeque<DeadLetter>,
+    capacity: usize,
+    subscribers: Vec<Box<actix::Subscriber<DeadLetter> + Send>>,
+}
+
+impl Default for DeadLetters {
+    fn default() -> Self {
+        DeadLetters{letters: VecDeque::new(),
+                    capacity: DEFAULT_CAPACITY,
+                    subscribers: Vec::new()}
+    }
+}
+
+impl Actor for DeadLetters {
+    type Context = Context<Self>;
+}
+
+impl actix::Supervised for DeadLetters {}
+
+impl actix::SystemService for DeadLetters {}
+
+#[doc(hidden)]
+impl Handler<DeadLetter> for DeadLetters {
+    type Result = ();
+
+    fn handle(&mut self, letter: DeadLetter, _: &mut Self::Context) {
+        let subscribers = std::mem::replace(&mut self.subscribers, Vec::new());
+        for subscr in subscribers {
+            if subscr.send(letter.clone()).is_ok() {
+                self.subscribers.push(subscr);
+            }
+        }
+
+        if self.capacity > 0 {
+            if self.letters.len() == self.capacity {
+                self.letters.pop_front();
+            }
+            self.letters.push_back(letter);
+        }
+    }
+}
+
+/// Subscribe to dead letters.
+pub struct Subscribe(pub Box<actix::Subscriber<DeadLetter> + Send>);
+
+impl ResponseType for Subscribe {
+    type Item = ();
+    type Error = ();
+}
+
+/// Add subscriber for dead letters
+impl Handler<Subscribe> for DeadLetters {
+    type Result = ();
+
+    fn handle(&mut self, msg: Subscribe, _: &mut Self::Context) {
+        self.subscribers.push(msg.0);
+    }
+}
+
+/// Get last dead letters, oldest first.
+pub struct GetDeadLetters;
+
+impl ResponseType for GetDeadLetters {
+    type Item = Vec<DeadLetter>;
+    type Error = ();
+}
+
+impl Handler<GetDeadLetters> for DeadLetters {
+    type Result = MessageResult<GetDeadLetters>;
+
+    fn handle(&mut self, _: GetDeadLetters, _: &mut Self::Context) -> Self::Result {
+        Ok(self.letters.iter().cloned().collect())
+    }
+}
+
+/// Set number of dead letters kept by `DeadLetters` service.
+///
+/// Oldest dead letters are dropped if buffer is larger than new capacity.
+pub struct SetCapacity(pub usize);
+
+impl ResponseType for SetCapacity {
+    type Item = ();
+    type Error = ();
+}
+
+impl Handler<SetCapacity> for DeadLetters {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetCapacity, _: &mut Self::Context) {
+        self.capacity = msg.0;
+        while self.letters.len() > self.capacity {
+            self.letters.pop_front();
+        }
+    }
+}
@@ -0,0 +1,291 @@
+//! Metrics exporter service
+//!
+//! `MetricsExporter` system service collects metrics from registered
+//! sources and renders them in Prometheus text exposition format.
+//! Any type that implements `MetricsSource` could be registered with
+//! `RegisterSource` message. Framework sources, dead letters, abandoned
+//! responses and sync arbiters, are registered automatically.
+//!
+//! Rendered text could be requested with `GetMetricsText` message, or
+//! written to a connection with `WriteMetrics` message, so exporter could
+//! be wired to application's own listener.
+//!
+//! ```rust,ignore
+//! struct Sessions(Arc<AtomicUsize>);
+//!
+//! impl MetricsSource for Sessions {
+//!     fn collect(&self) -> Vec<Metric> {
+//!         vec![Metric::gauge("chat_sessions", self.0.load(Ordering::Relaxed) as f64)
+//!              .help("Connected sessions")
+//!              .label("room", "main")]
+//!     }
+//! }
+//!
+//! MetricsExporter::from_registry().do_send(RegisterSource(Box::new(Sessions(counter))));
+//! let text = MetricsExporter::from_registry().call_fut(GetMetricsText);
+//! ```
+use std::io;
+use std::fmt::Write;
+use std::net::TcpStream as StdTcpStream;
+use std::sync::{Arc, Weak};
+use std::sync::atomic::Ordering;
+
+use futures::{future, Future};
+use tokio_core::net::TcpStream;
+use tokio_io::io::write_all;
+
+use prelude::*;
+use handler::abandoned_responses;
+use sync::SyncStats;
+use super::deadletter;
+
+/// Metric type
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MetricKind {
+    /// Value that only grows
+    Counter,
+    /// Value that could go up and down
+    Gauge,
+}
+
+/// Single metric sample
+#[derive(Debug, Clone, PartialEq)]
+pub struct Metric {
+    /// Metric name, samples with same name are rendered as one metric family
+    pub name: String,
+    /// Help text of metric family
+    pub help: String,
+    /// Type of metric family
+    pub kind: MetricKind,
+    /// Label names and values
+    pub labels: Vec<(String, String)>,
+    /// Sample value
+    pub value: f64,
+}
+
+impl Metric {
+    /// Create counter sample
+    pub fn counter<T: Into<String>>(name: T, value: f64) -> Metric {
+        Metric{name: name.into(), help: String::new(),
+               kind: MetricKind::Counter, labels: Vec::new(), value: value}
+    }
+
+    /// Create gauge sample
+    pub fn gauge<T: Into<String>>(name: T, value: f64) -> Metric {
+        Metric{name: name.into(), help: String::new(),
+               kind: MetricKind::Gauge, labels: Vec::new(), value: value}
+    }
+
+    /// Set help text
+    pub fn help<T: Into<String>>(mut self, help: T) -> Metric {
+        self.help = help.into();
+        self
+    }
+
+    /// Add label
+    pub fn label<K: Into<String>, V: Into<String>>(mut self, name: K, value: V) -> Metric {
+        self.labels.push((name.into(), value.into()));
+        self
+    }
+}
+
+/// Source of metrics, see `RegisterSource`
+pub trait MetricsSource: Send {
+    /// Collect current values
+    fn collect(&self) -> Vec<Metric>;
+
+    /// Source is gone and could be dropped, i.e. sync arbiter stopped
+    fn expired(&self) -> bool {
+        false
+    }
+}
+
+/// Register metrics source with `MetricsExporter`
+pub struct RegisterSource(pub Box<MetricsSource>);
+
+impl ResponseType for RegisterSource {
+    type Item = ();
+    type Error = ();
+}
+
+/// Render metrics of all registered sources in Prometheus text format
+pub struct GetMetricsText;
+
+impl ResponseType for GetMetricsText {
+    type Item = String;
+    type Error = ();
+}
+
+/// Write rendered metrics to the stream, stream is closed afterwards
+pub struct WriteMetrics(pub StdTcpStream);
+
+impl ResponseType for WriteMetrics {
+    type Item = ();
+    type Error = io::Error;
+}
+
+/// System service that aggregates metrics sources
+pub struct MetricsExporter {
+    sources: Vec<Box<MetricsSource>>,
+}
+
+impl Default for MetricsExporter {
+    fn default() -> Self {
+        MetricsExporter{sources: vec![Box::new(FrameworkMetrics)]}
+    }
+}
+
+impl Actor for MetricsExporter {
+    type Context = Context<Self>;
+}
+
+impl actix::Supervised for MetricsExporter {}
+
+impl actix::SystemService for MetricsExporter {}
+
+impl MetricsExporter {
+    fn render(&mut self) -> String {
+        self.sources.retain(|source| !source.expired());
+        let metrics: Vec<_> = self.sources.iter().flat_map(|source| source.collect()).collect();
+        render(&metrics)
+    }
+}
+
+impl Handler<RegisterSource> for MetricsExporter {
+    type Result = ();
+
+    fn handle(&mut self, msg: RegisterSource, _: &mut Self::Context) {
+        self.sources.push(msg.0);
+    }
+}
+
+impl Handler<GetMetricsText> for MetricsExporter {
+    type Result = MessageResult<GetMetricsText>;
+
+    fn handle(&mut self, _: GetMetricsText, _: &mut Self::Context) -> Self::Result {
+        Ok(self.render())
+    }
+}
+
+impl Handler<WriteMetrics> for MetricsExporter {
+    type Result = ResponseFuture<WriteMetrics>;
+
+    fn handle(&mut self, msg: WriteMetrics, _: &mut Self::Context) -> Self::Result {
+        let text = self.render();
+        match TcpStream::from_stream(msg.0, Arbiter::handle()) {
+            Ok(stream) => Box::new(write_all(stream, text).map(|_| ())),
+            Err(err) => Box::new(future::err(err)),
+        }
+    }
+}
+
+/// Render metrics in Prometheus text exposition format
+///
+/// Samples with same name are grouped into one family, families keep
+/// order of their first sample.
+pub fn render(metrics: &[Metric]) -> String {
+    let mut names: Vec<&str> = Vec::new();
+    for metric in metrics {
+        if !names.contains(&metric.name.as_str()) {
+            names.push(&metric.name);
+        }
+    }
+
+    let mut text = String::new();
+    for name in names {
+        let mut family = metrics.iter().filter(|m| m.name == name).peekable();
+        if let Some(first) = family.peek() {
+            if !first.help.is_empty() {
+                let _ = writeln!(text, "# HELP {} {}", name, escape(&first.help, false));
+            }
+            let _ = writeln!(text, "# TYPE {} {}", name, match first.kind {
+                MetricKind::Counter => "counter",
+                MetricKind::Gauge => "gauge",
+            });
+        }
+        for metric in family {
+            text.push_str(name);
+            if !metric.labels.is_empty() {
+                text.push('{');
+                for (idx, &(ref label, ref value)) in metric.labels.iter().enumerate() {
+                    if idx != 0 {
+                        text.push(',');
+                    }
+                    let _ = write!(text, "{}=\"{}\"", label, escape(value, true));
+                }
+                text.push('}');
+            }
+            let _ = writeln!(text, " {}", metric.value);
+        }
+    }
+    text
+}
+
+/// Escape help text or label value
+fn escape(s: &str, quote: bool) -> String {
+    let mut escaped = String::with_capacity(s.len());
+    for c in s.chars() {
+        match c {
+            '\\' => escaped.push_str("\\\\"),
+            '\n' => escaped.push_str("\\n"),
+            '"' if quote => escaped.push_str("\\\""),
+            c => escaped.push(c),
+        }
+    }
+    escaped
+}
+
+/// Process wide framework counters
+struct FrameworkMetrics;
+
+impl MetricsSource for FrameworkMetrics {
+    fn collect(&self) -> Vec<Metric> {
+        vec![
+            Metric::counter("actix_dead_letters_total", deadletter::published() as f64)
+                .help("Messages that could not be delivered or handled"),
+            Metric::counter("actix_abandoned_responses_total", abandoned_responses() as f64)
+                .help("Async responses dropped because caller is gone"),
+        ]
+    }
+}
+
+/// Counters of a sync arbiter, registered when arbiter starts
+pub(crate) struct SyncArbiterMetrics {
+    stats: Weak<SyncStats>,
+}
+
+impl SyncArbiterMetrics {
+    pub(crate) fn new(stats: &Arc<SyncStats>) -> SyncArbiterMetrics {
+        SyncArbiterMetrics{stats: Arc::downgrade(stats)}
+    }
+}
+
+impl MetricsSource for SyncArbiterMetrics {
+    fn collect(&self) -> Vec<Metric> {
+        let stats = match self.stats.upgrade() {
+            Some(stats) => stats,
+            None => return Vec::new(),
+        };
+        let sample = |metric: Metric| metric
+            .label("actor", stats.actor)
+            .label("arbiter", stats.arbiter.as_str());
+
+        vec![
+            sample(Metric::gauge("actix_sync_arbiter_threads", stats.threads as f64)
+                   .help("Worker threads of sync arbiter")),
+            sample(Metric::gauge("actix_sync_arbiter_busy_threads",
+                                 stats.busy.load(Ordering::Relaxed) as f64)
+                   .help("Worker threads that handle message")),
+            sample(Metric::gauge("actix_sync_arbiter_queued_messages",
+                                 stats.queued.load(Ordering::Relaxed) as f64)
+                   .help("Messages waiting for free worker thread")),
+            sample(Metric::counter("actix_sync_arbiter_handled_total",
+                                   stats.handled.load(Ordering::Relaxed) as f64)
+                   .help("Messages handled by sync arbiter")),
+        ]
+    }
+
+    fn expired(&self) -> bool {
+        self.stats.upgrade().is_none()
+    }
+}
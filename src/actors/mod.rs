@@ -1,6 +1,12 @@
 //! Helper actors
 
 mod resolver;
+mod pool;
+pub mod deadletter;
 pub mod signal;
+#[cfg(feature="metrics")]
+pub mod metrics;
 
-pub use self::resolver::{Connect, Resolve, Connector, ConnectorError};
+pub use self::resolver::{Connect, Resolve, Connector, ConnectorError, SetSocketConfig};
+pub use self::pool::{Acquire, ConnectionPool, EndpointStats, PoolConfig, PoolStats,
+                     PooledStream, SetPoolConfig};
@@ -0,0 +1,467 @@
+//! Outbound connection pool
+//!
+//! `ConnectionPool` arbiter service keeps connections to upstream endpoints
+//! for reuse. Connections are established with `Connector`. Acquired
+//! `PooledStream` returns to the pool on drop, unless i/o error or end of
+//! stream was observed. Idle connection is probed on checkout, connection
+//! closed by the peer is discarded.
+//!
+//! ```rust,ignore
+//! let pool: Address<_> = actors::ConnectionPool::from_registry();
+//!
+//! pool.call_fut(actors::Acquire::new("127.0.0.1:8080", Duration::from_secs(1)))
+//!     .then(|res| match res {
+//!         Ok(Ok(stream)) => {
+//!             // `stream` goes back to the pool on drop
+//!             Ok(())
+//!         },
+//!         _ => Err(()),
+//!     })
+//! ```
+use std::{fmt, io, mem};
+use std::io::{Read, Write};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
+
+use futures::{future, Async, Future, Poll};
+use futures::unsync::oneshot::{channel, Receiver, Sender};
+use tokio_core::net::TcpStream;
+use tokio_core::reactor::Timeout;
+use tokio_io::{AsyncRead, AsyncWrite};
+
+use prelude::*;
+use fut::wrap_future;
+use super::resolver::{Connect, Connector, ConnectorError};
+
+/// Per-endpoint limits of the pool
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PoolConfig {
+    /// Maximum number of idle connections
+    pub max_idle: usize,
+    /// Maximum number of connections, idle and acquired
+    pub max_total: usize,
+    /// Idle connection is closed after this period
+    pub idle_ttl: Duration,
+}
+
+impl Default for PoolConfig {
+    fn default() -> PoolConfig {
+        PoolConfig{max_idle: 4, max_total: 16, idle_ttl: Duration::from_secs(60)}
+    }
+}
+
+impl PoolConfig {
+    /// Set maximum number of idle connections
+    pub fn max_idle(mut self, max: usize) -> Self {
+        self.max_idle = max;
+        self
+    }
+
+    /// Set maximum number of connections
+    pub fn max_total(mut self, max: usize) -> Self {
+        self.max_total = max;
+        self
+    }
+
+    /// Set idle connection time to live
+    pub fn idle_ttl(mut self, ttl: Duration) -> Self {
+        self.idle_ttl = ttl;
+        self
+    }
+}
+
+/// Acquire connection to the endpoint
+///
+/// Request waits for connection up to `timeout` if endpoint reached
+/// `max_total` connections, then it fails with `ConnectorError::Timeout`.
+pub struct Acquire {
+    /// Endpoint address, same as `Connect::host()`
+    pub endpoint: String,
+    /// Time limit of waiting for connection
+    pub timeout: Duration,
+}
+
+impl Acquire {
+    pub fn new<T: Into<String>>(endpoint: T, timeout: Duration) -> Acquire {
+        Acquire{endpoint: endpoint.into(), timeout: timeout}
+    }
+}
+
+impl ResponseType for Acquire {
+    type Item = PooledStream;
+    type Error = ConnectorError;
+}
+
+/// Set limits of the pool, see `PoolConfig`
+pub struct SetPoolConfig(pub PoolConfig);
+
+impl ResponseType for SetPoolConfig {
+    type Item = ();
+    type Error = ();
+}
+
+/// Query statistics of every endpoint
+pub struct PoolStats;
+
+impl ResponseType for PoolStats {
+    type Item = HashMap<String, EndpointStats>;
+    type Error = ();
+}
+
+/// Statistics of one endpoint, see `PoolStats`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct EndpointStats {
+    /// Idle connections
+    pub idle: usize,
+    /// Acquired connections and connections being established
+    pub active: usize,
+    /// Requests waiting for connection
+    pub waiting: usize,
+    /// Number of established connections
+    pub created: usize,
+    /// Number of times idle connection was handed out
+    pub reused: usize,
+}
+
+/// Connection acquired from `ConnectionPool`
+///
+/// Connection returns to the pool on drop. I/O error or end of stream
+/// marks connection as broken, broken connection gets closed instead.
+pub struct PooledStream {
+    stream: Option<TcpStream>,
+    endpoint: String,
+    broken: bool,
+    pool: Address<ConnectionPool>,
+}
+
+impl PooledStream {
+    fn new(stream: TcpStream, endpoint: String, pool: Address<ConnectionPool>) -> PooledStream {
+        PooledStream{stream: Some(stream), endpoint: endpoint, broken: false, pool: pool}
+    }
+
+    /// Endpoint of the connection
+    pub fn endpoint(&self) -> &str {
+        &self.endpoint
+    }
+
+    /// Reference to the underlying stream
+    pub fn get_ref(&self) -> &TcpStream {
+        self.stream.as_ref().unwrap()
+    }
+
+    /// Mutable reference to the underlying stream
+    ///
+    /// Errors of direct i/o are not tracked, use `discard()` if connection
+    /// should not be reused.
+    pub fn get_mut(&mut self) -> &mut TcpStream {
+        self.stream.as_mut().unwrap()
+    }
+
+    /// Close connection instead of returning it to the pool
+    pub fn discard(mut self) {
+        self.broken = true;
+    }
+
+    fn check<T>(&mut self, res: io::Result<T>) -> io::Result<T> {
+        if let Err(ref err) = res {
+            if err.kind() != io::ErrorKind::WouldBlock {
+                self.broken = true;
+            }
+        }
+        res
+    }
+}
+
+impl fmt::Debug for PooledStream {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "PooledStream({:?})", self.endpoint)
+    }
+}
+
+impl Read for PooledStream {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let res = self.stream.as_mut().unwrap().read(buf);
+        match self.check(res) {
+            Ok(0) if !buf.is_empty() => {
+                self.broken = true;
+                Ok(0)
+            }
+            res => res,
+        }
+    }
+}
+
+impl Write for PooledStream {
+    fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+        let res = self.stream.as_mut().unwrap().write(buf);
+        self.check(res)
+    }
+
+    fn flush(&mut self) -> io::Result<()> {
+        let res = self.stream.as_mut().unwrap().flush();
+        self.check(res)
+    }
+}
+
+impl AsyncRead for PooledStream {}
+
+impl AsyncWrite for PooledStream {
+    fn shutdown(&mut self) -> Poll<(), io::Error> {
+        self.broken = true;
+        AsyncWrite::shutdown(self.stream.as_mut().unwrap())
+    }
+}
+
+impl Drop for PooledStream {
+    fn drop(&mut self) {
+        if let Some(stream) = self.stream.take() {
+            let stream = if self.broken { None } else { Some(stream) };
+            self.pool.do_send(
+                Release{endpoint: mem::replace(&mut self.endpoint, String::new()),
+                        stream: stream});
+        }
+    }
+}
+
+/// Connection is dropped by its user, `None` if it is broken
+struct Release {
+    endpoint: String,
+    stream: Option<TcpStream>,
+}
+
+impl ResponseType for Release {
+    type Item = ();
+    type Error = ();
+}
+
+type Waiter = Sender<Result<PooledStream, ConnectorError>>;
+
+#[derive(Default)]
+struct Endpoint {
+    idle: VecDeque<(TcpStream, Instant)>,
+    waiters: VecDeque<Waiter>,
+    /// Idle, acquired and connecting connections
+    total: usize,
+    connecting: usize,
+    created: usize,
+    reused: usize,
+}
+
+impl Endpoint {
+    /// Pop first waiter that still waits for connection
+    fn waiter(&mut self) -> Option<Waiter> {
+        while let Some(tx) = self.waiters.pop_front() {
+            if !tx.is_canceled() {
+                return Some(tx)
+            }
+        }
+        None
+    }
+}
+
+/// Check if idle connection could be reused, idle stream
+/// must not be readable, readable stream is closed by the peer
+fn healthy(stream: &TcpStream) -> bool {
+    match stream.peek(&mut [0; 1]) {
+        Err(ref err) if err.kind() == io::ErrorKind::WouldBlock => true,
+        _ => false,
+    }
+}
+
+/// Connection pool arbiter service
+///
+/// Limits of `PoolConfig` apply to every endpoint separately.
+#[derive(Default)]
+pub struct ConnectionPool {
+    config: PoolConfig,
+    endpoints: HashMap<String, Endpoint>,
+}
+
+impl Actor for ConnectionPool {
+    type Context = Context<Self>;
+}
+
+impl Supervised for ConnectionPool {}
+
+impl actix::ArbiterService for ConnectionPool {}
+
+impl ConnectionPool {
+    /// Start connections for waiting requests, within endpoint's limit
+    fn refill(&mut self, endpoint: &str, ctx: &mut Context<Self>) {
+        let max = self.config.max_total;
+        let ep = match self.endpoints.get_mut(endpoint) {
+            Some(ep) => ep,
+            None => return,
+        };
+        ep.waiters.retain(|tx| !tx.is_canceled());
+
+        while ep.waiters.len() > ep.connecting && ep.total < max {
+            ep.total += 1;
+            ep.connecting += 1;
+
+            let endpoint = endpoint.to_owned();
+            let fut = Connector::from_registry().call_fut(Connect::host(&endpoint));
+            ctx.spawn(wrap_future::<_, Self>(fut).then(move |res, act, ctx| {
+                match res {
+                    Ok(Ok(stream)) => act.connected(endpoint, stream, ctx),
+                    Ok(Err(err)) => act.failed(&endpoint, err, ctx),
+                    Err(_) => act.failed(&endpoint, ConnectorError::NotRunning, ctx),
+                }
+                actix::fut::ok(())
+            }));
+        }
+    }
+
+    fn connected(&mut self, endpoint: String, stream: TcpStream, ctx: &mut Context<Self>) {
+        if let Some(ep) = self.endpoints.get_mut(&endpoint) {
+            ep.connecting -= 1;
+            ep.created += 1;
+        }
+        self.put(endpoint, stream, false, ctx);
+    }
+
+    fn failed(&mut self, endpoint: &str, err: ConnectorError, ctx: &mut Context<Self>) {
+        if let Some(ep) = self.endpoints.get_mut(endpoint) {
+            ep.connecting -= 1;
+            ep.total -= 1;
+            if let Some(tx) = ep.waiter() {
+                let _ = tx.send(Err(err));
+            }
+        }
+        self.refill(endpoint, ctx);
+    }
+
+    /// Hand connection to waiting request or keep it idle
+    fn put(&mut self, endpoint: String, stream: TcpStream, reused: bool, ctx: &mut Context<Self>) {
+        let (max_idle, ttl) = (self.config.max_idle, self.config.idle_ttl);
+        let ep = match self.endpoints.get_mut(&endpoint) {
+            Some(ep) => ep,
+            None => return,
+        };
+
+        let mut stream = stream;
+        while let Some(tx) = ep.waiter() {
+            let pooled = PooledStream::new(stream, endpoint.clone(), ctx.address());
+            match tx.send(Ok(pooled)) {
+                Ok(()) => {
+                    if reused {
+                        ep.reused += 1;
+                    }
+                    return
+                }
+                // request is gone, take stream back
+                Err(res) => stream = res.ok().and_then(|mut s| s.stream.take()).unwrap(),
+            }
+        }
+
+        if ep.idle.len() < max_idle {
+            ep.idle.push_back((stream, Instant::now()));
+            ctx.run_later(ttl, |act, _| act.expire());
+        } else {
+            ep.total -= 1;
+        }
+    }
+
+    /// Close idle connections that outlived ttl
+    fn expire(&mut self) {
+        let ttl = self.config.idle_ttl;
+        for ep in self.endpoints.values_mut() {
+            while ep.idle.front().map_or(false, |&(_, since)| since.elapsed() >= ttl) {
+                ep.idle.pop_front();
+                ep.total -= 1;
+            }
+        }
+    }
+}
+
+/// Future of `Acquire` request
+struct AcquireFut {
+    rx: Receiver<Result<PooledStream, ConnectorError>>,
+    timeout: Timeout,
+}
+
+impl Future for AcquireFut {
+    type Item = PooledStream;
+    type Error = ConnectorError;
+
+    fn poll(&mut self) -> Poll<PooledStream, ConnectorError> {
+        match self.rx.poll() {
+            Ok(Async::Ready(res)) => return res.map(Async::Ready),
+            Ok(Async::NotReady) => (),
+            Err(_) => return Err(ConnectorError::NotRunning),
+        }
+        match self.timeout.poll() {
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            _ => Err(ConnectorError::Timeout),
+        }
+    }
+}
+
+impl Handler<Acquire> for ConnectionPool {
+    type Result = ResponseFuture<Acquire>;
+
+    fn handle(&mut self, msg: Acquire, ctx: &mut Context<Self>) -> Self::Result {
+        let ttl = self.config.idle_ttl;
+        {
+            let ep = self.endpoints.entry(msg.endpoint.clone()).or_insert_with(Endpoint::default);
+
+            // most recently used connection first
+            while let Some((stream, since)) = ep.idle.pop_back() {
+                if since.elapsed() < ttl && healthy(&stream) {
+                    ep.reused += 1;
+                    return Box::new(future::ok(
+                        PooledStream::new(stream, msg.endpoint, ctx.address())))
+                }
+                ep.total -= 1;
+            }
+        }
+
+        let (tx, rx) = channel();
+        self.endpoints.get_mut(&msg.endpoint).unwrap().waiters.push_back(tx);
+        self.refill(&msg.endpoint, ctx);
+
+        match Timeout::new(msg.timeout, Arbiter::handle()) {
+            Ok(timeout) => Box::new(AcquireFut{rx: rx, timeout: timeout}),
+            Err(err) => Box::new(future::err(ConnectorError::IoError(err))),
+        }
+    }
+}
+
+impl Handler<Release> for ConnectionPool {
+    type Result = ();
+
+    fn handle(&mut self, msg: Release, ctx: &mut Context<Self>) {
+        match msg.stream {
+            Some(stream) => self.put(msg.endpoint, stream, true, ctx),
+            None => {
+                if let Some(ep) = self.endpoints.get_mut(&msg.endpoint) {
+                    ep.total -= 1;
+                }
+                self.refill(&msg.endpoint, ctx);
+            }
+        }
+    }
+}
+
+impl Handler<SetPoolConfig> for ConnectionPool {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetPoolConfig, _: &mut Context<Self>) {
+        self.config = msg.0;
+    }
+}
+
+impl Handler<PoolStats> for ConnectionPool {
+    type Result = MessageResult<PoolStats>;
+
+    fn handle(&mut self, _: PoolStats, _: &mut Context<Self>) -> Self::Result {
+        Ok(self.endpoints.iter().map(|(name, ep)| {
+            let waiting = ep.waiters.iter().filter(|tx| !tx.is_canceled()).count();
+            (name.clone(), EndpointStats{idle: ep.idle.len(),
+                                         active: ep.total - ep.idle.len(),
+                                         waiting: waiting,
+                                         created: ep.created,
+                                         reused: ep.reused})
+        }).collect())
+    }
+}
@@ -19,7 +19,7 @@
 //!             actors::Resolve::host("localhost"))       // <- resolve "localhost"
 //!                 .then(|addrs| {
 //!                     println!("RESULT: {:?}", addrs);
-//! #                   Arbiter::system().send(actix::msgs::SystemExit(0));
+//! #                   Arbiter::system().do_send(actix::msgs::SystemExit(0));
 //!                     Ok::<_, ()>(())
 //!                 })
 //!    });
@@ -51,6 +51,7 @@ use tokio_core::reactor::Timeout;
 use tokio_core::net::{TcpStream, TcpStreamNew};
 
 use prelude::*;
+use server::SocketConfig;
 
 
 pub struct Resolve {
@@ -75,14 +76,21 @@ impl ResponseType for Resolve {
 pub struct Connect {
     name: String,
     port: Option<u16>,
+    socket: Option<SocketConfig>,
 }
 
 impl Connect {
     pub fn host<T: AsRef<str>>(host: T) -> Connect {
-        Connect{name: host.as_ref().to_owned(), port: None}
+        Connect{name: host.as_ref().to_owned(), port: None, socket: None}
     }
     pub fn host_and_port<T: AsRef<str>>(host: T, port: u16) -> Connect {
-        Connect{name: host.as_ref().to_owned(), port: Some(port)}
+        Connect{name: host.as_ref().to_owned(), port: Some(port), socket: None}
+    }
+
+    /// Socket options of this connection, overrides connector's options
+    pub fn socket_config(mut self, config: SocketConfig) -> Connect {
+        self.socket = Some(config);
+        self
     }
 }
 
@@ -91,6 +99,14 @@ impl ResponseType for Connect {
     type Error = ConnectorError;
 }
 
+/// Set socket options of connected streams, see `SocketConfig`
+pub struct SetSocketConfig(pub SocketConfig);
+
+impl ResponseType for SetSocketConfig {
+    type Item = ();
+    type Error = ();
+}
+
 #[derive(Fail, Debug)]
 pub enum ConnectorError {
     /// Failed to resolve the hostname
@@ -108,10 +124,15 @@ pub enum ConnectorError {
     /// Connection io error
     #[fail(display = "{}", _0)]
     IoError(io::Error),
+
+    /// Connector is used outside of arbiter's thread
+    #[fail(display = "Arbiter is not running")]
+    NotRunning,
 }
 
 pub struct Connector {
     resolver: ResolverFuture,
+    socket: SocketConfig,
 }
 
 impl Actor for Connector {
@@ -136,7 +157,7 @@ impl Default for Connector {
                     Arbiter::handle())
             }
         };
-        Connector{resolver: resolver}
+        Connector{resolver: resolver, socket: SocketConfig::default()}
     }
 
     #[cfg(not(unix))]
@@ -145,7 +166,7 @@ impl Default for Connector {
             ResolverConfig::default(),
             ResolverOpts::default(),
             Arbiter::handle());
-        Connector{resolver: resolver}
+        Connector{resolver: resolver, socket: SocketConfig::default()}
     }
 }
 
@@ -161,9 +182,18 @@ impl Handler<Connect> for Connector {
     type Result = ResponseActFuture<Self, Connect>;
 
     fn handle(&mut self, msg: Connect, _: &mut Self::Context) -> Self::Result {
+        let socket = msg.socket.unwrap_or(self.socket);
         Box::new(
             Resolver::new(msg.name, msg.port.unwrap_or(0), &self.resolver)
-                .and_then(|addrs, _, _| TcpConnector::new(addrs)))
+                .and_then(move |addrs, _, _| TcpConnector::new(addrs).socket_config(socket)))
+    }
+}
+
+impl Handler<SetSocketConfig> for Connector {
+    type Result = ();
+
+    fn handle(&mut self, msg: SetSocketConfig, _: &mut Self::Context) {
+        self.socket = msg.0;
     }
 }
 
@@ -259,8 +289,9 @@ impl ActorFuture for Resolver {
 /// Tcp stream connector
 pub struct TcpConnector {
     addrs: VecDeque<SocketAddr>,
-    timeout: Timeout,
+    timeout: Option<Timeout>,
     stream: Option<TcpStreamNew>,
+    socket: SocketConfig,
 }
 
 impl TcpConnector {
@@ -273,7 +304,14 @@ impl TcpConnector {
         TcpConnector {
             addrs: addrs,
             stream: None,
-            timeout: Timeout::new(timeout, Arbiter::handle()).unwrap() }
+            timeout: Arbiter::try_handle().map(|h| Timeout::new(timeout, h).unwrap()),
+            socket: SocketConfig::default() }
+    }
+
+    /// Set socket options of connected stream
+    pub fn socket_config(mut self, config: SocketConfig) -> TcpConnector {
+        self.socket = config;
+        self
     }
 }
 
@@ -286,15 +324,23 @@ impl ActorFuture for TcpConnector {
             -> Poll<Self::Item, Self::Error>
     {
         // timeout
-        if let Ok(Async::Ready(_)) = self.timeout.poll() {
-            return Err(ConnectorError::Timeout)
+        match self.timeout {
+            Some(ref mut timeout) => if let Ok(Async::Ready(_)) = timeout.poll() {
+                return Err(ConnectorError::Timeout)
+            },
+            None => return Err(ConnectorError::NotRunning),
         }
 
         // connect
         loop {
             if let Some(new) = self.stream.as_mut() {
                 match new.poll() {
-                    Ok(Async::Ready(sock)) => return Ok(Async::Ready(sock)),
+                    Ok(Async::Ready(sock)) => {
+                        if self.socket != SocketConfig::default() {
+                            self.socket.apply(&sock).map_err(ConnectorError::IoError)?;
+                        }
+                        return Ok(Async::Ready(sock))
+                    }
                     Ok(Async::NotReady) => return Ok(Async::NotReady),
                     Err(err) => {
                         if self.addrs.is_empty() {
@@ -25,18 +25,18 @@
 //!         match msg.0 {
 //!             signal::SignalType::Int => {
 //!                 println!("SIGINT received, exiting");
-//!                 Arbiter::system().send(actix::msgs::SystemExit(0));
+//!                 Arbiter::system().do_send(actix::msgs::SystemExit(0));
 //!             },
 //!             signal::SignalType::Hup => {
 //!                 println!("SIGHUP received, reloading");
 //!             },
 //!             signal::SignalType::Term => {
 //!                 println!("SIGTERM received, stopping");
-//!                 Arbiter::system().send(actix::msgs::SystemExit(0));
+//!                 Arbiter::system().do_send(actix::msgs::SystemExit(0));
 //!             },
 //!             signal::SignalType::Quit => {
 //!                 println!("SIGQUIT received, exiting");
-//!                 Arbiter::system().send(actix::msgs::SystemExit(0));
+//!                 Arbiter::system().do_send(actix::msgs::SystemExit(0));
 //!             }
 //!             _ => (),
 //!         }
@@ -53,7 +53,7 @@
 //!    // send SIGTERM
 //!    std::thread::spawn(move || {
 //!       // emulate SIGNTERM
-//!       addr.send(signal::Signal(signal::SignalType::Term));
+//!       addr.do_send(signal::Signal(signal::SignalType::Term));
 //!    });
 //!
 //!    // Run system, this function blocks until system runs
@@ -208,9 +208,9 @@ impl Actor for DefaultSignalsHandler {
     fn started(&mut self, ctx: &mut Self::Context) {
         let addr = Arbiter::system_registry().get::<ProcessSignals>();
         let slf: SyncAddress<_> = ctx.address();
-        addr.call(self, Subscribe(slf.into_subscriber()))
-            .map(|_, _, _| ())
-            .map_err(|_, _, _| ())
+        addr.call_fut(Subscribe(slf.into_subscriber()))
+            .into_actor(self)
+            .then(|_, _, _| actix::fut::ok(()))
             .wait(ctx)
     }
 }
@@ -224,18 +224,18 @@ impl actix::Handler<Signal> for DefaultSignalsHandler {
         match msg.0 {
             SignalType::Int => {
                 info!("SIGINT received, exiting");
-                Arbiter::system().send(actix::msgs::SystemExit(0));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
             }
             SignalType::Hup => {
                 info!("SIGHUP received, reloading");
             }
             SignalType::Term => {
                 info!("SIGTERM received, stopping");
-                Arbiter::system().send(actix::msgs::SystemExit(0));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
             }
             SignalType::Quit => {
                 info!("SIGQUIT received, exiting");
-                Arbiter::system().send(actix::msgs::SystemExit(0));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
             }
             _ => (),
         }
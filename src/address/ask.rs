@@ -0,0 +1,55 @@
+use std::any::Any;
+use std::sync::{Arc, Mutex};
+
+use futures::sync::oneshot::{channel, Sender, Receiver};
+
+use super::{SendError, Subscriber};
+
+/// Create single-use reply subscriber and future that resolves with the reply
+///
+/// Subscriber could be embedded into any message, i.e. for apis
+/// that expect reply-to subscriber instead of response channel.
+/// Only first message is accepted, following sends return `SendError::Closed`.
+/// Future fails with `Canceled` if subscriber gets dropped without reply.
+///
+/// ```rust,ignore
+/// let (reply_to, reply) = actix::ask::<Reply>();
+/// addr.do_send(Request{reply_to: reply_to});
+/// reply.and_then(|reply| ...)
+/// ```
+pub fn ask<R: Send + 'static>() -> (Box<Subscriber<R> + Send>, Receiver<R>) {
+    let (tx, rx) = channel();
+    (Box::new(AskSubscriber{tx: Arc::new(Mutex::new(Some(tx)))}), rx)
+}
+
+/// Subscriber backed by oneshot channel, clones share same channel
+struct AskSubscriber<R> {
+    tx: Arc<Mutex<Option<Sender<R>>>>,
+}
+
+impl<R: Send + 'static> Subscriber<R> for AskSubscriber<R> {
+    fn send(&self, msg: R) -> Result<(), SendError<R>> {
+        match self.tx.lock().unwrap().take() {
+            Some(tx) => tx.send(msg).map_err(SendError::Closed),
+            None => Err(SendError::Closed(msg)),
+        }
+    }
+
+    fn try_send(&self, msg: R) -> Result<(), SendError<R>> {
+        self.send(msg)
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    fn subscriber_eq(&self, other: &Subscriber<R>) -> bool {
+        other.as_any().downcast_ref::<AskSubscriber<R>>()
+            .map_or(false, |other| Arc::ptr_eq(&self.tx, &other.tx))
+    }
+
+    #[doc(hidden)]
+    fn boxed(&self) -> Box<Subscriber<R>> {
+        Box::new(AskSubscriber{tx: Arc::clone(&self.tx)})
+    }
+}
@@ -1,9 +1,15 @@
+use std::any::{type_name, Any};
 use std::marker::PhantomData;
+use std::thread::{self, ThreadId};
+use std::time::Instant;
 use futures::sync::oneshot::Sender;
 
 use actor::{Actor, AsyncContext};
 use context::Context;
-use handler::{Handler, ResponseType, MessageResult, MessageResponse};
+use handler::{Handler, ResponseType, MessageResponse, MessageResult};
+use pipe;
+use redelivery;
+use actors::deadletter::{self, DeadLetterReason};
 
 /// Converter trait, packs message to suitable envelope
 pub trait ToEnvelope<A: Actor> {
@@ -13,6 +19,19 @@ pub trait ToEnvelope<A: Actor> {
         where A: Handler<M>,
               M: ResponseType + Send + 'static,
               M::Item: Send, M::Error: Send;
+
+    /// Pack message that does not expect response, result of the message
+    /// never leaves actor's thread
+    ///
+    /// Default implementation calls actor's handler and drops the result.
+    fn pack_msg<M>(msg: M) -> Envelope<A>
+        where A: Handler<M>,
+              M: ResponseType + Send + 'static
+    {
+        Envelope::new(MessageEnvelope{msg: Some(msg),
+                                      sender: thread::current().id(),
+                                      act: PhantomData})
+    }
 }
 
 impl<A> ToEnvelope<A> for Context<A> where A: Actor<Context=Context<A>>
@@ -21,14 +40,25 @@ impl<A> ToEnvelope<A> for Context<A> where A: Actor<Context=Context<A>>
         where A: Handler<M>,
               M: ResponseType + 'static, M::Item: Send, M::Error: Send,
     {
-        Envelope(Box::new(
+        Envelope::new(
             RemoteEnvelope{msg: Some(msg),
                            tx: tx,
-                           act: PhantomData}))
+                           sender: thread::current().id(),
+                           act: PhantomData})
+    }
+
+    fn pack_msg<M>(msg: M) -> Envelope<A>
+        where A: Handler<M>, M: ResponseType + Send + 'static,
+    {
+        Envelope::new(
+            RemoteEnvelope{msg: Some(msg),
+                           tx: None,
+                           sender: thread::current().id(),
+                           act: PhantomData})
     }
 }
 
-pub struct Envelope<A>(Box<EnvelopeProxy<Actor=A>>);
+pub struct Envelope<A>(Box<EnvelopeProxy<Actor=A>>, Instant);
 
 impl<A> Envelope<A> where A: Actor {
 
@@ -36,12 +66,25 @@ impl<A> Envelope<A> where A: Actor {
     pub(crate) fn new<T>(envelop: T) -> Self
         where T: EnvelopeProxy<Actor=A> + Sized + 'static
     {
-        Envelope(Box::new(envelop))
+        Envelope(Box::new(envelop), Instant::now())
+    }
+
+    /// Time when envelope got created, i.e. message got sent
+    pub(crate) fn enqueued(&self) -> Instant {
+        self.1
     }
 
     pub(crate) fn handle(&mut self, act: &mut A, ctx: &mut A::Context) {
         self.0.handle(act, ctx)
     }
+
+    pub(crate) fn type_name(&self) -> &'static str {
+        self.0.type_name()
+    }
+
+    pub(crate) fn into_inner(self) -> Box<EnvelopeProxy<Actor=A>> {
+        self.0
+    }
 }
 
 // This is not safe! Local envelope could be send to different thread!
@@ -53,12 +96,36 @@ pub trait EnvelopeProxy {
 
     /// handle message within new actor and context
     fn handle(&mut self, act: &mut Self::Actor, ctx: &mut <Self::Actor as Actor>::Context);
+
+    /// Type name of the message
+    fn type_name(&self) -> &'static str {
+        type_name::<Self>()
+    }
+
+    /// Publish message as dead letter, handler of the message failed
+    /// `attempts` times
+    #[allow(unused_variables)]
+    fn quarantine(&mut self, attempts: u32) {}
+
+    /// Publish message as dead letter
+    #[allow(unused_variables)]
+    fn dead_letter(&mut self, reason: DeadLetterReason) {}
+
+    /// Take message and its response channel out of the envelope,
+    /// used by mailbox handoff, see `Handoff`
+    ///
+    /// Value is a `(M, Option<Sender<MessageResult<M>>>)` tuple, sender is
+    /// `futures::sync` or `futures::unsync` oneshot depends on envelope.
+    fn take_message(&mut self) -> Option<Box<Any>> {
+        None
+    }
 }
 
 pub struct RemoteEnvelope<A, M> where M: ResponseType {
     act: PhantomData<A>,
     msg: Option<M>,
     tx: Option<Sender<MessageResult<M>>>,
+    sender: ThreadId,
 }
 
 impl<A, M> From<RemoteEnvelope<A, M>> for Envelope<A>
@@ -79,6 +146,7 @@ impl<A, M> RemoteEnvelope<A, M> where A: Actor, M: ResponseType {
     {
         RemoteEnvelope{msg: Some(msg),
                        tx: tx,
+                       sender: thread::current().id(),
                        act: PhantomData}
     }
 }
@@ -95,9 +163,60 @@ impl<A, M> EnvelopeProxy for RemoteEnvelope<A, M>
             return
         }
 
+        if let Some(msg) = redelivery::take::<A, M>(&mut self.msg, ctx) {
+            pipe::handle(act, msg, ctx, tx)
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        type_name::<M>()
+    }
+
+    fn quarantine(&mut self, attempts: u32) {
+        if let Some(msg) = self.msg.take() {
+            deadletter::publish_from::<A, M>(
+                &msg, DeadLetterReason::Poisoned{attempts: attempts}, self.sender);
+        }
+    }
+
+    fn dead_letter(&mut self, reason: DeadLetterReason) {
+        if let Some(msg) = self.msg.take() {
+            deadletter::publish_from::<A, M>(&msg, reason, self.sender);
+        }
+    }
+
+    fn take_message(&mut self) -> Option<Box<Any>> {
+        let tx = self.tx.take();
+        self.msg.take().map(|msg| Box::new((msg, tx)) as Box<Any>)
+    }
+}
+
+/// Envelope of a message without response channel, works with any context
+struct MessageEnvelope<A, M> {
+    act: PhantomData<A>,
+    msg: Option<M>,
+    sender: ThreadId,
+}
+
+impl<A, M> EnvelopeProxy for MessageEnvelope<A, M>
+    where M: ResponseType + 'static, A: Actor + Handler<M>,
+{
+    type Actor = A;
+
+    fn handle(&mut self, act: &mut A, ctx: &mut A::Context) {
+        if let Some(msg) = self.msg.take() {
+            let response = <A as Handler<M>>::handle(act, msg, ctx);
+            response.handle::<Sender<MessageResult<M>>>(ctx, None)
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        type_name::<M>()
+    }
+
+    fn dead_letter(&mut self, reason: DeadLetterReason) {
         if let Some(msg) = self.msg.take() {
-            let fut = <Self::Actor as Handler<M>>::handle(act, msg, ctx);
-            fut.handle(ctx, tx)
+            deadletter::publish_from::<A, M>(&msg, reason, self.sender);
         }
     }
 }
@@ -0,0 +1,196 @@
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::{HashMap, VecDeque};
+use std::time::Duration;
+
+use futures::{Async, Future, Poll};
+use futures::unsync::oneshot::{channel, Receiver, Sender};
+use tokio_core::reactor::Timeout;
+
+use arbiter::Arbiter;
+use super::{SendError, Subscriber};
+
+/// Default grace period of `Subscriber::send_lazy()`
+pub(crate) const LAZY_SEND_GRACE: Duration = Duration::from_secs(1);
+
+thread_local!(
+    static QUEUES: RefCell<HashMap<usize, Rc<RefCell<LazyQueue>>>> =
+        RefCell::new(HashMap::new());
+);
+
+/// Outcome of `Subscriber::send_lazy()`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum LazySend {
+    /// Message is in actor's mailbox
+    Delivered,
+    /// Mailbox did not get room within grace period, message is dropped
+    Dropped,
+    /// Actor's mailbox is closed, message is dropped
+    Closed,
+}
+
+/// Future that resolves with outcome of `Subscriber::send_lazy()`
+///
+/// Retry does not depend on the handle, it is safe to drop it.
+#[must_use = "future do nothing unless polled"]
+pub struct LazySendHandle {
+    res: Option<LazySend>,
+    rx: Option<Receiver<LazySend>>,
+}
+
+impl LazySendHandle {
+    fn ready(res: LazySend) -> LazySendHandle {
+        LazySendHandle{res: Some(res), rx: None}
+    }
+}
+
+impl Future for LazySendHandle {
+    type Item = LazySend;
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<LazySend, ()> {
+        if let Some(res) = self.res.take() {
+            return Ok(Async::Ready(res))
+        }
+        match self.rx.as_mut().map(|rx| rx.poll()) {
+            Some(Ok(Async::NotReady)) => Ok(Async::NotReady),
+            Some(Ok(Async::Ready(res))) => Ok(Async::Ready(res)),
+            // arbiter stopped before retry completed
+            Some(Err(_)) | None => Ok(Async::Ready(LazySend::Dropped)),
+        }
+    }
+}
+
+/// Message that waits for room in mailbox
+trait LazyItem {
+    /// Try to send message, `None` if mailbox is still full.
+    /// If `park` is true, current task is notified once mailbox has room.
+    fn attempt(&mut self, park: bool) -> Option<LazySend>;
+
+    /// Report outcome to the handle
+    fn complete(&mut self, res: LazySend);
+}
+
+struct Pending<M: 'static> {
+    sub: Box<Subscriber<M>>,
+    msg: Option<M>,
+    tx: Option<Sender<LazySend>>,
+}
+
+impl<M: 'static> LazyItem for Pending<M> {
+    fn attempt(&mut self, park: bool) -> Option<LazySend> {
+        let msg = match self.msg.take() {
+            Some(msg) => msg,
+            None => return Some(LazySend::Dropped),
+        };
+        let res = if park { self.sub.try_send(msg) } else { self.sub.try_send_nowait(msg) };
+        match res {
+            Ok(()) => Some(LazySend::Delivered),
+            Err(SendError::Closed(_)) => Some(LazySend::Closed),
+            Err(SendError::Full(msg)) => {
+                self.msg = Some(msg);
+                None
+            }
+        }
+    }
+
+    fn complete(&mut self, res: LazySend) {
+        self.msg.take();
+        if let Some(tx) = self.tx.take() {
+            let _ = tx.send(res);
+        }
+    }
+}
+
+/// Messages of one sender, retried in order
+#[derive(Default)]
+struct LazyQueue {
+    items: VecDeque<(Box<LazyItem>, Duration)>,
+}
+
+/// Send message now, or once mailbox gets room within `grace` period
+pub(crate) fn send<M: 'static>(sub: Box<Subscriber<M>>, key: usize, msg: M, grace: Duration)
+                               -> LazySendHandle
+{
+    let queue = if key == 0 {
+        None
+    } else {
+        QUEUES.with(|cell| cell.borrow().get(&key).cloned())
+    };
+
+    // earlier messages of this sender still wait, keep order
+    if let Some(queue) = queue {
+        let (tx, rx) = channel();
+        let item = Pending{sub: sub, msg: Some(msg), tx: Some(tx)};
+        queue.borrow_mut().items.push_back((Box::new(item), grace));
+        return LazySendHandle{res: None, rx: Some(rx)}
+    }
+
+    let mut item = Pending{sub: sub, msg: Some(msg), tx: None};
+    if let Some(res) = item.attempt(false) {
+        return LazySendHandle::ready(res)
+    }
+    let handle = match Arbiter::try_handle() {
+        Some(handle) => handle,
+        None => return LazySendHandle::ready(LazySend::Dropped),
+    };
+
+    let (tx, rx) = channel();
+    item.tx = Some(tx);
+    let queue = Rc::new(RefCell::new(LazyQueue::default()));
+    queue.borrow_mut().items.push_back((Box::new(item), grace));
+    if key != 0 {
+        QUEUES.with(|cell| cell.borrow_mut().insert(key, Rc::clone(&queue)));
+    }
+    handle.spawn(LazyRetry{key: key, queue: queue, timeout: None});
+    LazySendHandle{res: None, rx: Some(rx)}
+}
+
+/// Retries queued messages of one sender
+struct LazyRetry {
+    key: usize,
+    queue: Rc<RefCell<LazyQueue>>,
+    timeout: Option<Timeout>,
+}
+
+impl Future for LazyRetry {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        loop {
+            let mut queue = self.queue.borrow_mut();
+            let res = match queue.items.front_mut() {
+                Some(&mut (ref mut item, grace)) => {
+                    let expired = match self.timeout {
+                        Some(ref mut timeout) => match timeout.poll() {
+                            Ok(Async::NotReady) => false,
+                            Ok(Async::Ready(_)) | Err(_) => true,
+                        },
+                        None => {
+                            let mut timeout = Timeout::new(grace, Arbiter::handle()).unwrap();
+                            let _ = timeout.poll();
+                            self.timeout = Some(timeout);
+                            false
+                        }
+                    };
+                    match item.attempt(!expired) {
+                        Some(res) => res,
+                        None if expired => LazySend::Dropped,
+                        None => return Ok(Async::NotReady),
+                    }
+                }
+                None => break,
+            };
+            if let Some((mut item, _)) = queue.items.pop_front() {
+                item.complete(res);
+            }
+            self.timeout = None;
+        }
+
+        if self.key != 0 {
+            QUEUES.with(|cell| cell.borrow_mut().remove(&self.key));
+        }
+        Ok(Async::Ready(()))
+    }
+}
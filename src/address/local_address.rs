@@ -1,9 +1,16 @@
+use std::any::Any;
+
 use actor::{Actor, AsyncContext};
-use address::{SendError, Subscriber};
-use handler::{Handler, ResponseType};
+use actors::deadletter;
+use msgs::{StopActor, TerminateActor};
+use handoff::Handoff;
+use futures::Future;
+
+use address::{MailboxError, Recipient, RequestSubscriber, SendError, Subscriber};
+use handler::{Handler, MessageResult, ResponseType};
 
-use super::local_channel::LocalAddrSender;
-use super::local_message::{LocalRequest, LocalFutRequest};
+use super::local_channel::{LocalAddrSender, LocalWeakSender};
+use super::local_message::{LocalRequest, LocalFutRequest, LocalSendFut, DeliveryFuture};
 
 
 /// Local address of the actor
@@ -30,11 +37,65 @@ impl<A> Address<A> where A: Actor, A::Context: AsyncContext<A> {
         self.tx.connected()
     }
 
+    /// Create weak address of the actor
+    ///
+    /// Weak address does not keep actor's mailbox connected.
+    pub fn downgrade(&self) -> WeakAddress<A> {
+        WeakAddress{tx: self.tx.downgrade()}
+    }
+
     /// Send message `M` to the actor `A`
     ///
-    /// This method ignores receiver capacity, it silently fails if mailbox is closed.
-    pub fn send<M>(&self, msg: M) where A: Handler<M>, M: ResponseType + 'static {
-        let _ = self.tx.do_send(msg);
+    /// Returned future resolves when message is placed to actor's mailbox.
+    /// If mailbox is full, future waits until mailbox has room, or fails with
+    /// `MailboxError::Full` if mailbox overflow policy is `Overflow::Reject`.
+    /// Future fails with `MailboxError::Closed` if mailbox is closed.
+    /// If future gets dropped while it waits for mailbox room, message is
+    /// published as dead letter with `DeadLetterReason::Dropped` reason.
+    pub fn send<M>(&self, msg: M) -> LocalSendFut<A, M>
+        where A: Handler<M>, M: ResponseType + 'static
+    {
+        match self.tx.try_send(msg, false) {
+            Ok(()) => LocalSendFut::new(None, false),
+            Err(SendError::Full(msg)) =>
+                LocalSendFut::new(Some((self.tx.clone(), msg)), false),
+            Err(SendError::Closed(_)) =>
+                LocalSendFut::new(None, true),
+        }
+    }
+
+    /// Send message `M` to the actor `A` and wait until it gets handled
+    ///
+    /// Returned future resolves when actor's handler is invoked, regardless
+    /// of handler's result. Future fails with `MailboxError::Closed` if
+    /// mailbox is closed or actor stops before message is handled.
+    /// If mailbox is full, future waits until mailbox has room.
+    pub fn send_confirmed<M>(&self, msg: M) -> DeliveryFuture<A, M>
+        where A: Handler<M>, M: ResponseType + 'static
+    {
+        match self.tx.send_confirmed(msg, false) {
+            Ok(rx) => DeliveryFuture::new(Some(rx), None),
+            Err(SendError::Full(msg)) =>
+                DeliveryFuture::new(None, Some((self.tx.clone(), msg))),
+            Err(SendError::Closed(_)) =>
+                DeliveryFuture::new(None, None),
+        }
+    }
+
+    /// Send message `M` to the actor `A`
+    ///
+    /// This method ignores receiver capacity, unless mailbox overflow policy
+    /// is `Overflow::Reject`. If mailbox is closed or rejects message,
+    /// message is published to `DeadLetters` service.
+    pub fn do_send<M>(&self, msg: M) where A: Handler<M>, M: ResponseType + 'static {
+        let res = if self.tx.rejects() {
+            self.tx.try_send(msg, false)
+        } else {
+            self.tx.do_send(msg)
+        };
+        if let Err(err) = res {
+            deadletter::publish::<A, M>(&err.into_inner());
+        }
     }
 
     /// Try to send message `M` to the actor `A`
@@ -46,20 +107,65 @@ impl<A> Address<A> where A: Actor, A::Context: AsyncContext<A> {
         self.tx.try_send(msg, false)
     }
 
+    /// Send message `M` to the system lane of the actor `A`
+    ///
+    /// System lane is meant for framework messages like stop requests, it is
+    /// drained before ordinary mailbox. Lane has small fixed capacity,
+    /// this method fails if lane is full or closed.
+    pub fn try_send_system<M>(&self, msg: M) -> Result<(), SendError<M>>
+        where A: Handler<M>, M: ResponseType + 'static
+    {
+        self.tx.send_system(msg)
+    }
+
+    /// Ask actor to stop
+    ///
+    /// Stop request is sent through system lane, so it is handled even if
+    /// mailbox is full. Actor could prevent stopping by returning `false`
+    /// from `Actor::stopping()` method.
+    pub fn stop(&self) where A: Handler<StopActor> {
+        if let Err(SendError::Full(msg)) = self.tx.send_system(StopActor) {
+            let _ = self.tx.do_send(msg);
+        }
+    }
+
+    /// Terminate actor, `Actor::stopping()` method is not called
+    ///
+    /// Request is sent through system lane, same as `stop()`.
+    pub fn terminate(&self) where A: Handler<TerminateActor> {
+        if let Err(SendError::Full(msg)) = self.tx.send_system(TerminateActor) {
+            let _ = self.tx.do_send(msg);
+        }
+    }
+
+    /// Move queued messages to replacement actor and stop actor, see `Handoff`
+    ///
+    /// Request is sent through system lane, same as `SyncAddress::handoff_to()`.
+    pub fn handoff_to<B>(&self, handoff: Handoff<B>) -> Result<(), SendError<Handoff<B>>>
+        where A: Handler<Handoff<B>>, B: Actor
+    {
+        self.tx.send_system(handoff)
+    }
+
     /// Send message to actor `A` and asynchronously wait for response.
     ///
     /// Communication channel to the actor is bounded.
     ///
     /// if returned `LocalRequest` object get dropped, message cancels.
+    ///
+    /// Calling actor is used only to infer type of the returned future,
+    /// use `addr.call_fut(msg).into_actor(self)` instead.
+    #[deprecated(since="0.5.0", note="use `call_fut(msg).into_actor(self)` instead")]
     pub fn call<B, M>(&self, _: &B, msg: M) -> LocalRequest<A, B, M>
         where A: Handler<M>, M: ResponseType + 'static, B: Actor, B::Context: AsyncContext<B>
     {
-        match self.tx.send(msg) {
-            Ok(rx) => LocalRequest::new(Some(rx), None),
+        let mailbox = self.tx.mailbox_id();
+        match self.tx.send(msg, false) {
+            Ok(rx) => LocalRequest::new(Some(rx), None, mailbox),
             Err(SendError::Full(msg)) =>
-                LocalRequest::new(None, Some((self.tx.clone(), msg))),
+                LocalRequest::new(None, Some((self.tx.clone(), msg)), mailbox),
             Err(SendError::Closed(_)) =>
-                LocalRequest::new(None, None)
+                LocalRequest::new(None, None, mailbox)
         }
     }
 
@@ -68,15 +174,17 @@ impl<A> Address<A> where A: Actor, A::Context: AsyncContext<A> {
     /// Communication channel to the actor is bounded.
     ///
     /// if returned `LocalReceiver` object get dropped, message cancels.
+    /// Use `into_actor()` to convert future to `ActorFuture`.
     pub fn call_fut<M>(&self, msg: M) -> LocalFutRequest<A, M>
         where A: Handler<M>, M: ResponseType + 'static
     {
-        match self.tx.send(msg) {
-            Ok(rx) => LocalFutRequest::new(Some(rx), None),
+        let mailbox = self.tx.mailbox_id();
+        match self.tx.send(msg, false) {
+            Ok(rx) => LocalFutRequest::new(Some(rx), None, mailbox),
             Err(SendError::Full(msg)) =>
-                LocalFutRequest::new(None, Some((self.tx.clone(), msg))),
+                LocalFutRequest::new(None, Some((self.tx.clone(), msg)), mailbox),
             Err(SendError::Closed(_)) =>
-                LocalFutRequest::new(None, None),
+                LocalFutRequest::new(None, None, mailbox),
         }
     }
 
@@ -86,6 +194,36 @@ impl<A> Address<A> where A: Actor, A::Context: AsyncContext<A> {
     {
         Box::new(self)
     }
+
+    /// Get `Recipient` for specific message type
+    pub fn into_recipient<M>(self) -> Recipient<M>
+        where A: Handler<M>, M: ResponseType + 'static
+    {
+        Recipient::new(Box::new(self))
+    }
+}
+
+/// Weak reference to the actor's local address
+///
+/// Weak address does not keep actor alive, actor stops once all of its
+/// addresses are dropped, regardless of weak addresses. Use it to break
+/// reference cycles, i.e. server that holds addresses of its sessions
+/// while sessions hold address of the server.
+pub struct WeakAddress<A> where A: Actor, A::Context: AsyncContext<A> {
+    tx: LocalWeakSender<A>
+}
+
+impl<A> Clone for WeakAddress<A> where A: Actor, A::Context: AsyncContext<A> {
+    fn clone(&self) -> Self {
+        WeakAddress{tx: self.tx.clone()}
+    }
+}
+
+impl<A> WeakAddress<A> where A: Actor, A::Context: AsyncContext<A> {
+    /// Get actor's address, `None` if actor is gone
+    pub fn upgrade(&self) -> Option<Address<A>> {
+        self.tx.upgrade().map(Address::new)
+    }
 }
 
 impl<A, M> Subscriber<M> for Address<A>
@@ -101,8 +239,35 @@ impl<A, M> Subscriber<M> for Address<A>
         self.tx.try_send(msg, true)
     }
 
+    fn try_send_nowait(&self, msg: M) -> Result<(), SendError<M>> {
+        self.tx.try_send(msg, false)
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
     #[doc(hidden)]
     fn boxed(&self) -> Box<Subscriber<M>> {
         Box::new(self.clone())
     }
+
+    #[doc(hidden)]
+    fn mailbox_id(&self) -> usize {
+        self.tx.mailbox_id()
+    }
+}
+
+impl<A, M> RequestSubscriber<M> for Address<A>
+    where A: Actor + Handler<M>,
+          A::Context: AsyncContext<A>,
+          M: ResponseType + 'static
+{
+    fn call(&self, msg: M) -> Box<Future<Item=MessageResult<M>, Error=MailboxError>> {
+        Box::new(self.call_fut(msg))
+    }
+
+    fn boxed_request(&self) -> Box<RequestSubscriber<M>> {
+        Box::new(self.clone())
+    }
 }
@@ -6,9 +6,12 @@
 //! These queues are the same as those in `futures::sync`, except they're not
 //! intended to be sent across threads.
 
+use std::{cmp, mem};
 use std::rc::{Rc, Weak};
 use std::cell::RefCell;
+use std::sync::Arc;
 use std::collections::VecDeque;
+use std::time::Instant;
 
 use futures::{Async, Poll, Stream};
 use futures::task::{self, Task};
@@ -16,14 +19,48 @@ use futures::unsync::oneshot::{channel, Receiver};
 
 use actor::{Actor, AsyncContext};
 use handler::{Handler, MessageResult, ResponseType};
-use super::{SendError, LocalEnvelope};
+use mailbox::{MailboxTrace, TraceKind, SYSTEM_LANE_CAPACITY};
+use super::{SendError, LocalEnvelope, Overflow};
 
 
 struct Shared<A: Actor> {
     buffer: VecDeque<LocalEnvelope<A>>,
     capacity: usize,
+    overflow: Overflow,
     blocked_senders: VecDeque<Task>,
     blocked_recv: Option<Task>,
+    recv_waiting: bool,
+    trace: Arc<MailboxTrace>,
+    system: VecDeque<LocalEnvelope<A>>,
+    // Number of weak senders, they do not keep receiver connected
+    weak: usize,
+}
+
+// Number of senders that keep receiver connected
+fn senders<A: Actor>(state: &Rc<RefCell<Shared<A>>>) -> usize {
+    Rc::weak_count(state) - state.borrow().weak
+}
+
+impl<A: Actor> Shared<A> {
+    fn push(&mut self, msg: LocalEnvelope<A>) {
+        self.trace.record(TraceKind::Enqueue, msg.env.type_name());
+        self.buffer.push_back(msg);
+    }
+
+    /// Check if new message could be accepted
+    ///
+    /// With `Overflow::DropOldest` policy mailbox always accepts new messages,
+    /// oldest messages get dropped by receiver. Zero capacity mailbox accepts
+    /// message only if receiver waits for it.
+    fn has_room(&self) -> bool {
+        if self.overflow == Overflow::DropOldest {
+            true
+        } else if self.capacity == 0 {
+            self.recv_waiting && self.buffer.is_empty()
+        } else {
+            self.buffer.len() < self.capacity
+        }
+    }
 }
 
 /// The transmission end of a channel.
@@ -41,6 +78,27 @@ impl<A> LocalAddrSender<A> where A: Actor, A::Context: AsyncContext<A> {
         }
     }
 
+    /// Identity of receiver's mailbox, zero if receiver is gone
+    pub fn mailbox_id(&self) -> usize {
+        match self.shared.upgrade() {
+            Some(shared) => shared.borrow().trace.mailbox_id(),
+            None => 0,
+        }
+    }
+
+    /// Create weak sender that does not keep receiver connected
+    pub fn downgrade(&self) -> LocalWeakSender<A> {
+        LocalWeakSender::new(Weak::clone(&self.shared))
+    }
+
+    /// Sender should not wait for room, see `Overflow::Reject`
+    pub fn rejects(&self) -> bool {
+        match self.shared.upgrade() {
+            Some(shared) => shared.borrow().overflow == Overflow::Reject,
+            None => false,
+        }
+    }
+
     /// Try to put message to a reciver queue, if queue is full
     /// return message back.
     ///
@@ -54,7 +112,7 @@ impl<A> LocalAddrSender<A> where A: Actor, A::Context: AsyncContext<A> {
         };
         let mut shared = shared.borrow_mut();
 
-        shared.buffer.push_back(LocalEnvelope::new(msg, None));
+        shared.push(LocalEnvelope::new(msg, None));
         if let Some(task) = shared.blocked_recv.take() {
             drop(shared);
             task.notify();
@@ -76,8 +134,8 @@ impl<A> LocalAddrSender<A> where A: Actor, A::Context: AsyncContext<A> {
         };
         let mut shared = shared.borrow_mut();
 
-        if shared.capacity == 0 || shared.buffer.len() < shared.capacity {
-            shared.buffer.push_back(LocalEnvelope::new(msg, None));
+        if shared.has_room() {
+            shared.push(LocalEnvelope::new(msg, None));
             if let Some(task) = shared.blocked_recv.take() {
                 drop(shared);
                 task.notify();
@@ -91,11 +149,65 @@ impl<A> LocalAddrSender<A> where A: Actor, A::Context: AsyncContext<A> {
         }
     }
 
+    /// Put message to the system lane of the receiver
+    ///
+    /// System lane has fixed capacity and it is drained before ordinary messages.
+    pub fn send_system<M>(&self, msg: M) -> Result<(), SendError<M>>
+        where A: Handler<M>, M: ResponseType + 'static
+    {
+        let shared = match self.shared.upgrade() {
+            Some(shared) => shared,
+            None => return Err(SendError::Closed(msg)),
+        };
+        let mut shared = shared.borrow_mut();
+
+        if shared.system.len() >= SYSTEM_LANE_CAPACITY {
+            return Err(SendError::Full(msg))
+        }
+        shared.system.push_back(LocalEnvelope::new(msg, None));
+        if let Some(task) = shared.blocked_recv.take() {
+            drop(shared);
+            task.notify();
+        }
+        Ok(())
+    }
+
     /// Try to put message to a reciver queue, if queue is full
     /// return message back.
     ///
-    /// This method registers current task in recivers queue.
-    pub fn send<M>(&self, msg: M) -> Result<Receiver<MessageResult<M>>, SendError<M>>
+    /// This method may register current task in recivers queue depends on
+    /// state of `park` parameter.
+    pub fn send<M>(&self, msg: M, park: bool) -> Result<Receiver<MessageResult<M>>, SendError<M>>
+        where A: Handler<M>, M: ResponseType + 'static
+    {
+        let shared = match self.shared.upgrade() {
+            Some(shared) => shared,
+            None => return Err(SendError::Closed(msg)),
+        };
+        let mut shared = shared.borrow_mut();
+
+        if shared.has_room() {
+            let (tx, rx) = channel();
+            shared.push(LocalEnvelope::new(msg, Some(tx)));
+            if let Some(task) = shared.blocked_recv.take() {
+                drop(shared);
+                task.notify();
+            }
+            Ok(rx)
+        } else {
+            if park {
+                shared.blocked_senders.push_back(task::current());
+            }
+            Err(SendError::Full(msg))
+        }
+    }
+
+    /// Put message to a receiver queue, returned receiver resolves once
+    /// message's handler is invoked.
+    ///
+    /// This method may register current task in recivers queue depends on
+    /// state of `park` parameter.
+    pub fn send_confirmed<M>(&self, msg: M, park: bool) -> Result<Receiver<()>, SendError<M>>
         where A: Handler<M>, M: ResponseType + 'static
     {
         let shared = match self.shared.upgrade() {
@@ -104,16 +216,18 @@ impl<A> LocalAddrSender<A> where A: Actor, A::Context: AsyncContext<A> {
         };
         let mut shared = shared.borrow_mut();
 
-        if shared.capacity == 0 || shared.buffer.len() < shared.capacity {
+        if shared.has_room() {
             let (tx, rx) = channel();
-            shared.buffer.push_back(LocalEnvelope::new(msg, Some(tx)));
+            shared.push(LocalEnvelope::with_confirm(msg, tx));
             if let Some(task) = shared.blocked_recv.take() {
                 drop(shared);
                 task.notify();
             }
             Ok(rx)
         } else {
-            shared.blocked_senders.push_back(task::current());
+            if park {
+                shared.blocked_senders.push_back(task::current());
+            }
             Err(SendError::Full(msg))
         }
     }
@@ -131,7 +245,7 @@ impl<A> Drop for LocalAddrSender<A> where A: Actor, A::Context: AsyncContext<A>
             Some(shared) => shared,
             None => return,
         };
-        if Rc::weak_count(&shared) == 1 {
+        if senders(&shared) == 1 {
             let task = { shared.borrow_mut().blocked_recv.take() };
             if let Some(task) = task {
                 // Wake up receiver as its stream has ended
@@ -141,6 +255,43 @@ impl<A> Drop for LocalAddrSender<A> where A: Actor, A::Context: AsyncContext<A>
     }
 }
 
+/// Weak sender, it does not keep receiver connected.
+pub(crate) struct LocalWeakSender<A> where A: Actor, A::Context: AsyncContext<A> {
+    shared: Weak<RefCell<Shared<A>>>,
+}
+
+impl<A> LocalWeakSender<A> where A: Actor, A::Context: AsyncContext<A> {
+    fn new(shared: Weak<RefCell<Shared<A>>>) -> LocalWeakSender<A> {
+        if let Some(shared) = shared.upgrade() {
+            shared.borrow_mut().weak += 1;
+        }
+        LocalWeakSender{shared: shared}
+    }
+
+    /// Get sender, `None` if receiver is gone
+    pub fn upgrade(&self) -> Option<LocalAddrSender<A>> {
+        if self.shared.upgrade().is_some() {
+            Some(LocalAddrSender{shared: Weak::clone(&self.shared)})
+        } else {
+            None
+        }
+    }
+}
+
+impl<A> Clone for LocalWeakSender<A> where A: Actor, A::Context: AsyncContext<A> {
+    fn clone(&self) -> Self {
+        LocalWeakSender::new(Weak::clone(&self.shared))
+    }
+}
+
+impl<A> Drop for LocalWeakSender<A> where A: Actor, A::Context: AsyncContext<A> {
+    fn drop(&mut self) {
+        if let Some(shared) = self.shared.upgrade() {
+            shared.borrow_mut().weak -= 1;
+        }
+    }
+}
+
 /// The receiving end of a channel which implements the `Stream` trait.
 ///
 /// This is created by the `channel` function.
@@ -156,19 +307,24 @@ impl<A> LocalAddrReceiver<A> where A: Actor, A::Context: AsyncContext<A> {
     /// traits which can be used to communicate a stream of values between tasks
     /// with backpressure. The channel capacity is exactly `cap`. On average,
     /// sending a message through this channel performs no dynamic allocation.
-    pub fn new(cap: usize) -> LocalAddrReceiver<A> {
+    pub fn new(cap: usize, trace: Arc<MailboxTrace>) -> LocalAddrReceiver<A> {
         LocalAddrReceiver {
             state: Rc::new(RefCell::new(Shared {
                 buffer: VecDeque::new(),
                 capacity: cap,
+                overflow: Overflow::Block,
                 blocked_senders: VecDeque::new(),
-                blocked_recv: None }))
+                blocked_recv: None,
+                recv_waiting: false,
+                trace: trace,
+                system: VecDeque::new(),
+                weak: 0 }))
         }
     }
 
     /// Check if receiver connected to senders
     pub fn connected(&self) -> bool {
-        Rc::weak_count(&self.state) != 0
+        senders(&self.state) != 0
     }
 
     /// Get the sender half
@@ -199,6 +355,85 @@ impl<A> LocalAddrReceiver<A> where A: Actor, A::Context: AsyncContext<A> {
             }
         }
     }
+
+    /// Get channel overflow policy
+    pub fn overflow(&self) -> Overflow {
+        self.state.borrow().overflow
+    }
+
+    /// Set channel overflow policy
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        let mut shared = self.state.borrow_mut();
+        shared.overflow = overflow;
+
+        // senders do not wait for room anymore
+        if overflow != Overflow::Block {
+            for task in shared.blocked_senders.drain(..) {
+                task.notify();
+            }
+        }
+    }
+
+    /// Type names of messages in the buffer, oldest first
+    pub fn queued_types(&self) -> Vec<&'static str> {
+        self.state.borrow().buffer.iter().map(|msg| msg.env.type_name()).collect()
+    }
+
+    /// Number of messages in the buffer
+    pub fn len(&self) -> usize {
+        self.state.borrow().buffer.len()
+    }
+
+    /// Time when the oldest message in the buffer was sent
+    pub fn oldest(&self) -> Option<Instant> {
+        self.state.borrow().buffer.front().map(|msg| msg.enqueued)
+    }
+
+    /// Put message to the system lane, used by context itself
+    pub fn push_system<M>(&mut self, msg: M) -> Result<(), SendError<M>>
+        where A: Handler<M>, M: ResponseType + 'static
+    {
+        let mut shared = self.state.borrow_mut();
+        if shared.system.len() >= SYSTEM_LANE_CAPACITY {
+            return Err(SendError::Full(msg))
+        }
+        shared.system.push_back(LocalEnvelope::new(msg, None));
+        Ok(())
+    }
+
+    /// Take next message from the system lane
+    pub fn poll_system(&mut self) -> Option<LocalEnvelope<A>> {
+        self.state.borrow_mut().system.pop_front()
+    }
+
+    /// Number of messages in the system lane
+    pub fn system_len(&self) -> usize {
+        self.state.borrow().system.len()
+    }
+
+    /// Disconnect senders and take all buffered messages, oldest first
+    ///
+    /// Existing senders get `SendError::Closed` error, system lane is kept.
+    pub fn drain(&mut self) -> Vec<LocalEnvelope<A>> {
+        let (cap, overflow, trace) = {
+            let shared = self.state.borrow();
+            (shared.capacity, shared.overflow, Arc::clone(&shared.trace))
+        };
+        let mut rx = LocalAddrReceiver::new(cap, trace);
+        rx.set_overflow(overflow);
+        let old = mem::replace(self, rx);
+
+        let mut old_shared = old.state.borrow_mut();
+        mem::swap(&mut self.state.borrow_mut().system, &mut old_shared.system);
+        old_shared.buffer.drain(..).collect()
+    }
+
+    /// Release unused buffer memory
+    pub fn shrink(&mut self) {
+        let mut shared = self.state.borrow_mut();
+        shared.buffer.shrink_to_fit();
+        shared.blocked_senders.shrink_to_fit();
+    }
 }
 
 impl<A> Stream for LocalAddrReceiver<A> where A: Actor, A::Context: AsyncContext<A> {
@@ -206,13 +441,26 @@ impl<A> Stream for LocalAddrReceiver<A> where A: Actor, A::Context: AsyncContext
     type Error = ();
 
     fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
-        if let Some(shared) = Rc::get_mut(&mut self.state) {
+        if senders(&self.state) == 0 {
             // All senders have been dropped, so drain the buffer and end the
             // stream.
-            return Ok(Async::Ready(shared.borrow_mut().buffer.pop_front()));
+            return Ok(Async::Ready(self.state.borrow_mut().buffer.pop_front()));
         }
 
         let mut shared = self.state.borrow_mut();
+
+        shared.recv_waiting = false;
+
+        // drop messages that do not fit into mailbox,
+        // zero capacity mailbox keeps newest message only
+        if shared.overflow == Overflow::DropOldest {
+            while shared.buffer.len() > cmp::max(shared.capacity, 1) {
+                if let Some(msg) = shared.buffer.pop_front() {
+                    shared.trace.record(TraceKind::Dropped, msg.env.type_name());
+                }
+            }
+        }
+
         if let Some(msg) = shared.buffer.pop_front() {
             if let Some(task) = shared.blocked_senders.pop_front() {
                 drop(shared);
@@ -221,6 +469,15 @@ impl<A> Stream for LocalAddrReceiver<A> where A: Actor, A::Context: AsyncContext
             Ok(Async::Ready(Some(msg)))
         } else {
             shared.blocked_recv = Some(task::current());
+
+            // rendezvous mailbox, let one of the senders hand off its message
+            if shared.capacity == 0 {
+                shared.recv_waiting = true;
+                if let Some(task) = shared.blocked_senders.pop_front() {
+                    drop(shared);
+                    task.notify();
+                }
+            }
             Ok(Async::NotReady)
         }
     }
@@ -260,16 +517,16 @@ mod tests {
         let sys = System::new("test");
 
         Arbiter::handle().spawn_fn(move || {
-            let mut recv = LocalAddrReceiver::<Act>::new(1);
+            let mut recv = LocalAddrReceiver::<Act>::new(1, MailboxTrace::new());
             assert_eq!(recv.capacity(), 1);
 
             let s1 = recv.sender();
             let s2 = recv.sender();
 
-            let _ = s1.send(Ping);
+            let _ = s1.send(Ping, true);
             assert_eq!(recv.state.borrow().buffer.len(), 1);
 
-            let _ = s2.send(Ping);
+            let _ = s2.send(Ping, true);
             assert_eq!(recv.state.borrow().buffer.len(), 1);
             assert_eq!(recv.state.borrow().blocked_senders.len(), 1);
 
@@ -277,10 +534,33 @@ mod tests {
             assert_eq!(recv.state.borrow().buffer.len(), 1);
             assert_eq!(recv.state.borrow().blocked_senders.len(), 0);
 
-            let _ = s2.send(Ping);
+            let _ = s2.send(Ping, true);
             assert_eq!(recv.state.borrow().buffer.len(), 2);
 
-            Arbiter::system().send(actix::msgs::SystemExit(0));
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        });
+
+        sys.run();
+    }
+
+    #[test]
+    fn test_shrink() {
+        let sys = System::new("test");
+
+        Arbiter::handle().spawn_fn(move || {
+            let mut recv = LocalAddrReceiver::<Act>::new(0, MailboxTrace::new());
+            let s = recv.sender();
+            for _ in 0..100 {
+                let _ = s.do_send(Ping);
+            }
+            while let Ok(Async::Ready(Some(_))) = recv.poll() {}
+            assert!(recv.state.borrow().buffer.capacity() >= 100);
+
+            recv.shrink();
+            assert!(recv.state.borrow().buffer.capacity() < 100);
+
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
             Ok(())
         });
 
@@ -1,13 +1,20 @@
 use std::rc::Rc;
+use std::any::{type_name, Any};
 use std::marker::PhantomData;
+use std::thread::{self, ThreadId};
+use std::time::Instant;
 use futures::unsync::oneshot::Sender;
 
 use actor::{Actor, AsyncContext};
-use handler::{Handler, ResponseType, MessageResponse};
+use handler::{Handler, ResponseType};
+use pipe;
+use redelivery;
+use actors::deadletter::{self, DeadLetterReason};
 use super::EnvelopeProxy;
 
 pub struct LocalEnvelope<A>{
     pub env: Box<EnvelopeProxy<Actor=A>>,
+    pub enqueued: Instant,
     act: PhantomData<Rc<A>>
 }
 
@@ -21,7 +28,26 @@ impl<A> LocalEnvelope<A> where A: Actor {
             env: Box::new(
                 InnerLocalEnvelope{msg: Some(msg),
                                    tx: tx,
+                                   confirm: None,
+                                   sender: thread::current().id(),
                                    act: PhantomData}),
+            enqueued: Instant::now(),
+            act: PhantomData}
+    }
+
+    /// Envelope that notifies `confirm` once handler is invoked
+    pub(crate) fn with_confirm<M>(msg: M, confirm: Sender<()>) -> Self
+        where M: ResponseType + 'static,
+              A: Actor + Handler<M>, A::Context: AsyncContext<A>
+    {
+        LocalEnvelope {
+            env: Box::new(
+                InnerLocalEnvelope{msg: Some(msg),
+                                   tx: None,
+                                   confirm: Some(confirm),
+                                   sender: thread::current().id(),
+                                   act: PhantomData}),
+            enqueued: Instant::now(),
             act: PhantomData}
     }
 }
@@ -30,6 +56,8 @@ struct InnerLocalEnvelope<A, M> where M: ResponseType {
     msg: Option<M>,
     act: PhantomData<A>,
     tx: Option<Sender<Result<M::Item, M::Error>>>,
+    confirm: Option<Sender<()>>,
+    sender: ThreadId,
 }
 
 impl<A, M> EnvelopeProxy for InnerLocalEnvelope<A, M>
@@ -44,8 +72,33 @@ impl<A, M> EnvelopeProxy for InnerLocalEnvelope<A, M>
         if tx.is_some() && tx.as_ref().unwrap().is_canceled() {
             return
         }
+        if let Some(msg) = redelivery::take::<A, M>(&mut self.msg, ctx) {
+            pipe::handle(act, msg, ctx, tx);
+            if let Some(confirm) = self.confirm.take() {
+                let _ = confirm.send(());
+            }
+        }
+    }
+
+    fn type_name(&self) -> &'static str {
+        type_name::<M>()
+    }
+
+    fn quarantine(&mut self, attempts: u32) {
         if let Some(msg) = self.msg.take() {
-            <Self::Actor as Handler<M>>::handle(act, msg, ctx).handle(ctx, tx)
+            deadletter::publish_from::<A, M>(
+                &msg, DeadLetterReason::Poisoned{attempts: attempts}, self.sender);
         }
     }
+
+    fn dead_letter(&mut self, reason: DeadLetterReason) {
+        if let Some(msg) = self.msg.take() {
+            deadletter::publish_from::<A, M>(&msg, reason, self.sender);
+        }
+    }
+
+    fn take_message(&mut self) -> Option<Box<Any>> {
+        let tx = self.tx.take();
+        self.msg.take().map(|msg| Box::new((msg, tx)) as Box<Any>)
+    }
 }
@@ -6,10 +6,13 @@ use futures::unsync::oneshot::Receiver;
 use tokio_core::reactor::Timeout;
 
 use arbiter::Arbiter;
+use actors::deadletter::{self, DeadLetterReason};
 use actor::{Actor, AsyncContext};
 use fut::ActorFuture;
+use contextitems::wait_pending;
 use handler::{Handler, MessageResult, ResponseType};
 
+use super::request_map::{RequestMap, RequestMapErr, RequestFlatten};
 use super::{SendError, MailboxError};
 use super::local_channel::LocalAddrSender;
 
@@ -25,6 +28,7 @@ pub struct LocalRequest<A, B, M>
     info: Option<(LocalAddrSender<A>, M)>,
     act: PhantomData<B>,
     timeout: Option<Timeout>,
+    mailbox: usize,
 }
 
 impl<A, B, M> LocalRequest<A, B, M>
@@ -33,17 +37,45 @@ impl<A, B, M> LocalRequest<A, B, M>
           B: Actor, B::Context: AsyncContext<B>
 {
     pub(crate) fn new(rx: Option<Receiver<Result<M::Item, M::Error>>>,
-                      info: Option<(LocalAddrSender<A>, M)>) -> LocalRequest<A, B, M> {
-        LocalRequest{rx: rx, info: info, act: PhantomData, timeout: None}
+                      info: Option<(LocalAddrSender<A>, M)>,
+                      mailbox: usize) -> LocalRequest<A, B, M> {
+        LocalRequest{rx: rx, info: info, act: PhantomData, timeout: None, mailbox: mailbox}
     }
 
-    /// Set message delivery timeout
+    /// Set request timeout
+    ///
+    /// Timeout covers both waiting for mailbox room and waiting for
+    /// handler's response. Future fails with `MailboxError::Timeout` once
+    /// `dur` passes, queued message is skipped once future gets dropped.
     pub fn timeout(mut self, dur: Duration) -> Self {
         self.timeout = Some(Timeout::new(dur, Arbiter::handle()).unwrap());
         self
     }
 
+    /// Map message result item, mailbox error is passed through
+    pub fn map<F, U>(self, f: F) -> RequestMap<Self, F> where F: FnOnce(M::Item) -> U {
+        RequestMap::new(self, f)
+    }
+
+    /// Map message result error, mailbox error is passed through
+    pub fn map_err<F, U>(self, f: F) -> RequestMapErr<Self, F> where F: FnOnce(M::Error) -> U {
+        RequestMapErr::new(self, f)
+    }
+
+    /// Collapse mailbox error and message error into one error type
+    ///
+    /// Future resolves to `M::Item`, both errors get converted into `E`.
+    pub fn flatten<E>(self) -> RequestFlatten<Self, E>
+        where E: From<MailboxError> + From<M::Error>
+    {
+        RequestFlatten::new(self)
+    }
+
     fn poll_timeout(&mut self) -> Poll<MessageResult<M>, MailboxError> {
+        // waiting context broke deadlock or waits for itself
+        if let Some(err) = wait_pending::<M>(self.mailbox) {
+            return Err(err)
+        }
         if let Some(ref mut timeout) = self.timeout {
             match timeout.poll() {
                 Ok(Async::Ready(())) => Err(MailboxError::Timeout),
@@ -67,8 +99,10 @@ impl<A, B, M> ActorFuture for LocalRequest<A, B, M>
     fn poll(&mut self, _: &mut B, _: &mut B::Context) -> Poll<Self::Item, Self::Error> {
         // send message
         if let Some((sender, msg)) = self.info.take() {
-            match sender.send(msg) {
+            let park = !sender.rejects();
+            match sender.send(msg, park) {
                 Ok(rx) => self.rx = Some(rx),
+                Err(SendError::Full(_)) if !park => return Err(MailboxError::Full),
                 Err(SendError::Full(msg)) => {
                     self.info = Some((sender, msg));
                     return self.poll_timeout();
@@ -84,7 +118,7 @@ impl<A, B, M> ActorFuture for LocalRequest<A, B, M>
                     self.rx = Some(rx);
                     self.poll_timeout()
                 }
-                Err(_) => Err(MailboxError::Closed),
+                Err(_) => Err(MailboxError::Canceled),
             }
         } else {
             Err(MailboxError::Closed)
@@ -100,23 +134,52 @@ pub struct LocalFutRequest<A, M>
     rx: Option<Receiver<Result<M::Item, M::Error>>>,
     info: Option<(LocalAddrSender<A>, M)>,
     timeout: Option<Timeout>,
+    mailbox: usize,
 }
 
 impl<A, M> LocalFutRequest<A, M>
     where A: Actor + Handler<M>, A::Context: AsyncContext<A>, M: ResponseType + 'static,
 {
     pub(crate) fn new(rx: Option<Receiver<Result<M::Item, M::Error>>>,
-                      info: Option<(LocalAddrSender<A>, M)>) -> LocalFutRequest<A, M> {
-        LocalFutRequest{rx: rx, info: info, timeout: None}
+                      info: Option<(LocalAddrSender<A>, M)>,
+                      mailbox: usize) -> LocalFutRequest<A, M> {
+        LocalFutRequest{rx: rx, info: info, timeout: None, mailbox: mailbox}
     }
 
-    /// Set message delivery timeout
+    /// Set request timeout
+    ///
+    /// Timeout covers both waiting for mailbox room and waiting for
+    /// handler's response. Future fails with `MailboxError::Timeout` once
+    /// `dur` passes, queued message is skipped once future gets dropped.
     pub fn timeout(mut self, dur: Duration) -> Self {
         self.timeout = Some(Timeout::new(dur, Arbiter::handle()).unwrap());
         self
     }
 
+    /// Map message result item, mailbox error is passed through
+    pub fn map<F, U>(self, f: F) -> RequestMap<Self, F> where F: FnOnce(M::Item) -> U {
+        RequestMap::new(self, f)
+    }
+
+    /// Map message result error, mailbox error is passed through
+    pub fn map_err<F, U>(self, f: F) -> RequestMapErr<Self, F> where F: FnOnce(M::Error) -> U {
+        RequestMapErr::new(self, f)
+    }
+
+    /// Collapse mailbox error and message error into one error type
+    ///
+    /// Future resolves to `M::Item`, both errors get converted into `E`.
+    pub fn flatten<E>(self) -> RequestFlatten<Self, E>
+        where E: From<MailboxError> + From<M::Error>
+    {
+        RequestFlatten::new(self)
+    }
+
     fn poll_timeout(&mut self) -> Poll<MessageResult<M>, MailboxError> {
+        // waiting context broke deadlock or waits for itself
+        if let Some(err) = wait_pending::<M>(self.mailbox) {
+            return Err(err)
+        }
         if let Some(ref mut timeout) = self.timeout {
             match timeout.poll() {
                 Ok(Async::Ready(())) => Err(MailboxError::Timeout),
@@ -138,8 +201,10 @@ impl<A, M> Future for LocalFutRequest<A, M>
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
         // send message
         if let Some((sender, msg)) = self.info.take() {
-            match sender.send(msg) {
+            let park = !sender.rejects();
+            match sender.send(msg, park) {
                 Ok(rx) => self.rx = Some(rx),
+                Err(SendError::Full(_)) if !park => return Err(MailboxError::Full),
                 Err(SendError::Full(msg)) => {
                     self.info = Some((sender, msg));
                     return self.poll_timeout();
@@ -155,6 +220,119 @@ impl<A, M> Future for LocalFutRequest<A, M>
                     self.rx = Some(rx);
                     self.poll_timeout()
                 },
+                Err(_) => Err(MailboxError::Canceled),
+            }
+        } else {
+            Err(MailboxError::Closed)
+        }
+    }
+}
+
+/// `LocalSendFut` is a `Future` which represents message delivery to actor's mailbox.
+///
+/// If mailbox is full, future waits until mailbox has room.
+#[must_use = "future do nothing unless polled"]
+pub struct LocalSendFut<A, M>
+    where A: Actor + Handler<M>, A::Context: AsyncContext<A>, M: ResponseType + 'static,
+{
+    info: Option<(LocalAddrSender<A>, M)>,
+    closed: bool,
+}
+
+impl<A, M> LocalSendFut<A, M>
+    where A: Actor + Handler<M>, A::Context: AsyncContext<A>, M: ResponseType + 'static,
+{
+    pub(crate) fn new(info: Option<(LocalAddrSender<A>, M)>, closed: bool) -> LocalSendFut<A, M> {
+        LocalSendFut{info: info, closed: closed}
+    }
+}
+
+impl<A, M> Future for LocalSendFut<A, M>
+    where A: Actor + Handler<M>, A::Context: AsyncContext<A>, M: ResponseType + 'static,
+{
+    type Item = ();
+    type Error = MailboxError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        if let Some((sender, msg)) = self.info.take() {
+            let park = !sender.rejects();
+            match sender.try_send(msg, park) {
+                Ok(()) => Ok(Async::Ready(())),
+                Err(SendError::Full(_)) if !park => Err(MailboxError::Full),
+                Err(SendError::Full(msg)) => {
+                    self.info = Some((sender, msg));
+                    Ok(Async::NotReady)
+                }
+                Err(SendError::Closed(_)) => Err(MailboxError::Closed),
+            }
+        } else if self.closed {
+            Err(MailboxError::Closed)
+        } else {
+            Ok(Async::Ready(()))
+        }
+    }
+}
+
+impl<A, M> Drop for LocalSendFut<A, M>
+    where A: Actor + Handler<M>, A::Context: AsyncContext<A>, M: ResponseType + 'static,
+{
+    fn drop(&mut self) {
+        if let Some((_, ref msg)) = self.info {
+            deadletter::publish_with_reason::<A, M>(msg, DeadLetterReason::Dropped);
+        }
+    }
+}
+
+/// `DeliveryFuture` resolves once message's handler is invoked,
+/// see `Address::send_confirmed()`
+///
+/// Future fails with `MailboxError::Closed` if actor stops before
+/// message gets handled. Handler's result is not reported.
+#[must_use = "future do nothing unless polled"]
+pub struct DeliveryFuture<A, M>
+    where A: Actor + Handler<M>, A::Context: AsyncContext<A>, M: ResponseType + 'static,
+{
+    rx: Option<Receiver<()>>,
+    info: Option<(LocalAddrSender<A>, M)>,
+}
+
+impl<A, M> DeliveryFuture<A, M>
+    where A: Actor + Handler<M>, A::Context: AsyncContext<A>, M: ResponseType + 'static,
+{
+    pub(crate) fn new(rx: Option<Receiver<()>>,
+                      info: Option<(LocalAddrSender<A>, M)>) -> DeliveryFuture<A, M> {
+        DeliveryFuture{rx: rx, info: info}
+    }
+}
+
+impl<A, M> Future for DeliveryFuture<A, M>
+    where A: Actor + Handler<M>, A::Context: AsyncContext<A>, M: ResponseType + 'static,
+{
+    type Item = ();
+    type Error = MailboxError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        // send message
+        if let Some((sender, msg)) = self.info.take() {
+            let park = !sender.rejects();
+            match sender.send_confirmed(msg, park) {
+                Ok(rx) => self.rx = Some(rx),
+                Err(SendError::Full(_)) if !park => return Err(MailboxError::Full),
+                Err(SendError::Full(msg)) => {
+                    self.info = Some((sender, msg));
+                    return Ok(Async::NotReady)
+                }
+                Err(SendError::Closed(_)) => return Err(MailboxError::Closed),
+            }
+        }
+
+        if let Some(mut rx) = self.rx.take() {
+            match rx.poll() {
+                Ok(Async::Ready(())) => Ok(Async::Ready(())),
+                Ok(Async::NotReady) => {
+                    self.rx = Some(rx);
+                    Ok(Async::NotReady)
+                }
                 Err(_) => Err(MailboxError::Closed),
             }
         } else {
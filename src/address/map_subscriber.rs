@@ -0,0 +1,81 @@
+use std::any::Any;
+use std::marker::PhantomData;
+
+use super::{SendError, Subscriber};
+
+/// Subscriber that converts messages before sending, see `Subscriber::map()`
+struct MapSubscriber<S: ?Sized, M, A2, F> {
+    inner: Box<S>,
+    f: F,
+    msg: PhantomData<fn(A2) -> M>,
+}
+
+impl<S, M, A2, F> MapSubscriber<S, M, A2, F>
+    where S: Subscriber<M> + ?Sized, M: 'static, A2: Clone, F: Fn(A2) -> M
+{
+    /// Call inner subscriber with converted message, error carries original message
+    fn call<T>(&self, msg: A2, send: T) -> Result<(), SendError<A2>>
+        where T: FnOnce(&S, M) -> Result<(), SendError<M>>
+    {
+        let orig = msg.clone();
+        send(&self.inner, (self.f)(msg)).map_err(|err| match err {
+            SendError::Full(_) => SendError::Full(orig),
+            SendError::Closed(_) => SendError::Closed(orig),
+        })
+    }
+}
+
+impl<S, M, A2, F> Subscriber<A2> for MapSubscriber<S, M, A2, F>
+    where S: Subscriber<M> + ?Sized + 'static, M: 'static,
+          A2: Clone + 'static, F: Fn(A2) -> M + Clone + 'static
+{
+    fn send(&self, msg: A2) -> Result<(), SendError<A2>> {
+        self.call(msg, |s, msg| s.send(msg))
+    }
+
+    fn try_send(&self, msg: A2) -> Result<(), SendError<A2>> {
+        self.call(msg, |s, msg| s.try_send(msg))
+    }
+
+    fn try_send_nowait(&self, msg: A2) -> Result<(), SendError<A2>> {
+        self.call(msg, |s, msg| s.try_send_nowait(msg))
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    #[doc(hidden)]
+    fn boxed(&self) -> Box<Subscriber<A2>> {
+        Box::new(MapSubscriber{inner: self.inner.boxed(), f: self.f.clone(), msg: PhantomData})
+    }
+
+    #[doc(hidden)]
+    fn mailbox_id(&self) -> usize {
+        self.inner.mailbox_id()
+    }
+}
+
+impl<M: 'static> Subscriber<M> {
+    /// Convert subscriber of `M` into subscriber of `A2`
+    ///
+    /// Messages are converted with `f` at send time, in sender's thread.
+    /// Failed send returns original message.
+    pub fn map<F, A2>(self: Box<Self>, f: F) -> Box<Subscriber<A2>>
+        where F: Fn(A2) -> M + Clone + 'static, A2: Clone + 'static
+    {
+        Box::new(MapSubscriber{inner: self, f: f, msg: PhantomData})
+    }
+}
+
+impl<M: 'static> Subscriber<M> + Send {
+    /// Convert subscriber of `M` into subscriber of `A2`
+    ///
+    /// Messages are converted with `f` at send time, in sender's thread.
+    /// Failed send returns original message.
+    pub fn map<F, A2>(self: Box<Self>, f: F) -> Box<Subscriber<A2> + Send>
+        where F: Fn(A2) -> M + Clone + Send + 'static, A2: Clone + 'static
+    {
+        Box::new(MapSubscriber{inner: self, f: f, msg: PhantomData})
+    }
+}
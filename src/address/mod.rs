@@ -1,5 +1,8 @@
 use std::{mem, fmt};
+use std::any::Any;
+use std::time::Duration;
 use futures::{Async, AsyncSink, Poll, Sink, StartSend};
+use failure::Fail;
 
 mod envelope;
 mod queue;
@@ -10,65 +13,58 @@ mod local_address;
 mod local_channel;
 mod local_message;
 mod local_envelope;
+mod request_map;
+mod map_subscriber;
+mod recipient;
+mod ask;
+mod lazy;
+#[cfg(feature="spill")]
+pub(crate) mod spill;
 
 use actor::{Actor, AsyncContext};
 
 pub use self::envelope::{Envelope, EnvelopeProxy, ToEnvelope, RemoteEnvelope};
-pub use self::local_address::Address;
-pub use self::local_message::{LocalRequest, LocalFutRequest};
+pub use self::local_address::{Address, WeakAddress};
+pub use self::local_message::{LocalRequest, LocalFutRequest, LocalSendFut, DeliveryFuture};
 pub(crate) use self::local_envelope::LocalEnvelope;
 pub(crate) use self::local_channel::LocalAddrReceiver;
 
-pub use self::sync_address::SyncAddress;
-pub use self::sync_message::{Request, RequestFut};
+pub use self::sync_address::{SyncAddress, WeakSyncAddress};
+pub use self::sync_message::{Request, RequestFut, SendFut};
+pub use self::request_map::{RequestMap, RequestMapErr, RequestFlatten};
+pub use self::ask::ask;
+pub use self::recipient::{Recipient, RecipientRequest, SyncRecipient};
+pub(crate) use self::recipient::{RequestSubscriber, SyncRequestSubscriber};
+pub use self::lazy::{LazySend, LazySendHandle};
+pub use self::sync_channel::AddressSender;
 pub(crate) use self::sync_channel::SyncAddressReceiver;
+pub use message::{MailboxError, SendError};
 
 
-pub enum SendError<T> {
-    Full(T),
-    Closed(T),
+/// Mailbox overflow policy
+///
+/// Defines behavior of the actor's mailbox when number of pending messages
+/// reaches mailbox capacity.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum Overflow {
+    /// Sender gets `SendError::Full` error or get parked until mailbox has room.
+    Block,
+    /// Mailbox accepts new message, oldest pending messages get dropped.
+    DropOldest,
+    /// Sender gets error instead of waiting for room. `send()` and
+    /// `call_fut()` futures fail with `MailboxError::Full`, `do_send()`
+    /// publishes message to `DeadLetters` service.
+    Reject,
 }
 
-#[derive(Fail)]
-/// Set of error that can occure during message delivery process
-pub enum MailboxError {
-    #[fail(display="Mailbox has closed")]
-    Closed,
-    #[fail(display="Message delivery timed out")]
-    Timeout,
-}
+impl Fail for MailboxError {}
 
-impl<T> SendError<T> {
-    pub fn into_inner(self) -> T {
-        match self {
-            SendError::Full(msg) | SendError::Closed(msg) => msg,
-        }
+impl Default for Overflow {
+    fn default() -> Overflow {
+        Overflow::Block
     }
 }
 
-impl<T> fmt::Debug for SendError<T> {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            SendError::Full(_) => write!(fmt, "SendError::Full(..)"),
-            SendError::Closed(_) => write!(fmt, "SendError::Closed(..)"),
-        }
-    }
-}
-
-impl<T> fmt::Display for SendError<T> {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        match *self {
-            SendError::Full(_) => write!(fmt, "send failed because receiver is full"),
-            SendError::Closed(_) => write!(fmt, "send failed because receiver is gone"),
-        }
-    }
-}
-
-impl fmt::Debug for MailboxError {
-    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
-        write!(fmt, "MailboxError({})", self)
-    }
-}
 
 /// Trait give access to actor's address
 pub trait ActorAddress<A, T> where A: Actor {
@@ -123,9 +119,55 @@ pub trait Subscriber<M: 'static> {
     /// register current task in receivers queue.
     fn try_send(&self, msg: M) -> Result<(), SendError<M>>;
 
+    /// Try send message without registering current task
+    ///
+    /// This method fails if actor's mailbox is full or closed.
+    /// Default implementation uses `try_send()`.
+    fn try_send_nowait(&self, msg: M) -> Result<(), SendError<M>> {
+        self.try_send(msg)
+    }
+
+    /// Send message now or once actor's mailbox has room
+    ///
+    /// If mailbox is full, message is retried when actor takes message
+    /// from its mailbox. Message is dropped if mailbox does not get room
+    /// within one second. Messages of the same sender thread to the same
+    /// actor keep their order. Handle resolves with outcome of the send,
+    /// retry runs in current arbiter even if handle is dropped.
+    fn send_lazy(&self, msg: M) -> LazySendHandle {
+        self.send_lazy_timeout(msg, lazy::LAZY_SEND_GRACE)
+    }
+
+    /// Same as `send_lazy()`, but message is dropped after `grace` period
+    fn send_lazy_timeout(&self, msg: M, grace: Duration) -> LazySendHandle {
+        lazy::send(self.boxed(), self.mailbox_id(), msg, grace)
+    }
+
+    /// Subscriber as `Any`, concrete subscriber could be recovered with
+    /// `subscriber.as_any().downcast_ref::<SyncAddress<A>>()`
+    fn as_any(&self) -> &Any;
+
+    /// Check if both subscribers deliver to the same actor
+    ///
+    /// Subscribers of actor's addresses are equal if they share actor's
+    /// mailbox, other subscribers are equal if they share underlying channel.
+    fn subscriber_eq(&self, other: &Subscriber<M>) -> bool {
+        match self.mailbox_id() {
+            0 => self.as_any() as *const Any as *const () ==
+                other.as_any() as *const Any as *const (),
+            id => id == other.mailbox_id(),
+        }
+    }
+
     #[doc(hidden)]
     /// Create boxed clone of the current subscriber
     fn boxed(&self) -> Box<Subscriber<M>>;
+
+    #[doc(hidden)]
+    /// Identity of actor's mailbox, `0` if subscriber is not backed by mailbox
+    fn mailbox_id(&self) -> usize {
+        0
+    }
 }
 
 /// Convenience impl to allow boxed Subscriber objects to be cloned using `Clone.clone()`.
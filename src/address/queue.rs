@@ -135,6 +135,31 @@ impl<T> Queue<T> {
 
         if self.head.load(Ordering::Acquire) == tail {Empty} else {Inconsistent}
     }
+
+    /// Reference to the oldest value
+    ///
+    /// This function is unsafe for the same reason as `pop()`.
+    pub unsafe fn peek(&self) -> Option<&T> {
+        let next = (*(*self.tail.get())).next.load(Ordering::Acquire);
+        if next.is_null() {
+            None
+        } else {
+            (*next).value.as_ref()
+        }
+    }
+
+    /// Calls `f` for every value in the queue without removing it.
+    ///
+    /// This function is unsafe for the same reason as `pop()`.
+    pub unsafe fn for_each<F: FnMut(&T)>(&self, mut f: F) {
+        let mut cur = (*(*self.tail.get())).next.load(Ordering::Acquire);
+        while !cur.is_null() {
+            if let Some(ref value) = (*cur).value {
+                f(value);
+            }
+            cur = (*cur).next.load(Ordering::Acquire);
+        }
+    }
 }
 
 impl<T> Drop for Queue<T> {
@@ -0,0 +1,228 @@
+use std::any::Any;
+use std::fmt;
+use std::time::Duration;
+
+use futures::{Async, Future, Poll};
+use tokio_core::reactor::Timeout;
+
+use arbiter::Arbiter;
+use handler::{MessageResult, ResponseType};
+
+use super::request_map::{RequestMap, RequestMapErr, RequestFlatten};
+use super::{MailboxError, SendError, Subscriber};
+
+/// Subscriber that is able to send request and wait for response
+pub(crate) trait RequestSubscriber<M: ResponseType + 'static>: Subscriber<M> {
+    /// Send request, returned future resolves with message result
+    fn call(&self, msg: M) -> Box<Future<Item=MessageResult<M>, Error=MailboxError>>;
+
+    /// Create boxed clone of the current subscriber
+    fn boxed_request(&self) -> Box<RequestSubscriber<M>>;
+}
+
+/// Request subscriber that could be sent to other threads
+pub(crate) trait SyncRequestSubscriber<M: ResponseType + 'static>: RequestSubscriber<M> + Send {
+    /// Create boxed clone of the current subscriber
+    fn boxed_sync_request(&self) -> Box<SyncRequestSubscriber<M>>;
+}
+
+/// Type erased address of the actor that handles message `M`
+///
+/// Unlike `Subscriber`, recipient is able to send request and wait for
+/// response, see `call()`. Recipient could be created with
+/// `Address::into_recipient()` or `SyncAddress::into_recipient()`,
+/// it implements `Subscriber<M>` as well.
+pub struct Recipient<M: ResponseType + 'static> {
+    tx: Box<RequestSubscriber<M>>,
+}
+
+impl<M: ResponseType + 'static> Recipient<M> {
+    pub(crate) fn new(tx: Box<RequestSubscriber<M>>) -> Recipient<M> {
+        Recipient{tx: tx}
+    }
+
+    /// Send message and asynchronously wait for response
+    ///
+    /// If mailbox is full, request waits until mailbox has room.
+    /// If returned future get dropped, message cancels.
+    pub fn call(&self, msg: M) -> RecipientRequest<M> {
+        RecipientRequest{fut: self.tx.call(msg), timeout: None}
+    }
+
+    /// Convert recipient to a `Subscriber`
+    pub fn into_subscriber(self) -> Box<Subscriber<M>> {
+        Box::new(self)
+    }
+}
+
+impl<M: ResponseType + 'static> Clone for Recipient<M> {
+    fn clone(&self) -> Recipient<M> {
+        Recipient{tx: self.tx.boxed_request()}
+    }
+}
+
+impl<M: ResponseType + 'static> fmt::Debug for Recipient<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Recipient<_>")
+    }
+}
+
+impl<M: ResponseType + 'static> Subscriber<M> for Recipient<M> {
+    fn send(&self, msg: M) -> Result<(), SendError<M>> {
+        self.tx.send(msg)
+    }
+
+    fn try_send(&self, msg: M) -> Result<(), SendError<M>> {
+        self.tx.try_send(msg)
+    }
+
+    fn try_send_nowait(&self, msg: M) -> Result<(), SendError<M>> {
+        self.tx.try_send_nowait(msg)
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    #[doc(hidden)]
+    fn boxed(&self) -> Box<Subscriber<M>> {
+        Box::new(self.clone())
+    }
+
+    #[doc(hidden)]
+    fn mailbox_id(&self) -> usize {
+        self.tx.mailbox_id()
+    }
+}
+
+/// Type erased `Send` address of the actor that handles message `M`
+///
+/// Same as `Recipient`, but could be moved to other threads. Recipient could
+/// be created with `SyncAddress::into_sync_recipient()`, request future
+/// returned by `call()` runs in the current arbiter.
+pub struct SyncRecipient<M: ResponseType + 'static> {
+    tx: Box<SyncRequestSubscriber<M>>,
+}
+
+impl<M: ResponseType + 'static> SyncRecipient<M> {
+    pub(crate) fn new(tx: Box<SyncRequestSubscriber<M>>) -> SyncRecipient<M> {
+        SyncRecipient{tx: tx}
+    }
+
+    /// Send message and asynchronously wait for response
+    ///
+    /// If mailbox is full, request waits until mailbox has room.
+    /// If returned future get dropped, message cancels.
+    pub fn call(&self, msg: M) -> RecipientRequest<M> {
+        RecipientRequest{fut: self.tx.call(msg), timeout: None}
+    }
+
+    /// Convert recipient to a `Subscriber`
+    pub fn into_subscriber(self) -> Box<Subscriber<M> + Send> {
+        Box::new(self)
+    }
+}
+
+impl<M: ResponseType + 'static> Clone for SyncRecipient<M> {
+    fn clone(&self) -> SyncRecipient<M> {
+        SyncRecipient{tx: self.tx.boxed_sync_request()}
+    }
+}
+
+impl<M: ResponseType + 'static> fmt::Debug for SyncRecipient<M> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "SyncRecipient<_>")
+    }
+}
+
+impl<M: ResponseType + 'static> From<SyncRecipient<M>> for Recipient<M> {
+    fn from(recipient: SyncRecipient<M>) -> Recipient<M> {
+        Recipient{tx: recipient.tx.boxed_request()}
+    }
+}
+
+impl<M: ResponseType + 'static> Subscriber<M> for SyncRecipient<M> {
+    fn send(&self, msg: M) -> Result<(), SendError<M>> {
+        self.tx.send(msg)
+    }
+
+    fn try_send(&self, msg: M) -> Result<(), SendError<M>> {
+        self.tx.try_send(msg)
+    }
+
+    fn try_send_nowait(&self, msg: M) -> Result<(), SendError<M>> {
+        self.tx.try_send_nowait(msg)
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    #[doc(hidden)]
+    fn boxed(&self) -> Box<Subscriber<M>> {
+        Box::new(self.clone())
+    }
+
+    #[doc(hidden)]
+    fn mailbox_id(&self) -> usize {
+        self.tx.mailbox_id()
+    }
+}
+
+/// `RecipientRequest` is a `Future` which represents response to `Recipient::call()`
+#[must_use = "future do nothing unless polled"]
+pub struct RecipientRequest<M: ResponseType + 'static> {
+    fut: Box<Future<Item=MessageResult<M>, Error=MailboxError>>,
+    timeout: Option<Timeout>,
+}
+
+impl<M: ResponseType + 'static> RecipientRequest<M> {
+    /// Set request timeout
+    ///
+    /// Timeout covers both waiting for mailbox room and waiting for
+    /// handler's response. Future fails with `MailboxError::Timeout` once
+    /// `dur` passes, queued message is skipped once future gets dropped.
+    pub fn timeout(mut self, dur: Duration) -> Self {
+        self.timeout = Some(Timeout::new(dur, Arbiter::handle()).unwrap());
+        self
+    }
+
+    /// Map message result item, mailbox error is passed through
+    pub fn map<F, U>(self, f: F) -> RequestMap<Self, F> where F: FnOnce(M::Item) -> U {
+        RequestMap::new(self, f)
+    }
+
+    /// Map message result error, mailbox error is passed through
+    pub fn map_err<F, U>(self, f: F) -> RequestMapErr<Self, F> where F: FnOnce(M::Error) -> U {
+        RequestMapErr::new(self, f)
+    }
+
+    /// Collapse mailbox error and message error into one error type
+    ///
+    /// Future resolves to `M::Item`, both errors get converted into `E`.
+    pub fn flatten<E>(self) -> RequestFlatten<Self, E>
+        where E: From<MailboxError> + From<M::Error>
+    {
+        RequestFlatten::new(self)
+    }
+}
+
+impl<M: ResponseType + 'static> Future for RecipientRequest<M> {
+    type Item = MessageResult<M>;
+    type Error = MailboxError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        if let Async::Ready(res) = self.fut.poll()? {
+            return Ok(Async::Ready(res))
+        }
+        if let Some(ref mut timeout) = self.timeout {
+            match timeout.poll() {
+                Ok(Async::Ready(())) => Err(MailboxError::Timeout),
+                Ok(Async::NotReady) => Ok(Async::NotReady),
+                Err(_) => unreachable!()
+            }
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+}
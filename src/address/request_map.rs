@@ -0,0 +1,167 @@
+use std::marker::PhantomData;
+
+use futures::{Async, Future, Poll};
+
+use actor::Actor;
+use fut::ActorFuture;
+
+use super::MailboxError;
+
+
+/// Request future that maps message result item, see `RequestFut::map()`
+#[must_use = "future do nothing unless polled"]
+pub struct RequestMap<R, F> {
+    req: R,
+    f: Option<F>,
+}
+
+impl<R, F> RequestMap<R, F> {
+    pub(crate) fn new(req: R, f: F) -> RequestMap<R, F> {
+        RequestMap{req: req, f: Some(f)}
+    }
+
+    /// Collapse mailbox error and message error into one error type
+    pub fn flatten<E>(self) -> RequestFlatten<Self, E> {
+        RequestFlatten::new(self)
+    }
+}
+
+impl<R, F, T, E, U> Future for RequestMap<R, F>
+    where R: Future<Item=Result<T, E>, Error=MailboxError>, F: FnOnce(T) -> U
+{
+    type Item = Result<U, E>;
+    type Error = MailboxError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.req.poll()? {
+            Async::Ready(res) => {
+                let f = self.f.take().expect("cannot poll RequestMap twice");
+                Ok(Async::Ready(res.map(f)))
+            }
+            Async::NotReady => Ok(Async::NotReady),
+        }
+    }
+}
+
+impl<R, F, T, E, U> ActorFuture for RequestMap<R, F>
+    where R: ActorFuture<Item=Result<T, E>, Error=MailboxError>, F: FnOnce(T) -> U
+{
+    type Item = Result<U, E>;
+    type Error = MailboxError;
+    type Actor = R::Actor;
+
+    fn poll(&mut self, act: &mut R::Actor,
+            ctx: &mut <R::Actor as Actor>::Context) -> Poll<Self::Item, Self::Error>
+    {
+        match self.req.poll(act, ctx)? {
+            Async::Ready(res) => {
+                let f = self.f.take().expect("cannot poll RequestMap twice");
+                Ok(Async::Ready(res.map(f)))
+            }
+            Async::NotReady => Ok(Async::NotReady),
+        }
+    }
+}
+
+/// Request future that maps message result error, see `RequestFut::map_err()`
+#[must_use = "future do nothing unless polled"]
+pub struct RequestMapErr<R, F> {
+    req: R,
+    f: Option<F>,
+}
+
+impl<R, F> RequestMapErr<R, F> {
+    pub(crate) fn new(req: R, f: F) -> RequestMapErr<R, F> {
+        RequestMapErr{req: req, f: Some(f)}
+    }
+
+    /// Collapse mailbox error and message error into one error type
+    pub fn flatten<E>(self) -> RequestFlatten<Self, E> {
+        RequestFlatten::new(self)
+    }
+}
+
+impl<R, F, T, E, U> Future for RequestMapErr<R, F>
+    where R: Future<Item=Result<T, E>, Error=MailboxError>, F: FnOnce(E) -> U
+{
+    type Item = Result<T, U>;
+    type Error = MailboxError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        match self.req.poll()? {
+            Async::Ready(res) => {
+                let f = self.f.take().expect("cannot poll RequestMapErr twice");
+                Ok(Async::Ready(res.map_err(f)))
+            }
+            Async::NotReady => Ok(Async::NotReady),
+        }
+    }
+}
+
+impl<R, F, T, E, U> ActorFuture for RequestMapErr<R, F>
+    where R: ActorFuture<Item=Result<T, E>, Error=MailboxError>, F: FnOnce(E) -> U
+{
+    type Item = Result<T, U>;
+    type Error = MailboxError;
+    type Actor = R::Actor;
+
+    fn poll(&mut self, act: &mut R::Actor,
+            ctx: &mut <R::Actor as Actor>::Context) -> Poll<Self::Item, Self::Error>
+    {
+        match self.req.poll(act, ctx)? {
+            Async::Ready(res) => {
+                let f = self.f.take().expect("cannot poll RequestMapErr twice");
+                Ok(Async::Ready(res.map_err(f)))
+            }
+            Async::NotReady => Ok(Async::NotReady),
+        }
+    }
+}
+
+/// Request future with collapsed errors, see `RequestFut::flatten()`
+///
+/// Both `MailboxError` and message error get converted into `E`.
+#[must_use = "future do nothing unless polled"]
+pub struct RequestFlatten<R, E> {
+    req: R,
+    err: PhantomData<E>,
+}
+
+impl<R, E> RequestFlatten<R, E> {
+    pub(crate) fn new(req: R) -> RequestFlatten<R, E> {
+        RequestFlatten{req: req, err: PhantomData}
+    }
+}
+
+impl<R, T, ME, E> Future for RequestFlatten<R, E>
+    where R: Future<Item=Result<T, ME>, Error=MailboxError>,
+          E: From<MailboxError> + From<ME>
+{
+    type Item = T;
+    type Error = E;
+
+    fn poll(&mut self) -> Poll<T, E> {
+        match self.req.poll()? {
+            Async::Ready(res) => Ok(Async::Ready(res?)),
+            Async::NotReady => Ok(Async::NotReady),
+        }
+    }
+}
+
+impl<R, T, ME, E> ActorFuture for RequestFlatten<R, E>
+    where R: ActorFuture<Item=Result<T, ME>, Error=MailboxError>,
+          E: From<MailboxError> + From<ME>
+{
+    type Item = T;
+    type Error = E;
+    type Actor = R::Actor;
+
+    fn poll(&mut self, act: &mut R::Actor,
+            ctx: &mut <R::Actor as Actor>::Context) -> Poll<T, E>
+    {
+        match self.req.poll(act, ctx)? {
+            Async::Ready(res) => Ok(Async::Ready(res?)),
+            Async::NotReady => Ok(Async::NotReady),
+        }
+    }
+}
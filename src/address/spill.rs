@@ -0,0 +1,136 @@
+//! Mailbox spillover to disk, see `ActorBuilder::spill_to_disk()`
+use std::any::type_name;
+use std::collections::HashMap;
+use std::fs::{self, File, OpenOptions};
+use std::io::{self, Read, Seek, SeekFrom, Write};
+use std::path::PathBuf;
+
+use serde::Serialize;
+use serde::de::DeserializeOwned;
+use serde_json;
+
+use actor::Actor;
+use handler::{Handler, ResponseType};
+use super::envelope::{Envelope, ToEnvelope};
+
+/// Maximum number of spilled messages handed to the actor within one mailbox poll
+pub(crate) const SPILL_BATCH: usize = 64;
+
+type Decoder<A> = fn(&[u8]) -> Option<Envelope<A>>;
+
+/// Spill file, sequence of length-prefixed records
+///
+/// Record consists of message type name and json encoded message, both
+/// prefixed with big endian `u32` length.
+pub(crate) struct Spill<A: Actor> {
+    path: PathBuf,
+    file: File,
+    read_pos: u64,
+    write_pos: u64,
+    max_bytes: u64,
+    len: usize,
+    decoders: HashMap<&'static str, Decoder<A>>,
+}
+
+impl<A: Actor> Spill<A> {
+    pub fn new(path: PathBuf, max_bytes: u64) -> io::Result<Spill<A>> {
+        let file = OpenOptions::new()
+            .read(true).write(true).create(true).truncate(true).open(&path)?;
+        Ok(Spill{path: path, file: file, read_pos: 0, write_pos: 0,
+                 max_bytes: max_bytes, len: 0, decoders: HashMap::new()})
+    }
+
+    /// Number of spilled messages
+    pub fn len(&self) -> usize {
+        self.len
+    }
+
+    /// Append message to spill file, returns `false` if file is full
+    pub fn push<M>(&mut self, msg: &M) -> io::Result<bool>
+        where A: Handler<M>, A::Context: ToEnvelope<A>,
+              M: ResponseType + Serialize + DeserializeOwned + Send + 'static,
+    {
+        let tp = type_name::<M>();
+        let payload = serde_json::to_vec(msg)?;
+        let size = 8 + tp.len() + payload.len();
+        if self.write_pos + size as u64 > self.max_bytes {
+            return Ok(false)
+        }
+
+        let mut record = Vec::with_capacity(size);
+        record.extend_from_slice(&(tp.len() as u32).to_be_bytes());
+        record.extend_from_slice(tp.as_bytes());
+        record.extend_from_slice(&(payload.len() as u32).to_be_bytes());
+        record.extend_from_slice(&payload);
+
+        self.file.seek(SeekFrom::Start(self.write_pos))?;
+        self.file.write_all(&record)?;
+        self.write_pos += size as u64;
+        self.len += 1;
+        self.decoders.entry(tp).or_insert(decode::<A, M>);
+        Ok(true)
+    }
+
+    /// Take oldest spilled message
+    pub fn pop(&mut self) -> Option<Envelope<A>> {
+        while self.len != 0 {
+            let record = self.read_record();
+            self.len -= 1;
+            if self.len == 0 {
+                // reuse file space
+                self.read_pos = 0;
+                self.write_pos = 0;
+                let _ = self.file.set_len(0);
+            }
+
+            match record {
+                Ok((tp, payload)) => {
+                    match self.decoders.get(tp.as_str()).and_then(|decode| decode(&payload)) {
+                        Some(env) => return Some(env),
+                        None => error!("Can not decode spilled message: {}", tp),
+                    }
+                }
+                Err(err) => {
+                    error!("Can not read spill file, dropping spilled messages: {}", err);
+                    self.len = 0;
+                    self.read_pos = 0;
+                    self.write_pos = 0;
+                    let _ = self.file.set_len(0);
+                }
+            }
+        }
+        None
+    }
+
+    fn read_record(&mut self) -> io::Result<(String, Vec<u8>)> {
+        self.file.seek(SeekFrom::Start(self.read_pos))?;
+        let tp = self.read_chunk()?;
+        let payload = self.read_chunk()?;
+        self.read_pos += 8 + tp.len() as u64 + payload.len() as u64;
+        let tp = String::from_utf8(tp)
+            .map_err(|err| io::Error::new(io::ErrorKind::InvalidData, err))?;
+        Ok((tp, payload))
+    }
+
+    fn read_chunk(&mut self) -> io::Result<Vec<u8>> {
+        let mut len = [0u8; 4];
+        self.file.read_exact(&mut len)?;
+        let mut buf = vec![0u8; u32::from_be_bytes(len) as usize];
+        self.file.read_exact(&mut buf)?;
+        Ok(buf)
+    }
+}
+
+impl<A: Actor> Drop for Spill<A> {
+    fn drop(&mut self) {
+        let _ = fs::remove_file(&self.path);
+    }
+}
+
+fn decode<A, M>(buf: &[u8]) -> Option<Envelope<A>>
+    where A: Actor + Handler<M>, A::Context: ToEnvelope<A>,
+          M: ResponseType + DeserializeOwned + Send + 'static,
+{
+    serde_json::from_slice::<M>(buf).ok()
+        .map(|msg| <A::Context as ToEnvelope<A>>::pack_msg(msg))
+}
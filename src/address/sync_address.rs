@@ -1,10 +1,93 @@
+use std::any::Any;
+use std::sync::Arc;
+use std::thread::{self, Thread};
+use std::time::{Duration, Instant};
+
+use futures::{Async, Future};
+use futures::executor::{self, Notify, NotifyHandle};
+use futures::sync::oneshot::Sender;
+#[cfg(feature="spill")]
+use serde::Serialize;
+#[cfg(feature="spill")]
+use serde::de::DeserializeOwned;
+
 use actor::Actor;
-use handler::{Handler, ResponseType};
+use actors::deadletter;
+use arbiter::Arbiter;
+use msgs::{StopActor, TerminateActor};
+use handoff::Handoff;
+use handler::{Handler, MessageResult, ResponseType};
 
-use super::{Request, RequestFut, SendError, Subscriber, ToEnvelope};
-use super::sync_channel::AddressSender;
+use super::{MailboxError, Recipient, Request, RequestFut, RequestSubscriber, SendFut, SendError,
+            Subscriber, SyncRecipient, SyncRequestSubscriber, ToEnvelope};
+use super::sync_channel::{AddressSender, WeakAddressSender};
 
 /// `Send` address of the actor. Actor can run in different thread
+///
+/// # Message ordering
+///
+/// Message is ordered at the moment mailbox accepts it. `do_send()`,
+/// `try_send()` and `start_send()` are accepted when method returns `Ok`,
+/// `send()` and `call_fut()` are accepted when mailbox has room, i.e.
+/// immediately or later when returned future resolves message delivery.
+///
+/// * Two messages accepted from the same thread are handled in order they
+///   got accepted, regardless of which address clone or which of the
+///   methods above was used.
+/// * Messages sent from different threads have no defined order, handler
+///   could see them interleaved in any way.
+/// * Message that waits for mailbox room is not accepted yet. Message sent
+///   while previous `send()` or `call_fut()` future still waits could
+///   overtake it, wait for the future first if order matters.
+/// * Messages of the system lane, `stop()`, `terminate()` and
+///   `try_send_system()`, are handled before ordinary messages.
+/// * Spilled messages keep their order among each other, see `spill_send()`.
+///   Message sent with other methods could overtake spilled messages.
+/// * `Overflow::DropOldest` mailbox drops pending messages, remaining
+///   messages keep their order.
+/// * `Overflow::Reject` mailbox does not accept messages while it is full,
+///   senders get `MailboxError::Full` instead of waiting for room.
+///
+/// # Message bounds
+///
+/// Methods that do not transport response, `send()`, `do_send()`,
+/// `try_send()` and subscribers, require only `M: Send`. `call_fut()`
+/// sends result back to the caller, so `M::Item` and `M::Error` have to be
+/// `Send` as well:
+///
+/// ```compile_fail,E0277
+/// # extern crate actix;
+/// use std::rc::Rc;
+/// use actix::prelude::*;
+///
+/// struct Log;
+///
+/// impl ResponseType for Log {
+///     type Item = ();
+///     type Error = Rc<String>;
+/// }
+///
+/// struct Logger;
+///
+/// impl Actor for Logger {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl Handler<Log> for Logger {
+///     type Result = MessageResult<Log>;
+///
+///     fn handle(&mut self, _: Log, _: &mut Context<Self>) -> Self::Result {
+///         Ok(())
+///     }
+/// }
+///
+/// fn main() {
+///     let sys = System::new("test");
+///     let addr: SyncAddress<_> = Logger.start();
+///     addr.do_send(Log);                 // ok
+///     let _ = addr.call_fut(Log);        // `Rc<String>` is not `Send`
+/// }
+/// ```
 pub struct SyncAddress<A> where A: Actor {
     tx: AddressSender<A>
 }
@@ -29,16 +112,66 @@ impl<A> SyncAddress<A> where A: Actor {
         self.tx.connected()
     }
 
+    /// Create weak address of the actor
+    ///
+    /// Weak address does not keep actor's mailbox connected.
+    pub fn downgrade(&self) -> WeakSyncAddress<A> {
+        WeakSyncAddress{tx: self.tx.downgrade()}
+    }
+
     /// Send message `M` to actor `A`
     ///
-    /// This function ignores receiver capacity and always successed.
-    /// Message cold be sent to an actor running in different thread.
-    pub fn send<M>(&self, msg: M)
+    /// Returned future resolves when message is placed to actor's mailbox.
+    /// If mailbox is full, future waits until mailbox has room, or fails with
+    /// `MailboxError::Full` if mailbox overflow policy is `Overflow::Reject`.
+    /// Future fails with `MailboxError::Closed` if mailbox is closed.
+    /// If future gets dropped while it waits for mailbox room, message is
+    /// published as dead letter with `DeadLetterReason::Dropped` reason.
+    pub fn send<M>(&self, msg: M) -> SendFut<A, M>
         where A: Handler<M>, <A as Actor>::Context: ToEnvelope<A>,
               M: ResponseType + Send + 'static,
-              M::Item: Send, M::Error: Send,
     {
-        let _ = self.tx.do_send(msg);
+        match self.tx.try_send(msg, false) {
+            Ok(()) => SendFut::new(None, false),
+            Err(SendError::Full(msg)) =>
+                SendFut::new(Some((self.tx.clone(), msg)), false),
+            Err(SendError::Closed(_)) =>
+                SendFut::new(None, true),
+        }
+    }
+
+    /// Send message `M` to actor `A`
+    ///
+    /// This function ignores receiver capacity, unless mailbox overflow
+    /// policy is `Overflow::Reject`. Message cold be sent to an actor running
+    /// in different thread. If mailbox is closed or rejects message,
+    /// message is published to `DeadLetters` service.
+    pub fn do_send<M>(&self, msg: M)
+        where A: Handler<M>, <A as Actor>::Context: ToEnvelope<A>,
+              M: ResponseType + Send + 'static,
+    {
+        let res = if self.tx.rejects() {
+            self.tx.try_send(msg, false)
+        } else {
+            self.tx.do_send(msg)
+        };
+        if let Err(err) = res {
+            deadletter::publish::<A, M>(&err.into_inner());
+        }
+    }
+
+    /// Send message `M` to actor `A`, spill it to disk if mailbox is full
+    ///
+    /// Spilled messages are handled in order once in-memory mailbox is
+    /// drained. This function fails with `SendError::Full` if spill file
+    /// reached its size limit, see `ActorBuilder::spill_to_disk()`.
+    /// If actor does not spill messages, this function works like `do_send()`.
+    #[cfg(feature="spill")]
+    pub fn spill_send<M>(&self, msg: M) -> Result<(), SendError<M>>
+        where A: Handler<M>, <A as Actor>::Context: ToEnvelope<A>,
+              M: ResponseType + Serialize + DeserializeOwned + Send + 'static,
+    {
+        self.tx.spill_send(msg)
     }
 
     /// Send message `M` to actor `A`
@@ -48,58 +181,236 @@ impl<A> SyncAddress<A> where A: Actor {
     pub fn try_send<M>(&self, msg: M) -> Result<(), SendError<M>>
         where A: Handler<M>, <A as Actor>::Context: ToEnvelope<A>,
               M: ResponseType + Send + 'static,
-              M::Item: Send, M::Error: Send,
     {
         self.tx.try_send(msg, false)
     }
 
+    /// Send message `M` to the system lane of actor `A`
+    ///
+    /// System lane is meant for framework messages like stop requests, it is
+    /// drained before ordinary mailbox. Lane has small fixed capacity,
+    /// this method fails if lane is full or closed.
+    pub fn try_send_system<M>(&self, msg: M) -> Result<(), SendError<M>>
+        where A: Handler<M>, <A as Actor>::Context: ToEnvelope<A>,
+              M: ResponseType + Send + 'static,
+    {
+        self.tx.send_system(msg)
+    }
+
+    /// Ask actor to stop
+    ///
+    /// Stop request is sent through system lane, so it is handled even if
+    /// mailbox is full. Actor could prevent stopping by returning `false`
+    /// from `Actor::stopping()` method.
+    pub fn stop(&self) where A: Handler<StopActor>, A::Context: ToEnvelope<A> {
+        if let Err(SendError::Full(msg)) = self.tx.send_system(StopActor) {
+            let _ = self.tx.do_send(msg);
+        }
+    }
+
+    /// Terminate actor, `Actor::stopping()` method is not called
+    ///
+    /// Request is sent through system lane, same as `stop()`.
+    pub fn terminate(&self) where A: Handler<TerminateActor>, A::Context: ToEnvelope<A> {
+        if let Err(SendError::Full(msg)) = self.tx.send_system(TerminateActor) {
+            let _ = self.tx.do_send(msg);
+        }
+    }
+
+    /// Move queued messages to replacement actor and stop actor, see `Handoff`
+    ///
+    /// Request is sent through system lane, so it is handled before queued
+    /// messages. This method fails if lane is full or closed.
+    pub fn handoff_to<B>(&self, handoff: Handoff<B>) -> Result<(), SendError<Handoff<B>>>
+        where A: Handler<Handoff<B>>, A::Context: ToEnvelope<A>, B: Actor
+    {
+        self.tx.send_system(handoff)
+    }
+
+    /// Put message with existing response channel to the mailbox,
+    /// mailbox capacity is ignored
+    pub(crate) fn push<M>(&self, msg: M, tx: Option<Sender<MessageResult<M>>>)
+                          -> Result<(), SendError<M>>
+        where A: Handler<M>, A::Context: ToEnvelope<A>,
+              M: ResponseType + Send + 'static, M::Item: Send, M::Error: Send,
+    {
+        self.tx.push(msg, tx)
+    }
+
+    /// Check if address is ready to accept message
+    ///
+    /// If actor's mailbox is full, current task get registered and it gets
+    /// notified when mailbox has room. Waiting tasks are notified in order
+    /// they got parked. Use `start_send()` to send message after
+    /// `Async::Ready` is returned. This method must be called from inside
+    /// of a task.
+    pub fn poll_ready(&mut self) -> Async<()> {
+        self.tx.poll_ready()
+    }
+
+    /// Send message `M` to actor `A` after `poll_ready()`
+    ///
+    /// Message is accepted even if mailbox is full, in that case following
+    /// `poll_ready()` call returns `Async::NotReady` until mailbox has room.
+    /// This method fails with `SendError::Full` if `poll_ready()` is not ready.
+    /// Rendezvous mailbox never buffers messages, for such mailbox this
+    /// method works like `try_send()` and fails with `SendError::Full`
+    /// unless actor waits for message.
+    pub fn start_send<M>(&mut self, msg: M) -> Result<(), SendError<M>>
+        where A: Handler<M>, <A as Actor>::Context: ToEnvelope<A>,
+              M: ResponseType + Send + 'static,
+    {
+        self.tx.start_send(msg)
+    }
+
     /// Send message to actor `A` and asynchronously wait for response.
     ///
     /// if returned `Request` object get dropped, message cancels.
+    ///
+    /// Calling actor is used only to infer type of the returned future,
+    /// use `addr.call_fut(msg).into_actor(self)` instead.
+    #[deprecated(since="0.5.0", note="use `call_fut(msg).into_actor(self)` instead")]
     pub fn call<B: Actor, M>(&self, _: &B, msg: M) -> Request<A, B, M>
         where A: Handler<M>, A::Context: ToEnvelope<A>,
               M: ResponseType + Send + 'static, M::Item: Send, M::Error: Send,
     {
-        match self.tx.send(msg) {
-            Ok(rx) => Request::new(Some(rx), None),
+        let mailbox = self.tx.mailbox_id();
+        match self.tx.send(msg, false) {
+            Ok(rx) => Request::new(Some(rx), None, mailbox),
             Err(SendError::Full(msg)) =>
-                Request::new(None, Some((self.tx.clone(), msg))),
+                Request::new(None, Some((self.tx.clone(), msg)), mailbox),
             Err(SendError::Closed(_)) =>
-                Request::new(None, None),
+                Request::new(None, None, mailbox),
         }
     }
 
     /// Send message to actor `A` and asynchronously wait for response.
     ///
     /// if returned `Receiver` object get dropped, message cancels.
+    /// To use response within actor's context, convert future with
+    /// `into_actor()`:
+    ///
+    /// ```rust,ignore
+    /// addr.call_fut(Ping)
+    ///     .into_actor(self)
+    ///     .then(|res, act, ctx| ...)
+    ///     .wait(ctx);
+    /// ```
     pub fn call_fut<M>(&self, msg: M) -> RequestFut<A, M>
         where A: Handler<M>, A::Context: ToEnvelope<A>,
               M: ResponseType + Send + 'static,
               M::Item: Send, M::Error: Send,
     {
-        match self.tx.send(msg) {
-            Ok(rx) => RequestFut::new(Some(rx), None),
+        let mailbox = self.tx.mailbox_id();
+        match self.tx.send(msg, false) {
+            Ok(rx) => RequestFut::new(Some(rx), None, mailbox),
             Err(SendError::Full(msg)) =>
-                RequestFut::new(None, Some((self.tx.clone(), msg))),
+                RequestFut::new(None, Some((self.tx.clone(), msg)), mailbox),
             Err(SendError::Closed(_)) =>
-                RequestFut::new(None, None),
+                RequestFut::new(None, None, mailbox),
+        }
+    }
+
+    /// Send message to actor `A` and block current thread until response
+    /// arrives, but no longer than `timeout`.
+    ///
+    /// Method is meant for threads that do not run actix system, like test
+    /// harness or ffi entry points, it does not need event loop. Request is
+    /// cancelled and `MailboxError::Timeout` is returned if `timeout` expires.
+    ///
+    /// Panics if it is called from arbiter's thread, blocked event loop
+    /// could never deliver response.
+    pub fn call_blocking<M>(&self, msg: M, timeout: Duration)
+                            -> Result<Result<M::Item, M::Error>, MailboxError>
+        where A: Handler<M>, A::Context: ToEnvelope<A>,
+              M: ResponseType + Send + 'static,
+              M::Item: Send, M::Error: Send,
+    {
+        if Arbiter::try_handle().is_some() {
+            panic!("SyncAddress::call_blocking() must not be called from an actix arbiter \
+                    thread, it would block arbiter's event loop; use call_fut()")
+        }
+
+        let deadline = Instant::now() + timeout;
+        let mut task = executor::spawn(self.call_fut(msg));
+        let notify = NotifyHandle::from(Arc::new(ThreadNotify(thread::current())));
+        loop {
+            match task.poll_future_notify(&notify, 0) {
+                Ok(Async::Ready(res)) => return Ok(res),
+                Ok(Async::NotReady) => (),
+                Err(err) => return Err(err),
+            }
+            let now = Instant::now();
+            if now >= deadline {
+                return Err(MailboxError::Timeout)
+            }
+            thread::park_timeout(deadline - now);
         }
     }
 
     /// Convert address to a `Subscriber` for specific message type
     pub fn into_subscriber<M: 'static + Send>(self) -> Box<Subscriber<M> + Send>
         where A: Handler<M>, A::Context: ToEnvelope<A>,
-              M: ResponseType + Send + 'static,
-              M::Item: Send, M::Error: Send {
+              M: ResponseType + Send + 'static {
         Box::new(self)
     }
+
+    /// Convert address to a `Recipient` for specific message type
+    pub fn into_recipient<M>(self) -> Recipient<M>
+        where A: Handler<M>, A::Context: ToEnvelope<A>,
+              M: ResponseType + Send + 'static, M::Item: Send, M::Error: Send,
+    {
+        Recipient::new(Box::new(self))
+    }
+
+    /// Convert address to a `SyncRecipient` for specific message type
+    ///
+    /// Unlike `Recipient`, returned recipient could be moved to other threads.
+    pub fn into_sync_recipient<M>(self) -> SyncRecipient<M>
+        where A: Handler<M>, A::Context: ToEnvelope<A>,
+              M: ResponseType + Send + 'static, M::Item: Send, M::Error: Send,
+    {
+        SyncRecipient::new(Box::new(self))
+    }
+}
+
+/// Weak reference to the actor's `Send` address
+///
+/// Weak address does not keep actor alive, actor stops once all of its
+/// addresses are dropped, regardless of weak addresses.
+pub struct WeakSyncAddress<A> where A: Actor {
+    tx: WeakAddressSender<A>
+}
+
+unsafe impl<A> Send for WeakSyncAddress<A> where A: Actor {}
+unsafe impl<A> Sync for WeakSyncAddress<A> where A: Actor {}
+
+impl<A> Clone for WeakSyncAddress<A> where A: Actor {
+    fn clone(&self) -> Self {
+        WeakSyncAddress{tx: self.tx.clone()}
+    }
+}
+
+impl<A> WeakSyncAddress<A> where A: Actor {
+    /// Get actor's address, `None` if actor is gone
+    pub fn upgrade(&self) -> Option<SyncAddress<A>> {
+        self.tx.upgrade().map(SyncAddress::new)
+    }
+}
+
+/// Wakes up thread that is blocked in `call_blocking()`
+struct ThreadNotify(Thread);
+
+impl Notify for ThreadNotify {
+    fn notify(&self, _: usize) {
+        self.0.unpark()
+    }
 }
 
 impl<A, M> Subscriber<M> for SyncAddress<A>
     where A: Actor + Handler<M>,
           <A as Actor>::Context: ToEnvelope<A>,
           M: ResponseType + Send + 'static,
-          M::Item: Send, M::Error: Send,
 {
     fn send(&self, msg: M) -> Result<(), SendError<M>> {
         self.tx.do_send(msg)
@@ -109,8 +420,45 @@ impl<A, M> Subscriber<M> for SyncAddress<A>
         self.tx.try_send(msg, true)
     }
 
+    fn try_send_nowait(&self, msg: M) -> Result<(), SendError<M>> {
+        self.tx.try_send(msg, false)
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
     #[doc(hidden)]
     fn boxed(&self) -> Box<Subscriber<M>> {
         Box::new(self.clone())
     }
+
+    #[doc(hidden)]
+    fn mailbox_id(&self) -> usize {
+        self.tx.mailbox_id()
+    }
+}
+
+impl<A, M> RequestSubscriber<M> for SyncAddress<A>
+    where A: Actor + Handler<M>,
+          <A as Actor>::Context: ToEnvelope<A>,
+          M: ResponseType + Send + 'static, M::Item: Send, M::Error: Send,
+{
+    fn call(&self, msg: M) -> Box<Future<Item=MessageResult<M>, Error=MailboxError>> {
+        Box::new(self.call_fut(msg))
+    }
+
+    fn boxed_request(&self) -> Box<RequestSubscriber<M>> {
+        Box::new(self.clone())
+    }
+}
+
+impl<A, M> SyncRequestSubscriber<M> for SyncAddress<A>
+    where A: Actor + Handler<M>,
+          <A as Actor>::Context: ToEnvelope<A>,
+          M: ResponseType + Send + 'static, M::Item: Send, M::Error: Send,
+{
+    fn boxed_sync_request(&self) -> Box<SyncRequestSubscriber<M>> {
+        Box::new(self.clone())
+    }
 }
@@ -1,21 +1,32 @@
 //! This is copy of [sync/mpsc/](https://github.com/alexcrichton/futures-rs)
-use std::usize;
+use std::{cmp, usize};
+#[cfg(feature="spill")]
+use std::any::type_name;
 use std::thread;
 use std::cell::Cell;
-use std::sync::atomic::AtomicUsize;
+use std::sync::atomic::{AtomicBool, AtomicUsize};
 use std::sync::atomic::Ordering::{SeqCst, Relaxed};
-use std::sync::{Arc, Mutex};
+use std::sync::{Arc, Mutex, Weak};
+use std::collections::VecDeque;
+use std::time::Instant;
 
 use futures::task::{self, Task};
 use futures::{Async, Poll, Stream};
-use futures::sync::oneshot::{channel as sync_channel, Receiver};
+use futures::sync::oneshot::{channel as sync_channel, Receiver, Sender};
 
 use actor::Actor;
 use handler::{Handler, ResponseType, MessageResult};
+use mailbox::{MailboxTrace, TraceKind, SYSTEM_LANE_CAPACITY};
 
-use super::SendError;
+use super::{SendError, Overflow};
 use super::queue::{Queue, PopResult};
 use super::envelope::{Envelope, ToEnvelope};
+#[cfg(feature="spill")]
+use super::spill::{Spill, SPILL_BATCH};
+#[cfg(feature="spill")]
+use serde::Serialize;
+#[cfg(feature="spill")]
+use serde::de::DeserializeOwned;
 
 
 /// The transmission end of a channel which is used to send values.
@@ -45,12 +56,26 @@ trait AssertKinds: Send + Sync + Clone {}
 /// `channel` method.
 pub struct SyncAddressReceiver<A: Actor> {
     inner: Arc<Inner<A>>,
+
+    // Number of spilled messages returned since last `start_batch()`
+    #[cfg(feature="spill")]
+    spill_batch: usize,
 }
 
 struct Inner<A: Actor> {
-    // Max buffer size of the channel. If `0` then the channel is unbounded.
+    // Max buffer size of the channel. If `UNBOUNDED` then the channel is
+    // unbounded, if `0` then messages are handed off to waiting receiver only.
     buffer: AtomicUsize,
 
+    // Receiver is parked and waits for messages, used by rendezvous channel
+    recv_waiting: AtomicBool,
+
+    // If `true` senders never block, receiver drops oldest messages instead.
+    drop_oldest: AtomicBool,
+
+    // If `true` senders get error instead of waiting for room.
+    reject: AtomicBool,
+
     // Internal channel state. Consists of the number of messages stored in the
     // channel as well as a flag signalling that the channel is closed.
     state: AtomicUsize,
@@ -66,6 +91,23 @@ struct Inner<A: Actor> {
 
     // Handle to the receiver's task.
     recv_task: Mutex<ReceiverTask>,
+
+    // Mailbox events log
+    trace: Arc<MailboxTrace>,
+
+    // System lane, bounded queue of framework messages
+    system_queue: Mutex<VecDeque<Envelope<A>>>,
+
+    // Number of messages in system lane, avoids locking of empty lane
+    system_len: AtomicUsize,
+
+    // Messages spilled to disk, see `ActorBuilder::spill_to_disk()`
+    #[cfg(feature="spill")]
+    spill: Mutex<Option<Spill<A>>>,
+
+    // Number of spilled messages, avoids locking of empty spill
+    #[cfg(feature="spill")]
+    spill_len: AtomicUsize,
 }
 
 // Struct representation of `Inner::state`.
@@ -104,6 +146,9 @@ const MAX_CAPACITY: usize = !(OPEN_MASK);
 // a channel. This is because each sender gets a guaranteed slot.
 const MAX_BUFFER: usize = MAX_CAPACITY >> 1;
 
+/// Buffer size of unbounded channel
+pub(crate) const UNBOUNDED: usize = MAX_BUFFER;
+
 // Sent to the consumer to wake up blocked producers
 #[derive(Debug)]
 struct SenderTask {
@@ -119,12 +164,15 @@ impl SenderTask {
         }
     }
 
-    fn notify(&mut self) {
+    // Returns `false` if sender is not parked, i.e. handle is stale
+    fn notify(&mut self) -> bool {
+        let parked = self.is_parked;
         self.is_parked = false;
 
         if let Some(task) = self.task.take() {
             task.notify();
         }
+        parked
     }
 }
 
@@ -142,12 +190,22 @@ impl SenderTask {
 /// The `Receiver` returned implements the `Stream` trait and has access to any
 /// number of the associated combinators for transforming the result.
 pub fn channel<A: Actor>(buffer: usize) -> (AddressSender<A>, SyncAddressReceiver<A>) {
+    channel_with_trace(buffer, MailboxTrace::new())
+}
+
+/// Creates channel that records events to existing mailbox trace
+pub(crate) fn channel_with_trace<A: Actor>(buffer: usize, trace: Arc<MailboxTrace>)
+                                           -> (AddressSender<A>, SyncAddressReceiver<A>)
+{
     // Check that the requested buffer size does not exceed the maximum buffer
     // size permitted by the system.
-    assert!(buffer < MAX_BUFFER, "requested buffer size too large");
+    assert!(buffer <= MAX_BUFFER, "requested buffer size too large");
 
     let inner = Arc::new(Inner {
         buffer: AtomicUsize::new(buffer),
+        drop_oldest: AtomicBool::new(false),
+        reject: AtomicBool::new(false),
+        recv_waiting: AtomicBool::new(false),
         state: AtomicUsize::new(INIT_STATE),
         message_queue: Queue::new(),
         parked_queue: Queue::new(),
@@ -156,6 +214,13 @@ pub fn channel<A: Actor>(buffer: usize) -> (AddressSender<A>, SyncAddressReceive
             unparked: false,
             task: None,
         }),
+        trace: trace,
+        system_queue: Mutex::new(VecDeque::new()),
+        system_len: AtomicUsize::new(0),
+        #[cfg(feature="spill")]
+        spill: Mutex::new(None),
+        #[cfg(feature="spill")]
+        spill_len: AtomicUsize::new(0),
     });
 
     let tx = AddressSender {
@@ -166,6 +231,8 @@ pub fn channel<A: Actor>(buffer: usize) -> (AddressSender<A>, SyncAddressReceive
 
     let rx = SyncAddressReceiver {
         inner: inner,
+        #[cfg(feature="spill")]
+        spill_batch: 0,
     };
 
     (tx, rx)
@@ -185,16 +252,33 @@ impl<A: Actor> AddressSender<A> {
         state.is_open
     }
 
+    /// Identity of receiver's mailbox
+    pub(crate) fn mailbox_id(&self) -> usize {
+        self.inner.trace.mailbox_id()
+    }
+
+    /// Sender should not wait for room, see `Overflow::Reject`
+    pub fn rejects(&self) -> bool {
+        self.inner.reject.load(Relaxed)
+    }
+
+    /// Create weak sender that does not keep receiver connected
+    pub(crate) fn downgrade(&self) -> WeakAddressSender<A> {
+        WeakAddressSender{inner: Arc::downgrade(&self.inner)}
+    }
+
     /// Attempts to send a message on this `Sender<A>` with blocking.
     ///
-    /// This function, must be called from inside of a task.
-    pub fn send<M>(&self, msg: M) -> Result<Receiver<MessageResult<M>>, SendError<M>>
+    /// If `park` is true, this function must be called from inside of a task.
+    pub fn send<M>(&self, msg: M, park: bool)
+                   -> Result<Receiver<MessageResult<M>>, SendError<M>>
         where A: Handler<M>, <A as Actor>::Context: ToEnvelope<A>,
               M::Item: Send, M::Error: Send,
               M: ResponseType + Send + 'static,
     {
-        // If the sender is currently blocked, reject the message
-        if !self.poll_unparked(false).is_ready() {
+        // If the sender is currently blocked, reject the message and
+        // make sure current task get notified when sender unparks
+        if !self.poll_unparked(park).is_ready() {
             return Err(SendError::Full(msg))
         }
 
@@ -213,7 +297,9 @@ impl<A: Actor> AddressSender<A> {
         // If the channel has reached capacity, then the sender task needs to
         // be parked. This will send the task handle on the parked task queue.
         if park_self {
-            self.park(true);
+            if park {
+                self.park(true);
+            }
             Err(SendError::Full(msg))
         } else {
             let (tx, rx) = sync_channel();
@@ -226,11 +312,10 @@ impl<A: Actor> AddressSender<A> {
     /// Attempts to send a message on this `Sender<A>` without blocking.
     pub fn try_send<M>(&self, msg: M, park: bool) -> Result<(), SendError<M>>
         where A: Handler<M>, <A as Actor>::Context: ToEnvelope<A>,
-              M::Item: Send, M::Error: Send,
               M: ResponseType + Send + 'static,
     {
         // If the sender is currently blocked, reject the message
-        if !self.poll_unparked(false).is_ready() {
+        if !self.poll_unparked(park).is_ready() {
             return Err(SendError::Full(msg))
         }
 
@@ -245,7 +330,34 @@ impl<A: Actor> AddressSender<A> {
             }
             Err(SendError::Full(msg))
         } else {
-            let env = <A::Context as ToEnvelope<A>>::pack(msg, None);
+            let env = <A::Context as ToEnvelope<A>>::pack_msg(msg);
+            self.queue_push_and_signal(env);
+            Ok(())
+        }
+    }
+
+    /// Attempts to push already packed envelope without blocking.
+    ///
+    /// Envelope could be created with `RemoteEnvelope::envelope()`,
+    /// it is returned back if channel is full or closed.
+    pub fn try_send_envelope(&self, env: Envelope<A>, park: bool)
+                             -> Result<(), SendError<Envelope<A>>>
+    {
+        if !self.poll_unparked(park).is_ready() {
+            return Err(SendError::Full(env))
+        }
+
+        let park_self = match self.inc_num_messages() {
+            Some(park_self) => park_self,
+            None => return Err(SendError::Closed(env)),
+        };
+
+        if park_self {
+            if park {
+                self.park(true);
+            }
+            Err(SendError::Full(env))
+        } else {
             self.queue_push_and_signal(env);
             Ok(())
         }
@@ -256,20 +368,142 @@ impl<A: Actor> AddressSender<A> {
     /// This function does not park current task.
     pub fn do_send<M>(&self, msg: M) -> Result<(), SendError<M>>
         where A: Handler<M>, <A as Actor>::Context: ToEnvelope<A>,
-              M::Item: Send, M::Error: Send,
               M: ResponseType + Send + 'static,
     {
         if self.inc_num_messages_force().is_none() {
             Err(SendError::Closed(msg))
         } else {
-            let env = <A::Context as ToEnvelope<A>>::pack(msg, None);
+            let env = <A::Context as ToEnvelope<A>>::pack_msg(msg);
             self.queue_push_and_signal(env);
             Ok(())
         }
     }
 
+    /// Put message with existing response channel to the queue,
+    /// mailbox capacity is ignored
+    pub(crate) fn push<M>(&self, msg: M, tx: Option<Sender<MessageResult<M>>>)
+                          -> Result<(), SendError<M>>
+        where A: Handler<M>, <A as Actor>::Context: ToEnvelope<A>,
+              M: ResponseType + Send + 'static, M::Item: Send, M::Error: Send,
+    {
+        if self.inc_num_messages_force().is_none() {
+            Err(SendError::Closed(msg))
+        } else {
+            let env = <A::Context as ToEnvelope<A>>::pack(msg, tx);
+            self.queue_push_and_signal(env);
+            Ok(())
+        }
+    }
+
+    /// Send a message to the system lane of the receiver
+    ///
+    /// System lane has fixed capacity and it is drained before ordinary messages.
+    pub fn send_system<M>(&self, msg: M) -> Result<(), SendError<M>>
+        where A: Handler<M>, <A as Actor>::Context: ToEnvelope<A>,
+              M: ResponseType + Send + 'static,
+    {
+        if !self.connected() {
+            return Err(SendError::Closed(msg))
+        }
+        {
+            let mut queue = self.inner.system_queue.lock().unwrap();
+            if queue.len() >= SYSTEM_LANE_CAPACITY {
+                return Err(SendError::Full(msg))
+            }
+            queue.push_back(<A::Context as ToEnvelope<A>>::pack_msg(msg));
+            self.inner.system_len.fetch_add(1, SeqCst);
+        }
+        self.signal();
+        Ok(())
+    }
+
+    /// Send a message, spill it to disk if mailbox is full
+    ///
+    /// Once message got spilled, following messages are spilled as well
+    /// until receiver drains spill file, so messages keep their order.
+    /// Without spill file this method works like `do_send()`.
+    #[cfg(feature="spill")]
+    pub fn spill_send<M>(&self, msg: M) -> Result<(), SendError<M>>
+        where A: Handler<M>, <A as Actor>::Context: ToEnvelope<A>,
+              M: ResponseType + Serialize + DeserializeOwned + Send + 'static,
+    {
+        let mut spill = self.inner.spill.lock().unwrap();
+        let spilling = match *spill {
+            Some(ref spill) => spill.len() != 0,
+            None => return self.do_send(msg),
+        };
+        if !spilling {
+            match self.inc_num_messages() {
+                Some(false) => {
+                    let env = <A::Context as ToEnvelope<A>>::pack_msg(msg);
+                    self.queue_push_and_signal(env);
+                    return Ok(())
+                }
+                Some(true) => (),
+                None => return Err(SendError::Closed(msg)),
+            }
+        } else if !self.connected() {
+            return Err(SendError::Closed(msg))
+        }
+
+        match spill.as_mut().unwrap().push(&msg) {
+            Ok(true) => {
+                self.inner.spill_len.fetch_add(1, SeqCst);
+                drop(spill);
+                self.inner.trace.record(TraceKind::Enqueue, type_name::<M>());
+                self.signal();
+                Ok(())
+            }
+            Ok(false) => Err(SendError::Full(msg)),
+            Err(err) => {
+                error!("Can not spill message to disk: {}", err);
+                Err(SendError::Full(msg))
+            }
+        }
+    }
+
+    /// Check if sender could send message
+    ///
+    /// If sender is parked, current task get notified once receiver
+    /// takes message from the channel.
+    pub fn poll_ready(&self) -> Async<()> {
+        self.poll_unparked(true)
+    }
+
+    /// Send message after `poll_ready()` returned `Async::Ready`
+    ///
+    /// Message is always accepted, if channel is full sender get parked
+    /// and the next `poll_ready()` call returns `Async::NotReady`.
+    /// Rendezvous channel never buffers message, so this method works
+    /// like `try_send()`.
+    pub fn start_send<M>(&self, msg: M) -> Result<(), SendError<M>>
+        where A: Handler<M>, <A as Actor>::Context: ToEnvelope<A>,
+              M: ResponseType + Send + 'static,
+    {
+        if self.inner.rendezvous() {
+            return self.try_send(msg, true)
+        }
+        if !self.poll_unparked(false).is_ready() {
+            return Err(SendError::Full(msg))
+        }
+
+        let park_self = match self.inc_num_messages_force() {
+            Some(park_self) => park_self && !self.inner.drop_oldest.load(Relaxed),
+            None => return Err(SendError::Closed(msg)),
+        };
+
+        let env = <A::Context as ToEnvelope<A>>::pack_msg(msg);
+        self.queue_push_and_signal(env);
+        if park_self {
+            self.park(true);
+        }
+        Ok(())
+    }
+
     // Push message to the queue and signal to the receiver
     fn queue_push_and_signal(&self, msg: Envelope<A>) {
+        self.inner.trace.record(TraceKind::Enqueue, msg.type_name());
+
         // Push the message onto the message queue
         self.inner.message_queue.push(msg);
 
@@ -288,9 +522,15 @@ impl<A: Actor> AddressSender<A> {
                 return None;
             }
 
-            // receiver is full
-            let buffer = self.inner.buffer.load(Relaxed);
-            let park_self = buffer != 0 && state.num_messages >= buffer;
+            // receiver is full, rendezvous channel accepts message
+            // only if receiver waits for it
+            let park_self = if self.inner.rendezvous() {
+                state.num_messages != 0 || !self.inner.recv_waiting.load(SeqCst)
+            } else {
+                let buffer = self.inner.buffer.load(Relaxed);
+                buffer != UNBOUNDED && state.num_messages >= buffer &&
+                    !self.inner.drop_oldest.load(Relaxed)
+            };
             if park_self {
                 return Some(true);
             }
@@ -319,7 +559,7 @@ impl<A: Actor> AddressSender<A> {
             match self.inner.state.compare_exchange(curr, next, SeqCst, SeqCst) {
                 Ok(_) => {
                     let buffer = self.inner.buffer.load(Relaxed);
-                    let park_self = buffer != 0 && state.num_messages >= buffer;
+                    let park_self = buffer != UNBOUNDED && state.num_messages >= buffer;
                     return Some(park_self)
                 }
                 Err(actual) => curr = actual,
@@ -377,6 +617,21 @@ impl<A: Actor> AddressSender<A> {
         // Check to make sure we weren't closed after we sent our task on the queue
         let state = decode_state(self.inner.state.load(SeqCst));
         self.maybe_parked.set(state.is_open);
+
+        // Receiver could take message before our task got queued and
+        // find parked queue empty, unpark ourself if mailbox has room
+        if !self.inner.rendezvous() {
+            let buffer = self.inner.buffer.load(Relaxed);
+            if state.num_messages < buffer {
+                self.sender_task.lock().unwrap().notify();
+            }
+        }
+
+        // Receiver could start waiting before our task got queued,
+        // wake it up so it unparks us
+        if self.inner.rendezvous() && self.inner.recv_waiting.load(SeqCst) {
+            self.signal();
+        }
     }
 
     fn poll_unparked(&self, do_park: bool) -> Async<()> {
@@ -454,6 +709,50 @@ impl<A: Actor> Drop for AddressSender<A> {
     }
 }
 
+/// Weak sender, it does not keep receiver connected.
+pub(crate) struct WeakAddressSender<A: Actor> {
+    inner: Weak<Inner<A>>,
+}
+
+impl<A: Actor> WeakAddressSender<A> {
+    /// Get sender, `None` if receiver is gone or channel is closed
+    pub fn upgrade(&self) -> Option<AddressSender<A>> {
+        let inner = match self.inner.upgrade() {
+            Some(inner) => inner,
+            None => return None,
+        };
+        if !decode_state(inner.state.load(SeqCst)).is_open {
+            return None
+        }
+
+        // same as Sender::clone, but disconnected channel is not revived,
+        // last sender could be dropped concurrently
+        let mut curr = inner.num_senders.load(SeqCst);
+        loop {
+            if curr == 0 {
+                return None
+            }
+            if curr == inner.max_senders() {
+                panic!("cannot clone `Sender` -- too many outstanding senders");
+            }
+            match inner.num_senders.compare_exchange(curr, curr + 1, SeqCst, SeqCst) {
+                Ok(_) => return Some(AddressSender {
+                    inner: inner,
+                    sender_task: Arc::new(Mutex::new(SenderTask::new())),
+                    maybe_parked: Cell::new(false),
+                }),
+                Err(actual) => curr = actual,
+            }
+        }
+    }
+}
+
+impl<A: Actor> Clone for WeakAddressSender<A> {
+    fn clone(&self) -> Self {
+        WeakAddressSender{inner: Weak::clone(&self.inner)}
+    }
+}
+
 //
 //
 // ===== impl Receiver =====
@@ -462,7 +761,147 @@ impl<A: Actor> Drop for AddressSender<A> {
 impl<A: Actor> SyncAddressReceiver<A> {
 
     pub fn connected(&self) -> bool {
-        self.inner.num_senders.load(SeqCst) != 0
+        if self.inner.num_senders.load(SeqCst) != 0 {
+            return true
+        }
+        // spilled messages are replayed after all senders are gone
+        #[cfg(feature="spill")]
+        {
+            if self.inner.spill_len.load(SeqCst) != 0 {
+                return true
+            }
+        }
+        false
+    }
+
+    /// Close channel, senders get `SendError::Closed` error
+    fn close(&mut self) {
+        let mut curr = self.inner.state.load(SeqCst);
+        loop {
+            let mut state = decode_state(curr);
+            if !state.is_open {
+                break
+            }
+            state.is_open = false;
+
+            let next = encode_state(&state);
+            match self.inner.state.compare_exchange(curr, next, SeqCst, SeqCst) {
+                Ok(_) => break,
+                Err(actual) => curr = actual,
+            }
+        }
+
+        // Wake up any threads waiting as they'll see that we've closed the
+        // channel and will continue on their merry way.
+        loop {
+            match unsafe { self.inner.parked_queue.pop() } {
+                PopResult::Data(task) => {
+                    task.lock().unwrap().notify();
+                }
+                PopResult::Empty => break,
+                PopResult::Inconsistent => thread::yield_now(),
+            }
+        }
+    }
+
+    /// Close channel and take all queued messages, oldest first
+    pub fn drain(&mut self) -> Vec<Envelope<A>> {
+        self.close();
+        let mut msgs = Vec::new();
+        while let Async::Ready(Some(msg)) = self.next_message() {
+            msgs.push(msg);
+        }
+        #[cfg(feature="spill")]
+        {
+            let mut spill = self.inner.spill.lock().unwrap();
+            if let Some(spill) = spill.as_mut() {
+                while let Some(msg) = spill.pop() {
+                    msgs.push(msg);
+                }
+            }
+            self.inner.spill_len.store(0, SeqCst);
+        }
+        msgs
+    }
+
+    /// Mailbox events log of this channel
+    pub(crate) fn trace(&self) -> Arc<MailboxTrace> {
+        Arc::clone(&self.inner.trace)
+    }
+
+    /// Type names of messages in the queue, oldest first
+    pub fn queued_types(&self) -> Vec<&'static str> {
+        let mut types = Vec::new();
+        // receiver is the only consumer of the queue
+        unsafe {
+            self.inner.message_queue.for_each(|msg| types.push(msg.type_name()));
+        }
+        types
+    }
+
+    /// Number of messages in the queue
+    pub fn len(&self) -> usize {
+        decode_state(self.inner.state.load(SeqCst)).num_messages
+    }
+
+    /// Time when the oldest message in the queue was sent
+    pub fn oldest(&self) -> Option<Instant> {
+        // receiver is the only consumer of the queue
+        unsafe { self.inner.message_queue.peek().map(|msg| msg.enqueued()) }
+    }
+
+    /// Number of messages in the system lane
+    pub fn system_len(&self) -> usize {
+        self.inner.system_len.load(SeqCst)
+    }
+
+    /// Take next message from the system lane
+    pub fn poll_system(&mut self) -> Option<Envelope<A>> {
+        if self.inner.system_len.load(SeqCst) == 0 {
+            return None
+        }
+        let msg = self.inner.system_queue.lock().unwrap().pop_front();
+        if msg.is_some() {
+            self.inner.system_len.fetch_sub(1, SeqCst);
+        }
+        msg
+    }
+
+    /// Spill messages that do not fit into mailbox to disk
+    #[cfg(feature="spill")]
+    pub(crate) fn set_spill(&mut self, spill: Spill<A>) {
+        *self.inner.spill.lock().unwrap() = Some(spill);
+    }
+
+    /// Start new mailbox poll, it limits number of spilled messages per poll
+    #[cfg(feature="spill")]
+    pub fn start_batch(&mut self) {
+        self.spill_batch = 0;
+    }
+
+    // Take next spilled message, messages are replayed once in-memory queue is empty
+    #[cfg(feature="spill")]
+    fn next_spilled(&mut self) -> Option<Async<Option<Envelope<A>>>> {
+        if self.inner.spill_len.load(SeqCst) == 0 {
+            return None
+        }
+        if self.spill_batch >= SPILL_BATCH {
+            // let other actors run, continue with next poll
+            task::current().notify();
+            return Some(Async::NotReady)
+        }
+
+        let msg = {
+            let mut spill = self.inner.spill.lock().unwrap();
+            let spill = spill.as_mut()?;
+            let msg = spill.pop();
+            self.inner.spill_len.store(spill.len(), SeqCst);
+            msg
+        };
+        msg.map(|msg| {
+            self.spill_batch += 1;
+            Async::Ready(Some(msg))
+        })
     }
 
     /// Set channel capacity
@@ -492,6 +931,25 @@ impl<A: Actor> SyncAddressReceiver<A> {
         }
     }
 
+    /// Set channel overflow policy
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.inner.drop_oldest.store(overflow == Overflow::DropOldest, Relaxed);
+        self.inner.reject.store(overflow == Overflow::Reject, Relaxed);
+
+        // senders do not block anymore
+        if overflow != Overflow::Block {
+            loop {
+                match unsafe { self.inner.parked_queue.pop() } {
+                    PopResult::Data(task) => {
+                        task.lock().unwrap().notify();
+                    }
+                    PopResult::Empty => return,
+                    PopResult::Inconsistent => thread::yield_now(),
+                }
+            }
+        }
+    }
+
     /// Get sender side of the channel
     pub fn sender(&mut self) -> AddressSender<A> {
         // this code same as Sender::clone
@@ -554,8 +1012,10 @@ impl<A: Actor> SyncAddressReceiver<A> {
         loop {
             match unsafe { self.inner.parked_queue.pop() } {
                 PopResult::Data(task) => {
-                    task.lock().unwrap().notify();
-                    return;
+                    // sender that unparked itself leaves stale handle
+                    if task.lock().unwrap().notify() {
+                        return;
+                    }
                 }
                 PopResult::Empty => {
                     // Queue empty, no task to wake up.
@@ -584,7 +1044,8 @@ impl<A: Actor> SyncAddressReceiver<A> {
         TryPark::Parked
     }
 
-    fn dec_num_messages(&self) {
+    // Decrement the number of queued messages. Returns number of remaining messages.
+    fn dec_num_messages(&self) -> usize {
         let mut curr = self.inner.state.load(SeqCst);
 
         loop {
@@ -594,7 +1055,7 @@ impl<A: Actor> SyncAddressReceiver<A> {
 
             let next = encode_state(&state);
             match self.inner.state.compare_exchange(curr, next, SeqCst, SeqCst) {
-                Ok(_) => break,
+                Ok(_) => return state.num_messages,
                 Err(actual) => curr = actual,
             }
         }
@@ -606,16 +1067,32 @@ impl<A: Actor> Stream for SyncAddressReceiver<A> {
     type Error = ();
 
     fn poll(&mut self) -> Poll<Option<Self::Item>, Self::Error> {
+        self.inner.recv_waiting.store(false, SeqCst);
+
         loop {
             // Try to read a message off of the message queue.
             let msg = match self.next_message() {
                 Async::Ready(msg) => msg,
                 Async::NotReady => {
+                    #[cfg(feature="spill")]
+                    {
+                        if let Some(msg) = self.next_spilled() {
+                            return Ok(msg)
+                        }
+                    }
+
                     // There are no messages to read, in this case, attempt to
                     // park. The act of parking will verify that the channel is
                     // still empty after the park operation has completed.
                     match self.try_park() {
                         TryPark::Parked => {
+                            // Rendezvous channel, let one of the senders
+                            // hand off its message
+                            if self.inner.rendezvous() {
+                                self.inner.recv_waiting.store(true, SeqCst);
+                                self.unpark_one();
+                            }
+
                             // The task was parked, and the channel is still
                             // empty, return NotReady.
                             return Ok(Async::NotReady);
@@ -630,12 +1107,25 @@ impl<A: Actor> Stream for SyncAddressReceiver<A> {
                 }
             };
 
+            // Decrement number of messages, sender that parks after this
+            // point sees free slot, see `AddressSender::park()`
+            let remaining = self.dec_num_messages();
+
             // If there are any parked task handles in the parked queue, pop
             // one and unpark it.
             self.unpark_one();
 
-            // Decrement number of messages
-            self.dec_num_messages();
+            // Drop message if it does not fit into mailbox
+            if self.inner.drop_oldest.load(Relaxed) {
+                // zero capacity mailbox keeps newest message only
+                let buffer = cmp::max(self.inner.buffer.load(Relaxed), 1);
+                if buffer != UNBOUNDED && remaining >= buffer {
+                    if let Some(ref msg) = msg {
+                        self.inner.trace.record(TraceKind::Dropped, msg.type_name());
+                    }
+                    continue
+                }
+            }
 
             // Return the message
             return Ok(Async::Ready(msg));
@@ -645,38 +1135,20 @@ impl<A: Actor> Stream for SyncAddressReceiver<A> {
 
 impl<A: Actor> Drop for SyncAddressReceiver<A> {
     fn drop(&mut self) {
-        // close
-        let mut curr = self.inner.state.load(SeqCst);
-        loop {
-            let mut state = decode_state(curr);
-            if !state.is_open {
-                break
-            }
-            state.is_open = false;
-
-            let next = encode_state(&state);
-            match self.inner.state.compare_exchange(curr, next, SeqCst, SeqCst) {
-                Ok(_) => break,
-                Err(actual) => curr = actual,
-            }
-        }
-
-        // Wake up any threads waiting as they'll see that we've closed the
-        // channel and will continue on their merry way.
-        loop {
-            match unsafe { self.inner.parked_queue.pop() } {
-                PopResult::Data(task) => {
-                    task.lock().unwrap().notify();
-                }
-                PopResult::Empty => break,
-                PopResult::Inconsistent => thread::yield_now(),
-            }
-        }
+        self.close();
 
         // Drain the channel of all pending messages
         while self.next_message().is_ready() {
             // ...
         }
+        self.inner.system_queue.lock().unwrap().clear();
+
+        // remove spill file
+        #[cfg(feature="spill")]
+        {
+            self.inner.spill.lock().unwrap().take();
+            self.inner.spill_len.store(0, SeqCst);
+        }
     }
 }
 
@@ -691,6 +1163,11 @@ impl<A: Actor> Inner<A> {
     fn max_senders(&self) -> usize {
         MAX_CAPACITY - self.buffer.load(Relaxed)
     }
+
+    // Zero capacity channel, messages are handed off to waiting receiver
+    fn rendezvous(&self) -> bool {
+        self.buffer.load(Relaxed) == 0 && !self.drop_oldest.load(Relaxed)
+    }
 }
 
 unsafe impl<A: Actor> Send for Inner<A> {}
@@ -749,14 +1226,14 @@ mod tests {
             let s2 = recv.sender();
 
             let arb: SyncAddress<_> = Arbiter::new("s1");
-            arb.send(actix::msgs::Execute::new(move || -> Result<(), ()> {
-                let _ = s1.send(Ping);
+            arb.do_send(actix::msgs::Execute::new(move || -> Result<(), ()> {
+                let _ = s1.send(Ping, true);
                 Ok(())
             }));
             thread::sleep(time::Duration::from_millis(100));
             let arb2 = Arbiter::new("s1");
-            arb2.send(actix::msgs::Execute::new(move || -> Result<(), ()> {
-                let _ = s2.send(Ping);
+            arb2.do_send(actix::msgs::Execute::new(move || -> Result<(), ()> {
+                let _ = s2.send(Ping, true);
                 Ok(())
             }));
 
@@ -790,7 +1267,7 @@ mod tests {
             };
             assert!(p.is_none());
 
-            Arbiter::system().send(actix::msgs::SystemExit(0));
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
             Ok(())
         });
 
@@ -6,11 +6,14 @@ use futures::sync::oneshot::Receiver;
 use tokio_core::reactor::Timeout;
 
 use arbiter::Arbiter;
+use actors::deadletter::{self, DeadLetterReason};
 use actor::{Actor, AsyncContext};
 use fut::ActorFuture;
+use contextitems::wait_pending;
 use handler::{Handler, ResponseType, MessageResult};
 
-use super::{SendError, MailboxError};
+use super::request_map::{RequestMap, RequestMapErr, RequestFlatten};
+use super::{SendError, MailboxError, ToEnvelope};
 use super::sync_channel::AddressSender;
 
 
@@ -21,22 +24,51 @@ pub struct Request<A, B, M> where A: Actor, B: Actor, M: ResponseType {
     info: Option<(AddressSender<A>, M)>,
     act: PhantomData<B>,
     timeout: Option<Timeout>,
+    mailbox: usize,
 }
 
-impl<A, B, M> Request<A, B, M> where A: Actor, B: Actor, M: ResponseType
+impl<A, B, M> Request<A, B, M> where A: Actor, B: Actor, M: ResponseType + 'static
 {
     pub(crate) fn new(rx: Option<Receiver<MessageResult<M>>>,
-                      info: Option<(AddressSender<A>, M)>) -> Request<A, B, M> {
-        Request{rx: rx, info: info, act: PhantomData, timeout: None}
+                      info: Option<(AddressSender<A>, M)>,
+                      mailbox: usize) -> Request<A, B, M> {
+        Request{rx: rx, info: info, act: PhantomData, timeout: None, mailbox: mailbox}
     }
 
-    /// Set message delivery timeout
+    /// Set request timeout
+    ///
+    /// Timeout covers both waiting for mailbox room and waiting for
+    /// handler's response. Future fails with `MailboxError::Timeout` once
+    /// `dur` passes, queued message is skipped once future gets dropped.
     pub fn timeout(mut self, dur: Duration) -> Self {
         self.timeout = Some(Timeout::new(dur, Arbiter::handle()).unwrap());
         self
     }
 
+    /// Map message result item, mailbox error is passed through
+    pub fn map<F, U>(self, f: F) -> RequestMap<Self, F> where F: FnOnce(M::Item) -> U {
+        RequestMap::new(self, f)
+    }
+
+    /// Map message result error, mailbox error is passed through
+    pub fn map_err<F, U>(self, f: F) -> RequestMapErr<Self, F> where F: FnOnce(M::Error) -> U {
+        RequestMapErr::new(self, f)
+    }
+
+    /// Collapse mailbox error and message error into one error type
+    ///
+    /// Future resolves to `M::Item`, both errors get converted into `E`.
+    pub fn flatten<E>(self) -> RequestFlatten<Self, E>
+        where E: From<MailboxError> + From<M::Error>
+    {
+        RequestFlatten::new(self)
+    }
+
     fn poll_timeout(&mut self) -> Poll<MessageResult<M>, MailboxError> {
+        // waiting context broke deadlock or waits for itself
+        if let Some(err) = wait_pending::<M>(self.mailbox) {
+            return Err(err)
+        }
         if let Some(ref mut timeout) = self.timeout {
             match timeout.poll() {
                 Ok(Async::Ready(())) => Err(MailboxError::Timeout),
@@ -59,11 +91,13 @@ impl<A, B, M> ActorFuture for Request<A, B, M>
 
     fn poll(&mut self, _: &mut B, _: &mut B::Context) -> Poll<Self::Item, Self::Error> {
         if let Some((sender, msg)) = self.info.take() {
-            match sender.send(msg) {
+            let park = !sender.rejects();
+            match sender.send(msg, park) {
                 Ok(rx) => self.rx = Some(rx),
+                Err(SendError::Full(_)) if !park => return Err(MailboxError::Full),
                 Err(SendError::Full(msg)) => {
                     self.info = Some((sender, msg));
-                    return Ok(Async::NotReady)
+                    return self.poll_timeout()
                 }
                 Err(SendError::Closed(_)) => return Err(MailboxError::Closed),
             }
@@ -76,7 +110,7 @@ impl<A, B, M> ActorFuture for Request<A, B, M>
                     self.rx = Some(rx);
                     self.poll_timeout()
                 }
-                Err(_) => Err(MailboxError::Closed),
+                Err(_) => Err(MailboxError::Canceled),
             }
         } else {
             Err(MailboxError::Closed)
@@ -90,22 +124,51 @@ pub struct RequestFut<A, M> where A: Actor, M: ResponseType {
     rx: Option<Receiver<MessageResult<M>>>,
     info: Option<(AddressSender<A>, M)>,
     timeout: Option<Timeout>,
+    mailbox: usize,
 }
 
-impl<A, M> RequestFut<A, M> where A: Actor, M: ResponseType
+impl<A, M> RequestFut<A, M> where A: Actor, M: ResponseType + 'static
 {
     pub(crate) fn new(rx: Option<Receiver<MessageResult<M>>>,
-                      info: Option<(AddressSender<A>, M)>) -> RequestFut<A, M> {
-        RequestFut{rx: rx, info: info, timeout: None}
+                      info: Option<(AddressSender<A>, M)>,
+                      mailbox: usize) -> RequestFut<A, M> {
+        RequestFut{rx: rx, info: info, timeout: None, mailbox: mailbox}
     }
 
-    /// Set message delivery timeout
+    /// Set request timeout
+    ///
+    /// Timeout covers both waiting for mailbox room and waiting for
+    /// handler's response. Future fails with `MailboxError::Timeout` once
+    /// `dur` passes, queued message is skipped once future gets dropped.
     pub fn timeout(mut self, dur: Duration) -> Self {
         self.timeout = Some(Timeout::new(dur, Arbiter::handle()).unwrap());
         self
     }
 
+    /// Map message result item, mailbox error is passed through
+    pub fn map<F, U>(self, f: F) -> RequestMap<Self, F> where F: FnOnce(M::Item) -> U {
+        RequestMap::new(self, f)
+    }
+
+    /// Map message result error, mailbox error is passed through
+    pub fn map_err<F, U>(self, f: F) -> RequestMapErr<Self, F> where F: FnOnce(M::Error) -> U {
+        RequestMapErr::new(self, f)
+    }
+
+    /// Collapse mailbox error and message error into one error type
+    ///
+    /// Future resolves to `M::Item`, both errors get converted into `E`.
+    pub fn flatten<E>(self) -> RequestFlatten<Self, E>
+        where E: From<MailboxError> + From<M::Error>
+    {
+        RequestFlatten::new(self)
+    }
+
     fn poll_timeout(&mut self) -> Poll<MessageResult<M>, MailboxError> {
+        // waiting context broke deadlock or waits for itself
+        if let Some(err) = wait_pending::<M>(self.mailbox) {
+            return Err(err)
+        }
         if let Some(ref mut timeout) = self.timeout {
             match timeout.poll() {
                 Ok(Async::Ready(())) => Err(MailboxError::Timeout),
@@ -127,11 +190,13 @@ impl<A, M> Future for RequestFut<A, M>
 
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
         if let Some((sender, msg)) = self.info.take() {
-            match sender.send(msg) {
+            let park = !sender.rejects();
+            match sender.send(msg, park) {
                 Ok(rx) => self.rx = Some(rx),
+                Err(SendError::Full(_)) if !park => return Err(MailboxError::Full),
                 Err(SendError::Full(msg)) => {
                     self.info = Some((sender, msg));
-                    return Ok(Async::NotReady)
+                    return self.poll_timeout()
                 }
                 Err(SendError::Closed(_)) => return Err(MailboxError::Closed),
             }
@@ -144,10 +209,61 @@ impl<A, M> Future for RequestFut<A, M>
                     self.rx = Some(rx);
                     self.poll_timeout()
                 }
-                Err(_) => Err(MailboxError::Closed),
+                Err(_) => Err(MailboxError::Canceled),
             }
         } else {
             Err(MailboxError::Closed)
         }
     }
 }
+
+/// `SendFut` is a `Future` which represents message delivery to actor's mailbox.
+///
+/// If mailbox is full, future waits until mailbox has room.
+#[must_use = "future do nothing unless polled"]
+pub struct SendFut<A, M> where A: Actor, M: ResponseType + 'static {
+    info: Option<(AddressSender<A>, M)>,
+    closed: bool,
+}
+
+impl<A, M> SendFut<A, M> where A: Actor, M: ResponseType + 'static
+{
+    pub(crate) fn new(info: Option<(AddressSender<A>, M)>, closed: bool) -> SendFut<A, M> {
+        SendFut{info: info, closed: closed}
+    }
+}
+
+impl<A, M> Future for SendFut<A, M>
+    where A: Actor + Handler<M>, A::Context: ToEnvelope<A>,
+          M: ResponseType + Send + 'static,
+{
+    type Item = ();
+    type Error = MailboxError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        if let Some((sender, msg)) = self.info.take() {
+            let park = !sender.rejects();
+            match sender.try_send(msg, park) {
+                Ok(()) => Ok(Async::Ready(())),
+                Err(SendError::Full(_)) if !park => Err(MailboxError::Full),
+                Err(SendError::Full(msg)) => {
+                    self.info = Some((sender, msg));
+                    Ok(Async::NotReady)
+                }
+                Err(SendError::Closed(_)) => Err(MailboxError::Closed),
+            }
+        } else if self.closed {
+            Err(MailboxError::Closed)
+        } else {
+            Ok(Async::Ready(()))
+        }
+    }
+}
+
+impl<A, M> Drop for SendFut<A, M> where A: Actor, M: ResponseType + 'static {
+    fn drop(&mut self) {
+        if let Some((_, ref msg)) = self.info {
+            deadletter::publish_with_reason::<A, M>(msg, DeadLetterReason::Dropped);
+        }
+    }
+}
@@ -1,18 +1,26 @@
 use std;
+use std::fmt;
 use std::thread;
-use std::cell::RefCell;
+use std::cell::{Cell, RefCell};
+use std::rc::{Rc, Weak};
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use uuid::Uuid;
-use tokio_core::reactor::{Core, Handle};
+use tokio_core::reactor::{Core, Handle, Timeout};
+use futures::{future, Async, Future, IntoFuture, Poll};
+use futures::task::{self, Task};
 use futures::sync::oneshot::{channel, Sender};
 
 use actor::{Actor, AsyncContext};
+use blocking::{BlockingPool, BlockingFuture};
 use address::{sync_channel, Address, SyncAddress};
 use context::Context;
 use mailbox::DEFAULT_CAPACITY;
-use msgs::{Execute, StartActor, StopArbiter};
-use handler::Handler;
+use msgs::{DumpActors, Execute, StartActor, StopArbiter, StopArbiterGraceful};
+use handler::{Handler, ResponseFuture};
 use registry::{Registry, SystemRegistry};
-use system::{System, RegisterArbiter, UnregisterArbiter};
+use system::{System, SystemConfig, ArbiterDied, RegisterArbiter, UnregisterArbiter};
 
 thread_local!(
     static HND: RefCell<Option<Handle>> = RefCell::new(None);
@@ -24,8 +32,162 @@ thread_local!(
     static SYSARB: RefCell<Option<SyncAddress<Arbiter>>> = RefCell::new(None);
     static SYSNAME: RefCell<Option<String>> = RefCell::new(None);
     static SYSREG: RefCell<Option<SystemRegistry>> = RefCell::new(None);
+    static RESIDENTS: RefCell<Residents> = RefCell::new(Residents::default());
+    static IDS: RefCell<Option<IdRange>> = RefCell::new(None);
+    static SYSCFG: RefCell<Option<Arc<SystemConfig>>> = RefCell::new(None);
+    static BLOCKING: RefCell<Option<BlockingPool>> = RefCell::new(None);
 );
 
+/// Number of ids in a range owned by arbiter
+const ID_RANGE_BITS: u32 = 32;
+
+/// Range of system-wide unique ids owned by current arbiter
+struct IdRange {
+    prefixes: Arc<AtomicUsize>,
+    prefix: u64,
+    next: u64,
+}
+
+impl IdRange {
+    /// Carve out new range, `prefixes` is shared by all arbiters of the system
+    fn new(prefixes: Arc<AtomicUsize>) -> IdRange {
+        let prefix = prefixes.fetch_add(1, Ordering::SeqCst) as u64;
+        IdRange{prefixes: prefixes, prefix: prefix, next: 0}
+    }
+
+    fn split(&self) -> IdRange {
+        IdRange::new(Arc::clone(&self.prefixes))
+    }
+
+    fn next(&mut self) -> u64 {
+        if self.next == 1 << ID_RANGE_BITS {
+            *self = self.split();
+        }
+        self.next += 1;
+        (self.prefix << ID_RANGE_BITS) | (self.next - 1)
+    }
+}
+
+/// Generate id that is unique within running system
+///
+/// Every arbiter owns range of ids that is carved out when arbiter starts,
+/// so ids are generated without synchronization.
+///
+/// Panics if it is called outside of arbiter's thread.
+pub fn unique_system_id() -> u64 {
+    IDS.with(|cell| match *cell.borrow_mut() {
+        Some(ref mut ids) => ids.next(),
+        None => panic!("System is not running"),
+    })
+}
+
+/// Contexts running in current arbiter's thread
+#[derive(Default)]
+struct Residents {
+    items: Vec<Weak<Resident>>,
+    live: usize,
+    stopping: bool,
+    waiter: Option<Task>,
+}
+
+/// Context's registration within arbiter
+///
+/// Arbiter uses it for graceful stop of all running contexts.
+pub(crate) struct Resident {
+    name: String,
+    stop: Cell<bool>,
+    handled: Cell<bool>,
+    abandon: Cell<bool>,
+    dead_letters: Cell<usize>,
+    wait: Cell<Option<(&'static str, Instant, usize)>>,
+    task: Task,
+}
+
+impl Resident {
+    /// Check if arbiter requested graceful stop
+    pub fn stop_requested(&self) -> bool {
+        self.stop.get()
+    }
+
+    /// Returns `true` only once after arbiter requested graceful stop
+    pub fn take_stop(&self) -> bool {
+        if self.stop.get() && !self.handled.get() {
+            self.handled.set(true);
+            true
+        } else {
+            false
+        }
+    }
+
+    /// Returns `true` only once after arbiter gave up on graceful stop
+    pub fn take_abandon(&self) -> bool {
+        self.abandon.replace(false)
+    }
+
+    /// Record number of messages dropped from abandoned mailbox
+    pub fn set_dead_letters(&self, count: usize) {
+        self.dead_letters.set(count)
+    }
+
+    /// Record wait future context is blocked on, `None` if context does not wait
+    pub fn set_wait(&self, wait: Option<(&'static str, Instant, usize)>) {
+        self.wait.set(wait)
+    }
+
+    fn info(&self) -> ActorInfo {
+        ActorInfo {
+            name: self.name.clone(),
+            arbiter: Arbiter::name(),
+            dead_letters: self.dead_letters.get(),
+            wait: self.wait.get().map(|(tp, since, queued)| WaitInfo {
+                message_type: tp,
+                waiting: since.elapsed(),
+                queued: queued,
+            }),
+        }
+    }
+}
+
+/// Actor that did not stop within shutdown timeout
+///
+/// See `StopArbiterGraceful` and `ShutdownReport`.
+#[derive(Debug, Clone, PartialEq)]
+pub struct ActorInfo {
+    /// Actor name, type name if actor is not named
+    pub name: String,
+    /// Name of the actor's arbiter
+    pub arbiter: String,
+    /// Number of queued messages that were published as dead letters
+    pub dead_letters: usize,
+    /// Wait future actor is blocked on, see `Context::wait_queue_info()`
+    pub wait: Option<WaitInfo>,
+}
+
+/// Wait future actor is blocked on
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct WaitInfo {
+    /// Wire name of the message outstanding request waits for, see
+    /// `MessageRegistry`, `<unknown>` if message is not registered
+    /// or wait future does not wait for a request
+    pub message_type: &'static str,
+    /// Time spent in wait
+    pub waiting: Duration,
+    /// Number of wait futures queued behind the current one
+    pub queued: usize,
+}
+
+impl Drop for Resident {
+    fn drop(&mut self) {
+        let _ = RESIDENTS.try_with(|cell| {
+            let mut residents = cell.borrow_mut();
+            residents.live -= 1;
+            if let Some(task) = residents.waiter.take() {
+                task.notify();
+            }
+        });
+    }
+}
+
 /// Event loop controller
 ///
 /// Arbiter controls event loop in it's thread. Each arbiter runs in separate
@@ -41,7 +203,7 @@ impl Actor for Arbiter {
 
     fn started(&mut self, ctx: &mut Context<Self>) {
         // register arbiter within system
-        Arbiter::system().send(
+        Arbiter::system().do_send(
             RegisterArbiter(self.id.simple().to_string(), ctx.address()));
     }
 }
@@ -58,10 +220,19 @@ impl Arbiter {
         let sys_name = Arbiter::system_name();
         let sys_arbiter = Arbiter::system_arbiter();
         let sys_registry = Arbiter::system_registry().clone();
+        let ids = IDS.with(|cell| cell.borrow().as_ref().map(|ids| ids.split()));
+        let sys_config = Arbiter::system_config();
+        let blocking = BLOCKING.with(|cell| cell.borrow().clone());
         let name = format!("arbiter:{:?}:{:?}",
                            id.hyphenated().to_string(), name.into());
 
         let _ = thread::Builder::new().name(name.clone()).spawn(move|| {
+            // notify system about arbiter's thread termination,
+            // guard is dropped during unwinding as well
+            let mut guard = ArbiterGuard {
+                id: id.simple().to_string(), name: name.clone(),
+                sys: sys.clone(), clean: false};
+
             let mut core = Core::new().unwrap();
 
             let (stop_tx, stop_rx) = channel();
@@ -74,6 +245,9 @@ impl Arbiter {
             SYSARB.with(|cell| *cell.borrow_mut() = Some(sys_arbiter));
             SYSNAME.with(|cell| *cell.borrow_mut() = Some(sys_name));
             SYSREG.with(|cell| *cell.borrow_mut() = Some(sys_registry));
+            IDS.with(|cell| *cell.borrow_mut() = ids);
+            SYSCFG.with(|cell| *cell.borrow_mut() = sys_config);
+            BLOCKING.with(|cell| *cell.borrow_mut() = blocking);
 
             // start arbiter
             let (addr, saddr) = Actor::start(Arbiter {sys: false, id: id});
@@ -81,28 +255,27 @@ impl Arbiter {
 
             if tx.send(saddr).is_err() {
                 error!("Can not start Arbiter, remote side is dead");
+                guard.clean = true;
             } else {
                 // run loop
-                let _ = match core.run(stop_rx) {
-                    Ok(code) => code,
-                    Err(_) => 1,
-                };
+                guard.clean = core.run(stop_rx).is_ok();
             }
-
-            // unregister arbiter
-            Arbiter::system().send(
-                UnregisterArbiter(id.simple().to_string()));
         });
 
         rx.recv().unwrap()
     }
 
-    pub(crate) fn new_system(name: String) -> Core {
+    pub(crate) fn new_system(name: String, cfg: Arc<SystemConfig>) -> Core {
         let core = Core::new().unwrap();
+        BLOCKING.with(|cell| *cell.borrow_mut() = Some(
+            BlockingPool::new(cfg.name.clone(), cfg.blocking_threads)));
+        SYSCFG.with(|cell| *cell.borrow_mut() = Some(cfg));
         HND.with(|cell| *cell.borrow_mut() = Some(core.handle()));
         REG.with(|cell| *cell.borrow_mut() = Some(Registry::new()));
         NAME.with(|cell| *cell.borrow_mut() = Some(name));
         SYSREG.with(|cell| *cell.borrow_mut() = Some(SystemRegistry::new()));
+        IDS.with(|cell| *cell.borrow_mut() = Some(
+            IdRange::new(Arc::new(AtomicUsize::new(0)))));
 
         // start arbiter
         let (addr, sys_addr) = Actor::start(
@@ -135,11 +308,19 @@ impl Arbiter {
     }
 
     /// This function returns system address,
+    ///
+    /// Panics if it is called outside of arbiter's thread.
     pub fn system() -> SyncAddress<System> {
-        SYS.with(|cell| match *cell.borrow() {
-            Some(ref addr) => addr.clone(),
-            None => panic!("System is not running"),
-        })
+        match Arbiter::try_system() {
+            Some(addr) => addr,
+            None => panic!("Arbiter::system() must be called from an actix arbiter thread; \
+                            use Arbiter::try_system()"),
+        }
+    }
+
+    /// This function returns system address if current thread runs arbiter.
+    pub fn try_system() -> Option<SyncAddress<System>> {
+        SYS.with(|cell| cell.borrow().as_ref().cloned())
     }
 
     /// This function returns system address,
@@ -166,11 +347,71 @@ impl Arbiter {
         })
     }
 
+    /// Configuration of the running system, `None` if system is not running
+    pub(crate) fn system_config() -> Option<Arc<SystemConfig>> {
+        SYSCFG.with(|cell| cell.borrow().clone())
+    }
+
     /// This function returns current event loop's handle,
+    ///
+    /// Panics if it is called outside of arbiter's thread.
     pub fn handle() -> &'static Handle {
+        match Arbiter::try_handle() {
+            Some(h) => h,
+            None => panic!("Arbiter::handle() must be called from an actix arbiter thread; \
+                            use Arbiter::try_handle()"),
+        }
+    }
+
+    /// This function returns current event loop's handle
+    /// if current thread runs arbiter.
+    pub fn try_handle() -> Option<&'static Handle> {
         HND.with(|cell| match *cell.borrow() {
-            Some(ref h) => unsafe{std::mem::transmute(h)},
-            None => panic!("Arbiter is not running"),
+            Some(ref h) => Some(unsafe{std::mem::transmute(h)}),
+            None => None,
+        })
+    }
+
+    /// Spawn future on current arbiter's event loop
+    ///
+    /// Unlike `Handle::spawn()` future could have any error type,
+    /// error is logged together with arbiter's name.
+    /// Panics if it is called outside of arbiter's thread.
+    pub fn spawn<F>(fut: F) where F: Future<Item=()> + 'static, F::Error: fmt::Debug {
+        let handle = match Arbiter::try_handle() {
+            Some(h) => h,
+            None => panic!("Arbiter::spawn() must be called from an actix arbiter thread"),
+        };
+        let name = Arbiter::name();
+        handle.spawn(fut.map_err(move |err| {
+            error!("Future spawned on arbiter {} failed: {:?}", name, err)
+        }));
+    }
+
+    /// Spawn future created by closure on current arbiter's event loop
+    ///
+    /// Closure is called on next event loop iteration, same as
+    /// `Handle::spawn_fn()`. See `Arbiter::spawn()` for error handling.
+    pub fn spawn_fn<F, R>(f: F)
+        where F: FnOnce() -> R + 'static,
+              R: IntoFuture<Item=()> + 'static, R::Error: fmt::Debug,
+    {
+        Arbiter::spawn(future::lazy(f))
+    }
+
+    /// Run blocking function on the system blocking pool
+    ///
+    /// Pool is shared by all arbiters of the system, its threads are started
+    /// on demand, see `SystemConfig::blocking_threads()`. Returned future
+    /// resolves with result of `f`, or with `Canceled` error if `f` panics.
+    ///
+    /// Panics if it is called outside of arbiter's thread.
+    pub fn spawn_blocking<F, R>(f: F) -> BlockingFuture<R>
+        where F: FnOnce() -> R + Send + 'static, R: Send + 'static
+    {
+        BLOCKING.with(|cell| match *cell.borrow() {
+            Some(ref pool) => pool.spawn(f),
+            None => panic!("Arbiter::spawn_blocking() must be called from an actix arbiter thread"),
         })
     }
 
@@ -182,6 +423,36 @@ impl Arbiter {
         })
     }
 
+    /// Register running context within current arbiter.
+    ///
+    /// Returns `None` if arbiter is not running in current thread.
+    /// This method has to be called from within a task.
+    pub(crate) fn resident(name: &str) -> Option<Rc<Resident>> {
+        if HND.with(|cell| cell.borrow().is_none()) {
+            return None
+        }
+        RESIDENTS.with(|cell| {
+            let mut residents = cell.borrow_mut();
+            let resident = Rc::new(Resident {
+                name: name.to_owned(),
+                stop: Cell::new(residents.stopping),
+                handled: Cell::new(false),
+                abandon: Cell::new(false),
+                dead_letters: Cell::new(0),
+                wait: Cell::new(None),
+                task: task::current(),
+            });
+
+            // cleanup stopped contexts
+            if residents.items.len() > residents.live * 2 + 16 {
+                residents.items.retain(|item| item.upgrade().is_some());
+            }
+            residents.live += 1;
+            residents.items.push(Rc::downgrade(&resident));
+            Some(resident)
+        })
+    }
+
     /// Start new arbiter and then start actor in created arbiter.
     /// Returns `SyncAddress` of created actor.
     pub fn start<A, F>(f: F) -> SyncAddress<A>
@@ -194,7 +465,7 @@ impl Arbiter {
         let addr = Arbiter::new("actor");
 
         // create actor
-        addr.send::<Execute>(
+        addr.do_send::<Execute>(
             Execute::new(move || {
                 let mut ctx = Context::with_receiver(None, srx);
                 let act = f(&mut ctx);
@@ -207,6 +478,24 @@ impl Arbiter {
     }
 }
 
+/// Unregisters arbiter within system on thread exit
+struct ArbiterGuard {
+    id: String,
+    name: String,
+    sys: SyncAddress<System>,
+    clean: bool,
+}
+
+impl Drop for ArbiterGuard {
+    fn drop(&mut self) {
+        if self.clean {
+            self.sys.do_send(UnregisterArbiter(self.id.clone()));
+        } else {
+            self.sys.do_send(ArbiterDied(self.id.clone(), self.name.clone()));
+        }
+    }
+}
+
 impl Handler<StopArbiter> for Arbiter {
     type Result = ();
 
@@ -225,6 +514,108 @@ impl Handler<StopArbiter> for Arbiter {
     }
 }
 
+impl Handler<DumpActors> for Arbiter {
+    type Result = Result<Vec<ActorInfo>, ()>;
+
+    fn handle(&mut self, _: DumpActors, _: &mut Context<Self>) -> Result<Vec<ActorInfo>, ()> {
+        let residents: Vec<_> = RESIDENTS.with(|cell| {
+            cell.borrow().items.iter().filter_map(|item| item.upgrade()).collect()
+        });
+        Ok(residents.iter().map(|r| r.info()).collect())
+    }
+}
+
+impl Handler<StopArbiterGraceful> for Arbiter {
+    type Result = ResponseFuture<StopArbiterGraceful>;
+
+    fn handle(&mut self, msg: StopArbiterGraceful, _: &mut Context<Self>)
+              -> ResponseFuture<StopArbiterGraceful>
+    {
+        if self.sys {
+            warn!("System arbiter received `StopArbiterGraceful` message.
+                  To shutdown system, `SystemExit` message should be send to `Address<System>`");
+            return Box::new(future::err(Vec::new()))
+        }
+
+        // ask all contexts to stop, arbiter's context stops as well
+        // so arbiter does not accept new messages
+        let residents: Vec<_> = RESIDENTS.with(|cell| {
+            let mut residents = cell.borrow_mut();
+            residents.stopping = true;
+            residents.items.iter().filter_map(|item| item.upgrade()).collect()
+        });
+        for resident in residents {
+            resident.stop.set(true);
+            resident.task.notify();
+        }
+
+        Box::new(GracefulStop {
+            timeout: Timeout::new(msg.timeout, Arbiter::handle()).unwrap(),
+            forced: None})
+    }
+}
+
+/// Waits until all arbiter's contexts stop, then stops event loop
+struct GracefulStop {
+    timeout: Timeout,
+    forced: Option<Vec<Rc<Resident>>>,
+}
+
+impl GracefulStop {
+    fn stop_loop(&self) {
+        STOP.with(|cell| {
+            if let Some(stop) = cell.borrow_mut().take() {
+                let _ = stop.send(0);
+            }
+        });
+    }
+}
+
+impl Future for GracefulStop {
+    type Item = ();
+    type Error = Vec<ActorInfo>;
+
+    fn poll(&mut self) -> Poll<(), Vec<ActorInfo>> {
+        // abandoned contexts had a chance to drop their mailboxes
+        if let Some(forced) = self.forced.take() {
+            self.stop_loop();
+            return Err(forced.iter().map(|r| r.info()).collect())
+        }
+
+        let done = RESIDENTS.with(|cell| {
+            let mut residents = cell.borrow_mut();
+            if residents.live == 0 {
+                true
+            } else {
+                residents.waiter = Some(task::current());
+                false
+            }
+        });
+        if done {
+            self.stop_loop();
+            return Ok(Async::Ready(()))
+        }
+
+        match self.timeout.poll() {
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Ok(Async::Ready(_)) | Err(_) => {
+                // contexts that are still running drop queued messages
+                // as dead letters, event loop stops on next poll
+                let forced: Vec<_> = RESIDENTS.with(|cell| {
+                    cell.borrow().items.iter().filter_map(|item| item.upgrade()).collect()
+                });
+                for resident in &forced {
+                    resident.abandon.set(true);
+                    resident.task.notify();
+                }
+                self.forced = Some(forced);
+                task::current().notify();
+                Ok(Async::NotReady)
+            }
+        }
+    }
+}
+
 impl<A> Handler<StartActor<A>> for Arbiter where A: Actor<Context=Context<A>> {
     type Result = SyncAddress<A>;
 
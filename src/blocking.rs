@@ -0,0 +1,109 @@
+//! Thread pool for blocking work, see `Arbiter::spawn_blocking()`
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+
+use crossbeam_channel as channel;
+use futures::{Future, Poll};
+use futures::sync::oneshot::{channel as oneshot, Canceled, Receiver};
+
+use abort;
+
+/// Idle pool thread exits after this period
+const KEEP_ALIVE: Duration = Duration::from_secs(10);
+
+type Job = Box<FnOnce() + Send>;
+
+struct Inner {
+    name: String,
+    max: usize,
+    tx: channel::Sender<Job>,
+    rx: channel::Receiver<Job>,
+    threads: AtomicUsize,
+    idle: AtomicUsize,
+}
+
+/// Blocking pool of the system, shared by all arbiters
+///
+/// Threads are started on demand, up to `max` threads.
+#[derive(Clone)]
+pub(crate) struct BlockingPool(Arc<Inner>);
+
+impl BlockingPool {
+    pub fn new(name: String, max: usize) -> BlockingPool {
+        let (tx, rx) = channel::unbounded();
+        BlockingPool(Arc::new(Inner{
+            name, max: ::std::cmp::max(max, 1), tx, rx,
+            threads: AtomicUsize::new(0),
+            idle: AtomicUsize::new(0),
+        }))
+    }
+
+    /// Run `f` on pool thread
+    pub fn spawn<F, R>(&self, f: F) -> BlockingFuture<R>
+        where F: FnOnce() -> R + Send + 'static, R: Send + 'static
+    {
+        let (tx, rx) = oneshot();
+        let job: Job = Box::new(move || {
+            let _ = tx.send(f());
+        });
+        let _ = self.0.tx.send(job);
+
+        // nobody waits for jobs, start new thread
+        if self.0.idle.load(Ordering::SeqCst) == 0 {
+            if self.0.threads.fetch_add(1, Ordering::SeqCst) < self.0.max {
+                let inner = Arc::clone(&self.0);
+                let name = format!("{}:blocking", inner.name);
+                if thread::Builder::new().name(name).spawn(move || worker(&inner)).is_err() {
+                    error!("Can not start blocking pool thread");
+                    self.0.threads.fetch_sub(1, Ordering::SeqCst);
+                }
+            } else {
+                self.0.threads.fetch_sub(1, Ordering::SeqCst);
+            }
+        }
+
+        BlockingFuture(rx)
+    }
+}
+
+fn worker(inner: &Inner) {
+    loop {
+        inner.idle.fetch_add(1, Ordering::SeqCst);
+        let job = inner.rx.recv_timeout(KEEP_ALIVE);
+        inner.idle.fetch_sub(1, Ordering::SeqCst);
+
+        let job = match job {
+            Ok(job) => job,
+            Err(_) => {
+                // job could be queued while thread was counted as idle
+                inner.threads.fetch_sub(1, Ordering::SeqCst);
+                match inner.rx.try_recv() {
+                    Ok(job) => {
+                        inner.threads.fetch_add(1, Ordering::SeqCst);
+                        job
+                    }
+                    Err(_) => return,
+                }
+            }
+        };
+        if abort::catch_unwind("blocking job isolation", job).is_err() {
+            error!("Blocking job panicked");
+        }
+    }
+}
+
+/// Result of the job spawned with `Arbiter::spawn_blocking()`
+///
+/// Future resolves to `Canceled` error if job panics.
+pub struct BlockingFuture<R>(Receiver<R>);
+
+impl<R> Future for BlockingFuture<R> {
+    type Item = R;
+    type Error = Canceled;
+
+    fn poll(&mut self) -> Poll<R, Canceled> {
+        self.0.poll()
+    }
+}
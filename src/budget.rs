@@ -0,0 +1,67 @@
+//! Per-poll budget of the context
+//!
+//! Budget is shared by context's message sources: mailbox, streams and
+//! interval timers. Every source gets a quantum of the budget each time
+//! context polls it, source yields once quantum or whole budget is used.
+//! Context wakes itself up if any source yielded, so remaining work
+//! continues on next poll.
+use std::cmp;
+use std::cell::Cell;
+
+#[derive(Clone, Copy)]
+pub(crate) struct PollBudget {
+    remaining: usize,
+    quantum: usize,
+    source: usize,
+    yielded: bool,
+}
+
+thread_local!(static BUDGET: Cell<Option<PollBudget>> = Cell::new(None));
+
+/// Start poll with `total` budget split between `sources`,
+/// returns budget of the outer poll
+pub(crate) fn begin(total: usize, sources: usize) -> Option<PollBudget> {
+    let quantum = cmp::max(total / cmp::max(sources, 1), 1);
+    BUDGET.with(|b| b.replace(Some(PollBudget {
+        remaining: total, quantum: quantum, source: quantum, yielded: false })))
+}
+
+/// Finish poll, returns `true` if any source yielded
+pub(crate) fn end(prev: Option<PollBudget>) -> bool {
+    BUDGET.with(|b| b.replace(prev)).map(|budget| budget.yielded).unwrap_or(false)
+}
+
+/// Next source is about to be polled, it gets fresh quantum
+pub(crate) fn next_source() {
+    BUDGET.with(|b| if let Some(mut budget) = b.get() {
+        budget.source = budget.quantum;
+        b.set(Some(budget));
+    })
+}
+
+/// Check if current source could handle one more item.
+///
+/// Without running budget sources are not limited.
+pub(crate) fn has_budget() -> bool {
+    BUDGET.with(|b| match b.get() {
+        Some(mut budget) => {
+            if budget.remaining == 0 || budget.source == 0 {
+                budget.yielded = true;
+                b.set(Some(budget));
+                false
+            } else {
+                true
+            }
+        }
+        None => true,
+    })
+}
+
+/// Current source handled one item
+pub(crate) fn consume() {
+    BUDGET.with(|b| if let Some(mut budget) = b.get() {
+        budget.remaining = budget.remaining.saturating_sub(1);
+        budget.source = budget.source.saturating_sub(1);
+        b.set(Some(budget));
+    })
+}
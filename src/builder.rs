@@ -0,0 +1,301 @@
+use std::any::type_name;
+use std::marker::PhantomData;
+use std::sync::Arc;
+#[cfg(feature="spill")]
+use std::path::PathBuf;
+use std::time::Duration;
+use futures::future;
+
+use actor::{Actor, Supervised};
+use arbiter::Arbiter;
+use address::{sync_channel, ActorAddress, Overflow, SyncAddress};
+use context::Context;
+use mailbox::DEFAULT_CAPACITY;
+use msgs::Execute;
+use persist::{self, StateStore};
+use supervisor::{RestartStrategy, Supervisor};
+
+/// Actor builder
+///
+/// Builder allows to configure actor's context before actor starts.
+/// Builder is accessible via `Actor::build()` method.
+///
+/// # Examples
+///
+/// ```rust
+/// use actix::*;
+///
+/// struct MyActor;
+/// impl Actor for MyActor {
+///     type Context = Context<Self>;
+/// }
+///
+/// # fn main() {
+/// // initialize system
+/// System::new("test");
+///
+/// let addr: Address<_> = MyActor::build()
+///     .mailbox_capacity(32)
+///     .overflow(Overflow::DropOldest)
+///     .name("session-42")
+///     .start(MyActor);
+/// # }
+/// ```
+///
+/// Use `on_arbiter()` method to start actor in different arbiter.
+/// In this case builder always returns `SyncAddress<A>`.
+pub struct ActorBuilder<A, Arb=()> where A: Actor<Context=Context<A>> {
+    cfg: BuilderConfig,
+    arbiter: Arb,
+    act: PhantomData<A>,
+}
+
+struct BuilderConfig {
+    capacity: Option<usize>,
+    overflow: Option<Overflow>,
+    name: Option<String>,
+    stop_on_disconnect: bool,
+    hibernate: Option<Duration>,
+    store: Option<Arc<StateStore + Send + Sync>>,
+    restart: RestartStrategy,
+    #[cfg(feature="spill")]
+    spill: Option<(PathBuf, u64)>,
+}
+
+impl BuilderConfig {
+    /// State store and snapshot key of supervised actor
+    fn take_store<A>(&mut self) -> Option<(Arc<StateStore + Send + Sync>, String)> {
+        let name = self.name.clone().unwrap_or_else(|| type_name::<A>().to_owned());
+        self.store.take().map(|store| (store, name))
+    }
+
+    fn apply<A>(self, ctx: &mut Context<A>) where A: Actor<Context=Context<A>> {
+        // system defaults are applied by context
+        if let Some(cap) = self.capacity {
+            ctx.set_mailbox_capacity(cap);
+        }
+        if let Some(overflow) = self.overflow {
+            ctx.set_mailbox_overflow(overflow);
+        }
+        ctx.set_stop_on_disconnect(self.stop_on_disconnect);
+        ctx.set_hibernate_after(self.hibernate);
+        if let Some(name) = self.name {
+            ctx.set_name(name);
+        }
+        #[cfg(feature="spill")]
+        {
+            if let Some((path, max_bytes)) = self.spill {
+                if let Err(err) = ctx.spill_to_disk(path, max_bytes) {
+                    error!("Can not create spill file: {}", err);
+                }
+            }
+        }
+    }
+}
+
+impl<A> ActorBuilder<A> where A: Actor<Context=Context<A>> {
+
+    pub(crate) fn new() -> ActorBuilder<A> {
+        ActorBuilder {
+            cfg: BuilderConfig {
+                capacity: None,
+                overflow: None,
+                name: None,
+                stop_on_disconnect: true,
+                hibernate: None,
+                store: None,
+                restart: RestartStrategy::default(),
+                #[cfg(feature="spill")]
+                spill: None,
+            },
+            arbiter: (),
+            act: PhantomData,
+        }
+    }
+
+    /// Start actor in specified arbiter.
+    pub fn on_arbiter(self, arb: &SyncAddress<Arbiter>) -> ActorBuilder<A, SyncAddress<Arbiter>> {
+        ActorBuilder {cfg: self.cfg, arbiter: arb.clone(), act: PhantomData}
+    }
+
+    /// Start new actor in current arbiter, returns address of newly created actor.
+    pub fn start<Addr>(self, act: A) -> Addr where A: ActorAddress<A, Addr> {
+        let mut ctx = Context::new(Some(act));
+        self.cfg.apply(&mut ctx);
+        let addr = <A as ActorAddress<A, Addr>>::get(&mut ctx);
+        ctx.run(Arbiter::handle());
+        addr
+    }
+
+    /// Start new actor in current arbiter, actor is created with `Context` object.
+    pub fn start_with<Addr, F>(self, f: F) -> Addr
+        where A: ActorAddress<A, Addr>,
+              F: FnOnce(&mut Context<A>) -> A + 'static
+    {
+        let mut ctx = Context::new(None);
+        self.cfg.apply(&mut ctx);
+        let addr = <A as ActorAddress<A, Addr>>::get(&mut ctx);
+
+        Arbiter::handle().spawn_fn(move || {
+            let act = f(&mut ctx);
+            ctx.set_actor(act);
+            ctx.run(Arbiter::handle());
+            future::ok(())
+        });
+        addr
+    }
+
+    /// Start new supervised actor in current arbiter.
+    pub fn start_supervised<Addr, F>(self, f: F) -> Addr
+        where A: Supervised + ActorAddress<A, Addr>,
+              F: FnOnce(&mut Context<A>) -> A + 'static
+    {
+        let mut cfg = self.cfg;
+        let store = cfg.take_store::<A>();
+        let restart = cfg.restart;
+        let mut ctx = Context::new(None);
+        cfg.apply(&mut ctx);
+        let mut act = f(&mut ctx);
+        if let Some((store, name)) = store {
+            persist::attach(&mut act, &mut ctx, store, name);
+        }
+        let addr = <A as ActorAddress<A, Addr>>::get(&mut ctx);
+        ctx.set_actor(act);
+        Supervisor::spawn_with_strategy(ctx, restart);
+        addr
+    }
+}
+
+impl<A> ActorBuilder<A, SyncAddress<Arbiter>> where A: Actor<Context=Context<A>> {
+
+    /// Start new actor in arbiter's thread, returns address of newly created actor.
+    pub fn start(self, act: A) -> SyncAddress<A> where A: Send {
+        self.start_with(move |_| act)
+    }
+
+    /// Start new actor in arbiter's thread, actor is created with `Context` object.
+    pub fn start_with<F>(self, f: F) -> SyncAddress<A>
+        where F: FnOnce(&mut Context<A>) -> A + Send + 'static
+    {
+        let ActorBuilder {cfg, arbiter, ..} = self;
+        let (tx, rx) = sync_channel::channel(cfg.capacity.unwrap_or(DEFAULT_CAPACITY));
+
+        arbiter.do_send(Execute::new(move || -> Result<(), ()> {
+            let mut ctx = Context::with_receiver(None, rx);
+            cfg.apply(&mut ctx);
+            let act = f(&mut ctx);
+            ctx.set_actor(act);
+            ctx.run(Arbiter::handle());
+            Ok(())
+        }));
+
+        SyncAddress::new(tx)
+    }
+
+    /// Start new supervised actor in arbiter's thread.
+    pub fn start_supervised<F>(self, f: F) -> SyncAddress<A>
+        where A: Supervised,
+              F: FnOnce(&mut Context<A>) -> A + Send + 'static
+    {
+        let ActorBuilder {mut cfg, arbiter, ..} = self;
+        let (tx, rx) = sync_channel::channel(cfg.capacity.unwrap_or(DEFAULT_CAPACITY));
+        let store = cfg.take_store::<A>();
+        let restart = cfg.restart;
+
+        arbiter.do_send(Execute::new(move || -> Result<(), ()> {
+            let mut ctx = Context::with_receiver(None, rx);
+            cfg.apply(&mut ctx);
+            let mut act = f(&mut ctx);
+            if let Some((store, name)) = store {
+                persist::attach(&mut act, &mut ctx, store, name);
+            }
+            ctx.set_actor(act);
+            Supervisor::spawn_with_strategy(ctx, restart);
+            Ok(())
+        }));
+
+        SyncAddress::new(tx)
+    }
+}
+
+impl<A, Arb> ActorBuilder<A, Arb> where A: Actor<Context=Context<A>> {
+
+    /// Set mailbox capacity
+    ///
+    /// By default system's mailbox capacity is used, see `SystemConfig`.
+    /// Zero capacity makes rendezvous mailbox, see
+    /// `Context::set_mailbox_capacity()`.
+    pub fn mailbox_capacity(mut self, cap: usize) -> Self {
+        self.cfg.capacity = Some(cap);
+        self
+    }
+
+    /// Set mailbox overflow policy
+    ///
+    /// By default system's overflow policy is used, see `SystemConfig`.
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.cfg.overflow = Some(overflow);
+        self
+    }
+
+    /// Set actor name
+    ///
+    /// Name is available via `Context::name()` method.
+    pub fn name<T: Into<String>>(mut self, name: T) -> Self {
+        self.cfg.name = Some(name.into());
+        self
+    }
+
+    /// Stop actor when all addresses get dropped and no evented objects left
+    ///
+    /// By default this behavior is enabled. If it is disabled, actor runs
+    /// until `ActorContext::stop()` get called.
+    pub fn stop_on_disconnect(mut self, val: bool) -> Self {
+        self.cfg.stop_on_disconnect = val;
+        self
+    }
+
+    /// Hibernate actor after specified period of inactivity
+    ///
+    /// Actor is inactive if its mailbox is empty and there are no spawned
+    /// futures. Hibernated actor releases internal buffers, it wakes up
+    /// on next incoming message. By default hibernation is disabled.
+    pub fn hibernate_after(mut self, dur: Duration) -> Self {
+        self.cfg.hibernate = Some(dur);
+        self
+    }
+
+    /// Set restart strategy of supervised actor
+    ///
+    /// Strategy is used by `start_supervised()`, by default supervisor
+    /// restarts actor immediately, see `RestartStrategy`.
+    pub fn restart_strategy(mut self, strategy: RestartStrategy) -> Self {
+        self.cfg.restart = strategy;
+        self
+    }
+
+    /// Keep state of supervised actor in `store` across process restarts
+    ///
+    /// Snapshot is keyed by actor name, see `name()`, or by actor type name
+    /// if name is not set. Snapshot is loaded by `start_supervised()` and
+    /// passed to `Supervised::restore()`, result of `Supervised::snapshot()`
+    /// is saved on every graceful stop. Missing or corrupted snapshot is
+    /// logged and actor starts fresh.
+    pub fn state_store<S>(mut self, store: S) -> Self
+        where S: StateStore + Send + Sync + 'static
+    {
+        self.cfg.store = Some(Arc::new(store));
+        self
+    }
+
+    /// Spill messages that do not fit into mailbox to disk
+    ///
+    /// Messages sent with `SyncAddress::spill_send()` while mailbox is full
+    /// are appended to file at `path` instead, until file reaches `max_bytes`.
+    /// See `Context::spill_to_disk()`.
+    #[cfg(feature="spill")]
+    pub fn spill_to_disk<P: Into<PathBuf>>(mut self, path: P, max_bytes: u64) -> Self {
+        self.cfg.spill = Some((path.into(), max_bytes));
+        self
+    }
+}
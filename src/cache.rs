@@ -0,0 +1,121 @@
+use std::any::{Any, TypeId};
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+
+use actor::{Actor, AsyncContext};
+use handler::{Handler, MessageResponse, MessageResult, ResponseChannel, ResponseType};
+
+struct CacheEntry {
+    ttl: Duration,
+    clone: fn(&Any) -> Box<Any>,
+    value: Option<(Instant, Box<Any>)>,
+}
+
+fn clone_item<T: Clone + 'static>(item: &Any) -> Box<Any> {
+    Box::new(item.downcast_ref::<T>().expect("wrong cached item type").clone())
+}
+
+/// Cached responses of the actor, see `Context::cache_response()`
+#[doc(hidden)]
+pub struct ResponseCache {
+    entries: HashMap<TypeId, CacheEntry>,
+}
+
+impl ResponseCache {
+    pub(crate) fn new() -> ResponseCache {
+        ResponseCache{entries: HashMap::new()}
+    }
+
+    /// Start caching responses for message `M`
+    pub(crate) fn enable<M>(&mut self, ttl: Duration)
+        where M: ResponseType + 'static, M::Item: Clone
+    {
+        self.entries.insert(TypeId::of::<M>(),
+                            CacheEntry{ttl: ttl, clone: clone_item::<M::Item>, value: None});
+    }
+
+    /// Drop cached response for message `M`
+    pub(crate) fn invalidate<M: 'static>(&mut self) {
+        if let Some(entry) = self.entries.get_mut(&TypeId::of::<M>()) {
+            entry.value.take();
+        }
+    }
+
+    fn enabled<M: 'static>(&self) -> bool {
+        self.entries.contains_key(&TypeId::of::<M>())
+    }
+
+    /// Copy of cached response, if it is not expired
+    fn get<M: ResponseType + 'static>(&mut self) -> Option<M::Item> {
+        let entry = self.entries.get_mut(&TypeId::of::<M>())?;
+        let expired = match entry.value {
+            Some((at, _)) => at.elapsed() >= entry.ttl,
+            None => return None,
+        };
+        if expired {
+            entry.value.take();
+            return None
+        }
+        let clone = entry.clone;
+        entry.value.as_ref()
+            .and_then(|&(_, ref item)| clone(&**item).downcast::<M::Item>().ok())
+            .map(|item| *item)
+    }
+
+    fn store<M: ResponseType + 'static>(&mut self, item: &M::Item) {
+        if let Some(entry) = self.entries.get_mut(&TypeId::of::<M>()) {
+            entry.value = Some((Instant::now(), (entry.clone)(item)));
+        }
+    }
+}
+
+/// Response channel that stores successful response to the cache
+struct CachingChannel<M, R> {
+    tx: R,
+    cache: Rc<RefCell<ResponseCache>>,
+    msg: PhantomData<M>,
+}
+
+impl<M, R> ResponseChannel<M> for CachingChannel<M, R>
+    where M: ResponseType + 'static, R: ResponseChannel<M>
+{
+    fn is_canceled(&self) -> bool {
+        self.tx.is_canceled()
+    }
+
+    fn poll_canceled(&mut self) -> bool {
+        self.tx.poll_canceled()
+    }
+
+    fn send(self, response: MessageResult<M>) {
+        if let Ok(ref item) = response {
+            self.cache.borrow_mut().store::<M>(item);
+        }
+        self.tx.send(response)
+    }
+}
+
+/// Handle message, response is served from the cache if caching is enabled
+/// for message type and cached response is not expired
+pub(crate) fn handle<A, M, R>(act: &mut A, msg: M, ctx: &mut A::Context, tx: Option<R>)
+    where A: Actor + Handler<M>, A::Context: AsyncContext<A>,
+          M: ResponseType + 'static, R: ResponseChannel<M>
+{
+    let cache = match (ctx.response_cache(), tx.is_some()) {
+        (Some(ref cache), true) if cache.borrow().enabled::<M>() => Rc::clone(cache),
+        _ => return <A as Handler<M>>::handle(act, msg, ctx).handle(ctx, tx),
+    };
+
+    let item = cache.borrow_mut().get::<M>();
+    if let Some(item) = item {
+        if let Some(tx) = tx {
+            tx.send(Ok(item))
+        }
+    } else {
+        let tx = tx.map(|tx| CachingChannel{tx: tx, cache: cache, msg: PhantomData});
+        <A as Handler<M>>::handle(act, msg, ctx).handle(ctx, tx)
+    }
+}
@@ -0,0 +1,104 @@
+//! Compatibility layer for `std::future::Future`
+//!
+//! This module is available with `compat` feature. It provides adapters
+//! in both directions, both adapters run on arbiter's event loop.
+//!
+//! * `CompatExt::compat()` converts futures 0.1 future, like `RequestFut`
+//!   or `LocalFutRequest`, into `std::future::Future`.
+//! * `future01()` converts `std::future::Future` into futures 0.1 future,
+//!   which could be spawned to arbiter or wrapped with `wrap_future()`.
+//!
+//! ```rust,ignore
+//! let fut = addr.call_fut(Ping).compat();
+//!
+//! Arbiter::handle().spawn(
+//!     actix::compat::future01(StdFuture(fut)).map_err(|_| ()));
+//! ```
+use std::pin::Pin;
+use std::sync::Arc;
+use std::task::{self as std_task, Wake, Waker};
+use std::future::Future as StdFuture;
+
+use futures::{Async, Future, Poll};
+use futures::executor::{self, Notify, NotifyHandle, Spawn};
+use futures::task::{self, Task};
+
+
+/// Extension trait, converts futures 0.1 future into `std::future::Future`
+pub trait CompatExt: Future + Sized {
+    /// Convert future into `std::future::Future`
+    fn compat(self) -> Compat<Self> {
+        Compat{fut: executor::spawn(self)}
+    }
+}
+
+impl<F: Future> CompatExt for F {}
+
+/// `std::future::Future` view of futures 0.1 future
+///
+/// Future resolves to `Result<F::Item, F::Error>`.
+#[must_use = "future do nothing unless polled"]
+pub struct Compat<F> {
+    fut: Spawn<F>,
+}
+
+impl<F> Unpin for Compat<F> {}
+
+impl<F: Future> StdFuture for Compat<F> {
+    type Output = Result<F::Item, F::Error>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut std_task::Context) -> std_task::Poll<Self::Output> {
+        let notify = NotifyHandle::from(Arc::new(WakerNotify(cx.waker().clone())));
+        match self.fut.poll_future_notify(&notify, 0) {
+            Ok(Async::Ready(item)) => std_task::Poll::Ready(Ok(item)),
+            Ok(Async::NotReady) => std_task::Poll::Pending,
+            Err(err) => std_task::Poll::Ready(Err(err)),
+        }
+    }
+}
+
+struct WakerNotify(Waker);
+
+impl Notify for WakerNotify {
+    fn notify(&self, _: usize) {
+        self.0.wake_by_ref()
+    }
+}
+
+/// Convert `std::future::Future` into futures 0.1 future
+pub fn future01<F, I, E>(fut: F) -> Future01<F> where F: StdFuture<Output=Result<I, E>> {
+    Future01{fut: Box::pin(fut)}
+}
+
+/// futures 0.1 view of `std::future::Future`
+#[must_use = "future do nothing unless polled"]
+pub struct Future01<F> {
+    fut: Pin<Box<F>>,
+}
+
+impl<F, I, E> Future for Future01<F> where F: StdFuture<Output=Result<I, E>> {
+    type Item = I;
+    type Error = E;
+
+    fn poll(&mut self) -> Poll<I, E> {
+        let waker = Waker::from(Arc::new(TaskWaker(task::current())));
+        let mut cx = std_task::Context::from_waker(&waker);
+        match self.fut.as_mut().poll(&mut cx) {
+            std_task::Poll::Ready(Ok(item)) => Ok(Async::Ready(item)),
+            std_task::Poll::Ready(Err(err)) => Err(err),
+            std_task::Poll::Pending => Ok(Async::NotReady),
+        }
+    }
+}
+
+struct TaskWaker(Task);
+
+impl Wake for TaskWaker {
+    fn wake(self: Arc<Self>) {
+        self.0.notify()
+    }
+
+    fn wake_by_ref(self: &Arc<Self>) {
+        self.0.notify()
+    }
+}
@@ -1,12 +1,30 @@
 use std::{mem, fmt};
+#[cfg(feature="spill")]
+use std::io;
+#[cfg(feature="spill")]
+use std::path::PathBuf;
+use std::any::type_name;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::time::{Duration, Instant};
 use futures::{Future, Poll};
+use futures::unsync::oneshot::Receiver;
 use tokio_core::reactor::Handle;
 
 use fut::ActorFuture;
 use actor::{Actor, Supervised,
-            ActorState, ActorContext, AsyncContext, SpawnHandle};
-use address::{Address, SyncAddress, SyncAddressReceiver};
-use contextimpl::ContextImpl;
+            ActorState, ActorContext, AsyncContext, SpawnHandle, TimerGroup};
+use arbiter::WaitInfo;
+use address::{Address, EnvelopeProxy, Overflow, SendError, Subscriber, SyncAddress, SyncAddressReceiver};
+use cache::ResponseCache;
+use pipe::MessagePipes;
+use redelivery::{Redelivery, RestartPolicy};
+use contextimpl::{ContextImpl, PollStats};
+use mailbox::{HandlerStat, MailboxHandle, MailboxReceiver, TraceEvent};
+use middleware::ActorMiddleware;
+use msgs::MailboxStatus;
+use handler::{Handler, ResponseType};
 
 /// Actor execution context
 pub struct Context<A> where A: Actor<Context=Context<A>> {
@@ -32,6 +50,13 @@ impl<A> AsyncContext<A> for Context<A> where A: Actor<Context=Self> {
     #[inline]
     fn spawn<F>(&mut self, fut: F) -> SpawnHandle
         where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static
+    {
+        self.inner.spawn_limited(fut)
+    }
+
+    #[inline]
+    fn spawn_internal<F>(&mut self, fut: F) -> SpawnHandle
+        where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static
     {
         self.inner.spawn(fut)
     }
@@ -48,6 +73,22 @@ impl<A> AsyncContext<A> for Context<A> where A: Actor<Context=Self> {
         self.inner.waiting()
     }
 
+    #[inline]
+    fn notify<M>(&mut self, msg: M) where A: Handler<M>, M: ResponseType + 'static {
+        if self.inner.state() == ActorState::Stopped {
+            error!("Context::notify called for stopped actor.");
+        } else {
+            self.inner.notify(msg)
+        }
+    }
+
+    #[inline]
+    fn spawn_exclusive<F>(&mut self, fut: F)
+        where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static
+    {
+        self.inner.spawn_exclusive(fut)
+    }
+
     #[inline]
     fn cancel_future(&mut self, handle: SpawnHandle) -> bool {
         self.inner.cancel_future(handle)
@@ -64,6 +105,24 @@ impl<A> AsyncContext<A> for Context<A> where A: Actor<Context=Self> {
     fn sync_address(&mut self) -> SyncAddress<A> {
         self.inner.sync_address()
     }
+
+    #[doc(hidden)]
+    #[inline]
+    fn response_cache(&self) -> Option<Rc<RefCell<ResponseCache>>> {
+        self.inner.response_cache()
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    fn message_pipes(&self) -> Option<Rc<RefCell<MessagePipes>>> {
+        self.inner.message_pipes()
+    }
+
+    #[doc(hidden)]
+    #[inline]
+    fn redelivery(&self) -> Option<Rc<RefCell<Redelivery>>> {
+        self.inner.redelivery()
+    }
 }
 
 impl<A> Context<A> where A: Actor<Context=Self> {
@@ -77,11 +136,408 @@ impl<A> Context<A> where A: Actor<Context=Self> {
 
     /// Set mailbox capacity
     ///
-    /// By default mailbox capacity is 16 messages.
+    /// By default mailbox capacity is 16 messages. Zero capacity makes
+    /// rendezvous mailbox, message is accepted only when actor waits for it.
     pub fn set_mailbox_capacity(&mut self, cap: usize) {
         self.inner.set_mailbox_capacity(cap)
     }
 
+    /// Start collecting message handling statistics
+    ///
+    /// Context records number of handled messages, total and max handling time
+    /// for each message type received through actor's address.
+    pub fn enable_handler_stats(&mut self) {
+        self.inner.enable_handler_stats()
+    }
+
+    /// Message handling statistics keyed by message type name
+    ///
+    /// Returns empty map if statistics is not enabled.
+    pub fn handler_stats(&self) -> HashMap<&'static str, HandlerStat> {
+        self.inner.handler_stats()
+    }
+
+    /// Start recording last `n` mailbox events
+    ///
+    /// Context records enqueue, dequeue, handler start and finish events, and
+    /// messages dropped because of mailbox overflow. Zero stops recording.
+    pub fn enable_mailbox_trace(&mut self, n: usize) {
+        self.inner.enable_mailbox_trace(n)
+    }
+
+    /// Recorded mailbox events, oldest first
+    pub fn mailbox_trace(&self) -> Vec<TraceEvent> {
+        self.inner.mailbox_trace()
+    }
+
+    /// Periodically send `MailboxStatus` report to the actor
+    ///
+    /// Report is delivered through system lane every `interval`, so handler
+    /// could shed load while mailbox is filling up. If system lane is full,
+    /// report is skipped. Cancel returned handle with `cancel_future()`
+    /// to stop reports.
+    pub fn enable_mailbox_reports(&mut self, interval: Duration) -> SpawnHandle
+        where A: Handler<MailboxStatus>
+    {
+        self.run_interval_at(Instant::now() + interval, interval, |_, ctx| {
+            let status = ctx.inner.mailbox_status();
+            let _ = ctx.notify_system(status);
+        })
+    }
+
+    /// Current mailbox status, see `enable_mailbox_reports()`
+    pub fn mailbox_status(&self) -> MailboxStatus {
+        self.inner.mailbox_status()
+    }
+
+    /// Spill messages that do not fit into mailbox to disk
+    ///
+    /// Messages sent with `SyncAddress::spill_send()` while mailbox is full
+    /// are appended to file at `path`, file size is limited by `max_bytes`.
+    /// Spilled messages are handled in order once mailbox is drained.
+    /// File is removed when actor stops.
+    #[cfg(feature="spill")]
+    pub fn spill_to_disk<P: Into<PathBuf>>(&mut self, path: P, max_bytes: u64) -> io::Result<()> {
+        self.inner.spill_to_disk(path.into(), max_bytes)
+    }
+
+    /// Defer handling of messages of type `M`
+    ///
+    /// Messages of type `M` received through actor's address are parked
+    /// in a side queue until `resume::<M>()` get called, other messages are
+    /// handled as usual. Side queue holds at most mailbox capacity messages,
+    /// overflowing messages get dropped and their requests cancel. Parked
+    /// messages are published as dead letters if actor stops.
+    pub fn defer<M>(&mut self) where A: Handler<M>, M: ResponseType + 'static {
+        self.inner.defer(type_name::<M>())
+    }
+
+    /// Resume handling of messages of type `M`
+    ///
+    /// Parked messages are handled in order they were received, before
+    /// any new message.
+    pub fn resume<M>(&mut self) where A: Handler<M>, M: ResponseType + 'static {
+        self.inner.resume(type_name::<M>())
+    }
+
+    /// Receive messages of additional mailbox
+    ///
+    /// Actor handles messages sent to its own addresses and to every
+    /// attached mailbox, attached mailboxes are polled in turn. Order of
+    /// messages is kept within each mailbox. Actor keeps running while
+    /// any of its mailboxes is connected.
+    ///
+    /// ```rust,ignore
+    /// let (addr, rx) = MailboxReceiver::new();
+    /// // hand out `addr`, messages are queued until mailbox is attached
+    /// let handle = ctx.attach_mailbox(rx);
+    /// ```
+    pub fn attach_mailbox(&mut self, rx: MailboxReceiver<A>) -> MailboxHandle {
+        self.inner.attach_mailbox(rx)
+    }
+
+    /// Close attached mailbox
+    ///
+    /// Senders of the mailbox get `SendError::Closed` error, messages that
+    /// are already queued are handled before any new message. Returns
+    /// `false` if mailbox is not attached.
+    pub fn detach_mailbox(&mut self, handle: MailboxHandle) -> bool {
+        self.inner.detach_mailbox(handle)
+    }
+
+    /// Close mailbox and take all pending messages, see `Handoff`
+    pub(crate) fn drain_mailbox(&mut self) -> Vec<Box<EnvelopeProxy<Actor=A>>> {
+        self.inner.drain_mailbox()
+    }
+
+    /// Receiver resolves after actor's `stopped()` method get called
+    pub(crate) fn stop_waiter(&mut self) -> Receiver<()> {
+        self.inner.stop_waiter()
+    }
+
+    /// Receiver resolves after actor's startup completes
+    pub(crate) fn start_waiter(&mut self) -> Receiver<()> {
+        self.inner.start_waiter()
+    }
+
+    /// Cache successful responses to message `M` for `ttl`
+    ///
+    /// Response is stored after handler's result resolves, subsequent
+    /// requests are answered from the cache without calling the handler
+    /// until cached response expires. Cache is keyed by message type only,
+    /// so it suits messages without parameters. Messages sent with
+    /// `do_send()` always reach the handler.
+    pub fn cache_response<M>(&mut self, ttl: Duration)
+        where A: Handler<M>, M: ResponseType + 'static, M::Item: Clone
+    {
+        self.inner.cache_response::<M>(ttl)
+    }
+
+    /// Drop cached response to message `M`
+    pub fn invalidate_cache<M>(&mut self) where A: Handler<M>, M: ResponseType + 'static {
+        self.inner.invalidate_cache::<M>()
+    }
+
+    /// Forward copy of every message `M` to `subscriber`
+    ///
+    /// Copy is sent with `Subscriber::try_send()` after actor's handler
+    /// for the message is called, copies that subscriber does not accept
+    /// are dropped and counted, see `Context::pipe_dropped()`. Multiple
+    /// pipes could be installed for one message type. Only messages
+    /// received through actor's address are piped.
+    pub fn pipe_to<M>(&mut self, subscriber: Box<Subscriber<M>>)
+        where A: Handler<M>, M: ResponseType + Clone + 'static
+    {
+        self.inner.pipe_to::<M>(subscriber)
+    }
+
+    /// Remove all pipes of message `M`
+    pub fn unpipe<M>(&mut self) where A: Handler<M>, M: ResponseType + 'static {
+        self.inner.unpipe::<M>()
+    }
+
+    /// Number of copies of message `M` dropped because pipe's subscriber
+    /// did not accept them
+    pub fn pipe_dropped<M>(&self) -> usize where A: Handler<M>, M: ResponseType + 'static {
+        self.inner.pipe_dropped::<M>()
+    }
+
+    /// Set restart policy of supervised actor, `RestartPolicy::DropMessage` by default
+    ///
+    /// With `RestartPolicy::KeepMailbox` policy supervisor restarts actor if
+    /// message handler panics and message that failed is delivered again,
+    /// until it gets quarantined after configured number of failed attempts.
+    /// Message types have to be registered with `Context::redeliver()`.
+    /// Policy has no effect for actors that are not started by `Supervisor`.
+    pub fn set_restart_policy(&mut self, policy: RestartPolicy) {
+        self.inner.set_restart_policy(policy)
+    }
+
+    /// Deliver message `M` again if actor fails during its handling
+    ///
+    /// Handler receives copy of the message, original message stays in the
+    /// mailbox until handler returns. Response channel of the request is
+    /// closed on first failure, caller gets `MailboxError::Canceled` and
+    /// redelivered message is handled as if it is sent with `do_send()`.
+    /// See `RestartPolicy::KeepMailbox`.
+    pub fn redeliver<M>(&mut self) where A: Handler<M>, M: ResponseType + Clone + 'static {
+        self.inner.redeliver::<M>()
+    }
+
+    /// Send message `msg` to self through system lane
+    ///
+    /// System lane is a small queue of fixed capacity, it is drained before
+    /// ordinary mailbox, so framework messages get handled even if mailbox
+    /// is flooded. Fails with `SendError::Full` if system lane is full.
+    pub fn notify_system<M>(&mut self, msg: M) -> Result<(), SendError<M>>
+        where A: Handler<M>, M: ResponseType + 'static
+    {
+        self.inner.notify_system(msg)
+    }
+
+    /// Install actor middleware
+    ///
+    /// Middleware wraps dispatch of every message received through actor's
+    /// address, see `ActorMiddleware`.
+    pub fn add_middleware<M>(&mut self, mw: M) where M: ActorMiddleware<A> {
+        self.inner.add_middleware(Box::new(mw))
+    }
+
+    /// Generate id that is unique within this actor instance
+    ///
+    /// Ids are taken from monotonic counter, counter is preserved across
+    /// actor restarts. See `actix::unique_system_id()` for system-wide ids.
+    pub fn unique_id(&mut self) -> u64 {
+        self.inner.unique_id()
+    }
+
+    /// Create new timer group
+    ///
+    /// Timers registered within a group with `run_later_in()` or
+    /// `add_to_group()` could be cancelled all at once with `cancel_group()`.
+    /// Pending timers are dropped when actor stops.
+    pub fn timer_group(&mut self) -> TimerGroup {
+        self.inner.timer_group()
+    }
+
+    /// Execute closure after specified period of time, timer belongs to `group`
+    pub fn run_later_in<F>(&mut self, group: TimerGroup, dur: Duration, f: F) -> SpawnHandle
+        where F: FnOnce(&mut A, &mut Self) + 'static
+    {
+        let handle = self.run_later(dur, f);
+        self.inner.add_to_group(group, handle);
+        handle
+    }
+
+    /// Register spawned timer, interval or any other future within `group`
+    pub fn add_to_group(&mut self, group: TimerGroup, handle: SpawnHandle) {
+        self.inner.add_to_group(group, handle)
+    }
+
+    /// Cancel all timers registered within `group`
+    pub fn cancel_group(&mut self, group: TimerGroup) {
+        self.inner.cancel_group(group)
+    }
+
+    /// Spawn outbound request future into context under `tag`
+    ///
+    /// `fut` is usually `call_fut()` request converted with `into_actor()`
+    /// together with its continuations, i.e. `.then(...)` chain. All requests
+    /// of the tag could be cancelled at once with `cancel_requests()`.
+    ///
+    /// ```rust,ignore
+    /// ctx.track_request("downstream",
+    ///     self.downstream.call_fut(Query).into_actor(self)
+    ///         .then(|res, act, ctx| { ... }));
+    /// ```
+    pub fn track_request<T, F>(&mut self, tag: T, fut: F) -> SpawnHandle
+        where T: Into<String>, F: ActorFuture<Item=(), Error=(), Actor=A> + 'static
+    {
+        self.inner.track_request(tag.into(), fut)
+    }
+
+    /// Drop all pending request futures registered under `tag`
+    ///
+    /// Continuations of dropped futures do not run, dropped response
+    /// receivers cancel underlying calls. Messages that are not handled yet
+    /// are skipped by recipient, handlers that are already running see
+    /// canceled response channel.
+    pub fn cancel_requests(&mut self, tag: &str) {
+        self.inner.cancel_requests(tag)
+    }
+
+    /// Number of pending request futures registered under `tag`
+    pub fn pending_requests(&self, tag: &str) -> usize {
+        self.inner.pending_requests(tag)
+    }
+
+    /// Spawn async future into context, ignore limit of spawned futures.
+    ///
+    /// Use this method for control-flow futures that should not wait
+    /// in overflow queue.
+    pub fn spawn_urgent<F>(&mut self, fut: F) -> SpawnHandle
+        where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static
+    {
+        self.inner.spawn_urgent(fut)
+    }
+
+    /// Set maximum number of concurrently running spawned futures
+    ///
+    /// If limit is reached, `AsyncContext::spawn()` puts future to overflow queue.
+    /// Futures from overflow queue start in spawn order as running futures complete.
+    /// Streams, timers, delayed messages and futures of async message responses
+    /// are not limited and not counted.
+    /// By default number of spawned futures is not limited (0).
+    pub fn set_max_spawned(&mut self, max: usize) {
+        self.inner.set_max_spawned(max)
+    }
+
+    /// Number of running futures spawned with `AsyncContext::spawn()`
+    /// or `spawn_urgent()`
+    pub fn spawned_count(&self) -> usize {
+        self.inner.spawned_count()
+    }
+
+    /// Number of spawned futures waiting in overflow queue
+    pub fn overflow_count(&self) -> usize {
+        self.inner.overflow_count()
+    }
+
+    /// Report actor that stays in `wait()` longer than `threshold` while
+    /// its mailbox holds messages
+    ///
+    /// This is typical deadlock of two actors that wait for responses of
+    /// each other. Error gets logged with actor name, type of the message
+    /// actor waits for and types of queued messages. With `break_wait`
+    /// pending requests of the wait future fail with `MailboxError::Timeout`.
+    /// `None` disables detection, by default system setting is used,
+    /// see `SystemConfig::deadlock_detection()`.
+    pub fn set_deadlock_detection(&mut self, threshold: Option<Duration>, break_wait: bool) {
+        self.inner.set_deadlock_detection(threshold, break_wait)
+    }
+
+    /// Wait future actor is blocked on, `None` if actor does not wait
+    ///
+    /// Info describes most recent future registered with `wait()`: type of
+    /// the message its outstanding request waits for, time spent in wait and
+    /// number of older wait futures queued behind it. Same info is reported
+    /// by arbiter's live dump, see `msgs::DumpActors`.
+    pub fn wait_queue_info(&self) -> Option<WaitInfo> {
+        self.inner.wait_queue_info()
+    }
+
+    /// Warn about actor that is polled more than `threshold` times per
+    /// second without progress
+    ///
+    /// Poll makes progress if message got dequeued from mailbox or one of
+    /// context's futures completed. Spinning actor usually has a future that
+    /// returns `NotReady` without registering wakeup, or a stream that keeps
+    /// waking up context. Counters are checked once a second, at most one
+    /// warning is logged per second. `None` disables detection, by default
+    /// system setting is used, see `SystemConfig::spin_detection()`.
+    pub fn set_spin_detection(&mut self, threshold: Option<u32>) {
+        self.inner.set_spin_detection(threshold)
+    }
+
+    /// Poll statistics of the last second
+    ///
+    /// Statistics is collected only if spin detection is enabled.
+    pub fn poll_stats(&self) -> PollStats {
+        self.inner.poll_stats()
+    }
+
+    /// Limit number of items actor handles within single poll
+    ///
+    /// Budget is shared by mailbox, streams and interval timers. Every poll
+    /// mailbox is processed first, then spawned futures in rotating order,
+    /// each source handles at most its even share of the budget, but at
+    /// least one item. Source that used its share yields and context
+    /// schedules next poll, so busy stream can not starve mailbox and
+    /// busy mailbox can not starve streams. System lane messages and
+    /// `notify()` messages sent during startup are not limited. Zero disables
+    /// budget, by default system setting is used,
+    /// see `SystemConfig::poll_budget()`.
+    pub fn set_poll_budget(&mut self, budget: usize) {
+        self.inner.set_poll_budget(budget)
+    }
+
+    /// Actor name
+    ///
+    /// Name could be set with `ActorBuilder::name()` method.
+    pub fn name(&self) -> Option<&str> {
+        self.inner.name()
+    }
+
+    /// Is actor hibernated
+    pub fn hibernated(&self) -> bool {
+        self.inner.hibernated()
+    }
+
+    #[inline]
+    pub(crate) fn set_hibernate_after(&mut self, dur: Option<Duration>) {
+        self.inner.set_hibernate_after(dur)
+    }
+
+    #[inline]
+    pub(crate) fn set_mailbox_overflow(&mut self, overflow: Overflow) {
+        self.inner.set_mailbox_overflow(overflow)
+    }
+
+    #[inline]
+    pub(crate) fn set_name(&mut self, name: String) {
+        self.inner.set_name(name)
+    }
+
+    #[inline]
+    pub(crate) fn set_snapshot_hook(&mut self, hook: Box<Fn(&A)>) {
+        self.inner.set_snapshot_hook(hook)
+    }
+
+    #[inline]
+    pub(crate) fn set_stop_on_disconnect(&mut self, val: bool) {
+        self.inner.set_stop_on_disconnect(val)
+    }
+
     #[inline]
     pub(crate) fn new(act: Option<A>) -> Context<A> {
         Context { inner: ContextImpl::new(act) }
@@ -97,6 +553,16 @@ impl<A> Context<A> where A: Actor<Context=Self> {
         handle.spawn(self.map(|_| ()).map_err(|_| ()));
     }
 
+    #[inline]
+    pub(crate) fn restart_policy(&self) -> RestartPolicy {
+        self.inner.restart_policy()
+    }
+
+    #[inline]
+    pub(crate) fn restartable(&self) -> bool {
+        self.inner.restartable()
+    }
+
     #[inline]
     pub(crate) fn restart(&mut self) -> bool where A: Supervised {
         let ctx: &mut Context<A> = unsafe {
@@ -1,13 +1,34 @@
 use std::mem;
+use std::any::type_name;
+#[cfg(feature="spill")]
+use std::io;
+#[cfg(feature="spill")]
+use std::path::PathBuf;
+use std::rc::Rc;
+use std::cell::{Cell, RefCell};
+use std::collections::{HashMap, VecDeque};
+use std::time::{Duration, Instant};
 
-use futures::{Async, Poll};
+use futures::{task, Async, Future, Poll};
+use futures::unsync::oneshot::{channel, Receiver, Sender};
 use smallvec::SmallVec;
+use tokio_core::reactor::Timeout;
 
 use fut::ActorFuture;
-use actor::{Actor, AsyncContext, ActorState, SpawnHandle, Supervised};
-use address::{Address, SyncAddress, SyncAddressReceiver};
-use contextitems::ActorWaitItem;
-use mailbox::Mailbox;
+use actor::{Actor, AsyncContext, ActorState, SpawnHandle, Supervised, TimerGroup};
+use arbiter::{Arbiter, Resident, WaitInfo};
+use actors::deadletter::DeadLetterReason;
+use budget;
+use cache::ResponseCache;
+use pipe::MessagePipes;
+use redelivery::{Redelivery, RestartPolicy};
+use pump;
+use address::{Address, EnvelopeProxy, Overflow, SendError, Subscriber, SyncAddress, SyncAddressReceiver};
+use contextitems::{ActorExclusiveItem, ActorMessageItem, ActorWaitItem};
+use handler::{Handler, ResponseType};
+use mailbox::{HandlerStat, Mailbox, MailboxHandle, MailboxReceiver, TraceEvent};
+use middleware::ActorMiddleware;
+use msgs::MailboxStatus;
 
 /// internal context state
 bitflags! {
@@ -15,12 +36,69 @@ bitflags! {
         const STARTED =  0b0000_0001;
         const RUNNING =  0b0000_0010;
         const STOPPING = 0b0000_0100;
+        const HIBERNATED = 0b0000_1000;
         const STOPPED =  0b0001_0000;
         const MODIFIED = 0b0010_0000;
+        const INITIALIZED = 0b0100_0000;
     }
 }
 
-type Item<A> = (SpawnHandle, Box<ActorFuture<Item=(), Error=(), Actor=A>>);
+/// Spawned future, flag is set for futures that count towards limit of spawned futures
+type Item<A> = (SpawnHandle, Box<ActorFuture<Item=(), Error=(), Actor=A>>, bool);
+
+/// Context poll statistics of the last reporting interval (one second),
+/// see `Context::set_spin_detection()`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PollStats {
+    /// Number of context polls
+    pub polls: u32,
+    /// Number of polls without progress, i.e. no message got dequeued
+    /// and no future completed
+    pub spurious: u32,
+}
+
+/// Poll counters of spin detection
+struct SpinDetector {
+    threshold: u32,
+    start: Instant,
+    current: PollStats,
+    last: PollStats,
+}
+
+impl SpinDetector {
+    fn new(threshold: u32) -> SpinDetector {
+        SpinDetector {
+            threshold: threshold,
+            start: Instant::now(),
+            current: PollStats::default(),
+            last: PollStats::default(),
+        }
+    }
+
+    fn record(&mut self, progress: bool, name: &str) {
+        self.current.polls += 1;
+        if !progress {
+            self.current.spurious += 1;
+        }
+        if self.start.elapsed() >= Duration::from_secs(1) {
+            if self.current.spurious > self.threshold {
+                warn!("Actor {} is spinning: {} polls in last second, {} without progress",
+                      name, self.current.polls, self.current.spurious);
+            }
+            self.last = self.current;
+            self.current = PollStats::default();
+            self.start = Instant::now();
+        }
+    }
+}
+
+/// Actor name for diagnostics
+fn actor_name<A>(name: &Option<String>) -> &str {
+    match *name {
+        Some(ref name) => name,
+        None => type_name::<A>(),
+    }
+}
 
 /// Actor execution context impl
 ///
@@ -30,9 +108,44 @@ pub struct ContextImpl<A> where A: Actor, A::Context: AsyncContext<A> {
     flags: ContextFlags,
     mailbox: Mailbox<A>,
     wait: SmallVec<[ActorWaitItem<A>; 2]>,
+    init: VecDeque<Box<ActorFuture<Item=(), Error=(), Actor=A>>>,
     items: SmallVec<[Item<A>; 3]>,
+    overflow: VecDeque<Item<A>>,
+    max_spawned: usize,
+    spawned: usize,
     handle: SpawnHandle,
     curr_handle: SpawnHandle,
+    curr_cancelled: bool,
+    exclusive: Rc<Cell<usize>>,
+    name: Option<String>,
+    stop_on_disconnect: bool,
+    resident: Option<Rc<Resident>>,
+    hibernate: Option<Duration>,
+    idle: Option<Timeout>,
+    groups: HashMap<TimerGroup, Vec<SpawnHandle>>,
+    next_group: usize,
+    requests: HashMap<String, TimerGroup>,
+    next_id: u64,
+    cache: Option<Rc<RefCell<ResponseCache>>>,
+    pipes: Option<Rc<RefCell<MessagePipes>>>,
+    redelivery: Option<Rc<RefCell<Redelivery>>>,
+    restart_policy: RestartPolicy,
+    stop_waiters: Vec<Sender<()>>,
+    start_waiter: Option<Sender<()>>,
+    snapshot: Option<Box<Fn(&A)>>,
+    deadlock: Option<(Duration, bool)>,
+    spin: Option<SpinDetector>,
+    progress: bool,
+    budget: usize,
+}
+
+impl<A> Drop for ContextImpl<A> where A: Actor, A::Context: AsyncContext<A> {
+    fn drop(&mut self) {
+        // messages left in mailbox of stopped actor become dead letters
+        for mut env in self.mailbox.drain() {
+            env.dead_letter(DeadLetterReason::Stopped);
+        }
+    }
 }
 
 impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
@@ -42,11 +155,38 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
         ContextImpl {
             act: act,
             wait: SmallVec::new(),
+            init: VecDeque::new(),
             items: SmallVec::new(),
+            overflow: VecDeque::new(),
+            max_spawned: 0,
+            spawned: 0,
             flags: ContextFlags::RUNNING,
             mailbox: Mailbox::default(),
             handle: SpawnHandle::default(),
             curr_handle: SpawnHandle::default(),
+            curr_cancelled: false,
+            exclusive: Rc::new(Cell::new(0)),
+            name: None,
+            stop_on_disconnect: true,
+            resident: None,
+            hibernate: None,
+            idle: None,
+            groups: HashMap::new(),
+            next_group: 0,
+            requests: HashMap::new(),
+            next_id: 0,
+            cache: None,
+            pipes: None,
+            redelivery: None,
+            restart_policy: RestartPolicy::DropMessage,
+            stop_waiters: Vec::new(),
+            start_waiter: None,
+            snapshot: None,
+            deadlock: Arbiter::system_config().and_then(|cfg| cfg.deadlock),
+            spin: Arbiter::system_config()
+                .and_then(|cfg| cfg.spin_threshold).map(SpinDetector::new),
+            progress: false,
+            budget: Arbiter::system_config().map(|cfg| cfg.poll_budget).unwrap_or(0),
         }
     }
 
@@ -55,11 +195,38 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
         ContextImpl {
             act: act,
             wait: SmallVec::new(),
+            init: VecDeque::new(),
             items: SmallVec::new(),
+            overflow: VecDeque::new(),
+            max_spawned: 0,
+            spawned: 0,
             flags: ContextFlags::RUNNING,
             mailbox: Mailbox::new(rx),
             handle: SpawnHandle::default(),
             curr_handle: SpawnHandle::default(),
+            curr_cancelled: false,
+            exclusive: Rc::new(Cell::new(0)),
+            name: None,
+            stop_on_disconnect: true,
+            resident: None,
+            hibernate: None,
+            idle: None,
+            groups: HashMap::new(),
+            next_group: 0,
+            requests: HashMap::new(),
+            next_id: 0,
+            cache: None,
+            pipes: None,
+            redelivery: None,
+            restart_policy: RestartPolicy::DropMessage,
+            stop_waiters: Vec::new(),
+            start_waiter: None,
+            snapshot: None,
+            deadlock: Arbiter::system_config().and_then(|cfg| cfg.deadlock),
+            spin: Arbiter::system_config()
+                .and_then(|cfg| cfg.spin_threshold).map(SpinDetector::new),
+            progress: false,
+            budget: Arbiter::system_config().map(|cfg| cfg.poll_budget).unwrap_or(0),
         }
     }
 
@@ -123,16 +290,93 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
 
     #[inline]
     /// Spawn new future to this context.
+    ///
+    /// Used for context machinery, i.e. streams, timers and message responses,
+    /// future ignores limit of spawned futures and is not counted.
     pub fn spawn<F>(&mut self, fut: F) -> SpawnHandle
         where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static
+    {
+        self.modify();
+        self.handle = self.handle.next();
+        self.items.push((self.handle, Box::new(fut), false));
+        self.handle
+    }
+
+    #[inline]
+    /// Spawn new user future to this context.
+    ///
+    /// If limit of spawned futures is reached, future is placed to overflow queue.
+    pub fn spawn_limited<F>(&mut self, fut: F) -> SpawnHandle
+        where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static
     {
         self.modify();
         self.handle = self.handle.next();
         let fut: Box<ActorFuture<Item=(), Error=(), Actor=A>> = Box::new(fut);
-        self.items.push((self.handle, fut));
+        if !self.overflow.is_empty() ||
+            (self.max_spawned != 0 && self.spawned >= self.max_spawned)
+        {
+            self.overflow.push_back((self.handle, fut, true));
+        } else {
+            self.spawned += 1;
+            self.items.push((self.handle, fut, true));
+        }
+        self.handle
+    }
+
+    #[inline]
+    /// Spawn new user future to this context, ignore limit of spawned futures.
+    pub fn spawn_urgent<F>(&mut self, fut: F) -> SpawnHandle
+        where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static
+    {
+        self.modify();
+        self.handle = self.handle.next();
+        self.spawned += 1;
+        self.items.push((self.handle, Box::new(fut), true));
         self.handle
     }
 
+    #[inline]
+    /// Set maximum number of concurrently running spawned futures
+    ///
+    /// By default number of spawned futures is not limited (0).
+    pub fn set_max_spawned(&mut self, max: usize) {
+        self.max_spawned = max;
+        self.spawn_overflow();
+    }
+
+    #[inline]
+    /// Number of running spawned futures
+    pub fn spawned_count(&self) -> usize {
+        self.spawned
+    }
+
+    #[inline]
+    /// Number of futures waiting in overflow queue
+    pub fn overflow_count(&self) -> usize {
+        self.overflow.len()
+    }
+
+    /// Move futures from overflow queue to running futures
+    fn spawn_overflow(&mut self) {
+        while self.max_spawned == 0 || self.spawned < self.max_spawned {
+            if let Some(item) = self.overflow.pop_front() {
+                self.modify();
+                self.spawned += 1;
+                self.items.push(item);
+            } else {
+                break
+            }
+        }
+    }
+
+    /// Remove running future, start next one from overflow queue
+    fn remove_item(&mut self, idx: usize) {
+        if self.items.swap_remove(idx).2 {
+            self.spawned -= 1;
+        }
+        self.spawn_overflow();
+    }
+
     #[inline]
     /// Spawn new future to this context and wait future completion.
     ///
@@ -142,19 +386,125 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
         self.wait.push(ActorWaitItem::new(f));
     }
 
+    #[inline]
+    /// Send message to self.
+    ///
+    /// Messages sent before actor completes startup, i.e. from `create()`
+    /// closure or `Actor::started()` method, get handled before
+    /// any mailbox message or stream item.
+    pub fn notify<M>(&mut self, msg: M) where A: Handler<M>, M: ResponseType + 'static {
+        if self.flags.contains(ContextFlags::INITIALIZED) {
+            self.spawn(ActorMessageItem::new(msg));
+        } else {
+            self.modify();
+            self.init.push_back(Box::new(ActorMessageItem::new(msg)));
+        }
+    }
+
+    #[inline]
+    /// Spawn new future to this context, stop processing incoming messages
+    /// until this future resolves.
+    ///
+    /// Other spawned futures continue running. Future ignores limit of spawned futures.
+    pub fn spawn_exclusive<F>(&mut self, fut: F)
+        where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static
+    {
+        self.spawn_urgent(ActorExclusiveItem::new(fut, Rc::clone(&self.exclusive)));
+    }
+
     #[inline]
     /// Cancel previously scheduled future.
     pub fn cancel_future(&mut self, handle: SpawnHandle) -> bool {
+        // running future can not be dropped during its own poll,
+        // it gets removed after poll completes
+        if handle == self.curr_handle && handle != SpawnHandle::default() {
+            self.curr_cancelled = true;
+            return true
+        }
         for idx in 0..self.items.len() {
             if self.items[idx].0 == handle {
                 self.modify();
-                self.items.swap_remove(idx);
+                self.remove_item(idx);
+                return true
+            }
+        }
+        for idx in 0..self.overflow.len() {
+            if self.overflow[idx].0 == handle {
+                self.overflow.remove(idx);
                 return true
             }
         }
         false
     }
 
+    /// Create new timer group
+    pub fn timer_group(&mut self) -> TimerGroup {
+        self.next_group += 1;
+        TimerGroup(self.next_group)
+    }
+
+    pub fn unique_id(&mut self) -> u64 {
+        self.next_id += 1;
+        self.next_id
+    }
+
+    /// Register spawned future within timer group
+    pub fn add_to_group(&mut self, group: TimerGroup, handle: SpawnHandle) {
+        let (items, overflow) = (&self.items, &self.overflow);
+        let handles = self.groups.entry(group).or_insert_with(Vec::new);
+
+        // forget completed timers
+        handles.retain(|h| items.iter().any(|item| item.0 == *h) ||
+                       overflow.iter().any(|item| item.0 == *h));
+        handles.push(handle);
+    }
+
+    /// Cancel all futures registered within timer group
+    pub fn cancel_group(&mut self, group: TimerGroup) {
+        if let Some(handles) = self.groups.remove(&group) {
+            for handle in handles {
+                self.cancel_future(handle);
+            }
+        }
+    }
+
+    /// Spawn request future within group of the tag
+    pub fn track_request<F>(&mut self, tag: String, fut: F) -> SpawnHandle
+        where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static
+    {
+        let group = match self.requests.get(&tag) {
+            Some(group) => *group,
+            None => {
+                let group = self.timer_group();
+                self.requests.insert(tag, group);
+                group
+            }
+        };
+        let handle = self.spawn_limited(fut);
+        self.add_to_group(group, handle);
+        handle
+    }
+
+    /// Cancel all request futures of the tag
+    pub fn cancel_requests(&mut self, tag: &str) {
+        if let Some(group) = self.requests.remove(tag) {
+            self.cancel_group(group);
+        }
+    }
+
+    /// Number of request futures of the tag that are not completed yet
+    pub fn pending_requests(&self, tag: &str) -> usize {
+        let handles = match self.requests.get(tag).and_then(|group| self.groups.get(group)) {
+            Some(handles) => handles,
+            None => return 0,
+        };
+        handles.iter()
+            .filter(|h| (**h != self.curr_handle || !self.curr_cancelled) &&
+                    (self.items.iter().any(|item| item.0 == **h) ||
+                     self.overflow.iter().any(|item| item.0 == **h)))
+            .count()
+    }
+
     #[inline]
     pub fn capacity(&mut self) -> usize {
         self.mailbox.capacity()
@@ -166,6 +516,223 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
         self.mailbox.set_capacity(cap);
     }
 
+    #[inline]
+    pub fn set_mailbox_overflow(&mut self, overflow: Overflow) {
+        self.modify();
+        self.mailbox.set_overflow(overflow);
+    }
+
+    #[inline]
+    pub fn enable_handler_stats(&mut self) {
+        self.mailbox.enable_stats();
+    }
+
+    #[inline]
+    pub fn handler_stats(&self) -> HashMap<&'static str, HandlerStat> {
+        self.mailbox.stats()
+    }
+
+    #[inline]
+    pub fn enable_mailbox_trace(&mut self, n: usize) {
+        self.mailbox.enable_trace(n);
+    }
+
+    #[inline]
+    pub fn mailbox_trace(&self) -> Vec<TraceEvent> {
+        self.mailbox.trace()
+    }
+
+    pub fn mailbox_status(&self) -> MailboxStatus {
+        let (len, oldest) = self.mailbox.status();
+        MailboxStatus {
+            len: len,
+            capacity: self.mailbox.capacity(),
+            oldest_age: oldest.map(|at| Instant::now() - at).unwrap_or_default(),
+        }
+    }
+
+    #[cfg(feature="spill")]
+    pub fn spill_to_disk(&mut self, path: PathBuf, max_bytes: u64) -> io::Result<()> {
+        self.mailbox.spill_to_disk(path, max_bytes)
+    }
+
+    #[inline]
+    pub fn defer(&mut self, tp: &'static str) {
+        self.mailbox.defer(tp);
+    }
+
+    #[inline]
+    pub fn attach_mailbox(&mut self, rx: MailboxReceiver<A>) -> MailboxHandle {
+        self.modify();
+        self.mailbox.attach(rx)
+    }
+
+    #[inline]
+    pub fn detach_mailbox(&mut self, handle: MailboxHandle) -> bool {
+        self.modify();
+        self.mailbox.detach(handle)
+    }
+
+    #[inline]
+    pub fn drain_mailbox(&mut self) -> Vec<Box<EnvelopeProxy<Actor=A>>> {
+        self.mailbox.drain()
+    }
+
+    #[inline]
+    pub fn resume(&mut self, tp: &'static str) {
+        self.modify();
+        self.mailbox.resume(tp);
+    }
+
+    pub fn cache_response<M>(&mut self, ttl: Duration)
+        where M: ResponseType + 'static, M::Item: Clone
+    {
+        self.cache.get_or_insert_with(|| Rc::new(RefCell::new(ResponseCache::new())))
+            .borrow_mut().enable::<M>(ttl);
+    }
+
+    pub fn invalidate_cache<M: 'static>(&mut self) {
+        if let Some(ref cache) = self.cache {
+            cache.borrow_mut().invalidate::<M>();
+        }
+    }
+
+    #[inline]
+    pub fn response_cache(&self) -> Option<Rc<RefCell<ResponseCache>>> {
+        self.cache.clone()
+    }
+
+    pub fn pipe_to<M: Clone + 'static>(&mut self, subscriber: Box<Subscriber<M>>) {
+        self.pipes.get_or_insert_with(|| Rc::new(RefCell::new(MessagePipes::new())))
+            .borrow_mut().add::<M>(subscriber);
+    }
+
+    pub fn unpipe<M: 'static>(&mut self) {
+        if let Some(ref pipes) = self.pipes {
+            pipes.borrow_mut().remove::<M>();
+        }
+    }
+
+    pub fn pipe_dropped<M: 'static>(&self) -> usize {
+        self.pipes.as_ref().map(|pipes| pipes.borrow().dropped::<M>()).unwrap_or(0)
+    }
+
+    #[inline]
+    pub fn message_pipes(&self) -> Option<Rc<RefCell<MessagePipes>>> {
+        self.pipes.clone()
+    }
+
+    pub fn set_restart_policy(&mut self, policy: RestartPolicy) {
+        self.restart_policy = policy;
+        self.mailbox.set_max_attempts(match policy {
+            RestartPolicy::DropMessage => None,
+            RestartPolicy::KeepMailbox{max_attempts} => Some(max_attempts),
+        });
+    }
+
+    #[inline]
+    pub fn restart_policy(&self) -> RestartPolicy {
+        self.restart_policy
+    }
+
+    pub fn redeliver<M: Clone + 'static>(&mut self) {
+        self.redelivery.get_or_insert_with(|| Rc::new(RefCell::new(Redelivery::new())))
+            .borrow_mut().add::<M>();
+    }
+
+    #[inline]
+    pub fn redelivery(&self) -> Option<Rc<RefCell<Redelivery>>> {
+        self.redelivery.clone()
+    }
+
+    #[inline]
+    pub fn notify_system<M>(&mut self, msg: M) -> Result<(), SendError<M>>
+        where A: Handler<M>, M: ResponseType + 'static
+    {
+        let res = self.mailbox.push_system(msg);
+        self.flags.insert(ContextFlags::MODIFIED);
+        res
+    }
+
+    #[inline]
+    pub fn add_middleware(&mut self, mw: Box<ActorMiddleware<A>>) {
+        self.mailbox.add_middleware(mw);
+    }
+
+    #[inline]
+    /// Actor name
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_ref().map(|s| s.as_str())
+    }
+
+    #[inline]
+    pub fn set_name(&mut self, name: String) {
+        self.name = Some(name);
+    }
+
+    /// Hook that is called on graceful stop, see `Supervised::snapshot()`
+    pub fn set_snapshot_hook(&mut self, hook: Box<Fn(&A)>) {
+        self.snapshot = Some(hook);
+    }
+
+    #[inline]
+    pub fn set_deadlock_detection(&mut self, threshold: Option<Duration>, break_wait: bool) {
+        self.deadlock = threshold.map(|threshold| (threshold, break_wait));
+    }
+
+    #[inline]
+    pub fn set_spin_detection(&mut self, threshold: Option<u32>) {
+        self.spin = threshold.map(SpinDetector::new);
+    }
+
+    /// Wait future context is blocked on
+    pub fn wait_queue_info(&self) -> Option<WaitInfo> {
+        self.wait.last().map(|item| WaitInfo {
+            message_type: item.message_type(),
+            waiting: item.elapsed(),
+            queued: self.wait.len() - 1,
+        })
+    }
+
+    /// Publish wait future context is blocked on to arbiter's live dump
+    fn publish_wait(&self) {
+        if let Some(ref resident) = self.resident {
+            resident.set_wait(self.wait.last().map(
+                |item| (item.message_type(), item.since(), self.wait.len() - 1)));
+        }
+    }
+
+    #[inline]
+    pub fn poll_stats(&self) -> PollStats {
+        self.spin.as_ref().map(|spin| spin.last).unwrap_or_default()
+    }
+
+    #[inline]
+    pub fn set_poll_budget(&mut self, budget: usize) {
+        self.budget = budget;
+    }
+
+    #[inline]
+    /// Stop actor when all addresses get dropped and no evented objects left
+    ///
+    /// By default this behavior is enabled.
+    pub fn set_stop_on_disconnect(&mut self, val: bool) {
+        self.stop_on_disconnect = val;
+    }
+
+    #[inline]
+    /// Hibernate actor after specified period of inactivity
+    pub fn set_hibernate_after(&mut self, dur: Option<Duration>) {
+        self.hibernate = dur;
+        self.idle = None;
+    }
+
+    #[inline]
+    /// Is actor hibernated
+    pub fn hibernated(&self) -> bool {
+        self.flags.contains(ContextFlags::HIBERNATED)
+    }
+
     #[inline]
     pub fn unsync_address(&mut self) -> Address<A> {
         self.modify();
@@ -183,7 +750,8 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
         if self.flags.intersects(ContextFlags::STOPPING | ContextFlags::STOPPED) {
             false
         } else {
-            self.mailbox.connected() || !self.items.is_empty() || !self.wait.is_empty()
+            !self.stop_on_disconnect || self.mailbox.connected() ||
+                !self.items.is_empty() || !self.wait.is_empty()
         }
     }
 
@@ -195,18 +763,162 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
     /// Restart context. Cleanup all futures, except address queue.
     #[inline]
     pub fn restart(&mut self, ctx: &mut A::Context) -> bool where A: Supervised {
-        if self.act.is_none() || !self.mailbox.connected() {
+        if !self.restartable() {
             false
         } else {
             self.flags = ContextFlags::RUNNING;
             self.wait = SmallVec::new();
+            self.publish_wait();
+            self.init = VecDeque::new();
             self.items = SmallVec::new();
+            self.spawned = 0;
+            self.overflow = VecDeque::new();
+            self.groups.clear();
+            self.requests.clear();
             self.handle = SpawnHandle::default();
+            self.idle = None;
             self.actor().restarting(ctx);
             true
         }
     }
 
+    /// Check if context could be restarted, i.e. address is connected
+    #[inline]
+    pub fn restartable(&self) -> bool {
+        self.act.is_some() && self.mailbox.connected() && !self.stop_requested()
+    }
+
+    /// Notify pending futures about actor shutdown
+    fn cancel_pending(&mut self, act: &mut A, ctx: &mut A::Context) {
+        let wait = mem::replace(&mut self.wait, SmallVec::new());
+        self.publish_wait();
+        let items = mem::replace(&mut self.items, SmallVec::new());
+        self.spawned = 0;
+        let overflow = mem::replace(&mut self.overflow, VecDeque::new());
+        self.groups.clear();
+        self.requests.clear();
+
+        for mut item in wait {
+            item.cancelled(act, ctx);
+        }
+        for (_, mut item, _) in items.into_iter().chain(overflow) {
+            item.cancelled(act, ctx);
+        }
+    }
+
+    /// Actor stops gracefully, save its state
+    fn snapshot(&self, act: &A) {
+        if let Some(ref hook) = self.snapshot {
+            hook(act);
+        }
+    }
+
+    /// Call `Actor::stopped()` and notify stop waiters
+    fn stopped(&mut self, act: &mut A, ctx: &mut A::Context) {
+        // deferred messages are never handled by stopped actor
+        for mut env in self.mailbox.take_deferred() {
+            env.dead_letter(DeadLetterReason::Stopped);
+        }
+        Actor::stopped(act, ctx);
+        for tx in self.stop_waiters.drain(..) {
+            let _ = tx.send(());
+        }
+    }
+
+    /// Receiver resolves after `Actor::stopped()` method get called
+    pub fn stop_waiter(&mut self) -> Receiver<()> {
+        let (tx, rx) = channel();
+        self.stop_waiters.push(tx);
+        rx
+    }
+
+    /// Receiver resolves after `Actor::started()` method and wait futures
+    /// registered during startup complete, it is canceled if actor
+    /// stops before that
+    pub fn start_waiter(&mut self) -> Receiver<()> {
+        let (tx, rx) = channel();
+        self.start_waiter = Some(tx);
+        rx
+    }
+
+    #[inline]
+    /// Check if arbiter requested graceful stop
+    fn stop_requested(&self) -> bool {
+        self.resident.as_ref().map(|r| r.stop_requested()).unwrap_or(false)
+    }
+
+    /// Check idle period, hibernate actor if it is expired
+    ///
+    /// Actor is idle if mailbox is empty and there are no spawned futures.
+    fn poll_idle(&mut self, act: &mut A, ctx: &mut A::Context) {
+        let dur = match self.hibernate {
+            Some(dur) => dur,
+            None => return,
+        };
+        if self.hibernated() {
+            return
+        }
+        if !self.items.is_empty() || !self.wait.is_empty() {
+            self.idle = None;
+            return
+        }
+
+        let mut idle = match self.idle.take() {
+            Some(idle) => idle,
+            None => Timeout::new(dur, Arbiter::handle()).unwrap(),
+        };
+        match idle.poll() {
+            Ok(Async::NotReady) => self.idle = Some(idle),
+            Ok(Async::Ready(_)) | Err(_) => {
+                Actor::hibernate(act, ctx);
+                self.flags.insert(ContextFlags::HIBERNATED);
+                self.wait = SmallVec::new();
+                self.publish_wait();
+                self.items = SmallVec::new();
+                self.spawned = 0;
+                self.overflow = VecDeque::new();
+                self.groups.clear();
+                self.requests.clear();
+                self.mailbox.shrink();
+            }
+        }
+    }
+
+    /// Poll most recent wait future, resolves to `true` if wait got broken
+    ///
+    /// Requests to own mailbox fail with `MailboxError::SelfCall`.
+    /// If deadlock detection is enabled, context that waits longer than
+    /// threshold while mailbox holds messages gets reported.
+    fn poll_wait(&mut self, act: &mut A, ctx: &mut A::Context) -> Async<bool> {
+        let mailbox = self.mailbox.id();
+        let item = self.wait.last_mut().unwrap();
+        if item.poll_probe(act, ctx, mailbox, false).is_ready() {
+            return Async::Ready(false)
+        }
+        let (threshold, break_wait) = match self.deadlock {
+            Some(deadlock) => deadlock,
+            None => return Async::NotReady,
+        };
+
+        let elapsed = item.elapsed();
+        if elapsed >= threshold {
+            let queued = self.mailbox.queued_types();
+            if !queued.is_empty() {
+                if item.report() {
+                    error!("Possible deadlock: actor {} waits for {} {:?}, queued messages: {}",
+                           actor_name::<A>(&self.name), item.message_type(), elapsed,
+                           queued.join(", "));
+                }
+                if break_wait {
+                    return item.poll_probe(act, ctx, mailbox, true).map(|_| true)
+                }
+                return Async::NotReady
+            }
+        }
+        item.schedule_check(threshold);
+        Async::NotReady
+    }
+
     #[inline]
     pub fn set_actor(&mut self, act: A) {
         self.act = Some(act);
@@ -214,8 +926,8 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
     }
 
     #[inline]
-    pub fn into_inner(self) -> Option<A> {
-        self.act
+    pub fn into_inner(mut self) -> Option<A> {
+        self.act.take()
     }
 
     #[inline]
@@ -223,16 +935,77 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
         self.flags.contains(ContextFlags::STARTED)
     }
 
+    /// Call `Actor::started()` on first poll
+    ///
+    /// Actor starts before poll budget is set up, so budget configured
+    /// in `started()` applies to the first poll as well.
+    fn start(&mut self, ctx: &mut A::Context) {
+        if self.flags.contains(ContextFlags::STARTED) {
+            return
+        }
+        let act: &mut A = if let Some(ref mut act) = self.act {
+            unsafe { mem::transmute(act) }
+        } else {
+            return
+        };
+        self.flags.insert(ContextFlags::STARTED);
+        if self.resident.is_none() {
+            self.resident = Arbiter::resident(actor_name::<A>(&self.name));
+        }
+        Actor::started(act, ctx);
+    }
+
     pub fn poll(&mut self, ctx: &mut A::Context) -> Poll<(), ()> {
+        pump::record_poll();
+        self.start(ctx);
+        if self.budget == 0 {
+            return self.poll_spin(ctx)
+        }
+
+        // budget is split between mailbox and spawned futures,
+        // futures start in rotating order so each one gets polled first in turn
+        if self.items.len() > 1 {
+            let item = self.items.remove(0);
+            self.items.push(item);
+        }
+        let prev = budget::begin(self.budget, self.items.len() + 1);
+        let res = self.poll_spin(ctx);
+        if budget::end(prev) {
+            // some source yielded, continue on next poll
+            task::current().notify();
+        }
+        res
+    }
+
+    fn poll_spin(&mut self, ctx: &mut A::Context) -> Poll<(), ()> {
+        if self.spin.is_none() {
+            return self.poll_actor(ctx)
+        }
+        self.progress = false;
+        let res = self.poll_actor(ctx);
+        if let Some(ref mut spin) = self.spin {
+            spin.record(self.progress, actor_name::<A>(&self.name));
+        }
+        res
+    }
+
+    fn poll_actor(&mut self, ctx: &mut A::Context) -> Poll<(), ()> {
         let act: &mut A = if let Some(ref mut act) = self.act {
             unsafe { mem::transmute(act) }
         } else {
             return Ok(Async::Ready(()))
         };
 
-        if !self.flags.contains(ContextFlags::STARTED) {
-            self.flags.insert(ContextFlags::STARTED);
-            Actor::started(act, ctx);
+        // arbiter gave up on graceful stop, queued messages become dead letters
+        if self.resident.as_ref().map(|r| r.take_abandon()).unwrap_or(false) {
+            let envelopes = self.mailbox.drain();
+            if let Some(ref resident) = self.resident {
+                resident.set_dead_letters(envelopes.len());
+            }
+            for mut env in envelopes {
+                env.dead_letter(DeadLetterReason::Stopped);
+            }
+            return Ok(Async::NotReady)
         }
 
         'outer: loop {
@@ -242,26 +1015,96 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
             // ctx.wait() always add to the back of the list
             // and we always have to check most recent future
             while !self.wait.is_empty() && !self.stopping() {
-                if let Some(item) = self.wait.last_mut() {
-                    match item.poll(act, ctx) {
-                        Async::Ready(_) => (),
-                        Async::NotReady => return Ok(Async::NotReady),
-                    }
+                match self.poll_wait(act, ctx) {
+                    Async::Ready(false) => (),
+                    Async::Ready(true) => {
+                        // deadlock is broken, let other waiting contexts
+                        // of the arbiter break their waits before mailbox is processed
+                        self.wait.pop();
+                        self.publish_wait();
+                        task::current().notify();
+                        return Ok(Async::NotReady)
+                    },
+                    Async::NotReady => {
+                        self.publish_wait();
+                        return Ok(Async::NotReady)
+                    },
                 }
                 self.wait.pop();
+                self.progress = true;
+                if self.wait.is_empty() {
+                    self.publish_wait();
+                }
             }
 
-            // process mailbox
-            self.mailbox.poll(act, ctx);
+            // initialization messages are handled before mailbox and items
+            if !self.flags.contains(ContextFlags::INITIALIZED) {
+                while !self.stopping() {
+                    if let Some(mut item) = self.init.pop_front() {
+                        let _ = item.poll(act, ctx);
+                        self.progress = true;
+                        if !self.wait.is_empty() && !self.stopping() {
+                            continue 'outer
+                        }
+                    } else {
+                        break
+                    }
+                }
+                self.init.clear();
+                self.flags.insert(ContextFlags::INITIALIZED);
+
+                // startup is complete, wait futures are resolved at this point
+                if let Some(tx) = self.start_waiter.take() {
+                    if !self.stopping() {
+                        let _ = tx.send(());
+                    }
+                }
+            }
+
+            // process mailbox, hibernated actor is restored before it handles
+            // next message. until then mailbox is not polled, task stays
+            // registered from the poll that preceded hibernation
+            budget::next_source();
+            if self.hibernated() && self.mailbox.has_pending() {
+                self.flags.remove(ContextFlags::HIBERNATED);
+                Actor::unhibernate(act, ctx);
+            }
+            if !self.hibernated() && self.mailbox.poll(act, ctx, &self.exclusive) {
+                self.progress = true;
+                if let (Some(dur), Some(idle)) = (self.hibernate, self.idle.as_mut()) {
+                    idle.reset(Instant::now() + dur);
+                }
+            }
             if !self.wait.is_empty() && !self.stopping() {
                 continue
             }
 
+            // arbiter requested graceful stop. mailbox poll returns early
+            // while exclusive response holds the mailbox, on deferred
+            // messages and once budget is exhausted, so request stays in
+            // place until nothing is left to handle
+            if !self.mailbox.has_pending() &&
+                self.resident.as_ref().map(|r| r.take_stop()).unwrap_or(false)
+            {
+                self.stop();
+            }
+
             // process items
+            let exclusive = self.exclusive.get() != 0;
             let mut idx = 0;
             while idx < self.items.len() && !self.stopping() {
                 self.curr_handle = self.items[idx].0;
-                match self.items[idx].1.poll(act, ctx) {
+                budget::next_source();
+                let res = self.items[idx].1.poll(act, ctx);
+                self.curr_handle = SpawnHandle::default();
+
+                // future cancelled itself
+                let res = if mem::replace(&mut self.curr_cancelled, false) {
+                    Ok(Async::Ready(()))
+                } else {
+                    res
+                };
+                match res {
                     Ok(Async::NotReady) => {
                         // item scheduled wait future
                         if !self.wait.is_empty() && !self.stopping() {
@@ -278,7 +1121,8 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
                         }
                     },
                     Ok(Async::Ready(())) | Err(_) => {
-                        self.items.swap_remove(idx);
+                        self.progress = true;
+                        self.remove_item(idx);
                         // one of the items scheduled wait future
                         if !self.wait.is_empty() && !self.stopping() {
                             continue 'outer
@@ -286,7 +1130,6 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
                     },
                 }
             }
-            self.curr_handle = SpawnHandle::default();
 
             // ContextFlags::MODIFIED indicates that new IO item has
             // been added during poll process
@@ -294,18 +1137,33 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
                 continue
             }
 
+            // exclusive response resolved, mailbox is open again
+            if exclusive && self.exclusive.get() == 0 {
+                continue
+            }
+
             // check state
             if self.flags.contains(ContextFlags::RUNNING) {
                 // possible stop condition
-                if !self.alive() && Actor::stopping(act, ctx) {
-                    self.flags = ContextFlags::STOPPED;
-                    Actor::stopped(act, ctx);
-                    return Ok(Async::Ready(()))
+                if !self.alive() {
+                    if Actor::stopping(act, ctx) {
+                        self.flags = ContextFlags::STOPPED;
+                        self.snapshot(act);
+                        self.cancel_pending(act, ctx);
+                        self.stopped(act, ctx);
+                        return Ok(Async::Ready(()))
+                    } else if self.alive() {
+                        // actor got new address or evented object in `stopping()`,
+                        // messages sent to new address must be processed
+                        continue
+                    }
                 }
             } else if self.flags.contains(ContextFlags::STOPPING) {
                 if Actor::stopping(act, ctx) {
                     self.flags = ContextFlags::STOPPED;
-                    Actor::stopped(act, ctx);
+                    self.snapshot(act);
+                    self.cancel_pending(act, ctx);
+                    self.stopped(act, ctx);
                     return Ok(Async::Ready(()))
                 } else {
                     self.flags.remove(ContextFlags::STOPPING);
@@ -313,10 +1171,14 @@ impl<A> ContextImpl<A> where A: Actor, A::Context: AsyncContext<A>
                     continue
                 }
             } else if self.flags.contains(ContextFlags::STOPPED) {
-                Actor::stopped(act, ctx);
+                self.cancel_pending(act, ctx);
+                self.stopped(act, ctx);
                 return Ok(Async::Ready(()))
             }
 
+            if self.flags.contains(ContextFlags::RUNNING) {
+                self.poll_idle(act, ctx);
+            }
             return Ok(Async::NotReady)
         }
     }
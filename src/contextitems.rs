@@ -1,35 +1,235 @@
+use std::{cmp, mem};
+use std::rc::Rc;
+use std::cell::Cell;
 use std::marker::PhantomData;
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use futures::{Async, Future, Poll, Stream};
-use tokio_core::reactor::Timeout;
+use tokio_core::reactor::{Interval, Timeout};
 
 use fut::ActorFuture;
 use arbiter::Arbiter;
+use budget;
+use pump::Deadline;
 use actor::{Actor, ActorContext, AsyncContext};
 use handler::{Handler, MessageResponse, ResponseType};
+use message::MailboxError;
+use msgregistry::MessageRegistry;
 
 
-pub(crate) struct ActorWaitItem<A: Actor>(Box<ActorFuture<Item=(), Error=(), Actor=A>>);
+pub(crate) struct ActorWaitItem<A: Actor> {
+    fut: Box<ActorFuture<Item=(), Error=(), Actor=A>>,
+    since: Instant,
+    message_type: Option<&'static str>,
+    reported: bool,
+    timer: Option<Timeout>,
+}
 
 impl<A> ActorWaitItem<A> where A: Actor, A::Context: ActorContext + AsyncContext<A> {
 
     #[inline]
     pub fn new<F>(fut: F) -> Self where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static {
-        ActorWaitItem(Box::new(fut))
+        ActorWaitItem {
+            fut: Box::new(fut),
+            since: Instant::now(),
+            message_type: None,
+            reported: false,
+            timer: None,
+        }
     }
 
     pub fn poll(&mut self, act: &mut A, ctx: &mut A::Context) -> Async<()> {
-        match self.0.poll(act, ctx) {
+        match self.fut.poll(act, ctx) {
             Ok(Async::NotReady) => {
                 if ctx.state().alive() {
                     Async::NotReady
                 } else {
+                    self.fut.cancelled(act, ctx);
                     Async::Ready(())
                 }
             },
             Ok(Async::Ready(_)) | Err(_) => Async::Ready(()),
         }
     }
+
+    /// Poll future and record message type of the pending request.
+    ///
+    /// `mailbox` is identity of waiting context's mailbox, requests to it
+    /// fail with `MailboxError::SelfCall`. With `break_wait` pending
+    /// requests fail with `MailboxError::Timeout`.
+    pub fn poll_probe(&mut self, act: &mut A, ctx: &mut A::Context,
+                      mailbox: usize, break_wait: bool) -> Async<()> {
+        let prev = WAIT_PROBE.with(
+            |probe| probe.replace(Some(WaitProbe{message_type: None,
+                                                 mailbox: mailbox,
+                                                 break_wait: break_wait})));
+        let res = self.poll(act, ctx);
+        let probe = WAIT_PROBE.with(|probe| probe.replace(prev));
+        if let Some(tp) = probe.and_then(|probe| probe.message_type) {
+            self.message_type = Some(tp);
+        }
+        res
+    }
+
+    /// Time spent in wait
+    pub fn elapsed(&self) -> Duration {
+        self.since.elapsed()
+    }
+
+    /// Time when wait started
+    pub fn since(&self) -> Instant {
+        self.since
+    }
+
+    /// Message type of the request future waits for, if known
+    pub fn message_type(&self) -> &'static str {
+        self.message_type.unwrap_or("<unknown>")
+    }
+
+    /// Mark item as reported, returns `false` if it is reported already
+    pub fn report(&mut self) -> bool {
+        !mem::replace(&mut self.reported, true)
+    }
+
+    /// Wake up context once `threshold` expires, following checks
+    /// are done every `threshold` period.
+    pub fn schedule_check(&mut self, threshold: Duration) {
+        if let Some(ref mut timer) = self.timer {
+            if let Ok(Async::NotReady) = timer.poll() {
+                return
+            }
+        }
+        let deadline = cmp::max(self.since + threshold, Instant::now() + threshold);
+        let mut timer = Timeout::new_at(deadline, Arbiter::handle()).unwrap();
+        let _ = timer.poll();
+        self.timer = Some(timer);
+    }
+
+    pub fn cancelled(&mut self, act: &mut A, ctx: &mut A::Context) {
+        self.fut.cancelled(act, ctx)
+    }
+}
+
+#[derive(Clone, Copy)]
+struct WaitProbe {
+    message_type: Option<&'static str>,
+    mailbox: usize,
+    break_wait: bool,
+}
+
+thread_local!(static WAIT_PROBE: Cell<Option<WaitProbe>> = Cell::new(None));
+
+/// Called by request futures that are not ready yet, `mailbox` is
+/// identity of request's target mailbox.
+///
+/// Records wire name of the message for deadlock report of waiting context,
+/// returns error if request has to fail.
+pub(crate) fn wait_pending<M: 'static>(mailbox: usize) -> Option<MailboxError> {
+    WAIT_PROBE.with(|probe| match probe.get() {
+        Some(mut p) => {
+            p.message_type = Some(MessageRegistry::name_of::<M>().unwrap_or("<unknown>"));
+            probe.set(Some(p));
+            if p.mailbox == mailbox {
+                Some(MailboxError::SelfCall)
+            } else if p.break_wait {
+                Some(MailboxError::Timeout)
+            } else {
+                None
+            }
+        },
+        None => None,
+    })
+}
+
+
+/// Wait future with deadline, see `AsyncContext::wait_timeout()`
+pub(crate) struct ActorWaitTimeoutItem<A: Actor, F, T> {
+    fut: Option<F>,
+    on_timeout: Option<T>,
+    timeout: Timeout,
+    act: PhantomData<A>,
+}
+
+impl<A: Actor, F, T> ActorWaitTimeoutItem<A, F, T> {
+    pub fn new(fut: F, timeout: Duration, on_timeout: T) -> Self {
+        ActorWaitTimeoutItem {
+            fut: Some(fut),
+            on_timeout: Some(on_timeout),
+            timeout: Timeout::new(timeout, Arbiter::handle()).unwrap(),
+            act: PhantomData,
+        }
+    }
+}
+
+impl<A, F, T> ActorFuture for ActorWaitTimeoutItem<A, F, T>
+    where A: Actor,
+          F: ActorFuture<Item=(), Error=(), Actor=A>,
+          T: FnOnce(&mut A, &mut A::Context)
+{
+    type Item = ();
+    type Error = ();
+    type Actor = A;
+
+    fn poll(&mut self, act: &mut A, ctx: &mut A::Context) -> Poll<(), ()> {
+        if let Some(ref mut fut) = self.fut {
+            match fut.poll(act, ctx) {
+                Ok(Async::NotReady) => (),
+                Ok(Async::Ready(_)) | Err(_) => return Ok(Async::Ready(())),
+            }
+        }
+        match self.timeout.poll() {
+            Ok(Async::NotReady) => Ok(Async::NotReady),
+            Ok(Async::Ready(_)) | Err(_) => {
+                // pending future is dropped, its requests get cancelled
+                self.fut.take();
+                if let Some(on_timeout) = self.on_timeout.take() {
+                    on_timeout(act, ctx);
+                }
+                Ok(Async::Ready(()))
+            }
+        }
+    }
+
+    fn cancelled(&mut self, act: &mut A, ctx: &mut A::Context) {
+        if let Some(ref mut fut) = self.fut {
+            fut.cancelled(act, ctx)
+        }
+    }
+}
+
+
+/// Future that holds context's mailbox closed until it is dropped
+pub(crate) struct ActorExclusiveItem<A: Actor> {
+    fut: Box<ActorFuture<Item=(), Error=(), Actor=A>>,
+    lock: Rc<Cell<usize>>,
+}
+
+impl<A: Actor> ActorExclusiveItem<A> {
+    pub fn new<F>(fut: F, lock: Rc<Cell<usize>>) -> Self
+        where F: ActorFuture<Item=(), Error=(), Actor=A> + 'static
+    {
+        lock.set(lock.get() + 1);
+        ActorExclusiveItem{fut: Box::new(fut), lock: lock}
+    }
+}
+
+impl<A: Actor> Drop for ActorExclusiveItem<A> {
+    fn drop(&mut self) {
+        self.lock.set(self.lock.get() - 1);
+    }
+}
+
+impl<A: Actor> ActorFuture for ActorExclusiveItem<A> {
+    type Item = ();
+    type Error = ();
+    type Actor = A;
+
+    fn poll(&mut self, act: &mut A, ctx: &mut A::Context) -> Poll<(), ()> {
+        self.fut.poll(act, ctx)
+    }
+
+    fn cancelled(&mut self, act: &mut A, ctx: &mut A::Context) {
+        self.fut.cancelled(act, ctx)
+    }
 }
 
 
@@ -78,6 +278,7 @@ pub(crate)
 struct ActorDelayedMessageItem<A, M> where A: Actor, M: ResponseType {
     msg: Option<M>,
     timeout: Timeout,
+    _deadline: Deadline,
     act: PhantomData<A>,
     m: PhantomData<M>,
 }
@@ -87,6 +288,7 @@ impl<A, M> ActorDelayedMessageItem<A, M> where A: Actor, M: ResponseType {
         ActorDelayedMessageItem {
             msg: Some(msg),
             timeout: Timeout::new(timeout, Arbiter::handle()).unwrap(),
+            _deadline: Deadline::new(Instant::now() + timeout),
             act: PhantomData,
             m: PhantomData,
         }
@@ -113,6 +315,56 @@ impl<A, M> ActorFuture for ActorDelayedMessageItem<A, M>
     }
 }
 
+pub(crate)
+struct ActorIntervalMessageItem<A, M> where A: Actor, M: ResponseType + Clone {
+    msg: M,
+    interval: Interval,
+    every: Duration,
+    deadline: Deadline,
+    act: PhantomData<A>,
+}
+
+impl<A, M> ActorIntervalMessageItem<A, M> where A: Actor, M: ResponseType + Clone {
+    pub fn new(msg: M, interval: Duration) -> Self {
+        ActorIntervalMessageItem {
+            msg: msg,
+            interval: Interval::new(interval, Arbiter::handle()).unwrap(),
+            every: interval,
+            deadline: Deadline::new(Instant::now() + interval),
+            act: PhantomData,
+        }
+    }
+}
+
+impl<A, M> ActorFuture for ActorIntervalMessageItem<A, M>
+    where A: Actor + Handler<M>, A::Context: AsyncContext<A>,
+          M: ResponseType + Clone + 'static,
+{
+    type Item = ();
+    type Error = ();
+    type Actor = A;
+
+    fn poll(&mut self, act: &mut A, ctx: &mut A::Context) -> Poll<Self::Item, Self::Error> {
+        loop {
+            if ctx.waiting() || !budget::has_budget() {
+                return Ok(Async::NotReady)
+            }
+
+            match self.interval.poll() {
+                Ok(Async::Ready(_)) => {
+                    let next = self.deadline.get() + self.every;
+                    self.deadline.reset(next);
+                    let fut = A::handle(act, self.msg.clone(), ctx);
+                    fut.handle::<()>(ctx, None);
+                    budget::consume();
+                }
+                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                Err(_) => unreachable!(),
+            }
+        }
+    }
+}
+
 pub(crate)
 struct ActorMessageItem<A, M> where A: Actor, M: ResponseType {
     msg: Option<M>,
@@ -163,13 +415,16 @@ impl<A, M: 'static, S> ActorFuture for ActorMessageStreamItem<A, M, S>
 
     fn poll(&mut self, act: &mut A, ctx: &mut A::Context) -> Poll<Self::Item, Self::Error> {
         loop {
+            // do not poll stream while context is waiting
+            if ctx.waiting() || !budget::has_budget() {
+                return Ok(Async::NotReady)
+            }
+
             match self.stream.poll() {
                 Ok(Async::Ready(Some(msg))) => {
                     let fut = Handler::handle(act, msg, ctx);
                     fut.handle::<()>(ctx, None);
-                    if ctx.waiting() {
-                        return Ok(Async::NotReady)
-                    }
+                    budget::consume();
                 }
                 Ok(Async::Ready(None)) => return Ok(Async::Ready(())),
                 Ok(Async::NotReady) => return Ok(Async::NotReady),
@@ -0,0 +1,135 @@
+//! Typed actor facade, see `actor_facade!`
+
+#[doc(hidden)]
+pub use futures::Future;
+
+/// Generate messages and typed address methods for actor's operations
+///
+/// Every method declares one message type. Macro generates message struct
+/// with public fields named after method arguments, `ResponseType`
+/// implementation and trait with a method per message. Trait is implemented
+/// for `Address<A>` and `SyncAddress<A>`, actor has to handle all messages.
+///
+/// Method with return type sends request, returned future resolves with
+/// `Result<T, ()>` and it fails with `MailboxError` if actor is gone.
+/// Future type is trait's associated type named after the message.
+/// Method without return type sends message with `do_send()`.
+///
+/// ```rust,ignore
+/// actor_facade! {
+///     /// Operations of the chat server
+///     pub trait ChatServerApi for ChatServer {
+///         /// List of available rooms
+///         fn list_rooms() -> Vec<String> as ListRooms;
+///         /// Join room, create room if it does not exist
+///         fn join(id: usize, name: String) as Join;
+///     }
+/// }
+///
+/// impl Handler<ListRooms> for ChatServer { ... }
+/// impl Handler<Join> for ChatServer { ... }
+///
+/// addr.join(id, "main".to_owned());
+/// addr.list_rooms().then(|res| ...)
+/// ```
+#[macro_export]
+macro_rules! actor_facade {
+    ($(#[$meta:meta])* pub trait $api:ident for $actor:ty { $($body:tt)* }) => {
+        actor_facade!(@parse [$(#[$meta])*] [pub] $api $actor [] [] $($body)*);
+    };
+    ($(#[$meta:meta])* trait $api:ident for $actor:ty { $($body:tt)* }) => {
+        actor_facade!(@parse [$(#[$meta])*] [] $api $actor [] [] $($body)*);
+    };
+
+    // request, method with return type
+    (@parse $meta:tt $vis:tt $api:ident $actor:ty [$($req:tt)*] [$($send:tt)*]
+     $(#[$m:meta])* fn $name:ident($($arg:ident: $ty:ty),*) -> $ret:ty as $msg:ident;
+     $($rest:tt)*) => {
+        actor_facade!(@parse $meta $vis $api $actor
+                      [$($req)* {[$(#[$m])*] $vis $name $msg $ret [$($arg: $ty),*]}]
+                      [$($send)*] $($rest)*);
+    };
+    // fire-and-forget message, method without return type
+    (@parse $meta:tt $vis:tt $api:ident $actor:ty [$($req:tt)*] [$($send:tt)*]
+     $(#[$m:meta])* fn $name:ident($($arg:ident: $ty:ty),*) as $msg:ident;
+     $($rest:tt)*) => {
+        actor_facade!(@parse $meta $vis $api $actor
+                      [$($req)*]
+                      [$($send)* {[$(#[$m])*] $vis $name $msg () [$($arg: $ty),*]}]
+                      $($rest)*);
+    };
+
+    (@parse [$($meta:tt)*] [$($vis:tt)*] $api:ident $actor:ty
+     [$({[$($rm:tt)*] [$($rv:tt)*] $rname:ident $rmsg:ident $rret:ty
+         [$($rarg:ident: $rty:ty),*]})*]
+     [$({[$($sm:tt)*] [$($sv:tt)*] $sname:ident $smsg:ident $sret:ty
+         [$($sarg:ident: $sty:ty),*]})*]) => {
+        $(
+            $($rm)*
+            $($rv)* struct $rmsg { $(pub $rarg: $rty),* }
+
+            impl $crate::ResponseType for $rmsg {
+                type Item = $rret;
+                type Error = ();
+            }
+        )*
+        $(
+            $($sm)*
+            $($sv)* struct $smsg { $(pub $sarg: $sty),* }
+
+            impl $crate::ResponseType for $smsg {
+                type Item = $sret;
+                type Error = ();
+            }
+        )*
+
+        $($meta)*
+        $($vis)* trait $api {
+            $(
+                type $rmsg: $crate::facade::Future<
+                    Item=::std::result::Result<$rret, ()>, Error=$crate::MailboxError> + 'static;
+
+                $($rm)*
+                fn $rname(&self $(, $rarg: $rty)*) -> Self::$rmsg;
+            )*
+            $(
+                $($sm)*
+                fn $sname(&self $(, $sarg: $sty)*);
+            )*
+        }
+
+        impl $api for $crate::SyncAddress<$actor>
+            where $actor: $($crate::Handler<$rmsg> +)* $($crate::Handler<$smsg> +)* $crate::Actor
+        {
+            $(
+                type $rmsg = $crate::dev::RequestFut<$actor, $rmsg>;
+
+                fn $rname(&self $(, $rarg: $rty)*) -> Self::$rmsg {
+                    self.call_fut($rmsg { $($rarg: $rarg),* })
+                }
+            )*
+            $(
+                fn $sname(&self $(, $sarg: $sty)*) {
+                    self.do_send($smsg { $($sarg: $sarg),* })
+                }
+            )*
+        }
+
+        impl $api for $crate::Address<$actor>
+            where $actor: $($crate::Handler<$rmsg> +)* $($crate::Handler<$smsg> +)* $crate::Actor
+        {
+            $(
+                type $rmsg = $crate::dev::LocalFutRequest<$actor, $rmsg>;
+
+                fn $rname(&self $(, $rarg: $rty)*) -> Self::$rmsg {
+                    self.call_fut($rmsg { $($rarg: $rarg),* })
+                }
+            )*
+            $(
+                fn $sname(&self $(, $sarg: $sty)*) {
+                    self.do_send($smsg { $($sarg: $sarg),* })
+                }
+            )*
+        }
+    };
+}
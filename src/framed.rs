@@ -1,16 +1,23 @@
-use std::mem;
+use std::{mem, thread};
 use std::rc::Rc;
+use std::sync::Arc;
 use std::marker::PhantomData;
-use std::cell::UnsafeCell;
+use std::cell::{RefCell, UnsafeCell};
 use std::collections::VecDeque;
+use std::time::{Duration, Instant};
 
-use futures::{Async, AsyncSink, Poll, Sink, Stream};
+use futures::{future, Async, AsyncSink, Poll, Sink, Stream};
+use futures::executor::{self, Notify, NotifyHandle};
+use futures::task::{self, Task};
 use futures::unsync::oneshot::{channel, Sender as UnsyncSender};
 use tokio_io::{AsyncRead, AsyncWrite};
 use tokio_io::codec::{Framed, Encoder, Decoder};
 
+use abort;
 use fut::ActorFuture;
-use actor::{Actor, AsyncContext};
+use actor::{Actor, ActorContext, AsyncContext, SpawnHandle};
+use handler::{Handler, ResponseType};
+use reconnect::DisconnectReason;
 use utils::Drain;
 use stream::StreamHandler;
 
@@ -19,6 +26,10 @@ pub enum FramedError<Codec: Encoder + Decoder> {
     SinkError(<Codec as Encoder>::Error),
 }
 
+/// Time limit of best-effort flush after actor's panic,
+/// see `FramedWriter::set_flush_on_failure()`
+const FAILURE_FLUSH_TIMEOUT: Duration = Duration::from_millis(100);
+
 bitflags! {
     struct FramedFlags: u8 {
         const STARTED = 0b0000_0001;
@@ -29,6 +40,49 @@ bitflags! {
     }
 }
 
+/// Pauses and resumes reading of `FramedReader`
+///
+/// Paused reader does not read frames, pending writes of the writer
+/// continue. Control is shared by reader, writer and clones of control.
+#[derive(Clone, Default)]
+pub struct ReaderControl(Rc<RefCell<ReadPause>>);
+
+#[derive(Default)]
+struct ReadPause {
+    paused: bool,
+    task: Option<Task>,
+}
+
+impl ReaderControl {
+    /// Stop reading frames
+    pub fn pause(&self) {
+        self.0.borrow_mut().paused = true;
+    }
+
+    /// Resume reading frames
+    pub fn resume(&self) {
+        let mut pause = self.0.borrow_mut();
+        pause.paused = false;
+        if let Some(task) = pause.task.take() {
+            task.notify();
+        }
+    }
+
+    /// Check if reading is paused
+    pub fn is_paused(&self) -> bool {
+        self.0.borrow().paused
+    }
+
+    /// Check if reading is paused, current task is notified on resume
+    fn poll_paused(&self) -> bool {
+        let mut pause = self.0.borrow_mut();
+        if pause.paused {
+            pause.task = Some(task::current());
+        }
+        pause.paused
+    }
+}
+
 /// Framed type wrapper, that simplify handling framed objects with actor
 pub struct FramedReader<Io, Codec>
     where Io: AsyncRead + AsyncWrite, Codec: Encoder + Decoder
@@ -36,6 +90,27 @@ pub struct FramedReader<Io, Codec>
     inner: Rc<UnsafeCell<InnerActorFramedCell<Io, Codec>>>,
 }
 
+/// Reader half of linked framed pair, see `FramedReader::wrap_linked()`
+pub struct LinkedFramedReader<Io, Codec>
+    where Io: AsyncRead + AsyncWrite, Codec: Encoder + Decoder
+{
+    reader: FramedReader<Io, Codec>,
+    stop: bool,
+}
+
+/// Connection of linked framed pair is closed
+///
+/// Pending frames of the writer are flushed at this point and framed object
+/// is shut down.
+pub struct ConnectionClosed<Codec: Encoder + Decoder> {
+    pub reason: DisconnectReason<Codec>,
+}
+
+impl<Codec: Encoder + Decoder> ResponseType for ConnectionClosed<Codec> {
+    type Item = ();
+    type Error = ();
+}
+
 /// Framed sink type wrapper, that simplify handling framed objects with actor
 pub struct FramedWriter<Io, Codec>
     where Io: AsyncRead + AsyncWrite, Codec: Encoder + Decoder
@@ -59,6 +134,8 @@ struct InnerActorFramedCell<Io, Codec>
     framed: Option<Framed<Io, Codec>>,
     sink_items: VecDeque<<Codec as Encoder>::Item>,
     error: Option<<Codec as Encoder>::Error>,
+    flush_on_failure: bool,
+    control: ReaderControl,
 }
 
 impl<Io, Codec> FramedReader<Io, Codec>
@@ -72,10 +149,68 @@ impl<Io, Codec> FramedReader<Io, Codec>
                 framed: Some(framed),
                 sink_items: VecDeque::new(),
                 error: None,
+                flush_on_failure: false,
+                control: ReaderControl::default(),
             }));
 
         (FramedReader{inner: Rc::clone(&inner)}, FramedWriter{inner: inner})
     }
+
+    /// Control that pauses and resumes reading
+    ///
+    /// ```rust,ignore
+    /// let (reader, writer) = FramedReader::wrap(stream.framed(ChatCodec));
+    /// let control = reader.control();
+    /// ctx.add_stream(reader);
+    /// ```
+    pub fn control(&self) -> ReaderControl {
+        unsafe{ &*self.inner.get() }.control.clone()
+    }
+
+    /// Wrap framed object into linked reader and writer pair
+    ///
+    /// Once read stream finishes or fails, pending frames of the writer get
+    /// flushed and framed object gets shut down. After that actor receives
+    /// single `ConnectionClosed` message and stops, see
+    /// `LinkedFramedReader::stop_on_close()`. `StreamHandler::error()` and
+    /// `StreamHandler::finished()` methods are not called for linked reader.
+    ///
+    /// ```rust,ignore
+    /// let (reader, writer) = FramedReader::wrap_linked(stream.framed(ChatCodec));
+    /// reader.start(ctx);
+    /// ```
+    pub fn wrap_linked(framed: Framed<Io, Codec>)
+                       -> (LinkedFramedReader<Io, Codec>, FramedWriter<Io, Codec>)
+    {
+        let (reader, writer) = FramedReader::wrap(framed);
+        (LinkedFramedReader{reader: reader, stop: true}, writer)
+    }
+}
+
+impl<Io, Codec> LinkedFramedReader<Io, Codec>
+    where Io: AsyncRead + AsyncWrite + 'static, Codec: Encoder + Decoder + 'static
+{
+    /// Stop actor after `ConnectionClosed` message, enabled by default
+    pub fn stop_on_close(mut self, stop: bool) -> Self {
+        self.stop = stop;
+        self
+    }
+
+    /// Control that pauses and resumes reading
+    pub fn control(&self) -> ReaderControl {
+        self.reader.control()
+    }
+
+    /// Start reading frames, reader runs within actor's context
+    pub fn start<A, T>(self, ctx: &mut T) -> SpawnHandle
+        where A: Actor<Context=T>
+                 + StreamHandler<<Codec as Decoder>::Item, FramedError<Codec>>
+                 + Handler<ConnectionClosed<Codec>, Result=()>,
+              T: AsyncContext<A>,
+    {
+        ctx.spawn_internal(LinkedFramedFut{
+            reader: self.reader, stop: self.stop, reason: None, act: PhantomData})
+    }
 }
 
 impl<Io, Codec> FramedWriter<Io, Codec>
@@ -103,6 +238,14 @@ impl<Io, Codec> FramedWriter<Io, Codec>
         self.as_ref().flags.contains(FramedFlags::STREAM_CLOSED | FramedFlags::SINK_CLOSED)
     }
 
+    /// Flush buffered frames if actor's thread panics, disabled by default
+    ///
+    /// Flush is best-effort and limited by short timeout, framed object
+    /// gets shut down afterwards.
+    pub fn set_flush_on_failure(&mut self, flush: bool) {
+        self.as_mut().flush_on_failure = flush && !abort::recovery_disabled("flush on failure");
+    }
+
     /// Send item to a sink.
     pub fn send(&mut self, msg: <Codec as Encoder>::Item) {
         let inner = self.as_mut();
@@ -142,6 +285,11 @@ impl<Io, Codec> FramedWriter<Io, Codec>
     pub fn take(&mut self) -> Option<Framed<Io, Codec>> {
         self.as_mut().framed.take()
     }
+
+    /// Control that pauses and resumes reading of the reader half
+    pub fn reader_control(&self) -> ReaderControl {
+        self.as_ref().control.clone()
+    }
 }
 
 impl<Io, Codec> Stream for FramedReader<Io, Codec>
@@ -168,15 +316,17 @@ impl<Io, Codec> Stream for FramedReader<Io, Codec>
 
         // check framed stream
         if !inner.flags.intersects(FramedFlags::CLOSING | FramedFlags::STREAM_CLOSED) {
-            match framed.poll() {
-                Ok(Async::NotReady) => (),
-                Ok(Async::Ready(Some(msg))) => return Ok(Async::Ready(Some(msg))),
-                Ok(Async::Ready(None)) => {
-                    inner.flags |= FramedFlags::SINK_CLOSED | FramedFlags::STREAM_CLOSED;
-                }
-                Err(err) => {
-                    inner.flags |= FramedFlags::SINK_CLOSED | FramedFlags::STREAM_CLOSED;
-                    return Err(FramedError::StreamError(err));
+            if !inner.control.poll_paused() {
+                match framed.poll() {
+                    Ok(Async::NotReady) => (),
+                    Ok(Async::Ready(Some(msg))) => return Ok(Async::Ready(Some(msg))),
+                    Ok(Async::Ready(None)) => {
+                        inner.flags |= FramedFlags::SINK_CLOSED | FramedFlags::STREAM_CLOSED;
+                    }
+                    Err(err) => {
+                        inner.flags |= FramedFlags::SINK_CLOSED | FramedFlags::STREAM_CLOSED;
+                        return Err(FramedError::StreamError(err));
+                    }
                 }
             }
 
@@ -238,6 +388,71 @@ impl<Io, Codec> Stream for FramedReader<Io, Codec>
     }
 }
 
+impl<Io, Codec> InnerActorFramedCell<Io, Codec>
+    where Io: AsyncRead + AsyncWrite, Codec: Encoder + Decoder
+{
+    // Write pending items and shut framed object down
+    fn poll_close(&mut self) -> Poll<(), <Codec as Encoder>::Error> {
+        if let Some(ref mut framed) = self.framed {
+            while let Some(item) = self.sink_items.pop_front() {
+                if let AsyncSink::NotReady(item) = framed.start_send(item)? {
+                    self.sink_items.push_front(item);
+                    return Ok(Async::NotReady)
+                }
+            }
+            try_ready!(framed.close());
+        }
+        Ok(Async::Ready(()))
+    }
+}
+
+impl<Io, Codec> Drop for InnerActorFramedCell<Io, Codec>
+    where Io: AsyncRead + AsyncWrite, Codec: Encoder + Decoder
+{
+    fn drop(&mut self) {
+        if self.flush_on_failure && thread::panicking() {
+            if let Some(framed) = self.framed.take() {
+                let items = mem::replace(&mut self.sink_items, VecDeque::new());
+                flush_and_close(framed, items);
+            }
+        }
+    }
+}
+
+struct NoopNotify;
+
+impl Notify for NoopNotify {
+    fn notify(&self, _: usize) {}
+}
+
+/// Write pending items and shut framed object down, gives up after `FAILURE_FLUSH_TIMEOUT`
+///
+/// Event loop is not available at this point, so sink is polled
+/// in place until it completes.
+fn flush_and_close<Io, Codec>(mut framed: Framed<Io, Codec>,
+                              mut items: VecDeque<<Codec as Encoder>::Item>)
+    where Io: AsyncRead + AsyncWrite, Codec: Encoder + Decoder
+{
+    let deadline = Instant::now() + FAILURE_FLUSH_TIMEOUT;
+    let mut task = executor::spawn(future::poll_fn(move || {
+        while let Some(item) = items.pop_front() {
+            if let AsyncSink::NotReady(item) = framed.start_send(item)? {
+                items.push_front(item);
+                return Ok(Async::NotReady)
+            }
+        }
+        framed.close()
+    }));
+
+    let notify = NotifyHandle::from(Arc::new(NoopNotify));
+    while let Ok(Async::NotReady) = task.poll_future_notify(&notify, 0) {
+        if Instant::now() >= deadline {
+            break
+        }
+        thread::sleep(Duration::from_millis(1));
+    }
+}
+
 impl<A, Io, Codec> ActorFuture for FramedDrain<A, Io, Codec>
     where A: StreamHandler<<Codec as Decoder>::Item, FramedError<Codec>>,
           A::Context: AsyncContext<A>,
@@ -309,10 +524,71 @@ impl<A, Io, Codec> ActorFuture for FramedDrain<A, Io, Codec>
     }
 }
 
+struct LinkedFramedFut<A, Io, Codec>
+    where Io: AsyncRead + AsyncWrite, Codec: Encoder + Decoder
+{
+    reader: FramedReader<Io, Codec>,
+    stop: bool,
+    reason: Option<DisconnectReason<Codec>>,
+    act: PhantomData<A>,
+}
+
+impl<A, Io, Codec> ActorFuture for LinkedFramedFut<A, Io, Codec>
+    where A: StreamHandler<<Codec as Decoder>::Item, FramedError<Codec>>
+             + Handler<ConnectionClosed<Codec>, Result=()>,
+          A::Context: AsyncContext<A>,
+          Io: AsyncRead + AsyncWrite,
+          Codec: Encoder + Decoder,
+{
+    type Item = ();
+    type Error = ();
+    type Actor = A;
+
+    fn poll(&mut self, act: &mut A, ctx: &mut A::Context) -> Poll<(), ()> {
+        while self.reason.is_none() {
+            // do not read while context is waiting, same as `add_stream()`
+            if ctx.waiting() {
+                return Ok(Async::NotReady)
+            }
+            match self.reader.poll() {
+                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                Ok(Async::Ready(Some(item))) =>
+                    <A as StreamHandler<_, _>>::handle(act, item, ctx),
+                Ok(Async::Ready(None)) => self.reason = Some(DisconnectReason::Closed),
+                Err(err) => self.reason = Some(DisconnectReason::Error(err)),
+            }
+        }
+
+        // flush writer, then shut framed object down
+        let inner = unsafe{ &mut *self.reader.inner.get() };
+        match inner.poll_close() {
+            Ok(Async::NotReady) => return Ok(Async::NotReady),
+            Ok(Async::Ready(())) => (),
+            Err(err) => {
+                if let Some(DisconnectReason::Closed) = self.reason {
+                    self.reason = Some(DisconnectReason::Error(FramedError::SinkError(err)));
+                }
+            }
+        }
+        inner.flags |= FramedFlags::SINK_CLOSED | FramedFlags::STREAM_CLOSED;
+        inner.framed.take();
+        inner.sink_items.clear();
+
+        let reason = self.reason.take().unwrap();
+        <A as Handler<ConnectionClosed<Codec>>>::handle(
+            act, ConnectionClosed{reason: reason}, ctx);
+        if self.stop {
+            ctx.stop();
+        }
+        Ok(Async::Ready(()))
+    }
+}
+
 #[cfg(test)]
 mod tests {
     use super::*;
     use std::{cmp, io};
+    use std::sync::Mutex;
     use bytes::{Bytes, BytesMut};
     use futures::Future;
     use tokio_io::{AsyncWrite, AsyncRead};
@@ -389,6 +665,45 @@ mod tests {
         }
     }
 
+    /// Write half of in-memory duplex, peer side observes written data
+    #[derive(Default)]
+    struct Peer {
+        data: Vec<u8>,
+        shutdown: bool,
+        eof: bool,
+    }
+
+    struct Duplex(Arc<Mutex<Peer>>);
+
+    impl AsyncRead for Duplex {}
+    impl io::Read for Duplex {
+        fn read(&mut self, _: &mut [u8]) -> Result<usize, io::Error> {
+            if self.0.lock().unwrap().eof {
+                Ok(0)
+            } else {
+                Err(io::Error::new(io::ErrorKind::WouldBlock, ""))
+            }
+        }
+    }
+
+    impl io::Write for Duplex {
+        fn write(&mut self, buf: &[u8]) -> io::Result<usize> {
+            self.0.lock().unwrap().data.extend_from_slice(buf);
+            Ok(buf.len())
+        }
+
+        fn flush(&mut self) -> io::Result<()> {
+            Ok(())
+        }
+    }
+
+    impl AsyncWrite for Duplex {
+        fn shutdown(&mut self) -> Poll<(), io::Error> {
+            self.0.lock().unwrap().shutdown = true;
+            Ok(Async::Ready(()))
+        }
+    }
+
     struct Item(Bytes);
     impl ResponseType for Item {
         type Item = ();
@@ -424,11 +739,23 @@ mod tests {
         msgs: Vec<Bytes>,
         closed: bool,
         error: Option<io::Error>,
+        conn_closed: usize,
     }
 
     impl TestActor {
         fn new() -> TestActor {
-            TestActor{msgs: Vec::new(), closed: false, error: None}
+            TestActor{msgs: Vec::new(), closed: false, error: None, conn_closed: 0}
+        }
+    }
+
+    impl Handler<ConnectionClosed<TestCodec>> for TestActor {
+        type Result = ();
+
+        fn handle(&mut self, msg: ConnectionClosed<TestCodec>, _: &mut Self::Context) {
+            if let DisconnectReason::Error(_) = msg.reason {
+                panic!("peer closed connection");
+            }
+            self.conn_closed += 1;
         }
     }
 
@@ -567,4 +894,89 @@ mod tests {
         assert!(ctx.actor().closed);
         assert!(cell.closed());
     }
+
+    fn panic_after_send(flush: bool) -> Peer {
+        let peer = Arc::new(Mutex::new(Peer::default()));
+        let duplex = Duplex(Arc::clone(&peer));
+
+        let res = thread::spawn(move || {
+            let mut ctx = Context::new(None);
+            let (rx, mut tx) = FramedReader::wrap(duplex.framed(TestCodec));
+            TestActor::add_stream(rx, &mut ctx);
+            ctx.set_actor(TestActor::new());
+            tx.set_flush_on_failure(flush);
+            let _ = ctx.poll();
+
+            // frame stays in framed buffer until next poll
+            tx.send(Bytes::from_static(b"er"));
+            panic!("actor failure");
+        }).join();
+        assert!(res.is_err());
+
+        let peer = mem::replace(&mut *peer.lock().unwrap(), Peer::default());
+        peer
+    }
+
+    #[test]
+    fn test_flush_on_failure() {
+        let peer = panic_after_send(true);
+        assert_eq!(peer.data, b"er");
+        assert!(peer.shutdown);
+
+        let peer = panic_after_send(false);
+        assert!(peer.data.is_empty());
+        assert!(!peer.shutdown);
+    }
+
+    fn linked_ctx(peer: &Arc<Mutex<Peer>>, stop: bool)
+                  -> (Context<TestActor>, FramedWriter<Duplex, TestCodec>)
+    {
+        let mut ctx = Context::new(None);
+        let (rx, tx) = FramedReader::wrap_linked(Duplex(Arc::clone(peer)).framed(TestCodec));
+        rx.stop_on_close(stop).start(&mut ctx);
+        ctx.set_actor(TestActor::new());
+        (ctx, tx)
+    }
+
+    #[test]
+    fn test_linked_peer_eof() {
+        let peer = Arc::new(Mutex::new(Peer::default()));
+        let (mut ctx, mut tx) = linked_ctx(&peer, true);
+        let _ = ctx.poll();
+
+        // peer half-closes while frame is still in framed buffer
+        tx.send(Bytes::from_static(b"by"));
+        peer.lock().unwrap().eof = true;
+        let _ = ctx.poll();
+
+        assert_eq!(peer.lock().unwrap().data, b"by");
+        assert!(peer.lock().unwrap().shutdown);
+        assert!(tx.closed());
+        assert_eq!(ctx.actor().conn_closed, 1);
+        assert!(!ctx.actor().closed);
+        assert_eq!(ctx.state(), ActorState::Stopped);
+    }
+
+    #[test]
+    fn test_linked_keep_running() {
+        // mailbox registers current task
+        future::lazy(|| {
+            let peer = Arc::new(Mutex::new(Peer::default()));
+            let (mut ctx, mut tx) = linked_ctx(&peer, false);
+            let _addr: Address<_> = ctx.address();
+            let _ = ctx.poll();
+
+            peer.lock().unwrap().eof = true;
+            let _ = ctx.poll();
+            assert!(peer.lock().unwrap().shutdown);
+            assert_eq!(ctx.actor().conn_closed, 1);
+            assert_eq!(ctx.state(), ActorState::Running);
+
+            // framed object is gone
+            tx.send(Bytes::from_static(b"by"));
+            let _ = ctx.poll();
+            assert!(peer.lock().unwrap().data.is_empty());
+            Ok::<_, ()>(())
+        }).wait().unwrap();
+    }
 }
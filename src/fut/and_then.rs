@@ -46,4 +46,8 @@ impl<A, B, F> ActorFuture for AndThen<A, B, F>
             })
         })
     }
+
+    fn cancelled(&mut self, act: &mut A::Actor, ctx: &mut <A::Actor as Actor>::Context) {
+        self.state.cancelled(act, ctx)
+    }
 }
@@ -51,4 +51,12 @@ impl<A, B, C> Chain<A, B, C>
             }
         }
     }
+
+    pub fn cancelled(&mut self, srv: &mut A::Actor, ctx: &mut <A::Actor as Actor>::Context) {
+        match *self {
+            Chain::First(ref mut a, _) => a.cancelled(srv, ctx),
+            Chain::Second(ref mut b) => b.cancelled(srv, ctx),
+            Chain::Done => (),
+        }
+    }
 }
@@ -43,4 +43,11 @@ impl<A, B> ActorFuture for Either<A, B>
             Either::B(ref mut b) => b.poll(act, ctx),
         }
     }
+
+    fn cancelled(&mut self, act: &mut A::Actor, ctx: &mut <A::Actor as Actor>::Context) {
+        match *self {
+            Either::A(ref mut a) => a.cancelled(act, ctx),
+            Either::B(ref mut b) => b.cancelled(act, ctx),
+        }
+    }
 }
@@ -39,4 +39,8 @@ impl<A:ActorFuture, E:From<A::Error>> ActorFuture for FromErr<A, E> {
         };
         e.map_err(From::from)
     }
+
+    fn cancelled(&mut self, act: &mut A::Actor, ctx: &mut <A::Actor as Actor>::Context) {
+        self.future.cancelled(act, ctx)
+    }
 }
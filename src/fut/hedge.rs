@@ -0,0 +1,96 @@
+use std::time::Duration;
+use futures::{Async, Future, Poll};
+use tokio_core::reactor::Timeout;
+
+use address::MailboxError;
+use arbiter::Arbiter;
+
+
+/// Send request to a replica and hedge it with second request.
+///
+/// `primary` request is issued immediately, if it does not resolve within
+/// `delay`, `secondary` is called to issue same request to another replica.
+/// Future resolves with first successful response, pending request gets
+/// dropped, so it cancels. If both requests fail, future resolves with last
+/// failure. If `primary` fails before `delay`, secondary is not issued.
+///
+/// ```rust,ignore
+/// fut::hedge(replica1.call_fut(Get), move || replica2.call_fut(Get),
+///            Duration::from_millis(20))
+/// ```
+pub fn hedge<P, S, F, T, E>(primary: P, secondary: F, delay: Duration) -> Hedge<P, S, F>
+    where P: Future<Item=Result<T, E>, Error=MailboxError>,
+          S: Future<Item=Result<T, E>, Error=MailboxError>,
+          F: FnOnce() -> S
+{
+    Hedge {
+        primary: Some(primary),
+        secondary: None,
+        factory: Some(secondary),
+        delay: Timeout::new(delay, Arbiter::handle()).unwrap(),
+    }
+}
+
+/// Future for the `hedge` function
+#[must_use = "futures do nothing unless polled"]
+pub struct Hedge<P, S, F> {
+    primary: Option<P>,
+    secondary: Option<S>,
+    factory: Option<F>,
+    delay: Timeout,
+}
+
+impl<P, S, F, T, E> Future for Hedge<P, S, F>
+    where P: Future<Item=Result<T, E>, Error=MailboxError>,
+          S: Future<Item=Result<T, E>, Error=MailboxError>,
+          F: FnOnce() -> S
+{
+    type Item = Result<T, E>;
+    type Error = MailboxError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let res = match self.primary.as_mut().map(|fut| fut.poll()) {
+            Some(Ok(Async::NotReady)) => {
+                // primary is late, issue secondary request
+                if self.factory.is_some() {
+                    match self.delay.poll() {
+                        Ok(Async::NotReady) => (),
+                        _ => self.secondary = self.factory.take().map(|f| f()),
+                    }
+                }
+                None
+            }
+            Some(res) => {
+                self.primary.take();
+                self.factory.take();
+                Some(res)
+            }
+            None => None,
+        };
+        match res {
+            Some(Ok(Async::Ready(Ok(item)))) => {
+                self.secondary.take();
+                return Ok(Async::Ready(Ok(item)))
+            }
+            Some(res) if self.secondary.is_none() => return res,
+            _ => (),
+        }
+
+        let res = match self.secondary.as_mut().map(|fut| fut.poll()) {
+            Some(Ok(Async::NotReady)) | None => return Ok(Async::NotReady),
+            Some(res) => res,
+        };
+        self.secondary.take();
+        match res {
+            Ok(Async::Ready(Ok(item))) => {
+                self.primary.take();
+                Ok(Async::Ready(Ok(item)))
+            }
+            res => if self.primary.is_none() {
+                res
+            } else {
+                Ok(Async::NotReady)
+            },
+        }
+    }
+}
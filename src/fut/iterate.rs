@@ -0,0 +1,70 @@
+//! Definition of the `Iterate` combinator, time-sliced iterator draining
+
+use std::mem;
+use std::marker::PhantomData;
+use futures::{Async, Poll};
+use futures::task;
+
+use fut::ActorFuture;
+use actor::Actor;
+
+
+/// Collect iterator into `Vec`, `chunk_size` items per poll.
+///
+/// Future yields to the reactor after every chunk, so building large
+/// response does not block other actors and timers of the arbiter.
+/// Items keep iterator's order. Future could be used as async handler
+/// result:
+///
+/// ```rust,ignore
+/// impl Handler<ListRooms> for ChatServer {
+///     type Result = ResponseActFuture<Self, ListRooms>;
+///
+///     fn handle(&mut self, _: ListRooms, _: &mut Context<Self>) -> Self::Result {
+///         let rooms: Vec<_> = self.rooms.keys().cloned().collect();
+///         Box::new(fut::iterate(rooms, 10_000))
+///     }
+/// }
+/// ```
+pub fn iterate<I, E, A>(iter: I, chunk_size: usize) -> Iterate<I::IntoIter, E, A>
+    where I: IntoIterator
+{
+    assert!(chunk_size != 0, "chunk size has to be greater than zero");
+    Iterate {
+        iter: iter.into_iter(),
+        items: Vec::new(),
+        chunk_size: chunk_size,
+        act: PhantomData,
+    }
+}
+
+/// Future for the `iterate` function
+#[must_use = "futures do nothing unless polled"]
+pub struct Iterate<I, E, A> where I: Iterator {
+    iter: I,
+    items: Vec<I::Item>,
+    chunk_size: usize,
+    act: PhantomData<(E, A)>,
+}
+
+impl<I, E, A> ActorFuture for Iterate<I, E, A> where I: Iterator, A: Actor {
+    type Item = Vec<I::Item>;
+    type Error = E;
+    type Actor = A;
+
+    fn poll(&mut self, _: &mut A, _: &mut A::Context) -> Poll<Vec<I::Item>, E> {
+        if self.items.is_empty() {
+            self.items.reserve(self.iter.size_hint().0);
+        }
+        for _ in 0..self.chunk_size {
+            match self.iter.next() {
+                Some(item) => self.items.push(item),
+                None => return Ok(Async::Ready(mem::replace(&mut self.items, Vec::new()))),
+            }
+        }
+
+        // let other tasks run before next chunk
+        task::current().notify();
+        Ok(Async::NotReady)
+    }
+}
@@ -47,4 +47,8 @@ impl<U, A, F> ActorFuture for Map<A, F>
             Err(err) => Err(err)
         }
     }
+
+    fn cancelled(&mut self, act: &mut A::Actor, ctx: &mut <A::Actor as Actor>::Context) {
+        self.future.cancelled(act, ctx)
+    }
 }
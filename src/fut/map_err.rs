@@ -44,6 +44,10 @@ impl<U, A, F> ActorFuture for MapErr<A, F>
             Ok(err) => Ok(err)
         }
     }
+
+    fn cancelled(&mut self, act: &mut A::Actor, ctx: &mut <A::Actor as Actor>::Context) {
+        self.future.cancelled(act, ctx)
+    }
 }
 
 pub struct DropErr<A> where A: ActorFuture {
@@ -76,4 +80,8 @@ impl<A> ActorFuture for DropErr<A>
             Err(_) => Err(()),
         }
     }
+
+    fn cancelled(&mut self, act: &mut A::Actor, ctx: &mut <A::Actor as Actor>::Context) {
+        self.future.cancelled(act, ctx)
+    }
 }
@@ -21,6 +21,8 @@ mod stream_finish;
 mod stream_fold;
 mod stream_timeout;
 mod helpers;
+mod hedge;
+mod iterate;
 
 pub use self::either::Either;
 pub use self::and_then::AndThen;
@@ -38,6 +40,8 @@ pub use self::stream_finish::StreamFinish;
 pub use self::stream_fold::StreamFold;
 pub use self::stream_timeout::StreamTimeout;
 pub use self::helpers::{Finish, FinishStream};
+pub use self::hedge::{hedge, Hedge};
+pub use self::iterate::{iterate, Iterate};
 
 use actor::Actor;
 
@@ -61,6 +65,14 @@ pub trait ActorFuture {
     fn poll(&mut self, srv: &mut Self::Actor, ctx: &mut <Self::Actor as Actor>::Context)
             -> Poll<Self::Item, Self::Error>;
 
+    /// Method is called if actor stops while this future is still pending.
+    ///
+    /// Context calls this method for every pending spawned or wait future
+    /// before `Actor::stopped()` get called. Future could use it for cleanup
+    /// that requires access to the actor or its context.
+    #[allow(unused_variables)]
+    fn cancelled(&mut self, srv: &mut Self::Actor, ctx: &mut <Self::Actor as Actor>::Context) {}
+
     /// Map this future's result to a different type, returning a new future of
     /// the resulting type.
     fn map<F, U>(self, f: F) -> Map<Self, F>
@@ -241,6 +253,10 @@ impl<F: ActorFuture + ?Sized> ActorFuture for Box<F> {
             ctx: &mut <Self::Actor as Actor>::Context) -> Poll<Self::Item, Self::Error> {
         (**self).poll(srv, ctx)
     }
+
+    fn cancelled(&mut self, srv: &mut Self::Actor, ctx: &mut <Self::Actor as Actor>::Context) {
+        (**self).cancelled(srv, ctx)
+    }
 }
 
 /// Helper trait that allows conversion of normal future into `ActorFuture`
@@ -83,4 +83,10 @@ impl<S, F, Fut, T> ActorFuture for StreamFold<S, F, Fut, T>
             }
         }
     }
+
+    fn cancelled(&mut self, act: &mut S::Actor, ctx: &mut <S::Actor as Actor>::Context) {
+        if let State::Processing(ref mut fut) = self.state {
+            fut.cancelled(act, ctx)
+        }
+    }
 }
@@ -44,4 +44,8 @@ impl<A, B, F> ActorFuture for Then<A, B, F>
             Ok(Err(f(a, act, ctx).into_future()))
         })
     }
+
+    fn cancelled(&mut self, act: &mut A::Actor, ctx: &mut <A::Actor as Actor>::Context) {
+        self.state.cancelled(act, ctx)
+    }
 }
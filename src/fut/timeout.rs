@@ -48,4 +48,8 @@ impl<F> ActorFuture for Timeout<F> where F: ActorFuture
 
         self.fut.poll(act, ctx)
     }
+
+    fn cancelled(&mut self, act: &mut F::Actor, ctx: &mut <F::Actor as Actor>::Context) {
+        self.fut.cancelled(act, ctx)
+    }
 }
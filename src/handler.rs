@@ -1,4 +1,5 @@
-use futures::Future;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use futures::{Async, Future, Poll};
 use futures::sync::oneshot::Sender as SyncSender;
 use futures::unsync::oneshot::Sender as UnsyncSender;
 
@@ -7,6 +8,8 @@ use fut::{self, ActorFuture};
 use actor::{Actor, AsyncContext};
 use address::SyncAddress;
 use context::Context;
+use message::MailboxError;
+pub use message::{MessageResult, ResponseType};
 
 /// Message handler
 ///
@@ -23,25 +26,6 @@ pub trait Handler<M> where Self: Actor, M: ResponseType {
     fn handle(&mut self, msg: M, ctx: &mut Self::Context) -> Self::Result;
 }
 
-/// Message response type
-pub trait ResponseType {
-
-    /// The type of value that this message will resolved with if it is successful.
-    type Item: 'static;
-
-    /// The type of error that this message will resolve with if it fails in a normal fashion.
-    type Error: 'static;
-}
-
-impl<I, E> ResponseType for Result<I, E> where I: ResponseType {
-    type Item = <I as ResponseType>::Item;
-    type Error = ();
-}
-
-/// A specialized [`Result`](https://doc.rust-lang.org/std/result/enum.Result.html)
-/// for message result responses
-pub type MessageResult<M: ResponseType> = Result<M::Item, M::Error>;
-
 /// A specialized actor future for async message handler
 pub type ResponseActFuture<A, M: ResponseType> =
     Box<ActorFuture<Item=M::Item, Error=M::Error, Actor=A>>;
@@ -54,6 +38,11 @@ pub trait ResponseChannel<M: ResponseType>: 'static {
 
     fn is_canceled(&self) -> bool;
 
+    /// Check if receiver is gone, registers current task for cancel notification
+    fn poll_canceled(&mut self) -> bool {
+        self.is_canceled()
+    }
+
     fn send(self, response: MessageResult<M>);
 }
 
@@ -67,6 +56,13 @@ impl<M: ResponseType + 'static> ResponseChannel<M> for SyncSender<MessageResult<
         SyncSender::is_canceled(self)
     }
 
+    fn poll_canceled(&mut self) -> bool {
+        match SyncSender::poll_cancel(self) {
+            Ok(Async::Ready(())) | Err(_) => true,
+            Ok(Async::NotReady) => false,
+        }
+    }
+
     fn send(self, response: MessageResult<M>) {
         let _ = SyncSender::send(self, response);
     }
@@ -77,6 +73,13 @@ impl<M: ResponseType + 'static> ResponseChannel<M> for UnsyncSender<MessageResul
         UnsyncSender::is_canceled(self)
     }
 
+    fn poll_canceled(&mut self) -> bool {
+        match UnsyncSender::poll_cancel(self) {
+            Ok(Async::Ready(())) | Err(_) => true,
+            Ok(Async::NotReady) => false,
+        }
+    }
+
     fn send(self, response: MessageResult<M>) {
         let _ = UnsyncSender::send(self, response);
     }
@@ -118,27 +121,45 @@ impl<A, M, B> MessageResponse<A, M> for SyncAddress<B>
 }
 
 impl<A, M> MessageResponse<A, M> for ResponseActFuture<A, M>
-    where A: Actor, M: ResponseType, A::Context: AsyncContext<A>
+    where A: Actor, M: ResponseType + 'static, A::Context: AsyncContext<A>
 {
     fn handle<R: ResponseChannel<M>>(self, ctx: &mut A::Context, tx: Option<R>) {
-        ctx.spawn(
-            self.then(move |res, _, _| {
-                if let Some(tx) = tx {
-                    tx.send(res);
-                }
-                fut::ok(())
-            }));
+        ctx.spawn_internal(ActorResponseFut{fut: self, tx: tx});
     }
 }
 
 impl<A, M> MessageResponse<A, M> for ResponseFuture<M>
-    where A: Actor, M: ResponseType, A::Context: AsyncContext<A>
+    where A: Actor, M: ResponseType + 'static, A::Context: AsyncContext<A>
 {
     fn handle<R: ResponseChannel<M>>(self, _: &mut A::Context, tx: Option<R>) {
-        Arbiter::handle().spawn(self.then(move |res| {
-            tx.map(|tx| tx.send(res));
-            Ok(())
-        }));
+        Arbiter::handle().spawn(ResponseFut{fut: self, tx: tx});
+    }
+}
+
+/// Async response that is handled exclusively
+///
+/// Context does not process any incoming messages until response future
+/// resolves, already spawned futures and timers continue running.
+/// See `AsyncContext::spawn_exclusive()` for details.
+pub struct ExclusiveResponse<A, M> where A: Actor, M: ResponseType {
+    fut: Box<ActorFuture<Item=M::Item, Error=M::Error, Actor=A>>,
+}
+
+impl<A, M> ExclusiveResponse<A, M> where A: Actor, M: ResponseType {
+
+    /// Create exclusive response
+    pub fn new<T>(fut: T) -> Self
+        where T: ActorFuture<Item=M::Item, Error=M::Error, Actor=A> + 'static
+    {
+        ExclusiveResponse {fut: Box::new(fut)}
+    }
+}
+
+impl<A, M> MessageResponse<A, M> for ExclusiveResponse<A, M>
+    where A: Actor, M: ResponseType + 'static, A::Context: AsyncContext<A>
+{
+    fn handle<R: ResponseChannel<M>>(self, ctx: &mut A::Context, tx: Option<R>) {
+        ctx.spawn_exclusive(ActorResponseFut{fut: self.fut, tx: tx});
     }
 }
 
@@ -177,21 +198,15 @@ impl<A, M> Response<A, M> where A: Actor, M: ResponseType {
 }
 
 impl<A, M> MessageResponse<A, M> for Response<A, M>
-    where A: Actor, M: ResponseType, A::Context: AsyncContext<A>
+    where A: Actor, M: ResponseType + 'static, A::Context: AsyncContext<A>
 {
     fn handle<R: ResponseChannel<M>>(self, ctx: &mut A::Context, tx: Option<R>) {
         match self.item {
             ResponseTypeItem::Fut(fut) => {
-                Arbiter::handle().spawn(fut.then(move |res| {
-                    tx.map(|tx| tx.send(res));
-                    Ok(())
-                }));
+                Arbiter::handle().spawn(ResponseFut{fut: fut, tx: tx});
             },
             ResponseTypeItem::AFut(fut) => {
-                ctx.spawn(fut.then(move |res, _, _| {
-                    tx.map(|tx| tx.send(res));
-                    fut::ok(())
-                }));
+                ctx.spawn_internal(ActorResponseFut{fut: fut, tx: tx});
             },
             ResponseTypeItem::Result(res) => {
                 tx.map(|tx| tx.send(res));
@@ -199,3 +214,306 @@ impl<A, M> MessageResponse<A, M> for Response<A, M>
         }
     }
 }
+
+/// Response computed on the system blocking pool
+///
+/// Handler moves message together with snapshot of actor state it needs
+/// into closure, closure runs on a thread of the blocking pool, see
+/// `Arbiter::spawn_blocking()`. Result is passed back to the actor thread
+/// and could be applied to the actor with `BlockingResponse::apply()`,
+/// so `&mut self` is never shared with other threads.
+///
+/// Response is not exclusive, actor keeps handling other messages, streams
+/// and timers while closure runs. Result is applied once it is ready,
+/// between other messages, so messages sent after this one could be handled
+/// before result gets applied. With `exclusive()` context does not process
+/// incoming messages until result is applied. Result is applied even if
+/// caller drops response receiver. If closure panics, response is dropped
+/// and caller receives `MailboxError::Canceled`.
+///
+/// ```rust,ignore
+/// impl Handler<Lookup> for Db {
+///     type Result = BlockingResponse<Self, Lookup>;
+///
+///     fn handle(&mut self, msg: Lookup, _: &mut Context<Self>) -> Self::Result {
+///         let path = self.path.clone();
+///         BlockingResponse::apply(
+///             move || ffi::lookup(&path, msg.0),
+///             |res, act: &mut Db, _| {
+///                 act.lookups += 1;
+///                 res
+///             })
+///     }
+/// }
+/// ```
+pub struct BlockingResponse<A, M> where A: Actor, M: ResponseType {
+    start: Box<FnOnce() -> Box<ActorFuture<Item=MessageResult<M>, Error=(), Actor=A>>>,
+    exclusive: bool,
+}
+
+impl<A, M> BlockingResponse<A, M> where A: Actor, M: ResponseType + 'static {
+
+    /// Run `f` on the blocking pool, its result is the response
+    pub fn new<F>(f: F) -> Self
+        where F: FnOnce() -> MessageResult<M> + Send + 'static,
+              M::Item: Send, M::Error: Send,
+    {
+        BlockingResponse::apply(f, |res, _, _| res)
+    }
+
+    /// Run `f` on the blocking pool, then apply its result to the actor
+    /// on the actor thread
+    pub fn apply<F, T, U>(f: F, apply: U) -> Self
+        where F: FnOnce() -> T + Send + 'static, T: Send + 'static,
+              U: FnOnce(T, &mut A, &mut A::Context) -> MessageResult<M> + 'static,
+    {
+        BlockingResponse {
+            start: Box::new(move || Box::new(
+                fut::wrap_future(Arbiter::spawn_blocking(f))
+                    .map(apply)
+                    .map_err(|_, _, _| error!("Blocking handler panicked")))),
+            exclusive: false,
+        }
+    }
+
+    /// Do not process incoming messages until result is applied
+    pub fn exclusive(mut self) -> Self {
+        self.exclusive = true;
+        self
+    }
+}
+
+impl<A, M> MessageResponse<A, M> for BlockingResponse<A, M>
+    where A: Actor, M: ResponseType + 'static, A::Context: AsyncContext<A>
+{
+    fn handle<R: ResponseChannel<M>>(self, ctx: &mut A::Context, tx: Option<R>) {
+        let fut = (self.start)().map(move |res, _, _| {
+            if let Some(tx) = tx {
+                tx.send(res);
+            }
+        });
+        if self.exclusive {
+            ctx.spawn_exclusive(fut);
+        } else {
+            ctx.spawn_internal(fut);
+        }
+    }
+}
+
+/// What happens to forwarded response if proxy actor stops,
+/// see `ForwardResponse`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum ForwardMode {
+    /// Backend request is dropped together with proxy's futures, caller
+    /// receives `MailboxError::Canceled` as soon as proxy stops.
+    /// This is default mode.
+    Cancel,
+    /// Backend request keeps running in the arbiter, caller receives
+    /// backend's response even if proxy stopped in between.
+    Complete,
+}
+
+impl Default for ForwardMode {
+    fn default() -> ForwardMode {
+        ForwardMode::Cancel
+    }
+}
+
+/// Response forwarded from another actor's request
+///
+/// Proxy actor passes message to a backend and replies with backend's
+/// response, caller and backend request are tied together. If backend
+/// request fails with `MailboxError`, caller receives
+/// `MailboxError::Canceled`. If caller drops its receiver, backend request
+/// is dropped as well. Behavior after proxy stops is configured with
+/// `ForwardMode`.
+///
+/// ```rust,ignore
+/// impl Handler<Get> for Proxy {
+///     type Result = ForwardResponse<Get>;
+///
+///     fn handle(&mut self, msg: Get, _: &mut Context<Self>) -> Self::Result {
+///         ForwardResponse::new(self.backend.call_fut(msg))
+///             .mode(ForwardMode::Complete)
+///     }
+/// }
+/// ```
+pub struct ForwardResponse<M> where M: ResponseType {
+    fut: Box<Future<Item=MessageResult<M>, Error=MailboxError>>,
+    mode: ForwardMode,
+}
+
+impl<M> ForwardResponse<M> where M: ResponseType {
+
+    /// Forward result of backend request, i.e. `Address::call_fut()`
+    pub fn new<F>(request: F) -> Self
+        where F: Future<Item=MessageResult<M>, Error=MailboxError> + 'static
+    {
+        ForwardResponse{fut: Box::new(request), mode: ForwardMode::default()}
+    }
+
+    /// Set behavior after proxy actor stops
+    pub fn mode(mut self, mode: ForwardMode) -> Self {
+        self.mode = mode;
+        self
+    }
+}
+
+impl<A, M> MessageResponse<A, M> for ForwardResponse<M>
+    where A: Actor, M: ResponseType + 'static, A::Context: AsyncContext<A>
+{
+    fn handle<R: ResponseChannel<M>>(self, ctx: &mut A::Context, tx: Option<R>) {
+        let fut = ForwardFut{fut: self.fut, tx: tx};
+        match self.mode {
+            ForwardMode::Cancel => { ctx.spawn_internal(fut::wrap_future(fut)); }
+            ForwardMode::Complete => Arbiter::handle().spawn(fut),
+        }
+    }
+}
+
+/// Response that is always computed to completion
+///
+/// By default async response future gets dropped as soon as caller
+/// drops response receiver. Wrap response with `MustComplete` if
+/// handler has side effects that must finish.
+///
+/// ```rust,ignore
+/// impl Handler<Store> for Storage {
+///     type Result = MustComplete<ResponseFuture<Store>>;
+///
+///     fn handle(&mut self, msg: Store, _: &mut Context<Self>) -> Self::Result {
+///         MustComplete(Box::new(self.write(msg)))
+///     }
+/// }
+/// ```
+pub struct MustComplete<T>(pub T);
+
+impl<A, M, T> MessageResponse<A, M> for MustComplete<T>
+    where A: Actor, M: ResponseType + 'static, T: MessageResponse<A, M>
+{
+    fn handle<R: ResponseChannel<M>>(self, ctx: &mut A::Context, tx: Option<R>) {
+        self.0.handle(ctx, tx.map(MustCompleteChannel))
+    }
+}
+
+struct MustCompleteChannel<R>(R);
+
+impl<M: ResponseType, R: ResponseChannel<M>> ResponseChannel<M> for MustCompleteChannel<R> {
+    fn is_canceled(&self) -> bool {
+        self.0.is_canceled()
+    }
+
+    fn poll_canceled(&mut self) -> bool {
+        false
+    }
+
+    fn send(self, response: MessageResult<M>) {
+        self.0.send(response)
+    }
+}
+
+static ABANDONED: AtomicUsize = AtomicUsize::new(0);
+
+/// Number of async responses dropped because caller is gone
+pub fn abandoned_responses() -> usize {
+    ABANDONED.load(Ordering::Relaxed)
+}
+
+fn abandoned<M>() {
+    ABANDONED.fetch_add(1, Ordering::Relaxed);
+    debug!("Response receiver is gone, drop response future");
+}
+
+/// Response future, it gets dropped once response receiver is gone
+struct ActorResponseFut<A, M, R> where A: Actor, M: ResponseType {
+    fut: Box<ActorFuture<Item=M::Item, Error=M::Error, Actor=A>>,
+    tx: Option<R>,
+}
+
+impl<A, M, R> ActorFuture for ActorResponseFut<A, M, R>
+    where A: Actor, M: ResponseType, R: ResponseChannel<M>
+{
+    type Item = ();
+    type Error = ();
+    type Actor = A;
+
+    fn poll(&mut self, act: &mut A, ctx: &mut A::Context) -> Poll<(), ()> {
+        if let Some(ref mut tx) = self.tx {
+            if tx.poll_canceled() {
+                abandoned::<M>();
+                return Ok(Async::Ready(()))
+            }
+        }
+        let res = match self.fut.poll(act, ctx) {
+            Ok(Async::NotReady) => return Ok(Async::NotReady),
+            Ok(Async::Ready(item)) => Ok(item),
+            Err(err) => Err(err),
+        };
+        if let Some(tx) = self.tx.take() {
+            tx.send(res);
+        }
+        Ok(Async::Ready(()))
+    }
+
+    fn cancelled(&mut self, act: &mut A, ctx: &mut A::Context) {
+        self.fut.cancelled(act, ctx)
+    }
+}
+
+/// Same as `ActorResponseFut` but for plain futures
+struct ResponseFut<M, R> where M: ResponseType {
+    fut: Box<Future<Item=M::Item, Error=M::Error>>,
+    tx: Option<R>,
+}
+
+impl<M, R> Future for ResponseFut<M, R> where M: ResponseType, R: ResponseChannel<M> {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        if let Some(ref mut tx) = self.tx {
+            if tx.poll_canceled() {
+                abandoned::<M>();
+                return Ok(Async::Ready(()))
+            }
+        }
+        let res = match self.fut.poll() {
+            Ok(Async::NotReady) => return Ok(Async::NotReady),
+            Ok(Async::Ready(item)) => Ok(item),
+            Err(err) => Err(err),
+        };
+        if let Some(tx) = self.tx.take() {
+            tx.send(res);
+        }
+        Ok(Async::Ready(()))
+    }
+}
+
+/// Forwarded response future, see `ForwardResponse`
+struct ForwardFut<M, R> where M: ResponseType {
+    fut: Box<Future<Item=MessageResult<M>, Error=MailboxError>>,
+    tx: Option<R>,
+}
+
+impl<M, R> Future for ForwardFut<M, R> where M: ResponseType, R: ResponseChannel<M> {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        if let Some(ref mut tx) = self.tx {
+            if tx.poll_canceled() {
+                abandoned::<M>();
+                return Ok(Async::Ready(()))
+            }
+        }
+        match self.fut.poll() {
+            Ok(Async::NotReady) => return Ok(Async::NotReady),
+            Ok(Async::Ready(res)) => if let Some(tx) = self.tx.take() {
+                tx.send(res);
+            },
+            // response channel is dropped, caller gets `MailboxError::Canceled`
+            Err(_) => (),
+        }
+        Ok(Async::Ready(()))
+    }
+}
@@ -0,0 +1,124 @@
+//! Mailbox handoff to replacement actor, see `SyncAddress::handoff_to()`
+use std::any::Any;
+
+use futures::Future;
+use futures::sync::oneshot::{channel, Sender};
+use futures::unsync::oneshot::Sender as LocalSender;
+
+use actor::{Actor, ActorContext};
+use actors::deadletter::{self, DeadLetterReason};
+use address::{SendError, SyncAddress, ToEnvelope};
+use arbiter::Arbiter;
+use context::Context;
+use handler::{Handler, MessageResult, ResponseType};
+
+type Route<B> = fn(Box<Any>, &SyncAddress<B>) -> Result<(), Box<Any>>;
+
+/// Replacement actor and message types that move to its mailbox
+///
+/// Actor that gets handoff closes its mailbox, messages of registered
+/// types are moved to the replacement actor's mailbox in order they were
+/// queued, then actor stops. Pending requests are answered by replacement
+/// actor. Messages of other types are published to `DeadLetters` service,
+/// same as messages that are sent after handoff.
+///
+/// ```rust,ignore
+/// let new: SyncAddress<_> = CounterV2::default().start();
+/// old.handoff_to(Handoff::new(new).route::<Inc>().route::<Get>());
+/// ```
+pub struct Handoff<B: Actor> {
+    addr: SyncAddress<B>,
+    routes: Vec<Route<B>>,
+}
+
+impl<B: Actor> Handoff<B> {
+    /// Create handoff to actor `B`
+    pub fn new(addr: SyncAddress<B>) -> Handoff<B> {
+        Handoff{addr: addr, routes: Vec::new()}
+    }
+
+    /// Move messages of type `M` to actor `B`
+    pub fn route<M>(mut self) -> Self
+        where B: Handler<M>, B::Context: ToEnvelope<B>,
+              M: ResponseType + Send + 'static, M::Item: Send, M::Error: Send,
+    {
+        self.routes.push(route::<B, M>);
+        self
+    }
+
+    /// Move message to actor `B`, returns message back if its type is not routed
+    fn deliver(&self, mut msg: Box<Any>) -> Result<(), Box<Any>> {
+        for route in &self.routes {
+            match route(msg, &self.addr) {
+                Ok(()) => return Ok(()),
+                Err(m) => msg = m,
+            }
+        }
+        Err(msg)
+    }
+}
+
+impl<B: Actor> ResponseType for Handoff<B> {
+    type Item = ();
+    type Error = ();
+}
+
+impl<A, B> Handler<Handoff<B>> for A where A: Actor<Context=Context<A>>, B: Actor {
+    type Result = ();
+
+    fn handle(&mut self, msg: Handoff<B>, ctx: &mut Context<A>) {
+        for mut env in ctx.drain_mailbox() {
+            let tp = env.type_name();
+            let moved = match env.take_message() {
+                Some(m) => msg.deliver(m).is_ok(),
+                None => false,
+            };
+            if !moved {
+                warn!("Message {} is not routed to replacement actor", tp);
+                env.dead_letter(DeadLetterReason::Closed);
+            }
+        }
+        ctx.stop();
+    }
+}
+
+fn route<B, M>(msg: Box<Any>, addr: &SyncAddress<B>) -> Result<(), Box<Any>>
+    where B: Handler<M>, B::Context: ToEnvelope<B>,
+          M: ResponseType + Send + 'static, M::Item: Send, M::Error: Send,
+{
+    // message from sync address
+    let msg = match msg.downcast::<(M, Option<Sender<MessageResult<M>>>)>() {
+        Ok(msg) => {
+            let (msg, tx) = *msg;
+            push(addr, msg, tx);
+            return Ok(())
+        }
+        Err(msg) => msg,
+    };
+
+    // message from local address, response is passed back to actor's thread
+    let (msg, tx) = *msg.downcast::<(M, Option<LocalSender<MessageResult<M>>>)>()?;
+    match tx {
+        Some(tx) => {
+            let (sync_tx, rx) = channel();
+            push(addr, msg, Some(sync_tx));
+            Arbiter::handle().spawn(rx.then(move |res| {
+                if let Ok(res) = res {
+                    let _ = tx.send(res);
+                }
+                Ok(())
+            }));
+        }
+        None => push(addr, msg, None),
+    }
+    Ok(())
+}
+
+fn push<B, M>(addr: &SyncAddress<B>, msg: M, tx: Option<Sender<MessageResult<M>>>)
+    where B: Handler<M>, B::Context: ToEnvelope<B>,
+          M: ResponseType + Send + 'static, M::Item: Send, M::Error: Send,
+{
+    if let Err(SendError::Closed(msg)) = addr.push(msg, tx) {
+        deadletter::publish::<B, M>(&msg);
+    }
+}
@@ -16,22 +16,43 @@
 //! * Actor supervision.
 //! * Typed messages (No `Any` type). Generic messages are allowed.
 //! * Minimum supported Rust version: 1.20 or later
+//!
+//! ## Runtime feature
+//!
+//! Actors runtime is enabled by default `runtime` feature. Crate compiled
+//! with `default-features = false` is `no_std` and contains only message
+//! definition layer: `ResponseType`, `MessageResult`, `MailboxError`,
+//! `SendError` and `#[derive(Message)]`.
+
+#![cfg_attr(not(feature="runtime"), no_std)]
 
+#[cfg(feature="runtime")]
 #[macro_use]
 extern crate log;
+#[cfg(feature="runtime")]
 extern crate libc;
+#[cfg(feature="runtime")]
 extern crate uuid;
+#[cfg(feature="runtime")]
 extern crate smallvec;
+#[cfg(feature="runtime")]
 extern crate crossbeam_channel;
+#[cfg(feature="runtime")]
 #[macro_use]
 extern crate bitflags;
+#[cfg(feature="runtime")]
 #[macro_use]
 extern crate futures;
+#[cfg(feature="runtime")]
 extern crate tokio_io;
+#[cfg(feature="runtime")]
 extern crate tokio_core;
+#[cfg(feature="runtime")]
 extern crate tokio_signal;
+#[cfg(feature="runtime")]
 extern crate trust_dns_resolver;
 
+#[cfg(feature="runtime")]
 #[macro_use]
 extern crate failure;
 
@@ -40,51 +61,167 @@ extern crate failure;
 #[macro_use]
 extern crate actix_derive;
 
-#[cfg(test)]
+#[cfg(any(test, feature="messages"))]
 extern crate bytes;
 
+#[cfg(feature="serialize")]
+extern crate serde;
+#[cfg(feature="serialize")]
+extern crate serde_json;
+
+#[cfg(not(feature="runtime"))]
+extern crate alloc;
+
 #[doc(hidden)]
 pub use actix_derive::*;
 
+mod message;
+
+#[cfg(feature="runtime")]
+mod abort;
+#[cfg(feature="runtime")]
 mod actor;
+#[cfg(feature="runtime")]
 mod arbiter;
+#[cfg(feature="runtime")]
+mod blocking;
+#[cfg(feature="runtime")]
+mod budget;
+#[cfg(feature="runtime")]
+mod builder;
+#[cfg(feature="runtime")]
 mod context;
+#[cfg(feature="runtime")]
 mod contextimpl;
+#[cfg(feature="runtime")]
 mod contextitems;
+#[cfg(feature="runtime")]
 mod handler;
+#[cfg(feature="runtime")]
 mod framed;
+#[cfg(feature="runtime")]
+mod reconnect;
+#[cfg(feature="runtime")]
+mod server;
+#[cfg(feature="runtime")]
+mod loadgen;
+#[cfg(feature="runtime")]
 mod stream;
+#[cfg(feature="runtime")]
 mod registry;
+#[cfg(feature="runtime")]
 mod system;
+#[cfg(feature="runtime")]
 mod supervisor;
+#[cfg(feature="runtime")]
+mod tree;
 
+#[cfg(feature="runtime")]
 mod address;
+#[cfg(feature="runtime")]
 mod mailbox;
+#[cfg(feature="runtime")]
+mod middleware;
+#[cfg(feature="runtime")]
+mod cache;
+#[cfg(feature="runtime")]
+mod pipe;
+#[cfg(feature="runtime")]
+mod pump;
+#[cfg(feature="runtime")]
+mod redelivery;
+#[cfg(feature="runtime")]
+mod persist;
+#[cfg(feature="runtime")]
+mod handoff;
+#[cfg(feature="runtime")]
+mod msgregistry;
 
+#[cfg(feature="runtime")]
 pub mod fut;
+#[cfg(feature="runtime")]
 pub mod actors;
+#[cfg(feature="runtime")]
 pub mod msgs;
+#[cfg(feature="runtime")]
 pub mod sync;
+#[cfg(feature="runtime")]
 pub mod utils;
+#[cfg(feature="runtime")]
+#[doc(hidden)]
+pub mod facade;
+
+#[cfg(feature="compat")]
+pub mod compat;
+
+#[cfg(feature="messages")]
+pub mod messages;
 
+pub use message::{ResponseType, MessageResult, IntoMessageError, MailboxError, SendError};
+#[cfg(feature="runtime")]
 pub use fut::{ActorFuture, ActorStream, WrapFuture, WrapStream, FinishStream};
+#[cfg(feature="runtime")]
 pub use actor::{Actor, ActorState, Supervised,
-                ActorContext, AsyncContext, SpawnHandle};
-pub use handler::{Handler, Response, ResponseType,
-                  MessageResult, ResponseFuture, ResponseActFuture};
-pub use arbiter::Arbiter;
-pub use address::{Address, ActorAddress, SyncAddress, Subscriber, ToEnvelope, MailboxError};
+                ActorContext, AsyncContext, SpawnHandle, TimerGroup, StartedFuture};
+#[cfg(feature="runtime")]
+pub use handler::{Handler, Response, ExclusiveResponse, MustComplete,
+                  ResponseFuture, ResponseActFuture, BlockingResponse,
+                  ForwardResponse, ForwardMode};
+#[cfg(feature="runtime")]
+pub use arbiter::{Arbiter, ActorInfo, WaitInfo, unique_system_id};
+#[cfg(feature="runtime")]
+pub use blocking::BlockingFuture;
+#[cfg(feature="runtime")]
+pub use builder::ActorBuilder;
+#[cfg(feature="runtime")]
+pub use address::{Address, ActorAddress, SyncAddress, Subscriber, ToEnvelope,
+                  Overflow, ask, LazySend, LazySendHandle, WeakAddress, WeakSyncAddress,
+                  Recipient, RecipientRequest, SyncRecipient};
+#[cfg(feature="runtime")]
 pub use context::Context;
-pub use framed::{FramedReader, FramedWriter, FramedError};
+#[cfg(feature="runtime")]
+pub use contextimpl::PollStats;
+#[cfg(feature="runtime")]
+pub use mailbox::{HandlerStat, MailboxHandle, MailboxReceiver, TraceEvent, TraceKind};
+#[cfg(feature="runtime")]
+pub use middleware::{ActorMiddleware, MiddlewareAction};
+#[cfg(feature="runtime")]
+pub use framed::{FramedReader, FramedWriter, FramedError, LinkedFramedReader,
+                 ConnectionClosed, ReaderControl};
+#[cfg(feature="runtime")]
+pub use reconnect::{ReconnectingClient, ReconnectingClientBuilder, Backoff,
+                    Connected, Disconnected, DisconnectReason};
+#[cfg(feature="runtime")]
+pub use server::{TcpServer, TcpServerHandle, SocketConfig};
+#[cfg(feature="runtime")]
+pub use loadgen::{LoadGen, GetReport, LoadReport};
+#[cfg(feature="runtime")]
 pub use stream::StreamHandler;
+#[cfg(feature="runtime")]
 pub use sync::{SyncContext, SyncArbiter};
-pub use registry::{Registry, SystemRegistry, ArbiterService, SystemService};
-pub use system::{System, SystemRunner};
-pub use supervisor::Supervisor;
+#[cfg(feature="runtime")]
+pub use registry::{Registry, SystemRegistry, ArbiterService, SystemService, Lazy};
+#[cfg(feature="runtime")]
+pub use system::{System, SystemRunner, SystemConfig, PumpReport, ShutdownReport,
+                 SystemExitReason};
+#[cfg(feature="runtime")]
+pub use supervisor::{Supervisor, RestartStrategy};
+#[cfg(feature="runtime")]
+pub use tree::{SupervisorTree, ChildTerminated, ChildId, TerminationReason, TreeStrategy};
+#[cfg(feature="runtime")]
+pub use redelivery::RestartPolicy;
+#[cfg(feature="runtime")]
+pub use persist::{StateStore, FileStore};
+#[cfg(feature="runtime")]
+pub use handoff::Handoff;
+#[cfg(feature="runtime")]
+pub use msgregistry::{MessageRegistry, MessageInfo, RegistryError};
 
+#[cfg(feature="runtime")]
 #[doc(hidden)]
 pub use context::ContextFutureSpawner;
 
+#[cfg(feature="runtime")]
 pub mod prelude {
 //! The `actix` prelude
 //!
@@ -100,29 +237,41 @@ pub mod prelude {
     pub use actix_derive::*;
 
     pub use fut::{ActorFuture, ActorStream, WrapFuture, WrapStream};
-    pub use actor::{Actor, ActorState, ActorContext, AsyncContext, Supervised, SpawnHandle};
+    pub use actor::{Actor, ActorState, ActorContext, AsyncContext, Supervised, SpawnHandle,
+                    TimerGroup};
     pub use arbiter::Arbiter;
-    pub use address::{Address, SyncAddress, SendError, MailboxError};
+    pub use builder::ActorBuilder;
+    pub use address::{Address, SyncAddress, SendError, MailboxError, Overflow};
+    pub use address::{WeakAddress, WeakSyncAddress, Recipient, SyncRecipient};
     pub use context::{Context, ContextFutureSpawner};
     pub use framed::{FramedReader, FramedWriter, FramedError};
     pub use registry::{ArbiterService, SystemService};
     pub use stream::StreamHandler;
-    pub use handler::{Handler, Response, ResponseType,
-                      MessageResult, ResponseFuture, ResponseActFuture};
+    pub use handler::{Handler, Response, ResponseType, ExclusiveResponse, MustComplete,
+                      MessageResult, ResponseFuture, ResponseActFuture, BlockingResponse,
+                      ForwardResponse, ForwardMode};
     pub use system::System;
     pub use sync::{SyncContext, SyncArbiter};
-    pub use supervisor::Supervisor;
+    pub use supervisor::{Supervisor, RestartStrategy};
+    pub use tree::{SupervisorTree, ChildTerminated, ChildId, TerminationReason, TreeStrategy};
 
     pub mod actix {
         pub use prelude::*;
         pub use fut;
         pub use msgs;
         pub use address::{Subscriber, ActorAddress};
-        pub use registry::{ArbiterService, SystemService};
-        pub use utils::Condition;
+        pub use registry::{ArbiterService, SystemService, Lazy};
+        pub use utils::{Condition, FanOut, FanOutStrategy, Rooms, Router, RoutingStrategy, Shards,
+                        StartupBarrier, RateLimiter, Decision, CircuitBreaker,
+                        BreakerState, CircuitError};
+        pub use reconnect::{ReconnectingClient, Backoff, Connected, Disconnected,
+                            DisconnectReason};
+        pub use server::{TcpServer, TcpServerHandle, SocketConfig};
+        pub use loadgen::{LoadGen, GetReport, LoadReport};
     }
 }
 
+#[cfg(feature="runtime")]
 pub mod dev {
 //! The `actix` prelude for library developers
 //!
@@ -132,14 +281,73 @@ pub mod dev {
 //! ```
 //! # #![allow(unused_imports)]
 //! use actix::dev::*;
+//! ```
+//!
+//! It also exposes channel used by actor's mailbox, so messages could be
+//! delivered from custom transport. Messages get packed with
+//! `RemoteEnvelope::envelope()` and pushed to the sender half:
+//!
+//! ```rust
+//! # extern crate actix;
+//! # extern crate futures;
+//! use futures::{future, Future};
+//! use actix::dev::*;
+//! use actix::msgs::SystemExit;
+//!
+//! struct Sum(usize);
+//!
+//! struct Add(usize);
+//!
+//! impl ResponseType for Add {
+//!     type Item = usize;
+//!     type Error = ();
+//! }
+//!
+//! impl Actor for Sum {
+//!     type Context = Context<Self>;
+//! }
+//!
+//! impl Handler<Add> for Sum {
+//!     type Result = MessageResult<Add>;
+//!
+//!     fn handle(&mut self, msg: Add, _: &mut Context<Self>) -> Self::Result {
+//!         self.0 += msg.0;
+//!         Ok(self.0)
+//!     }
+//! }
+//!
+//! fn main() {
+//!     let sys = System::new("test");
+//!
+//!     let (tx, rx) = MailboxReceiver::channel(16);
+//!     let _: () = Sum::create_with_receiver(rx, |_| Sum(0));
+//!
+//!     let env: Envelope<Sum> = RemoteEnvelope::envelope(Add(1), None).into();
+//!     tx.try_send_envelope(env, false).ok().unwrap();
+//!
+//!     // response is delivered through oneshot channel
+//!     let (res_tx, res_rx) = futures::sync::oneshot::channel();
+//!     let env: Envelope<Sum> = RemoteEnvelope::envelope(Add(2), Some(res_tx)).into();
+//!     tx.try_send_envelope(env, false).ok().unwrap();
+//!
+//!     Arbiter::handle().spawn(res_rx.then(|res| {
+//!         assert_eq!(res.unwrap(), Ok(3));
+//!         Arbiter::system().do_send(SystemExit(0));
+//!         future::ok(())
+//!     }));
+//!     sys.run();
+//! }
 //! ```
 
     pub use prelude::*;
     pub use prelude::actix::*;
 
     pub use contextimpl::ContextImpl;
-    pub use handler::{MessageResponse, ResponseChannel};
-    pub use address::{ActorAddress, SendError,
-                      Envelope, ToEnvelope, RemoteEnvelope, Request,
-                      LocalRequest, LocalFutRequest};
+    pub use handler::{MessageResponse, ResponseChannel, abandoned_responses};
+    pub use address::{ActorAddress, AddressSender, SendError,
+                      Envelope, ToEnvelope, RemoteEnvelope, Request, RequestFut,
+                      LocalRequest, LocalFutRequest, LocalSendFut, DeliveryFuture, SendFut,
+                      RequestMap, RequestMapErr, RequestFlatten};
+    pub use mailbox::{HandlerStat, MailboxReceiver, TraceEvent, TraceKind};
+    pub use middleware::{ActorMiddleware, MiddlewareAction};
 }
@@ -0,0 +1,394 @@
+use std::io;
+use std::cmp;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::time::{Duration, Instant};
+
+use futures::Future;
+use tokio_core::net::TcpStream;
+use tokio_io::AsyncRead;
+use tokio_io::codec::{Encoder, Decoder};
+
+use actor::{Actor, AsyncContext, TimerGroup};
+use actors::{Connect, Connector};
+use address::Address;
+use context::Context;
+use framed::FramedError;
+use handler::{Handler, ResponseType, MessageResult};
+use msgs::StopActor;
+use reconnect::{ReconnectingClient, Backoff, Connected, Disconnected};
+use registry::ArbiterService;
+use stream::StreamHandler;
+
+/// How often new requests get dispatched
+const TICK: Duration = Duration::from_millis(10);
+
+/// Load generator for framed servers
+///
+/// Generator opens configured number of connections to the target through
+/// `Connector` service, connections reconnect automatically. Requests
+/// produced by the factory are spread over connections at configured rate.
+/// Server is expected to answer every request with exactly one frame,
+/// responses are matched to requests in order they were sent over
+/// connection. Number of unanswered requests per connection is limited by
+/// pipeline depth, if every connection is saturated generator waits and
+/// catches up with the rate later.
+///
+/// Requests that were in flight when connection got lost, responses without
+/// request and requests that could not be sent are counted as errors.
+/// Use `GetReport` message to get current statistics.
+///
+/// ```rust,ignore
+/// let gen: Address<_> = LoadGen::new("127.0.0.1:8080", LineCodec, |n| format!("ping {}", n))
+///     .connections(10)
+///     .rate(1000)
+///     .requests(10_000)
+///     .start();
+///
+/// gen.call_fut(GetReport).map(|report| println!("p99: {:?}", report.unwrap().p99));
+/// ```
+pub struct LoadGen<C> where C: Encoder + Decoder + Clone + 'static {
+    target: String,
+    codec: C,
+    factory: Box<FnMut(usize) -> <C as Encoder>::Item>,
+    connections: usize,
+    rate: usize,
+    requests: usize,
+    depth: usize,
+    backoff: Backoff,
+    conns: Vec<Address<Conn<C>>>,
+    stats: Rc<RefCell<Stats>>,
+    dispatched: usize,
+    next: usize,
+    started: Option<Instant>,
+    group: Option<TimerGroup>,
+}
+
+impl<C> LoadGen<C> where C: Encoder + Decoder + Clone + 'static {
+    /// Create load generator for `target` address
+    ///
+    /// `target` is resolved with `Connector` service. `factory` is called with
+    /// sequence number of every request.
+    pub fn new<T, F>(target: T, codec: C, factory: F) -> LoadGen<C>
+        where T: Into<String>, F: FnMut(usize) -> <C as Encoder>::Item + 'static
+    {
+        LoadGen {
+            target: target.into(),
+            codec: codec,
+            factory: Box::new(factory),
+            connections: 1,
+            rate: 100,
+            requests: 0,
+            depth: 16,
+            backoff: Backoff::exponential(Duration::from_millis(10), Duration::from_secs(1)),
+            conns: Vec::new(),
+            stats: Rc::new(RefCell::new(Stats::default())),
+            dispatched: 0,
+            next: 0,
+            started: None,
+            group: None,
+        }
+    }
+
+    /// Set number of concurrent connections, by default one connection is used
+    pub fn connections(mut self, connections: usize) -> Self {
+        self.connections = cmp::max(connections, 1);
+        self
+    }
+
+    /// Set number of requests per second, by default 100 requests per second
+    ///
+    /// `0` sends requests as fast as pipeline depth allows.
+    pub fn rate(mut self, rate: usize) -> Self {
+        self.rate = rate;
+        self
+    }
+
+    /// Stop sending after `requests` requests, by default generator runs
+    /// until it gets stopped
+    pub fn requests(mut self, requests: usize) -> Self {
+        self.requests = requests;
+        self
+    }
+
+    /// Set number of unanswered requests per connection, by default 16
+    pub fn pipeline_depth(mut self, depth: usize) -> Self {
+        self.depth = cmp::max(depth, 1);
+        self
+    }
+
+    /// Set reconnect delay policy
+    ///
+    /// By default exponential backoff from 10 milliseconds to 1 second is used.
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    fn dispatch(&mut self, ctx: &mut Context<Self>) {
+        let due = if self.rate == 0 {
+            usize::max_value()
+        } else {
+            let elapsed = self.started.map(|at| at.elapsed()).unwrap_or_default();
+            let millis = elapsed.as_secs() as usize * 1000
+                + (elapsed.subsec_nanos() / 1_000_000) as usize;
+            self.rate.saturating_mul(millis) / 1000
+        };
+        let due = if self.requests == 0 { due } else { cmp::min(due, self.requests) };
+
+        while self.dispatched < due {
+            // pick next connection that has room in its pipeline
+            let idx = {
+                let stats = self.stats.borrow();
+                let n = self.conns.len();
+                match (0..n).map(|i| (self.next + i) % n)
+                    .find(|idx| stats.pending[*idx] < self.depth)
+                {
+                    Some(idx) => idx,
+                    None => break,
+                }
+            };
+            let item = (self.factory)(self.dispatched);
+            self.stats.borrow_mut().pending[idx] += 1;
+            self.conns[idx].do_send(Fire::<C>(item));
+            self.dispatched += 1;
+            self.next = idx + 1;
+        }
+
+        if self.requests != 0 && self.dispatched == self.requests {
+            if let Some(group) = self.group.take() {
+                ctx.cancel_group(group);
+            }
+        }
+    }
+}
+
+impl<C> Actor for LoadGen<C> where C: Encoder + Decoder + Clone + 'static {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        self.stats.borrow_mut().pending = vec![0; self.connections];
+        for idx in 0..self.connections {
+            self.conns.push(Conn {
+                idx: idx,
+                target: self.target.clone(),
+                codec: self.codec.clone(),
+                depth: self.depth,
+                backoff: self.backoff,
+                client: None,
+                connected: false,
+                in_flight: VecDeque::new(),
+                stats: Rc::clone(&self.stats),
+            }.start());
+        }
+        self.started = Some(Instant::now());
+
+        let group = ctx.timer_group();
+        let handle = ctx.run_interval_at(Instant::now() + TICK, TICK, |act, ctx| act.dispatch(ctx));
+        ctx.add_to_group(group, handle);
+        self.group = Some(group);
+    }
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        for conn in self.conns.drain(..) {
+            conn.do_send(StopActor);
+        }
+    }
+}
+
+/// Get load generator statistics, see `LoadGen`
+pub struct GetReport;
+
+impl ResponseType for GetReport {
+    type Item = LoadReport;
+    type Error = ();
+}
+
+/// Load generator statistics
+///
+/// Latency is measured from the moment request is written to connection
+/// until its response is decoded, percentiles are zero if no response
+/// is received yet.
+#[derive(Debug, Clone, PartialEq)]
+pub struct LoadReport {
+    /// Number of requests passed to connections
+    pub sent: usize,
+    /// Number of received responses
+    pub received: usize,
+    /// Number of failed requests and unexpected responses
+    pub errors: usize,
+    /// Number of requests that wait for response
+    pub in_flight: usize,
+    /// Number of established connections, including reconnects
+    pub connects: usize,
+    /// Time since generator started
+    pub elapsed: Duration,
+    /// Median latency
+    pub p50: Duration,
+    /// 90th percentile latency
+    pub p90: Duration,
+    /// 99th percentile latency
+    pub p99: Duration,
+    /// Maximum latency
+    pub max: Duration,
+}
+
+impl<C> Handler<GetReport> for LoadGen<C> where C: Encoder + Decoder + Clone + 'static {
+    type Result = MessageResult<GetReport>;
+
+    fn handle(&mut self, _: GetReport, _: &mut Context<Self>) -> Self::Result {
+        let mut stats = self.stats.borrow_mut();
+        stats.latencies.sort();
+        let latencies = &stats.latencies;
+
+        Ok(LoadReport {
+            sent: stats.sent,
+            received: latencies.len(),
+            errors: stats.errors,
+            in_flight: stats.pending.iter().sum(),
+            connects: stats.connects,
+            elapsed: self.started.map(|at| at.elapsed()).unwrap_or_default(),
+            p50: percentile(latencies, 0.5),
+            p90: percentile(latencies, 0.9),
+            p99: percentile(latencies, 0.99),
+            max: latencies.last().cloned().unwrap_or_default(),
+        })
+    }
+}
+
+/// Nearest-rank percentile of sorted latencies
+fn percentile(sorted: &[Duration], q: f64) -> Duration {
+    if sorted.is_empty() {
+        return Duration::default()
+    }
+    let rank = (q * sorted.len() as f64).ceil() as usize;
+    sorted[cmp::min(cmp::max(rank, 1), sorted.len()) - 1]
+}
+
+#[derive(Default)]
+struct Stats {
+    sent: usize,
+    errors: usize,
+    connects: usize,
+    pending: Vec<usize>,
+    latencies: Vec<Duration>,
+}
+
+/// Request for a single connection
+struct Fire<C: Encoder>(<C as Encoder>::Item);
+
+impl<C: Encoder> ResponseType for Fire<C> {
+    type Item = ();
+    type Error = ();
+}
+
+/// Single connection of load generator
+struct Conn<C> where C: Encoder + Decoder {
+    idx: usize,
+    target: String,
+    codec: C,
+    depth: usize,
+    backoff: Backoff,
+    client: Option<ReconnectingClient<TcpStream, C>>,
+    connected: bool,
+    in_flight: VecDeque<Instant>,
+    stats: Rc<RefCell<Stats>>,
+}
+
+impl<C> Conn<C> where C: Encoder + Decoder {
+    /// Requests are not answered, count them as errors
+    fn fail(&mut self, count: usize) {
+        let mut stats = self.stats.borrow_mut();
+        stats.errors += count;
+        stats.pending[self.idx] -= count;
+    }
+}
+
+impl<C> Actor for Conn<C> where C: Encoder + Decoder + Clone + 'static {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        let target = self.target.clone();
+        let codec = self.codec.clone();
+
+        self.client = Some(
+            ReconnectingClient::build(move || {
+                let codec = codec.clone();
+                Box::new(Connector::from_registry()
+                         .call_fut(Connect::host(target.as_str()))
+                         .then(move |res| match res {
+                             Ok(Ok(stream)) => Ok(stream.framed(codec)),
+                             Ok(Err(err)) =>
+                                 Err(io::Error::new(io::ErrorKind::Other, format!("{}", err))),
+                             Err(_) =>
+                                 Err(io::Error::new(io::ErrorKind::Other, "Connector is gone")),
+                         }))
+            })
+            .backoff(self.backoff)
+            .buffer(self.depth)
+            .start(ctx));
+    }
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        if let Some(mut client) = self.client.take() {
+            client.close();
+        }
+        let lost = self.in_flight.len();
+        self.in_flight.clear();
+        self.fail(lost);
+    }
+}
+
+impl<C> Handler<Fire<C>> for Conn<C> where C: Encoder + Decoder + Clone + 'static {
+    type Result = ();
+
+    fn handle(&mut self, msg: Fire<C>, _: &mut Context<Self>) {
+        match self.client.as_mut().map(|client| client.send(msg.0)) {
+            Some(Ok(())) => {
+                self.in_flight.push_back(Instant::now());
+                self.stats.borrow_mut().sent += 1;
+            }
+            _ => self.fail(1),
+        }
+    }
+}
+
+impl<C> StreamHandler<<C as Decoder>::Item, FramedError<C>> for Conn<C>
+    where C: Encoder + Decoder + Clone + 'static
+{
+    fn handle(&mut self, _: <C as Decoder>::Item, _: &mut Context<Self>) {
+        if let Some(sent) = self.in_flight.pop_front() {
+            let mut stats = self.stats.borrow_mut();
+            stats.latencies.push(sent.elapsed());
+            stats.pending[self.idx] -= 1;
+        } else {
+            // response without request
+            self.stats.borrow_mut().errors += 1;
+        }
+    }
+}
+
+impl<C> Handler<Connected> for Conn<C> where C: Encoder + Decoder + Clone + 'static {
+    type Result = ();
+
+    fn handle(&mut self, _: Connected, _: &mut Context<Self>) {
+        self.connected = true;
+        self.stats.borrow_mut().connects += 1;
+    }
+}
+
+impl<C> Handler<Disconnected<C>> for Conn<C> where C: Encoder + Decoder + Clone + 'static {
+    type Result = ();
+
+    fn handle(&mut self, _: Disconnected<C>, _: &mut Context<Self>) {
+        // requests written to lost connection never get response,
+        // requests sent while disconnected are buffered by client
+        if self.connected {
+            self.connected = false;
+            let lost = self.in_flight.len();
+            self.in_flight.clear();
+            self.fail(lost);
+        }
+    }
+}
@@ -1,7 +1,26 @@
+use std::cmp;
+use std::cell::Cell;
+#[cfg(feature="spill")]
+use std::io;
+#[cfg(feature="spill")]
+use std::path::PathBuf;
+use std::collections::{HashMap, VecDeque};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use std::thread::{self, ThreadId};
+use std::time::{Duration, Instant};
 use futures::{Async, Stream};
 
 use actor::{Actor, AsyncContext};
-use address::{sync_channel, Address, LocalAddrReceiver, SyncAddress, SyncAddressReceiver};
+use arbiter::Arbiter;
+use budget;
+use pump;
+use middleware::{self, ActorMiddleware};
+use address::{sync_channel, Address, AddressSender, EnvelopeProxy, LocalAddrReceiver,
+              Overflow, SendError, SyncAddress, SyncAddressReceiver};
+use handler::{Handler, ResponseType};
+#[cfg(feature="spill")]
+use address::spill::Spill;
 
 /// Maximum number of consecutive polls in a loop
 const MAX_SYNC_POLLS: u32 = 256;
@@ -9,19 +28,288 @@ const MAX_SYNC_POLLS: u32 = 256;
 /// Default address channel capacity
 pub const DEFAULT_CAPACITY: usize = 16;
 
+/// Capacity of the system lane, separate queue for framework messages
+pub const SYSTEM_LANE_CAPACITY: usize = 4;
+
+
+/// Handling statistics for specific message type
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct HandlerStat {
+    /// Number of handled messages
+    pub count: usize,
+    /// Total time spent in message handler
+    pub total: Duration,
+    /// Longest handler execution time
+    pub max: Duration,
+}
+
+impl HandlerStat {
+    /// Average handler execution time
+    pub fn average(&self) -> Duration {
+        if self.count == 0 {
+            Duration::new(0, 0)
+        } else {
+            self.total / self.count as u32
+        }
+    }
+
+    fn record(&mut self, dur: Duration) {
+        self.count += 1;
+        self.total += dur;
+        if dur > self.max {
+            self.max = dur;
+        }
+    }
+}
+
+/// Handler execution time tracking
+#[derive(Default)]
+struct Timing {
+    /// Statistics per message type, `None` until statistics get enabled
+    stats: Option<HashMap<&'static str, HandlerStat>>,
+    /// Handlers running longer than threshold get logged
+    slow: Option<Duration>,
+}
+
+impl Timing {
+    fn enabled(&self) -> bool {
+        self.stats.is_some() || self.slow.is_some()
+    }
+
+    fn record(&mut self, tp: &'static str, dur: Duration) {
+        if let Some(ref mut stats) = self.stats {
+            stats.entry(tp).or_insert_with(HandlerStat::default).record(dur);
+        }
+        match self.slow {
+            Some(slow) if dur > slow => warn!("Slow handler of {}: {:?}", tp, dur),
+            _ => (),
+        }
+    }
+}
+
+/// Mailbox trace event kind
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum TraceKind {
+    /// Message is placed to the mailbox
+    Enqueue,
+    /// Message is taken from the mailbox
+    Dequeue,
+    /// Message handler is called
+    HandlerStart,
+    /// Message handler returned
+    HandlerFinish,
+    /// Message is dropped because of mailbox overflow
+    Dropped,
+}
+
+/// Mailbox trace event, see `Context::enable_mailbox_trace()`
+#[derive(Debug, Clone)]
+pub struct TraceEvent {
+    /// Event kind
+    pub kind: TraceKind,
+    /// Type name of the message
+    pub message_type: &'static str,
+    /// Thread that caused event, for `Enqueue` event it is sender's thread
+    pub thread: ThreadId,
+    /// Time of the event
+    pub at: Instant,
+}
+
+/// Bounded log of mailbox events, shared between mailbox and its channels
+pub(crate) struct MailboxTrace {
+    enabled: AtomicBool,
+    events: Mutex<(usize, VecDeque<TraceEvent>)>,
+}
+
+impl MailboxTrace {
+    pub fn new() -> Arc<MailboxTrace> {
+        Arc::new(MailboxTrace{enabled: AtomicBool::new(false),
+                              events: Mutex::new((0, VecDeque::new()))})
+    }
+
+    /// Keep last `n` events, zero disables tracing
+    pub fn enable(&self, n: usize) {
+        let mut events = self.events.lock().unwrap();
+        events.0 = n;
+        while events.1.len() > n {
+            events.1.pop_front();
+        }
+        self.enabled.store(n != 0, Ordering::Relaxed);
+    }
+
+    #[inline]
+    pub fn record(&self, kind: TraceKind, message_type: &'static str) {
+        if !self.enabled.load(Ordering::Relaxed) {
+            return
+        }
+        let mut events = self.events.lock().unwrap();
+        if events.0 == 0 {
+            return
+        }
+        if events.1.len() == events.0 {
+            events.1.pop_front();
+        }
+        events.1.push_back(TraceEvent{kind: kind,
+                                      message_type: message_type,
+                                      thread: thread::current().id(),
+                                      at: Instant::now()});
+    }
+
+    pub fn events(&self) -> Vec<TraceEvent> {
+        self.events.lock().unwrap().1.iter().cloned().collect()
+    }
+
+    /// Identity of the mailbox, trace is shared by all channels of actor
+    #[inline]
+    pub fn mailbox_id(&self) -> usize {
+        self as *const MailboxTrace as usize
+    }
+}
+
+/// Attached mailbox token, see `Context::attach_mailbox()`
+#[derive(Eq, PartialEq, Debug, Copy, Clone, Hash)]
+pub struct MailboxHandle(usize);
+
+/// Receiving side of a mailbox that is not bound to an actor yet
+///
+/// Address could be handed out before actor exists, messages are queued
+/// until receiver is attached to actor's context with
+/// `Context::attach_mailbox()`.
+pub struct MailboxReceiver<A: Actor> {
+    rx: SyncAddressReceiver<A>,
+}
+
+impl<A: Actor> MailboxReceiver<A> {
+    /// Create mailbox with default capacity
+    pub fn new() -> (SyncAddress<A>, MailboxReceiver<A>) {
+        MailboxReceiver::with_capacity(DEFAULT_CAPACITY)
+    }
+
+    /// Create mailbox with specific capacity
+    pub fn with_capacity(cap: usize) -> (SyncAddress<A>, MailboxReceiver<A>) {
+        let (tx, rx) = sync_channel::channel(cap);
+        (SyncAddress::new(tx), MailboxReceiver{rx: rx})
+    }
+
+    /// Create detached channel pair with specific capacity
+    ///
+    /// Sender accepts packed envelopes, receiver could be passed
+    /// to `Actor::create_with_receiver()` or `Context::attach_mailbox()`.
+    pub fn channel(cap: usize) -> (AddressSender<A>, MailboxReceiver<A>) {
+        let (tx, rx) = sync_channel::channel(cap);
+        (tx, MailboxReceiver{rx: rx})
+    }
+
+    pub(crate) fn into_inner(self) -> SyncAddressReceiver<A> {
+        self.rx
+    }
+}
 
 pub(crate) struct Mailbox<A> where A: Actor, A::Context: AsyncContext<A> {
     sync_msgs: Option<SyncAddressReceiver<A>>,
+    attached: Vec<(MailboxHandle, SyncAddressReceiver<A>)>,
+    next_handle: usize,
     unsync_msgs: LocalAddrReceiver<A>,
+    timing: Timing,
+    trace: Arc<MailboxTrace>,
+    middlewares: Vec<Box<ActorMiddleware<A>>>,
+    deferred: Deferred<A>,
+    inflight: Inflight<A>,
+}
+
+type Proxy<A> = Box<EnvelopeProxy<Actor=A>>;
+
+/// Messages parked by `Context::defer()`
+struct Deferred<A> {
+    queues: HashMap<&'static str, VecDeque<Proxy<A>>>,
+    resumed: VecDeque<Proxy<A>>,
+}
+
+impl<A> Deferred<A> {
+    fn new() -> Self {
+        Deferred{queues: HashMap::new(), resumed: VecDeque::new()}
+    }
+
+    /// Park message if its type is deferred, returns message back otherwise.
+    /// Message is dropped if queue already holds `cap` messages.
+    fn park(&mut self, tp: &'static str, env: Proxy<A>,
+            cap: usize, trace: &MailboxTrace) -> Option<Proxy<A>> {
+        if let Some(queue) = self.queues.get_mut(tp) {
+            if queue.len() < cap {
+                queue.push_back(env);
+            } else {
+                trace.record(TraceKind::Dropped, tp);
+            }
+            None
+        } else {
+            Some(env)
+        }
+    }
+}
+
+/// Message that is being handled, see `RestartPolicy::KeepMailbox`
+///
+/// Envelope stays in the mailbox while its handler runs. If handler fails,
+/// envelope with number of delivery attempts survives actor restart.
+struct Inflight<A> {
+    max_attempts: Option<u32>,
+    env: Option<(Proxy<A>, u32)>,
+}
+
+impl<A> Inflight<A> where A: Actor {
+    fn new() -> Self {
+        Inflight{max_attempts: None, env: None}
+    }
+
+    /// Call `f` with envelope, envelope is kept until `f` returns
+    fn run<F>(&mut self, mut env: Proxy<A>, attempts: u32, f: F) where F: FnOnce(&mut Proxy<A>) {
+        if self.max_attempts.is_none() {
+            return f(&mut env)
+        }
+        self.env = Some((env, attempts + 1));
+        if let Some((ref mut env, _)) = self.env {
+            f(env);
+        }
+        self.env = None;
+    }
+
+    /// Envelope that was handled during actor failure, it gets
+    /// quarantined if handler failed `max_attempts` times
+    fn take_failed(&mut self, trace: &MailboxTrace) -> Option<(Proxy<A>, u32)> {
+        let (mut env, attempts) = match self.env.take() {
+            Some(item) => item,
+            None => return None,
+        };
+        match self.max_attempts {
+            Some(max) if attempts < max => Some((env, attempts)),
+            _ => {
+                let tp = env.type_name();
+                warn!("Message {} is quarantined after {} failed attempts", tp, attempts);
+                trace.record(TraceKind::Dropped, tp);
+                env.quarantine(attempts);
+                None
+            }
+        }
+    }
 }
 
 impl<A> Default for Mailbox<A> where A: Actor, A::Context: AsyncContext<A> {
 
     #[inline]
     fn default() -> Self {
-        Mailbox {
+        let trace = MailboxTrace::new();
+        let mut mailbox = Mailbox {
             sync_msgs: None,
-            unsync_msgs: LocalAddrReceiver::new(DEFAULT_CAPACITY) }
+            attached: Vec::new(),
+            next_handle: 0,
+            unsync_msgs: LocalAddrReceiver::new(DEFAULT_CAPACITY, Arc::clone(&trace)),
+            timing: Timing::default(),
+            trace: trace,
+            middlewares: Vec::new(),
+            deferred: Deferred::new(),
+            inflight: Inflight::new() };
+        mailbox.apply_system_config();
+        mailbox
     }
 }
 
@@ -38,9 +326,28 @@ impl<A> Mailbox<A> where A: Actor, A::Context: AsyncContext<A>
 {
     #[inline]
     pub fn new(rx: SyncAddressReceiver<A>) -> Self {
-        Mailbox {
+        let trace = rx.trace();
+        let mut mailbox = Mailbox {
             sync_msgs: Some(rx),
-            unsync_msgs: LocalAddrReceiver::new(16) }
+            attached: Vec::new(),
+            next_handle: 0,
+            unsync_msgs: LocalAddrReceiver::new(DEFAULT_CAPACITY, Arc::clone(&trace)),
+            timing: Timing::default(),
+            trace: trace,
+            middlewares: Vec::new(),
+            deferred: Deferred::new(),
+            inflight: Inflight::new() };
+        mailbox.apply_system_config();
+        mailbox
+    }
+
+    /// Use mailbox defaults of the running system, see `SystemConfig`
+    fn apply_system_config(&mut self) {
+        if let Some(cfg) = Arbiter::system_config() {
+            self.set_capacity(cfg.capacity);
+            self.set_overflow(cfg.overflow);
+            self.timing.slow = cfg.slow_handler;
+        }
     }
 
     pub fn capacity(&self) -> usize {
@@ -51,16 +358,192 @@ impl<A> Mailbox<A> where A: Actor, A::Context: AsyncContext<A>
         self.unsync_msgs.set_capacity(cap);
         self.sync_msgs.as_mut().map(|msgs| msgs.set_capacity(cap));
     }
-    
+
+    pub fn set_overflow(&mut self, overflow: Overflow) {
+        self.unsync_msgs.set_overflow(overflow);
+        self.sync_msgs.as_mut().map(|msgs| msgs.set_overflow(overflow));
+    }
+
+    /// Start collecting message handling statistics
+    pub fn enable_stats(&mut self) {
+        if self.timing.stats.is_none() {
+            self.timing.stats = Some(HashMap::new());
+        }
+    }
+
+    /// Message handling statistics, empty if statistics is not enabled
+    pub fn stats(&self) -> HashMap<&'static str, HandlerStat> {
+        self.timing.stats.clone().unwrap_or_default()
+    }
+
+    /// Start recording last `n` mailbox events, zero stops recording
+    pub fn enable_trace(&mut self, n: usize) {
+        self.trace.enable(n);
+    }
+
+    /// Recorded mailbox events, oldest first
+    pub fn trace(&self) -> Vec<TraceEvent> {
+        self.trace.events()
+    }
+
+    /// Identity of the mailbox, see `MailboxTrace::mailbox_id()`
+    pub fn id(&self) -> usize {
+        self.trace.mailbox_id()
+    }
+
+    /// Type names of messages waiting for dispatch, resumed messages first
+    pub fn queued_types(&self) -> Vec<&'static str> {
+        let mut types: Vec<_> = self.deferred.resumed.iter().map(|env| env.type_name()).collect();
+        types.extend(self.unsync_msgs.queued_types());
+        if let Some(ref msgs) = self.sync_msgs {
+            types.extend(msgs.queued_types());
+        }
+        types
+    }
+
+    /// Number of messages waiting in the mailbox and time when
+    /// the oldest of them was sent
+    pub fn status(&self) -> (usize, Option<Instant>) {
+        let mut len = self.unsync_msgs.len();
+        let mut oldest = self.unsync_msgs.oldest();
+        if let Some(ref msgs) = self.sync_msgs {
+            len += msgs.len();
+            oldest = match (oldest, msgs.oldest()) {
+                (Some(a), Some(b)) => Some(cmp::min(a, b)),
+                (a, b) => a.or(b),
+            };
+        }
+        (len, oldest)
+    }
+
+    /// Check if mailbox holds messages waiting for dispatch, including
+    /// system lane and attached mailboxes
+    pub fn has_pending(&self) -> bool {
+        self.status().0 != 0 || !self.deferred.resumed.is_empty() ||
+            self.unsync_msgs.system_len() != 0 ||
+            self.sync_msgs.as_ref().map(|msgs| msgs.system_len() != 0).unwrap_or(false) ||
+            self.attached.iter().any(|&(_, ref msgs)| msgs.len() != 0)
+    }
+
+    /// Install middleware, it wraps dispatch of every message
+    pub fn add_middleware(&mut self, mw: Box<ActorMiddleware<A>>) {
+        self.middlewares.push(mw);
+    }
+
+    /// Park messages of type `tp` instead of dispatching them
+    pub fn defer(&mut self, tp: &'static str) {
+        self.deferred.queues.entry(tp).or_insert_with(VecDeque::new);
+    }
+
+    /// Dispatch parked messages of type `tp` before new messages
+    pub fn resume(&mut self, tp: &'static str) {
+        if let Some(queue) = self.deferred.queues.remove(tp) {
+            self.deferred.resumed.extend(queue);
+        }
+    }
+
+    /// Keep envelope while its handler runs and deliver it again after
+    /// actor failure, at most `max_attempts` times
+    pub fn set_max_attempts(&mut self, max_attempts: Option<u32>) {
+        self.inflight.max_attempts = max_attempts;
+        if max_attempts.is_none() {
+            self.inflight.env = None;
+        }
+    }
+
+    /// Take resumed and parked messages out of the mailbox,
+    /// message types are not deferred anymore
+    pub fn take_deferred(&mut self) -> Vec<Proxy<A>> {
+        let mut msgs: Vec<_> = self.deferred.resumed.drain(..).collect();
+        for (_, queue) in self.deferred.queues.drain() {
+            msgs.extend(queue);
+        }
+        msgs
+    }
+
+    /// Spill messages that do not fit into mailbox to disk
+    #[cfg(feature="spill")]
+    pub fn spill_to_disk(&mut self, path: PathBuf, max_bytes: u64) -> io::Result<()> {
+        let spill = Spill::new(path, max_bytes)?;
+        if self.sync_msgs.is_none() {
+            // sender is not needed yet, address is created on demand
+            let (_, mut rx) = sync_channel::channel_with_trace(
+                self.unsync_msgs.capacity(), Arc::clone(&self.trace));
+            rx.set_overflow(self.unsync_msgs.overflow());
+            self.sync_msgs = Some(rx);
+        }
+        if let Some(ref mut msgs) = self.sync_msgs {
+            msgs.set_spill(spill);
+        }
+        Ok(())
+    }
+
+    /// Close mailbox and take all pending messages
+    ///
+    /// Resumed messages go first, then unsync and sync messages, parked
+    /// messages are last. Messages sent afterwards are dead-lettered.
+    pub fn drain(&mut self) -> Vec<Proxy<A>> {
+        let mut msgs: Vec<_> = self.deferred.resumed.drain(..).collect();
+        msgs.extend(self.unsync_msgs.drain().into_iter().map(|msg| msg.env));
+        if let Some(mut rx) = self.sync_msgs.take() {
+            msgs.extend(rx.drain().into_iter().map(|msg| msg.into_inner()));
+        }
+        for (_, mut rx) in self.attached.drain(..) {
+            msgs.extend(rx.drain().into_iter().map(|msg| msg.into_inner()));
+        }
+        for (_, queue) in self.deferred.queues.drain() {
+            msgs.extend(queue);
+        }
+        msgs
+    }
+
+    /// Put message to the system lane
+    pub fn push_system<M>(&mut self, msg: M) -> Result<(), SendError<M>>
+        where A: Handler<M>, M: ResponseType + 'static
+    {
+        self.unsync_msgs.push_system(msg)
+    }
+
+    /// Release unused memory of unsync messages queue
+    pub fn shrink(&mut self) {
+        self.unsync_msgs.shrink();
+    }
+
     #[inline]
     pub fn connected(&self) -> bool {
         self.unsync_msgs.connected() ||
-            self.sync_msgs.as_ref().map(|msgs| msgs.connected()).unwrap_or(false)
+            self.sync_msgs.as_ref().map(|msgs| msgs.connected()).unwrap_or(false) ||
+            self.attached.iter().any(|&(_, ref msgs)| msgs.connected())
+    }
+
+    /// Receive messages of additional mailbox
+    pub fn attach(&mut self, rx: MailboxReceiver<A>) -> MailboxHandle {
+        self.next_handle += 1;
+        let handle = MailboxHandle(self.next_handle);
+        self.attached.push((handle, rx.rx));
+        handle
+    }
+
+    /// Close attached mailbox, returns `false` if mailbox is not attached
+    ///
+    /// Queued messages are handled before any new message.
+    pub fn detach(&mut self, handle: MailboxHandle) -> bool {
+        match self.attached.iter().position(|&(h, _)| h == handle) {
+            Some(idx) => {
+                let (_, mut rx) = self.attached.remove(idx);
+                self.deferred.resumed.extend(
+                    rx.drain().into_iter().map(|msg| msg.into_inner()));
+                true
+            }
+            None => false,
+        }
     }
 
     pub fn remote_address(&mut self) -> SyncAddress<A> {
         if self.sync_msgs.is_none() {
-            let (tx, rx) = sync_channel::channel(self.unsync_msgs.capacity());
+            let (tx, mut rx) = sync_channel::channel_with_trace(
+                self.unsync_msgs.capacity(), Arc::clone(&self.trace));
+            rx.set_overflow(self.unsync_msgs.overflow());
             self.sync_msgs = Some(rx);
             SyncAddress::new(tx)
         } else {
@@ -76,19 +559,67 @@ impl<A> Mailbox<A> where A: Actor, A::Context: AsyncContext<A>
         Address::new(self.unsync_msgs.sender())
     }
 
-    pub fn poll(&mut self, act: &mut A, ctx: &mut A::Context) {
+    /// Process pending messages, returns true if at least one message got handled
+    ///
+    /// Processing stops if context is waiting, `exclusive` response is pending
+    /// or mailbox used its share of context's poll budget.
+    pub fn poll(&mut self, act: &mut A, ctx: &mut A::Context, exclusive: &Cell<usize>) -> bool {
         let mut n_polls = NumPolls(0);
+        let mut handled = false;
+        let cap = self.unsync_msgs.capacity();
+        #[cfg(feature="spill")]
+        {
+            if let Some(ref mut msgs) = self.sync_msgs {
+                msgs.start_batch();
+            }
+        }
         loop {
             let mut not_ready = true;
 
             // unsync messages
             loop {
-                if ctx.waiting() { return }
+                if ctx.waiting() || exclusive.get() != 0 { return handled }
+
+                // system lane is drained before every ordinary message
+                if poll_system(&mut self.unsync_msgs, self.sync_msgs.as_mut(), act, ctx) {
+                    handled = true;
+                    continue
+                }
+
+                // mailbox used its share of context's poll budget
+                if !budget::has_budget() { return handled }
+
+                // message that was handled during actor failure goes first
+                if let Some((env, attempts)) = self.inflight.take_failed(&self.trace) {
+                    handled = true;
+                    let tp = env.type_name();
+                    dispatch(env, attempts, tp, &mut self.timing, &self.trace,
+                             &mut self.middlewares, &mut self.inflight, act, ctx);
+                    budget::consume();
+                    continue
+                }
+
+                // resumed messages go before new ones
+                if let Some(env) = self.deferred.resumed.pop_front() {
+                    handled = true;
+                    let tp = env.type_name();
+                    dispatch(env, 0, tp, &mut self.timing, &self.trace,
+                             &mut self.middlewares, &mut self.inflight, act, ctx);
+                    budget::consume();
+                    continue
+                }
 
                 match self.unsync_msgs.poll() {
-                    Ok(Async::Ready(Some(mut msg))) => {
+                    Ok(Async::Ready(Some(msg))) => {
                         not_ready = false;
-                        msg.env.handle(act, ctx);
+                        handled = true;
+                        let tp = msg.env.type_name();
+                        self.trace.record(TraceKind::Dequeue, tp);
+                        if let Some(env) = self.deferred.park(tp, msg.env, cap, &self.trace) {
+                            dispatch(env, 0, tp, &mut self.timing, &self.trace,
+                                     &mut self.middlewares, &mut self.inflight, act, ctx);
+                        }
+                        budget::consume();
                     }
                     Ok(Async::Ready(None)) | Ok(Async::NotReady) | Err(_) => break,
                 }
@@ -99,12 +630,45 @@ impl<A> Mailbox<A> where A: Actor, A::Context: AsyncContext<A>
             // sync messages
             if let Some(ref mut msgs) = self.sync_msgs {
                 loop {
-                    if ctx.waiting() { return }
+                    if ctx.waiting() || exclusive.get() != 0 { return handled }
+
+                    if poll_system(&mut self.unsync_msgs, Some(&mut *msgs), act, ctx) {
+                        handled = true;
+                        continue
+                    }
+
+                    if !budget::has_budget() { return handled }
+
+                    if let Some((env, attempts)) = self.inflight.take_failed(&self.trace) {
+                        handled = true;
+                        let tp = env.type_name();
+                        dispatch(env, attempts, tp, &mut self.timing, &self.trace,
+                                 &mut self.middlewares, &mut self.inflight, act, ctx);
+                        budget::consume();
+                        continue
+                    }
+
+                    if let Some(env) = self.deferred.resumed.pop_front() {
+                        handled = true;
+                        let tp = env.type_name();
+                        dispatch(env, 0, tp, &mut self.timing, &self.trace,
+                                 &mut self.middlewares, &mut self.inflight, act, ctx);
+                        budget::consume();
+                        continue
+                    }
 
                     match msgs.poll() {
-                        Ok(Async::Ready(Some(mut msg))) => {
+                        Ok(Async::Ready(Some(msg))) => {
                             not_ready = false;
-                            msg.handle(act, ctx);
+                            handled = true;
+                            let tp = msg.type_name();
+                            self.trace.record(TraceKind::Dequeue, tp);
+                            let env = msg.into_inner();
+                            if let Some(env) = self.deferred.park(tp, env, cap, &self.trace) {
+                                dispatch(env, 0, tp, &mut self.timing, &self.trace,
+                                         &mut self.middlewares, &mut self.inflight, act, ctx);
+                            }
+                            budget::consume();
                         }
                         Ok(Async::Ready(None)) | Ok(Async::NotReady) | Err(_) => break,
                     }
@@ -113,9 +677,94 @@ impl<A> Mailbox<A> where A: Actor, A::Context: AsyncContext<A>
                 }
             }
 
+            // attached mailboxes, one message from each in turn
+            let mut idx = 0;
+            let mut idle = 0;
+            while idle < self.attached.len() {
+                if ctx.waiting() || exclusive.get() != 0 { return handled }
+                if !budget::has_budget() { return handled }
+
+                if idx >= self.attached.len() {
+                    idx = 0;
+                }
+                match self.attached[idx].1.poll() {
+                    Ok(Async::Ready(Some(msg))) => {
+                        not_ready = false;
+                        handled = true;
+                        idle = 0;
+                        let tp = msg.type_name();
+                        self.trace.record(TraceKind::Dequeue, tp);
+                        let env = msg.into_inner();
+                        if let Some(env) = self.deferred.park(tp, env, cap, &self.trace) {
+                            dispatch(env, 0, tp, &mut self.timing, &self.trace,
+                                     &mut self.middlewares, &mut self.inflight, act, ctx);
+                        }
+                        budget::consume();
+                    }
+                    Ok(Async::Ready(None)) | Ok(Async::NotReady) | Err(_) => idle += 1,
+                }
+                idx += 1;
+            }
+
             if not_ready {
-                return
+                return handled
+            }
+        }
+    }
+}
+
+/// Run message through middlewares and handler, record statistics and trace
+fn dispatch<A>(env: Proxy<A>, attempts: u32, tp: &'static str,
+               timing: &mut Timing,
+               trace: &MailboxTrace, middlewares: &mut Vec<Box<ActorMiddleware<A>>>,
+               inflight: &mut Inflight<A>,
+               act: &mut A, ctx: &mut A::Context)
+    where A: Actor, A::Context: AsyncContext<A>
+{
+    pump::record_message();
+    if middleware::before(middlewares, tp, act, ctx) {
+        trace.record(TraceKind::HandlerStart, tp);
+        inflight.run(env, attempts, |env| {
+            if timing.enabled() {
+                let start = Instant::now();
+                env.handle(act, ctx);
+                timing.record(tp, start.elapsed());
+            } else {
+                env.handle(act, ctx);
+            }
+        });
+        trace.record(TraceKind::HandlerFinish, tp);
+        middleware::after(middlewares, tp, act, ctx);
+    } else {
+        // dropped envelope cancels caller's request
+        trace.record(TraceKind::Dropped, tp);
+    }
+}
+
+/// Handle messages of system lanes, returns true if at least one message got handled
+fn poll_system<A>(unsync_msgs: &mut LocalAddrReceiver<A>,
+                  sync_msgs: Option<&mut SyncAddressReceiver<A>>,
+                  act: &mut A, ctx: &mut A::Context) -> bool
+    where A: Actor, A::Context: AsyncContext<A>
+{
+    let mut handled = false;
+    while !ctx.waiting() {
+        if let Some(mut msg) = unsync_msgs.poll_system() {
+            msg.env.handle(act, ctx);
+            handled = true;
+        } else {
+            break
+        }
+    }
+    if let Some(msgs) = sync_msgs {
+        while !ctx.waiting() {
+            if let Some(mut msg) = msgs.poll_system() {
+                msg.handle(act, ctx);
+                handled = true;
+            } else {
+                break
             }
         }
     }
+    handled
 }
@@ -0,0 +1,196 @@
+//! Message definition layer
+//!
+//! Traits and types in this module do not depend on the runtime, crate
+//! compiled with `default-features = false` contains only this layer and
+//! `#[derive(Message)]`. Shared message crates could use it to define
+//! messages for peers that do not run actors.
+#[cfg(feature="runtime")]
+use std::fmt;
+#[cfg(not(feature="runtime"))]
+use core::fmt;
+#[cfg(not(feature="runtime"))]
+use alloc::string::String;
+
+/// Message response type
+pub trait ResponseType {
+
+    /// The type of value that this message will resolved with if it is successful.
+    type Item: 'static;
+
+    /// The type of error that this message will resolve with if it fails in a normal fashion.
+    type Error: 'static;
+
+    /// Render message payload for dead letters inspection.
+    ///
+    /// By default payload is not rendered, messages that implement `Debug`
+    /// could override this method with `Some(format!("{:?}", self))`.
+    fn render(&self) -> Option<String> {
+        None
+    }
+}
+
+impl<I, E> ResponseType for Result<I, E> where I: ResponseType {
+    type Item = <I as ResponseType>::Item;
+    type Error = ();
+}
+
+/// A specialized [`Result`](https://doc.rust-lang.org/std/result/enum.Result.html)
+/// for message result responses
+pub type MessageResult<M: ResponseType> = Result<M::Item, M::Error>;
+
+/// Conversion of an error into message's error type
+///
+/// Implemented for every error that `M::Error` could be created from with
+/// `From`, so `?` works in handler returning `MessageResult<M>` and generic
+/// code could require `E: IntoMessageError<M>`. `message_error!` generates
+/// `From` impls for message's error type.
+pub trait IntoMessageError<M: ResponseType> {
+    /// Convert error into `M::Error`
+    fn into_message_error(self) -> M::Error;
+}
+
+impl<M, E> IntoMessageError<M> for E where M: ResponseType, M::Error: From<E> {
+    fn into_message_error(self) -> M::Error {
+        M::Error::from(self)
+    }
+}
+
+/// Define message error type with conversions from underlying errors
+///
+/// Macro generates enum with a variant per underlying error, `From` impl
+/// for every variant, `Display` that renders underlying error and
+/// `std::error::Error` impl. Handler returning `MessageResult<M>` could
+/// use `?` on any of underlying errors if `M::Error` is generated type.
+///
+/// ```rust,ignore
+/// message_error! {
+///     /// Errors of `Load` message
+///     pub enum LoadError {
+///         Io(io::Error),
+///         Parse(ParseIntError),
+///     }
+/// }
+///
+/// impl ResponseType for Load {
+///     type Item = u32;
+///     type Error = LoadError;
+/// }
+///
+/// impl Handler<Load> for Store {
+///     type Result = MessageResult<Load>;
+///
+///     fn handle(&mut self, msg: Load, _: &mut Context<Self>) -> Self::Result {
+///         let mut s = String::new();
+///         File::open(&msg.0)?.read_to_string(&mut s)?;
+///         Ok(s.trim().parse()?)
+///     }
+/// }
+/// ```
+#[macro_export]
+macro_rules! message_error {
+    ($(#[$meta:meta])* pub enum $name:ident {
+        $($(#[$vmeta:meta])* $variant:ident($ty:ty)),* $(,)* }) => {
+        $(#[$meta])*
+        #[derive(Debug)]
+        pub enum $name { $($(#[$vmeta])* $variant($ty)),* }
+
+        message_error!(@impl $name $($variant $ty),*);
+    };
+    ($(#[$meta:meta])* enum $name:ident {
+        $($(#[$vmeta:meta])* $variant:ident($ty:ty)),* $(,)* }) => {
+        $(#[$meta])*
+        #[derive(Debug)]
+        enum $name { $($(#[$vmeta])* $variant($ty)),* }
+
+        message_error!(@impl $name $($variant $ty),*);
+    };
+
+    (@impl $name:ident $($variant:ident $ty:ty),*) => {
+        $(
+            impl From<$ty> for $name {
+                fn from(err: $ty) -> $name {
+                    $name::$variant(err)
+                }
+            }
+        )*
+
+        impl ::std::fmt::Display for $name {
+            fn fmt(&self, f: &mut ::std::fmt::Formatter) -> ::std::fmt::Result {
+                match *self {
+                    $($name::$variant(ref err) => ::std::fmt::Display::fmt(err, f),)*
+                }
+            }
+        }
+
+        impl ::std::error::Error for $name {}
+    };
+}
+
+/// Set of errors that can occur during message send
+pub enum SendError<T> {
+    Full(T),
+    Closed(T),
+}
+
+#[derive(PartialEq, Copy, Clone)]
+/// Set of error that can occure during message delivery process
+pub enum MailboxError {
+    /// Mailbox is closed, message could not be delivered
+    Closed,
+    /// Message delivery or response did not complete within specified timeout
+    Timeout,
+    /// Message is delivered, but response channel got dropped, i.e. actor
+    /// stopped before message got handled or response future got dropped
+    Canceled,
+    /// Actor waits for response to its own request, i.e.
+    /// `ctx.address().call_fut(msg).into_actor(self).wait(ctx)`.
+    /// Message could never be handled, because context does not
+    /// process mailbox while it waits.
+    SelfCall,
+    /// Mailbox is full and its overflow policy is `Overflow::Reject`
+    Full,
+}
+
+impl<T> SendError<T> {
+    pub fn into_inner(self) -> T {
+        match self {
+            SendError::Full(msg) | SendError::Closed(msg) => msg,
+        }
+    }
+}
+
+impl<T> fmt::Debug for SendError<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SendError::Full(_) => write!(fmt, "SendError::Full(..)"),
+            SendError::Closed(_) => write!(fmt, "SendError::Closed(..)"),
+        }
+    }
+}
+
+impl<T> fmt::Display for SendError<T> {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            SendError::Full(_) => write!(fmt, "send failed because receiver is full"),
+            SendError::Closed(_) => write!(fmt, "send failed because receiver is gone"),
+        }
+    }
+}
+
+impl fmt::Debug for MailboxError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "MailboxError({})", self)
+    }
+}
+
+impl fmt::Display for MailboxError {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            MailboxError::Closed => write!(fmt, "Mailbox has closed"),
+            MailboxError::Timeout => write!(fmt, "Message delivery timed out"),
+            MailboxError::Canceled => write!(fmt, "Message response canceled"),
+            MailboxError::SelfCall => write!(fmt, "Actor waits for its own message"),
+            MailboxError::Full => write!(fmt, "Mailbox is full"),
+        }
+    }
+}
@@ -0,0 +1,42 @@
+//! Messages that carry `Bytes` payloads
+//!
+//! Messages are moved through mailboxes, envelope owns the message
+//! until it is passed to the handler by value. Neither addresses nor
+//! channels require `Clone` from message types, so payload is never copied
+//! on its way to the actor. Cloning of `Bytes` itself only bumps reference
+//! counter of the shared buffer.
+//!
+//! This module requires `messages` feature.
+//!
+//! ```rust,ignore
+//! impl Handler<Data> for Parser {
+//!     type Result = ();
+//!
+//!     fn handle(&mut self, msg: Data, _: &mut Context<Self>) {
+//!         self.buf.extend_from_slice(&msg.0);
+//!     }
+//! }
+//! ```
+use std::net::SocketAddr;
+
+use bytes::Bytes;
+
+use handler::ResponseType;
+
+/// Chunk of data
+#[derive(Clone, Debug, PartialEq)]
+pub struct Data(pub Bytes);
+
+impl ResponseType for Data {
+    type Item = ();
+    type Error = ();
+}
+
+/// Chunk of data with peer address, i.e. udp datagram
+#[derive(Clone, Debug, PartialEq)]
+pub struct DataWithAddr(pub Bytes, pub SocketAddr);
+
+impl ResponseType for DataWithAddr {
+    type Item = ();
+    type Error = ();
+}
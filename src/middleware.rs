@@ -0,0 +1,48 @@
+use actor::Actor;
+
+/// Middleware decision for a message, see `ActorMiddleware::before()`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum MiddlewareAction {
+    /// Pass message to next middleware and to the handler
+    Continue,
+    /// Drop message, handler is not called and caller's request gets canceled
+    Drop,
+}
+
+/// Actor middleware, wraps dispatch of every message received through
+/// actor's address
+///
+/// Middlewares are installed with `Context::add_middleware()` and get called
+/// in order of installation. Middleware must not install other middlewares.
+#[allow(unused_variables)]
+pub trait ActorMiddleware<A: Actor>: 'static {
+
+    /// Method is called before message handler
+    fn before(&mut self, msg_type: &'static str,
+              act: &mut A, ctx: &mut A::Context) -> MiddlewareAction;
+
+    /// Method is called after message handler, in reverse order of installation.
+    /// It is not called for dropped messages.
+    fn after(&mut self, msg_type: &'static str, act: &mut A, ctx: &mut A::Context) {}
+}
+
+/// Run `before` methods, returns false if message has to be dropped
+pub(crate) fn before<A: Actor>(middlewares: &mut [Box<ActorMiddleware<A>>],
+                               msg_type: &'static str,
+                               act: &mut A, ctx: &mut A::Context) -> bool {
+    for mw in middlewares.iter_mut() {
+        if mw.before(msg_type, act, ctx) == MiddlewareAction::Drop {
+            return false
+        }
+    }
+    true
+}
+
+/// Run `after` methods in reverse order
+pub(crate) fn after<A: Actor>(middlewares: &mut [Box<ActorMiddleware<A>>],
+                              msg_type: &'static str,
+                              act: &mut A, ctx: &mut A::Context) {
+    for mw in middlewares.iter_mut().rev() {
+        mw.after(msg_type, act, ctx);
+    }
+}
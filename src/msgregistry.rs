@@ -0,0 +1,262 @@
+//! Registry of message types keyed by wire name, see `register_message!`
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+use std::fmt;
+use std::sync::Mutex;
+
+#[cfg(feature="serialize")]
+use serde::de::DeserializeOwned;
+#[cfg(feature="serialize")]
+use serde_json;
+
+use handler::ResponseType;
+
+/// Decode payload to boxed message
+type DeserializeFn = fn(&[u8]) -> Result<Box<Any + Send>, String>;
+
+/// Registered message type
+#[derive(Clone)]
+pub struct MessageInfo {
+    name: &'static str,
+    type_name: &'static str,
+    type_id: TypeId,
+    deserialize: Option<DeserializeFn>,
+}
+
+impl MessageInfo {
+    /// Wire name of the message
+    pub fn name(&self) -> &'static str {
+        self.name
+    }
+
+    /// Rust type name of the message
+    pub fn type_name(&self) -> &'static str {
+        self.type_name
+    }
+
+    /// `TypeId` of the message
+    pub fn type_id(&self) -> TypeId {
+        self.type_id
+    }
+
+    /// Message is registered with deserialize function
+    pub fn is_deserializable(&self) -> bool {
+        self.deserialize.is_some()
+    }
+}
+
+impl fmt::Debug for MessageInfo {
+    fn fmt(&self, fmt: &mut fmt::Formatter) -> fmt::Result {
+        write!(fmt, "MessageInfo({:?}, {}, deserializable: {})",
+               self.name, self.type_name, self.deserialize.is_some())
+    }
+}
+
+/// Message registry errors
+#[derive(Fail, Debug)]
+pub enum RegistryError {
+    /// Wire name is used by another message type
+    #[fail(display = "Message name {:?} is registered by {}, can not register {}",
+           name, registered, rejected)]
+    Collision {
+        name: &'static str,
+        registered: &'static str,
+        rejected: &'static str,
+    },
+
+    /// Message type is registered under another wire name
+    #[fail(display = "Message {} is registered as {:?}, can not register it as {:?}",
+           type_name, registered, rejected)]
+    Renamed {
+        type_name: &'static str,
+        registered: &'static str,
+        rejected: &'static str,
+    },
+
+    /// No message is registered under the wire name
+    #[fail(display = "Message {:?} is not registered", _0)]
+    Unknown(String),
+
+    /// Message is registered without deserialize function
+    #[fail(display = "Message {:?} is not deserializable", _0)]
+    NotDeserializable(&'static str),
+
+    /// Payload can not be decoded
+    #[fail(display = "Can not decode message {:?}: {}", _0, _1)]
+    Decode(&'static str, String),
+}
+
+struct Messages {
+    by_name: HashMap<&'static str, MessageInfo>,
+    by_type: HashMap<TypeId, &'static str>,
+}
+
+static MESSAGES: Mutex<Option<Messages>> = Mutex::new(None);
+
+fn with_messages<F, R>(f: F) -> R where F: FnOnce(&mut Messages) -> R {
+    let mut messages = MESSAGES.lock().unwrap();
+    f(messages.get_or_insert_with(|| Messages{by_name: HashMap::new(), by_type: HashMap::new()}))
+}
+
+/// Process wide registry of message types
+///
+/// Type-erased and remote messages are identified by wire name, registry
+/// maps wire names to message types and, with `serialize` feature, to
+/// functions that decode payload of the message. Messages get registered
+/// with `register_message!` macro, usually during application start.
+///
+/// Every wire name belongs to exactly one message type and every message
+/// type has exactly one wire name. Registration of the same type under the
+/// same name is no-op, so it is safe to register messages from
+/// multiple places.
+///
+/// ```rust
+/// # #[macro_use] extern crate actix;
+/// use actix::{MessageRegistry, ResponseType};
+///
+/// struct Ping;
+///
+/// impl ResponseType for Ping {
+///     type Item = ();
+///     type Error = ();
+/// }
+///
+/// struct Pong;
+///
+/// impl ResponseType for Pong {
+///     type Item = ();
+///     type Error = ();
+/// }
+///
+/// fn main() {
+///     register_message!(Ping).unwrap();
+///     register_message!(Pong, "app.pong").unwrap();
+///
+///     assert_eq!(MessageRegistry::name_of::<Pong>(), Some("app.pong"));
+///     assert!(MessageRegistry::get("Ping").is_some());
+/// }
+/// ```
+pub struct MessageRegistry;
+
+impl MessageRegistry {
+    /// Register message type `M` under wire name `name`
+    pub fn register<M>(name: &'static str) -> Result<(), RegistryError>
+        where M: ResponseType + 'static
+    {
+        MessageRegistry::insert::<M>(name, None)
+    }
+
+    /// Register message type `M` under wire name `name` together with
+    /// function that decodes json payload of the message,
+    /// see `MessageRegistry::deserialize()`
+    #[cfg(feature="serialize")]
+    pub fn register_deserialize<M>(name: &'static str) -> Result<(), RegistryError>
+        where M: ResponseType + DeserializeOwned + Send + 'static
+    {
+        MessageRegistry::insert::<M>(name, Some(deserialize::<M>))
+    }
+
+    fn insert<M: 'static>(name: &'static str, deserialize: Option<DeserializeFn>)
+                          -> Result<(), RegistryError>
+    {
+        let info = MessageInfo{
+            name,
+            type_name: ::std::any::type_name::<M>(),
+            type_id: TypeId::of::<M>(),
+            deserialize,
+        };
+
+        with_messages(|messages| {
+            if let Some(registered) = messages.by_name.get_mut(name) {
+                if registered.type_id != info.type_id {
+                    return Err(RegistryError::Collision{
+                        name, registered: registered.type_name, rejected: info.type_name})
+                }
+                if registered.deserialize.is_none() {
+                    registered.deserialize = info.deserialize;
+                }
+                return Ok(())
+            }
+            if let Some(registered) = messages.by_type.get(&info.type_id) {
+                return Err(RegistryError::Renamed{
+                    type_name: info.type_name, registered: *registered, rejected: name})
+            }
+            messages.by_type.insert(info.type_id, name);
+            messages.by_name.insert(name, info);
+            Ok(())
+        })
+    }
+
+    /// Message registered under wire name `name`
+    pub fn get(name: &str) -> Option<MessageInfo> {
+        with_messages(|messages| messages.by_name.get(name).cloned())
+    }
+
+    /// Wire name of message type `M`
+    pub fn name_of<M: 'static>() -> Option<&'static str> {
+        with_messages(|messages| messages.by_type.get(&TypeId::of::<M>()).cloned())
+    }
+
+    /// All registered messages ordered by wire name
+    pub fn registered_messages() -> Vec<MessageInfo> {
+        let mut infos: Vec<_> = with_messages(
+            |messages| messages.by_name.values().cloned().collect());
+        infos.sort_by_key(|info| info.name);
+        infos
+    }
+
+    /// Decode json payload of message registered under wire name `name`
+    ///
+    /// Returned message could be downcasted to the registered type.
+    #[cfg(feature="serialize")]
+    pub fn deserialize(name: &str, payload: &[u8]) -> Result<Box<Any + Send>, RegistryError> {
+        let info = match MessageRegistry::get(name) {
+            Some(info) => info,
+            None => return Err(RegistryError::Unknown(name.to_owned())),
+        };
+        match info.deserialize {
+            Some(deserialize) => deserialize(payload)
+                .map_err(|err| RegistryError::Decode(info.name, err)),
+            None => Err(RegistryError::NotDeserializable(info.name)),
+        }
+    }
+}
+
+#[cfg(feature="serialize")]
+fn deserialize<M: DeserializeOwned + Send + 'static>(payload: &[u8])
+                                                     -> Result<Box<Any + Send>, String>
+{
+    match serde_json::from_slice::<M>(payload) {
+        Ok(msg) => Ok(Box::new(msg)),
+        Err(err) => Err(format!("{}", err)),
+    }
+}
+
+/// Register message type in `MessageRegistry`
+///
+/// Wire name defaults to the type as it is written in macro invocation,
+/// explicit name could be passed as second argument. With `serialize`
+/// feature, `deserialize` prefix registers json decoder of the message as
+/// well, message has to implement `Deserialize` in that case.
+///
+/// ```rust,ignore
+/// register_message!(Ping)?;
+/// register_message!(Pong, "app.pong")?;
+/// register_message!(deserialize Data)?;
+/// register_message!(deserialize Stats, "app.stats")?;
+/// ```
+#[macro_export]
+macro_rules! register_message {
+    (deserialize $ty:ty) => {
+        $crate::MessageRegistry::register_deserialize::<$ty>(stringify!($ty))
+    };
+    (deserialize $ty:ty, $name:expr) => {
+        $crate::MessageRegistry::register_deserialize::<$ty>($name)
+    };
+    ($ty:ty) => {
+        $crate::MessageRegistry::register::<$ty>(stringify!($ty))
+    };
+    ($ty:ty, $name:expr) => {
+        $crate::MessageRegistry::register::<$ty>($name)
+    };
+}
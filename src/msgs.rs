@@ -1,9 +1,16 @@
 //! Actix system messages
 
-use actor::Actor;
-use address::SyncAddress;
+use std::collections::HashMap;
+use std::time::Duration;
+
+use futures::Future;
+
+use actor::{Actor, ActorContext};
+use address::{SyncAddress, ToEnvelope};
+use arbiter::ActorInfo;
 use context::Context;
-use handler::ResponseType;
+use handler::{Handler, MessageResult, ResponseFuture, ResponseType};
+use mailbox::{HandlerStat, TraceEvent};
 
 /// Stop system execution
 pub struct SystemExit(pub i32);
@@ -21,6 +28,36 @@ impl ResponseType for StopArbiter {
     type Error = ();
 }
 
+/// Gracefully stop arbiter execution
+///
+/// Arbiter asks each actor running in arbiter's thread to stop. Actor handles
+/// all messages already queued in its mailbox, then `stopping` and `stopped`
+/// lifecycle methods get called. Arbiter waits for all actors to finish, but no
+/// longer than `timeout`, then it stops event loop and arbiter's thread exits.
+///
+/// Actors that did not stop within `timeout` are abandoned, their queued
+/// messages are published as dead letters. Response resolves with list
+/// of abandoned actors as error.
+pub struct StopArbiterGraceful {
+    pub timeout: Duration,
+}
+
+impl ResponseType for StopArbiterGraceful {
+    type Item = ();
+    type Error = Vec<ActorInfo>;
+}
+
+/// List actors running in arbiter's thread
+///
+/// Live diagnostic dump, each `ActorInfo` describes wait future
+/// actor is blocked on, if any.
+pub struct DumpActors;
+
+impl ResponseType for DumpActors {
+    type Item = Vec<ActorInfo>;
+    type Error = ();
+}
+
 /// Start actor in arbiter's thread
 pub struct StartActor<A: Actor>(Box<FnBox<A>>);
 
@@ -69,7 +106,7 @@ impl<A: Actor, F: FnOnce() -> SyncAddress<A> + Send + 'static> FnBox<A> for F {
 ///    type Context = Context<Self>;
 ///
 ///    fn started(&mut self, ctx: &mut Context<Self>) {
-///        self.addr.send(actix::msgs::Execute::new(|| -> Result<(), ()> {
+///        self.addr.do_send(actix::msgs::Execute::new(|| -> Result<(), ()> {
 ///            // do something
 ///            // ...
 ///            Ok(())
@@ -114,3 +151,162 @@ impl<I, E, F> FnExec<I, E> for F
         (*self)()
     }
 }
+
+/// Request message handling statistics from actor
+///
+/// Every actor with `Context` handles this message, response is a map
+/// of message type name to `HandlerStat`. Map is empty unless statistics
+/// is enabled with `Context::enable_handler_stats()`.
+pub struct GetHandlerStats;
+
+impl ResponseType for GetHandlerStats {
+    type Item = HashMap<&'static str, HandlerStat>;
+    type Error = ();
+}
+
+impl<A> Handler<GetHandlerStats> for A where A: Actor<Context=Context<A>> {
+    type Result = MessageResult<GetHandlerStats>;
+
+    fn handle(&mut self, _: GetHandlerStats, ctx: &mut Context<A>) -> Self::Result {
+        Ok(ctx.handler_stats())
+    }
+}
+
+/// Get actor's mailbox trace
+///
+/// Every actor with `Context` handles this message, response is a list
+/// of recorded mailbox events. List is empty unless trace is enabled
+/// with `Context::enable_mailbox_trace()`.
+pub struct GetMailboxTrace;
+
+impl ResponseType for GetMailboxTrace {
+    type Item = Vec<TraceEvent>;
+    type Error = ();
+}
+
+impl<A> Handler<GetMailboxTrace> for A where A: Actor<Context=Context<A>> {
+    type Result = MessageResult<GetMailboxTrace>;
+
+    fn handle(&mut self, _: GetMailboxTrace, ctx: &mut Context<A>) -> Self::Result {
+        Ok(ctx.mailbox_trace())
+    }
+}
+
+/// Mailbox health report, see `Context::enable_mailbox_reports()`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct MailboxStatus {
+    /// Number of messages waiting in the mailbox
+    pub len: usize,
+    /// Mailbox capacity
+    pub capacity: usize,
+    /// Time the oldest waiting message spent in the mailbox,
+    /// zero if mailbox is empty
+    pub oldest_age: Duration,
+}
+
+impl ResponseType for MailboxStatus {
+    type Item = ();
+    type Error = ();
+}
+
+/// Stop actor, see `SyncAddress::stop()`
+///
+/// Every actor with `Context` handles this message. Actor could prevent
+/// stopping by returning `false` from `Actor::stopping()` method.
+pub struct StopActor;
+
+impl ResponseType for StopActor {
+    type Item = ();
+    type Error = ();
+}
+
+impl<A> Handler<StopActor> for A where A: Actor<Context=Context<A>> {
+    type Result = ();
+
+    fn handle(&mut self, _: StopActor, ctx: &mut Context<A>) {
+        ctx.stop()
+    }
+}
+
+/// Terminate actor, see `SyncAddress::terminate()`
+///
+/// Every actor with `Context` handles this message, actor stops immediately.
+pub struct TerminateActor;
+
+impl ResponseType for TerminateActor {
+    type Item = ();
+    type Error = ();
+}
+
+impl<A> Handler<TerminateActor> for A where A: Actor<Context=Context<A>> {
+    type Result = ();
+
+    fn handle(&mut self, _: TerminateActor, ctx: &mut Context<A>) {
+        ctx.terminate()
+    }
+}
+
+/// Stop actor and wait until it is stopped
+///
+/// Every actor with `Context` handles this message, response resolves
+/// after actor's `stopped()` method get called. System sends this message
+/// to actors registered with `ShutdownPhase`.
+pub struct StopPhase;
+
+impl ResponseType for StopPhase {
+    type Item = ();
+    type Error = ();
+}
+
+impl<A> Handler<StopPhase> for A where A: Actor<Context=Context<A>> {
+    type Result = ResponseFuture<StopPhase>;
+
+    fn handle(&mut self, _: StopPhase, ctx: &mut Context<A>) -> Self::Result {
+        let rx = ctx.stop_waiter();
+        ctx.stop();
+        Box::new(rx.map_err(|_| ()))
+    }
+}
+
+/// Register actor within system shutdown phase
+///
+/// On `SystemExit` system stops registered actors phase by phase, in
+/// ascending phase order. Actors of the next phase are stopped only after
+/// all actors of the previous phase are stopped. Arbiters are stopped after
+/// last phase. Whole process is bounded by system's shutdown timeout,
+/// see `SystemRunner::shutdown_timeout()`.
+///
+/// ```rust,ignore
+/// Arbiter::system().do_send(ShutdownPhase::new(0, ctx.address()));
+/// ```
+pub struct ShutdownPhase {
+    pub(crate) phase: usize,
+    pub(crate) addr: Box<PhaseStopper>,
+}
+
+impl ResponseType for ShutdownPhase {
+    type Item = ();
+    type Error = ();
+}
+
+impl ShutdownPhase {
+    pub fn new<A>(phase: usize, addr: SyncAddress<A>) -> ShutdownPhase
+        where A: Actor + Handler<StopPhase>, A::Context: ToEnvelope<A>
+    {
+        ShutdownPhase{phase: phase, addr: Box::new(addr)}
+    }
+}
+
+/// Type erased address of the actor registered within shutdown phase
+pub(crate) trait PhaseStopper: Send {
+    fn stop(&self) -> Box<Future<Item=(), Error=()>>;
+}
+
+impl<A> PhaseStopper for SyncAddress<A>
+    where A: Actor + Handler<StopPhase>, A::Context: ToEnvelope<A>
+{
+    fn stop(&self) -> Box<Future<Item=(), Error=()>> {
+        // stopped actor has closed mailbox
+        Box::new(self.call_fut(StopPhase).then(|_| Ok(())))
+    }
+}
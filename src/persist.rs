@@ -0,0 +1,123 @@
+//! Actor state snapshots, see `ActorBuilder::state_store()`
+use std::fs;
+use std::io::{self, Read, Write};
+use std::path::PathBuf;
+use std::sync::Arc;
+
+use actor::{Actor, Supervised};
+use context::Context;
+
+/// Storage of actor state snapshots, keyed by actor name
+pub trait StateStore {
+    /// Load snapshot, `Ok(None)` if there is no snapshot for actor
+    fn load(&self, name: &str) -> io::Result<Option<Vec<u8>>>;
+
+    /// Save snapshot, replaces previous snapshot of actor
+    fn save(&self, name: &str, snapshot: &[u8]) -> io::Result<()>;
+}
+
+const MAGIC: &[u8; 8] = b"ACTXSNAP";
+const HEADER: usize = 24;
+
+/// File based state store
+///
+/// Every actor's snapshot is stored in separate file within directory.
+/// Snapshot is written to temporary file first and then renamed, so
+/// interrupted save does not damage previous snapshot. File contains
+/// checksum of the snapshot, corrupted file fails to load with
+/// `io::ErrorKind::InvalidData` error.
+pub struct FileStore {
+    dir: PathBuf,
+}
+
+impl FileStore {
+    /// Create store in `dir`, directory is created on first save
+    pub fn new<P: Into<PathBuf>>(dir: P) -> FileStore {
+        FileStore{dir: dir.into()}
+    }
+
+    /// Path of actor's snapshot file
+    pub fn path(&self, name: &str) -> PathBuf {
+        let name: String = name.chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '-' || c == '.' { c } else { '_' })
+            .collect();
+        self.dir.join(format!("{}.snapshot", name))
+    }
+}
+
+impl StateStore for FileStore {
+    fn load(&self, name: &str) -> io::Result<Option<Vec<u8>>> {
+        let mut data = Vec::new();
+        match fs::File::open(self.path(name)) {
+            Ok(mut file) => file.read_to_end(&mut data)?,
+            Err(ref err) if err.kind() == io::ErrorKind::NotFound => return Ok(None),
+            Err(err) => return Err(err),
+        };
+
+        if data.len() < HEADER || &data[..8] != MAGIC {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Snapshot header is invalid"))
+        }
+        let len = read_u64(&data[8..16]) as usize;
+        let sum = read_u64(&data[16..24]);
+        let snapshot = data.split_off(HEADER);
+        if snapshot.len() != len || checksum(&snapshot) != sum {
+            return Err(io::Error::new(io::ErrorKind::InvalidData, "Snapshot is corrupted"))
+        }
+        Ok(Some(snapshot))
+    }
+
+    fn save(&self, name: &str, snapshot: &[u8]) -> io::Result<()> {
+        fs::create_dir_all(&self.dir)?;
+        let path = self.path(name);
+        let tmp = path.with_extension("snapshot.tmp");
+        {
+            let mut file = fs::File::create(&tmp)?;
+            file.write_all(MAGIC)?;
+            file.write_all(&write_u64(snapshot.len() as u64))?;
+            file.write_all(&write_u64(checksum(snapshot)))?;
+            file.write_all(snapshot)?;
+            file.sync_all()?;
+        }
+        fs::rename(tmp, path)
+    }
+}
+
+fn read_u64(buf: &[u8]) -> u64 {
+    buf.iter().rev().fold(0, |n, b| (n << 8) | u64::from(*b))
+}
+
+fn write_u64(mut n: u64) -> [u8; 8] {
+    let mut buf = [0; 8];
+    for b in &mut buf {
+        *b = n as u8;
+        n >>= 8;
+    }
+    buf
+}
+
+/// FNV-1a hash of the snapshot
+fn checksum(data: &[u8]) -> u64 {
+    data.iter().fold(0xcbf2_9ce4_8422_2325, |hash, b| {
+        (hash ^ u64::from(*b)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// Restore actor state and save snapshot on graceful stop
+pub(crate) fn attach<A>(act: &mut A, ctx: &mut Context<A>,
+                        store: Arc<StateStore + Send + Sync>, name: String)
+    where A: Supervised + Actor<Context=Context<A>>
+{
+    match store.load(&name) {
+        Ok(Some(snapshot)) => act.restore(snapshot),
+        Ok(None) => (),
+        Err(err) => error!("Can not restore state of {}, starting fresh: {}", name, err),
+    }
+
+    ctx.set_snapshot_hook(Box::new(move |act: &A| {
+        if let Some(snapshot) = act.snapshot() {
+            if let Err(err) = store.save(&name, &snapshot) {
+                error!("Can not save state of {}: {}", name, err);
+            }
+        }
+    }));
+}
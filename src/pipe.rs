@@ -0,0 +1,93 @@
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+use actor::{Actor, AsyncContext};
+use address::Subscriber;
+use cache;
+use handler::{Handler, ResponseChannel, ResponseType};
+
+/// Subscribers of one message type
+struct Pipe<M: 'static> {
+    clone: fn(&M) -> M,
+    subscribers: Vec<Box<Subscriber<M>>>,
+}
+
+/// Message pipes of the actor, see `Context::pipe_to()`
+#[doc(hidden)]
+pub struct MessagePipes {
+    pipes: HashMap<TypeId, Box<Any>>,
+    dropped: HashMap<TypeId, usize>,
+}
+
+impl MessagePipes {
+    pub(crate) fn new() -> MessagePipes {
+        MessagePipes{pipes: HashMap::new(), dropped: HashMap::new()}
+    }
+
+    /// Forward copies of message `M` to `subscriber`
+    pub(crate) fn add<M: Clone + 'static>(&mut self, subscriber: Box<Subscriber<M>>) {
+        self.pipes.entry(TypeId::of::<M>())
+            .or_insert_with(|| Box::new(Pipe::<M>{clone: M::clone, subscribers: Vec::new()}))
+            .downcast_mut::<Pipe<M>>().expect("wrong pipe type")
+            .subscribers.push(subscriber);
+    }
+
+    /// Remove all pipes of message `M`
+    pub(crate) fn remove<M: 'static>(&mut self) {
+        self.pipes.remove(&TypeId::of::<M>());
+    }
+
+    /// Number of copies of message `M` that subscribers did not accept
+    pub(crate) fn dropped<M: 'static>(&self) -> usize {
+        self.dropped.get(&TypeId::of::<M>()).cloned().unwrap_or(0)
+    }
+
+    /// Copy of the message, if message type is piped
+    fn copy<M: 'static>(&self, msg: &M) -> Option<M> {
+        self.pipes.get(&TypeId::of::<M>())
+            .and_then(|pipe| pipe.downcast_ref::<Pipe<M>>())
+            .map(|pipe| (pipe.clone)(msg))
+    }
+
+    /// Send message to every subscriber of its type with `try_send()`
+    fn forward<M: 'static>(&mut self, msg: M) {
+        let pipe = match self.pipes.get(&TypeId::of::<M>())
+            .and_then(|pipe| pipe.downcast_ref::<Pipe<M>>())
+        {
+            Some(pipe) => pipe,
+            None => return,
+        };
+        let mut dropped = 0;
+        if let Some((last, subscribers)) = pipe.subscribers.split_last() {
+            for subscriber in subscribers {
+                if subscriber.try_send((pipe.clone)(&msg)).is_err() {
+                    dropped += 1;
+                }
+            }
+            if last.try_send(msg).is_err() {
+                dropped += 1;
+            }
+        }
+        if dropped != 0 {
+            *self.dropped.entry(TypeId::of::<M>()).or_insert(0) += dropped;
+        }
+    }
+}
+
+/// Handle message, copy of the message is forwarded to pipes of its type
+/// after handler is called
+pub(crate) fn handle<A, M, R>(act: &mut A, msg: M, ctx: &mut A::Context, tx: Option<R>)
+    where A: Actor + Handler<M>, A::Context: AsyncContext<A>,
+          M: ResponseType + 'static, R: ResponseChannel<M>
+{
+    let pipes = match ctx.message_pipes() {
+        Some(pipes) => pipes,
+        None => return cache::handle(act, msg, ctx, tx),
+    };
+
+    let copy = pipes.borrow().copy(&msg);
+    cache::handle(act, msg, ctx, tx);
+    if let Some(copy) = copy {
+        pipes.borrow_mut().forward(copy);
+    }
+}
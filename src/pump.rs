@@ -0,0 +1,93 @@
+//! Work accounting of the current thread, see `SystemRunner::run_pending()`
+//!
+//! Accounting is off until `run_pending()` gets called on the thread,
+//! threads that use `run()` do not pay for it.
+use std::cell::{Cell, RefCell};
+use std::collections::BTreeMap;
+use std::time::Instant;
+
+thread_local!(
+    static ENABLED: Cell<bool> = Cell::new(false);
+    static POLLS: Cell<usize> = Cell::new(0);
+    static MESSAGES: Cell<usize> = Cell::new(0);
+    static DEADLINES: RefCell<BTreeMap<Instant, usize>> = RefCell::new(BTreeMap::new());
+);
+
+/// Start work accounting of the current thread
+pub(crate) fn enable() {
+    ENABLED.with(|enabled| enabled.set(true));
+}
+
+fn enabled() -> bool {
+    ENABLED.with(|enabled| enabled.get())
+}
+
+/// Actor context got polled
+pub(crate) fn record_poll() {
+    if enabled() {
+        POLLS.with(|polls| polls.set(polls.get().wrapping_add(1)));
+    }
+}
+
+/// Mailbox message got handled
+pub(crate) fn record_message() {
+    if enabled() {
+        MESSAGES.with(|messages| messages.set(messages.get().wrapping_add(1)));
+    }
+}
+
+/// Number of context polls and handled messages since thread start
+pub(crate) fn counters() -> (usize, usize) {
+    (POLLS.with(|polls| polls.get()), MESSAGES.with(|messages| messages.get()))
+}
+
+/// Earliest deadline of pending context timers
+pub(crate) fn next_deadline() -> Option<Instant> {
+    DEADLINES.with(|deadlines| deadlines.borrow().keys().next().cloned())
+}
+
+/// Deadline of the context timer, it is reported while guard is alive
+pub(crate) struct Deadline {
+    at: Instant,
+    registered: bool,
+}
+
+impl Deadline {
+    pub fn new(at: Instant) -> Deadline {
+        let registered = enabled();
+        if registered {
+            DEADLINES.with(|deadlines| *deadlines.borrow_mut().entry(at).or_insert(0) += 1);
+        }
+        Deadline{at: at, registered: registered}
+    }
+
+    pub fn get(&self) -> Instant {
+        self.at
+    }
+
+    /// Timer got rescheduled
+    pub fn reset(&mut self, at: Instant) {
+        *self = Deadline::new(at);
+    }
+}
+
+impl Drop for Deadline {
+    fn drop(&mut self) {
+        if !self.registered {
+            return
+        }
+        let _ = DEADLINES.try_with(|deadlines| {
+            let mut deadlines = deadlines.borrow_mut();
+            let last = match deadlines.get_mut(&self.at) {
+                Some(count) => {
+                    *count -= 1;
+                    *count == 0
+                }
+                None => false,
+            };
+            if last {
+                deadlines.remove(&self.at);
+            }
+        });
+    }
+}
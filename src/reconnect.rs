@@ -0,0 +1,397 @@
+use std::io;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::cmp;
+use std::marker::PhantomData;
+use std::collections::VecDeque;
+use std::time::Duration;
+
+use futures::{Async, Future, Poll, Stream};
+use tokio_core::reactor::Timeout;
+use tokio_io::{AsyncRead, AsyncWrite};
+use tokio_io::codec::{Framed, Encoder, Decoder};
+
+use fut::ActorFuture;
+use actor::{Actor, AsyncContext, SpawnHandle};
+use address::SendError;
+use arbiter::Arbiter;
+use framed::{FramedReader, FramedWriter, FramedError};
+use handler::{Handler, ResponseType};
+use stream::StreamHandler;
+
+type ConnectFuture<Io, Codec> = Box<Future<Item=Framed<Io, Codec>, Error=io::Error>>;
+
+/// Reconnect delay policy
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct Backoff {
+    initial: Duration,
+    max: Duration,
+    factor: u32,
+}
+
+impl Backoff {
+    /// Wait same amount of time before each attempt
+    pub fn fixed(delay: Duration) -> Backoff {
+        Backoff{initial: delay, max: delay, factor: 1}
+    }
+
+    /// Double delay after each failed attempt, but no longer than `max`
+    pub fn exponential(initial: Duration, max: Duration) -> Backoff {
+        Backoff{initial: initial, max: max, factor: 2}
+    }
+
+    /// Delay before specified reconnect attempt, first attempt is `0`
+    pub fn delay(&self, attempt: usize) -> Duration {
+        let mut delay = self.initial;
+        for _ in 0..attempt {
+            if delay >= self.max {
+                break
+            }
+            delay *= self.factor;
+        }
+        cmp::min(delay, self.max)
+    }
+
+    /// Longest delay of the policy
+    pub(crate) fn max(&self) -> Duration {
+        self.max
+    }
+}
+
+impl Default for Backoff {
+    fn default() -> Backoff {
+        Backoff::exponential(Duration::from_millis(100), Duration::from_secs(30))
+    }
+}
+
+/// Connection is established and handshake is completed
+#[derive(Debug)]
+pub struct Connected;
+
+impl ResponseType for Connected {
+    type Item = ();
+    type Error = ();
+}
+
+/// Connection is lost, client reconnects unless it is closed
+pub struct Disconnected<Codec: Encoder + Decoder> {
+    pub reason: DisconnectReason<Codec>,
+}
+
+impl<Codec: Encoder + Decoder> ResponseType for Disconnected<Codec> {
+    type Item = ();
+    type Error = ();
+}
+
+/// Reason of connection loss
+pub enum DisconnectReason<Codec: Encoder + Decoder> {
+    /// Peer closed connection
+    Closed,
+    /// Stream or sink error
+    Error(FramedError<Codec>),
+}
+
+/// Framed client connection that reconnects automatically
+///
+/// Client uses connect factory to establish connection, runs optional
+/// handshake and then feeds decoded frames to actor's `StreamHandler::handle()`.
+/// Owning actor gets `Connected` message after each successful handshake and
+/// `Disconnected` message when connection is lost. After disconnect client
+/// waits according to `Backoff` policy and connects again. Failed connect
+/// and handshake attempts are retried with increasing delay.
+///
+/// Items passed to `send()` while client is disconnected are buffered and
+/// get written after next successful handshake. If buffer is full, or
+/// buffering is disabled with `buffer(0)`, `send()` returns item back.
+///
+/// ```rust,ignore
+/// impl Actor for Session {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, ctx: &mut Context<Self>) {
+///         let addr = self.addr;
+///         self.client = Some(
+///             ReconnectingClient::build(move || {
+///                 Box::new(TcpStream::connect(&addr, Arbiter::handle())
+///                          .map(|io| io.framed(LineCodec)))
+///             })
+///             .backoff(Backoff::fixed(Duration::from_secs(1)))
+///             .start(ctx));
+///     }
+/// }
+/// ```
+pub struct ReconnectingClient<Io, Codec>
+    where Io: AsyncRead + AsyncWrite, Codec: Encoder + Decoder
+{
+    inner: Rc<RefCell<Inner<Io, Codec>>>,
+    handle: SpawnHandle,
+}
+
+/// `ReconnectingClient` builder
+pub struct ReconnectingClientBuilder<Io, Codec>
+    where Io: AsyncRead + AsyncWrite, Codec: Encoder + Decoder
+{
+    connect: Box<FnMut() -> ConnectFuture<Io, Codec>>,
+    handshake: Option<Box<FnMut(Framed<Io, Codec>) -> ConnectFuture<Io, Codec>>>,
+    backoff: Backoff,
+    capacity: usize,
+}
+
+struct Inner<Io, Codec> where Io: AsyncRead + AsyncWrite, Codec: Encoder + Decoder {
+    writer: Option<FramedWriter<Io, Codec>>,
+    buffer: VecDeque<<Codec as Encoder>::Item>,
+    capacity: usize,
+    closed: bool,
+}
+
+impl<Io, Codec> ReconnectingClient<Io, Codec>
+    where Io: AsyncRead + AsyncWrite + 'static, Codec: Encoder + Decoder + 'static
+{
+    /// Create client builder, `connect` is called for every connection attempt
+    pub fn build<F>(connect: F) -> ReconnectingClientBuilder<Io, Codec>
+        where F: FnMut() -> ConnectFuture<Io, Codec> + 'static
+    {
+        ReconnectingClientBuilder {
+            connect: Box::new(connect),
+            handshake: None,
+            backoff: Backoff::default(),
+            capacity: 16,
+        }
+    }
+
+    /// Check if connection is established
+    pub fn connected(&self) -> bool {
+        self.inner.borrow().writer.is_some()
+    }
+
+    /// Send item to a peer
+    ///
+    /// Returns `SendError::Full` if client is disconnected and buffer is full,
+    /// `SendError::Closed` if client is closed.
+    pub fn send(&mut self, item: <Codec as Encoder>::Item)
+                -> Result<(), SendError<<Codec as Encoder>::Item>>
+    {
+        let mut inner = self.inner.borrow_mut();
+        let inner = &mut *inner;
+        if inner.closed {
+            return Err(SendError::Closed(item))
+        }
+        if let Some(ref mut writer) = inner.writer {
+            writer.send(item);
+            Ok(())
+        } else if inner.buffer.len() < inner.capacity {
+            inner.buffer.push_back(item);
+            Ok(())
+        } else {
+            Err(SendError::Full(item))
+        }
+    }
+
+    /// Close connection and stop reconnecting
+    ///
+    /// Buffered items are dropped.
+    pub fn close(&mut self) {
+        let mut inner = self.inner.borrow_mut();
+        inner.closed = true;
+        inner.buffer.clear();
+        if let Some(ref mut writer) = inner.writer {
+            writer.close();
+        }
+    }
+
+    /// Spawn handle of connection future
+    pub fn handle(&self) -> SpawnHandle {
+        self.handle
+    }
+}
+
+impl<Io, Codec> ReconnectingClientBuilder<Io, Codec>
+    where Io: AsyncRead + AsyncWrite + 'static, Codec: Encoder + Decoder + 'static
+{
+    /// Run handshake on every new connection
+    ///
+    /// Handshake future resolves to the framed object that is used for
+    /// the rest of the connection. If handshake fails, client reconnects.
+    pub fn handshake<F>(mut self, handshake: F) -> Self
+        where F: FnMut(Framed<Io, Codec>) -> ConnectFuture<Io, Codec> + 'static
+    {
+        self.handshake = Some(Box::new(handshake));
+        self
+    }
+
+    /// Set reconnect delay policy
+    ///
+    /// By default exponential backoff from 100 milliseconds to 30 seconds is used.
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Set number of items buffered while client is disconnected
+    ///
+    /// By default 16 items are buffered. `0` disables buffering.
+    pub fn buffer(mut self, cap: usize) -> Self {
+        self.capacity = cap;
+        self
+    }
+
+    /// Start connecting, connection runs within actor's context
+    pub fn start<A, T>(self, ctx: &mut T) -> ReconnectingClient<Io, Codec>
+        where A: Actor<Context=T>
+                 + StreamHandler<<Codec as Decoder>::Item, FramedError<Codec>>
+                 + Handler<Connected, Result=()> + Handler<Disconnected<Codec>, Result=()>,
+              T: AsyncContext<A>,
+    {
+        let inner = Rc::new(RefCell::new(
+            Inner{writer: None, buffer: VecDeque::new(), capacity: self.capacity, closed: false}));
+
+        let fut = ReconnectFut {
+            inner: Rc::clone(&inner),
+            connect: self.connect,
+            handshake: self.handshake,
+            backoff: self.backoff,
+            attempt: 0,
+            state: State::Delay(None),
+            act: PhantomData,
+        };
+        let handle = ctx.spawn_internal(fut);
+
+        ReconnectingClient{inner: inner, handle: handle}
+    }
+}
+
+enum State<Io, Codec> where Io: AsyncRead + AsyncWrite, Codec: Encoder + Decoder {
+    Delay(Option<Timeout>),
+    Connecting(ConnectFuture<Io, Codec>),
+    Handshake(ConnectFuture<Io, Codec>),
+    Connected(FramedReader<Io, Codec>),
+}
+
+struct ReconnectFut<A, Io, Codec> where Io: AsyncRead + AsyncWrite, Codec: Encoder + Decoder {
+    inner: Rc<RefCell<Inner<Io, Codec>>>,
+    connect: Box<FnMut() -> ConnectFuture<Io, Codec>>,
+    handshake: Option<Box<FnMut(Framed<Io, Codec>) -> ConnectFuture<Io, Codec>>>,
+    backoff: Backoff,
+    attempt: usize,
+    state: State<Io, Codec>,
+    act: PhantomData<A>,
+}
+
+impl<A, Io, Codec> ReconnectFut<A, Io, Codec>
+    where Io: AsyncRead + AsyncWrite + 'static, Codec: Encoder + Decoder + 'static
+{
+    fn retry(&mut self, err: &io::Error) {
+        warn!("Can not establish connection: {}", err);
+        let delay = self.backoff.delay(self.attempt);
+        self.attempt += 1;
+        self.state = State::Delay(Some(Timeout::new(delay, Arbiter::handle()).unwrap()));
+    }
+
+    fn connected(&mut self, framed: Framed<Io, Codec>) {
+        let (reader, mut writer) = FramedReader::wrap(framed);
+        let mut inner = self.inner.borrow_mut();
+        for item in inner.buffer.drain(..) {
+            writer.send(item);
+        }
+        inner.writer = Some(writer);
+        self.attempt = 0;
+        self.state = State::Connected(reader);
+    }
+}
+
+impl<A, Io, Codec> ActorFuture for ReconnectFut<A, Io, Codec>
+    where A: StreamHandler<<Codec as Decoder>::Item, FramedError<Codec>>
+             + Handler<Connected, Result=()> + Handler<Disconnected<Codec>, Result=()>,
+          A::Context: AsyncContext<A>,
+          Io: AsyncRead + AsyncWrite + 'static,
+          Codec: Encoder + Decoder + 'static,
+{
+    type Item = ();
+    type Error = ();
+    type Actor = A;
+
+    fn poll(&mut self, act: &mut A, ctx: &mut A::Context) -> Poll<(), ()> {
+        loop {
+            let closed = self.inner.borrow().closed;
+
+            let state = match self.state {
+                State::Delay(ref mut timeout) => {
+                    if closed {
+                        return Ok(Async::Ready(()))
+                    }
+                    if let Some(ref mut timeout) = *timeout {
+                        match timeout.poll() {
+                            Ok(Async::NotReady) => return Ok(Async::NotReady),
+                            Ok(Async::Ready(_)) | Err(_) => (),
+                        }
+                    }
+                    State::Connecting((self.connect)())
+                }
+                State::Connecting(ref mut fut) => {
+                    if closed {
+                        return Ok(Async::Ready(()))
+                    }
+                    match fut.poll() {
+                        Ok(Async::NotReady) => return Ok(Async::NotReady),
+                        Ok(Async::Ready(framed)) => {
+                            if let Some(ref mut handshake) = self.handshake {
+                                State::Handshake(handshake(framed))
+                            } else {
+                                self.connected(framed);
+                                <A as Handler<Connected>>::handle(act, Connected, ctx);
+                                continue
+                            }
+                        }
+                        Err(err) => {
+                            self.retry(&err);
+                            continue
+                        }
+                    }
+                }
+                State::Handshake(ref mut fut) => {
+                    if closed {
+                        return Ok(Async::Ready(()))
+                    }
+                    match fut.poll() {
+                        Ok(Async::NotReady) => return Ok(Async::NotReady),
+                        Ok(Async::Ready(framed)) => {
+                            self.connected(framed);
+                            <A as Handler<Connected>>::handle(act, Connected, ctx);
+                            continue
+                        }
+                        Err(err) => {
+                            self.retry(&err);
+                            continue
+                        }
+                    }
+                }
+                State::Connected(ref mut reader) => {
+                    // do not read while context is waiting, same as `add_stream()`
+                    if ctx.waiting() {
+                        return Ok(Async::NotReady)
+                    }
+                    let reason = match reader.poll() {
+                        Ok(Async::NotReady) => return Ok(Async::NotReady),
+                        Ok(Async::Ready(Some(item))) => {
+                            <A as StreamHandler<_, _>>::handle(act, item, ctx);
+                            continue
+                        }
+                        Ok(Async::Ready(None)) => DisconnectReason::Closed,
+                        Err(err) => DisconnectReason::Error(err),
+                    };
+                    self.inner.borrow_mut().writer.take();
+                    <A as Handler<Disconnected<Codec>>>::handle(
+                        act, Disconnected{reason: reason}, ctx);
+
+                    if self.inner.borrow().closed {
+                        return Ok(Async::Ready(()))
+                    }
+                    let delay = self.backoff.delay(self.attempt);
+                    self.attempt += 1;
+                    State::Delay(Some(Timeout::new(delay, Arbiter::handle()).unwrap()))
+                }
+            };
+            self.state = state;
+        }
+    }
+}
@@ -0,0 +1,68 @@
+//! Redelivery of messages after actor failure, see `Context::set_restart_policy()`
+use std::any::{Any, TypeId};
+use std::collections::HashMap;
+
+use actor::{Actor, AsyncContext};
+
+/// Restart policy of supervised actor
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum RestartPolicy {
+    /// Message that was handled during actor failure is lost,
+    /// panic in message handler is not caught. This is default policy.
+    DropMessage,
+    /// Supervisor restarts actor if message handler panics, message that was
+    /// handled during failure stays at the head of the mailbox and is delivered
+    /// to the restarted actor. After `max_attempts` failed deliveries message is
+    /// quarantined, it is published to `DeadLetters` service with
+    /// `DeadLetterReason::Poisoned` reason and mailbox continues with next message.
+    ///
+    /// Only message types registered with `Context::redeliver()` are delivered
+    /// again, other messages are lost as with `DropMessage` policy.
+    KeepMailbox {
+        max_attempts: u32,
+    },
+}
+
+impl Default for RestartPolicy {
+    fn default() -> RestartPolicy {
+        RestartPolicy::DropMessage
+    }
+}
+
+/// Message types that survive actor failure, see `Context::redeliver()`
+#[doc(hidden)]
+pub struct Redelivery {
+    clones: HashMap<TypeId, Box<Any>>,
+}
+
+impl Redelivery {
+    pub(crate) fn new() -> Redelivery {
+        Redelivery{clones: HashMap::new()}
+    }
+
+    pub(crate) fn add<M: Clone + 'static>(&mut self) {
+        let clone: fn(&M) -> M = M::clone;
+        self.clones.insert(TypeId::of::<M>(), Box::new(clone));
+    }
+
+    fn copy<M: 'static>(&self, msg: &M) -> Option<M> {
+        self.clones.get(&TypeId::of::<M>())
+            .and_then(|clone| clone.downcast_ref::<fn(&M) -> M>())
+            .map(|clone| clone(msg))
+    }
+}
+
+/// Take message out of the envelope, envelope keeps copy of the message
+/// if its type is redelivered after failure
+pub(crate) fn take<A, M>(msg: &mut Option<M>, ctx: &A::Context) -> Option<M>
+    where A: Actor, A::Context: AsyncContext<A>, M: 'static
+{
+    if let Some(redelivery) = ctx.redelivery() {
+        if let Some(ref msg) = *msg {
+            if let Some(copy) = redelivery.borrow().copy(msg) {
+                return Some(copy)
+            }
+        }
+    }
+    msg.take()
+}
@@ -4,7 +4,7 @@ use std::collections::HashMap;
 use std::default::Default;
 use std::sync::{Arc, Mutex};
 
-use actor::{Actor, Supervised};
+use actor::{Actor, AsyncContext, Supervised};
 use arbiter::Arbiter;
 use address::{Address, SyncAddress};
 use context::Context;
@@ -44,7 +44,7 @@ use supervisor::Supervisor;
 ///
 ///    fn handle(&mut self, _: Ping, ctx: &mut Context<Self>) {
 ///       println!("ping");
-/// #     Arbiter::system().send(actix::msgs::SystemExit(0));
+/// #     Arbiter::system().do_send(actix::msgs::SystemExit(0));
 ///    }
 /// }
 ///
@@ -55,7 +55,7 @@ use supervisor::Supervisor;
 ///
 ///    fn started(&mut self, _: &mut Context<Self>) {
 ///       let act = Arbiter::registry().get::<MyActor1>();
-///       act.send(Ping);
+///       act.do_send(Ping);
 ///    }
 /// }
 ///
@@ -121,17 +121,74 @@ impl Registry {
                 return addr.clone()
             }
         }
-        let addr: Address<_> = Supervisor::start(|ctx| {
-            let mut act = A::default();
-            act.service_started(ctx);
-            act
-        });
-
+        // service is registered before it starts, so services could
+        // resolve each other during startup
+        let mut ctx = Context::new(None);
+        let addr: Address<A> = ctx.address();
         self.registry.borrow_mut().insert(id, Box::new(addr.clone()));
+
+        let mut act = A::default();
+        act.service_started(&mut ctx);
+        ctx.set_actor(act);
+        Supervisor::spawn(ctx);
         addr
     }
 }
 
+/// Address of arbiter's service that is resolved on first use
+///
+/// `Lazy` does not touch registry until `get()` is called, service gets
+/// started by registry if it is not running yet. Actors that hold `Lazy`
+/// handles of each other could be created in any order, circular
+/// references included.
+///
+/// ```rust,ignore
+/// #[derive(Default)]
+/// struct Ping { pong: Lazy<Pong> }
+///
+/// #[derive(Default)]
+/// struct Pong { ping: Lazy<Ping> }
+///
+/// impl Handler<Msg> for Ping {
+///     fn handle(&mut self, msg: Msg, _: &mut Context<Self>) {
+///         self.pong.get().do_send(msg);
+///     }
+/// }
+/// ```
+pub struct Lazy<A: ArbiterService> {
+    addr: RefCell<Option<Address<A>>>,
+}
+
+impl<A: ArbiterService> Lazy<A> {
+    pub fn new() -> Lazy<A> {
+        Lazy{addr: RefCell::new(None)}
+    }
+
+    /// Address of the service, starts service if it is not running
+    ///
+    /// Panics if it is called outside of arbiter's thread.
+    pub fn get(&self) -> Address<A> {
+        self.addr.borrow_mut().get_or_insert_with(|| Arbiter::registry().get::<A>()).clone()
+    }
+
+    /// Check if address is resolved already
+    pub fn is_resolved(&self) -> bool {
+        self.addr.borrow().is_some()
+    }
+}
+
+impl<A: ArbiterService> Default for Lazy<A> {
+    fn default() -> Lazy<A> {
+        Lazy::new()
+    }
+}
+
+impl<A: ArbiterService> Clone for Lazy<A> {
+    fn clone(&self) -> Lazy<A> {
+        Lazy{addr: RefCell::new(self.addr.borrow().clone())}
+    }
+}
+
 // TODO: Remove lock
 /// System wide actors registry
 ///
@@ -0,0 +1,289 @@
+use std::io;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::net::SocketAddr;
+use std::time::Duration;
+
+use futures::{task, Async, Future, Poll};
+use futures::task::Task;
+use tokio_core::net::{TcpListener, TcpStream};
+use tokio_core::reactor::Timeout;
+
+use arbiter::Arbiter;
+use reconnect::Backoff;
+
+/// Socket options applied to accepted or connected streams
+///
+/// Options that are not set keep system defaults.
+///
+/// ```rust,ignore
+/// let config = SocketConfig::default()
+///     .nodelay(true)
+///     .keepalive(Some(Duration::from_secs(60)));
+/// TcpServer::new(addr).socket_config(config).start(factory)?;
+/// ```
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct SocketConfig {
+    /// Set `TCP_NODELAY`
+    pub nodelay: bool,
+    /// Set `SO_KEEPALIVE` with idle time
+    pub keepalive: Option<Duration>,
+    /// Size of receive buffer
+    pub recv_buf: Option<usize>,
+    /// Size of send buffer
+    pub send_buf: Option<usize>,
+}
+
+impl SocketConfig {
+    /// Set `TCP_NODELAY` option
+    pub fn nodelay(mut self, nodelay: bool) -> Self {
+        self.nodelay = nodelay;
+        self
+    }
+
+    /// Set keepalive idle time, `None` disables keepalive
+    pub fn keepalive(mut self, keepalive: Option<Duration>) -> Self {
+        self.keepalive = keepalive;
+        self
+    }
+
+    /// Set size of receive buffer
+    pub fn recv_buf(mut self, size: usize) -> Self {
+        self.recv_buf = Some(size);
+        self
+    }
+
+    /// Set size of send buffer
+    pub fn send_buf(mut self, size: usize) -> Self {
+        self.send_buf = Some(size);
+        self
+    }
+
+    /// Apply options to the stream
+    pub fn apply(&self, stream: &TcpStream) -> io::Result<()> {
+        stream.set_nodelay(self.nodelay)?;
+        stream.set_keepalive(self.keepalive)?;
+        if let Some(size) = self.recv_buf {
+            stream.set_recv_buffer_size(size)?;
+        }
+        if let Some(size) = self.send_buf {
+            stream.set_send_buffer_size(size)?;
+        }
+        Ok(())
+    }
+}
+
+/// TCP acceptor that runs in current arbiter
+///
+/// Server binds listener and calls session factory for every accepted
+/// connection, factory usually starts session actor for the stream.
+/// Accept errors, i.e. exhausted file descriptors, are logged and
+/// accepting continues after delay according to `Backoff` policy.
+///
+/// ```rust,ignore
+/// let server = TcpServer::new("127.0.0.1:12345".parse().unwrap())
+///     .start(|stream, peer| {
+///         ChatSession::create(|ctx| ChatSession::new(stream, peer, ctx));
+///     })?;
+///
+/// // stop accepting connections, existing sessions keep running
+/// server.stop();
+/// ```
+pub struct TcpServer {
+    addr: SocketAddr,
+    backoff: Backoff,
+    socket: SocketConfig,
+}
+
+impl TcpServer {
+    /// Create server for specified address
+    pub fn new(addr: SocketAddr) -> TcpServer {
+        TcpServer {
+            addr: addr,
+            backoff: Backoff::exponential(Duration::from_millis(10), Duration::from_secs(1)),
+            socket: SocketConfig::default(),
+        }
+    }
+
+    /// Set delay policy for failed accept attempts
+    ///
+    /// By default exponential backoff from 10 milliseconds to 1 second is used.
+    pub fn backoff(mut self, backoff: Backoff) -> Self {
+        self.backoff = backoff;
+        self
+    }
+
+    /// Set socket options of accepted streams
+    ///
+    /// Options are applied before stream is passed to session factory.
+    pub fn socket_config(mut self, config: SocketConfig) -> Self {
+        self.socket = config;
+        self
+    }
+
+    /// Bind listener and start accepting connections in current arbiter
+    ///
+    /// `factory` is called with stream and peer address of every
+    /// accepted connection.
+    pub fn start<F>(self, mut factory: F) -> io::Result<TcpServerHandle>
+        where F: FnMut(TcpStream, SocketAddr) + 'static
+    {
+        self.start_configured(move |stream, peer, _| factory(stream, peer))
+    }
+
+    /// Bind listener and start accepting connections in current arbiter
+    ///
+    /// Same as `start()`, but `factory` also gets socket options that are
+    /// applied to the stream. Factory could override options of particular
+    /// connection, i.e. `config.nodelay(false).apply(&stream)`.
+    pub fn start_configured<F>(self, factory: F) -> io::Result<TcpServerHandle>
+        where F: FnMut(TcpStream, SocketAddr, SocketConfig) + 'static
+    {
+        let listener = TcpListener::bind(&self.addr, Arbiter::handle())?;
+        let addr = listener.local_addr()?;
+        let inner = Rc::new(RefCell::new(
+            Inner{paused: false, stopped: false, task: None}));
+
+        Arbiter::handle().spawn(AcceptFut {
+            listener: Some(listener),
+            inner: Rc::clone(&inner),
+            factory: Box::new(factory),
+            socket: self.socket,
+            backoff: self.backoff,
+            attempt: 0,
+            delay: None,
+        });
+
+        Ok(TcpServerHandle{addr: addr, inner: inner})
+    }
+}
+
+struct Inner {
+    paused: bool,
+    stopped: bool,
+    task: Option<Task>,
+}
+
+impl Inner {
+    fn wake(&mut self) {
+        if let Some(task) = self.task.take() {
+            task.notify();
+        }
+    }
+}
+
+/// Handle of running `TcpServer`
+///
+/// Server keeps running if handle gets dropped, use `stop()` to close listener.
+#[derive(Clone)]
+pub struct TcpServerHandle {
+    addr: SocketAddr,
+    inner: Rc<RefCell<Inner>>,
+}
+
+impl TcpServerHandle {
+    /// Local address of the listener
+    pub fn local_addr(&self) -> SocketAddr {
+        self.addr
+    }
+
+    /// Check if server is paused
+    pub fn paused(&self) -> bool {
+        self.inner.borrow().paused
+    }
+
+    /// Stop accepting connections
+    ///
+    /// Listener stays open, new connections wait in listener's backlog
+    /// until server is resumed.
+    pub fn pause(&self) {
+        self.inner.borrow_mut().paused = true;
+    }
+
+    /// Continue accepting connections
+    pub fn resume(&self) {
+        let mut inner = self.inner.borrow_mut();
+        inner.paused = false;
+        inner.wake();
+    }
+
+    /// Close listener, connections from backlog are dropped
+    ///
+    /// Already accepted sessions are not affected.
+    pub fn stop(&self) {
+        let mut inner = self.inner.borrow_mut();
+        inner.stopped = true;
+        inner.wake();
+    }
+}
+
+struct AcceptFut {
+    listener: Option<TcpListener>,
+    inner: Rc<RefCell<Inner>>,
+    factory: Box<FnMut(TcpStream, SocketAddr, SocketConfig)>,
+    socket: SocketConfig,
+    backoff: Backoff,
+    attempt: usize,
+    delay: Option<Timeout>,
+}
+
+impl Future for AcceptFut {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        {
+            let mut inner = self.inner.borrow_mut();
+            if inner.stopped {
+                self.listener.take();
+                return Ok(Async::Ready(()))
+            }
+            inner.task = Some(task::current());
+            if inner.paused {
+                return Ok(Async::NotReady)
+            }
+        }
+
+        if let Some(mut delay) = self.delay.take() {
+            match delay.poll() {
+                Ok(Async::NotReady) => {
+                    self.delay = Some(delay);
+                    return Ok(Async::NotReady)
+                }
+                Ok(Async::Ready(_)) | Err(_) => (),
+            }
+        }
+
+        let listener = self.listener.as_mut().unwrap();
+        loop {
+            match listener.accept() {
+                Ok((stream, peer)) => {
+                    self.attempt = 0;
+                    if self.socket != SocketConfig::default() {
+                        if let Err(err) = self.socket.apply(&stream) {
+                            warn!("Can not set socket options of {}: {}", peer, err);
+                        }
+                    }
+                    (self.factory)(stream, peer, self.socket);
+
+                    // factory could pause or stop server
+                    let inner = self.inner.borrow();
+                    if inner.paused || inner.stopped {
+                        task::current().notify();
+                        return Ok(Async::NotReady)
+                    }
+                }
+                Err(ref err) if err.kind() == io::ErrorKind::WouldBlock =>
+                    return Ok(Async::NotReady),
+                Err(err) => {
+                    let delay = self.backoff.delay(self.attempt);
+                    self.attempt += 1;
+                    error!("Can not accept connection: {}, retry in {:?}", err, delay);
+                    self.delay = Some(Timeout::new(delay, Arbiter::handle()).unwrap());
+                    task::current().notify();
+                    return Ok(Async::NotReady)
+                }
+            }
+        }
+    }
+}
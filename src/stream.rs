@@ -2,6 +2,7 @@ use std::marker::PhantomData;
 use futures::{Async, Poll, Stream};
 
 use fut::ActorFuture;
+use budget;
 use actor::{Actor, ActorState, ActorContext, AsyncContext, SpawnHandle};
 
 /// Stream handler
@@ -50,7 +51,7 @@ pub trait StreamHandler<I, E> where Self: Actor
     ///
     ///     fn handle(&mut self, item: Ping, ctx: &mut Context<MyActor>) {
     ///         println!("PING");
-    /// #       Arbiter::system().send(actix::msgs::SystemExit(0));
+    /// #       Arbiter::system().do_send(actix::msgs::SystemExit(0));
     ///     }
     ///
     ///     fn finished(&mut self, ctx: &mut Self::Context) {
@@ -81,7 +82,7 @@ pub trait StreamHandler<I, E> where Self: Actor
             error!("Context::add_stream called for stopped actor.");
             SpawnHandle::default()
         } else {
-            ctx.spawn(ActorStream::new(fut))
+            ctx.spawn_internal(ActorStream::new(fut))
         }
     }
 }
@@ -114,20 +115,30 @@ impl<A, M, E, S> ActorFuture for ActorStream<A, M, E, S>
             self.started = true;
             <A as StreamHandler<M, E>>::started(act, ctx);
         }
-        
+
         loop {
+            // do not poll stream while context is waiting,
+            // so io backpressure applies
+            if ctx.waiting() {
+                return Ok(Async::NotReady)
+            }
+
+            // stream used its share of context's poll budget
+            if !budget::has_budget() {
+                return Ok(Async::NotReady)
+            }
+
             match self.stream.poll() {
                 Ok(Async::Ready(Some(msg))) => {
                     A::handle(act, msg, ctx);
-                    if ctx.waiting() {
-                        return Ok(Async::NotReady)
-                    }
+                    budget::consume();
                 }
                 Err(err) => {
                     if A::error(act, err, ctx) {
                         A::finished(act, ctx);
                         return Ok(Async::Ready(()))
                     }
+                    budget::consume();
                 },
                 Ok(Async::Ready(None)) => {
                     A::finished(act, ctx);
@@ -1,11 +1,147 @@
+use std::collections::VecDeque;
+use std::collections::hash_map::RandomState;
+use std::hash::{BuildHasher, Hasher};
+use std::time::{Duration, Instant};
 use futures::{Future, Async, Poll};
+use tokio_core::reactor::Timeout;
 
+use abort;
 use actor::{Actor, Supervised};
 use arbiter::Arbiter;
 use address::{sync_channel, ActorAddress, SyncAddress};
 use context::Context;
 use mailbox::DEFAULT_CAPACITY;
 use msgs::Execute;
+use reconnect::Backoff;
+use redelivery::RestartPolicy;
+
+/// Restart strategy of the supervisor
+///
+/// Defines how long supervisor waits before actor restarts and how many
+/// restarts are allowed. By default actor restarts immediately and
+/// unconditionally. Mailbox keeps accepting messages while supervisor waits,
+/// they are handled by the restarted actor.
+///
+/// ```rust
+/// # use std::time::Duration;
+/// # use actix::RestartStrategy;
+/// // 100ms, 200ms, 400ms ... up to 10s, delay is randomly shortened by up to 20%,
+/// // actor stops for good after 5 restarts within a minute
+/// let strategy = RestartStrategy::exponential(Duration::from_millis(100), Duration::from_secs(10))
+///     .jitter(0.2)
+///     .max_restarts(5, Duration::from_secs(60));
+/// ```
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct RestartStrategy {
+    backoff: Option<Backoff>,
+    jitter: f64,
+    limit: Option<(usize, Duration)>,
+}
+
+impl RestartStrategy {
+    /// Restart actor immediately, this is default strategy
+    pub fn immediate() -> RestartStrategy {
+        RestartStrategy{backoff: None, jitter: 0.0, limit: None}
+    }
+
+    /// Wait same amount of time before each restart
+    pub fn fixed(delay: Duration) -> RestartStrategy {
+        RestartStrategy{backoff: Some(Backoff::fixed(delay)), ..RestartStrategy::immediate()}
+    }
+
+    /// Double delay after each restart, but no longer than `max`
+    ///
+    /// Delay starts over from `initial` once actor runs longer
+    /// than `max` without failure.
+    pub fn exponential(initial: Duration, max: Duration) -> RestartStrategy {
+        RestartStrategy{backoff: Some(Backoff::exponential(initial, max)),
+                        ..RestartStrategy::immediate()}
+    }
+
+    /// Shorten each delay by random part of it, up to `ratio`
+    ///
+    /// Jitter spreads restarts of actors that fail at the same time.
+    /// Panics if `ratio` is not within `0.0..=1.0`.
+    pub fn jitter(mut self, ratio: f64) -> Self {
+        assert!(ratio >= 0.0 && ratio <= 1.0, "jitter ratio must be within 0.0..=1.0");
+        self.jitter = ratio;
+        self
+    }
+
+    /// Stop actor permanently if it fails more than `max` times within `window`
+    pub fn max_restarts(mut self, max: usize, window: Duration) -> Self {
+        self.limit = Some((max, window));
+        self
+    }
+}
+
+impl Default for RestartStrategy {
+    fn default() -> RestartStrategy {
+        RestartStrategy::immediate()
+    }
+}
+
+/// Restart history of supervised actor
+struct Restarts {
+    strategy: RestartStrategy,
+    history: VecDeque<Instant>,
+    attempt: usize,
+    restarted: Option<Instant>,
+    rng: u64,
+}
+
+impl Restarts {
+    fn new(strategy: RestartStrategy) -> Restarts {
+        Restarts {
+            strategy: strategy,
+            history: VecDeque::new(),
+            attempt: 0,
+            restarted: None,
+            rng: RandomState::new().build_hasher().finish() | 1,
+        }
+    }
+
+    /// Delay before next restart, `None` if restart limit is reached
+    fn next_delay(&mut self) -> Option<Duration> {
+        let now = Instant::now();
+        if let Some((max, window)) = self.strategy.limit {
+            while self.history.front().map_or(false, |t| now.duration_since(*t) > window) {
+                self.history.pop_front();
+            }
+            if self.history.len() >= max {
+                return None
+            }
+            self.history.push_back(now);
+        }
+
+        let backoff = match self.strategy.backoff {
+            Some(backoff) => backoff,
+            None => return Some(Duration::from_secs(0)),
+        };
+        // actor worked long enough, start over
+        if self.restarted.map_or(false, |t| now > t && now.duration_since(t) > backoff.max()) {
+            self.attempt = 0;
+        }
+        let mut delay = backoff.delay(self.attempt);
+        self.attempt += 1;
+
+        if self.strategy.jitter > 0.0 {
+            let ms = delay.as_secs() * 1000 + u64::from(delay.subsec_nanos() / 1_000_000);
+            let rand = (self.random() >> 11) as f64 / (1u64 << 53) as f64;
+            delay -= Duration::from_millis((ms as f64 * self.strategy.jitter * rand) as u64);
+        }
+        self.restarted = Some(now + delay);
+        Some(delay)
+    }
+
+    /// xorshift64* generator
+    fn random(&mut self) -> u64 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        self.rng.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+}
 
 /// Actor supervisor
 ///
@@ -18,7 +154,14 @@ use msgs::Execute;
 ///
 /// `Supervisor` can not guarantee that actor successfully process incoming message.
 /// If actor fails during message processing, this message can not be recovered. Sender
-/// would receive `Err(Cancelled)` error in this situation.
+/// would receive `Err(MailboxError::Canceled)` error in this situation.
+///
+/// With `RestartPolicy::KeepMailbox` policy supervisor restarts actor if message
+/// handler panics, and message is delivered to the restarted actor again,
+/// see `Context::set_restart_policy()`.
+///
+/// Supervisor restarts actor immediately, use `RestartStrategy` to delay
+/// restarts or to limit number of restarts, see `start_with_strategy()`.
 ///
 /// ## Example
 ///
@@ -46,7 +189,7 @@ use msgs::Execute;
 ///
 ///     fn handle(&mut self, _: Die, ctx: &mut Context<MyActor>) {
 ///         ctx.stop();
-/// #       Arbiter::system().send(actix::msgs::SystemExit(0));
+/// #       Arbiter::system().do_send(actix::msgs::SystemExit(0));
 ///     }
 /// }
 ///
@@ -55,12 +198,14 @@ use msgs::Execute;
 ///
 ///     let addr: Address<_> = actix::Supervisor::start(|_| MyActor);
 ///
-///     addr.send(Die);
+///     addr.do_send(Die);
 ///     sys.run();
 /// }
 /// ```
 pub struct Supervisor<A> where A: Supervised + Actor<Context=Context<A>> {
-    ctx: A::Context
+    ctx: A::Context,
+    restarts: Restarts,
+    delay: Option<Timeout>,
 }
 
 impl<A> Supervisor<A> where A: Supervised + Actor<Context=Context<A>>
@@ -92,6 +237,17 @@ impl<A> Supervisor<A> where A: Supervised + Actor<Context=Context<A>>
     pub fn start<Addr, F>(f: F) -> Addr
         where F: FnOnce(&mut A::Context) -> A + 'static,
               A: Actor<Context=Context<A>> + ActorAddress<A, Addr>
+    {
+        Supervisor::start_with_strategy(RestartStrategy::default(), f)
+    }
+
+    /// Start new supervised actor in current Arbiter with specific restart strategy
+    ///
+    /// Once restart limit of the strategy is reached, supervisor stops
+    /// actor permanently and its mailbox gets closed.
+    pub fn start_with_strategy<Addr, F>(strategy: RestartStrategy, f: F) -> Addr
+        where F: FnOnce(&mut A::Context) -> A + 'static,
+              A: Actor<Context=Context<A>> + ActorAddress<A, Addr>
     {
         // create actor
         let mut ctx = Context::new(None);
@@ -100,7 +256,7 @@ impl<A> Supervisor<A> where A: Supervised + Actor<Context=Context<A>>
         ctx.set_actor(act);
 
         // create supervisor
-        Arbiter::handle().spawn(Supervisor::<A>{ctx: ctx});
+        Supervisor::spawn_with_strategy(ctx, strategy);
 
         addr
     }
@@ -112,16 +268,27 @@ impl<A> Supervisor<A> where A: Supervised + Actor<Context=Context<A>>
     {
         let (tx, rx) = sync_channel::channel(DEFAULT_CAPACITY);
 
-        addr.send(Execute::new(move || -> Result<(), ()> {
+        addr.do_send(Execute::new(move || -> Result<(), ()> {
             let mut ctx = Context::with_receiver(None, rx);
             let act = f(&mut ctx);
             ctx.set_actor(act);
-            Arbiter::handle().spawn(Supervisor::<A>{ctx: ctx});
+            Supervisor::spawn(ctx);
             Ok(())
         }));
 
         SyncAddress::new(tx)
     }
+
+    /// Run supervised context in current arbiter
+    pub(crate) fn spawn(ctx: Context<A>) {
+        Supervisor::spawn_with_strategy(ctx, RestartStrategy::default())
+    }
+
+    /// Run supervised context in current arbiter with specific restart strategy
+    pub(crate) fn spawn_with_strategy(ctx: Context<A>, strategy: RestartStrategy) {
+        Arbiter::handle().spawn(
+            Supervisor::<A>{ctx: ctx, restarts: Restarts::new(strategy), delay: None});
+    }
 }
 
 #[doc(hidden)]
@@ -131,14 +298,51 @@ impl<A> Future for Supervisor<A> where A: Supervised + Actor<Context=Context<A>>
 
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
         loop {
-            match self.ctx.poll() {
+            // actor waits for restart
+            if let Some(mut delay) = self.delay.take() {
+                if let Ok(Async::NotReady) = delay.poll() {
+                    self.delay = Some(delay);
+                    return Ok(Async::NotReady)
+                }
+                if !self.ctx.restart() {
+                    return Ok(Async::Ready(()))
+                }
+            }
+
+            let res = match self.ctx.restart_policy() {
+                RestartPolicy::DropMessage => self.ctx.poll(),
+                RestartPolicy::KeepMailbox{..} => {
+                    let ctx = &mut self.ctx;
+                    match abort::catch_unwind("supervised actor restart", || ctx.poll()) {
+                        Ok(res) => res,
+                        Err(_) => {
+                            error!("Supervised actor panicked, restarting");
+                            Err(())
+                        }
+                    }
+                }
+            };
+            match res {
                 Ok(Async::NotReady) =>
                     return Ok(Async::NotReady),
                 Ok(Async::Ready(_)) | Err(_) => {
                     // stop if context's address is not connected
-                    if !self.ctx.restart() {
+                    if !self.ctx.restartable() {
                         return Ok(Async::Ready(()))
                     }
+                    match self.restarts.next_delay() {
+                        None => {
+                            error!("Supervised actor reached restart limit, stopping");
+                            return Ok(Async::Ready(()))
+                        }
+                        Some(delay) => if delay == Duration::from_secs(0) {
+                            if !self.ctx.restart() {
+                                return Ok(Async::Ready(()))
+                            }
+                        } else {
+                            self.delay = Some(Timeout::new(delay, Arbiter::handle()).unwrap());
+                        },
+                    }
                 }
             }
         }
@@ -61,11 +61,11 @@
 //!
 //!     // send 5 messages
 //!     for n in 5..10 {
-//!         addr.send(Fibonacci(n));
+//!         addr.do_send(Fibonacci(n));
 //!     }
 //!
 //!     Arbiter::handle().spawn_fn(|| {
-//! #        Arbiter::system().send(actix::msgs::SystemExit(0));
+//! #        Arbiter::system().do_send(actix::msgs::SystemExit(0));
 //!         futures::future::result(Ok(()))
 //!     });
 //!
@@ -73,19 +73,26 @@
 //! }
 //! ```
 use std::{mem, thread};
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::any::{type_name, Any};
 use std::marker::PhantomData;
 
 use crossbeam_channel as channel;
 use futures::{Async, Future, Poll, Stream};
+use futures::sync::oneshot::{self as sync_oneshot, Canceled, Receiver as SyncReceiver};
 use futures::sync::oneshot::Sender as SyncSender;
 
 use actor::{Actor, ActorContext, ActorState};
 use arbiter::Arbiter;
 use address::{sync_channel, SyncAddress, SyncAddressReceiver,
-              Envelope, EnvelopeProxy, ToEnvelope};
+              Envelope, EnvelopeProxy, ToEnvelope, SendError, Subscriber};
 use context::Context;
 use handler::{Handler, ResponseType, MessageResponse, MessageResult};
+#[cfg(feature="metrics")]
+use actors::metrics::{MetricsExporter, RegisterSource, SyncArbiterMetrics};
+#[cfg(feature="metrics")]
+use registry::SystemService;
 
 
 /// Sync arbiter
@@ -93,6 +100,19 @@ pub struct SyncArbiter<A> where A: Actor<Context=SyncContext<A>> {
     queue: channel::Sender<SyncContextProtocol<A>>,
     msgs: SyncAddressReceiver<A>,
     threads: usize,
+    stats: Arc<SyncStats>,
+}
+
+/// Sync arbiter counters, shared by arbiter and its worker threads
+pub(crate) struct SyncStats {
+    pub actor: &'static str,
+    pub arbiter: String,
+    pub threads: usize,
+    /// Messages waiting for free worker
+    pub queued: AtomicUsize,
+    /// Workers that handle message at the moment
+    pub busy: AtomicUsize,
+    pub handled: AtomicUsize,
 }
 
 impl<A> SyncArbiter<A> where A: Actor<Context=SyncContext<A>> + Send {
@@ -104,19 +124,32 @@ impl<A> SyncArbiter<A> where A: Actor<Context=SyncContext<A>> + Send {
     {
         let factory = Arc::new(factory);
         let (sender, receiver) = channel::unbounded();
+        let stats = Arc::new(SyncStats {
+            actor: type_name::<A>(),
+            arbiter: Arbiter::name(),
+            threads: threads,
+            queued: AtomicUsize::new(0),
+            busy: AtomicUsize::new(0),
+            handled: AtomicUsize::new(0),
+        });
 
         for _ in 0..threads {
             let f = Arc::clone(&factory);
             let actor_queue = receiver.clone();
+            let stats = Arc::clone(&stats);
 
             thread::spawn(move || {
-                SyncContext::new(f, actor_queue).run()
+                SyncContext::new(f, actor_queue, stats).run()
             });
         }
 
-        let (tx, rx) = sync_channel::channel(0);
+        #[cfg(feature="metrics")]
+        MetricsExporter::from_registry().do_send(
+            RegisterSource(Box::new(SyncArbiterMetrics::new(&stats))));
+
+        let (tx, rx) = sync_channel::channel(sync_channel::UNBOUNDED);
         Arbiter::handle().spawn(
-            SyncArbiter{queue: sender, msgs: rx, threads: threads});
+            SyncArbiter{queue: sender, msgs: rx, threads: threads, stats: stats});
 
         SyncAddress::new(tx)
     }
@@ -135,9 +168,11 @@ impl<A> Future for SyncArbiter<A> where A: Actor<Context=SyncContext<A>>
     fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
         loop {
             match self.msgs.poll() {
-                Ok(Async::Ready(Some(msg))) =>
+                Ok(Async::Ready(Some(msg))) => {
+                    self.stats.queued.fetch_add(1, Ordering::Relaxed);
                     self.queue.send(SyncContextProtocol::Envelope(msg))
-                    .expect("Should not fail"),
+                        .expect("Should not fail")
+                }
                 Ok(Async::NotReady) => break,
                 Ok(Async::Ready(None)) | Err(_) => unreachable!(),
             }
@@ -166,6 +201,12 @@ impl<A> ToEnvelope<A> for SyncContext<A>
     {
         Envelope::new(SyncEnvelope::new(msg, tx))
     }
+
+    fn pack_msg<M>(msg: M) -> Envelope<A>
+        where A: Handler<M>, M: ResponseType + Send + 'static,
+    {
+        Envelope::new(SyncEnvelope::new(msg, None))
+    }
 }
 
 enum SyncContextProtocol<A> where A: Actor<Context=SyncContext<A>> {
@@ -180,18 +221,20 @@ pub struct SyncContext<A> where A: Actor<Context=SyncContext<A>> {
     stopping: bool,
     state: ActorState,
     factory: Arc<Fn() -> A + Send + Sync>,
+    stats: Arc<SyncStats>,
 }
 
 impl<A> SyncContext<A> where A: Actor<Context=Self> {
     /// Create new SyncContext
     fn new(factory: Arc<Fn() -> A+Send+Sync>,
-           queue: channel::Receiver<SyncContextProtocol<A>>) -> Self {
+           queue: channel::Receiver<SyncContextProtocol<A>>, stats: Arc<SyncStats>) -> Self {
         SyncContext {
             act: factory(),
             queue: queue,
             stopping: false,
             state: ActorState::Started,
             factory: factory,
+            stats: stats,
         }
     }
 
@@ -216,7 +259,11 @@ impl<A> SyncContext<A> where A: Actor<Context=Self> {
                     return
                 },
                 Ok(SyncContextProtocol::Envelope(mut env)) => {
+                    self.stats.queued.fetch_sub(1, Ordering::Relaxed);
+                    self.stats.busy.fetch_add(1, Ordering::Relaxed);
                     env.handle(&mut self.act, ctx);
+                    self.stats.busy.fetch_sub(1, Ordering::Relaxed);
+                    self.stats.handled.fetch_add(1, Ordering::Relaxed);
                 },
                 Err(_) => (),
             }
@@ -296,3 +343,118 @@ impl<A, M> EnvelopeProxy for SyncEnvelope<A, M>
         }
     }
 }
+
+/// Create one-time value handoff, returns completer and listener
+///
+/// `Completer` could be used from any thread, `Listener` is a future that
+/// resolves with completed value. Use `into_actor()` to wait for a value
+/// within actor's context.
+///
+/// ```rust
+/// # extern crate actix;
+/// # extern crate futures;
+/// # use futures::Future;
+/// use actix::prelude::*;
+///
+/// fn main() {
+///     let sys = System::new("test");
+///     let (completer, listener) = actix::sync::oneshot();
+///
+///     std::thread::spawn(move || {
+///         completer.complete(42).unwrap();
+///     });
+///
+///     Arbiter::handle().spawn(listener.then(|res| {
+///         assert_eq!(res.unwrap(), 42);
+/// #       Arbiter::system().do_send(actix::msgs::SystemExit(0));
+///         Ok(())
+///     }));
+///
+///     sys.run();
+/// }
+/// ```
+pub fn oneshot<T>() -> (Completer<T>, Listener<T>) {
+    let (tx, rx) = sync_oneshot::channel();
+    (Completer{tx: Arc::new(Mutex::new(Some(tx)))}, Listener{rx: rx})
+}
+
+/// Completing side of `oneshot()`
+///
+/// Completer is cloneable, first `complete()` call wins. If all completers
+/// get dropped without completion, listener resolves with `Canceled` error.
+/// Completer implements `Subscriber<T>`, so it could be used anywhere
+/// actor's subscriber is expected.
+pub struct Completer<T> {
+    tx: Arc<Mutex<Option<SyncSender<T>>>>,
+}
+
+impl<T> Completer<T> {
+    /// Complete with value
+    ///
+    /// Returns value back if completer is already completed or
+    /// listener is dropped.
+    pub fn complete(&self, value: T) -> Result<(), T> {
+        if let Some(tx) = self.tx.lock().unwrap().take() {
+            tx.send(value)
+        } else {
+            Err(value)
+        }
+    }
+
+    /// Check if value is already completed or listener is dropped
+    pub fn is_completed(&self) -> bool {
+        match *self.tx.lock().unwrap() {
+            Some(ref tx) => tx.is_canceled(),
+            None => true,
+        }
+    }
+}
+
+impl<T> Clone for Completer<T> {
+    fn clone(&self) -> Self {
+        Completer{tx: Arc::clone(&self.tx)}
+    }
+}
+
+impl<T: Send + 'static> Subscriber<T> for Completer<T> {
+
+    fn send(&self, msg: T) -> Result<(), SendError<T>> {
+        self.complete(msg).map_err(SendError::Closed)
+    }
+
+    fn try_send(&self, msg: T) -> Result<(), SendError<T>> {
+        self.complete(msg).map_err(SendError::Closed)
+    }
+
+    fn try_send_nowait(&self, msg: T) -> Result<(), SendError<T>> {
+        self.complete(msg).map_err(SendError::Closed)
+    }
+
+    fn as_any(&self) -> &Any {
+        self
+    }
+
+    fn subscriber_eq(&self, other: &Subscriber<T>) -> bool {
+        other.as_any().downcast_ref::<Completer<T>>()
+            .map_or(false, |other| Arc::ptr_eq(&self.tx, &other.tx))
+    }
+
+    fn boxed(&self) -> Box<Subscriber<T>> {
+        Box::new(self.clone())
+    }
+}
+
+/// Listening side of `oneshot()`, resolves with completed value
+#[must_use = "future do nothing unless polled"]
+pub struct Listener<T> {
+    rx: SyncReceiver<T>,
+}
+
+impl<T> Future for Listener<T> {
+    type Item = T;
+    type Error = Canceled;
+
+    fn poll(&mut self) -> Poll<T, Canceled> {
+        self.rx.poll()
+    }
+}
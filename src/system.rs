@@ -1,14 +1,33 @@
-use std::collections::HashMap;
-use tokio_core::reactor::{Core, Handle};
-use futures::Future;
+use std::mem;
+use std::panic::PanicInfo;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::collections::{BTreeMap, HashMap};
+use tokio_core::reactor::{Core, Handle, Timeout};
+use futures::{future, Async, Future};
 use futures::sync::oneshot::{channel, Receiver, Sender};
 
+use abort;
 use actor::Actor;
-use address::SyncAddress;
-use arbiter::Arbiter;
+use actors::deadletter;
+use address::{Overflow, SyncAddress};
+use arbiter::{Arbiter, ActorInfo};
 use handler::{Handler, ResponseType};
 use context::Context;
-use msgs::{SystemExit, StopArbiter};
+use mailbox::DEFAULT_CAPACITY;
+use msgs::{SystemExit, StopArbiterGraceful, ShutdownPhase, PhaseStopper};
+use pump;
+
+/// Maximum number of event loop turns within single `run_pending()` call
+const MAX_PUMP_TURNS: usize = 256;
+
+/// Default maximum number of blocking pool threads
+const DEFAULT_BLOCKING_THREADS: usize = 16;
+
+/// Time arbiters get after shutdown timeout to report abandoned actors
+const ARBITER_GRACE: u64 = 200;
 
 /// System is an actor which manages process.
 ///
@@ -33,7 +52,7 @@ use msgs::{SystemExit, StopArbiter};
 ///    fn started(&mut self, ctx: &mut Context<Self>) {
 ///        ctx.run_later(self.dur, |act, ctx| {
 ///            // send `SystemExit` to `System` actor.
-///            Arbiter::system().send(actix::msgs::SystemExit(0));
+///            Arbiter::system().do_send(actix::msgs::SystemExit(0));
 ///        });
 ///    }
 /// }
@@ -51,8 +70,11 @@ use msgs::{SystemExit, StopArbiter};
 /// }
 /// ```
 pub struct System {
-    stop: Option<Sender<i32>>,
+    stop: Option<Sender<ShutdownReport>>,
     arbiters: HashMap<String, SyncAddress<Arbiter>>,
+    stop_on_failure: bool,
+    shutdown_timeout: Duration,
+    phases: BTreeMap<usize, Vec<Box<PhaseStopper>>>,
 }
 
 impl Actor for System {
@@ -64,27 +86,220 @@ impl System {
     #[cfg_attr(feature="cargo-clippy", allow(new_ret_no_self))]
     /// Create new system
     pub fn new<T: Into<String>>(name: T) -> SystemRunner {
-        let name = name.into();
-        let core = Arbiter::new_system(name.clone());
+        System::with_config(SystemConfig::new(name))
+    }
+
+    /// Create new system with configuration
+    ///
+    /// Configuration could not be changed once system is created.
+    pub fn with_config(cfg: SystemConfig) -> SystemRunner {
+        let name = cfg.name.clone();
+        let timeout = cfg.shutdown_timeout;
+        let core = Arbiter::new_system(name.clone(), Arc::new(cfg));
         let (stop_tx, stop_rx) = channel();
 
         // start system
         let sys = System {
-            arbiters: HashMap::new(), stop: Some(stop_tx)}.start();
+            arbiters: HashMap::new(), stop: Some(stop_tx), stop_on_failure: true,
+            shutdown_timeout: timeout, phases: BTreeMap::new()}.start();
         Arbiter::set_system(sys, name);
 
         SystemRunner {
             core: core,
             stop: stop_rx,
+            report: None,
         }
     }
+
+    /// Check if panics unwind
+    ///
+    /// With `panic = "abort"` panic terminates the process, so actor restart
+    /// of `RestartPolicy::KeepMailbox`, panic isolation of blocking jobs and
+    /// `FramedWriter::set_flush_on_failure()` are disabled, warning is
+    /// logged once when one of them is used.
+    pub fn unwind_supported() -> bool {
+        abort::unwind_supported()
+    }
+
+    /// Set last-resort handler for panics
+    ///
+    /// Handler runs from panic hook after previously installed hook,
+    /// then `log` integration gets flushed, so log records are not lost
+    /// if process aborts. Metrics exporter renders metrics on request and
+    /// does not buffer output. Without `panic = "abort"` handler runs for
+    /// every panic as well, including panics that get caught.
+    ///
+    /// Subsequent call replaces handler.
+    pub fn set_abort_handler(handler: Box<Fn(&PanicInfo) + Send + Sync>) {
+        abort::set_handler(handler)
+    }
+}
+
+/// System configuration, see `System::with_config()`
+///
+/// Mailbox settings are defaults for every actor of the system,
+/// actor could override them with `ActorBuilder`.
+///
+/// ```rust
+/// # extern crate actix;
+/// # use actix::*;
+/// # fn main() {
+/// let sys = System::with_config(
+///     SystemConfig::new("app")
+///         .mailbox_capacity(64)
+///         .slow_handler(std::time::Duration::from_millis(10)));
+/// # }
+/// ```
+#[derive(Clone, Debug)]
+pub struct SystemConfig {
+    pub(crate) name: String,
+    pub(crate) capacity: usize,
+    pub(crate) overflow: Overflow,
+    pub(crate) slow_handler: Option<Duration>,
+    pub(crate) deadlock: Option<(Duration, bool)>,
+    pub(crate) spin_threshold: Option<u32>,
+    pub(crate) poll_budget: usize,
+    pub(crate) blocking_threads: usize,
+    pub(crate) shutdown_timeout: Duration,
+}
+
+impl SystemConfig {
+    /// Create configuration with default settings
+    pub fn new<T: Into<String>>(name: T) -> SystemConfig {
+        SystemConfig {
+            name: name.into(),
+            capacity: DEFAULT_CAPACITY,
+            overflow: Overflow::Block,
+            slow_handler: None,
+            deadlock: None,
+            spin_threshold: None,
+            poll_budget: 0,
+            blocking_threads: DEFAULT_BLOCKING_THREADS,
+            shutdown_timeout: Duration::from_secs(5),
+        }
+    }
+
+    /// Set default mailbox capacity, 16 messages by default
+    pub fn mailbox_capacity(mut self, cap: usize) -> Self {
+        self.capacity = cap;
+        self
+    }
+
+    /// Set default mailbox overflow policy, `Overflow::Block` by default
+    pub fn overflow(mut self, overflow: Overflow) -> Self {
+        self.overflow = overflow;
+        self
+    }
+
+    /// Log warning for message handlers that run longer than `dur`
+    ///
+    /// By default handlers are not timed.
+    pub fn slow_handler(mut self, dur: Duration) -> Self {
+        self.slow_handler = Some(dur);
+        self
+    }
+
+    /// Detect actors that stay in `wait()` longer than `threshold`
+    ///
+    /// See `Context::set_deadlock_detection()`, detection is disabled by default.
+    pub fn deadlock_detection(mut self, threshold: Duration, break_wait: bool) -> Self {
+        self.deadlock = Some((threshold, break_wait));
+        self
+    }
+
+    /// Warn about actors that are polled more than `threshold` times
+    /// per second without progress
+    ///
+    /// See `Context::set_spin_detection()`, detection is disabled by default.
+    pub fn spin_detection(mut self, threshold: u32) -> Self {
+        self.spin_threshold = Some(threshold);
+        self
+    }
+
+    /// Limit number of items actor handles within single poll
+    ///
+    /// See `Context::set_poll_budget()`, budget is not limited by default.
+    pub fn poll_budget(mut self, budget: usize) -> Self {
+        self.poll_budget = budget;
+        self
+    }
+
+    /// Set maximum number of blocking pool threads, 16 threads by default
+    ///
+    /// See `Arbiter::spawn_blocking()`.
+    pub fn blocking_threads(mut self, threads: usize) -> Self {
+        self.blocking_threads = threads;
+        self
+    }
+
+    /// Set system shutdown timeout, see `SystemRunner::shutdown_timeout()`
+    pub fn shutdown_timeout(mut self, timeout: Duration) -> Self {
+        self.shutdown_timeout = timeout;
+        self
+    }
 }
 
 /// Helper object that runs System's event loop
 #[must_use="SystemRunner must be run"]
 pub struct SystemRunner {
     core: Core,
-    stop: Receiver<i32>,
+    stop: Receiver<ShutdownReport>,
+    report: Option<ShutdownReport>,
+}
+
+/// Work done by `SystemRunner::run_pending()`
+#[derive(Debug, Default, Clone, Copy, PartialEq)]
+pub struct PumpReport {
+    /// Number of event loop turns
+    pub turns: usize,
+    /// Number of actor context polls
+    pub polls: usize,
+    /// Number of handled mailbox messages
+    pub messages: usize,
+    /// Earliest deadline of pending context timers, i.e. `run_later()`,
+    /// `notify_later()`, `notify_interval()` and `run_interval_at()` timers
+    pub next_timer: Option<Instant>,
+    /// Exit code, set once `SystemExit` is handled and system is shut down
+    pub exit_code: Option<i32>,
+}
+
+/// Why system stopped, see `ShutdownReport::reason`
+#[derive(Debug, Clone, PartialEq)]
+pub enum SystemExitReason {
+    /// `SystemExit` message got received
+    Exit,
+    /// Arbiter's thread terminated unexpectedly,
+    /// see `SystemRunner::stop_on_arbiter_failure()`
+    ArbiterDied {
+        /// Arbiter's id
+        id: String,
+        /// Arbiter's thread name, it includes name passed to `Arbiter::new()`
+        name: String,
+    },
+}
+
+impl Default for SystemExitReason {
+    fn default() -> Self {
+        SystemExitReason::Exit
+    }
+}
+
+/// System shutdown summary, see `SystemRunner::run_report()`
+#[derive(Debug, Default, Clone, PartialEq)]
+pub struct ShutdownReport {
+    /// Exit code of the system
+    pub exit_code: i32,
+    /// Why system stopped
+    pub reason: SystemExitReason,
+    /// Actors that did not stop within shutdown timeout
+    pub forced: Vec<ActorInfo>,
+    /// Ids of arbiters that did not respond within shutdown timeout,
+    /// their threads are left running
+    pub arbiters: Vec<String>,
+    /// Number of dead letters produced during shutdown
+    pub dead_letters: usize,
+    /// Time spent on shutdown
+    pub duration: Duration,
 }
 
 impl SystemRunner {
@@ -94,18 +309,96 @@ impl SystemRunner {
         Arbiter::handle()
     }
 
+    /// Stop system if one of the arbiters' threads dies unexpectedly
+    ///
+    /// By default this behavior is enabled, in this case `run()` method
+    /// returns `1` as exit code. Failed arbiter is reported in
+    /// `ShutdownReport::reason`, see `run_report()`.
+    pub fn stop_on_arbiter_failure(self, val: bool) -> Self {
+        Arbiter::system().do_send(StopOnArbiterFailure(val));
+        self
+    }
+
+    /// Set system shutdown timeout
+    ///
+    /// On `SystemExit` system asks all arbiters to stop their actors and waits
+    /// until every actor completes `stopping` and `stopped` lifecycle methods,
+    /// but no longer than shutdown timeout. By default timeout is 5 seconds.
+    /// Actors that are still running get abandoned, see `run_report()`.
+    pub fn shutdown_timeout(self, timeout: Duration) -> Self {
+        Arbiter::system().do_send(ShutdownTimeout(timeout));
+        self
+    }
+
     /// This function will start event loop and will finish once the `SystemExit`
     /// message get received.
+    ///
+    /// Actors running in other arbiters are stopped before this function returns,
+    /// see `shutdown_timeout()`.
     pub fn run(self) -> i32 {
-        let SystemRunner { mut core, stop, ..} = self;
+        self.run_report().exit_code
+    }
+
+    /// Same as `run()`, but returns shutdown report
+    ///
+    /// Report lists actors that did not stop within shutdown timeout,
+    /// their queued messages are published as dead letters.
+    pub fn run_report(self) -> ShutdownReport {
+        let SystemRunner { mut core, stop, report } = self;
+        if let Some(report) = report {
+            return report
+        }
 
         // run loop
         match core.run(stop) {
-            Ok(code) => code,
-            Err(_) => 1,
+            Ok(report) => report,
+            Err(_) => ShutdownReport{exit_code: 1, ..Default::default()},
         }
     }
 
+    /// Process all ready work of the current thread and return
+    ///
+    /// This method is for embedding actix into event loop of another
+    /// application, it has to be called periodically instead of `run()`.
+    /// Event loop is turned without blocking until actors of the current
+    /// thread stop making progress. Report contains amount of done work
+    /// and deadline of the next timer, so caller could sleep until it.
+    /// Arbiters in other threads keep running on their own.
+    ///
+    /// Work accounting starts with the first call, timers scheduled
+    /// before it are not reported in `PumpReport::next_timer`.
+    pub fn run_pending(&mut self) -> PumpReport {
+        pump::enable();
+        let (polls, messages) = pump::counters();
+        let mut report = PumpReport::default();
+
+        loop {
+            let before = pump::counters();
+            self.core.turn(Some(Duration::new(0, 0)));
+            report.turns += 1;
+            if pump::counters() == before || report.turns == MAX_PUMP_TURNS {
+                break
+            }
+        }
+
+        if self.report.is_none() {
+            let stop = &mut self.stop;
+            self.report = self.core.run(future::lazy(|| Ok::<_, ()>(
+                match stop.poll() {
+                    Ok(Async::Ready(report)) => Some(report),
+                    Ok(Async::NotReady) => None,
+                    Err(_) => Some(ShutdownReport{exit_code: 1, ..Default::default()}),
+                }))).unwrap();
+        }
+
+        let (polls_after, messages_after) = pump::counters();
+        report.polls = polls_after.wrapping_sub(polls);
+        report.messages = messages_after.wrapping_sub(messages);
+        report.next_timer = pump::next_deadline();
+        report.exit_code = self.report.as_ref().map(|r| r.exit_code);
+        report
+    }
+
     pub fn run_until_complete<F, I, E>(&mut self, fut: F) -> Result<I, E>
         where F: Future<Item=I, Error=E>
     {
@@ -113,19 +406,73 @@ impl SystemRunner {
     }
 }
 
+impl System {
+    fn exit(&mut self, code: i32, reason: SystemExitReason) {
+        let stop = if let Some(stop) = self.stop.take() {
+            stop
+        } else {
+            return
+        };
+
+        // stop registered actors phase by phase
+        let phases = mem::replace(&mut self.phases, BTreeMap::new());
+        let mut stop_phases: Box<Future<Item=(), Error=()>> = Box::new(future::ok(()));
+        for (_, actors) in phases {
+            stop_phases = Box::new(stop_phases.and_then(move |_| {
+                future::join_all(actors.iter().map(|addr| addr.stop()).collect::<Vec<_>>())
+                    .map(|_| ())
+            }));
+        }
+
+        // gracefully stop arbiters, each arbiter responds once
+        // all its actors are stopped or reports abandoned actors
+        let start = Instant::now();
+        let letters = deadletter::published();
+        let timeout = self.shutdown_timeout;
+        let forced = Rc::new(RefCell::new(Vec::new()));
+        let pending = Rc::new(RefCell::new(
+            self.arbiters.keys().cloned().collect::<Vec<_>>()));
+        let arbiters: Vec<_> = self.arbiters.iter()
+            .map(|(id, addr)| (id.clone(), addr.clone())).collect();
+        let (f, p) = (Rc::clone(&forced), Rc::clone(&pending));
+        let arbiters = stop_phases.and_then(move |_| future::join_all(
+            arbiters.into_iter().map(move |(id, addr)| {
+                let (forced, pending) = (Rc::clone(&f), Rc::clone(&p));
+                addr.call_fut(StopArbiterGraceful{timeout: timeout})
+                    .then(move |res| {
+                        if let Ok(Err(actors)) = res {
+                            forced.borrow_mut().extend(actors);
+                        }
+                        pending.borrow_mut().retain(|item| *item != id);
+                        Ok::<_, ()>(())
+                    })
+            }).collect::<Vec<_>>()));
+
+        // stop event loop, but wait no longer than shutdown timeout
+        let timeout = timeout + Duration::from_millis(ARBITER_GRACE);
+        Arbiter::handle().spawn(
+            arbiters
+                .select2(Timeout::new(timeout, Arbiter::handle()).unwrap())
+                .then(move |_| {
+                    let _ = stop.send(ShutdownReport {
+                        exit_code: code,
+                        reason: reason,
+                        forced: mem::replace(&mut *forced.borrow_mut(), Vec::new()),
+                        arbiters: mem::replace(&mut *pending.borrow_mut(), Vec::new()),
+                        dead_letters: deadletter::published().wrapping_sub(letters),
+                        duration: start.elapsed(),
+                    });
+                    Ok(())
+                }));
+    }
+}
+
 impl Handler<SystemExit> for System {
     type Result = ();
 
     fn handle(&mut self, msg: SystemExit, _: &mut Context<Self>)
     {
-        // stop arbiters
-        for addr in self.arbiters.values() {
-            addr.send(StopArbiter(msg.0));
-        }
-        // stop event loop
-        if let Some(stop) = self.stop.take() {
-            let _ = stop.send(msg.0);
-        }
+        self.exit(msg.0, SystemExitReason::Exit)
     }
 }
 
@@ -165,3 +512,76 @@ impl Handler<UnregisterArbiter> for System {
         self.arbiters.remove(&msg.0);
     }
 }
+
+/// Arbiter's thread terminated unexpectedly
+pub(crate) struct ArbiterDied(pub String, pub String);
+
+#[doc(hidden)]
+impl ResponseType for ArbiterDied {
+    type Item = ();
+    type Error = ();
+}
+
+#[doc(hidden)]
+impl Handler<ArbiterDied> for System {
+    type Result = ();
+
+    fn handle(&mut self, msg: ArbiterDied, _: &mut Context<Self>)
+    {
+        self.arbiters.remove(&msg.0);
+        if self.stop_on_failure {
+            error!("Arbiter {} died unexpectedly, stopping system", msg.1);
+            self.exit(1, SystemExitReason::ArbiterDied{id: msg.0, name: msg.1});
+        } else {
+            warn!("Arbiter {} died unexpectedly", msg.1);
+        }
+    }
+}
+
+/// Set arbiter failure policy
+pub(crate) struct StopOnArbiterFailure(pub bool);
+
+#[doc(hidden)]
+impl ResponseType for StopOnArbiterFailure {
+    type Item = ();
+    type Error = ();
+}
+
+#[doc(hidden)]
+impl Handler<StopOnArbiterFailure> for System {
+    type Result = ();
+
+    fn handle(&mut self, msg: StopOnArbiterFailure, _: &mut Context<Self>)
+    {
+        self.stop_on_failure = msg.0;
+    }
+}
+
+/// Set system shutdown timeout
+pub(crate) struct ShutdownTimeout(pub Duration);
+
+#[doc(hidden)]
+impl ResponseType for ShutdownTimeout {
+    type Item = ();
+    type Error = ();
+}
+
+#[doc(hidden)]
+impl Handler<ShutdownTimeout> for System {
+    type Result = ();
+
+    fn handle(&mut self, msg: ShutdownTimeout, _: &mut Context<Self>)
+    {
+        self.shutdown_timeout = msg.0;
+    }
+}
+
+#[doc(hidden)]
+impl Handler<ShutdownPhase> for System {
+    type Result = ();
+
+    fn handle(&mut self, msg: ShutdownPhase, _: &mut Context<Self>)
+    {
+        self.phases.entry(msg.phase).or_insert_with(Vec::new).push(msg.addr);
+    }
+}
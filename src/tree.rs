@@ -0,0 +1,383 @@
+use std::any::type_name;
+use std::cell::RefCell;
+use std::collections::VecDeque;
+use std::marker::PhantomData;
+use std::mem;
+use std::rc::Rc;
+use std::time::{Duration, Instant};
+use futures::{Async, Future, Poll};
+use futures::task::{self, Task};
+
+use abort;
+use actor::{Actor, ActorContext, AsyncContext, Supervised};
+use address::{ActorAddress, Address, WeakAddress};
+use arbiter::Arbiter;
+use context::Context;
+use handler::{Handler, ResponseType};
+
+/// How `SupervisorTree` reacts to terminated child
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TreeStrategy {
+    /// Restart terminated child only
+    OneForOne,
+    /// Restart all children of the tree
+    OneForAll,
+    /// Restart terminated child and all children started after it
+    RestForOne,
+}
+
+/// Why child actor terminated
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub enum TerminationReason {
+    /// Child actor stopped
+    Stopped,
+    /// Child actor panicked
+    Panicked,
+}
+
+/// Identity of the child within its `SupervisorTree`
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct ChildId(usize);
+
+/// Message is sent to the supervising actor once its child terminates
+///
+/// Child waits until supervising actor passes this message to
+/// `SupervisorTree::handle_terminated()`.
+#[derive(Debug)]
+pub struct ChildTerminated {
+    /// Terminated child
+    pub id: ChildId,
+    /// Termination reason
+    pub reason: TerminationReason,
+}
+
+impl ResponseType for ChildTerminated {
+    type Item = ();
+    type Error = ();
+}
+
+#[derive(Debug, Clone, Copy, PartialEq)]
+enum ChildState {
+    /// Child is running
+    Running,
+    /// Child terminated, waits for decision of the tree
+    Terminated,
+    /// Tree asks to restart child
+    Restart,
+    /// Tree asks to stop child
+    Stop,
+    /// Child is stopped for good
+    Finished,
+}
+
+/// State shared between tree and child runner
+struct ChildControl {
+    state: ChildState,
+    task: Option<Task>,
+}
+
+impl ChildControl {
+    fn set(&mut self, state: ChildState) {
+        if self.state != ChildState::Finished {
+            self.state = state;
+            if let Some(task) = self.task.take() {
+                task.notify();
+            }
+        }
+    }
+}
+
+struct Child {
+    id: ChildId,
+    control: Rc<RefCell<ChildControl>>,
+}
+
+/// Set of child actors owned by supervising actor
+///
+/// Tree starts children in current arbiter and restarts them according
+/// to `TreeStrategy`. Terminated child sends `ChildTerminated` message
+/// to the supervising actor and waits, supervising actor passes message to
+/// `handle_terminated()` which restarts child and, depending on strategy,
+/// its siblings. Child panics are caught and reported as
+/// `TerminationReason::Panicked`.
+///
+/// If restart limit is reached, tree escalates failure: all children get
+/// stopped and supervising actor stops as well. If supervising actor is a
+/// child of another tree, that tree gets notified in turn. Children do not
+/// keep supervising actor alive, they are stopped once tree gets dropped.
+///
+/// Child that stopped because all its addresses are gone is not restarted
+/// and supervising actor is not notified.
+///
+/// ```rust
+/// # extern crate actix;
+/// # use std::time::Duration;
+/// # use actix::prelude::*;
+/// struct Worker;
+///
+/// impl Actor for Worker {
+///     type Context = Context<Self>;
+/// }
+///
+/// impl actix::Supervised for Worker {}
+///
+/// struct Parent {
+///     tree: SupervisorTree<Parent>,
+/// }
+///
+/// impl Actor for Parent {
+///     type Context = Context<Self>;
+///
+///     fn started(&mut self, ctx: &mut Context<Self>) {
+///         let (_, _worker): (_, Address<_>) = self.tree.start_child(ctx, |_| Worker);
+/// #       Arbiter::system().do_send(actix::msgs::SystemExit(0));
+///     }
+/// }
+///
+/// impl Handler<ChildTerminated> for Parent {
+///     type Result = ();
+///
+///     fn handle(&mut self, msg: ChildTerminated, ctx: &mut Context<Self>) {
+///         self.tree.handle_terminated(msg, ctx);
+///     }
+/// }
+///
+/// fn main() {
+///     let sys = System::new("test");
+///     let _: Address<_> = Parent{
+///         tree: SupervisorTree::new(TreeStrategy::OneForAll)
+///             .max_restarts(3, Duration::from_secs(5))}.start();
+///     sys.run();
+/// }
+/// ```
+pub struct SupervisorTree<P> {
+    strategy: TreeStrategy,
+    limit: Option<(usize, Duration)>,
+    history: VecDeque<Instant>,
+    children: Vec<Child>,
+    next_id: usize,
+    act: PhantomData<P>,
+}
+
+impl<P> SupervisorTree<P> where P: Actor<Context=Context<P>> + Handler<ChildTerminated> {
+    /// Create empty tree with specific restart strategy
+    pub fn new(strategy: TreeStrategy) -> SupervisorTree<P> {
+        SupervisorTree {
+            strategy: strategy,
+            limit: None,
+            history: VecDeque::new(),
+            children: Vec::new(),
+            next_id: 0,
+            act: PhantomData,
+        }
+    }
+
+    /// Escalate failure if tree restarts more than `max` times within `window`
+    ///
+    /// By default number of restarts is not limited.
+    pub fn max_restarts(mut self, max: usize, window: Duration) -> Self {
+        self.limit = Some((max, window));
+        self
+    }
+
+    /// Start child actor in current arbiter
+    ///
+    /// Type of returned address depends on variable type, same as `Actor::create()`.
+    pub fn start_child<A, Addr, F>(&mut self, ctx: &mut Context<P>, f: F) -> (ChildId, Addr)
+        where A: Supervised + Actor<Context=Context<A>> + ActorAddress<A, Addr>,
+              F: FnOnce(&mut Context<A>) -> A,
+    {
+        self.children.retain(|child| child.control.borrow().state != ChildState::Finished);
+
+        let mut child_ctx = Context::new(None);
+        let act = f(&mut child_ctx);
+        let addr = <A as ActorAddress<A, Addr>>::get(&mut child_ctx);
+        child_ctx.set_actor(act);
+
+        let id = ChildId(self.next_id);
+        self.next_id += 1;
+        let control = Rc::new(RefCell::new(ChildControl{state: ChildState::Running, task: None}));
+        self.children.push(Child{id: id, control: Rc::clone(&control)});
+
+        let parent: Address<P> = ctx.address();
+        Arbiter::handle().spawn(ChildRunner{
+            id: id,
+            ctx: child_ctx,
+            control: control,
+            parent: parent.downgrade(),
+            running: true,
+            stopping: false,
+        });
+        (id, addr)
+    }
+
+    /// Apply restart strategy to terminated child
+    ///
+    /// Notifications of children that are already stopped or restarted
+    /// by the tree are ignored.
+    pub fn handle_terminated(&mut self, msg: ChildTerminated, ctx: &mut Context<P>) {
+        let idx = match self.children.iter().position(|child| child.id == msg.id) {
+            Some(idx) => idx,
+            None => return,
+        };
+        if self.children[idx].control.borrow().state != ChildState::Terminated {
+            return
+        }
+
+        if !self.allow_restart() {
+            error!("Supervisor tree of {} reached restart limit, child {:?} {:?}, stopping",
+                   type_name::<P>(), msg.id, msg.reason);
+            self.stop_all();
+            ctx.stop();
+            return
+        }
+
+        let restart = match self.strategy {
+            TreeStrategy::OneForOne => &self.children[idx..idx + 1],
+            TreeStrategy::OneForAll => &self.children[..],
+            TreeStrategy::RestForOne => &self.children[idx..],
+        };
+        for child in restart {
+            child.control.borrow_mut().set(ChildState::Restart);
+        }
+    }
+
+    /// Stop child permanently, returns `false` if child is unknown
+    pub fn stop_child(&mut self, id: ChildId) -> bool {
+        match self.children.iter().position(|child| child.id == id) {
+            Some(idx) => {
+                self.children.remove(idx).control.borrow_mut().set(ChildState::Stop);
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Stop all children permanently
+    pub fn stop_all(&mut self) {
+        for child in self.children.drain(..) {
+            child.control.borrow_mut().set(ChildState::Stop);
+        }
+    }
+
+    /// Number of children that are not stopped permanently
+    pub fn len(&self) -> usize {
+        self.children.iter()
+            .filter(|child| child.control.borrow().state != ChildState::Finished)
+            .count()
+    }
+
+    /// Check if tree has no children
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Record restart, returns `false` if restart limit is reached
+    fn allow_restart(&mut self) -> bool {
+        if let Some((max, window)) = self.limit {
+            let now = Instant::now();
+            while self.history.front().map_or(false, |t| now.duration_since(*t) > window) {
+                self.history.pop_front();
+            }
+            if self.history.len() >= max {
+                return false
+            }
+            self.history.push_back(now);
+        }
+        true
+    }
+}
+
+impl<P> Drop for SupervisorTree<P> {
+    fn drop(&mut self) {
+        for child in self.children.drain(..) {
+            child.control.borrow_mut().set(ChildState::Stop);
+        }
+    }
+}
+
+/// Runs child context and executes decisions of the tree
+struct ChildRunner<A, P>
+    where A: Supervised + Actor<Context=Context<A>>, P: Actor<Context=Context<P>>
+{
+    id: ChildId,
+    ctx: Context<A>,
+    control: Rc<RefCell<ChildControl>>,
+    parent: WeakAddress<P>,
+    running: bool,
+    stopping: bool,
+}
+
+impl<A, P> ChildRunner<A, P>
+    where A: Supervised + Actor<Context=Context<A>>,
+          P: Actor<Context=Context<P>> + Handler<ChildTerminated>
+{
+    fn finish(&mut self) -> Poll<(), ()> {
+        self.control.borrow_mut().state = ChildState::Finished;
+        Ok(Async::Ready(()))
+    }
+}
+
+impl<A, P> Future for ChildRunner<A, P>
+    where A: Supervised + Actor<Context=Context<A>>,
+          P: Actor<Context=Context<P>> + Handler<ChildTerminated>
+{
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<(), ()> {
+        loop {
+            let state = self.control.borrow().state;
+
+            if self.running {
+                // tree asks to stop or restart running child
+                if state != ChildState::Running && !self.stopping {
+                    self.stopping = true;
+                    self.ctx.stop();
+                }
+                let ctx = &mut self.ctx;
+                let reason = match abort::catch_unwind("supervisor tree", || ctx.poll()) {
+                    Ok(Ok(Async::NotReady)) => {
+                        self.control.borrow_mut().task = Some(task::current());
+                        return Ok(Async::NotReady)
+                    }
+                    Ok(_) => TerminationReason::Stopped,
+                    Err(_) => {
+                        error!("Child actor {} panicked", type_name::<A>());
+                        TerminationReason::Panicked
+                    }
+                };
+                self.running = false;
+
+                if !mem::replace(&mut self.stopping, false) {
+                    // address is gone, nothing to restart
+                    if !self.ctx.restartable() {
+                        return self.finish()
+                    }
+                    match self.parent.upgrade() {
+                        Some(parent) => {
+                            self.control.borrow_mut().state = ChildState::Terminated;
+                            parent.do_send(ChildTerminated{id: self.id, reason: reason});
+                        }
+                        None => return self.finish(),
+                    }
+                }
+                continue
+            }
+
+            match state {
+                ChildState::Restart => if self.ctx.restart() {
+                    self.running = true;
+                    self.control.borrow_mut().state = ChildState::Running;
+                } else {
+                    return self.finish()
+                },
+                ChildState::Terminated | ChildState::Running => {
+                    self.control.borrow_mut().task = Some(task::current());
+                    return Ok(Async::NotReady)
+                }
+                ChildState::Stop | ChildState::Finished => return self.finish(),
+            }
+        }
+    }
+}
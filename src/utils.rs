@@ -1,11 +1,27 @@
-use std::time::Duration;
-use futures::{Async, Future, Poll};
+use std::{cmp, fmt, mem};
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::hash::{BuildHasher, Hash, Hasher};
+use std::collections::{HashMap, HashSet};
+use std::collections::hash_map::{DefaultHasher, Entry, RandomState};
+use std::time::{Duration, Instant};
+use futures::{Async, Future, IntoFuture, Poll};
+use futures::sync;
 use futures::unsync::oneshot;
 use tokio_core::reactor::Timeout;
 
 use fut::ActorFuture;
-use actor::Actor;
+use actor::{Actor, AsyncContext};
+use address::{MailboxError, SendError, Subscriber, SyncAddress, ToEnvelope, SendFut, RequestFut};
 use arbiter::Arbiter;
+use budget;
+use pump::Deadline;
+use context::Context;
+use framed::ReaderControl;
+use handler::{Handler, MessageResult, ResponseType};
 
 pub struct Condition<T> where T: Clone {
     waiters: Vec<oneshot::Sender<T>>,
@@ -50,10 +66,1121 @@ impl Future for Drain {
     }
 }
 
+/// Broadcast strategy of the `FanOut`
+///
+/// Strategy defines what happens to the message if subscriber's mailbox is full.
+#[derive(PartialEq, Debug, Copy, Clone)]
+pub enum FanOutStrategy {
+    /// Skip subscriber, number of dropped messages is counted per subscriber.
+    DropSlow,
+    /// Wait until subscriber accepts message.
+    WaitAll,
+    /// Skip subscriber, remove it if its mailbox stays full longer
+    /// than specified grace period.
+    Disconnect(Duration),
+}
+
+/// Broadcast messages to a set of subscribers
+///
+/// Subscribers with closed mailbox get removed on next broadcast.
+///
+/// ```rust,ignore
+/// let mut fanout = FanOut::new(FanOutStrategy::DropSlow);
+/// let id = fanout.subscribe(addr.subscriber());
+///
+/// fanout.broadcast(Msg);
+/// println!("dropped: {}", fanout.dropped(id));
+/// ```
+pub struct FanOut<M> where M: Clone + 'static {
+    strategy: FanOutStrategy,
+    entries: Vec<FanOutEntry<M>>,
+    next: usize,
+}
+
+struct FanOutEntry<M: 'static> {
+    id: usize,
+    subscr: Box<Subscriber<M>>,
+    dropped: usize,
+    full_since: Option<Instant>,
+}
+
+impl<M> FanOut<M> where M: Clone + 'static {
+
+    pub fn new(strategy: FanOutStrategy) -> FanOut<M> {
+        FanOut { strategy: strategy, entries: Vec::new(), next: 0 }
+    }
+
+    /// Add subscriber, returns subscriber's id
+    pub fn subscribe(&mut self, subscr: Box<Subscriber<M>>) -> usize {
+        self.next += 1;
+        self.entries.push(
+            FanOutEntry { id: self.next, subscr: subscr, dropped: 0, full_since: None });
+        self.next
+    }
+
+    /// Remove subscriber
+    pub fn unsubscribe(&mut self, id: usize) -> bool {
+        let len = self.entries.len();
+        self.entries.retain(|entry| entry.id != id);
+        len != self.entries.len()
+    }
+
+    /// Check if subscriber is registered
+    pub fn contains(&self, id: usize) -> bool {
+        self.entries.iter().any(|entry| entry.id == id)
+    }
+
+    /// Number of registered subscribers
+    pub fn len(&self) -> usize {
+        self.entries.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.entries.is_empty()
+    }
+
+    /// Number of messages dropped for specific subscriber
+    pub fn dropped(&self, id: usize) -> usize {
+        self.entries.iter()
+            .find(|entry| entry.id == id).map(|entry| entry.dropped).unwrap_or(0)
+    }
+
+    /// Send message to all subscribers
+    ///
+    /// With `FanOutStrategy::WaitAll` strategy returned future resolves
+    /// when every subscriber accepted message, otherwise it is resolved immediately.
+    pub fn broadcast(&mut self, msg: M) -> Broadcast<M> {
+        let now = Instant::now();
+        let mut pending = Vec::new();
+
+        let mut idx = 0;
+        while idx < self.entries.len() {
+            let keep = {
+                let entry = &mut self.entries[idx];
+                match entry.subscr.try_send_nowait(msg.clone()) {
+                    Ok(()) => {
+                        entry.full_since = None;
+                        true
+                    }
+                    Err(SendError::Closed(_)) => false,
+                    Err(SendError::Full(msg)) => match self.strategy {
+                        FanOutStrategy::DropSlow => {
+                            entry.dropped += 1;
+                            true
+                        }
+                        FanOutStrategy::WaitAll => {
+                            pending.push((entry.subscr.boxed(), msg));
+                            true
+                        }
+                        FanOutStrategy::Disconnect(grace) => {
+                            entry.dropped += 1;
+                            let since = *entry.full_since.get_or_insert(now);
+                            now.duration_since(since) <= grace
+                        }
+                    },
+                }
+            };
+            if keep {
+                idx += 1;
+            } else {
+                self.entries.remove(idx);
+            }
+        }
+        Broadcast { pending: pending }
+    }
+}
+
+/// Future returned by `FanOut::broadcast()` method
+pub struct Broadcast<M: 'static> {
+    pending: Vec<(Box<Subscriber<M>>, M)>,
+}
+
+impl<M: 'static> Future for Broadcast<M> {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        for (subscr, msg) in mem::replace(&mut self.pending, Vec::new()) {
+            if let Err(SendError::Full(msg)) = subscr.try_send(msg) {
+                self.pending.push((subscr, msg));
+            }
+        }
+        if self.pending.is_empty() {
+            Ok(Async::Ready(()))
+        } else {
+            Ok(Async::NotReady)
+        }
+    }
+}
+
+/// Chat rooms, members of a room receive messages broadcasted to the room
+///
+/// Member is identified by `id` and could join several rooms, member's
+/// subscriber is shared by all rooms it has joined. Broadcast uses
+/// `try_send()`, message is dropped for member with full mailbox and
+/// member with closed mailbox leaves all rooms. Room exists while it
+/// has members. `Rooms` is meant to be stored in server actor.
+///
+/// ```rust,ignore
+/// let mut rooms = Rooms::new();
+/// rooms.join("Main", id, addr.into_subscriber());
+///
+/// // message to everyone except sender
+/// rooms.broadcast("Main", Message(text), Some(id));
+/// ```
+pub struct Rooms<M> where M: Clone + 'static {
+    rooms: HashMap<String, HashSet<usize>>,
+    members: HashMap<usize, RoomMember<M>>,
+}
+
+struct RoomMember<M: 'static> {
+    subscr: Box<Subscriber<M>>,
+    rooms: usize,
+    dropped: usize,
+}
+
+impl<M> Default for Rooms<M> where M: Clone + 'static {
+    fn default() -> Rooms<M> {
+        Rooms::new()
+    }
+}
+
+impl<M> Rooms<M> where M: Clone + 'static {
+
+    pub fn new() -> Rooms<M> {
+        Rooms { rooms: HashMap::new(), members: HashMap::new() }
+    }
+
+    /// Join room, room is created if it does not exist
+    ///
+    /// Subscriber replaces previous subscriber of the member.
+    /// Returns `false` if member is in the room already.
+    pub fn join(&mut self, room: &str, id: usize, subscr: Box<Subscriber<M>>) -> bool {
+        let joined = self.rooms.entry(room.to_owned()).or_insert_with(HashSet::new).insert(id);
+        match self.members.entry(id) {
+            Entry::Occupied(mut entry) => {
+                let member = entry.get_mut();
+                member.subscr = subscr;
+                if joined {
+                    member.rooms += 1;
+                }
+            }
+            Entry::Vacant(entry) => {
+                entry.insert(RoomMember{subscr: subscr, rooms: 1, dropped: 0});
+            }
+        }
+        joined
+    }
+
+    /// Leave room, returns `false` if member is not in the room
+    pub fn leave(&mut self, room: &str, id: usize) -> bool {
+        let left = match self.rooms.get_mut(room) {
+            Some(members) => members.remove(&id),
+            None => false,
+        };
+        if left {
+            if self.rooms.get(room).map(|members| members.is_empty()).unwrap_or(false) {
+                self.rooms.remove(room);
+            }
+            let gone = match self.members.get_mut(&id) {
+                Some(member) => {
+                    member.rooms -= 1;
+                    member.rooms == 0
+                }
+                None => false,
+            };
+            if gone {
+                self.members.remove(&id);
+            }
+        }
+        left
+    }
+
+    /// Leave all rooms, returns names of rooms member has left
+    pub fn leave_all(&mut self, id: usize) -> Vec<String> {
+        let mut left = Vec::new();
+        if self.members.remove(&id).is_some() {
+            for (name, members) in &mut self.rooms {
+                if members.remove(&id) {
+                    left.push(name.clone());
+                }
+            }
+            self.rooms.retain(|_, members| !members.is_empty());
+        }
+        left
+    }
+
+    /// Send message to all members of the room, except `skip`
+    ///
+    /// Returns number of members that accepted message.
+    pub fn broadcast(&mut self, room: &str, msg: M, skip: Option<usize>) -> usize {
+        let ids: Vec<usize> = match self.rooms.get(room) {
+            Some(members) => members.iter().cloned().filter(|id| Some(*id) != skip).collect(),
+            None => return 0,
+        };
+
+        let mut sent = 0;
+        let mut closed = Vec::new();
+        for id in ids {
+            if let Some(member) = self.members.get_mut(&id) {
+                match member.subscr.try_send_nowait(msg.clone()) {
+                    Ok(()) => sent += 1,
+                    Err(SendError::Full(_)) => member.dropped += 1,
+                    Err(SendError::Closed(_)) => closed.push(id),
+                }
+            }
+        }
+        for id in closed {
+            self.leave_all(id);
+        }
+        sent
+    }
+
+    /// Subscriber of the member
+    pub fn subscriber(&self, id: usize) -> Option<Box<Subscriber<M>>> {
+        self.members.get(&id).map(|member| member.subscr.boxed())
+    }
+
+    /// Check if member is in the room
+    pub fn contains(&self, room: &str, id: usize) -> bool {
+        self.rooms.get(room).map(|members| members.contains(&id)).unwrap_or(false)
+    }
+
+    /// Names of existing rooms
+    pub fn rooms(&self) -> Vec<String> {
+        self.rooms.keys().cloned().collect()
+    }
+
+    /// Rooms member has joined
+    pub fn rooms_of(&self, id: usize) -> Vec<String> {
+        self.rooms.iter()
+            .filter(|&(_, members)| members.contains(&id))
+            .map(|(name, _)| name.clone())
+            .collect()
+    }
+
+    /// Number of members in the room
+    pub fn members(&self, room: &str) -> usize {
+        self.rooms.get(room).map(|members| members.len()).unwrap_or(0)
+    }
+
+    /// Number of members in all rooms
+    pub fn len(&self) -> usize {
+        self.members.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.members.is_empty()
+    }
+
+    /// Number of messages dropped for the member because its mailbox was full
+    pub fn dropped(&self, id: usize) -> usize {
+        self.members.get(&id).map(|member| member.dropped).unwrap_or(0)
+    }
+}
+
+/// Set of actor instances, messages are routed by a key
+///
+/// Key is extracted from the message and hashed to pick the shard, so all
+/// messages with the same key are handled by the same instance in order
+/// they were sent.
+///
+/// ```rust,ignore
+/// let rooms = Shards::start(4, |_| Room::default(), |msg: &Join| msg.room_id);
+/// rooms.do_send(Join{room_id: 10, ..});
+/// ```
+pub struct Shards<A, M> where A: Actor {
+    shards: Vec<SyncAddress<A>>,
+    key: Arc<Fn(&M) -> u64 + Send + Sync>,
+}
+
+impl<A, M> Shards<A, M> where A: Actor {
+
+    /// Create shards from addresses of running instances
+    ///
+    /// Panics if `addrs` is empty.
+    pub fn new<K>(addrs: Vec<SyncAddress<A>>, key: K) -> Shards<A, M>
+        where K: Fn(&M) -> u64 + Send + Sync + 'static
+    {
+        assert!(!addrs.is_empty(), "at least one shard is required");
+        Shards { shards: addrs, key: Arc::new(key) }
+    }
+
+    /// Start `n` instances, each one in its own arbiter
+    ///
+    /// `factory` is called with shard's index.
+    pub fn start<F, K>(n: usize, factory: F, key: K) -> Shards<A, M>
+        where A: Actor<Context=Context<A>>,
+              F: Fn(usize) -> A + Send + Sync + 'static,
+              K: Fn(&M) -> u64 + Send + Sync + 'static
+    {
+        let factory = Arc::new(factory);
+        let addrs = (0..n)
+            .map(|idx| {
+                let factory = Arc::clone(&factory);
+                Arbiter::start(move |_| factory(idx))
+            })
+            .collect();
+        Shards::new(addrs, key)
+    }
+
+    /// Number of shards
+    pub fn len(&self) -> usize {
+        self.shards.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.shards.is_empty()
+    }
+
+    /// Address of the shard that handles messages with specified key
+    pub fn shard_for(&self, key: u64) -> &SyncAddress<A> {
+        let mut hasher = DefaultHasher::new();
+        key.hash(&mut hasher);
+        &self.shards[(hasher.finish() % self.shards.len() as u64) as usize]
+    }
+
+    /// Send message to its shard, see `SyncAddress::send()`
+    pub fn send(&self, msg: M) -> SendFut<A, M>
+        where A: Handler<M>, A::Context: ToEnvelope<A>,
+              M: ResponseType + Send + 'static,
+    {
+        self.shard_for((self.key)(&msg)).send(msg)
+    }
+
+    /// Send message to its shard, see `SyncAddress::do_send()`
+    pub fn do_send(&self, msg: M)
+        where A: Handler<M>, A::Context: ToEnvelope<A>,
+              M: ResponseType + Send + 'static,
+    {
+        self.shard_for((self.key)(&msg)).do_send(msg)
+    }
+
+    /// Send message to its shard and wait for response, see `SyncAddress::call_fut()`
+    pub fn call_fut(&self, msg: M) -> RequestFut<A, M>
+        where A: Handler<M>, A::Context: ToEnvelope<A>,
+              M: ResponseType + Send + 'static, M::Item: Send, M::Error: Send,
+    {
+        self.shard_for((self.key)(&msg)).call_fut(msg)
+    }
+}
+
+impl<A, M> Clone for Shards<A, M> where A: Actor {
+    fn clone(&self) -> Self {
+        Shards { shards: self.shards.clone(), key: Arc::clone(&self.key) }
+    }
+}
+
+/// Barrier that resolves once every declared token reported readiness
+///
+/// Barrier is cheap to clone, clones share the same state and could be sent
+/// to actors in other arbiters. Usually actor reports readiness from
+/// `Actor::started()` method.
+///
+/// ```rust,ignore
+/// let barrier = StartupBarrier::new(vec!["db", "cache"]);
+///
+/// // in `Database::started()`
+/// self.barrier.ready("db");
+///
+/// barrier.wait_all()
+///     .timeout(Duration::from_secs(5))
+///     .then(|res| ...)
+/// ```
+pub struct StartupBarrier<T> {
+    state: Arc<Mutex<BarrierState<T>>>,
+}
+
+struct BarrierState<T> {
+    pending: Vec<T>,
+    waiters: Vec<sync::oneshot::Sender<()>>,
+}
+
+impl<T> StartupBarrier<T> where T: PartialEq + Clone {
+
+    /// Create barrier that waits for `tokens`
+    pub fn new<I: IntoIterator<Item=T>>(tokens: I) -> StartupBarrier<T> {
+        StartupBarrier {
+            state: Arc::new(Mutex::new(BarrierState {
+                pending: tokens.into_iter().collect(),
+                waiters: Vec::new() }))
+        }
+    }
+
+    /// Report readiness of `token`
+    ///
+    /// Returns `false` if token is unknown or it is reported already.
+    pub fn ready(&self, token: T) -> bool {
+        let mut state = self.state.lock().unwrap();
+        match state.pending.iter().position(|t| *t == token) {
+            Some(idx) => {
+                state.pending.remove(idx);
+                if state.pending.is_empty() {
+                    for waiter in state.waiters.drain(..) {
+                        let _ = waiter.send(());
+                    }
+                }
+                true
+            }
+            None => false,
+        }
+    }
+
+    /// Tokens that did not report readiness yet, in declaration order
+    pub fn missing(&self) -> Vec<T> {
+        self.state.lock().unwrap().pending.clone()
+    }
+
+    /// Future that resolves once every token reported readiness
+    pub fn wait_all(&self) -> BarrierWait<T> {
+        let mut state = self.state.lock().unwrap();
+        let rx = if state.pending.is_empty() {
+            None
+        } else {
+            let (tx, rx) = sync::oneshot::channel();
+            state.waiters.push(tx);
+            Some(rx)
+        };
+        BarrierWait { rx: rx, barrier: self.clone(), timeout: None }
+    }
+}
+
+impl<T> Clone for StartupBarrier<T> {
+    fn clone(&self) -> Self {
+        StartupBarrier { state: Arc::clone(&self.state) }
+    }
+}
+
+/// Future returned by `StartupBarrier::wait_all()` method
+#[must_use = "future do nothing unless polled"]
+pub struct BarrierWait<T> {
+    rx: Option<sync::oneshot::Receiver<()>>,
+    barrier: StartupBarrier<T>,
+    timeout: Option<Timeout>,
+}
+
+impl<T> BarrierWait<T> {
+    /// Fail with `StartupTimeout` if tokens do not report within `dur`
+    pub fn timeout(mut self, dur: Duration) -> Self {
+        self.timeout = Some(Timeout::new(dur, Arbiter::handle()).unwrap());
+        self
+    }
+}
+
+impl<T> Future for BarrierWait<T> where T: PartialEq + Clone {
+    type Item = ();
+    type Error = StartupTimeout<T>;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        if let Some(ref mut rx) = self.rx {
+            // barrier keeps sender alive
+            if let Ok(Async::NotReady) = rx.poll() {
+                if let Some(ref mut timeout) = self.timeout {
+                    if let Ok(Async::Ready(())) = timeout.poll() {
+                        return Err(StartupTimeout { missing: self.barrier.missing() })
+                    }
+                }
+                return Ok(Async::NotReady)
+            }
+        }
+        Ok(Async::Ready(()))
+    }
+}
+
+/// Error of `BarrierWait` future, holds tokens that did not report in time
+#[derive(Clone, Debug, PartialEq)]
+pub struct StartupTimeout<T> {
+    pub missing: Vec<T>,
+}
+
+impl<T: fmt::Debug> fmt::Display for StartupTimeout<T> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        write!(f, "Startup timed out, missing: {:?}", self.missing)
+    }
+}
+
+/// Routee selection strategy of the `Router`
+#[derive(PartialEq, Debug, Clone)]
+pub enum RoutingStrategy {
+    /// Pick routees in turn.
+    RoundRobin,
+    /// Pick routee with the least number of outstanding requests.
+    LeastOutstanding,
+    /// Pick routees in turn proportionally to their weights,
+    /// weights are given in order of routees.
+    Weighted(Vec<u32>),
+    /// Pick two random routees and use the one with less outstanding requests.
+    PowerOfTwoChoices,
+}
+
+/// Set of actor instances, every message is handled by one of them
+///
+/// Outstanding requests are tracked per routee for requests sent with
+/// `call_fut()`, request is completed once its future resolves or gets dropped.
+/// Messages sent with `do_send()` are not tracked.
+///
+/// ```rust,ignore
+/// let mut router = Router::start(4, |_| Worker, RoutingStrategy::LeastOutstanding);
+/// router.call_fut(Job(1)).then(|res| ...);
+/// ```
+pub struct Router<A> where A: Actor {
+    routees: Vec<Routee<A>>,
+    strategy: RoutingStrategy,
+    next: usize,
+    rng: u64,
+}
+
+struct Routee<A> where A: Actor {
+    addr: SyncAddress<A>,
+    outstanding: Arc<AtomicUsize>,
+    current: i64,
+}
+
+impl<A> Router<A> where A: Actor {
+
+    /// Create router from addresses of running instances
+    ///
+    /// Panics if `addrs` is empty or if weights of `RoutingStrategy::Weighted`
+    /// do not match routees or all weights are zero.
+    pub fn new(addrs: Vec<SyncAddress<A>>, strategy: RoutingStrategy) -> Router<A> {
+        assert!(!addrs.is_empty(), "at least one routee is required");
+        if let RoutingStrategy::Weighted(ref weights) = strategy {
+            assert_eq!(weights.len(), addrs.len(), "weight is required for every routee");
+            assert!(weights.iter().any(|w| *w != 0), "at least one weight must be positive");
+        }
+        let mut hasher = RandomState::new().build_hasher();
+        hasher.write_usize(addrs.len());
+        Router {
+            routees: addrs.into_iter()
+                .map(|addr| Routee{addr: addr, outstanding: Arc::default(), current: 0})
+                .collect(),
+            strategy: strategy,
+            next: 0,
+            rng: hasher.finish() | 1,
+        }
+    }
+
+    /// Start `n` instances, each one in its own arbiter
+    ///
+    /// `factory` is called with routee's index.
+    pub fn start<F>(n: usize, factory: F, strategy: RoutingStrategy) -> Router<A>
+        where A: Actor<Context=Context<A>>,
+              F: Fn(usize) -> A + Send + Sync + 'static,
+    {
+        let factory = Arc::new(factory);
+        let addrs = (0..n)
+            .map(|idx| {
+                let factory = Arc::clone(&factory);
+                Arbiter::start(move |_| factory(idx))
+            })
+            .collect();
+        Router::new(addrs, strategy)
+    }
+
+    /// Number of routees
+    pub fn len(&self) -> usize {
+        self.routees.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.routees.is_empty()
+    }
+
+    /// Number of outstanding requests of routee at `idx`
+    pub fn outstanding(&self, idx: usize) -> usize {
+        self.routees[idx].outstanding.load(Ordering::SeqCst)
+    }
+
+    /// Index of the routee for next message
+    fn pick(&mut self) -> usize {
+        let len = self.routees.len();
+        match self.strategy {
+            RoutingStrategy::RoundRobin => {
+                let idx = self.next % len;
+                self.next = idx + 1;
+                idx
+            }
+            RoutingStrategy::LeastOutstanding => {
+                // scan starts at next routee, so ties are spread evenly
+                let start = self.next % len;
+                self.next = start + 1;
+                (0..len).map(|i| (start + i) % len)
+                    .min_by_key(|idx| self.outstanding(*idx)).unwrap()
+            }
+            RoutingStrategy::Weighted(ref weights) => {
+                // smooth weighted round robin
+                let mut best = 0;
+                for (idx, routee) in self.routees.iter_mut().enumerate() {
+                    routee.current += i64::from(weights[idx]);
+                }
+                for idx in 1..len {
+                    if self.routees[idx].current > self.routees[best].current {
+                        best = idx;
+                    }
+                }
+                self.routees[best].current -= weights.iter().map(|w| i64::from(*w)).sum::<i64>();
+                best
+            }
+            RoutingStrategy::PowerOfTwoChoices => {
+                if len == 1 {
+                    return 0
+                }
+                let first = (self.random() % len as u64) as usize;
+                let second = (first + 1 + (self.random() % (len as u64 - 1)) as usize) % len;
+                if self.outstanding(second) < self.outstanding(first) {
+                    second
+                } else {
+                    first
+                }
+            }
+        }
+    }
+
+    /// xorshift64* generator
+    fn random(&mut self) -> u64 {
+        self.rng ^= self.rng >> 12;
+        self.rng ^= self.rng << 25;
+        self.rng ^= self.rng >> 27;
+        self.rng.wrapping_mul(0x2545_f491_4f6c_dd1d)
+    }
+
+    /// Send message to next routee, see `SyncAddress::send()`
+    pub fn send<M>(&mut self, msg: M) -> SendFut<A, M>
+        where A: Handler<M>, A::Context: ToEnvelope<A>,
+              M: ResponseType + Send + 'static,
+    {
+        let idx = self.pick();
+        self.routees[idx].addr.send(msg)
+    }
+
+    /// Send message to next routee, see `SyncAddress::do_send()`
+    pub fn do_send<M>(&mut self, msg: M)
+        where A: Handler<M>, A::Context: ToEnvelope<A>,
+              M: ResponseType + Send + 'static,
+    {
+        let idx = self.pick();
+        self.routees[idx].addr.do_send(msg)
+    }
+
+    /// Send message to next routee and wait for response, see `SyncAddress::call_fut()`
+    pub fn call_fut<M>(&mut self, msg: M) -> RouterRequest<A, M>
+        where A: Handler<M>, A::Context: ToEnvelope<A>,
+              M: ResponseType + Send + 'static, M::Item: Send, M::Error: Send,
+    {
+        let idx = self.pick();
+        let routee = &self.routees[idx];
+        routee.outstanding.fetch_add(1, Ordering::SeqCst);
+        RouterRequest {
+            fut: routee.addr.call_fut(msg),
+            outstanding: Some(Outstanding(Arc::clone(&routee.outstanding))),
+        }
+    }
+}
+
+/// Decrements routee's counter of outstanding requests on drop
+struct Outstanding(Arc<AtomicUsize>);
+
+impl Drop for Outstanding {
+    fn drop(&mut self) {
+        self.0.fetch_sub(1, Ordering::SeqCst);
+    }
+}
+
+/// Future returned by `Router::call_fut()` method
+#[must_use = "future do nothing unless polled"]
+pub struct RouterRequest<A, M> where A: Actor, M: ResponseType {
+    fut: RequestFut<A, M>,
+    outstanding: Option<Outstanding>,
+}
+
+impl<A, M> Future for RouterRequest<A, M>
+    where A: Actor + Handler<M>, A::Context: ToEnvelope<A>,
+          M: ResponseType + Send + 'static, M::Item: Send, M::Error: Send,
+{
+    type Item = MessageResult<M>;
+    type Error = MailboxError;
+
+    fn poll(&mut self) -> Poll<Self::Item, Self::Error> {
+        let res = self.fut.poll();
+        if let Ok(Async::NotReady) = res {
+            return res
+        }
+        self.outstanding.take();
+        res
+    }
+}
+
+/// Decision of `RateLimiter::check()`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum Decision {
+    /// Event is within budget
+    Allow,
+    /// Budget is exhausted, retry event after specified period
+    Defer(Duration),
+    /// Budget is exhausted for longer than limiter's max delay
+    Drop,
+}
+
+/// Token bucket rate limiter
+///
+/// Bucket holds up to `burst` tokens and refills with `rate` tokens per
+/// second, every allowed event takes one token. Tokens are refilled lazily
+/// on `check()`, limiter does not run timers of its own. Limiter is meant
+/// to be stored in session actor.
+///
+/// With `pause_reader()` limiter pauses framed reader once bucket gets
+/// empty, and resumes it with `run_later()` timer when next token is
+/// available, so reader produces frames at configured rate.
+///
+/// ```rust,ignore
+/// let (reader, writer) = FramedReader::wrap(stream.framed(ChatCodec));
+/// let limiter = RateLimiter::new(100, 10).pause_reader(reader.control());
+///
+/// impl StreamHandler<ChatRequest, FramedError<ChatCodec>> for ChatSession {
+///     fn handle(&mut self, msg: ChatRequest, ctx: &mut Context<Self>) {
+///         match self.limiter.check(ctx) {
+///             Decision::Allow => self.process(msg, ctx),
+///             Decision::Defer(dur) => { ctx.run_later(dur, move |act, ctx| act.handle(msg, ctx)); }
+///             Decision::Drop => (),
+///         }
+///     }
+/// }
+/// ```
+pub struct RateLimiter {
+    interval: Duration,
+    tolerance: Duration,
+    max_delay: Duration,
+    // time when bucket is full again
+    full_at: Instant,
+    reader: Option<ReaderControl>,
+}
+
+impl RateLimiter {
+    /// Create limiter with `rate` tokens per second and bucket of `burst` tokens
+    pub fn new(rate: u32, burst: u32) -> RateLimiter {
+        assert!(rate > 0, "rate must be non zero");
+        let interval = from_nanos(1_000_000_000 / u64::from(rate));
+        RateLimiter {
+            interval: interval,
+            tolerance: interval * (cmp::max(burst, 1) - 1),
+            max_delay: Duration::from_secs(1),
+            full_at: Instant::now(),
+            reader: None,
+        }
+    }
+
+    /// Event that waits longer than `max_delay` for a token is dropped,
+    /// one second by default
+    pub fn max_delay(mut self, dur: Duration) -> Self {
+        self.max_delay = dur;
+        self
+    }
+
+    /// Pause framed reader while bucket is empty
+    pub fn pause_reader(mut self, control: ReaderControl) -> Self {
+        self.reader = Some(control);
+        self
+    }
+
+    /// Number of available tokens
+    pub fn available(&self) -> u32 {
+        let now = Instant::now();
+        let used = if self.full_at > now { self.full_at - now } else { Duration::new(0, 0) };
+        if used > self.tolerance {
+            0
+        } else {
+            (as_nanos(self.tolerance - used) / as_nanos(self.interval)) as u32 + 1
+        }
+    }
+
+    /// Take token for an event
+    pub fn check<A, T>(&mut self, ctx: &mut T) -> Decision
+        where A: Actor<Context=T>, T: AsyncContext<A>
+    {
+        let now = Instant::now();
+        let full_at = cmp::max(self.full_at, now);
+
+        // next token is available at `full_at - tolerance`
+        if full_at - now > self.tolerance {
+            let wait = full_at - now - self.tolerance;
+            return if wait > self.max_delay { Decision::Drop } else { Decision::Defer(wait) }
+        }
+        self.full_at = full_at + self.interval;
+
+        if self.full_at - now > self.tolerance {
+            if let Some(ref reader) = self.reader {
+                if !reader.is_paused() {
+                    reader.pause();
+                    let reader = reader.clone();
+                    ctx.run_later(self.full_at - now - self.tolerance, move |_, _| reader.resume());
+                }
+            }
+        }
+        Decision::Allow
+    }
+}
+
+/// State of `CircuitBreaker`
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub enum BreakerState {
+    /// Requests are sent to downstream
+    Closed,
+    /// Requests fail fast until cooldown expires
+    Open,
+    /// Cooldown expired, one probe request is allowed
+    HalfOpen,
+}
+
+/// Error of request made through `CircuitBreaker`
+#[derive(Debug, PartialEq)]
+pub enum CircuitError<E> {
+    /// Circuit is open, request was not sent
+    CircuitOpen,
+    /// Request did not complete within breaker's timeout
+    Timeout,
+    /// Request failed
+    Failed(E),
+}
+
+impl<E: fmt::Display> fmt::Display for CircuitError<E> {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match *self {
+            CircuitError::CircuitOpen => write!(f, "Circuit is open"),
+            CircuitError::Timeout => write!(f, "Request timed out"),
+            CircuitError::Failed(ref err) => write!(f, "Request failed: {}", err),
+        }
+    }
+}
+
+struct BreakerInner {
+    state: BreakerState,
+    failures: u32,
+    opened: Instant,
+    probe: bool,
+    observer: Option<Box<Fn(BreakerState, BreakerState)>>,
+}
+
+impl BreakerInner {
+    fn transition(&mut self, state: BreakerState) {
+        let prev = mem::replace(&mut self.state, state);
+        if prev != state {
+            if let Some(ref observer) = self.observer {
+                observer(prev, state);
+            }
+        }
+    }
+
+    fn open(&mut self) {
+        self.opened = Instant::now();
+        self.transition(BreakerState::Open);
+    }
+}
+
+/// Circuit breaker for downstream calls
+///
+/// Breaker counts consecutive failed requests, request fails if its future
+/// resolves with error or does not complete within `timeout()`. Once
+/// `threshold` failures are reached breaker opens and following requests
+/// fail with `CircuitError::CircuitOpen` without calling request factory.
+/// After `cooldown` breaker half-opens and lets one probe request through,
+/// successful probe closes breaker, failed probe opens it again. Breaker is
+/// meant to be stored in calling actor.
+///
+/// Request future of `call_fut()` resolves with `MailboxError` only,
+/// handler errors have to be mapped to future's error to count as failures.
+///
+/// ```rust,ignore
+/// let breaker = CircuitBreaker::new(5, Duration::from_secs(10))
+///     .timeout(Duration::from_secs(1))
+///     .on_transition(|from, to| warn!("Backend breaker: {:?} -> {:?}", from, to));
+///
+/// impl Handler<Query> for Session {
+///     fn handle(&mut self, msg: Query, ctx: &mut Context<Self>) -> Self::Result {
+///         let backend = self.backend.clone();
+///         self.breaker.call(ctx, move || backend.call_fut(msg))
+///             .then(|res, act, ctx| ...)
+///             .spawn(ctx);
+///     }
+/// }
+/// ```
+pub struct CircuitBreaker {
+    threshold: u32,
+    cooldown: Duration,
+    timeout: Option<Duration>,
+    inner: Rc<RefCell<BreakerInner>>,
+}
+
+impl CircuitBreaker {
+    /// Create breaker that opens after `threshold` consecutive failures
+    /// and half-opens after `cooldown`
+    pub fn new(threshold: u32, cooldown: Duration) -> CircuitBreaker {
+        CircuitBreaker {
+            threshold: cmp::max(threshold, 1),
+            cooldown: cooldown,
+            timeout: None,
+            inner: Rc::new(RefCell::new(BreakerInner {
+                state: BreakerState::Closed,
+                failures: 0,
+                opened: Instant::now(),
+                probe: false,
+                observer: None,
+            })),
+        }
+    }
+
+    /// Fail requests that do not complete within `dur`, requests are not
+    /// limited by default
+    pub fn timeout(mut self, dur: Duration) -> Self {
+        self.timeout = Some(dur);
+        self
+    }
+
+    /// Observer is called with previous and new state on every state transition
+    pub fn on_transition<F>(self, f: F) -> Self where F: Fn(BreakerState, BreakerState) + 'static {
+        self.inner.borrow_mut().observer = Some(Box::new(f));
+        self
+    }
+
+    /// Current state
+    pub fn state(&self) -> BreakerState {
+        let inner = self.inner.borrow();
+        if inner.state == BreakerState::Open && inner.opened.elapsed() >= self.cooldown {
+            BreakerState::HalfOpen
+        } else {
+            inner.state
+        }
+    }
+
+    /// Number of consecutive failures
+    pub fn failures(&self) -> u32 {
+        self.inner.borrow().failures
+    }
+
+    /// Make request through breaker
+    ///
+    /// `f` is called only if breaker allows request. Returned future has to be
+    /// spawned to actor's context, or used with `wait()`.
+    pub fn call<A, F, R>(&mut self, _: &mut A::Context, f: F) -> CircuitRequest<A, R::Future>
+        where A: Actor, F: FnOnce() -> R, R: IntoFuture
+    {
+        let probe = {
+            let mut inner = self.inner.borrow_mut();
+            if inner.state == BreakerState::Open && inner.opened.elapsed() >= self.cooldown {
+                inner.transition(BreakerState::HalfOpen);
+            }
+            match inner.state {
+                BreakerState::Closed => false,
+                BreakerState::HalfOpen if !inner.probe => {
+                    inner.probe = true;
+                    true
+                }
+                _ => return CircuitRequest {
+                    fut: None, timeout: None, probe: false,
+                    inner: Rc::clone(&self.inner), threshold: self.threshold,
+                    act: PhantomData,
+                },
+            }
+        };
+        CircuitRequest {
+            fut: Some(f().into_future()),
+            timeout: self.timeout.map(|dur| Timeout::new(dur, Arbiter::handle()).unwrap()),
+            probe: probe,
+            inner: Rc::clone(&self.inner),
+            threshold: self.threshold,
+            act: PhantomData,
+        }
+    }
+}
+
+/// Future returned by `CircuitBreaker::call()` method
+#[must_use = "future do nothing unless polled"]
+pub struct CircuitRequest<A, F> {
+    fut: Option<F>,
+    timeout: Option<Timeout>,
+    probe: bool,
+    inner: Rc<RefCell<BreakerInner>>,
+    threshold: u32,
+    act: PhantomData<A>,
+}
+
+impl<A, F> CircuitRequest<A, F> {
+    /// Record result of the request
+    fn complete(&mut self, success: bool) {
+        self.fut = None;
+        self.timeout = None;
+        let mut inner = self.inner.borrow_mut();
+        if self.probe {
+            self.probe = false;
+            inner.probe = false;
+            if success {
+                inner.failures = 0;
+                inner.transition(BreakerState::Closed);
+            } else {
+                inner.open();
+            }
+        } else if inner.state == BreakerState::Closed {
+            // results of requests sent before breaker opened are ignored
+            if success {
+                inner.failures = 0;
+            } else {
+                inner.failures += 1;
+                if inner.failures >= self.threshold {
+                    inner.open();
+                }
+            }
+        }
+    }
+}
+
+impl<A, F> Drop for CircuitRequest<A, F> {
+    fn drop(&mut self) {
+        // dropped probe lets next request through
+        if self.probe {
+            self.inner.borrow_mut().probe = false;
+        }
+    }
+}
+
+impl<A, F> ActorFuture for CircuitRequest<A, F> where A: Actor, F: Future {
+    type Item = F::Item;
+    type Error = CircuitError<F::Error>;
+    type Actor = A;
+
+    fn poll(&mut self, _: &mut A, _: &mut A::Context) -> Poll<F::Item, CircuitError<F::Error>> {
+        let res = match self.fut {
+            Some(ref mut fut) => fut.poll(),
+            None => return Err(CircuitError::CircuitOpen),
+        };
+        match res {
+            Ok(Async::Ready(item)) => {
+                self.complete(true);
+                Ok(Async::Ready(item))
+            }
+            Err(err) => {
+                self.complete(false);
+                Err(CircuitError::Failed(err))
+            }
+            Ok(Async::NotReady) => {
+                if let Some(ref mut timeout) = self.timeout {
+                    match timeout.poll() {
+                        Ok(Async::NotReady) => return Ok(Async::NotReady),
+                        Ok(Async::Ready(_)) | Err(_) => (),
+                    }
+                } else {
+                    return Ok(Async::NotReady)
+                }
+                self.complete(false);
+                Err(CircuitError::Timeout)
+            }
+        }
+    }
+}
+
 pub(crate)
 struct TimerFunc<A> where A: Actor {
     f: Option<Box<TimerFuncBox<A>>>,
     timeout: Timeout,
+    _deadline: Deadline,
 }
 
 impl<A> TimerFunc<A> where A: Actor {
@@ -62,7 +1189,8 @@ impl<A> TimerFunc<A> where A: Actor {
     {
         TimerFunc {
             f: Some(Box::new(f)),
-            timeout: Timeout::new(timeout, Arbiter::handle()).unwrap()}
+            timeout: Timeout::new(timeout, Arbiter::handle()).unwrap(),
+            _deadline: Deadline::new(Instant::now() + timeout)}
     }
 }
 
@@ -99,3 +1227,76 @@ impl<A> ActorFuture for TimerFunc<A> where A: Actor {
         }
     }
 }
+
+pub(crate)
+struct IntervalFunc<A> where A: Actor {
+    f: Box<FnMut(&mut A, &mut A::Context)>,
+    every: Duration,
+    next: Instant,
+    timeout: Timeout,
+    deadline: Deadline,
+}
+
+impl<A> IntervalFunc<A> where A: Actor {
+    pub fn new<F>(start: Instant, every: Duration, f: F) -> IntervalFunc<A>
+        where F: FnMut(&mut A, &mut A::Context) + 'static
+    {
+        assert!(every > Duration::new(0, 0), "interval must be non zero");
+        let next = next_tick(start, every, Instant::now());
+        IntervalFunc {
+            f: Box::new(f),
+            every: every,
+            next: next,
+            timeout: Timeout::new_at(next, Arbiter::handle()).unwrap(),
+            deadline: Deadline::new(next)}
+    }
+}
+
+/// First `start + N * every` instant that is not earlier than `now`
+fn next_tick(start: Instant, every: Duration, now: Instant) -> Instant {
+    if start >= now {
+        return start
+    }
+    let every = as_nanos(every);
+    let ticks = (as_nanos(now - start) + every - 1) / every;
+    start + from_nanos(ticks * every)
+}
+
+fn as_nanos(dur: Duration) -> u64 {
+    dur.as_secs() * 1_000_000_000 + u64::from(dur.subsec_nanos())
+}
+
+fn from_nanos(nanos: u64) -> Duration {
+    Duration::new(nanos / 1_000_000_000, (nanos % 1_000_000_000) as u32)
+}
+
+#[doc(hidden)]
+impl<A> ActorFuture for IntervalFunc<A> where A: Actor {
+    type Item = ();
+    type Error = ();
+    type Actor = A;
+
+    fn poll(&mut self, act: &mut Self::Actor, ctx: &mut <Self::Actor as Actor>::Context)
+            -> Poll<Self::Item, Self::Error>
+    {
+        loop {
+            if !budget::has_budget() {
+                return Ok(Async::NotReady)
+            }
+
+            match self.timeout.poll() {
+                Ok(Async::Ready(_)) => {
+                    (self.f)(act, ctx);
+                    budget::consume();
+
+                    // ticks missed during handler execution get skipped
+                    self.next = next_tick(self.next + self.every, self.every, Instant::now());
+                    self.timeout.reset(self.next);
+                    self.deadline.reset(self.next);
+                }
+                Ok(Async::NotReady) => return Ok(Async::NotReady),
+                Err(_) => unreachable!(),
+            }
+        }
+    }
+}
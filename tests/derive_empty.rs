@@ -32,7 +32,7 @@ fn response_derive_empty() {
             _ => panic!("Something went wrong"),
         }
         
-        Arbiter::system().send(msgs::SystemExit(0));
+        Arbiter::system().do_send(msgs::SystemExit(0));
         future::result(Ok(()))
     }));
 
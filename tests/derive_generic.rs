@@ -0,0 +1,71 @@
+extern crate futures;
+#[macro_use] extern crate actix;
+
+use std::fmt::Debug;
+use actix::prelude::*;
+use futures::{future, Future};
+
+#[derive(Message)]
+#[rtype(usize)]
+struct Borrowed<'a>(&'a str);
+
+#[derive(Message)]
+#[rtype(T)]
+struct Envelope<T: Send + 'static>(pub T);
+
+#[derive(Message)]
+#[rtype(String)]
+struct Describe<T>(pub T) where T: Debug;
+
+fn response_type<M: ResponseType>(_: &M) {}
+
+struct GenericActor;
+
+impl Actor for GenericActor {
+    type Context = Context<Self>;
+}
+
+impl Handler<Envelope<usize>> for GenericActor {
+    type Result = MessageResult<Envelope<usize>>;
+
+    fn handle(&mut self, msg: Envelope<usize>, _: &mut Context<Self>) -> Self::Result {
+        Ok(msg.0 * 2)
+    }
+}
+
+impl<T: Debug + 'static> Handler<Describe<T>> for GenericActor {
+    type Result = MessageResult<Describe<T>>;
+
+    fn handle(&mut self, msg: Describe<T>, _: &mut Context<Self>) -> Self::Result {
+        Ok(format!("{:?}", msg.0))
+    }
+}
+
+#[test]
+fn response_derive_lifetime() {
+    let s = String::from("borrowed");
+    response_type(&Borrowed(&s));
+}
+
+#[test]
+fn response_derive_generic() {
+    let system = System::new("test");
+    let addr: Address<_> = GenericActor.start();
+    let res = addr.call_fut(Envelope(21usize))
+        .join(addr.call_fut(Describe(vec![1, 2])));
+
+    system.handle().spawn(res.then(|res| {
+        match res {
+            Ok((Ok(num), Ok(s))) => {
+                assert_eq!(num, 42);
+                assert_eq!(s, "[1, 2]");
+            }
+            _ => panic!("Something went wrong"),
+        }
+
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        future::result(Ok(()))
+    }));
+
+    system.run();
+}
@@ -34,7 +34,7 @@ pub fn response_derive_one() {
             _ => panic!("Something went wrong"),
         }
         
-        Arbiter::system().send(actix::msgs::SystemExit(0));
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
         future::result(Ok(()))
     }));
 
@@ -0,0 +1,76 @@
+extern crate actix;
+#[macro_use]
+extern crate log;
+
+use std::env;
+use std::process::Command;
+use std::sync::Mutex;
+use log::{Log, Metadata, Record};
+use actix::prelude::*;
+
+/// Logger that writes records to stderr only on flush
+struct BufferedLogger(Mutex<Vec<String>>);
+
+impl Log for BufferedLogger {
+    fn enabled(&self, _: &Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &Record) {
+        if record.target() == "test_abort" {
+            self.0.lock().unwrap().push(format!("{}", record.args()));
+        }
+    }
+
+    fn flush(&self) {
+        for line in self.0.lock().unwrap().drain(..) {
+            eprintln!("flushed: {}", line);
+        }
+    }
+}
+
+static LOGGER: BufferedLogger = BufferedLogger(Mutex::new(Vec::new()));
+
+struct Faulty;
+
+impl Actor for Faulty {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _: &mut Context<Self>) {
+        info!("state before panic");
+        panic!("faulty actor");
+    }
+}
+
+/// Runs in child process, panic escapes `System::run()`
+fn child() {
+    log::set_logger(&LOGGER).unwrap();
+    log::set_max_level(log::LevelFilter::Info);
+    System::set_abort_handler(Box::new(|info| eprintln!("abort handler: {}", info)));
+
+    let sys = System::new("test");
+    let _: () = Faulty.start();
+    sys.run();
+}
+
+#[test]
+fn test_abort_handler() {
+    if env::var("ACTIX_ABORT_CHILD").is_ok() {
+        return child()
+    }
+    assert!(System::unwind_supported());
+
+    let output = Command::new(env::current_exe().unwrap())
+        .args(&["--exact", "test_abort_handler", "--nocapture", "--test-threads=1"])
+        .env("ACTIX_ABORT_CHILD", "1")
+        .output()
+        .unwrap();
+    assert!(!output.status.success());
+
+    let stderr = String::from_utf8_lossy(&output.stderr);
+    let handler = stderr.find("abort handler: ").expect("handler did not run");
+    let flushed = stderr.find("flushed: state before panic").expect("log is not flushed");
+    assert!(stderr[handler..].contains("faulty actor"));
+    // log is flushed after handler, records of handler are not lost
+    assert!(handler < flushed);
+}
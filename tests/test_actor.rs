@@ -23,7 +23,7 @@ impl Actor for MyActor {
     type Context = actix::Context<Self>;
 
     fn stopping(&mut self, _: &mut Self::Context) -> bool {
-        Arbiter::system().send(actix::msgs::SystemExit(0));
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
         true
     }
 }
@@ -158,14 +158,14 @@ fn test_restart_sync_actor() {
         stopped: Arc::clone(&stopped1),
         msgs: Arc::clone(&msgs1),
         stop: started1.load(Ordering::Relaxed) == 0});
-    addr.send(Num(2));
+    addr.do_send(Num(2));
 
     Arbiter::handle().spawn_fn(move || {
         addr.call_fut(Num(4))
             .then(move |_| {
                 Timeout::new(Duration::new(0, 1_000_000), Arbiter::handle()).unwrap()
                     .then(move |_| {
-                        Arbiter::system().send(actix::msgs::SystemExit(0));
+                        Arbiter::system().do_send(actix::msgs::SystemExit(0));
                         future::result(Ok(()))
                     })
             })
@@ -190,7 +190,7 @@ impl Handler<Result<Num, ()>> for MyActor2 {
 
     fn handle(&mut self, msg: Result<Num, ()>, _: &mut Self::Context) {
         self.0.fetch_add(msg.unwrap().0, Ordering::Relaxed);
-        Arbiter::system().send(actix::msgs::SystemExit(0));
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
     }
 }
 
@@ -213,3 +213,79 @@ fn test_add_future() {
     sys.run();
     assert_eq!(count.load(Ordering::Relaxed), 10);
 }
+
+struct Plain {
+    veto: bool,
+    stopping: Arc<AtomicUsize>,
+    stopped: Arc<AtomicUsize>,
+}
+
+impl Actor for Plain {
+    type Context = Context<Self>;
+
+    fn stopping(&mut self, _: &mut Self::Context) -> bool {
+        self.stopping.fetch_add(1, Ordering::Relaxed);
+        !self.veto
+    }
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.stopped.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn test_stop_by_address() {
+    let sys = System::new("test");
+    let stopping = Arc::new(AtomicUsize::new(0));
+    let stopped = Arc::new(AtomicUsize::new(0));
+
+    let addr: SyncAddress<_> = Plain{veto: false,
+                                     stopping: Arc::clone(&stopping),
+                                     stopped: Arc::clone(&stopped)}.start();
+    addr.stop();
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(20), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                assert!(!addr.connected());
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+
+    sys.run();
+    assert_eq!(stopping.load(Ordering::Relaxed), 1);
+    assert_eq!(stopped.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_stop_by_address_veto() {
+    let sys = System::new("test");
+    let stopping = Arc::new(AtomicUsize::new(0));
+    let stopped = Arc::new(AtomicUsize::new(0));
+
+    let addr: Address<_> = Plain{veto: true,
+                                 stopping: Arc::clone(&stopping),
+                                 stopped: Arc::clone(&stopped)}.start();
+    addr.stop();
+
+    let (stopping2, stopped2) = (Arc::clone(&stopping), Arc::clone(&stopped));
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(20), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                // actor vetoed stop request
+                assert!(addr.connected());
+                assert_eq!(stopping2.load(Ordering::Relaxed), 1);
+                assert_eq!(stopped2.load(Ordering::Relaxed), 0);
+
+                addr.terminate();
+                Timeout::new(Duration::from_millis(20), Arbiter::handle()).unwrap()
+                    .then(move |_| {
+                        assert!(!addr.connected());
+                        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                        future::result(Ok(()))
+                    })
+            }));
+
+    sys.run();
+    assert_eq!(stopping.load(Ordering::Relaxed), 1);
+    assert_eq!(stopped.load(Ordering::Relaxed), 1);
+}
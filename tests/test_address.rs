@@ -2,12 +2,15 @@
 extern crate futures;
 extern crate tokio_core;
 
-use std::sync::Arc;
+use std::thread;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicUsize, Ordering};
 use std::time::Duration;
-use futures::{future, Future, Sink};
+use futures::{future, Async, Future, Sink};
 use tokio_core::reactor::Timeout;
 use actix::prelude::*;
+use actix::Subscriber;
 
 #[derive(Message, Debug)]
 struct Ping(usize);
@@ -36,7 +39,7 @@ impl actix::Handler<Ping> for MyActor3 {
     type Result = MessageResult<Ping>;
 
     fn handle(&mut self, _: Ping, _: &mut actix::Context<MyActor3>) -> Self::Result {
-        Arbiter::system().send(actix::msgs::SystemExit(0));
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
         Err(())
     }
 }
@@ -48,15 +51,15 @@ fn test_address() {
 
     let addr: Address<_> = MyActor(Arc::clone(&count)).start();
     let addr2 = addr.clone();
-    addr.send(Ping(0));
+    addr.do_send(Ping(0));
 
     Arbiter::handle().spawn_fn(move || {
-        addr2.send(Ping(1));
+        addr2.do_send(Ping(1));
 
         Timeout::new(Duration::new(0, 100), Arbiter::handle()).unwrap()
             .then(move |_| {
-                addr2.send(Ping(2));
-                Arbiter::system().send(actix::msgs::SystemExit(0));
+                addr2.do_send(Ping(2));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
                 future::result(Ok(()))
             })
     });
@@ -72,13 +75,13 @@ fn test_subscriber_sink() {
 
     let addr: Address<_> = MyActor(Arc::clone(&count)).start();
     let addr2 = addr.clone();
-    addr.send(Ping(0));
+    addr.do_send(Ping(0));
 
     Arbiter::handle().spawn(
         addr2.into_subscriber()
             .send(Ping(1))
             .then(|addr| addr.unwrap().send(Ping(2)).then(|_| {
-                Arbiter::system().send(actix::msgs::SystemExit(0));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
                 Ok(())
             }))
     );
@@ -96,20 +99,20 @@ fn test_sync_address() {
     let addr: SyncAddress<_> = MyActor(Arc::clone(&count)).start();
     let addr2 = addr.clone();
     let addr3 = addr.clone();
-    addr.send(Ping(1));
+    addr.do_send(Ping(1));
 
-    arbiter.send(actix::msgs::Execute::new(move || -> Result<(), ()> {
-        addr3.send(Ping(2));
-        Arbiter::system().send(actix::msgs::SystemExit(0));
+    arbiter.do_send(actix::msgs::Execute::new(move || -> Result<(), ()> {
+        addr3.do_send(Ping(2));
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
         Ok(())
     }));
     
     Arbiter::handle().spawn_fn(move || {
-        addr2.send(Ping(3));
+        addr2.do_send(Ping(3));
 
         Timeout::new(Duration::new(0, 100), Arbiter::handle()).unwrap()
             .then(move |_| {
-                addr2.send(Ping(4));
+                addr2.do_send(Ping(4));
                 future::result(Ok(()))
             })
     });
@@ -125,13 +128,13 @@ fn test_sync_subscriber_sink() {
 
     let addr: SyncAddress<_> = MyActor(Arc::clone(&count)).start();
     let addr2 = addr.clone();
-    addr.send(Ping(0));
+    addr.do_send(Ping(0));
 
     Arbiter::handle().spawn(
         addr2.into_subscriber()
             .send(Ping(1))
             .then(|addr| addr.unwrap().send(Ping(2)).then(|_| {
-                Arbiter::system().send(actix::msgs::SystemExit(0));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
                 Ok(())
             }))
     );
@@ -140,6 +143,174 @@ fn test_sync_subscriber_sink() {
     assert_eq!(count.load(Ordering::Relaxed), 3);
 }
 
+#[derive(Message)]
+struct Parsed(u64);
+
+struct Collector(Arc<Mutex<Vec<u64>>>);
+
+impl Actor for Collector {
+    type Context = Context<Self>;
+}
+
+impl actix::Handler<Parsed> for Collector {
+    type Result = ();
+
+    fn handle(&mut self, msg: Parsed, ctx: &mut Context<Self>) {
+        let mut values = self.0.lock().unwrap();
+        values.push(msg.0);
+        if values.len() == 3 {
+            ctx.stop();
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        }
+    }
+}
+
+#[test]
+fn test_map_subscriber() {
+    let sys = System::new("test");
+    let values = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: SyncAddress<_> = Collector(Arc::clone(&values)).start();
+    let sub: Box<actix::Subscriber<String> + Send> = addr.into_subscriber::<Parsed>()
+        .map(|s: String| Parsed(s.parse().unwrap()));
+    let producer = sub.clone();
+    thread::spawn(move || {
+        for s in &["1", "2", "3"] {
+            actix::Subscriber::send(&*producer, s.to_string()).unwrap();
+        }
+    });
+
+    sys.run();
+    assert_eq!(*values.lock().unwrap(), vec![1, 2, 3]);
+
+    // original message comes back with error
+    match actix::Subscriber::send(&*sub, "7".to_owned()) {
+        Err(SendError::Closed(s)) => assert_eq!(s, "7"),
+        _ => panic!("subscriber should be closed"),
+    }
+}
+
+#[derive(Debug, PartialEq)]
+struct Greeting(String);
+
+#[derive(Message)]
+struct Hello {
+    name: String,
+    reply_to: Box<actix::Subscriber<Greeting> + Send>,
+}
+
+struct Greeter;
+
+impl Actor for Greeter {
+    type Context = Context<Self>;
+}
+
+impl actix::Handler<Hello> for Greeter {
+    type Result = ();
+
+    fn handle(&mut self, msg: Hello, _: &mut Context<Self>) {
+        let reply = Greeting(format!("hello {}", msg.name));
+        assert!(actix::Subscriber::send(&*msg.reply_to, reply).is_ok());
+
+        // subscriber is single-use
+        match actix::Subscriber::send(&*msg.reply_to, Greeting("again".to_owned())) {
+            Err(SendError::Closed(Greeting(s))) => assert_eq!(s, "again"),
+            _ => panic!("second reply should be rejected"),
+        }
+    }
+}
+
+#[test]
+fn test_ask() {
+    let sys = System::new("test");
+
+    let addr: SyncAddress<_> = Greeter.start();
+    let (reply_to, reply) = actix::ask::<Greeting>();
+    addr.do_send(Hello{name: "actix".to_owned(), reply_to: reply_to});
+
+    Arbiter::handle().spawn(reply.then(|res| {
+        assert_eq!(res.unwrap(), Greeting("hello actix".to_owned()));
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        Ok(())
+    }));
+
+    sys.run();
+}
+
+/// Message with result that is not `Send`
+struct Record(usize);
+
+impl actix::ResponseType for Record {
+    type Item = std::rc::Rc<usize>;
+    type Error = ();
+}
+
+struct Journal {
+    records: Arc<Mutex<Vec<usize>>>,
+    defer: bool,
+}
+
+impl Actor for Journal {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        if self.defer {
+            ctx.defer::<Record>();
+        }
+    }
+}
+
+impl actix::Handler<Record> for Journal {
+    type Result = Result<std::rc::Rc<usize>, ()>;
+
+    fn handle(&mut self, msg: Record, _: &mut Context<Self>) -> Self::Result {
+        self.records.lock().unwrap().push(msg.0);
+        Err(())
+    }
+}
+
+#[test]
+fn test_send_confirmed() {
+    let sys = System::new("test");
+    let records = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: Address<_> = Journal{records: Arc::clone(&records), defer: false}.start();
+    let confirmed = Arc::clone(&records);
+    Arbiter::handle().spawn(
+        addr.send_confirmed(Record(1)).then(move |res| {
+            // handler's error is not reported
+            assert!(res.is_ok());
+            assert_eq!(*confirmed.lock().unwrap(), vec![1]);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+}
+
+#[test]
+fn test_send_confirmed_actor_stopped() {
+    let sys = System::new("test");
+    let records = Arc::new(Mutex::new(Vec::new()));
+
+    // message stays deferred until actor stops
+    let addr: Address<_> = Journal{records: Arc::clone(&records), defer: true}.start();
+    let delivery = addr.send_confirmed(Record(1));
+    addr.stop();
+    Arbiter::handle().spawn(
+        delivery.then(|res| {
+            match res {
+                Err(MailboxError::Closed) => (),
+                _ => panic!("delivery should fail"),
+            }
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+    assert!(records.lock().unwrap().is_empty());
+}
+
 #[test]
 fn test_error_result() {
     let sys = System::new("test");
@@ -185,7 +356,7 @@ fn test_message_timeout() {
     let count2 = Arc::clone(&count);
 
     Arbiter::handle().spawn_fn(move || {
-        addr.send(Ping(0));
+        addr.do_send(Ping(0));
         addr.call_fut(Ping(0))
             .timeout(Duration::new(0, 1_000))
             .then(move |res| {
@@ -196,7 +367,7 @@ fn test_message_timeout() {
                     },
                     _ => panic!("Should not happen"),
                 }
-                Arbiter::system().send(actix::msgs::SystemExit(0));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
                 futures::future::result(Ok(()))
             })
     });
@@ -214,7 +385,7 @@ fn test_sync_message_timeout() {
     let count2 = Arc::clone(&count);
 
     Arbiter::handle().spawn_fn(move || {
-        addr.send(Ping(0));
+        addr.do_send(Ping(0));
         addr.call_fut(Ping(0))
             .timeout(Duration::new(0, 1_000))
             .then(move |res| {
@@ -225,7 +396,7 @@ fn test_sync_message_timeout() {
                     },
                     _ => panic!("Should not happen"),
                 }
-                Arbiter::system().send(actix::msgs::SystemExit(0));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
                 futures::future::result(Ok(()))
             })
     });
@@ -240,9 +411,10 @@ impl Actor for TimeoutActor2 {
     type Context = Context<Self>;
 
     fn started(&mut self, ctx: &mut Self::Context) {
-        self.0.send(Ping(0));
-        self.0.call(self, Ping(0))
+        self.0.do_send(Ping(0));
+        self.0.call_fut(Ping(0))
             .timeout(Duration::new(0, 1_000))
+            .into_actor(self)
             .then(move |res, act, _| {
                 match res {
                     Ok(Err(_)) => panic!("Should not happen"),
@@ -251,7 +423,7 @@ impl Actor for TimeoutActor2 {
                     },
                     _ => panic!("Should not happen"),
                 }
-                Arbiter::system().send(actix::msgs::SystemExit(0));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
                 actix::fut::ok(())
             })
             .wait(ctx)
@@ -278,9 +450,10 @@ impl Actor for TimeoutActor3 {
     type Context = Context<Self>;
 
     fn started(&mut self, ctx: &mut Self::Context) {
-        self.0.send(Ping(0));
-        self.0.call(self, Ping(0))
+        self.0.do_send(Ping(0));
+        self.0.call_fut(Ping(0))
             .timeout(Duration::new(0, 1_000))
+            .into_actor(self)
             .then(move |res, act, _| {
                 match res {
                     Ok(Err(_)) => panic!("Should not happen"),
@@ -289,7 +462,7 @@ impl Actor for TimeoutActor3 {
                     },
                     _ => panic!("Should not happen"),
                 }
-                Arbiter::system().send(actix::msgs::SystemExit(0));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
                 actix::fut::ok(())
             })
             .wait(ctx)
@@ -308,3 +481,562 @@ fn test_sync_call_message_timeout() {
     sys.run();
     assert_eq!(count.load(Ordering::Relaxed), 1);
 }
+
+struct StopActor;
+
+impl Actor for StopActor {
+    type Context = Context<Self>;
+}
+
+impl actix::Handler<Ping> for StopActor {
+    type Result = ResponseActFuture<Self, Ping>;
+
+    fn handle(&mut self, _: Ping, ctx: &mut Self::Context) -> Self::Result {
+        // response future never resolves, actor stops
+        ctx.stop();
+        Box::new(actix::fut::wrap_future(future::empty()))
+    }
+}
+
+#[test]
+fn test_call_closed() {
+    let sys = System::new("test");
+
+    let addr: Address<_> = StopActor.start();
+    let saddr: SyncAddress<_> = StopActor.start();
+    Arbiter::handle().spawn_fn(move || {
+        // stop actors
+        addr.do_send(Ping(0));
+        saddr.do_send(Ping(0));
+
+        Timeout::new(Duration::new(0, 1_000), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                assert!(!addr.connected());
+                assert!(!saddr.connected());
+                addr.call_fut(Ping(1))
+                    .then(move |res| {
+                        assert_eq!(res.unwrap_err(), MailboxError::Closed);
+                        saddr.call_fut(Ping(1))
+                    })
+                    .then(|res| {
+                        assert_eq!(res.unwrap_err(), MailboxError::Closed);
+                        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                        Ok(())
+                    })
+            })
+    });
+
+    sys.run();
+}
+
+#[test]
+fn test_call_canceled() {
+    let sys = System::new("test");
+
+    let addr: Address<_> = StopActor.start();
+    let saddr: SyncAddress<_> = StopActor.start();
+    Arbiter::handle().spawn(
+        addr.call_fut(Ping(0))
+            .then(move |res| {
+                assert_eq!(res.unwrap_err(), MailboxError::Canceled);
+                saddr.call_fut(Ping(0))
+            })
+            .then(|res| {
+                assert_eq!(res.unwrap_err(), MailboxError::Canceled);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            })
+    );
+
+    sys.run();
+}
+
+#[test]
+fn test_send_capacity() {
+    let sys = System::new("test");
+    let count = Arc::new(AtomicUsize::new(0));
+    let addr: Address<_> = MyActor::build()
+        .mailbox_capacity(1)
+        .start(MyActor(Arc::clone(&count)));
+
+    // try_send fails immediately if mailbox is full
+    assert!(addr.try_send(Ping(0)).is_ok());
+    assert!(addr.try_send(Ping(1)).is_err());
+
+    // do_send ignores mailbox capacity
+    addr.do_send(Ping(1));
+
+    // send waits until mailbox has room
+    let res_count = Arc::clone(&count);
+    Arbiter::handle().spawn(
+        addr.send(Ping(2)).then(move |res| {
+            assert!(res.is_ok());
+            assert!(res_count.load(Ordering::Relaxed) >= 1);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+    assert_eq!(count.load(Ordering::Relaxed), 3);
+}
+
+#[test]
+fn test_sync_send_capacity() {
+    let sys = System::new("test");
+    let count = Arc::new(AtomicUsize::new(0));
+    let addr: SyncAddress<_> = MyActor::build()
+        .mailbox_capacity(1)
+        .start(MyActor(Arc::clone(&count)));
+
+    // try_send fails immediately if mailbox is full
+    let mut sent = 0;
+    while addr.try_send(Ping(sent)).is_ok() {
+        sent += 1;
+    }
+    assert!(sent >= 1);
+
+    // do_send ignores mailbox capacity
+    addr.do_send(Ping(sent));
+
+    // send waits until mailbox has room
+    let res_count = Arc::clone(&count);
+    Arbiter::handle().spawn(
+        addr.send(Ping(sent + 1)).then(move |res| {
+            assert!(res.is_ok());
+            assert!(res_count.load(Ordering::Relaxed) >= 1);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+    assert_eq!(count.load(Ordering::Relaxed), sent + 2);
+}
+
+struct Producers(Arc<Mutex<Vec<usize>>>);
+
+impl Actor for Producers {
+    type Context = Context<Self>;
+}
+
+impl actix::Handler<Ping> for Producers {
+    type Result = ();
+
+    fn handle(&mut self, msg: Ping, _: &mut Self::Context) {
+        let mut received = self.0.lock().unwrap();
+        received.push(msg.0);
+        if received.len() == 10 {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        }
+    }
+}
+
+fn producer(id: usize, mut addr: SyncAddress<Producers>) -> Box<Future<Item=(), Error=()>> {
+    let mut sent = 0;
+    Box::new(future::poll_fn(move || {
+        while sent < 5 {
+            if addr.poll_ready().is_not_ready() {
+                return Ok(Async::NotReady)
+            }
+            assert!(addr.start_send(Ping(id)).is_ok());
+            sent += 1;
+        }
+        Ok(Async::Ready(()))
+    }))
+}
+
+#[test]
+fn test_sync_poll_ready() {
+    let sys = System::new("test");
+    let received = Arc::new(Mutex::new(Vec::new()));
+    let addr: SyncAddress<_> = Producers::build()
+        .mailbox_capacity(1)
+        .start(Producers(Arc::clone(&received)));
+
+    // both producers get parked on full mailbox and woken in turn
+    Arbiter::handle().spawn(producer(0, addr.clone()));
+    Arbiter::handle().spawn(producer(1, addr));
+
+    sys.run();
+    assert_eq!(*received.lock().unwrap(), vec![0, 1, 0, 1, 0, 1, 0, 1, 0, 1]);
+}
+
+#[test]
+fn test_send_closed() {
+    let sys = System::new("test");
+
+    let addr: Address<_> = StopActor.start();
+    let saddr: SyncAddress<_> = StopActor.start();
+    Arbiter::handle().spawn_fn(move || {
+        // stop actors
+        addr.do_send(Ping(0));
+        saddr.do_send(Ping(0));
+
+        Timeout::new(Duration::new(0, 1_000), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                assert!(addr.try_send(Ping(1)).is_err());
+                assert!(saddr.try_send(Ping(1)).is_err());
+                addr.send(Ping(1))
+                    .then(move |res| {
+                        assert_eq!(res.unwrap_err(), MailboxError::Closed);
+                        saddr.send(Ping(1))
+                    })
+                    .then(|res| {
+                        assert_eq!(res.unwrap_err(), MailboxError::Closed);
+                        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                        Ok(())
+                    })
+            })
+    });
+
+    sys.run();
+}
+
+#[derive(Message)]
+#[rtype(usize, String)]
+struct Divide(usize, usize);
+
+struct Divider;
+
+impl Actor for Divider {
+    type Context = Context<Self>;
+}
+
+impl actix::Handler<Divide> for Divider {
+    type Result = MessageResult<Divide>;
+
+    fn handle(&mut self, msg: Divide, _: &mut Context<Self>) -> Self::Result {
+        if msg.1 == 0 {
+            Err("division by zero".to_owned())
+        } else {
+            Ok(msg.0 / msg.1)
+        }
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum DivideError {
+    Mailbox(MailboxError),
+    Divide(String),
+}
+
+impl From<MailboxError> for DivideError {
+    fn from(err: MailboxError) -> DivideError {
+        DivideError::Mailbox(err)
+    }
+}
+
+impl From<String> for DivideError {
+    fn from(err: String) -> DivideError {
+        DivideError::Divide(err)
+    }
+}
+
+#[test]
+fn test_request_map() {
+    let sys = System::new("test");
+
+    let addr: Address<_> = Divider.start();
+    let saddr: SyncAddress<_> = Divider.start();
+    Arbiter::handle().spawn(
+        addr.call_fut(Divide(10, 2)).map(|n| n * 10)
+            .join(saddr.call_fut(Divide(1, 0)).map_err(|e| e.len()))
+            .then(|res| {
+                let (res1, res2) = res.unwrap();
+                assert_eq!(res1, Ok(50));
+                assert_eq!(res2, Err("division by zero".len()));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+}
+
+#[derive(Debug, PartialEq)]
+struct MailboxErrorOrUnit(Option<MailboxError>);
+
+impl From<MailboxError> for MailboxErrorOrUnit {
+    fn from(err: MailboxError) -> Self {
+        MailboxErrorOrUnit(Some(err))
+    }
+}
+
+impl From<()> for MailboxErrorOrUnit {
+    fn from(_: ()) -> Self {
+        MailboxErrorOrUnit(None)
+    }
+}
+
+#[test]
+fn test_request_flatten() {
+    let sys = System::new("test");
+
+    let addr: Address<_> = Divider.start();
+    let saddr: SyncAddress<_> = Divider.start();
+    let stopped: Address<_> = StopActor.start();
+    stopped.do_send(Ping(0));
+
+    Arbiter::handle().spawn(
+        addr.call_fut(Divide(10, 2)).map(|n| n + 1).flatten::<DivideError>()
+            .then(move |res| {
+                assert_eq!(res, Ok(6));
+                saddr.call_fut(Divide(1, 0)).flatten::<DivideError>()
+            })
+            .then(move |res| {
+                assert_eq!(res, Err(DivideError::Divide("division by zero".to_owned())));
+                Timeout::new(Duration::new(0, 1_000), Arbiter::handle()).unwrap()
+                    .then(move |_| stopped.call_fut(Ping(1)).flatten::<MailboxErrorOrUnit>())
+            })
+            .then(|res| {
+                assert_eq!(res, Err(MailboxErrorOrUnit(Some(MailboxError::Closed))));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+}
+
+/// Message whose error never leaves actor's thread
+struct Log(&'static str);
+
+impl ResponseType for Log {
+    type Item = ();
+    type Error = Rc<String>;
+}
+
+struct Logger(Arc<Mutex<Vec<&'static str>>>);
+
+impl Actor for Logger {
+    type Context = Context<Self>;
+}
+
+impl Handler<Log> for Logger {
+    type Result = MessageResult<Log>;
+
+    fn handle(&mut self, msg: Log, _: &mut Context<Self>) -> Self::Result {
+        self.0.lock().unwrap().push(msg.0);
+        if self.0.lock().unwrap().len() == 4 {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        }
+        Ok(())
+    }
+}
+
+#[test]
+fn test_send_non_send_response() {
+    let sys = System::new("test");
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: SyncAddress<_> = Logger(Arc::clone(&log)).start();
+    addr.do_send(Log("do_send"));
+    addr.try_send(Log("try_send")).unwrap();
+    addr.clone().into_subscriber().try_send(Log("subscriber")).unwrap();
+    Arbiter::handle().spawn(addr.send(Log("send")).map_err(|_| ()));
+
+    sys.run();
+    assert_eq!(*log.lock().unwrap(), vec!["do_send", "try_send", "subscriber", "send"]);
+}
+
+#[test]
+fn test_subscriber_downcast() {
+    let _sys = System::new("test");
+    let addr: SyncAddress<_> = MyActor(Arc::new(AtomicUsize::new(0))).start();
+    let subs: Vec<Box<actix::Subscriber<Ping>>> = vec![
+        addr.clone().into_subscriber(), MyActor3.start::<Address<_>>().into_subscriber()];
+
+    assert!(subs[0].as_any().downcast_ref::<SyncAddress<MyActor>>().is_some());
+    assert!(subs[0].as_any().downcast_ref::<Address<MyActor>>().is_none());
+    assert!(subs[0].as_any().downcast_ref::<SyncAddress<MyActor3>>().is_none());
+    assert!(subs[1].as_any().downcast_ref::<Address<MyActor3>>().is_some());
+
+    // recovered address is callable
+    let recovered = subs[0].as_any().downcast_ref::<SyncAddress<MyActor>>().unwrap();
+    assert!(recovered.connected());
+}
+
+#[test]
+fn test_subscriber_eq() {
+    let _sys = System::new("test");
+    let (local, remote): (Address<_>, SyncAddress<_>) =
+        MyActor(Arc::new(AtomicUsize::new(0))).start();
+    let other: SyncAddress<_> = MyActor(Arc::new(AtomicUsize::new(0))).start();
+
+    let sub1 = remote.clone().into_subscriber::<Ping>();
+    let sub2 = sub1.clone();
+    let sub3 = local.into_subscriber::<Ping>();
+    let sub4 = other.into_subscriber::<Ping>();
+
+    assert!(sub1.subscriber_eq(&*sub2));
+    assert!(sub1.subscriber_eq(&*sub3));
+    assert!(!sub1.subscriber_eq(&*sub4));
+    assert!(!sub4.subscriber_eq(&*sub3));
+
+    let (ask1, _rx) = actix::ask::<usize>();
+    let (ask2, _rx2) = actix::ask::<usize>();
+    assert!(ask1.subscriber_eq(&*ask1.clone()));
+    assert!(!ask1.subscriber_eq(&*ask2));
+}
+
+#[derive(Message)]
+struct Hang;
+
+/// Never answers `Hang`
+struct Mute;
+
+impl Actor for Mute {
+    type Context = Context<Self>;
+}
+
+impl Handler<Hang> for Mute {
+    type Result = ResponseFuture<Hang>;
+
+    fn handle(&mut self, _: Hang, _: &mut Context<Self>) -> ResponseFuture<Hang> {
+        Box::new(future::empty())
+    }
+}
+
+#[test]
+fn test_call_blocking() {
+    let sys = System::new("test");
+    let divider: SyncAddress<_> = Divider.start();
+    let mute: SyncAddress<_> = Mute.start();
+    let system = Arbiter::system();
+
+    // plain thread, no actix system
+    let handle = thread::spawn(move || {
+        let res = (divider.call_blocking(Divide(10, 2), Duration::from_secs(1)),
+                   divider.call_blocking(Divide(1, 0), Duration::from_secs(1)),
+                   mute.call_blocking(Hang, Duration::from_millis(50)));
+        system.do_send(actix::msgs::SystemExit(0));
+        res
+    });
+    sys.run();
+
+    let (ok, err, timeout) = handle.join().unwrap();
+    assert_eq!(ok, Ok(Ok(5)));
+    assert_eq!(err, Ok(Err("division by zero".to_owned())));
+    assert_eq!(timeout, Err(MailboxError::Timeout));
+}
+
+#[test]
+#[should_panic(expected = "must not be called from an actix arbiter thread")]
+fn test_call_blocking_in_arbiter() {
+    let _sys = System::new("test");
+    let addr: SyncAddress<_> = Divider.start();
+    let _ = addr.call_blocking(Divide(10, 2), Duration::from_secs(1));
+}
+
+struct Tracked {
+    pings: Arc<AtomicUsize>,
+    stopped: Arc<AtomicUsize>,
+}
+
+impl Actor for Tracked {
+    type Context = Context<Self>;
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        self.stopped.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+impl Handler<Ping> for Tracked {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) {
+        self.pings.fetch_add(1, Ordering::SeqCst);
+    }
+}
+
+#[test]
+fn test_weak_address() {
+    let sys = System::new("test");
+    let pings = Arc::new(AtomicUsize::new(0));
+    let stopped = Arc::new(AtomicUsize::new(0));
+
+    let addr: Address<_> = Tracked{pings: Arc::clone(&pings), stopped: Arc::clone(&stopped)}.start();
+    let sync: SyncAddress<_> = Tracked{pings: Arc::clone(&pings), stopped: Arc::clone(&stopped)}.start();
+    let weak = addr.downgrade();
+    let weak_sync = sync.downgrade();
+
+    // upgraded addresses deliver messages
+    weak.clone().upgrade().unwrap().do_send(Ping(0));
+    weak_sync.clone().upgrade().unwrap().do_send(Ping(1));
+    drop(addr);
+    drop(sync);
+
+    let stopped2 = Arc::clone(&stopped);
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                // weak addresses do not keep actors alive
+                assert_eq!(stopped2.load(Ordering::SeqCst), 2);
+                assert!(weak.upgrade().is_none());
+                assert!(weak_sync.upgrade().is_none());
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+    sys.run();
+    assert_eq!(pings.load(Ordering::SeqCst), 2);
+}
+
+#[test]
+fn test_recipient() {
+    let sys = System::new("test");
+    let local: Address<_> = Divider.start();
+    let sync: SyncAddress<_> = Divider.start();
+    let mute: Address<_> = Mute.start();
+
+    // recipients of different address types share one type
+    let recipients: Vec<Recipient<Divide>> = vec![local.into_recipient(), sync.into_recipient()];
+    let hang = mute.into_recipient::<Hang>();
+    assert!(recipients[0].subscriber_eq(&recipients[0].clone()));
+    assert!(!recipients[0].subscriber_eq(&recipients[1]));
+
+    let res = Arc::new(Mutex::new(None));
+    let res2 = Arc::clone(&res);
+    Arbiter::handle().spawn(
+        recipients[0].call(Divide(10, 2))
+            .join3(recipients[1].call(Divide(1, 0)),
+                   recipients[1].call(Divide(9, 3)).map(|n| n * 2))
+            .then(move |r| {
+                let hang = hang.call(Hang).timeout(Duration::from_millis(20));
+                future::result(r).join(hang.then(Ok::<_, MailboxError>))
+            })
+            .then(move |r| {
+                *res2.lock().unwrap() = Some(r.unwrap());
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+    sys.run();
+
+    let ((ok, err, mapped), timeout) = res.lock().unwrap().take().unwrap();
+    assert_eq!(ok, Ok(5));
+    assert_eq!(err, Err("division by zero".to_owned()));
+    assert_eq!(mapped, Ok(6));
+    assert_eq!(timeout, Err(MailboxError::Timeout));
+}
+
+#[test]
+fn test_sync_recipient() {
+    let sys = System::new("test");
+    let sync: SyncAddress<_> = Divider.start();
+    let recipient: SyncRecipient<Divide> = sync.into_sync_recipient();
+    let local: Recipient<Divide> = recipient.clone().into();
+    assert!(local.try_send(Divide(1, 1)).is_ok());
+
+    // recipient is moved to other thread, request future is waited there
+    let (tx, rx) = futures::sync::oneshot::channel();
+    thread::spawn(move || {
+        let _ = tx.send(recipient.call(Divide(8, 2)).wait());
+    });
+
+    let res = Arc::new(Mutex::new(None));
+    let res2 = Arc::clone(&res);
+    Arbiter::handle().spawn(rx.then(move |r| {
+        *res2.lock().unwrap() = Some(r.unwrap());
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        future::result(Ok(()))
+    }));
+    sys.run();
+
+    assert_eq!(res.lock().unwrap().take().unwrap(), Ok(Ok(4)));
+}
@@ -1,9 +1,14 @@
 extern crate futures;
+extern crate log;
+extern crate tokio_core;
 #[macro_use]extern crate actix;
 
-use std::sync::Arc;
+use std::thread;
+use std::collections::HashSet;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use futures::Future;
+use std::time::Duration;
+use futures::{Future, Stream};
 use actix::prelude::*;
 
 #[derive(Debug, Message)]
@@ -20,7 +25,7 @@ impl Handler<Ping> for MyActor {
 
     fn handle(&mut self, _: Ping, _: &mut actix::Context<MyActor>) {
         self.0.store(self.0.load(Ordering::Relaxed) + 1, Ordering::Relaxed);
-        Arbiter::system().send(actix::msgs::SystemExit(0));
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
     }
 }
 
@@ -34,12 +39,30 @@ fn test_start_actor() {
         MyActor(act_count)
     });
 
-    addr.send(Ping(1));
+    addr.do_send(Ping(1));
     sys.run();
     assert_eq!(count.load(Ordering::Relaxed), 1);
 }
 
 
+#[derive(Debug)]
+enum StartError {
+    Mailbox,
+    Start,
+}
+
+impl From<MailboxError> for StartError {
+    fn from(_: MailboxError) -> StartError {
+        StartError::Mailbox
+    }
+}
+
+impl From<()> for StartError {
+    fn from(_: ()) -> StartError {
+        StartError::Start
+    }
+}
+
 #[test]
 fn test_start_actor_message() {
     let sys = System::new("test");
@@ -52,11 +75,496 @@ fn test_start_actor_message() {
         arbiter.call_fut(
             actix::msgs::StartActor::new(move |_| {
                 MyActor(act_count)
-            })).then(|res| {
-                res.unwrap().unwrap().send(Ping(1));
+            })).flatten::<StartError>().then(|res| {
+                res.unwrap().do_send(Ping(1));
                 Ok(())
             }));
 
     sys.run();
     assert_eq!(count.load(Ordering::Relaxed), 1);
 }
+
+struct Worker {
+    msgs: Arc<AtomicUsize>,
+    stopped: Arc<AtomicUsize>,
+}
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        self.stopped.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Handler<Ping> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut actix::Context<Worker>) {
+        self.msgs.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn test_stop_arbiter_graceful() {
+    let sys = System::new("test");
+    let msgs = Arc::new(AtomicUsize::new(0));
+    let stopped = Arc::new(AtomicUsize::new(0));
+
+    let arbiter = Arbiter::new("graceful");
+    let (act_msgs, act_stopped) = (Arc::clone(&msgs), Arc::clone(&stopped));
+    let addr = Worker::build()
+        .on_arbiter(&arbiter)
+        .start_with(move |_| Worker{msgs: act_msgs, stopped: act_stopped});
+    for i in 0..10 {
+        addr.do_send(Ping(i));
+    }
+
+    let (res_msgs, res_stopped) = (Arc::clone(&msgs), Arc::clone(&stopped));
+    Arbiter::handle().spawn(
+        arbiter.call_fut(
+            actix::msgs::StopArbiterGraceful{timeout: Duration::from_secs(5)})
+            .then(move |r| {
+                assert!(r.unwrap().is_ok());
+                // all messages are handled before arbiter reports completion
+                assert_eq!(res_msgs.load(Ordering::Relaxed), 10);
+                assert_eq!(res_stopped.load(Ordering::Relaxed), 1);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+    assert_eq!(msgs.load(Ordering::Relaxed), 10);
+    assert!(!addr.connected());
+}
+
+/// Worker that handles single message per poll
+struct LimitedWorker(Worker);
+
+impl Actor for LimitedWorker {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.set_poll_budget(1);
+    }
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        self.0.stopped.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl Handler<Ping> for LimitedWorker {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut actix::Context<LimitedWorker>) {
+        self.0.msgs.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn test_stop_arbiter_graceful_budget() {
+    let sys = System::new("test");
+    let msgs = Arc::new(AtomicUsize::new(0));
+    let stopped = Arc::new(AtomicUsize::new(0));
+
+    let arbiter = Arbiter::new("graceful-budget");
+    let (act_msgs, act_stopped) = (Arc::clone(&msgs), Arc::clone(&stopped));
+    let addr = LimitedWorker::build()
+        .on_arbiter(&arbiter)
+        .start_with(move |_| LimitedWorker(Worker{msgs: act_msgs, stopped: act_stopped}));
+    for i in 0..10 {
+        addr.do_send(Ping(i));
+    }
+
+    Arbiter::handle().spawn(
+        arbiter.call_fut(
+            actix::msgs::StopArbiterGraceful{timeout: Duration::from_secs(5)})
+            .then(move |r| {
+                assert!(r.unwrap().is_ok());
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+    // mailbox yields after every message, actor stops once it is empty
+    assert_eq!(msgs.load(Ordering::Relaxed), 10);
+    assert_eq!(stopped.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_system_exit_stops_arbiters() {
+    let sys = System::new("test").shutdown_timeout(Duration::from_secs(5));
+    let msgs = Arc::new(AtomicUsize::new(0));
+    let stopped = Arc::new(AtomicUsize::new(0));
+
+    for name in &["exit1", "exit2"] {
+        let (act_msgs, act_stopped) = (Arc::clone(&msgs), Arc::clone(&stopped));
+        let _: SyncAddress<_> = Worker::build()
+            .on_arbiter(&Arbiter::new(*name))
+            .start_with(move |_| Worker{msgs: act_msgs, stopped: act_stopped});
+    }
+    Arbiter::system().do_send(actix::msgs::SystemExit(0));
+
+    assert_eq!(sys.run(), 0);
+    // stopped() of every actor runs before system returns
+    assert_eq!(stopped.load(Ordering::Relaxed), 2);
+}
+
+/// Actor that never gets to stop, it waits forever after start
+struct Stubborn;
+
+impl Actor for Stubborn {
+    type Context = actix::Context<Self>;
+
+    fn started(&mut self, ctx: &mut actix::Context<Self>) {
+        // arbiter tracks actor once it is started
+        Arbiter::system().do_send(actix::msgs::SystemExit(3));
+        AsyncContext::wait(ctx, actix::fut::wrap_future(futures::future::empty::<(), ()>()));
+    }
+
+    fn stopping(&mut self, _: &mut actix::Context<Self>) -> bool {
+        false
+    }
+}
+
+impl Handler<Ping> for Stubborn {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut actix::Context<Self>) {}
+}
+
+#[test]
+fn test_shutdown_report() {
+    let sys = System::new("test").shutdown_timeout(Duration::from_millis(100));
+    let msgs = Arc::new(AtomicUsize::new(0));
+    let stopped = Arc::new(AtomicUsize::new(0));
+
+    let _: SyncAddress<_> = Worker::build()
+        .on_arbiter(&Arbiter::new("polite"))
+        .start_with(move |_| Worker{msgs: msgs, stopped: stopped});
+    let addr: SyncAddress<_> = Stubborn::build()
+        .name("stubborn")
+        .on_arbiter(&Arbiter::new("stubborn"))
+        .start_with(|_| Stubborn);
+    for i in 0..5 {
+        addr.do_send(Ping(i));
+    }
+
+    let report = sys.run_report();
+    assert_eq!(report.exit_code, 3);
+    assert_eq!(report.forced.len(), 1);
+    assert_eq!(report.forced[0].name, "stubborn");
+    assert_eq!(report.forced[0].dead_letters, 5);
+    assert!(report.dead_letters >= 5);
+    assert!(report.arbiters.is_empty());
+    assert!(report.duration >= Duration::from_millis(100));
+    assert!(!addr.connected());
+}
+
+struct Phased {
+    name: &'static str,
+    phase: usize,
+    log: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Actor for Phased {
+    type Context = actix::Context<Self>;
+
+    fn started(&mut self, ctx: &mut actix::Context<Self>) {
+        Arbiter::system().do_send(
+            actix::msgs::ShutdownPhase::new(self.phase, ctx.address()));
+    }
+
+    fn stopped(&mut self, _: &mut actix::Context<Self>) {
+        // earlier phases are slower, without ordering they would finish last
+        thread::sleep(Duration::from_millis(10 * (3 - self.phase as u64)));
+        self.log.lock().unwrap().push(self.name);
+    }
+}
+
+#[test]
+fn test_system_shutdown_phases() {
+    let sys = System::new("test");
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    for &(name, phase) in &[("writer", 2), ("processor", 1), ("ingestion", 0)] {
+        let log = Arc::clone(&log);
+        let _: SyncAddress<_> = Phased::build()
+            .on_arbiter(&Arbiter::new(name))
+            .start_with(move |_| Phased{name: name, phase: phase, log: log});
+    }
+
+    // let actors register their phases
+    Arbiter::handle().spawn(
+        tokio_core::reactor::Timeout::new(Duration::from_millis(50), Arbiter::handle())
+            .unwrap()
+            .then(|_| {
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(*log.lock().unwrap(), vec!["ingestion", "processor", "writer"]);
+}
+
+#[test]
+fn test_arbiter_failure() {
+    let sys = System::new("test");
+
+    let arbiter = Arbiter::new("failure");
+    arbiter.do_send(actix::msgs::Execute::new(|| -> Result<(), ()> {
+        panic!("arbiter failure")
+    }));
+
+    let report = sys.run_report();
+    assert_eq!(report.exit_code, 1);
+    match report.reason {
+        actix::SystemExitReason::ArbiterDied{id, name} => {
+            assert!(!id.is_empty());
+            assert!(name.ends_with(":\"failure\""));
+        }
+        reason => panic!("unexpected exit reason {:?}", reason),
+    }
+}
+
+#[test]
+fn test_arbiter_failure_ignore() {
+    let sys = System::new("test").stop_on_arbiter_failure(false);
+    let count = Arc::new(AtomicUsize::new(0));
+
+    let arbiter = Arbiter::new("failure");
+    arbiter.do_send(actix::msgs::Execute::new(|| -> Result<(), ()> {
+        panic!("arbiter failure")
+    }));
+
+    // system keeps running after arbiter failure
+    let act_count = Arc::clone(&count);
+    Arbiter::handle().spawn(
+        arbiter.call_fut(actix::msgs::Execute::new(|| -> Result<(), ()> { Ok(()) }))
+            .then(move |res| {
+                assert!(res.is_err());
+                let addr: Address<_> = MyActor(act_count).start();
+                addr.do_send(Ping(1));
+                Ok(())
+            }));
+
+    assert_eq!(sys.run(), 0);
+    assert_eq!(count.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_try_accessors() {
+    let sys = System::new("test");
+    assert!(Arbiter::try_handle().is_some());
+    assert!(Arbiter::try_system().is_some());
+
+    let res = std::thread::spawn(|| {
+        (Arbiter::try_handle().is_none(), Arbiter::try_system().is_none())
+    }).join().unwrap();
+    assert_eq!(res, (true, true));
+
+    Arbiter::system().do_send(actix::msgs::SystemExit(0));
+    sys.run();
+}
+
+#[test]
+#[should_panic(expected = "must be called from an actix arbiter thread")]
+fn test_handle_outside_arbiter() {
+    Arbiter::handle();
+}
+
+struct Steps(u64);
+
+impl ResponseType for Steps {
+    type Item = u64;
+    type Error = ();
+}
+
+struct MustSteps(u64);
+
+impl ResponseType for MustSteps {
+    type Item = u64;
+    type Error = ();
+}
+
+/// Each response takes `n` steps, 10 milliseconds each
+struct Stepper(Arc<AtomicUsize>);
+
+impl Stepper {
+    fn steps(&self, n: u64) -> Box<Future<Item=u64, Error=()>> {
+        let count = Arc::clone(&self.0);
+        Box::new(
+            tokio_core::reactor::Interval::new(Duration::from_millis(10), Arbiter::handle())
+                .unwrap()
+                .take(n)
+                .for_each(move |_| {
+                    count.fetch_add(1, Ordering::Relaxed);
+                    Ok(())
+                })
+                .map(move |_| n)
+                .map_err(|_| ()))
+    }
+}
+
+impl Actor for Stepper {
+    type Context = Context<Self>;
+}
+
+impl Handler<Steps> for Stepper {
+    type Result = ResponseActFuture<Self, Steps>;
+
+    fn handle(&mut self, msg: Steps, _: &mut Context<Self>) -> Self::Result {
+        Box::new(actix::fut::wrap_future(self.steps(msg.0)))
+    }
+}
+
+impl Handler<MustSteps> for Stepper {
+    type Result = MustComplete<ResponseFuture<MustSteps>>;
+
+    fn handle(&mut self, msg: MustSteps, _: &mut Context<Self>) -> Self::Result {
+        MustComplete(self.steps(msg.0))
+    }
+}
+
+/// Call stepper from separate arbiter, stop that arbiter after `stop`
+fn call_and_stop_caller<F>(f: F, stop: Duration)
+    where F: FnOnce() -> Box<Future<Item=(), Error=()>> + Send + 'static
+{
+    let arbiter = Arbiter::new("caller");
+    arbiter.do_send(actix::msgs::Execute::new(move || -> Result<(), ()> {
+        Arbiter::handle().spawn(f());
+        Ok(())
+    }));
+
+    Arbiter::handle().spawn(
+        tokio_core::reactor::Timeout::new(stop, Arbiter::handle()).unwrap()
+            .then(move |_| {
+                arbiter.do_send(actix::msgs::StopArbiter(0));
+                Ok(())
+            }));
+}
+
+#[test]
+fn test_abandoned_response() {
+    let sys = System::new("test");
+    let steps = Arc::new(AtomicUsize::new(0));
+    let abandoned = actix::dev::abandoned_responses();
+
+    let addr: SyncAddress<_> = Stepper(Arc::clone(&steps)).start();
+    call_and_stop_caller(
+        move || Box::new(addr.call_fut(Steps(50)).then(|_| Ok(()))),
+        Duration::from_millis(35));
+
+    let res_steps = Arc::clone(&steps);
+    Arbiter::handle().spawn(
+        tokio_core::reactor::Timeout::new(Duration::from_millis(300), Arbiter::handle())
+            .unwrap()
+            .then(move |_| {
+                // response future is dropped shortly after caller is gone
+                assert!(res_steps.load(Ordering::Relaxed) < 15);
+                assert!(actix::dev::abandoned_responses() > abandoned);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+}
+
+#[test]
+fn test_must_complete_response() {
+    let sys = System::new("test");
+    let steps = Arc::new(AtomicUsize::new(0));
+
+    let addr: SyncAddress<_> = Stepper(Arc::clone(&steps)).start();
+    call_and_stop_caller(
+        move || Box::new(addr.call_fut(MustSteps(20)).then(|_| Ok(()))),
+        Duration::from_millis(35));
+
+    let res_steps = Arc::clone(&steps);
+    Arbiter::handle().spawn(
+        tokio_core::reactor::Timeout::new(Duration::from_millis(400), Arbiter::handle())
+            .unwrap()
+            .then(move |_| {
+                assert_eq!(res_steps.load(Ordering::Relaxed), 20);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+}
+
+#[test]
+fn test_unique_system_id() {
+    let sys = System::new("test");
+
+    let gen_ids = || actix::msgs::Execute::new(|| -> Result<Vec<u64>, ()> {
+        Ok((0..50_000).map(|_| actix::unique_system_id()).collect())
+    });
+    let arb1 = Arbiter::new("ids1");
+    let arb2 = Arbiter::new("ids2");
+
+    Arbiter::handle().spawn(
+        arb1.call_fut(gen_ids()).join(arb2.call_fut(gen_ids()))
+            .then(|res| {
+                let (ids1, ids2) = res.unwrap();
+                let mut ids: HashSet<u64> = ids1.unwrap().into_iter().collect();
+                ids.extend(ids2.unwrap());
+                assert_eq!(ids.len(), 100_000);
+
+                // system arbiter owns its own range
+                assert!(!ids.contains(&actix::unique_system_id()));
+
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+}
+
+/// Collects log records produced by this test binary
+struct RecordLogger(Mutex<Vec<String>>);
+
+impl log::Log for RecordLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        self.0.lock().unwrap().push(format!("{}", record.args()));
+    }
+
+    fn flush(&self) {}
+}
+
+#[derive(Debug)]
+struct SpawnError(&'static str);
+
+#[test]
+fn test_spawn_error_logging() {
+    let logger: &'static RecordLogger = Box::leak(Box::new(RecordLogger(Mutex::new(Vec::new()))));
+    log::set_logger(logger).unwrap();
+    log::set_max_level(log::LevelFilter::Error);
+
+    let sys = System::new("test");
+    let arbiter = Arbiter::new("spawn-arbiter");
+    arbiter.do_send(actix::msgs::Execute::new(|| -> Result<(), ()> {
+        Arbiter::spawn(futures::future::err::<(), _>(SpawnError("broken")));
+        Arbiter::spawn_fn(|| {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok::<_, SpawnError>(())
+        });
+        Ok(())
+    }));
+    sys.run();
+
+    let records: Vec<_> = logger.0.lock().unwrap().iter()
+        .filter(|rec| rec.contains("spawn-arbiter")).cloned().collect();
+    assert_eq!(records.len(), 1);
+    assert!(records[0].contains("SpawnError(\"broken\")"));
+}
+
+#[test]
+#[should_panic(expected = "must be called from an actix arbiter thread")]
+fn test_spawn_outside_arbiter() {
+    Arbiter::spawn_fn(|| Ok::<_, ()>(()));
+}
@@ -0,0 +1,128 @@
+extern crate actix;
+extern crate futures;
+
+use std::sync::{Arc, Mutex};
+use futures::Future;
+use actix::prelude::*;
+use actix::{MailboxHandle, MailboxReceiver};
+use actix::msgs::SystemExit;
+
+struct Num(&'static str, usize);
+
+impl ResponseType for Num {
+    type Item = ();
+    type Error = ();
+}
+
+struct Detach;
+
+impl ResponseType for Detach {
+    type Item = bool;
+    type Error = ();
+}
+
+struct Server {
+    mailboxes: Vec<MailboxReceiver<Server>>,
+    handles: Vec<MailboxHandle>,
+    log: Arc<Mutex<Vec<(&'static str, usize)>>>,
+}
+
+impl Actor for Server {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        for rx in self.mailboxes.drain(..) {
+            self.handles.push(ctx.attach_mailbox(rx));
+        }
+    }
+}
+
+impl Handler<Num> for Server {
+    type Result = ();
+
+    fn handle(&mut self, msg: Num, _: &mut Context<Self>) {
+        self.log.lock().unwrap().push((msg.0, msg.1));
+    }
+}
+
+impl Handler<Detach> for Server {
+    type Result = MessageResult<Detach>;
+
+    fn handle(&mut self, _: Detach, ctx: &mut Context<Self>) -> Self::Result {
+        let handle = self.handles.remove(0);
+        Ok(ctx.detach_mailbox(handle))
+    }
+}
+
+fn entries(log: &[(&'static str, usize)], tag: &str) -> Vec<usize> {
+    log.iter().filter(|&&(t, _)| t == tag).map(|&(_, n)| n).collect()
+}
+
+#[test]
+fn test_attach_mailbox() {
+    let sys = System::new("test");
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    // old address exists before actor
+    let (old, rx) = MailboxReceiver::new();
+    for i in 0..5 {
+        old.do_send(Num("old", i));
+    }
+    let new: SyncAddress<_> = Server{
+        mailboxes: vec![rx], handles: Vec::new(), log: Arc::clone(&log)}.start();
+    for i in 0..5 {
+        new.do_send(Num("new", i));
+    }
+
+    let closed = Arc::new(Mutex::new(None));
+    let closed2 = Arc::clone(&closed);
+    let (old2, new2) = (old.clone(), new.clone());
+    Arbiter::handle().spawn(
+        old.call_fut(Detach)
+            .then(move |res| {
+                assert_eq!(res.unwrap(), Ok(true));
+                *closed2.lock().unwrap() = Some(match old2.try_send(Num("old", 99)) {
+                    Err(SendError::Closed(_)) => true,
+                    _ => false,
+                });
+                new2.call_fut(Num("new", 5))
+            })
+            .then(|res| {
+                assert!(res.is_ok());
+                Arbiter::system().do_send(SystemExit(0));
+                Ok(())
+            }));
+    sys.run();
+
+    assert_eq!(closed.lock().unwrap().take(), Some(true));
+    let log = log.lock().unwrap();
+    assert_eq!(entries(&log, "old"), (0..5).collect::<Vec<_>>());
+    assert_eq!(entries(&log, "new"), (0..6).collect::<Vec<_>>());
+}
+
+#[test]
+fn test_attached_mailboxes_fairness() {
+    let sys = System::new("test");
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let (a, rx_a) = MailboxReceiver::new();
+    let (b, rx_b) = MailboxReceiver::new();
+    for i in 0..3 {
+        a.do_send(Num("a", i));
+    }
+    for i in 0..3 {
+        b.do_send(Num("b", i));
+    }
+    let _: () = Server{
+        mailboxes: vec![rx_a, rx_b], handles: Vec::new(), log: Arc::clone(&log)}.start();
+
+    Arbiter::handle().spawn(b.call_fut(Num("b", 3)).then(|_| {
+        Arbiter::system().do_send(SystemExit(0));
+        Ok(())
+    }));
+    sys.run();
+
+    // mailboxes are polled in turn
+    assert_eq!(*log.lock().unwrap(),
+               vec![("a", 0), ("b", 0), ("a", 1), ("b", 1), ("a", 2), ("b", 2), ("b", 3)]);
+}
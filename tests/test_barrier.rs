@@ -0,0 +1,82 @@
+extern crate actix;
+extern crate futures;
+
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use futures::Future;
+use actix::prelude::*;
+use actix::utils::{StartupBarrier, StartupTimeout};
+
+/// Reports readiness after `delay`
+struct Service {
+    token: &'static str,
+    delay: u64,
+    barrier: StartupBarrier<&'static str>,
+}
+
+impl Actor for Service {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.run_later(Duration::from_millis(self.delay), |act, _| {
+            assert!(act.barrier.ready(act.token));
+            assert!(!act.barrier.ready(act.token));
+        });
+    }
+}
+
+fn start_services(barrier: &StartupBarrier<&'static str>, services: &[(&'static str, u64)]) {
+    let arbiters = [Arbiter::new("first"), Arbiter::new("second")];
+    for (idx, &(token, delay)) in services.iter().enumerate() {
+        let _: SyncAddress<_> = Service::build()
+            .on_arbiter(&arbiters[idx % 2])
+            .start(Service{token: token, delay: delay, barrier: barrier.clone()});
+    }
+}
+
+#[test]
+fn test_barrier_all_ready() {
+    let sys = System::new("test");
+    let barrier = StartupBarrier::new(vec!["db", "cache", "auth"]);
+    let resolved = Arc::new(AtomicUsize::new(0));
+
+    let (barrier2, resolved2) = (barrier.clone(), Arc::clone(&resolved));
+    Arbiter::handle().spawn(
+        barrier.wait_all()
+            .timeout(Duration::from_secs(5))
+            .then(move |res| {
+                assert!(res.is_ok());
+                assert!(barrier2.missing().is_empty());
+                resolved2.fetch_add(1, Ordering::SeqCst);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+    start_services(&barrier, &[("db", 50), ("cache", 0), ("auth", 20)]);
+
+    sys.run();
+    assert_eq!(resolved.load(Ordering::SeqCst), 1);
+
+    // barrier stays open
+    assert!(barrier.wait_all().wait().is_ok());
+}
+
+#[test]
+fn test_barrier_timeout() {
+    let sys = System::new("test");
+    let barrier = StartupBarrier::new(vec!["db", "cache", "auth"]);
+
+    Arbiter::handle().spawn(
+        barrier.wait_all()
+            .timeout(Duration::from_millis(200))
+            .then(|res| {
+                let err = res.unwrap_err();
+                assert_eq!(err, StartupTimeout{missing: vec!["cache"]});
+                assert_eq!(format!("{}", err), "Startup timed out, missing: [\"cache\"]");
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+    start_services(&barrier, &[("db", 0), ("auth", 10)]);
+
+    sys.run();
+}
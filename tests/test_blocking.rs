@@ -0,0 +1,105 @@
+extern crate actix;
+extern crate futures;
+
+use std::thread;
+use std::time::{Duration, Instant};
+use futures::Future;
+use actix::prelude::*;
+
+/// Blocking call that takes 200ms
+struct Compute(usize);
+
+impl ResponseType for Compute {
+    type Item = usize;
+    type Error = ();
+}
+
+struct GetState;
+
+impl ResponseType for GetState {
+    type Item = (usize, usize);
+    type Error = ();
+}
+
+struct Worker {
+    ticks: usize,
+    result: usize,
+    ticks_during_compute: usize,
+}
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.run_interval_at(
+            Instant::now(), Duration::from_millis(10), |act, _| act.ticks += 1);
+    }
+}
+
+impl Handler<Compute> for Worker {
+    type Result = BlockingResponse<Self, Compute>;
+
+    fn handle(&mut self, msg: Compute, _: &mut Context<Self>) -> Self::Result {
+        let ticks = self.ticks;
+        BlockingResponse::apply(
+            move || {
+                thread::sleep(Duration::from_millis(200));
+                msg.0 * 2
+            },
+            move |res, act: &mut Worker, _| {
+                act.result = res;
+                act.ticks_during_compute = act.ticks - ticks;
+                Ok(res)
+            })
+    }
+}
+
+impl Handler<GetState> for Worker {
+    type Result = MessageResult<GetState>;
+
+    fn handle(&mut self, _: GetState, _: &mut Context<Self>) -> Self::Result {
+        Ok((self.result, self.ticks_during_compute))
+    }
+}
+
+#[test]
+fn test_blocking_response() {
+    let sys = System::new("test");
+    let addr: Address<_> = Worker{ticks: 0, result: 0, ticks_during_compute: 0}.start();
+
+    Arbiter::handle().spawn(
+        addr.call_fut(Compute(21)).then(move |res| {
+            assert_eq!(res.unwrap().unwrap(), 42);
+            addr.call_fut(GetState).then(|res| {
+                let (result, ticks) = res.unwrap().unwrap();
+                // result is applied to the actor
+                assert_eq!(result, 42);
+                // timer kept running while handler was blocked
+                assert!(ticks >= 10, "timer is stalled: {} ticks", ticks);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            })
+        }));
+
+    sys.run();
+}
+
+#[test]
+fn test_spawn_blocking_panic() {
+    let sys = System::new("test");
+
+    Arbiter::handle().spawn(
+        Arbiter::spawn_blocking(|| -> usize { panic!("blocking job failed") })
+            .then(|res| {
+                assert!(res.is_err());
+                Arbiter::spawn_blocking(|| thread::current().name().map(|n| n.to_owned()))
+            })
+            .then(|res| {
+                // pool thread survives the panic
+                assert!(res.unwrap().unwrap().ends_with(":blocking"));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+}
@@ -0,0 +1,201 @@
+extern crate actix;
+extern crate futures;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::Duration;
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::msgs::SystemExit;
+use actix::utils::{BreakerState, CircuitBreaker, CircuitError};
+
+struct Query;
+
+impl ResponseType for Query {
+    type Item = u32;
+    type Error = ();
+}
+
+/// Query that is never answered
+struct Hang;
+
+impl ResponseType for Hang {
+    type Item = ();
+    type Error = ();
+}
+
+struct Backend {
+    healthy: Arc<AtomicBool>,
+    hits: Arc<AtomicUsize>,
+}
+
+impl Actor for Backend {
+    type Context = Context<Self>;
+}
+
+impl Handler<Query> for Backend {
+    type Result = MessageResult<Query>;
+
+    fn handle(&mut self, _: Query, _: &mut Context<Self>) -> MessageResult<Query> {
+        self.hits.fetch_add(1, Ordering::SeqCst);
+        if self.healthy.load(Ordering::SeqCst) { Ok(42) } else { Err(()) }
+    }
+}
+
+impl Handler<Hang> for Backend {
+    type Result = ResponseFuture<Hang>;
+
+    fn handle(&mut self, _: Hang, _: &mut Context<Self>) -> ResponseFuture<Hang> {
+        Box::new(future::empty())
+    }
+}
+
+type Results = Arc<Mutex<Vec<(Result<u32, CircuitError<()>>, BreakerState)>>>;
+
+struct Caller {
+    backend: Address<Backend>,
+    breaker: CircuitBreaker,
+    healthy: Arc<AtomicBool>,
+    results: Results,
+}
+
+impl Actor for Caller {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.notify(Step(0));
+    }
+}
+
+struct Step(usize);
+
+impl ResponseType for Step {
+    type Item = ();
+    type Error = ();
+}
+
+impl Caller {
+    /// Query backend, record result and breaker state, then run `next` step
+    fn query(&mut self, ctx: &mut Context<Self>, next: Option<usize>) {
+        let backend = self.backend.clone();
+        self.breaker.call(ctx, move || backend.call_fut(Query).then(|res| match res {
+            Ok(Ok(val)) => Ok(val),
+            _ => Err(()),
+        }))
+            .then(move |res, act: &mut Caller, ctx: &mut Context<Caller>| {
+                act.results.lock().unwrap().push((res, act.breaker.state()));
+                if let Some(next) = next {
+                    ctx.notify(Step(next));
+                }
+                actix::fut::ok(())
+            })
+            .spawn(ctx);
+    }
+}
+
+impl Handler<Step> for Caller {
+    type Result = ();
+
+    fn handle(&mut self, msg: Step, ctx: &mut Context<Self>) {
+        match msg.0 {
+            // failures open breaker
+            n @ 0...3 => self.query(ctx, Some(n + 1)),
+            // backend recovers, wait for cooldown
+            4 => {
+                self.healthy.store(true, Ordering::SeqCst);
+                ctx.run_later(Duration::from_millis(150), |_, ctx| ctx.notify(Step(5)));
+            }
+            // probe, second request fails fast while probe is in flight
+            5 => {
+                assert_eq!(self.breaker.state(), BreakerState::HalfOpen);
+                self.query(ctx, Some(6));
+                self.query(ctx, None);
+            }
+            6 => self.query(ctx, Some(7)),
+            _ => Arbiter::system().do_send(SystemExit(0)),
+        }
+    }
+}
+
+#[test]
+fn test_circuit_breaker() {
+    let sys = System::new("test");
+    let healthy = Arc::new(AtomicBool::new(false));
+    let hits = Arc::new(AtomicUsize::new(0));
+    let results: Results = Arc::default();
+    let transitions = Rc::new(RefCell::new(Vec::new()));
+
+    let backend: Address<_> = Backend{healthy: Arc::clone(&healthy), hits: Arc::clone(&hits)}.start();
+    let trans = Rc::clone(&transitions);
+    let res = Arc::clone(&results);
+    let _: () = Caller::create(move |_| Caller {
+        backend: backend,
+        breaker: CircuitBreaker::new(3, Duration::from_millis(100))
+            .on_transition(move |from, to| trans.borrow_mut().push((from, to))),
+        healthy: healthy,
+        results: res,
+    });
+    sys.run();
+
+    let results = results.lock().unwrap();
+    assert_eq!(*results, vec![
+        (Err(CircuitError::Failed(())), BreakerState::Closed),
+        (Err(CircuitError::Failed(())), BreakerState::Closed),
+        (Err(CircuitError::Failed(())), BreakerState::Open),
+        (Err(CircuitError::CircuitOpen), BreakerState::Open),
+        (Err(CircuitError::CircuitOpen), BreakerState::HalfOpen),
+        (Ok(42), BreakerState::Closed),
+        (Ok(42), BreakerState::Closed),
+    ]);
+    // open breaker does not touch backend
+    assert_eq!(hits.load(Ordering::SeqCst), 5);
+    assert_eq!(*transitions.borrow(), vec![
+        (BreakerState::Closed, BreakerState::Open),
+        (BreakerState::Open, BreakerState::HalfOpen),
+        (BreakerState::HalfOpen, BreakerState::Closed),
+    ]);
+}
+
+struct Timeouts {
+    backend: Address<Backend>,
+    breaker: CircuitBreaker,
+    results: Arc<Mutex<Vec<Result<(), CircuitError<MailboxError>>>>>,
+}
+
+impl Actor for Timeouts {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        let backend = self.backend.clone();
+        self.breaker.call(ctx, move || Future::map(backend.call_fut(Hang), |_| ()))
+            .then(|res, act: &mut Timeouts, ctx: &mut Context<Timeouts>| {
+                act.results.lock().unwrap().push(res);
+                let backend = act.backend.clone();
+                act.breaker.call(ctx, move || Future::map(backend.call_fut(Hang), |_| ()))
+            })
+            .then(|res, act: &mut Timeouts, _: &mut Context<Timeouts>| {
+                act.results.lock().unwrap().push(res);
+                Arbiter::system().do_send(SystemExit(0));
+                actix::fut::ok(())
+            })
+            .wait(ctx);
+    }
+}
+
+#[test]
+fn test_circuit_breaker_timeout() {
+    let sys = System::new("test");
+    let backend: Address<_> = Backend{healthy: Arc::default(), hits: Arc::default()}.start();
+    let results = Arc::new(Mutex::new(Vec::new()));
+    let _: Address<_> = Timeouts {
+        backend: backend,
+        breaker: CircuitBreaker::new(1, Duration::from_secs(10)).timeout(Duration::from_millis(50)),
+        results: Arc::clone(&results),
+    }.start();
+    sys.run();
+
+    assert_eq!(*results.lock().unwrap(), vec![Err(CircuitError::Timeout),
+                                              Err(CircuitError::CircuitOpen)]);
+}
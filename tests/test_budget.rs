@@ -0,0 +1,132 @@
+extern crate actix;
+extern crate futures;
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use futures::{Async, Poll, Stream};
+use actix::prelude::*;
+
+const BUDGET: usize = 10;
+
+/// Stream that always has next item
+struct Forever;
+
+impl Stream for Forever {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<()>, ()> {
+        Ok(Async::Ready(Some(())))
+    }
+}
+
+struct Tick;
+
+impl ResponseType for Tick {
+    type Item = ();
+    type Error = ();
+}
+
+/// Records order of handled items, `m` for messages and `s` for stream items
+struct Fair {
+    log: Arc<Mutex<Vec<char>>>,
+    expected: usize,
+}
+
+impl Actor for Fair {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.set_poll_budget(BUDGET);
+        Fair::add_stream(Forever, ctx);
+    }
+}
+
+impl StreamHandler<(), ()> for Fair {
+    fn handle(&mut self, _: (), _: &mut Context<Self>) {
+        self.log.lock().unwrap().push('s');
+    }
+}
+
+impl Handler<Tick> for Fair {
+    type Result = ();
+
+    fn handle(&mut self, _: Tick, _: &mut Context<Self>) {
+        let mut log = self.log.lock().unwrap();
+        log.push('m');
+        if log.iter().filter(|c| **c == 'm').count() == self.expected {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        }
+    }
+}
+
+/// Longest run of `c` in the log
+fn longest_run(log: &[char], c: char) -> usize {
+    let (mut longest, mut run) = (0, 0);
+    for item in log {
+        if *item == c {
+            run += 1;
+            if run > longest {
+                longest = run;
+            }
+        } else {
+            run = 0;
+        }
+    }
+    longest
+}
+
+#[test]
+fn test_budget_busy_stream_and_mailbox() {
+    let sys = System::new("test");
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: Address<_> = Fair::build().mailbox_capacity(64)
+        .start(Fair{log: Arc::clone(&log), expected: 40});
+    for _ in 0..40 {
+        addr.do_send(Tick);
+    }
+
+    sys.run();
+
+    // while mailbox has messages both sources make progress in every poll,
+    // each one uses its half of the budget
+    let log = log.lock().unwrap();
+    let last = log.iter().rposition(|c| *c == 'm').unwrap();
+    let log = &log[..last+1];
+    assert_eq!(log.iter().filter(|c| **c == 'm').count(), 40);
+    assert!(log.iter().filter(|c| **c == 's').count() >= 35);
+    assert!(longest_run(log, 'm') <= BUDGET / 2, "{:?}", log);
+    assert!(longest_run(log, 's') <= BUDGET / 2, "{:?}", log);
+}
+
+struct Sender(Address<Fair>);
+
+impl Actor for Sender {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.run_interval_at(
+            Instant::now(), Duration::from_millis(5), |act, _| act.0.do_send(Tick));
+    }
+}
+
+#[test]
+fn test_budget_trickle_of_messages() {
+    let sys = System::new("test");
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: Address<_> = Fair{log: Arc::clone(&log), expected: 10}.start();
+    let _: Address<_> = Sender(addr).start();
+
+    sys.run();
+
+    // every message got handled in time, stream kept running between messages
+    let log = log.lock().unwrap();
+    assert_eq!(log.iter().filter(|c| **c == 'm').count(), 10);
+    let pos: Vec<_> = log.iter().enumerate()
+        .filter(|&(_, c)| *c == 'm').map(|(idx, _)| idx).collect();
+    for win in pos.windows(2) {
+        assert!(win[1] - win[0] > 1, "stream is starved");
+    }
+}
@@ -0,0 +1,223 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use futures::Future;
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+
+struct Num(usize);
+
+impl ResponseType for Num {
+    type Item = ();
+    type Error = ();
+}
+
+struct MyActor {
+    msgs: Arc<Mutex<Vec<usize>>>,
+}
+
+impl Actor for MyActor {
+    type Context = Context<Self>;
+}
+
+impl Handler<Num> for MyActor {
+    type Result = ();
+
+    fn handle(&mut self, msg: Num, _: &mut Context<MyActor>) {
+        self.msgs.lock().unwrap().push(msg.0);
+        if msg.0 == 3 {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        }
+    }
+}
+
+#[test]
+fn test_builder_capacity() {
+    let sys = System::new("test");
+    let msgs = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: Address<_> = MyActor::build()
+        .mailbox_capacity(2)
+        .start(MyActor{msgs: Arc::clone(&msgs)});
+
+    assert!(addr.try_send(Num(1)).is_ok());
+    assert!(addr.try_send(Num(2)).is_ok());
+    assert!(addr.try_send(Num(3)).is_err());
+    addr.do_send(Num(3));
+
+    sys.run();
+    assert_eq!(*msgs.lock().unwrap(), vec![1, 2, 3]);
+}
+
+#[test]
+fn test_builder_overflow_drop_oldest() {
+    let sys = System::new("test");
+    let msgs = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: Address<_> = MyActor::build()
+        .mailbox_capacity(2)
+        .overflow(Overflow::DropOldest)
+        .start(MyActor{msgs: Arc::clone(&msgs)});
+
+    assert!(addr.try_send(Num(0)).is_ok());
+    assert!(addr.try_send(Num(1)).is_ok());
+    assert!(addr.try_send(Num(2)).is_ok());
+    assert!(addr.try_send(Num(3)).is_ok());
+
+    sys.run();
+    assert_eq!(*msgs.lock().unwrap(), vec![2, 3]);
+}
+
+#[test]
+fn test_builder_overflow_reject() {
+    let sys = System::new("test");
+    let msgs = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: Address<_> = MyActor::build()
+        .mailbox_capacity(2)
+        .overflow(Overflow::Reject)
+        .start(MyActor{msgs: Arc::clone(&msgs)});
+    let sync: SyncAddress<_> = MyActor::build()
+        .mailbox_capacity(1)
+        .overflow(Overflow::Reject)
+        .start(MyActor{msgs: Arc::clone(&msgs)});
+
+    assert!(addr.try_send(Num(1)).is_ok());
+    assert!(addr.try_send(Num(2)).is_ok());
+    assert!(sync.try_send(Num(20)).is_ok());
+
+    // senders do not wait for room
+    addr.do_send(Num(10));
+    assert_eq!(addr.send(Num(11)).wait(), Err(MailboxError::Full));
+    assert_eq!(addr.call_fut(Num(12)).wait().unwrap_err(), MailboxError::Full);
+    assert_eq!(addr.send_confirmed(Num(13)).wait(), Err(MailboxError::Full));
+    sync.do_send(Num(21));
+    assert_eq!(sync.send(Num(22)).wait(), Err(MailboxError::Full));
+    assert_eq!(sync.call_fut(Num(23)).wait().unwrap_err(), MailboxError::Full);
+
+    // mailbox accepts messages once drained
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            .then(move |_| addr.send(Num(4)).join(sync.call_fut(Num(24))))
+            .then(|res| {
+                assert!(res.is_ok());
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+    let mut msgs = msgs.lock().unwrap().clone();
+    msgs.sort();
+    assert_eq!(msgs, vec![1, 2, 4, 20, 24]);
+}
+
+#[test]
+fn test_system_config_capacity() {
+    let sys = System::with_config(actix::SystemConfig::new("test").mailbox_capacity(4));
+    let msgs = Arc::new(Mutex::new(Vec::new()));
+
+    // mailbox capacity of the system
+    let addr: Address<_> = MyActor{msgs: Arc::clone(&msgs)}.start();
+    for i in 0..4 {
+        assert!(addr.try_send(Num(10 + i)).is_ok());
+    }
+    assert!(addr.try_send(Num(14)).is_err());
+
+    // builder overrides system defaults
+    let addr: Address<_> = MyActor::build()
+        .mailbox_capacity(16)
+        .start(MyActor{msgs: Arc::clone(&msgs)});
+    for i in 0..4 {
+        assert!(addr.try_send(Num(20 + i)).is_ok());
+    }
+    assert!(addr.try_send(Num(24)).is_ok());
+    addr.do_send(Num(3));
+
+    sys.run();
+    assert_eq!(msgs.lock().unwrap().len(), 10);
+}
+
+struct NameActor(Arc<Mutex<Option<String>>>);
+
+impl Actor for NameActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        *self.0.lock().unwrap() = ctx.name().map(|s| s.to_owned());
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+    }
+}
+
+#[test]
+fn test_builder_name() {
+    let sys = System::new("test");
+    let name = Arc::new(Mutex::new(None));
+
+    let _: () = NameActor::build()
+        .name("session-42")
+        .start(NameActor(Arc::clone(&name)));
+
+    sys.run();
+    assert_eq!(*name.lock().unwrap(), Some("session-42".to_owned()));
+}
+
+struct ArbiterActor(Arc<Mutex<Option<String>>>);
+
+impl Actor for ArbiterActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _: &mut Context<Self>) {
+        *self.0.lock().unwrap() = Some(Arbiter::name());
+        Arbiter::system().do_send(actix::msgs::SystemExit(0));
+    }
+}
+
+#[test]
+fn test_builder_on_arbiter() {
+    let sys = System::new("test");
+    let name = Arc::new(Mutex::new(None));
+    let arbiter = Arbiter::new("builder-arbiter");
+
+    let act_name = Arc::clone(&name);
+    let _addr = ArbiterActor::build()
+        .on_arbiter(&arbiter)
+        .start_with(move |_| ArbiterActor(act_name));
+
+    sys.run();
+    assert!(name.lock().unwrap().as_ref().unwrap().contains("builder-arbiter"));
+}
+
+struct KeepAlive(Arc<AtomicUsize>);
+
+impl Actor for KeepAlive {
+    type Context = Context<Self>;
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[test]
+fn test_builder_stop_on_disconnect() {
+    let sys = System::new("test");
+    let stopped = Arc::new(AtomicUsize::new(0));
+
+    let _: () = KeepAlive::build()
+        .stop_on_disconnect(false)
+        .start(KeepAlive(Arc::clone(&stopped)));
+    let _: () = KeepAlive::build()
+        .start(KeepAlive(Arc::clone(&stopped)));
+
+    Arbiter::handle().spawn(
+        futures::future::ok::<(), ()>(()).then(|_| {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+    assert_eq!(stopped.load(Ordering::Relaxed), 1);
+}
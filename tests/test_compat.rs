@@ -0,0 +1,141 @@
+#![cfg(feature="compat")]
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::pin::Pin;
+use std::sync::{Arc, Mutex};
+use std::task::{Context as StdContext, Poll as StdPoll};
+use std::future::Future as StdFuture;
+use std::time::Duration;
+
+use futures::Future;
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::dev::LocalFutRequest;
+use actix::compat::{future01, Compat, CompatExt};
+
+struct Double(usize);
+
+impl ResponseType for Double {
+    type Item = usize;
+    type Error = ();
+}
+
+struct Calc;
+
+impl Actor for Calc {
+    type Context = Context<Self>;
+}
+
+impl Handler<Double> for Calc {
+    type Result = ResponseFuture<Double>;
+
+    fn handle(&mut self, msg: Double, _: &mut Context<Self>) -> Self::Result {
+        // response is ready after timeout
+        Box::new(
+            Timeout::new(Duration::new(0, 1_000), Arbiter::handle()).unwrap()
+                .map(move |_| msg.0 * 2)
+                .map_err(|_| ()))
+    }
+}
+
+/// Hand written `std::future::Future`, calls actor twice
+struct Caller {
+    addr: Address<Calc>,
+    fut: Compat<LocalFutRequest<Calc, Double>>,
+    first: Option<usize>,
+}
+
+impl StdFuture for Caller {
+    type Output = Result<(usize, usize), ()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut StdContext) -> StdPoll<Self::Output> {
+        loop {
+            let res = match Pin::new(&mut self.fut).poll(cx) {
+                StdPoll::Ready(Ok(Ok(res))) => res,
+                StdPoll::Ready(_) => return StdPoll::Ready(Err(())),
+                StdPoll::Pending => return StdPoll::Pending,
+            };
+            if let Some(first) = self.first {
+                return StdPoll::Ready(Ok((first, res)))
+            }
+            self.first = Some(res);
+            self.fut = self.addr.call_fut(Double(res)).compat();
+        }
+    }
+}
+
+#[test]
+fn test_compat_call_fut() {
+    let sys = System::new("test");
+    let addr: Address<_> = Calc.start();
+    let result = Arc::new(Mutex::new(None));
+
+    let res = Arc::clone(&result);
+    let caller = Caller{fut: addr.call_fut(Double(2)).compat(), addr: addr, first: None};
+    Arbiter::handle().spawn(
+        future01(caller).then(move |r| {
+            *res.lock().unwrap() = Some(r);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+    assert_eq!(*result.lock().unwrap(), Some(Ok((4, 8))));
+}
+
+/// `std::future::Future` which is not ready on first poll
+struct YieldOnce(Option<usize>, bool);
+
+impl StdFuture for YieldOnce {
+    type Output = Result<usize, ()>;
+
+    fn poll(mut self: Pin<&mut Self>, cx: &mut StdContext) -> StdPoll<Self::Output> {
+        if !self.1 {
+            self.1 = true;
+            cx.waker().wake_by_ref();
+            StdPoll::Pending
+        } else {
+            StdPoll::Ready(Ok(self.0.take().unwrap()))
+        }
+    }
+}
+
+struct Spawn(usize);
+
+impl ResponseType for Spawn {
+    type Item = ();
+    type Error = ();
+}
+
+struct Spawner(Arc<Mutex<Option<usize>>>);
+
+impl Actor for Spawner {
+    type Context = Context<Self>;
+}
+
+impl Handler<Spawn> for Spawner {
+    type Result = ();
+
+    fn handle(&mut self, msg: Spawn, ctx: &mut Context<Self>) {
+        actix::fut::wrap_future::<_, Self>(future01(YieldOnce(Some(msg.0 * 2), false)))
+            .map(|res, act, _| {
+                *act.0.lock().unwrap() = Some(res);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            })
+            .spawn(ctx);
+    }
+}
+
+#[test]
+fn test_compat_spawn_in_handler() {
+    let sys = System::new("test");
+    let result = Arc::new(Mutex::new(None));
+
+    let addr: Address<_> = Spawner(Arc::clone(&result)).start();
+    addr.do_send(Spawn(21));
+
+    sys.run();
+    assert_eq!(*result.lock().unwrap(), Some(42));
+}
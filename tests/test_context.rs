@@ -3,10 +3,11 @@
 extern crate futures;
 extern crate tokio_core;
 
-use std::time::Duration;
-use std::sync::Arc;
+use std::time::{Duration, Instant};
+use std::thread;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use futures::{future, Future};
+use futures::{future, Future, Poll, Stream};
 use futures::stream::once;
 use futures::unsync::mpsc::unbounded;
 use tokio_core::reactor::Timeout;
@@ -20,6 +21,7 @@ enum Op {
     TimeoutStop,
     RunAfter,
     RunAfterStop,
+    RunAfterCancel,
 }
 
 struct MyActor{op: Op}
@@ -42,7 +44,7 @@ impl Actor for MyActor {
             },
             Op::RunAfter => {
                 ctx.run_later(Duration::new(0, 100), |_, _| {
-                    Arbiter::system().send(SystemExit(0));
+                    Arbiter::system().do_send(SystemExit(0));
                 });
             }
             Op::RunAfterStop => {
@@ -51,11 +53,20 @@ impl Actor for MyActor {
                 });
                 ctx.stop();
             }
+            Op::RunAfterCancel => {
+                let handle = ctx.run_later(Duration::from_millis(10), |_, _| {
+                    panic!("cancelled timer fired");
+                });
+                assert!(ctx.cancel_future(handle));
+                ctx.run_later(Duration::from_millis(50), |_, _| {
+                    Arbiter::system().do_send(SystemExit(0));
+                });
+            }
         }
     }
 
     fn stopped(&mut self, _: &mut Context<MyActor>) {
-        Arbiter::system().send(SystemExit(0));
+        Arbiter::system().do_send(SystemExit(0));
     }
 }
 
@@ -69,7 +80,7 @@ impl Handler<TimeoutMessage> for MyActor {
         if self.op != Op::Timeout {
             assert!(false, "should not happen {:?}", self.op);
         }
-        Arbiter::system().send(SystemExit(0));
+        Arbiter::system().do_send(SystemExit(0));
     }
 }
 
@@ -92,7 +103,7 @@ fn test_add_timeout_cancel() {
     Arbiter::handle().spawn(
         Timeout::new(Duration::new(0, 1000), Arbiter::handle()).unwrap()
             .then(|_| {
-                Arbiter::system().send(SystemExit(0));
+                Arbiter::system().do_send(SystemExit(0));
                 future::result(Ok(()))
             })
     );
@@ -128,6 +139,15 @@ fn test_run_after_stop() {
     sys.run();
 }
 
+#[test]
+fn test_run_after_cancel() {
+    let sys = System::new("test");
+
+    let _addr: Address<_> = MyActor{op: Op::RunAfterCancel}.start();
+
+    sys.run();
+}
+
 
 struct ContextWait {cnt: Arc<AtomicUsize>}
 
@@ -150,7 +170,7 @@ impl Handler<Ping> for ContextWait {
             .into_actor(self)
             .wait(ctx);
 
-        Arbiter::system().send(SystemExit(0));
+        Arbiter::system().do_send(SystemExit(0));
     }
 }
 
@@ -160,9 +180,9 @@ fn test_wait_context() {
 
     let m = Arc::new(AtomicUsize::new(0));
     let addr: Address<_> = ContextWait{cnt: Arc::clone(&m)}.start();
-    addr.send(Ping);
-    addr.send(Ping);
-    addr.send(Ping);
+    addr.do_send(Ping);
+    addr.do_send(Ping);
+    addr.do_send(Ping);
 
     sys.run();
 
@@ -189,6 +209,81 @@ fn test_message_stream_wait_context() {
     assert_eq!(m.load(Ordering::Relaxed), 1);
 }
 
+/// Stream wrapper, counts number of polls
+struct Counting<S> {
+    stream: S,
+    polls: Arc<AtomicUsize>,
+}
+
+impl<S: Stream> Stream for Counting<S> {
+    type Item = S::Item;
+    type Error = S::Error;
+
+    fn poll(&mut self) -> Poll<Option<S::Item>, S::Error> {
+        self.polls.fetch_add(1, Ordering::Relaxed);
+        self.stream.poll()
+    }
+}
+
+struct StreamWait {
+    items: usize,
+    polls: Arc<AtomicUsize>,
+    seen: Arc<Mutex<Vec<usize>>>,
+}
+
+impl StreamWait {
+    fn wait(&mut self, ctx: &mut Context<Self>) {
+        Timeout::new(Duration::from_millis(20), Arbiter::handle()).unwrap()
+            .into_actor(self)
+            .then(|_, act: &mut StreamWait, _: &mut _| {
+                // stream is not polled while context is waiting
+                act.seen.lock().unwrap().push(act.polls.load(Ordering::Relaxed));
+                actix::fut::ok(())
+            })
+            .wait(ctx);
+    }
+}
+
+impl Actor for StreamWait {
+    type Context = actix::Context<Self>;
+}
+
+impl StreamHandler<Ping, ()> for StreamWait {
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.wait(ctx);
+    }
+
+    fn handle(&mut self, _: Ping, ctx: &mut Self::Context) {
+        self.items += 1;
+        if self.items == 1 {
+            self.wait(ctx);
+        } else {
+            Arbiter::system().do_send(SystemExit(0));
+        }
+    }
+}
+
+#[test]
+fn test_stream_wait_no_poll() {
+    let sys = System::new("test");
+
+    let polls = Arc::new(AtomicUsize::new(0));
+    let seen = Arc::new(Mutex::new(Vec::new()));
+    let (polls2, seen2) = (Arc::clone(&polls), Arc::clone(&seen));
+    let _addr: Address<_> = StreamWait::create(move |ctx| {
+        let (tx, rx) = unbounded();
+        let _ = tx.unbounded_send(Ping);
+        let _ = tx.unbounded_send(Ping);
+        StreamWait::add_stream(Counting{stream: rx, polls: Arc::clone(&polls2)}, ctx);
+        StreamWait{items: 0, polls: polls2, seen: seen2}
+    });
+    sys.run();
+
+    assert_eq!(*seen.lock().unwrap(), vec![0, 1]);
+    assert!(polls.load(Ordering::Relaxed) >= 2);
+}
+
 #[test]
 fn test_stream_wait_context() {
     let sys = System::new("test");
@@ -222,7 +317,7 @@ impl Handler<Ping> for ContextNoWait {
         let cnt = self.cnt.load(Ordering::Relaxed);
         self.cnt.store(cnt+1, Ordering::Relaxed);
 
-        Arbiter::system().send(SystemExit(0));
+        Arbiter::system().do_send(SystemExit(0));
     }
 }
 
@@ -233,7 +328,7 @@ impl Handler<Result<Ping, ()>> for ContextNoWait {
         let cnt = self.cnt.load(Ordering::Relaxed);
         self.cnt.store(cnt+1, Ordering::Relaxed);
 
-        Arbiter::system().send(SystemExit(0));
+        Arbiter::system().do_send(SystemExit(0));
     }
 }
 
@@ -243,9 +338,9 @@ fn test_nowait_context() {
 
     let m = Arc::new(AtomicUsize::new(0));
     let addr: Address<_> = ContextNoWait{cnt: Arc::clone(&m)}.start();
-    addr.send(Ping);
-    addr.send(Ping);
-    addr.send(Ping);
+    addr.do_send(Ping);
+    addr.do_send(Ping);
+    addr.do_send(Ping);
     sys.run();
 
     assert_eq!(m.load(Ordering::Relaxed), 3);
@@ -301,7 +396,7 @@ impl StreamHandler<Ping, ()> for ContextHandle {
 
     fn handle(&mut self, _: Ping, ctx: &mut Self::Context) {
         self.h.store(ctx.handle().into_usize(), Ordering::Relaxed);
-        Arbiter::system().send(SystemExit(0));
+        Arbiter::system().do_send(SystemExit(0));
     }
 }
 
@@ -325,3 +420,1179 @@ fn test_current_context_handle() {
 
     assert_eq!(m.load(Ordering::Relaxed), h.load(Ordering::Relaxed));
 }
+
+struct LimitActor {
+    running: usize,
+    max_running: usize,
+    done: Vec<usize>,
+    result: Arc<Mutex<(usize, Vec<usize>)>>,
+}
+
+impl Actor for LimitActor {
+    type Context = actix::Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<LimitActor>) {
+        ctx.set_max_spawned(2);
+
+        for i in 0..5 {
+            ctx.spawn(actix::fut::ok(()).then(move |_: Result<(), ()>, act: &mut LimitActor, _: &mut _| {
+                act.running += 1;
+                act.max_running = std::cmp::max(act.running, act.max_running);
+
+                Timeout::new(Duration::from_millis(10), Arbiter::handle()).unwrap()
+                    .actfuture()
+                    .then(move |_, act: &mut LimitActor, _: &mut _| {
+                        act.running -= 1;
+                        act.done.push(i);
+                        if act.done.len() == 5 {
+                            *act.result.lock().unwrap() = (act.max_running, act.done.clone());
+                            Arbiter::system().do_send(SystemExit(0));
+                        }
+                        actix::fut::ok(())
+                    })
+            }));
+        }
+        assert_eq!(ctx.spawned_count(), 2);
+        assert_eq!(ctx.overflow_count(), 3);
+
+        ctx.spawn_urgent(actix::fut::ok(()));
+        assert_eq!(ctx.spawned_count(), 3);
+        assert_eq!(ctx.overflow_count(), 3);
+    }
+}
+
+#[test]
+fn test_max_spawned() {
+    let sys = System::new("test");
+    let result = Arc::new(Mutex::new((0, Vec::new())));
+
+    let _addr: Address<_> = LimitActor{
+        running: 0, max_running: 0, done: Vec::new(), result: Arc::clone(&result)}.start();
+    sys.run();
+
+    let result = result.lock().unwrap();
+    assert_eq!(result.0, 2);
+    assert_eq!(result.1, vec![0, 1, 2, 3, 4]);
+}
+
+struct SaturatedActor {
+    events: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Actor for SaturatedActor {
+    type Context = actix::Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.set_max_spawned(1);
+        ctx.spawn(actix::fut::wrap_future(future::empty()));
+        ctx.spawn(actix::fut::ok(()));
+
+        ctx.run_later(Duration::from_millis(10), |act, ctx| {
+            act.events.lock().unwrap().push("timer");
+            assert_eq!(ctx.spawned_count(), 1);
+            assert_eq!(ctx.overflow_count(), 1);
+            Arbiter::system().do_send(SystemExit(0));
+        });
+        Self::add_stream(once::<Ping, ()>(Ok(Ping)), ctx);
+
+        assert_eq!(ctx.spawned_count(), 1);
+        assert_eq!(ctx.overflow_count(), 1);
+    }
+}
+
+impl StreamHandler<Ping, ()> for SaturatedActor {
+    fn handle(&mut self, _: Ping, _: &mut Self::Context) {
+        self.events.lock().unwrap().push("stream");
+    }
+}
+
+#[test]
+fn test_max_spawned_ignores_internal_futures() {
+    let sys = System::new("test");
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let _addr: Address<_> = SaturatedActor{events: Arc::clone(&events)}.start();
+    sys.run();
+
+    assert_eq!(*events.lock().unwrap(), vec!["stream", "timer"]);
+}
+
+#[derive(Message, Clone)]
+struct Tick;
+
+struct IntervalActor {
+    ticks: Arc<AtomicUsize>,
+    stop: bool,
+    handle: SpawnHandle,
+}
+
+impl Actor for IntervalActor {
+    type Context = actix::Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.handle = ctx.notify_interval(Tick, Duration::from_millis(10));
+    }
+}
+
+impl Handler<Tick> for IntervalActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Tick, ctx: &mut Self::Context) {
+        if self.ticks.fetch_add(1, Ordering::Relaxed) + 1 == 3 {
+            if self.stop {
+                ctx.stop();
+            } else {
+                ctx.cancel_future(self.handle);
+            }
+        }
+    }
+}
+
+fn run_interval(stop: bool) -> usize {
+    let sys = System::new("test");
+    let ticks = Arc::new(AtomicUsize::new(0));
+
+    let addr: Address<_> = IntervalActor{
+        ticks: Arc::clone(&ticks), stop: stop, handle: SpawnHandle::default()}.start();
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(150), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                drop(addr);
+                Arbiter::system().do_send(SystemExit(0));
+                future::result(Ok(()))
+            }));
+    sys.run();
+
+    ticks.load(Ordering::Relaxed)
+}
+
+#[test]
+fn test_notify_interval_cancel() {
+    assert_eq!(run_interval(false), 3);
+}
+
+#[test]
+fn test_notify_interval_stop() {
+    assert_eq!(run_interval(true), 3);
+}
+
+struct AlignedActor {
+    start: Instant,
+    ticks: Arc<Mutex<Vec<Instant>>>,
+}
+
+impl Actor for AlignedActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        let ticks = Arc::clone(&self.ticks);
+        ctx.run_interval_at(self.start, Duration::from_millis(50), move |_, ctx| {
+            let mut ticks = ticks.lock().unwrap();
+            ticks.push(Instant::now());
+            if ticks.len() == 4 {
+                ctx.stop();
+                Arbiter::system().do_send(SystemExit(0));
+            }
+            // handler consumes most of an interval
+            thread::sleep(Duration::from_millis(40));
+        });
+    }
+}
+
+#[test]
+fn test_run_interval_at() {
+    let sys = System::new("test");
+    let ticks = Arc::new(Mutex::new(Vec::new()));
+
+    // start is in the past, first tick fires at next aligned boundary
+    let start = Instant::now() - Duration::from_millis(30);
+    let _: () = AlignedActor{start: start, ticks: Arc::clone(&ticks)}.start();
+    sys.run();
+
+    let ticks = ticks.lock().unwrap();
+    assert_eq!(ticks.len(), 4);
+    for (n, tick) in ticks.iter().enumerate() {
+        let aligned = start + Duration::from_millis(50 * (n as u64 + 1));
+        assert!(*tick >= aligned);
+        assert!(*tick - aligned < Duration::from_millis(25));
+    }
+}
+
+struct TimerActor {
+    handle: SpawnHandle,
+    ticks: Arc<Mutex<Vec<Instant>>>,
+}
+
+impl Actor for TimerActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.handle = ctx.run_interval(Duration::from_millis(20), |act, ctx| {
+            let mut ticks = act.ticks.lock().unwrap();
+            ticks.push(Instant::now());
+            if ticks.len() == 3 {
+                // closure may cancel its own interval
+                assert!(ctx.cancel_future(act.handle));
+                ctx.run_later(Duration::from_millis(60), |_, _| {
+                    Arbiter::system().do_send(SystemExit(0));
+                });
+            }
+        });
+    }
+}
+
+#[test]
+fn test_run_interval() {
+    let sys = System::new("test");
+    let ticks = Arc::new(Mutex::new(Vec::new()));
+
+    let start = Instant::now();
+    let _: () = TimerActor{handle: SpawnHandle::default(),
+                              ticks: Arc::clone(&ticks)}.start();
+    sys.run();
+
+    let ticks = ticks.lock().unwrap();
+    assert_eq!(ticks.len(), 3);
+    for (n, tick) in ticks.iter().enumerate() {
+        assert!(*tick >= start + Duration::from_millis(20 * (n as u64 + 1)));
+    }
+}
+
+struct Exclusive(usize);
+
+impl ResponseType for Exclusive {
+    type Item = ();
+    type Error = ();
+}
+
+struct Plain(usize);
+
+impl ResponseType for Plain {
+    type Item = ();
+    type Error = ();
+}
+
+struct OrderActor {
+    log: Arc<Mutex<Vec<String>>>,
+    wait: bool,
+}
+
+impl OrderActor {
+    fn delay(&mut self, n: usize, ms: u64)
+             -> Box<ActorFuture<Item=(), Error=(), Actor=OrderActor>>
+    {
+        self.log.lock().unwrap().push(format!("start {}", n));
+        Box::new(
+            Timeout::new(Duration::from_millis(ms), Arbiter::handle()).unwrap()
+                .into_actor(self)
+                .then(move |_, act: &mut OrderActor, _: &mut _| {
+                    act.log.lock().unwrap().push(format!("end {}", n));
+                    actix::fut::ok(())
+                }))
+    }
+}
+
+impl Actor for OrderActor {
+    type Context = actix::Context<Self>;
+}
+
+impl Handler<Exclusive> for OrderActor {
+    type Result = ExclusiveResponse<Self, Exclusive>;
+
+    fn handle(&mut self, msg: Exclusive, ctx: &mut Self::Context) -> Self::Result {
+        if msg.0 == 1 {
+            // spawned futures keep running
+            ctx.run_later(Duration::from_millis(5), |act, _| {
+                act.log.lock().unwrap().push("timer".to_owned());
+            });
+            // explicit wait takes precedence over exclusive response
+            if self.wait {
+                Timeout::new(Duration::from_millis(30), Arbiter::handle()).unwrap()
+                    .into_actor(self)
+                    .then(|_, act: &mut OrderActor, _: &mut _| {
+                        act.log.lock().unwrap().push("wait".to_owned());
+                        actix::fut::ok(())
+                    })
+                    .wait(ctx);
+            }
+        }
+        ExclusiveResponse::new(self.delay(msg.0, 20))
+    }
+}
+
+impl Handler<Plain> for OrderActor {
+    type Result = ResponseActFuture<Self, Plain>;
+
+    fn handle(&mut self, msg: Plain, _: &mut Self::Context) -> Self::Result {
+        self.delay(msg.0, 20)
+    }
+}
+
+fn run_order<M>(wait: bool, m1: M, m2: M) -> Vec<String>
+    where OrderActor: Handler<M>, M: ResponseType<Item=(), Error=()> + 'static
+{
+    let sys = System::new("test");
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: Address<_> = OrderActor{log: Arc::clone(&log), wait: wait}.start();
+    let res = addr.call_fut(m1).join(addr.call_fut(m2));
+    Arbiter::handle().spawn(res.then(|res| {
+        assert!(res.is_ok());
+        Arbiter::system().do_send(SystemExit(0));
+        future::result(Ok(()))
+    }));
+    sys.run();
+
+    let log = log.lock().unwrap();
+    log.clone()
+}
+
+#[test]
+fn test_exclusive_response() {
+    assert_eq!(run_order(false, Exclusive(1), Exclusive(2)),
+               vec!["start 1", "timer", "end 1", "start 2", "end 2"]);
+}
+
+#[test]
+fn test_exclusive_response_wait() {
+    assert_eq!(run_order(true, Exclusive(1), Exclusive(2)),
+               vec!["start 1", "wait", "timer", "end 1", "start 2", "end 2"]);
+}
+
+#[test]
+fn test_plain_async_response() {
+    assert_eq!(run_order(false, Plain(1), Plain(2)),
+               vec!["start 1", "start 2", "end 1", "end 2"]);
+}
+
+/// Future holds a guard, it resolves on first poll if `ready` is set
+struct GuardFuture {
+    ready: bool,
+    cancelled: Arc<AtomicUsize>,
+}
+
+impl ActorFuture for GuardFuture {
+    type Item = ();
+    type Error = ();
+    type Actor = CancelActor;
+
+    fn poll(&mut self, _: &mut CancelActor, _: &mut Context<CancelActor>) -> Poll<(), ()> {
+        if self.ready {
+            Ok(futures::Async::Ready(()))
+        } else {
+            Ok(futures::Async::NotReady)
+        }
+    }
+
+    fn cancelled(&mut self, act: &mut CancelActor, _: &mut Context<CancelActor>) {
+        assert!(!act.stopped);
+        self.cancelled.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Message)]
+struct Stop;
+
+struct CancelActor {
+    stopped: bool,
+    pending: Arc<AtomicUsize>,
+    completed: Arc<AtomicUsize>,
+}
+
+impl Actor for CancelActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.spawn(GuardFuture{ready: false, cancelled: Arc::clone(&self.pending)});
+        ctx.spawn(GuardFuture{ready: true, cancelled: Arc::clone(&self.completed)});
+        GuardFuture{ready: false, cancelled: Arc::clone(&self.pending)}
+            .map(|_, _, _| ())
+            .then(|_, _, _| actix::fut::ok(()))
+            .timeout(Duration::new(10, 0), ())
+            .spawn(ctx);
+        ctx.notify_later(Stop, Duration::new(0, 1_000));
+    }
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        self.stopped = true;
+        Arbiter::system().do_send(SystemExit(0));
+    }
+}
+
+impl actix::Handler<Stop> for CancelActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Stop, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+#[test]
+fn test_future_cancelled() {
+    let sys = System::new("test");
+    let pending = Arc::new(AtomicUsize::new(0));
+    let completed = Arc::new(AtomicUsize::new(0));
+
+    let _: () = CancelActor{stopped: false,
+                            pending: Arc::clone(&pending),
+                            completed: Arc::clone(&completed)}.start();
+    sys.run();
+
+    assert_eq!(pending.load(Ordering::Relaxed), 2);
+    assert_eq!(completed.load(Ordering::Relaxed), 0);
+}
+
+/// Response future that never resolves, counts `cancelled()` calls
+struct HoldFuture(Arc<AtomicUsize>);
+
+impl ActorFuture for HoldFuture {
+    type Item = ();
+    type Error = ();
+    type Actor = HoldActor;
+
+    fn poll(&mut self, _: &mut HoldActor, _: &mut Context<HoldActor>) -> Poll<(), ()> {
+        Ok(futures::Async::NotReady)
+    }
+
+    fn cancelled(&mut self, _: &mut HoldActor, _: &mut Context<HoldActor>) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+#[derive(Message)]
+struct Hold;
+
+struct HoldActor(Arc<AtomicUsize>);
+
+impl Actor for HoldActor {
+    type Context = Context<Self>;
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        Arbiter::system().do_send(SystemExit(0));
+    }
+}
+
+impl actix::Handler<Hold> for HoldActor {
+    type Result = ResponseActFuture<Self, Hold>;
+
+    fn handle(&mut self, _: Hold, _: &mut Context<Self>) -> Self::Result {
+        Box::new(HoldFuture(Arc::clone(&self.0)))
+    }
+}
+
+impl actix::Handler<Stop> for HoldActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Stop, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+/// Response future built with combinators
+#[derive(Message)]
+struct HoldChain;
+
+impl actix::Handler<HoldChain> for HoldActor {
+    type Result = ResponseActFuture<Self, HoldChain>;
+
+    fn handle(&mut self, _: HoldChain, _: &mut Context<Self>) -> Self::Result {
+        Box::new(HoldFuture(Arc::clone(&self.0))
+                 .map(|_, _, _| ())
+                 .then(|res, _, _| actix::fut::result(res))
+                 .timeout(Duration::new(10, 0), ()))
+    }
+}
+
+/// Response future that folds a stream
+#[derive(Message)]
+struct HoldFold;
+
+impl actix::Handler<HoldFold> for HoldActor {
+    type Result = ResponseActFuture<Self, HoldFold>;
+
+    fn handle(&mut self, _: HoldFold, _: &mut Context<Self>) -> Self::Result {
+        Box::new(actix::fut::wrap_stream(once::<(), ()>(Ok(())))
+                 .fold((), |_, _, act: &mut HoldActor, _| HoldFuture(Arc::clone(&act.0))))
+    }
+}
+
+#[test]
+fn test_response_future_cancelled() {
+    let sys = System::new("test");
+    let cancelled = Arc::new(AtomicUsize::new(0));
+
+    let addr: Address<_> = HoldActor(Arc::clone(&cancelled)).start();
+    addr.do_send(Hold);
+    addr.do_send(Stop);
+    sys.run();
+
+    assert_eq!(cancelled.load(Ordering::Relaxed), 1);
+}
+
+#[test]
+fn test_response_future_combinators_cancelled() {
+    let sys = System::new("test");
+    let cancelled = Arc::new(AtomicUsize::new(0));
+
+    let addr: Address<_> = HoldActor(Arc::clone(&cancelled)).start();
+    addr.do_send(HoldChain);
+    addr.do_send(HoldFold);
+
+    // stop once stream fold waits for its future
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(10), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                addr.do_send(Stop);
+                Ok(())
+            }));
+    sys.run();
+
+    assert_eq!(cancelled.load(Ordering::Relaxed), 2);
+}
+
+#[derive(Message)]
+struct Slow;
+
+struct StatsActor;
+
+impl Actor for StatsActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.enable_handler_stats();
+    }
+}
+
+impl actix::Handler<Ping> for StatsActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) {}
+}
+
+impl actix::Handler<Slow> for StatsActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Slow, _: &mut Context<Self>) {
+        std::thread::sleep(Duration::from_millis(5));
+    }
+}
+
+impl actix::Handler<Stop> for StatsActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Stop, ctx: &mut Context<Self>) {
+        let stats = ctx.handler_stats();
+        assert_eq!(stats.len(), 3);
+        Arbiter::system().do_send(SystemExit(0));
+    }
+}
+
+#[test]
+fn test_handler_stats() {
+    let sys = System::new("test");
+
+    let (addr, sync_addr): (Address<_>, SyncAddress<_>) = StatsActor.start();
+    for _ in 0..3 {
+        addr.do_send(Ping);
+    }
+    sync_addr.do_send(Slow);
+    sync_addr.do_send(Slow);
+
+    let stats_addr = addr.clone();
+    Arbiter::handle().spawn(
+        sync_addr.call_fut(actix::msgs::GetHandlerStats).then(move |res| {
+            let stats = res.unwrap().unwrap();
+            let ping = stats.iter().find(|&(k, _)| k.ends_with("::Ping")).unwrap().1;
+            assert_eq!(ping.count, 3);
+            assert!(ping.max <= ping.total);
+
+            let slow = stats.iter().find(|&(k, _)| k.ends_with("::Slow")).unwrap().1;
+            assert_eq!(slow.count, 2);
+            assert!(slow.max >= Duration::from_millis(5));
+            assert!(slow.total >= Duration::from_millis(10));
+            assert!(slow.average() >= Duration::from_millis(5));
+
+            stats_addr.do_send(Stop);
+            Ok(())
+        }));
+
+    sys.run();
+}
+
+#[derive(Message)]
+struct First;
+
+#[derive(Message)]
+struct Second;
+
+struct TraceActor(Arc<Mutex<Vec<&'static str>>>);
+
+impl Actor for TraceActor {
+    type Context = Context<Self>;
+}
+
+impl actix::Handler<First> for TraceActor {
+    type Result = ();
+
+    fn handle(&mut self, _: First, _: &mut Context<Self>) {
+        self.0.lock().unwrap().push("First");
+    }
+}
+
+impl actix::Handler<Second> for TraceActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Second, _: &mut Context<Self>) {
+        self.0.lock().unwrap().push("Second");
+    }
+}
+
+#[test]
+fn test_mailbox_trace() {
+    let sys = System::new("test");
+    let observed = Arc::new(Mutex::new(Vec::new()));
+
+    let act_observed = Arc::clone(&observed);
+    let addr: SyncAddress<_> = TraceActor::create(move |ctx| {
+        ctx.enable_mailbox_trace(64);
+        TraceActor(act_observed)
+    });
+
+    // actor enables trace before senders start
+    let barrier = Arc::new(std::sync::Barrier::new(2));
+    Arbiter::handle().spawn(
+        future::lazy(move || {
+            // two senders interleave their messages
+            let senders: Vec<_> = (0..2).map(|n| {
+                let (addr, barrier) = (addr.clone(), Arc::clone(&barrier));
+                thread::spawn(move || {
+                    barrier.wait();
+                    for _ in 0..5 {
+                        if n == 0 { addr.do_send(First) } else { addr.do_send(Second) }
+                    }
+                })
+            }).collect();
+            for sender in senders {
+                sender.join().unwrap();
+            }
+            addr.call_fut(actix::msgs::GetMailboxTrace)
+        }).then(move |res| {
+            let trace = res.unwrap().unwrap();
+            let name = |ev: &actix::TraceEvent| ev.message_type.rsplit("::").next().unwrap();
+
+            let dequeued: Vec<_> = trace.iter()
+                .filter(|ev| ev.kind == actix::TraceKind::Dequeue && name(ev) != "GetMailboxTrace")
+                .map(|ev| name(ev)).collect();
+            assert_eq!(dequeued, *observed.lock().unwrap());
+            assert_eq!(dequeued.len(), 10);
+
+            let mut threads: Vec<_> = trace.iter()
+                .filter(|ev| ev.kind == actix::TraceKind::Enqueue && name(ev) != "GetMailboxTrace")
+                .map(|ev| ev.thread).collect();
+            threads.dedup();
+            assert!(threads.len() >= 2);
+            assert_eq!(trace.iter()
+                       .filter(|ev| ev.kind == actix::TraceKind::HandlerFinish).count(), 10);
+
+            Arbiter::system().do_send(SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+}
+
+#[derive(Message)]
+#[rtype(usize)]
+struct Guarded;
+
+struct CountingMiddleware(Arc<AtomicUsize>);
+
+impl actix::ActorMiddleware<MiddlewareActor> for CountingMiddleware {
+    fn before(&mut self, _: &'static str, _: &mut MiddlewareActor,
+              _: &mut Context<MiddlewareActor>) -> actix::MiddlewareAction {
+        self.0.fetch_add(1, Ordering::Relaxed);
+        actix::MiddlewareAction::Continue
+    }
+}
+
+struct GuardMiddleware;
+
+impl actix::ActorMiddleware<MiddlewareActor> for GuardMiddleware {
+    fn before(&mut self, msg_type: &'static str, _: &mut MiddlewareActor,
+              _: &mut Context<MiddlewareActor>) -> actix::MiddlewareAction {
+        if msg_type.ends_with("::Guarded") {
+            actix::MiddlewareAction::Drop
+        } else {
+            actix::MiddlewareAction::Continue
+        }
+    }
+}
+
+struct MiddlewareActor{seen: Arc<AtomicUsize>, handled: Arc<AtomicUsize>}
+
+impl Actor for MiddlewareActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.add_middleware(CountingMiddleware(Arc::clone(&self.seen)));
+        ctx.add_middleware(GuardMiddleware);
+    }
+}
+
+impl actix::Handler<Ping> for MiddlewareActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) {
+        self.handled.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl actix::Handler<Guarded> for MiddlewareActor {
+    type Result = MessageResult<Guarded>;
+
+    fn handle(&mut self, _: Guarded, _: &mut Context<Self>) -> Self::Result {
+        self.handled.fetch_add(1, Ordering::Relaxed);
+        Ok(1)
+    }
+}
+
+#[test]
+fn test_middleware() {
+    let sys = System::new("test");
+    let seen = Arc::new(AtomicUsize::new(0));
+    let handled = Arc::new(AtomicUsize::new(0));
+
+    let (addr, sync_addr): (Address<_>, SyncAddress<_>) = MiddlewareActor{
+        seen: Arc::clone(&seen), handled: Arc::clone(&handled)}.start();
+    addr.do_send(Ping);
+    sync_addr.do_send(Ping);
+
+    Arbiter::handle().spawn(
+        sync_addr.call_fut(Guarded).then(move |res| {
+            // dropped message cancels caller
+            match res {
+                Err(MailboxError::Canceled) => (),
+                _ => panic!("request should be canceled"),
+            }
+            addr.call_fut(Guarded)
+        }).then(|res| {
+            match res {
+                Err(MailboxError::Canceled) => (),
+                _ => panic!("request should be canceled"),
+            }
+            Arbiter::system().do_send(SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+    assert_eq!(seen.load(Ordering::Relaxed), 4);
+    assert_eq!(handled.load(Ordering::Relaxed), 2);
+}
+
+#[derive(Message)]
+struct StopNow(Arc<AtomicUsize>);
+
+struct Flooded(Arc<AtomicUsize>);
+
+impl Actor for Flooded {
+    type Context = Context<Self>;
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        Arbiter::system().do_send(SystemExit(0));
+    }
+}
+
+impl actix::Handler<Ping> for Flooded {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) {
+        self.0.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+impl actix::Handler<StopNow> for Flooded {
+    type Result = ();
+
+    fn handle(&mut self, msg: StopNow, ctx: &mut Context<Self>) {
+        // no ordinary message is handled after stop request is sent
+        assert_eq!(self.0.load(Ordering::Relaxed), msg.0.load(Ordering::Relaxed));
+        ctx.stop();
+    }
+}
+
+#[test]
+fn test_system_lane() {
+    let sys = System::new("test");
+    let handled = Arc::new(AtomicUsize::new(0));
+
+    let (addr, sync_addr): (Address<_>, SyncAddress<_>) =
+        Flooded(Arc::clone(&handled)).start();
+
+    // keep mailbox full
+    Arbiter::handle().spawn(
+        futures::stream::repeat::<_, ()>(())
+            .for_each(move |_| addr.send(Ping).map_err(|_| ())));
+
+    let sent_at = Arc::new(AtomicUsize::new(0));
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(20), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                assert!(handled.load(Ordering::Relaxed) > 0);
+                sent_at.store(handled.load(Ordering::Relaxed), Ordering::Relaxed);
+                assert!(sync_addr.try_send_system(StopNow(sent_at)).is_ok());
+                Ok(())
+            }));
+
+    sys.run();
+}
+
+struct GroupsActor(Arc<Mutex<Vec<&'static str>>>);
+
+impl Actor for GroupsActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        let (first, second) = (ctx.timer_group(), ctx.timer_group());
+        ctx.run_later_in(first, Duration::from_millis(5), |act, _| {
+            act.0.lock().unwrap().push("first-retry")
+        });
+        ctx.run_later_in(first, Duration::from_millis(10), |act, _| {
+            act.0.lock().unwrap().push("first-deadline")
+        });
+        ctx.run_later_in(second, Duration::from_millis(5), |act, _| {
+            act.0.lock().unwrap().push("second-retry")
+        });
+        ctx.run_later_in(second, Duration::from_millis(10), |act, _| {
+            act.0.lock().unwrap().push("second-deadline")
+        });
+        ctx.cancel_group(first);
+
+        ctx.run_later(Duration::from_millis(50), |_, _| {
+            Arbiter::system().do_send(SystemExit(0));
+        });
+    }
+}
+
+#[test]
+fn test_timer_groups() {
+    let sys = System::new("test");
+    let fired = Arc::new(Mutex::new(Vec::new()));
+
+    let _: () = GroupsActor(Arc::clone(&fired)).start();
+    sys.run();
+
+    assert_eq!(*fired.lock().unwrap(), vec!["second-retry", "second-deadline"]);
+}
+
+struct Die;
+
+impl ResponseType for Die {
+    type Item = ();
+    type Error = ();
+}
+
+/// Registers timer within a group, cancels that group after restart
+struct RestartGroupsActor {
+    group: Option<TimerGroup>,
+    fired: Arc<Mutex<Vec<&'static str>>>,
+}
+
+impl Actor for RestartGroupsActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        match self.group {
+            None => {
+                let group = ctx.timer_group();
+                ctx.run_later_in(group, Duration::from_secs(10), |act, _| {
+                    act.fired.lock().unwrap().push("stale")
+                });
+                self.group = Some(group);
+            }
+            Some(group) => {
+                // handle of this timer is reused after restart
+                ctx.run_later(Duration::from_millis(5), |act, _| {
+                    act.fired.lock().unwrap().push("fresh")
+                });
+                ctx.cancel_group(group);
+                ctx.run_later(Duration::from_millis(50), |_, _| {
+                    Arbiter::system().do_send(SystemExit(0));
+                });
+            }
+        }
+    }
+}
+
+impl Supervised for RestartGroupsActor {}
+
+impl actix::Handler<Die> for RestartGroupsActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Die, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+#[test]
+fn test_timer_groups_restart() {
+    let sys = System::new("test");
+    let fired = Arc::new(Mutex::new(Vec::new()));
+
+    let act_fired = Arc::clone(&fired);
+    let addr: Address<_> = actix::Supervisor::start(
+        move |_| RestartGroupsActor{group: None, fired: act_fired});
+    addr.do_send(Die);
+    sys.run();
+
+    assert_eq!(*fired.lock().unwrap(), vec!["fresh"]);
+}
+
+struct GetValue;
+
+impl ResponseType for GetValue {
+    type Item = usize;
+    type Error = ();
+}
+
+struct Invalidate;
+
+impl ResponseType for Invalidate {
+    type Item = ();
+    type Error = ();
+}
+
+struct CachedActor(Arc<AtomicUsize>);
+
+impl Actor for CachedActor {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.cache_response::<GetValue>(Duration::from_millis(100));
+    }
+}
+
+impl Handler<GetValue> for CachedActor {
+    type Result = MessageResult<GetValue>;
+
+    fn handle(&mut self, _: GetValue, _: &mut Context<Self>) -> Self::Result {
+        Ok(self.0.fetch_add(1, Ordering::Relaxed) + 1)
+    }
+}
+
+impl Handler<Invalidate> for CachedActor {
+    type Result = ();
+
+    fn handle(&mut self, _: Invalidate, ctx: &mut Context<Self>) {
+        ctx.invalidate_cache::<GetValue>();
+    }
+}
+
+#[test]
+fn test_response_cache() {
+    let sys = System::new("test");
+    let hits = Arc::new(AtomicUsize::new(0));
+
+    let addr: Address<_> = CachedActor(Arc::clone(&hits)).start();
+    let (addr2, addr3) = (addr.clone(), addr.clone());
+    let (hits2, hits3) = (Arc::clone(&hits), Arc::clone(&hits));
+    Arbiter::handle().spawn(
+        addr.call_fut(GetValue)
+            .join3(addr.call_fut(GetValue), addr.call_fut(GetValue))
+            .and_then(move |res| {
+                assert_eq!(res, (Ok(1), Ok(1), Ok(1)));
+                assert_eq!(hits.load(Ordering::Relaxed), 1);
+
+                addr2.do_send(Invalidate);
+                addr2.call_fut(GetValue)
+            })
+            .and_then(move |res| {
+                assert_eq!(res, Ok(2));
+                assert_eq!(hits2.load(Ordering::Relaxed), 2);
+                Timeout::new(Duration::from_millis(150), Arbiter::handle()).unwrap()
+                    .map_err(|_| unreachable!())
+            })
+            .and_then(move |_| addr3.call_fut(GetValue))
+            .then(move |res| {
+                assert_eq!(res.unwrap(), Ok(3));
+                assert_eq!(hits3.load(Ordering::Relaxed), 3);
+                Arbiter::system().do_send(SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+}
+
+#[derive(Message)]
+struct Read(usize);
+
+#[derive(Message)]
+struct Write(usize);
+
+#[derive(Message)]
+struct Rebalance(bool);
+
+struct Rebalancer(Arc<Mutex<Vec<String>>>);
+
+impl Actor for Rebalancer {
+    type Context = Context<Self>;
+}
+
+impl Handler<Read> for Rebalancer {
+    type Result = ();
+
+    fn handle(&mut self, msg: Read, _: &mut Context<Self>) {
+        self.0.lock().unwrap().push(format!("r{}", msg.0));
+    }
+}
+
+impl Handler<Write> for Rebalancer {
+    type Result = ();
+
+    fn handle(&mut self, msg: Write, _: &mut Context<Self>) {
+        self.0.lock().unwrap().push(format!("w{}", msg.0));
+    }
+}
+
+impl Handler<Rebalance> for Rebalancer {
+    type Result = ();
+
+    fn handle(&mut self, msg: Rebalance, ctx: &mut Context<Self>) {
+        if msg.0 {
+            ctx.defer::<Write>();
+        } else {
+            ctx.resume::<Write>();
+        }
+    }
+}
+
+#[test]
+fn test_defer_messages() {
+    let sys = System::new("test");
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: SyncAddress<_> = Rebalancer(Arc::clone(&log)).start();
+    addr.do_send(Write(0));
+    addr.do_send(Rebalance(true));
+    addr.do_send(Write(1));
+    addr.do_send(Read(0));
+    addr.do_send(Write(2));
+    addr.do_send(Read(1));
+    addr.do_send(Rebalance(false));
+    addr.do_send(Write(3));
+    addr.do_send(Read(2));
+
+    // deferred request cancels when actor stops
+    addr.do_send(Rebalance(true));
+    Arbiter::handle().spawn(
+        addr.call_fut(Write(4))
+            .join(addr.call_fut(actix::msgs::StopActor))
+            .then(|res| {
+                match res {
+                    Err(MailboxError::Canceled) => (),
+                    _ => panic!("deferred request should cancel"),
+                }
+                Arbiter::system().do_send(SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+
+    assert_eq!(*log.lock().unwrap(), vec!["w0", "r0", "r1", "w1", "w2", "w3", "r2"]);
+}
+
+struct Sessions;
+
+impl Actor for Sessions {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        let ids: Vec<_> = (0..3).map(|_| ctx.unique_id()).collect();
+        assert!(ids[0] < ids[1] && ids[1] < ids[2]);
+        Arbiter::system().do_send(SystemExit(0));
+    }
+}
+
+#[test]
+fn test_unique_id() {
+    let sys = System::new("test");
+    let _: () = Sessions.start();
+    sys.run();
+}
+
+struct NoReply;
+
+impl ResponseType for NoReply {
+    type Item = ();
+    type Error = ();
+}
+
+/// Never answers `NoReply`
+struct Mute;
+
+impl Actor for Mute {
+    type Context = Context<Self>;
+}
+
+impl Handler<NoReply> for Mute {
+    type Result = ResponseFuture<NoReply>;
+
+    fn handle(&mut self, _: NoReply, _: &mut Context<Self>) -> ResponseFuture<NoReply> {
+        Box::new(future::empty())
+    }
+}
+
+struct Note(&'static str);
+
+impl ResponseType for Note {
+    type Item = ();
+    type Error = ();
+}
+
+struct Blocked {
+    mute: Address<Mute>,
+    log: Arc<Mutex<Vec<(&'static str, Instant)>>>,
+}
+
+impl Actor for Blocked {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        let fut = self.mute.call_fut(NoReply)
+            .into_actor(self)
+            .then(|_, act: &mut Blocked, _: &mut Context<Blocked>| {
+                act.log.lock().unwrap().push(("answered", Instant::now()));
+                actix::fut::ok(())
+            });
+        ctx.wait_timeout(fut, Duration::from_millis(100), |act, _| {
+            act.log.lock().unwrap().push(("timeout", Instant::now()));
+        });
+    }
+}
+
+impl Handler<Note> for Blocked {
+    type Result = ();
+
+    fn handle(&mut self, msg: Note, _: &mut Context<Self>) {
+        self.log.lock().unwrap().push((msg.0, Instant::now()));
+        if msg.0 == "second" {
+            Arbiter::system().do_send(SystemExit(0));
+        }
+    }
+}
+
+#[test]
+fn test_wait_timeout() {
+    let sys = System::new("test");
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let start = Instant::now();
+    let mute: Address<_> = Mute.start();
+    let addr: Address<_> = Blocked{mute: mute, log: Arc::clone(&log)}.start();
+    addr.do_send(Note("first"));
+    addr.do_send(Note("second"));
+    sys.run();
+
+    let log = log.lock().unwrap();
+    assert_eq!(log.iter().map(|rec| rec.0).collect::<Vec<_>>(), vec!["timeout", "first", "second"]);
+    assert!(log[0].1 - start >= Duration::from_millis(100));
+}
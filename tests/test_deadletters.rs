@@ -0,0 +1,262 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::thread;
+use std::time::Duration;
+use futures::Future;
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::actors::deadletter::{DeadLetterReason, DeadLetters, GetDeadLetters, SetCapacity};
+
+struct Ping(usize);
+
+impl ResponseType for Ping {
+    type Item = ();
+    type Error = ();
+}
+
+#[derive(Debug)]
+struct Pong(usize);
+
+impl ResponseType for Pong {
+    type Item = ();
+    type Error = ();
+
+    fn render(&self) -> Option<String> {
+        Some(format!("{:?}", self))
+    }
+}
+
+struct Stopped;
+
+impl Actor for Stopped {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+impl Handler<Ping> for Stopped {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) {}
+}
+
+impl Handler<Pong> for Stopped {
+    type Result = ();
+
+    fn handle(&mut self, _: Pong, _: &mut Context<Self>) {}
+}
+
+#[test]
+fn test_dead_letters_ring_buffer() {
+    let sys = System::new("test");
+    DeadLetters::from_registry().do_send(SetCapacity(4));
+
+    let addr: Address<_> = Stopped.start();
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                assert!(!addr.connected());
+                for i in 0..3 {
+                    addr.do_send(Ping(i));
+                }
+                for i in 3..6 {
+                    addr.do_send(Pong(i));
+                }
+                DeadLetters::from_registry().call_fut(GetDeadLetters)
+            })
+            .then(|res| {
+                let letters = res.unwrap().unwrap();
+
+                // only newest letters are retained
+                assert_eq!(letters.len(), 4);
+                assert!(letters[0].message_type.ends_with("::Ping"));
+                assert_eq!(letters[0].rendered, None);
+                for (letter, i) in letters[1..].iter().zip(3..) {
+                    assert!(letter.message_type.ends_with("::Pong"));
+                    assert!(letter.actor_type.ends_with("::Stopped"));
+                    assert_eq!(letter.rendered, Some(format!("Pong({})", i)));
+                }
+                assert!(letters[0].at <= letters[3].at);
+
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok::<_, ()>(())
+            }));
+
+    sys.run();
+}
+
+#[derive(Debug)]
+struct Job(usize);
+
+impl ResponseType for Job {
+    type Item = ();
+    type Error = ();
+
+    fn render(&self) -> Option<String> {
+        Some(format!("{:?}", self))
+    }
+}
+
+struct StopOnJob;
+
+impl Actor for StopOnJob {
+    type Context = Context<Self>;
+}
+
+impl Handler<Job> for StopOnJob {
+    type Result = ();
+
+    fn handle(&mut self, _: Job, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+#[test]
+fn test_dead_letters_stopped_actor() {
+    let sys = System::new("test");
+
+    // messages are queued before actor starts, first one stops actor
+    let addr: SyncAddress<_> = StopOnJob.start();
+    let sync_addr = addr.clone();
+    addr.do_send(Job(0));
+    addr.do_send(Job(1));
+    let other = thread::spawn(move || {
+        sync_addr.do_send(Job(2));
+        thread::current().id()
+    }).join().unwrap();
+    drop(addr);
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            .then(|_| DeadLetters::from_registry().call_fut(GetDeadLetters))
+            .then(move |res| {
+                let mut letters = res.unwrap().unwrap();
+                letters.sort_by_key(|letter| letter.rendered.clone());
+
+                assert_eq!(letters.len(), 2);
+                assert_eq!(letters[0].rendered, Some("Job(1)".to_owned()));
+                assert_eq!(letters[0].sender, thread::current().id());
+                assert_eq!(letters[1].rendered, Some("Job(2)".to_owned()));
+                assert_eq!(letters[1].sender, other);
+                for letter in &letters {
+                    assert_eq!(letter.reason, DeadLetterReason::Stopped);
+                    assert!(letter.actor_type.ends_with("::StopOnJob"));
+                }
+
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok::<_, ()>(())
+            }));
+
+    sys.run();
+}
+
+struct Deferring;
+
+impl Actor for Deferring {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.defer::<Job>();
+    }
+}
+
+impl Handler<Job> for Deferring {
+    type Result = ();
+
+    fn handle(&mut self, _: Job, _: &mut Context<Self>) {}
+}
+
+impl Handler<Ping> for Deferring {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+#[test]
+fn test_dead_letters_deferred() {
+    let sys = System::new("test");
+
+    let addr: Address<_> = Deferring.start();
+    addr.do_send(Job(0));
+    addr.do_send(Job(1));
+    addr.do_send(Ping(0));
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                assert!(!addr.connected());
+                DeadLetters::from_registry().call_fut(GetDeadLetters)
+            })
+            .then(|res| {
+                let letters = res.unwrap().unwrap();
+
+                // parked messages are published when actor stops
+                assert_eq!(letters.len(), 2);
+                assert_eq!(letters[0].rendered, Some("Job(0)".to_owned()));
+                assert_eq!(letters[1].rendered, Some("Job(1)".to_owned()));
+                for letter in &letters {
+                    assert_eq!(letter.reason, DeadLetterReason::Stopped);
+                    assert!(letter.actor_type.ends_with("::Deferring"));
+                }
+
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok::<_, ()>(())
+            }));
+
+    sys.run();
+}
+
+struct Idle;
+
+impl Actor for Idle {
+    type Context = Context<Self>;
+}
+
+impl Handler<Job> for Idle {
+    type Result = ();
+
+    fn handle(&mut self, _: Job, _: &mut Context<Self>) {}
+}
+
+#[test]
+fn test_dead_letters_dropped_send() {
+    let sys = System::new("test");
+
+    // mailboxes are full, send futures get dropped before they deliver
+    let addr: Address<_> = Idle::build().mailbox_capacity(1).start(Idle);
+    let sync_addr: SyncAddress<_> = Idle::build().mailbox_capacity(1).start(Idle);
+    assert!(addr.try_send(Job(0)).is_ok());
+    drop(addr.send(Job(1)));
+    while sync_addr.try_send(Job(2)).is_ok() {}
+    drop(sync_addr.send(Job(3)));
+
+    // delivered send does not produce dead letter
+    Arbiter::handle().spawn(addr.send(Job(4)).map_err(|_| ()));
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            .then(|_| DeadLetters::from_registry().call_fut(GetDeadLetters))
+            .then(move |res| {
+                let letters = res.unwrap().unwrap();
+
+                assert_eq!(letters.len(), 2);
+                assert_eq!(letters[0].rendered, Some("Job(1)".to_owned()));
+                assert_eq!(letters[1].rendered, Some("Job(3)".to_owned()));
+                for letter in &letters {
+                    assert_eq!(letter.reason, DeadLetterReason::Dropped);
+                    assert!(letter.actor_type.ends_with("::Idle"));
+                }
+
+                drop((addr, sync_addr));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok::<_, ()>(())
+            }));
+
+    sys.run();
+}
@@ -0,0 +1,335 @@
+extern crate actix;
+extern crate futures;
+extern crate log;
+extern crate tokio_core;
+
+use std::thread;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::{future, Future};
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::{ActorInfo, MessageRegistry, WaitInfo};
+use actix::msgs::DumpActors;
+
+struct RecordLogger(Mutex<Vec<String>>);
+
+impl log::Log for RecordLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        self.0.lock().unwrap().push(format!("{}", record.args()));
+    }
+
+    fn flush(&self) {}
+}
+
+struct Ping;
+
+impl ResponseType for Ping {
+    type Item = ();
+    type Error = ();
+}
+
+/// Call peer and wait for response
+struct Start(SyncAddress<Peer>);
+
+impl ResponseType for Start {
+    type Item = ();
+    type Error = ();
+}
+
+/// Call own address, `wait` selects `wait()` or `spawn()`, `sync` selects address type
+struct CallSelf {
+    wait: bool,
+    sync: bool,
+}
+
+impl ResponseType for CallSelf {
+    type Item = ();
+    type Error = ();
+}
+
+/// Call peer and wait for response, peer does not wait
+struct CallPeer(Address<Peer>);
+
+impl ResponseType for CallPeer {
+    type Item = ();
+    type Error = ();
+}
+
+struct Sleep(u64);
+
+impl ResponseType for Sleep {
+    type Item = ();
+    type Error = ();
+}
+
+struct Peer {
+    threshold: Option<Duration>,
+    results: Arc<Mutex<Vec<Result<(), MailboxError>>>>,
+}
+
+impl Actor for Peer {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        if let Some(threshold) = self.threshold {
+            ctx.set_deadlock_detection(Some(threshold), false);
+        }
+    }
+}
+
+impl Handler<Start> for Peer {
+    type Result = ();
+
+    fn handle(&mut self, msg: Start, ctx: &mut Context<Self>) {
+        msg.0.call_fut(Ping)
+            .into_actor(self)
+            .then(|res, act: &mut Peer, _: &mut Context<Peer>| {
+                act.results.lock().unwrap().push(res.map(|_| ()));
+                actix::fut::ok(())
+            })
+            .wait(ctx);
+    }
+}
+
+impl Handler<CallSelf> for Peer {
+    type Result = ();
+
+    fn handle(&mut self, msg: CallSelf, ctx: &mut Context<Self>) {
+        let fut = if msg.sync {
+            let addr: SyncAddress<_> = ctx.address();
+            actix::fut::Either::A(addr.call_fut(Ping).into_actor(self))
+        } else {
+            let addr: Address<_> = ctx.address();
+            actix::fut::Either::B(addr.call_fut(Ping).into_actor(self))
+        };
+        let fut = fut.then(|res, act: &mut Peer, _: &mut Context<Peer>| {
+            act.results.lock().unwrap().push(res.map(|_| ()));
+            actix::fut::ok(())
+        });
+        if msg.wait {
+            fut.wait(ctx)
+        } else {
+            fut.spawn(ctx)
+        }
+    }
+}
+
+impl Handler<CallPeer> for Peer {
+    type Result = ();
+
+    fn handle(&mut self, msg: CallPeer, ctx: &mut Context<Self>) {
+        msg.0.call_fut(Ping)
+            .into_actor(self)
+            .then(|res, act: &mut Peer, _: &mut Context<Peer>| {
+                act.results.lock().unwrap().push(res.map(|_| ()));
+                actix::fut::ok(())
+            })
+            .wait(ctx);
+    }
+}
+
+impl Handler<Ping> for Peer {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) {}
+}
+
+impl Handler<Sleep> for Peer {
+    type Result = ();
+
+    fn handle(&mut self, msg: Sleep, _: &mut Context<Self>) {
+        thread::sleep(Duration::from_millis(msg.0));
+    }
+}
+
+fn start_peers(names: (&str, &str), threshold: Option<Duration>)
+               -> Arc<Mutex<Vec<Result<(), MailboxError>>>>
+{
+    let results = Arc::new(Mutex::new(Vec::new()));
+    let peer = |name: &str| -> SyncAddress<_> {
+        let results = Arc::clone(&results);
+        Peer::build().name(name).start(Peer{threshold: threshold, results: results})
+    };
+    let (a, b) = (peer(names.0), peer(names.1));
+    a.do_send(Start(b.clone()));
+    b.do_send(Start(a));
+    Arc::clone(&results)
+}
+
+#[test]
+fn test_deadlock_report() {
+    let logger: &'static RecordLogger = Box::leak(Box::new(RecordLogger(Mutex::new(Vec::new()))));
+    log::set_logger(logger).unwrap();
+    log::set_max_level(log::LevelFilter::Error);
+
+    let sys = System::new("test");
+    MessageRegistry::register::<Ping>("deadlock.ping").unwrap();
+    let results = start_peers(("report-a", "report-b"), Some(Duration::from_millis(100)));
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(400), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                // actors are still stuck
+                assert!(results.lock().unwrap().is_empty());
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+    sys.run();
+
+    let records = logger.0.lock().unwrap();
+    for name in &["report-a", "report-b"] {
+        let records: Vec<_> = records.iter()
+            .filter(|rec| rec.contains(&format!("actor {} ", name))).collect();
+        assert_eq!(records.len(), 1, "{:?}", records);
+        assert!(records[0].contains("waits for deadlock.ping"));
+        assert!(records[0].contains("queued messages: test_deadlock::Ping"));
+    }
+}
+
+#[test]
+fn test_deadlock_break() {
+    let sys = System::with_config(
+        actix::SystemConfig::new("test").deadlock_detection(Duration::from_millis(100), true));
+    let results = start_peers(("break-a", "break-b"), None);
+
+    // keep arbiter busy, so both waits expire before contexts get polled
+    let sleeper: SyncAddress<_> = Peer{threshold: None, results: Arc::default()}.start();
+    sleeper.do_send(Sleep(200));
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(500), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                let results = results.lock().unwrap();
+                assert_eq!(*results, vec![Err(MailboxError::Timeout), Err(MailboxError::Timeout)]);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+    sys.run();
+}
+
+/// Run `CallSelf` requests, then `CallPeer`, returns results
+fn call_self(requests: Vec<CallSelf>) -> Vec<Result<(), MailboxError>> {
+    let sys = System::new("test");
+    let results = Arc::new(Mutex::new(Vec::new()));
+    let peer: Address<_> = Peer{threshold: None, results: Arc::clone(&results)}.start();
+    let other: Address<_> = Peer{threshold: None, results: Arc::default()}.start();
+
+    for msg in requests {
+        peer.do_send(msg);
+    }
+    peer.do_send(CallPeer(other));
+
+    let res = Arc::clone(&results);
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(100), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+    sys.run();
+
+    let results = res.lock().unwrap().clone();
+    results
+}
+
+#[test]
+fn test_wait_self_call() {
+    let results = call_self(vec![CallSelf{wait: true, sync: false},
+                                 CallSelf{wait: true, sync: true}]);
+    assert_eq!(results, vec![Err(MailboxError::SelfCall),
+                             Err(MailboxError::SelfCall),
+                             Ok(())]);
+}
+
+#[test]
+fn test_spawn_self_call() {
+    let results = call_self(vec![CallSelf{wait: false, sync: false},
+                                 CallSelf{wait: false, sync: true}]);
+    assert_eq!(results, vec![Ok(()), Ok(()), Ok(())]);
+}
+
+/// Never answers `Ping`
+struct Silent;
+
+impl Actor for Silent {
+    type Context = Context<Self>;
+}
+
+impl Handler<Ping> for Silent {
+    type Result = ResponseFuture<Ping>;
+
+    fn handle(&mut self, _: Ping, _: &mut Context<Self>) -> ResponseFuture<Ping> {
+        Box::new(future::empty())
+    }
+}
+
+/// Wait for `Silent`
+struct Stuck(Address<Silent>);
+
+impl ResponseType for Stuck {
+    type Item = ();
+    type Error = ();
+}
+
+impl Handler<Stuck> for Peer {
+    type Result = ();
+
+    fn handle(&mut self, msg: Stuck, ctx: &mut Context<Self>) {
+        assert!(ctx.wait_queue_info().is_none());
+        msg.0.call_fut(Ping).into_actor(self).then(|_, _, _| actix::fut::ok(())).wait(ctx);
+        actix::fut::ok::<_, _, Peer>(()).wait(ctx);
+
+        // wait futures are not polled yet
+        let info = ctx.wait_queue_info().unwrap();
+        assert_eq!(info.message_type, "<unknown>");
+        assert_eq!(info.queued, 1);
+    }
+}
+
+fn dump(name: &'static str) -> Box<Future<Item=Option<WaitInfo>, Error=()>> {
+    Box::new(Arbiter::arbiter().call_fut(DumpActors).then(move |res| {
+        let actors: Vec<ActorInfo> = res.unwrap().unwrap();
+        let info = actors.iter().find(|info| info.name == name).unwrap();
+        Ok(info.wait)
+    }))
+}
+
+fn delay(ms: u64) -> Timeout {
+    Timeout::new(Duration::from_millis(ms), Arbiter::handle()).unwrap()
+}
+
+#[test]
+fn test_wait_queue_dump() {
+    let sys = System::new("test");
+    MessageRegistry::register::<Ping>("deadlock.ping").unwrap();
+    let silent: Address<_> = Silent.start();
+    let peer: Address<_> = Peer::build().name("stuck")
+        .start(Peer{threshold: None, results: Arc::default()});
+    peer.do_send(Stuck(silent));
+
+    let res = Arc::new(Mutex::new(None));
+    let res2 = Arc::clone(&res);
+    Arbiter::handle().spawn(
+        delay(50)
+            .then(|_| dump("stuck"))
+            .and_then(|first| delay(100).then(move |_| dump("stuck").map(move |second| (first, second))))
+            .then(move |r| {
+                *res2.lock().unwrap() = Some(r.unwrap());
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+    sys.run();
+
+    let (first, second) = res.lock().unwrap().take().unwrap();
+    let (first, second) = (first.unwrap(), second.unwrap());
+    assert_eq!(first.message_type, "deadlock.ping");
+    assert_eq!(second.message_type, "deadlock.ping");
+    assert_eq!(second.queued, 0);
+    assert!(first.waiting >= Duration::from_millis(40), "{:?}", first);
+    assert!(second.waiting >= first.waiting + Duration::from_millis(90), "{:?}", second);
+}
@@ -0,0 +1,112 @@
+#[macro_use]
+extern crate actix;
+extern crate futures;
+
+use std::sync::{Arc, Mutex};
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::msgs::SystemExit;
+
+struct Rooms {
+    rooms: Vec<String>,
+    joined: Arc<Mutex<Vec<(usize, String)>>>,
+}
+
+impl Actor for Rooms {
+    type Context = Context<Self>;
+}
+
+actor_facade! {
+    /// Operations of rooms actor
+    pub trait RoomsApi for Rooms {
+        /// List of rooms
+        fn list_rooms() -> Vec<String> as ListRooms;
+        /// Add room, returns number of rooms
+        fn add(name: String) -> usize as Add;
+        /// Join room
+        fn join(id: usize, name: String) as Join;
+    }
+}
+
+impl Handler<ListRooms> for Rooms {
+    type Result = MessageResult<ListRooms>;
+
+    fn handle(&mut self, _: ListRooms, _: &mut Context<Self>) -> Self::Result {
+        Ok(self.rooms.clone())
+    }
+}
+
+impl Handler<Add> for Rooms {
+    type Result = MessageResult<Add>;
+
+    fn handle(&mut self, msg: Add, _: &mut Context<Self>) -> Self::Result {
+        self.rooms.push(msg.name);
+        Ok(self.rooms.len())
+    }
+}
+
+impl Handler<Join> for Rooms {
+    type Result = ();
+
+    fn handle(&mut self, msg: Join, _: &mut Context<Self>) {
+        self.joined.lock().unwrap().push((msg.id, msg.name));
+    }
+}
+
+#[test]
+fn test_facade() {
+    let sys = System::new("test");
+    let joined = Arc::new(Mutex::new(Vec::new()));
+    let (local, remote): (Address<_>, SyncAddress<_>) =
+        Rooms{rooms: vec!["main".to_owned()], joined: Arc::clone(&joined)}.start();
+
+    // fire-and-forget
+    local.join(1, "main".to_owned());
+    remote.join(2, "main".to_owned());
+
+    let res = Arc::new(Mutex::new(None));
+    let res2 = Arc::clone(&res);
+    Arbiter::handle().spawn(
+        local.add("local".to_owned())
+            .join(remote.add("remote".to_owned()))
+            .and_then(move |added| Future::map(remote.list_rooms(), move |rooms| (added, rooms)))
+            .then(move |r| {
+                *res2.lock().unwrap() = Some(r.unwrap());
+                Arbiter::system().do_send(SystemExit(0));
+                future::ok(())
+            }));
+    sys.run();
+
+    let (added, rooms) = res.lock().unwrap().take().unwrap();
+    assert_eq!(added, (Ok(2), Ok(3)));
+    assert_eq!(rooms, Ok(vec!["main".to_owned(), "local".to_owned(), "remote".to_owned()]));
+    let mut joined = joined.lock().unwrap().clone();
+    joined.sort();
+    assert_eq!(joined, vec![(1, "main".to_owned()), (2, "main".to_owned())]);
+}
+
+/// Facade methods are usable through generic code
+fn count_rooms<T: RoomsApi>(addr: &T) -> Box<Future<Item=usize, Error=()>> {
+    Box::new(addr.list_rooms().then(|res| match res {
+        Ok(Ok(rooms)) => Ok(rooms.len()),
+        _ => Err(()),
+    }))
+}
+
+#[test]
+fn test_facade_generic() {
+    let sys = System::new("test");
+    let addr: SyncAddress<_> = Rooms{rooms: vec!["a".to_owned(), "b".to_owned()],
+                                     joined: Arc::default()}.start();
+
+    let res = Arc::new(Mutex::new(None));
+    let res2 = Arc::clone(&res);
+    Arbiter::handle().spawn(count_rooms(&addr).then(move |r| {
+        *res2.lock().unwrap() = Some(r);
+        Arbiter::system().do_send(SystemExit(0));
+        Ok(())
+    }));
+    sys.run();
+
+    assert_eq!(res.lock().unwrap().take(), Some(Ok(2)));
+}
@@ -0,0 +1,152 @@
+#[macro_use] extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::sync::Arc;
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::time::Duration;
+use futures::Future;
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::utils::{FanOut, FanOutStrategy};
+
+#[derive(Message, Clone)]
+struct Msg(usize);
+
+/// Counts received messages, slow actor has mailbox capacity 1
+/// and does not handle messages for first 200 milliseconds.
+struct Counter {
+    count: Arc<AtomicUsize>,
+    slow: bool,
+}
+
+impl Actor for Counter {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        if self.slow {
+            Timeout::new(Duration::from_millis(200), Arbiter::handle()).unwrap()
+                .map_err(|_| ())
+                .into_actor(self)
+                .wait(ctx);
+        }
+    }
+}
+
+impl Handler<Msg> for Counter {
+    type Result = ();
+
+    fn handle(&mut self, _: Msg, _: &mut Context<Self>) {
+        self.count.fetch_add(1, Ordering::Relaxed);
+    }
+}
+
+fn subscribers(fanout: &mut FanOut<Msg>) -> (Vec<Arc<AtomicUsize>>, Vec<usize>) {
+    let mut counts = Vec::new();
+    let mut ids = Vec::new();
+    for slow in &[false, false, true] {
+        let count = Arc::new(AtomicUsize::new(0));
+        let addr: Address<_> = Counter::build()
+            .mailbox_capacity(if *slow { 1 } else { 16 })
+            .start(Counter{count: Arc::clone(&count), slow: *slow});
+        ids.push(fanout.subscribe(addr.into_subscriber()));
+        counts.push(count);
+    }
+    (counts, ids)
+}
+
+fn load(counts: &[Arc<AtomicUsize>]) -> Vec<usize> {
+    counts.iter().map(|c| c.load(Ordering::Relaxed)).collect()
+}
+
+fn delay(millis: u64) -> Timeout {
+    Timeout::new(Duration::from_millis(millis), Arbiter::handle()).unwrap()
+}
+
+#[test]
+fn test_fanout_drop_slow() {
+    let sys = System::new("test");
+    let mut fanout = FanOut::new(FanOutStrategy::DropSlow);
+    let (counts, ids) = subscribers(&mut fanout);
+
+    for n in 0..3 {
+        fanout.broadcast(Msg(n));
+    }
+    assert_eq!(fanout.len(), 3);
+    assert_eq!(fanout.dropped(ids[0]), 0);
+    assert_eq!(fanout.dropped(ids[1]), 0);
+    assert_eq!(fanout.dropped(ids[2]), 2);
+
+    Arbiter::handle().spawn(
+        delay(300).then(|_| {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+    assert_eq!(load(&counts), vec![3, 3, 1]);
+}
+
+#[test]
+fn test_fanout_wait_all() {
+    let sys = System::new("test");
+    let fanout = Rc::new(RefCell::new(FanOut::new(FanOutStrategy::WaitAll)));
+    let (counts, _) = subscribers(&mut fanout.borrow_mut());
+
+    // first message fills slow subscriber's mailbox
+    fanout.borrow_mut().broadcast(Msg(0));
+
+    let done = Rc::new(Cell::new(false));
+    let done2 = Rc::clone(&done);
+    Arbiter::handle().spawn(
+        fanout.borrow_mut().broadcast(Msg(1))
+            .then(move |_| {
+                done2.set(true);
+                Ok(())
+            }));
+
+    let done3 = Rc::clone(&done);
+    Arbiter::handle().spawn(
+        delay(50).then(move |_| {
+            // slow subscriber is still busy
+            assert!(!done3.get());
+            delay(500)
+        }).then(move |_| {
+            assert!(done.get());
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+    assert_eq!(load(&counts), vec![2, 2, 2]);
+}
+
+#[test]
+fn test_fanout_disconnect() {
+    let sys = System::new("test");
+    let fanout = Rc::new(RefCell::new(
+        FanOut::new(FanOutStrategy::Disconnect(Duration::from_millis(20)))));
+    let (counts, ids) = subscribers(&mut fanout.borrow_mut());
+
+    fanout.borrow_mut().broadcast(Msg(0));
+    fanout.borrow_mut().broadcast(Msg(1));
+    assert_eq!(fanout.borrow().len(), 3);
+    assert_eq!(fanout.borrow().dropped(ids[2]), 1);
+
+    Arbiter::handle().spawn(
+        delay(50).then(move |_| {
+            // slow subscriber stays full longer than grace period
+            fanout.borrow_mut().broadcast(Msg(2));
+            assert_eq!(fanout.borrow().len(), 2);
+            assert!(!fanout.borrow().contains(ids[2]));
+            delay(10)
+        }).then(|_| {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+    assert_eq!(load(&counts), vec![3, 3, 0]);
+}
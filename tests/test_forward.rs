@@ -0,0 +1,114 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::time::{Duration, Instant};
+use futures::Future;
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::{ForwardResponse, ForwardMode};
+use actix::msgs::SystemExit;
+
+const BACKEND_DELAY: u64 = 200;
+
+struct Get;
+
+impl ResponseType for Get {
+    type Item = u32;
+    type Error = ();
+}
+
+struct Die;
+
+impl ResponseType for Die {
+    type Item = ();
+    type Error = ();
+}
+
+/// Answers after delay
+struct Backend;
+
+impl Actor for Backend {
+    type Context = Context<Self>;
+}
+
+impl Handler<Get> for Backend {
+    type Result = ResponseFuture<Get>;
+
+    fn handle(&mut self, _: Get, _: &mut Context<Self>) -> Self::Result {
+        Box::new(
+            Timeout::new(Duration::from_millis(BACKEND_DELAY), Arbiter::handle()).unwrap()
+                .map(|_| 42)
+                .map_err(|_| ()))
+    }
+}
+
+struct Proxy {
+    backend: Address<Backend>,
+    mode: ForwardMode,
+}
+
+impl Actor for Proxy {
+    type Context = Context<Self>;
+}
+
+impl Handler<Get> for Proxy {
+    type Result = ForwardResponse<Get>;
+
+    fn handle(&mut self, msg: Get, _: &mut Context<Self>) -> Self::Result {
+        ForwardResponse::new(self.backend.call_fut(msg)).mode(self.mode)
+    }
+}
+
+impl Handler<Die> for Proxy {
+    type Result = ();
+
+    fn handle(&mut self, _: Die, ctx: &mut Context<Self>) {
+        ctx.stop();
+    }
+}
+
+/// Kill proxy while backend request is pending, returns client's result
+/// and time it took to resolve
+fn kill_proxy(mode: ForwardMode) -> (Result<Result<u32, ()>, MailboxError>, Duration) {
+    let sys = System::new("test");
+    let result = Rc::new(RefCell::new(None));
+
+    let backend: Address<_> = Backend.start();
+    let proxy: Address<_> = Proxy{backend: backend, mode: mode}.start();
+
+    let start = Instant::now();
+    let res = Rc::clone(&result);
+    Arbiter::handle().spawn(
+        proxy.call_fut(Get).then(move |r| {
+            *res.borrow_mut() = Some((r, start.elapsed()));
+            Arbiter::system().do_send(SystemExit(0));
+            Ok(())
+        }));
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(20), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                proxy.do_send(Die);
+                Ok(())
+            }));
+
+    sys.run();
+    let res = result.borrow_mut().take();
+    res.unwrap()
+}
+
+#[test]
+fn test_forward_cancel_on_proxy_death() {
+    let (res, elapsed) = kill_proxy(ForwardMode::Cancel);
+    assert_eq!(res, Err(MailboxError::Canceled));
+    assert!(elapsed < Duration::from_millis(BACKEND_DELAY), "{:?}", elapsed);
+}
+
+#[test]
+fn test_forward_complete_after_proxy_death() {
+    let (res, elapsed) = kill_proxy(ForwardMode::Complete);
+    assert_eq!(res, Ok(Ok(42)));
+    assert!(elapsed >= Duration::from_millis(BACKEND_DELAY), "{:?}", elapsed);
+}
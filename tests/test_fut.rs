@@ -2,12 +2,14 @@ extern crate actix;
 extern crate futures;
 extern crate tokio_core;
 
+use std::rc::Rc;
+use std::cell::RefCell;
 use std::sync::Arc;
-use std::sync::atomic::{AtomicBool, Ordering};
-use std::time::Duration;
+use std::sync::atomic::{AtomicBool, AtomicUsize, Ordering};
+use std::time::{Duration, Instant};
 use futures::{Future, Stream};
 use futures::stream::futures_ordered;
-use tokio_core::reactor::Timeout;
+use tokio_core::reactor::{Interval, Timeout};
 use actix::prelude::*;
 use actix::msgs::SystemExit;
 
@@ -27,14 +29,14 @@ impl Actor for MyActor {
     fn started(&mut self, ctx: &mut Self::Context) {
         Timeout::new(Duration::new(0, 5_000_000), Arbiter::handle()).unwrap()
             .then(|_| {
-                Arbiter::system().send(SystemExit(0));
+                Arbiter::system().do_send(SystemExit(0));
                 Ok::<_, Error>(())
             })
             .into_actor(self)
             .timeout(Duration::new(0, 100), Error::Timeout)
             .map_err(|e, act, _| if e == Error::Timeout {
                 act.timeout.store(true, Ordering::Relaxed);
-                Arbiter::system().send(SystemExit(0));
+                Arbiter::system().do_send(SystemExit(0));
                 ()
             })
             .wait(ctx)
@@ -71,7 +73,7 @@ impl Actor for MyStreamActor {
             .timeout(Duration::new(0, 100), Error::Timeout)
             .map_err(|e, act, _| if e == Error::Timeout {
                 act.timeout.store(true, Ordering::Relaxed);
-                Arbiter::system().send(SystemExit(0));
+                Arbiter::system().do_send(SystemExit(0));
                 ()
             })
             .finish()
@@ -89,3 +91,121 @@ fn test_stream_timeout() {
     sys.run();
     assert!(timeout.load(Ordering::Relaxed), "Not timeout");
 }
+
+struct Get;
+
+impl ResponseType for Get {
+    type Item = &'static str;
+    type Error = &'static str;
+}
+
+struct Replica {
+    name: &'static str,
+    latency: Duration,
+    ok: bool,
+    hits: Arc<AtomicUsize>,
+}
+
+impl Actor for Replica {
+    type Context = Context<Self>;
+}
+
+impl Handler<Get> for Replica {
+    type Result = ResponseFuture<Get>;
+
+    fn handle(&mut self, _: Get, _: &mut Context<Self>) -> Self::Result {
+        self.hits.fetch_add(1, Ordering::Relaxed);
+        let (name, ok) = (self.name, self.ok);
+        Box::new(Timeout::new(self.latency, Arbiter::handle()).unwrap()
+                 .then(move |_| if ok { Ok(name) } else { Err(name) }))
+    }
+}
+
+fn replica(name: &'static str, ms: u64, ok: bool) -> (SyncAddress<Replica>, Arc<AtomicUsize>) {
+    let hits = Arc::new(AtomicUsize::new(0));
+    let replica = Replica{name: name, latency: Duration::from_millis(ms),
+                          ok: ok, hits: Arc::clone(&hits)};
+    (replica.start(), hits)
+}
+
+#[test]
+fn test_hedge() {
+    let mut sys = System::new("test");
+    let delay = Duration::from_millis(20);
+
+    // slow primary, secondary fires after delay and wins
+    let (slow, slow_hits) = replica("slow", 200, true);
+    let (fast, fast_hits) = replica("fast", 0, true);
+    let res = sys.run_until_complete(
+        actix::fut::hedge(slow.call_fut(Get), move || fast.call_fut(Get), delay));
+    assert_eq!(res, Ok(Ok("fast")));
+    assert_eq!(slow_hits.load(Ordering::Relaxed), 1);
+    assert_eq!(fast_hits.load(Ordering::Relaxed), 1);
+
+    // fast primary, secondary is never issued
+    let (fast, fast_hits) = replica("fast", 0, true);
+    let (slow, slow_hits) = replica("slow", 200, true);
+    let res = sys.run_until_complete(
+        actix::fut::hedge(fast.call_fut(Get), move || slow.call_fut(Get), delay));
+    assert_eq!(res, Ok(Ok("fast")));
+    assert_eq!(fast_hits.load(Ordering::Relaxed), 1);
+    assert_eq!(slow_hits.load(Ordering::Relaxed), 0);
+
+    // both fail, last failure wins
+    let (first, _) = replica("first", 50, false);
+    let (second, _) = replica("second", 0, false);
+    let res = sys.run_until_complete(
+        actix::fut::hedge(first.call_fut(Get), move || second.call_fut(Get), delay));
+    assert_eq!(res, Ok(Err("first")));
+}
+
+struct ListRooms;
+
+impl ResponseType for ListRooms {
+    type Item = Vec<String>;
+    type Error = ();
+}
+
+struct Rooms;
+
+impl Actor for Rooms {
+    type Context = Context<Self>;
+}
+
+impl Handler<ListRooms> for Rooms {
+    type Result = ResponseActFuture<Self, ListRooms>;
+
+    fn handle(&mut self, _: ListRooms, _: &mut Context<Self>) -> Self::Result {
+        Box::new(actix::fut::iterate((0..1_000_000).map(|i| i.to_string()), 10_000))
+    }
+}
+
+#[test]
+fn test_iterate() {
+    let mut sys = System::new("test");
+    let ticks = Rc::new(RefCell::new(Vec::new()));
+
+    let t = Rc::clone(&ticks);
+    Arbiter::handle().spawn(
+        Interval::new(Duration::from_millis(5), Arbiter::handle()).unwrap()
+            .for_each(move |_| {
+                t.borrow_mut().push(Instant::now());
+                Ok(())
+            })
+            .map_err(|_| ()));
+
+    let addr: Address<_> = Rooms.start();
+    let start = Instant::now();
+    let rooms = sys.run_until_complete(addr.call_fut(ListRooms)).unwrap().unwrap();
+    let end = Instant::now();
+
+    assert_eq!(rooms.len(), 1_000_000);
+    assert!(rooms.iter().enumerate().all(|(i, room)| *room == i.to_string()));
+
+    // timer keeps firing while response is built
+    let mut points = vec![start];
+    points.extend(ticks.borrow().iter().filter(|t| **t > start && **t < end));
+    points.push(end);
+    let max_gap = points.windows(2).map(|w| w[1] - w[0]).max().unwrap();
+    assert!(max_gap < Duration::from_millis(50), "{:?}", max_gap);
+}
@@ -0,0 +1,109 @@
+extern crate actix;
+extern crate futures;
+
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicBool, Ordering};
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::Handoff;
+use actix::msgs::SystemExit;
+
+struct Get(usize);
+
+impl ResponseType for Get {
+    type Item = (usize, &'static str);
+    type Error = ();
+}
+
+struct Other;
+
+impl ResponseType for Other {
+    type Item = ();
+    type Error = ();
+}
+
+struct Old {
+    stopped: Arc<AtomicBool>,
+}
+
+impl Actor for Old {
+    type Context = Context<Self>;
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        self.stopped.store(true, Ordering::Relaxed);
+    }
+}
+
+impl Handler<Get> for Old {
+    type Result = MessageResult<Get>;
+
+    fn handle(&mut self, msg: Get, _: &mut Context<Self>) -> Self::Result {
+        Ok((msg.0, "old"))
+    }
+}
+
+impl Handler<Other> for Old {
+    type Result = ();
+
+    fn handle(&mut self, _: Other, _: &mut Context<Self>) {}
+}
+
+struct New {
+    handled: Arc<Mutex<Vec<usize>>>,
+}
+
+impl Actor for New {
+    type Context = Context<Self>;
+}
+
+impl Handler<Get> for New {
+    type Result = MessageResult<Get>;
+
+    fn handle(&mut self, msg: Get, _: &mut Context<Self>) -> Self::Result {
+        self.handled.lock().unwrap().push(msg.0);
+        Ok((msg.0, "new"))
+    }
+}
+
+#[test]
+fn test_handoff() {
+    let sys = System::new("test");
+    let stopped = Arc::new(AtomicBool::new(false));
+    let handled = Arc::new(Mutex::new(Vec::new()));
+
+    let (old_local, old): (Address<_>, SyncAddress<_>) =
+        Old{stopped: Arc::clone(&stopped)}.start();
+    let new: SyncAddress<_> = New{handled: Arc::clone(&handled)}.start();
+
+    // actor is not started yet, all messages stay in the mailbox
+    let remote: Vec<_> = (0..10).map(|i| old.call_fut(Get(i))).collect();
+    let local: Vec<_> = (10..12).map(|i| old_local.call_fut(Get(i))).collect();
+    let other = old.call_fut(Other);
+    old.handoff_to(Handoff::new(new).route::<Get>()).unwrap();
+
+    let results = Arc::new(Mutex::new(None));
+    let res = Arc::clone(&results);
+    Arbiter::handle().spawn(
+        future::join_all(remote)
+            .join(future::join_all(local))
+            .map_err(|e| panic!("request failed: {}", e))
+            .join(other.then(|res| Ok(res.is_err())))
+            .then(move |r: Result<_, ()>| {
+                *res.lock().unwrap() = Some(r.unwrap());
+                Arbiter::system().do_send(SystemExit(0));
+                Ok(())
+            }));
+    sys.run();
+
+    let ((remote, local), other_failed) = results.lock().unwrap().take().unwrap();
+    let expected: Vec<_> = (0..10).map(|i| Ok((i, "new"))).collect();
+    assert_eq!(remote, expected);
+    assert_eq!(local, vec![Ok((10, "new")), Ok((11, "new"))]);
+    assert!(other_failed);
+
+    // queued sync messages keep order
+    let handled = handled.lock().unwrap();
+    let remote_order: Vec<_> = handled.iter().cloned().filter(|i| *i < 10).collect();
+    assert_eq!(remote_order, (0..10).collect::<Vec<_>>());
+    assert!(stopped.load(Ordering::Relaxed));
+}
@@ -0,0 +1,120 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::sync::{Arc, Mutex};
+use std::time::{Duration, Instant};
+use futures::{future, Future};
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::{LazySend, Subscriber};
+use actix::msgs::SystemExit;
+
+struct Num(usize);
+
+impl ResponseType for Num {
+    type Item = ();
+    type Error = ();
+}
+
+/// Actor does not handle messages for `block` period after start
+struct Blocked {
+    block: Option<Duration>,
+    msgs: Arc<Mutex<Vec<usize>>>,
+}
+
+impl Actor for Blocked {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        let fut: Box<Future<Item=(), Error=()>> = match self.block {
+            Some(dur) => Box::new(Timeout::new(dur, Arbiter::handle()).unwrap().map_err(|_| ())),
+            None => Box::new(future::empty()),
+        };
+        AsyncContext::wait(ctx, actix::fut::wrap_future(fut));
+    }
+}
+
+impl Handler<Num> for Blocked {
+    type Result = ();
+
+    fn handle(&mut self, msg: Num, _: &mut Context<Self>) {
+        self.msgs.lock().unwrap().push(msg.0);
+    }
+}
+
+#[test]
+fn test_send_lazy_delivered() {
+    let sys = System::new("test");
+    let msgs = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: Address<_> = Blocked::build()
+        .mailbox_capacity(3)
+        .start(Blocked{block: Some(Duration::from_millis(50)), msgs: Arc::clone(&msgs)});
+    let sub = addr.into_subscriber::<Num>();
+
+    // mailbox has room, message is delivered right away
+    let first = sub.send_lazy(Num(0));
+    assert!(sub.try_send_nowait(Num(1)).is_ok());
+    assert!(sub.try_send_nowait(Num(2)).is_ok());
+
+    // mailbox is full, messages wait until actor is unblocked
+    let handles: Vec<_> = (3..6).map(|i| sub.send_lazy(Num(i))).collect();
+
+    let res = Arc::new(Mutex::new(None));
+    let res2 = Arc::clone(&res);
+    Arbiter::handle().spawn(
+        first.join(future::join_all(handles)).then(move |r| {
+            *res2.lock().unwrap() = Some(r.unwrap());
+            Arbiter::system().do_send(SystemExit(0));
+            Ok(())
+        }));
+    sys.run();
+
+    let (first, rest) = res.lock().unwrap().take().unwrap();
+    assert_eq!(first, LazySend::Delivered);
+    assert_eq!(rest, vec![LazySend::Delivered; 3]);
+    assert_eq!(*msgs.lock().unwrap(), vec![0, 1, 2, 3, 4, 5]);
+}
+
+#[test]
+fn test_send_lazy_dropped() {
+    let sys = System::new("test");
+    let msgs = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: SyncAddress<_> = Blocked::build()
+        .mailbox_capacity(1)
+        .start(Blocked{block: None, msgs: Arc::clone(&msgs)});
+    let sub = addr.into_subscriber::<Num>();
+    assert!(sub.try_send_nowait(Num(0)).is_ok());
+
+    let start = Instant::now();
+    let res = Arc::new(Mutex::new(None));
+    let res2 = Arc::clone(&res);
+    Arbiter::handle().spawn(
+        sub.send_lazy_timeout(Num(1), Duration::from_millis(100))
+            .join(sub.send_lazy_timeout(Num(2), Duration::from_millis(100)))
+            .then(move |r| {
+                *res2.lock().unwrap() = Some((r.unwrap(), start.elapsed()));
+                Arbiter::system().do_send(SystemExit(0));
+                Ok(())
+            }));
+    sys.run();
+
+    let (res, elapsed) = res.lock().unwrap().take().unwrap();
+    assert_eq!(res, (LazySend::Dropped, LazySend::Dropped));
+    // every queued message gets its own grace period
+    assert!(elapsed >= Duration::from_millis(200));
+    assert!(msgs.lock().unwrap().is_empty());
+}
+
+#[test]
+fn test_send_lazy_closed() {
+    let sys = System::new("test");
+    let addr: SyncAddress<_> = Blocked{block: None, msgs: Arc::default()}.start();
+    let sub = addr.into_subscriber::<Num>();
+    drop(sys);
+
+    // actor is dropped with system
+    assert_eq!(sub.send_lazy(Num(0)).wait(), Ok(LazySend::Closed));
+}
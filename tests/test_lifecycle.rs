@@ -4,7 +4,7 @@ extern crate actix;
 extern crate futures;
 extern crate tokio_core;
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicBool, Ordering};
 use std::time::Duration;
 use futures::{future, Future};
@@ -93,7 +93,7 @@ fn test_active_address() {
     Arbiter::handle().spawn(
         Timeout::new(Duration::new(0, 100), Arbiter::handle()).unwrap()
             .then(|_| {
-                Arbiter::system().send(SystemExit(0));
+                Arbiter::system().do_send(SystemExit(0));
                 future::result(Ok(()))
             })
     );
@@ -122,7 +122,7 @@ fn test_active_sync_address() {
     Arbiter::handle().spawn(
         Timeout::new(Duration::new(0, 100), Arbiter::handle()).unwrap()
             .then(|_| {
-                Arbiter::system().send(SystemExit(0));
+                Arbiter::system().do_send(SystemExit(0));
                 future::result(Ok(()))
             })
     );
@@ -162,7 +162,7 @@ fn test_stop_after_drop_address() {
                 drop(addr);
                 Timeout::new(Duration::new(0, 10_000), Arbiter::handle()).unwrap()
                     .then(|_| {
-                        Arbiter::system().send(SystemExit(0));
+                        Arbiter::system().do_send(SystemExit(0));
                         future::result(Ok(()))
                     })
             })
@@ -201,7 +201,7 @@ fn test_stop_after_drop_sync_address() {
         Timeout::new(Duration::new(0, 100), Arbiter::handle()).unwrap()
             .then(move |_| {
                 drop(addr);
-                Arbiter::system().send(SystemExit(0));
+                Arbiter::system().do_send(SystemExit(0));
                 future::result(Ok(()))
             })
     });
@@ -244,7 +244,7 @@ fn test_stop_after_drop_sync_actor() {
 
                 Timeout::new(Duration::from_secs(2), Arbiter::handle()).unwrap()
                     .then(move |_| {
-                        Arbiter::system().send(SystemExit(0));
+                        Arbiter::system().do_send(SystemExit(0));
                         future::result(Ok(()))
                     })
             })
@@ -274,7 +274,7 @@ fn test_stop() {
     Arbiter::handle().spawn(
         Timeout::new(Duration::new(0, 100), Arbiter::handle()).unwrap()
             .then(|_| {
-                Arbiter::system().send(SystemExit(0));
+                Arbiter::system().do_send(SystemExit(0));
                 future::result(Ok(()))
             })
     );
@@ -303,7 +303,7 @@ fn test_stop_restore_after_stopping() {
     Arbiter::handle().spawn(
         Timeout::new(Duration::new(0, 100), Arbiter::handle()).unwrap()
             .then(|_| {
-                Arbiter::system().send(SystemExit(0));
+                Arbiter::system().do_send(SystemExit(0));
                 future::result(Ok(()))
             })
     );
@@ -313,3 +313,337 @@ fn test_stop_restore_after_stopping() {
     assert!(stopping.load(Ordering::Relaxed), "Not stopping");
     assert!(!stopped.load(Ordering::Relaxed), "Stopped");
 }
+
+struct Ping;
+
+impl ResponseType for Ping {
+    type Item = ();
+    type Error = ();
+}
+
+struct Sleeper {
+    buf: Vec<u8>,
+    events: Arc<Mutex<Vec<String>>>,
+}
+
+impl Actor for Sleeper {
+    type Context = actix::Context<Self>;
+
+    fn hibernate(&mut self, ctx: &mut Self::Context) {
+        assert!(!ctx.hibernated());
+        self.buf = Vec::new();
+        self.events.lock().unwrap().push(format!("hibernate {}", self.buf.capacity()));
+    }
+
+    fn unhibernate(&mut self, _: &mut Self::Context) {
+        self.buf.reserve(1024);
+        self.events.lock().unwrap().push("unhibernate".to_owned());
+    }
+}
+
+impl actix::Handler<Ping> for Sleeper {
+    type Result = ();
+
+    fn handle(&mut self, _: Ping, ctx: &mut Self::Context) {
+        assert!(!ctx.hibernated());
+        self.events.lock().unwrap().push(format!("ping {}", self.buf.capacity() >= 1024));
+    }
+}
+
+#[test]
+fn test_hibernate() {
+    let sys = System::new("test");
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: Address<_> = Sleeper::build()
+        .hibernate_after(Duration::from_millis(10))
+        .start(Sleeper{buf: Vec::with_capacity(1024), events: Arc::clone(&events)});
+    addr.do_send(Ping);
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(100), Arbiter::handle()).unwrap()
+            .then(move |_| addr.call_fut(Ping))
+            .then(|res| {
+                assert!(res.unwrap().is_ok());
+                Arbiter::system().do_send(SystemExit(0));
+                future::result(Ok(()))
+            })
+    );
+
+    sys.run();
+    assert_eq!(*events.lock().unwrap(),
+               vec!["ping true", "hibernate 0", "unhibernate", "ping true"]);
+}
+
+struct Dormant(Arc<Mutex<Vec<&'static str>>>);
+
+impl Actor for Dormant {
+    type Context = actix::Context<Self>;
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        self.0.lock().unwrap().push("stopped");
+    }
+
+    fn hibernate(&mut self, _: &mut Self::Context) {
+        self.0.lock().unwrap().push("hibernate");
+    }
+
+    fn unhibernate(&mut self, _: &mut Self::Context) {
+        self.0.lock().unwrap().push("unhibernate");
+    }
+}
+
+#[test]
+fn test_hibernate_stop_without_message() {
+    let sys = System::new("test");
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    // graceful stop of the arbiter wakes hibernated actor, but it is
+    // not mailbox activity, actor stops without being restored
+    let events2 = Arc::clone(&events);
+    let _addr: SyncAddress<_> = Dormant::build()
+        .hibernate_after(Duration::from_millis(10))
+        .on_arbiter(&Arbiter::new("dormant"))
+        .start_with(move |_| Dormant(events2));
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(100), Arbiter::handle()).unwrap()
+            .then(|_| {
+                Arbiter::system().do_send(SystemExit(0));
+                future::result(Ok(()))
+            })
+    );
+
+    sys.run();
+    assert_eq!(*events.lock().unwrap(), vec!["hibernate", "stopped"]);
+}
+
+#[test]
+fn test_hibernate_disabled() {
+    let sys = System::new("test");
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let addr: Address<_> = Sleeper{
+        buf: Vec::with_capacity(1024), events: Arc::clone(&events)}.start();
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(50), Arbiter::handle()).unwrap()
+            .then(move |_| addr.call_fut(Ping))
+            .then(|res| {
+                assert!(res.unwrap().is_ok());
+                Arbiter::system().do_send(SystemExit(0));
+                future::result(Ok(()))
+            })
+    );
+
+    sys.run();
+    assert_eq!(*events.lock().unwrap(), vec!["ping true"]);
+}
+
+struct Event(&'static str);
+
+impl ResponseType for Event {
+    type Item = ();
+    type Error = ();
+}
+
+struct Scripted(Arc<Mutex<Vec<&'static str>>>);
+
+impl Actor for Scripted {
+    type Context = actix::Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        self.0.lock().unwrap().push("started");
+        ctx.notify(Event("init 2"));
+
+        let log = Arc::clone(&self.0);
+        Timeout::new(Duration::new(0, 1_000), Arbiter::handle()).unwrap()
+            .map(move |_| log.lock().unwrap().push("wait"))
+            .map_err(|_| ())
+            .into_actor(self)
+            .wait(ctx);
+    }
+}
+
+impl actix::Handler<Event> for Scripted {
+    type Result = ();
+
+    fn handle(&mut self, msg: Event, ctx: &mut Self::Context) {
+        self.0.lock().unwrap().push(msg.0);
+        if msg.0 == "init 2" {
+            ctx.notify(Event("init 3"));
+        }
+        if msg.0 == "external" {
+            ctx.notify(Event("notify"));
+        }
+    }
+}
+
+impl StreamHandler<&'static str, ()> for Scripted {
+    fn handle(&mut self, item: &'static str, _: &mut Self::Context) {
+        self.0.lock().unwrap().push(item);
+    }
+
+    fn finished(&mut self, _: &mut Self::Context) {
+        Arbiter::system().do_send(SystemExit(0));
+    }
+}
+
+#[test]
+fn test_initialization_order() {
+    let sys = System::new("test");
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let act_log = Arc::clone(&log);
+    let addr: Address<_> = Scripted::create(move |ctx| {
+        Scripted::add_stream(futures::stream::iter_ok(vec!["stream"]), ctx);
+        ctx.notify(Event("init 1"));
+        Scripted(act_log)
+    });
+    addr.do_send(Event("external"));
+
+    sys.run();
+    assert_eq!(*log.lock().unwrap(),
+               vec!["started", "wait", "init 1", "init 2", "init 3",
+                    "external", "stream", "notify"]);
+}
+
+struct Resurrect {
+    sync: bool,
+    resurrected: Arc<Mutex<usize>>,
+    delivered: Arc<Mutex<usize>>,
+}
+
+impl Actor for Resurrect {
+    type Context = actix::Context<Self>;
+
+    fn stopping(&mut self, ctx: &mut Self::Context) -> bool {
+        let mut resurrected = self.resurrected.lock().unwrap();
+        if *resurrected == 5 {
+            return true
+        }
+        *resurrected += 1;
+
+        // hand new address to someone else, it keeps actor alive for a while
+        let timeout = Timeout::new(Duration::new(0, 1_000), Arbiter::handle()).unwrap();
+        if self.sync {
+            let addr: SyncAddress<_> = ctx.address();
+            assert!(addr.connected());
+            addr.do_send(Event("resurrected"));
+            Arbiter::handle().spawn(timeout.then(move |_| {
+                assert!(addr.connected());
+                drop(addr);
+                Ok(())
+            }));
+        } else {
+            let addr: Address<_> = ctx.address();
+            assert!(addr.connected());
+            addr.do_send(Event("resurrected"));
+            Arbiter::handle().spawn(timeout.then(move |_| {
+                assert!(addr.connected());
+                drop(addr);
+                Ok(())
+            }));
+        }
+        false
+    }
+
+    fn stopped(&mut self, _: &mut Self::Context) {
+        Arbiter::system().do_send(SystemExit(0));
+    }
+}
+
+impl actix::Handler<Event> for Resurrect {
+    type Result = ();
+
+    fn handle(&mut self, _: Event, _: &mut Self::Context) {
+        *self.delivered.lock().unwrap() += 1;
+    }
+}
+
+fn resurrect(sync: bool) {
+    let sys = System::new("test");
+    let resurrected = Arc::new(Mutex::new(0));
+    let delivered = Arc::new(Mutex::new(0));
+
+    let _: () = Resurrect{sync: sync,
+                          resurrected: Arc::clone(&resurrected),
+                          delivered: Arc::clone(&delivered)}.start();
+
+    sys.run();
+    assert_eq!(*resurrected.lock().unwrap(), 5);
+    assert_eq!(*delivered.lock().unwrap(), 5);
+}
+
+#[test]
+fn test_resurrect_from_stopping() {
+    resurrect(false);
+}
+
+#[test]
+fn test_resurrect_from_stopping_sync() {
+    resurrect(true);
+}
+
+/// Actor waits for a timer in `started()`, or stops right away
+struct SlowStart {
+    ready: Arc<AtomicBool>,
+    abort: bool,
+}
+
+impl Actor for SlowStart {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Self::Context) {
+        if self.abort {
+            ctx.stop();
+            return
+        }
+        Timeout::new(Duration::from_millis(100), Arbiter::handle()).unwrap()
+            .map_err(|_| ())
+            .into_actor(self)
+            .map(|_, act, _| act.ready.store(true, Ordering::Relaxed))
+            .wait(ctx);
+    }
+}
+
+#[test]
+fn test_start_notified() {
+    let sys = System::new("test");
+    let ready = Arc::new(AtomicBool::new(false));
+    let resolved = Arc::new(AtomicBool::new(false));
+
+    let (_addr, started): (Address<_>, _) =
+        SlowStart{ready: Arc::clone(&ready), abort: false}.start_notified();
+    let (ready2, resolved2) = (Arc::clone(&ready), Arc::clone(&resolved));
+    Arbiter::handle().spawn(started.then(move |res| {
+        assert!(res.is_ok());
+        // wait future of `started()` is complete
+        assert!(ready2.load(Ordering::Relaxed));
+        resolved2.store(true, Ordering::Relaxed);
+        Arbiter::system().do_send(SystemExit(0));
+        Ok(())
+    }));
+
+    sys.run();
+    assert!(resolved.load(Ordering::Relaxed));
+}
+
+#[test]
+fn test_create_notified_aborted() {
+    let sys = System::new("test");
+    let failed = Arc::new(AtomicBool::new(false));
+
+    let ready = Arc::new(AtomicBool::new(false));
+    let (_addr, started): (SyncAddress<_>, _) =
+        SlowStart::create_notified(move |_| SlowStart{ready: ready, abort: true});
+    let failed2 = Arc::clone(&failed);
+    Arbiter::handle().spawn(started.then(move |res| {
+        failed2.store(res.is_err(), Ordering::Relaxed);
+        Arbiter::system().do_send(SystemExit(0));
+        Ok(())
+    }));
+
+    sys.run();
+    assert!(failed.load(Ordering::Relaxed));
+}
@@ -0,0 +1,103 @@
+extern crate actix;
+extern crate bytes;
+extern crate futures;
+extern crate tokio_core;
+extern crate tokio_io;
+
+use std::io;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::time::Duration;
+use bytes::BytesMut;
+use futures::{future, Future};
+use futures::future::Loop;
+use tokio_core::reactor::Timeout;
+use tokio_io::AsyncRead;
+use tokio_io::codec::{Decoder, Encoder};
+use tokio_io::io::copy;
+use actix::prelude::*;
+use actix::{TcpServer, LoadGen, GetReport, LoadReport};
+
+const REQUESTS: usize = 500;
+
+#[derive(Clone)]
+struct LineCodec;
+
+impl Decoder for LineCodec {
+    type Item = String;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<String>, io::Error> {
+        if let Some(pos) = src.iter().position(|b| *b == b'\n') {
+            let line = src.split_to(pos + 1);
+            Ok(Some(String::from_utf8_lossy(&line[..pos]).into_owned()))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl Encoder for LineCodec {
+    type Item = String;
+    type Error = io::Error;
+
+    fn encode(&mut self, msg: String, dst: &mut BytesMut) -> Result<(), io::Error> {
+        dst.extend(msg.as_bytes());
+        dst.extend(b"\n");
+        Ok(())
+    }
+}
+
+#[test]
+fn test_loadgen_echo() {
+    let sys = System::new("test");
+
+    let server = TcpServer::new("127.0.0.1:0".parse().unwrap())
+        .start(|stream, _| {
+            let (reader, writer) = stream.split();
+            Arbiter::handle().spawn(copy(reader, writer).map(|_| ()).map_err(|_| ()));
+        }).unwrap();
+
+    let gen: Address<_> = LoadGen::new(
+        server.local_addr().to_string(), LineCodec, |n| format!("request {}", n))
+        .connections(10)
+        .rate(10_000)
+        .requests(REQUESTS)
+        .pipeline_depth(4)
+        .start();
+
+    // poll report until every request is answered
+    let report = Rc::new(RefCell::new(None));
+    let result = Rc::clone(&report);
+    Arbiter::handle().spawn(future::loop_fn((), move |_| {
+        let gen = gen.clone();
+        let result = Rc::clone(&result);
+        Timeout::new(Duration::from_millis(20), Arbiter::handle()).unwrap()
+            .map_err(|_| ())
+            .and_then(move |_| gen.call_fut(GetReport).then(|res| match res {
+                Ok(Ok(report)) => Ok(report),
+                _ => Err(()),
+            }))
+            .map(move |report: LoadReport| {
+                if report.received + report.errors < REQUESTS {
+                    Loop::Continue(())
+                } else {
+                    *result.borrow_mut() = Some(report);
+                    Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                    Loop::Break(())
+                }
+            })
+    }));
+
+    sys.run();
+    let report = report.borrow_mut().take().unwrap();
+    assert_eq!(report.sent, REQUESTS);
+    assert_eq!(report.received, REQUESTS);
+    assert_eq!(report.errors, 0);
+    assert_eq!(report.in_flight, 0);
+    assert_eq!(report.connects, 10);
+    assert!(report.p50 <= report.p90);
+    assert!(report.p90 <= report.p99);
+    assert!(report.p99 <= report.max);
+    assert!(report.max > Duration::from_millis(0));
+}
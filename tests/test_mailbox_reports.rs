@@ -0,0 +1,103 @@
+extern crate actix;
+extern crate futures;
+
+use std::thread;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use actix::prelude::*;
+use actix::msgs::{MailboxStatus, SystemExit};
+
+struct Work;
+
+impl ResponseType for Work {
+    type Item = ();
+    type Error = ();
+}
+
+/// Every handled `Work` message adds two more, so mailbox keeps growing
+struct Busy {
+    reports: Arc<Mutex<Vec<MailboxStatus>>>,
+    expected: usize,
+    handle: Option<SpawnHandle>,
+}
+
+impl Actor for Busy {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.set_poll_budget(2);
+        self.handle = Some(ctx.enable_mailbox_reports(Duration::from_millis(10)));
+    }
+}
+
+impl Handler<Work> for Busy {
+    type Result = ();
+
+    fn handle(&mut self, _: Work, ctx: &mut Context<Self>) {
+        thread::sleep(Duration::from_millis(2));
+        let addr: Address<_> = ctx.address();
+        addr.do_send(Work);
+        addr.do_send(Work);
+    }
+}
+
+impl Handler<MailboxStatus> for Busy {
+    type Result = ();
+
+    fn handle(&mut self, msg: MailboxStatus, ctx: &mut Context<Self>) {
+        let mut reports = self.reports.lock().unwrap();
+        reports.push(msg);
+        if reports.len() == self.expected {
+            if let Some(handle) = self.handle.take() {
+                ctx.cancel_future(handle);
+            }
+            // no more reports after cancellation
+            ctx.run_later(Duration::from_millis(50), |_, _| {
+                Arbiter::system().do_send(SystemExit(0));
+            });
+        }
+    }
+}
+
+fn start(expected: usize, work: bool) -> Arc<Mutex<Vec<MailboxStatus>>> {
+    let reports = Arc::new(Mutex::new(Vec::new()));
+    let addr: Address<_> = Busy::build()
+        .mailbox_capacity(10_000)
+        .start(Busy{reports: Arc::clone(&reports), expected, handle: None});
+    if work {
+        addr.do_send(Work);
+    }
+    reports
+}
+
+#[test]
+fn test_reports_under_backlog() {
+    let sys = System::new("test");
+    let reports = start(5, true);
+    sys.run();
+
+    let reports = reports.lock().unwrap();
+    assert_eq!(reports.len(), 5);
+    for report in reports.iter() {
+        assert_eq!(report.capacity, 10_000);
+        assert!(report.len > 0);
+        assert!(report.oldest_age > Duration::from_millis(0));
+    }
+    for pair in reports.windows(2) {
+        assert!(pair[1].len > pair[0].len, "mailbox does not grow: {:?}", *reports);
+    }
+}
+
+#[test]
+fn test_reports_empty_mailbox() {
+    let sys = System::new("test");
+    let reports = start(3, false);
+    sys.run();
+
+    let reports = reports.lock().unwrap();
+    assert_eq!(reports.len(), 3);
+    for report in reports.iter() {
+        assert_eq!(*report, MailboxStatus{
+            len: 0, capacity: 10_000, oldest_age: Duration::from_millis(0)});
+    }
+}
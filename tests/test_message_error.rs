@@ -0,0 +1,103 @@
+#[macro_use]
+extern crate actix;
+extern crate futures;
+
+use std::{env, fs, io};
+use std::io::Read;
+use std::num::ParseIntError;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::IntoMessageError;
+use actix::msgs::SystemExit;
+
+message_error! {
+    /// Errors of `Load` message
+    pub enum LoadError {
+        Io(io::Error),
+        Parse(ParseIntError),
+    }
+}
+
+/// Read number from file
+struct Load(PathBuf);
+
+impl ResponseType for Load {
+    type Item = u32;
+    type Error = LoadError;
+}
+
+struct Store;
+
+impl Actor for Store {
+    type Context = Context<Self>;
+}
+
+impl Handler<Load> for Store {
+    type Result = MessageResult<Load>;
+
+    fn handle(&mut self, msg: Load, _: &mut Context<Self>) -> Self::Result {
+        let mut s = String::new();
+        fs::File::open(&msg.0)?.read_to_string(&mut s)?;
+        Ok(s.trim().parse()?)
+    }
+}
+
+fn file(name: &str, content: &str) -> PathBuf {
+    let path = env::temp_dir().join(format!("actix-{}-{}", name, std::process::id()));
+    fs::write(&path, content).unwrap();
+    path
+}
+
+#[test]
+fn test_message_error() {
+    let sys = System::new("test");
+    let number = file("number", "42\n");
+    let garbage = file("garbage", "forty two");
+    let missing = env::temp_dir().join("actix-missing-file");
+
+    let res = Arc::new(Mutex::new(None));
+    let res2 = Arc::clone(&res);
+    let addr: Address<_> = Store.start();
+    Arbiter::handle().spawn(
+        addr.call_fut(Load(number.clone()))
+            .join3(addr.call_fut(Load(garbage.clone())), addr.call_fut(Load(missing)))
+            .then(move |r| {
+                *res2.lock().unwrap() = Some(r.unwrap());
+                Arbiter::system().do_send(SystemExit(0));
+                future::ok(())
+            }));
+    sys.run();
+    let _ = fs::remove_file(number);
+    let _ = fs::remove_file(garbage);
+
+    let (number, garbage, missing) = res.lock().unwrap().take().unwrap();
+    assert_eq!(number.unwrap(), 42);
+    match garbage {
+        Err(LoadError::Parse(err)) => assert_eq!(err.to_string(), "invalid digit found in string"),
+        res => panic!("parse error expected: {:?}", res),
+    }
+    match missing {
+        Err(LoadError::Io(ref err)) if err.kind() == io::ErrorKind::NotFound => (),
+        res => panic!("io error expected: {:?}", res),
+    }
+}
+
+/// Generic helper converts any supported error into message's error
+fn load_err<M: ResponseType, E: IntoMessageError<M>>(err: E) -> MessageResult<M> {
+    Err(err.into_message_error())
+}
+
+#[test]
+fn test_into_message_error() {
+    let err = "x".parse::<u32>().unwrap_err();
+    match load_err::<Load, _>(err) {
+        Err(err @ LoadError::Parse(_)) => assert_eq!(err.to_string(), "invalid digit found in string"),
+        _ => panic!("parse error expected"),
+    }
+    match load_err::<Load, _>(io::Error::new(io::ErrorKind::Other, "disk")) {
+        Err(LoadError::Io(err)) => assert_eq!(err.to_string(), "disk"),
+        _ => panic!("io error expected"),
+    }
+}
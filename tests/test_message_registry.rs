@@ -0,0 +1,101 @@
+#[macro_use]
+extern crate actix;
+#[cfg(feature="serialize")]
+#[macro_use]
+extern crate serde_derive;
+
+use actix::{MessageRegistry, RegistryError};
+
+#[derive(Message)]
+struct Ping;
+
+#[derive(Message)]
+struct Pong;
+
+#[derive(Message)]
+struct Stats;
+
+#[test]
+fn test_register_messages() {
+    register_message!(Ping, "test.ping").unwrap();
+    register_message!(Pong).unwrap();
+    register_message!(Stats, "test.stats").unwrap();
+
+    // registration of the same type under the same name is no-op
+    register_message!(Pong).unwrap();
+
+    let names: Vec<_> = MessageRegistry::registered_messages()
+        .iter().map(|info| info.name()).collect();
+    for name in &["test.ping", "Pong", "test.stats"] {
+        assert!(names.contains(name), "{:?}", names);
+    }
+
+    let info = MessageRegistry::get("test.stats").unwrap();
+    assert!(info.type_name().ends_with("Stats"));
+    assert_eq!(info.type_id(), std::any::TypeId::of::<Stats>());
+    assert_eq!(MessageRegistry::name_of::<Pong>(), Some("Pong"));
+    assert!(MessageRegistry::get("test.unknown").is_none());
+}
+
+mod v1 {
+    #[derive(Message)]
+    pub struct Transfer;
+}
+
+mod v2 {
+    #[derive(Message)]
+    pub struct Transfer;
+}
+
+#[test]
+fn test_collision() {
+    register_message!(v1::Transfer, "test.transfer").unwrap();
+
+    match register_message!(v2::Transfer, "test.transfer") {
+        Err(err @ RegistryError::Collision{..}) => {
+            let msg = format!("{}", err);
+            assert!(msg.contains("v1::Transfer"), "{}", msg);
+            assert!(msg.contains("v2::Transfer"), "{}", msg);
+        }
+        res => panic!("collision is not detected: {:?}", res),
+    }
+    match register_message!(v1::Transfer, "test.transfer.v1") {
+        Err(RegistryError::Renamed{registered, ..}) => assert_eq!(registered, "test.transfer"),
+        res => panic!("rename is not detected: {:?}", res),
+    }
+
+    // first registration is preserved
+    let info = MessageRegistry::get("test.transfer").unwrap();
+    assert!(info.type_name().ends_with("v1::Transfer"));
+}
+
+#[cfg(feature="serialize")]
+#[test]
+fn test_deserialize() {
+    #[derive(Message, Deserialize, Debug, PartialEq)]
+    struct Sample {
+        seq: usize,
+    }
+
+    #[derive(Message, Deserialize)]
+    struct Quiet;
+
+    register_message!(deserialize Sample, "test.de.sample").unwrap();
+    register_message!(Quiet, "test.de.quiet").unwrap();
+
+    let msg = MessageRegistry::deserialize("test.de.sample", br#"{"seq": 5}"#).unwrap();
+    assert_eq!(*msg.downcast::<Sample>().unwrap(), Sample{seq: 5});
+
+    match MessageRegistry::deserialize("test.de.sample", b"[]") {
+        Err(RegistryError::Decode(name, _)) => assert_eq!(name, "test.de.sample"),
+        _ => panic!("payload is not valid"),
+    }
+    match MessageRegistry::deserialize("test.de.quiet", b"null") {
+        Err(RegistryError::NotDeserializable(name)) => assert_eq!(name, "test.de.quiet"),
+        _ => panic!("message is registered without deserializer"),
+    }
+    match MessageRegistry::deserialize("test.de.missing", b"null") {
+        Err(RegistryError::Unknown(name)) => assert_eq!(name, "test.de.missing"),
+        _ => panic!("message is not registered"),
+    }
+}
@@ -0,0 +1,90 @@
+#![cfg(feature="messages")]
+extern crate actix;
+extern crate bytes;
+extern crate futures;
+
+use bytes::Bytes;
+use futures::Future;
+use actix::prelude::*;
+use actix::messages::{Data, DataWithAddr};
+
+/// Payload with address of its buffer.
+///
+/// Type does not implement `Clone`, so it compiles only if
+/// addresses and channels move messages.
+struct Frame {
+    data: Bytes,
+    ptr: usize,
+}
+
+impl Frame {
+    fn new(size: usize) -> Frame {
+        let data = Bytes::from(vec![7u8; size]);
+        let ptr = data.as_ptr() as usize;
+        Frame{data: data, ptr: ptr}
+    }
+}
+
+impl ResponseType for Frame {
+    type Item = bool;
+    type Error = ();
+}
+
+/// Replies whether payload buffer is the original one
+struct Sink;
+
+impl Actor for Sink {
+    type Context = Context<Self>;
+}
+
+impl Handler<Frame> for Sink {
+    type Result = MessageResult<Frame>;
+
+    fn handle(&mut self, msg: Frame, _: &mut Context<Self>) -> Self::Result {
+        Ok(msg.data.as_ptr() as usize == msg.ptr)
+    }
+}
+
+impl Handler<Data> for Sink {
+    type Result = ();
+
+    fn handle(&mut self, msg: Data, _: &mut Context<Self>) {
+        assert_eq!(msg.0.len(), 64 * 1024);
+    }
+}
+
+impl Handler<DataWithAddr> for Sink {
+    type Result = ();
+
+    fn handle(&mut self, msg: DataWithAddr, _: &mut Context<Self>) {
+        assert_eq!(&msg.0[..], b"datagram");
+        assert_eq!(msg.1, "127.0.0.1:8080".parse().unwrap());
+    }
+}
+
+#[test]
+fn test_frames_are_moved() {
+    let sys = System::new("test");
+
+    let local: Address<_> = Sink.start();
+    let remote: SyncAddress<_> = Sink::build().on_arbiter(&Arbiter::new("sink")).start(Sink);
+
+    let data = Bytes::from(vec![0u8; 64 * 1024]);
+    local.do_send(Data(data.clone()));
+    remote.do_send(Data(data));
+    remote.do_send(DataWithAddr(Bytes::from_static(b"datagram"),
+                                "127.0.0.1:8080".parse().unwrap()));
+
+    Arbiter::handle().spawn(
+        local.call_fut(Frame::new(64 * 1024))
+            .join(remote.call_fut(Frame::new(64 * 1024)))
+            .then(|res| {
+                let (local, remote) = res.unwrap();
+                assert!(local.unwrap());
+                assert!(remote.unwrap());
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+}
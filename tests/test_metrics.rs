@@ -0,0 +1,144 @@
+#![cfg(feature="metrics")]
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::thread;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::io::Read;
+use std::net::{TcpListener, TcpStream};
+use std::time::Duration;
+use futures::{future, Future};
+use futures::future::Loop;
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::actors::metrics::{Metric, MetricsSource, MetricsExporter, RegisterSource,
+                             GetMetricsText, WriteMetrics};
+use actix::msgs::SystemExit;
+
+struct Work;
+
+impl ResponseType for Work {
+    type Item = ();
+    type Error = ();
+}
+
+struct Worker;
+
+impl Actor for Worker {
+    type Context = SyncContext<Self>;
+}
+
+impl Handler<Work> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: Work, _: &mut SyncContext<Self>) {}
+}
+
+struct Fake;
+
+impl MetricsSource for Fake {
+    fn collect(&self) -> Vec<Metric> {
+        vec![
+            Metric::counter("fake_requests_total", 7.0)
+                .help("Fake requests")
+                .label("actor", "Fake")
+                .label("path", "a\"b"),
+            Metric::counter("fake_requests_total", 3.0)
+                .label("actor", "Other")
+                .label("path", "/"),
+            Metric::gauge("fake_load", 0.5),
+        ]
+    }
+}
+
+#[test]
+fn test_metrics_text() {
+    let sys = System::new("test");
+    let arbiter = Arbiter::name();
+    let addr = SyncArbiter::start(2, || Worker);
+    let labels = format!("{{actor=\"test_metrics::Worker\",arbiter=\"{}\"}}", arbiter);
+    let handled = format!("actix_sync_arbiter_handled_total{} 5", labels);
+    let text = Rc::new(RefCell::new(String::new()));
+
+    let result = Rc::clone(&text);
+    Arbiter::handle().spawn(
+        future::join_all((0..5).map(|_| addr.call_fut(Work)).collect::<Vec<_>>())
+            .map_err(|_| ())
+            .and_then(|_| {
+                MetricsExporter::from_registry().do_send(RegisterSource(Box::new(Fake)));
+
+                // worker counts message after response is sent
+                future::loop_fn((), move |_| {
+                    let handled = handled.clone();
+                    let result = Rc::clone(&result);
+                    Timeout::new(Duration::from_millis(10), Arbiter::handle()).unwrap()
+                        .map_err(|_| ())
+                        .and_then(|_| MetricsExporter::from_registry().call_fut(GetMetricsText)
+                                  .then(|res| match res {
+                                      Ok(Ok(text)) => Ok(text),
+                                      _ => Err(()),
+                                  }))
+                        .map(move |text| {
+                            let done = text.lines().any(|line| line == handled);
+                            *result.borrow_mut() = text;
+                            if done { Loop::Break(()) } else { Loop::Continue(()) }
+                        })
+                })
+            })
+            .map(|_| Arbiter::system().do_send(SystemExit(0))));
+    sys.run();
+
+    let text = text.borrow();
+    let lines: Vec<_> = text.lines().collect();
+
+    for line in &[
+        "# HELP fake_requests_total Fake requests".to_owned(),
+        "# TYPE fake_requests_total counter".to_owned(),
+        "fake_requests_total{actor=\"Fake\",path=\"a\\\"b\"} 7".to_owned(),
+        "fake_requests_total{actor=\"Other\",path=\"/\"} 3".to_owned(),
+        "# TYPE fake_load gauge".to_owned(),
+        "fake_load 0.5".to_owned(),
+        "# TYPE actix_sync_arbiter_handled_total counter".to_owned(),
+        format!("actix_sync_arbiter_handled_total{} 5", labels),
+        format!("actix_sync_arbiter_threads{} 2", labels),
+        format!("actix_sync_arbiter_queued_messages{} 0", labels),
+        "# TYPE actix_dead_letters_total counter".to_owned(),
+    ] {
+        assert!(lines.contains(&line.as_str()), "{:?} is missing in:\n{}", line, *text);
+    }
+
+    // family header goes once, before its samples
+    let header = lines.iter().position(|l| *l == "# TYPE fake_requests_total counter").unwrap();
+    assert_eq!(lines.iter().filter(|l| l.starts_with("# TYPE fake_requests_total")).count(), 1);
+    assert!(lines[header + 1].starts_with("fake_requests_total{actor=\"Fake\""));
+    assert!(lines[header + 2].starts_with("fake_requests_total{actor=\"Other\""));
+}
+
+#[test]
+fn test_metrics_write() {
+    let sys = System::new("test");
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let client = TcpStream::connect(listener.local_addr().unwrap()).unwrap();
+    let (stream, _) = listener.accept().unwrap();
+
+    let reader = thread::spawn(move || {
+        let mut client = client;
+        let mut text = String::new();
+        client.read_to_string(&mut text).unwrap();
+        text
+    });
+
+    Arbiter::handle().spawn(
+        MetricsExporter::from_registry().call_fut(WriteMetrics(stream))
+            .then(|res| {
+                res.unwrap().unwrap();
+                Arbiter::system().do_send(SystemExit(0));
+                Ok(())
+            }));
+    sys.run();
+
+    let text = reader.join().unwrap();
+    assert!(text.contains("# TYPE actix_abandoned_responses_total counter\n"));
+}
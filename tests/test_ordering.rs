@@ -0,0 +1,156 @@
+#[macro_use] extern crate actix;
+extern crate futures;
+
+use std::thread;
+use std::sync::{Arc, Mutex};
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::msgs::{Execute, SystemExit};
+
+const SENDERS: usize = 8;
+const MESSAGES: usize = 2000;
+const SYSTEM_SENDER: usize = usize::max_value();
+
+#[derive(Message)]
+#[rtype(usize)]
+struct Seq {
+    sender: usize,
+    seq: usize,
+}
+
+/// Records every handled message, stops system after `expected` messages
+struct Collector {
+    log: Arc<Mutex<Vec<(usize, usize)>>>,
+    expected: usize,
+}
+
+impl Actor for Collector {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        // senders flood mailbox, let context yield between batches
+        ctx.set_poll_budget(128);
+    }
+}
+
+impl Handler<Seq> for Collector {
+    type Result = MessageResult<Seq>;
+
+    fn handle(&mut self, msg: Seq, _: &mut Context<Self>) -> Self::Result {
+        let mut log = self.log.lock().unwrap();
+        log.push((msg.sender, msg.seq));
+        if log.len() == self.expected {
+            Arbiter::system().do_send(SystemExit(0));
+        }
+        Ok(msg.seq)
+    }
+}
+
+fn collector(capacity: usize, expected: usize) -> (SyncAddress<Collector>, Arc<Mutex<Vec<(usize, usize)>>>) {
+    let log = Arc::new(Mutex::new(Vec::new()));
+    let addr: SyncAddress<_> = Collector::build()
+        .mailbox_capacity(capacity)
+        .start(Collector{log: Arc::clone(&log), expected});
+    (addr, log)
+}
+
+/// Every sender's messages are handled in send order
+fn assert_fifo(log: &[(usize, usize)], senders: usize, messages: usize) {
+    let mut next = vec![0; senders];
+    for &(sender, seq) in log {
+        assert_eq!(seq, next[sender], "sender {} is out of order", sender);
+        next[sender] += 1;
+    }
+    assert_eq!(next, vec![messages; senders]);
+}
+
+#[test]
+fn test_same_sender_fifo_across_arbiters() {
+    let sys = System::new("test");
+    let (addr, log) = collector(16, SENDERS * MESSAGES);
+
+    for sender in 0..SENDERS {
+        let addr = addr.clone();
+        let arbiter = Arbiter::new(format!("sender-{}", sender));
+        arbiter.do_send(Execute::new(move || -> Result<(), ()> {
+            // address clones share sender's order
+            let clone = addr.clone();
+            for seq in 0..MESSAGES {
+                let msg = Seq{sender, seq};
+                match seq % 3 {
+                    0 => addr.do_send(msg),
+                    1 => clone.do_send(msg),
+                    _ => if let Err(err) = addr.try_send(msg) {
+                        clone.do_send(err.into_inner())
+                    },
+                }
+            }
+            Ok(())
+        }));
+    }
+
+    sys.run();
+    assert_fifo(&log.lock().unwrap(), SENDERS, MESSAGES);
+}
+
+#[test]
+fn test_parked_senders_fifo() {
+    let sys = System::new("test");
+    let (addr, log) = collector(1, SENDERS * MESSAGES);
+
+    for sender in 0..SENDERS {
+        let addr = addr.clone();
+        thread::spawn(move || {
+            for seq in 0..MESSAGES {
+                let msg = Seq{sender, seq};
+                if seq % 2 == 0 {
+                    addr.send(msg).wait().unwrap();
+                } else {
+                    assert_eq!(addr.call_fut(msg).wait().unwrap(), Ok(seq));
+                }
+            }
+        });
+    }
+
+    sys.run();
+    assert_fifo(&log.lock().unwrap(), SENDERS, MESSAGES);
+}
+
+#[test]
+fn test_call_send_interleaving() {
+    let sys = System::new("test");
+    let (addr, log) = collector(MESSAGES, MESSAGES);
+
+    thread::spawn(move || {
+        let mut calls = Vec::new();
+        for seq in 0..MESSAGES {
+            let msg = Seq{sender: 0, seq};
+            if seq % 2 == 0 {
+                addr.do_send(msg);
+            } else {
+                calls.push(addr.call_fut(msg).map(move |res| assert_eq!(res, seq)));
+            }
+        }
+        future::join_all(calls).wait().unwrap();
+    });
+
+    sys.run();
+    assert_fifo(&log.lock().unwrap(), 1, MESSAGES);
+}
+
+#[test]
+fn test_system_lane_first() {
+    let sys = System::new("test");
+    let (addr, log) = collector(16, 11);
+
+    // actor is not started yet, all messages are pending
+    for seq in 0..10 {
+        addr.do_send(Seq{sender: 0, seq});
+    }
+    addr.try_send_system(Seq{sender: SYSTEM_SENDER, seq: 0}).unwrap();
+
+    sys.run();
+    let log = log.lock().unwrap();
+    assert_eq!(log[0], (SYSTEM_SENDER, 0));
+    assert_fifo(&log[1..], 1, 10);
+}
@@ -0,0 +1,131 @@
+extern crate actix;
+extern crate futures;
+
+use std::fs;
+use std::path::PathBuf;
+use std::sync::{Arc, Mutex};
+use futures::Future;
+use actix::prelude::*;
+use actix::{FileStore, StateStore};
+use actix::msgs::SystemExit;
+
+struct Inc;
+
+impl ResponseType for Inc {
+    type Item = ();
+    type Error = ();
+}
+
+struct Shutdown;
+
+impl ResponseType for Shutdown {
+    type Item = ();
+    type Error = ();
+}
+
+/// Records count seen in first `started()`
+struct Counter {
+    count: u64,
+    started: Arc<Mutex<Option<u64>>>,
+}
+
+impl Actor for Counter {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _: &mut Context<Self>) {
+        // supervisor restarts stopped actor, keep first generation only
+        let mut started = self.started.lock().unwrap();
+        if started.is_none() {
+            *started = Some(self.count);
+        }
+    }
+}
+
+impl Supervised for Counter {
+    fn snapshot(&self) -> Option<Vec<u8>> {
+        Some(self.count.to_string().into_bytes())
+    }
+
+    fn restore(&mut self, snapshot: Vec<u8>) {
+        if let Some(count) = String::from_utf8(snapshot).ok().and_then(|s| s.parse().ok()) {
+            self.count = count;
+        }
+    }
+}
+
+impl Handler<Inc> for Counter {
+    type Result = ();
+
+    fn handle(&mut self, _: Inc, _: &mut Context<Self>) {
+        self.count += 1;
+    }
+}
+
+impl Handler<Shutdown> for Counter {
+    type Result = ();
+
+    fn handle(&mut self, _: Shutdown, ctx: &mut Context<Self>) {
+        ctx.stop();
+        Arbiter::system().do_send(SystemExit(0));
+    }
+}
+
+fn store_dir(test: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("actix-{}-{}", test, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    dir
+}
+
+/// Run counter in its own system, returns count seen in `started()`
+fn run_counter(dir: &PathBuf, incs: usize) -> u64 {
+    let sys = System::new("test");
+    let started = Arc::new(Mutex::new(None));
+
+    let st = Arc::clone(&started);
+    let addr: Address<_> = Counter::build()
+        .name("counter")
+        .state_store(FileStore::new(dir.clone()))
+        .start_supervised(move |_| Counter{count: 0, started: st});
+    for _ in 0..incs {
+        addr.do_send(Inc);
+    }
+    Arbiter::handle().spawn(addr.call_fut(Shutdown).then(|_| Ok(())));
+
+    sys.run();
+    let count = started.lock().unwrap().take().unwrap();
+    count
+}
+
+#[test]
+fn test_restore_after_graceful_stop() {
+    let dir = store_dir("restore");
+
+    assert_eq!(run_counter(&dir, 3), 0);
+    assert_eq!(FileStore::new(dir.clone()).load("counter").unwrap(), Some(b"3".to_vec()));
+
+    // next generation resumes from saved count
+    assert_eq!(run_counter(&dir, 2), 3);
+    assert_eq!(FileStore::new(dir.clone()).load("counter").unwrap(), Some(b"5".to_vec()));
+
+    let _ = fs::remove_dir_all(&dir);
+}
+
+#[test]
+fn test_corrupted_snapshot() {
+    let dir = store_dir("corrupted");
+    let store = FileStore::new(dir.clone());
+    store.save("counter", b"42").unwrap();
+
+    // damage payload, checksum does not match
+    let path = store.path("counter");
+    let mut data = fs::read(&path).unwrap();
+    *data.last_mut().unwrap() = b'7';
+    fs::write(&path, data).unwrap();
+    assert!(store.load("counter").is_err());
+
+    // actor starts fresh, snapshot is replaced on stop
+    assert_eq!(run_counter(&dir, 1), 0);
+    assert_eq!(store.load("counter").unwrap(), Some(b"1".to_vec()));
+
+    let _ = fs::remove_dir_all(&dir);
+}
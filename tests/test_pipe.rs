@@ -0,0 +1,128 @@
+extern crate actix;
+extern crate futures;
+
+use futures::Future;
+use actix::prelude::*;
+
+#[derive(Clone)]
+struct Deposit(usize);
+
+impl ResponseType for Deposit {
+    type Item = ();
+    type Error = ();
+}
+
+struct StopAudit;
+
+impl ResponseType for StopAudit {
+    type Item = ();
+    type Error = ();
+}
+
+struct GetTotal;
+
+impl ResponseType for GetTotal {
+    type Item = usize;
+    type Error = ();
+}
+
+struct GetLog;
+
+impl ResponseType for GetLog {
+    type Item = Vec<usize>;
+    type Error = ();
+}
+
+/// Receives copies of deposits
+struct Audit(Vec<usize>);
+
+impl Actor for Audit {
+    type Context = Context<Self>;
+}
+
+impl Handler<Deposit> for Audit {
+    type Result = ();
+
+    fn handle(&mut self, msg: Deposit, _: &mut Context<Self>) {
+        self.0.push(msg.0);
+    }
+}
+
+impl Handler<GetLog> for Audit {
+    type Result = MessageResult<GetLog>;
+
+    fn handle(&mut self, _: GetLog, _: &mut Context<Self>) -> Self::Result {
+        Ok(self.0.clone())
+    }
+}
+
+struct Account {
+    total: usize,
+    audits: Vec<Address<Audit>>,
+}
+
+impl Actor for Account {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        for audit in &self.audits {
+            ctx.pipe_to::<Deposit>(audit.clone().into_subscriber());
+        }
+    }
+}
+
+impl Handler<Deposit> for Account {
+    type Result = ();
+
+    fn handle(&mut self, msg: Deposit, _: &mut Context<Self>) {
+        self.total += msg.0;
+    }
+}
+
+impl Handler<StopAudit> for Account {
+    type Result = ();
+
+    fn handle(&mut self, _: StopAudit, ctx: &mut Context<Self>) {
+        ctx.unpipe::<Deposit>();
+    }
+}
+
+impl Handler<GetTotal> for Account {
+    type Result = MessageResult<GetTotal>;
+
+    fn handle(&mut self, _: GetTotal, ctx: &mut Context<Self>) -> Self::Result {
+        assert_eq!(ctx.pipe_dropped::<Deposit>(), 0);
+        Ok(self.total)
+    }
+}
+
+#[test]
+fn test_pipe_to() {
+    let sys = System::new("test");
+    let audit1: Address<_> = Audit(Vec::new()).start();
+    let audit2: Address<_> = Audit(Vec::new()).start();
+    let account: Address<_> = Account{
+        total: 0, audits: vec![audit1.clone(), audit2.clone()]}.start();
+
+    account.do_send(Deposit(1));
+    account.do_send(Deposit(2));
+    account.do_send(Deposit(3));
+    account.do_send(StopAudit);
+    account.do_send(Deposit(4));
+
+    Arbiter::handle().spawn(
+        account.call_fut(GetTotal).then(move |total| {
+            audit1.call_fut(GetLog).then(move |log1| {
+                audit2.call_fut(GetLog).then(move |log2| {
+                    // removed pipe does not affect actor's own handling
+                    assert_eq!(total.unwrap().unwrap(), 10);
+                    assert_eq!(log1.unwrap().unwrap(), vec![1, 2, 3]);
+                    assert_eq!(log2.unwrap().unwrap(), vec![1, 2, 3]);
+                    Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                    Ok(())
+                })
+            })
+        }));
+
+    sys.run();
+}
@@ -0,0 +1,188 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::io::{Read, Write};
+use std::net::{Shutdown, TcpListener};
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use std::thread;
+use std::time::Duration;
+use futures::{future, Future};
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::actors::{Acquire, ConnectionPool, ConnectorError, EndpointStats,
+                    PoolConfig, PoolStats, SetPoolConfig};
+use actix::msgs::SystemExit;
+
+/// Echo listener, counts accepted and closed connections
+struct Echo {
+    addr: String,
+    accepted: Arc<AtomicUsize>,
+    closed: Arc<AtomicUsize>,
+}
+
+fn echo() -> Echo {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap().to_string();
+    let accepted = Arc::new(AtomicUsize::new(0));
+    let closed = Arc::new(AtomicUsize::new(0));
+
+    let (acc, cl) = (Arc::clone(&accepted), Arc::clone(&closed));
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            acc.fetch_add(1, Ordering::SeqCst);
+            let cl = Arc::clone(&cl);
+            thread::spawn(move || {
+                let mut buf = [0; 64];
+                loop {
+                    match stream.read(&mut buf) {
+                        Ok(0) | Err(_) => break,
+                        Ok(n) => stream.write_all(&buf[..n]).unwrap(),
+                    }
+                }
+                let _ = stream.shutdown(Shutdown::Both);
+                cl.fetch_add(1, Ordering::SeqCst);
+            });
+        }
+    });
+    Echo{addr: addr, accepted: accepted, closed: closed}
+}
+
+fn configure(config: PoolConfig) -> Address<ConnectionPool> {
+    let pool: Address<_> = ConnectionPool::from_registry();
+    pool.do_send(SetPoolConfig(config));
+    pool
+}
+
+fn stats(pool: &Address<ConnectionPool>, endpoint: String)
+         -> Box<Future<Item=EndpointStats, Error=()>>
+{
+    Box::new(pool.call_fut(PoolStats).then(move |res| match res {
+        Ok(Ok(mut stats)) => Ok(stats.remove(&endpoint).unwrap()),
+        _ => Err(()),
+    }))
+}
+
+fn delay(ms: u64) -> Timeout {
+    Timeout::new(Duration::from_millis(ms), Arbiter::handle()).unwrap()
+}
+
+#[test]
+fn test_pool_reuse() {
+    let sys = System::new("test");
+    let echo = echo();
+    let pool = configure(PoolConfig::default());
+    let timeout = Duration::from_secs(1);
+
+    let (pool2, ep) = (pool.clone(), echo.addr.clone());
+    let res = Arc::new(Mutex::new(None));
+    let res2 = Arc::clone(&res);
+    Arbiter::handle().spawn(
+        pool.call_fut(Acquire::new(echo.addr.as_str(), timeout))
+            .then(move |res| {
+                let stream = res.unwrap().unwrap();
+                let local = stream.get_ref().local_addr().unwrap();
+                drop(stream);
+                pool2.call_fut(Acquire::new(ep.as_str(), timeout))
+                    .then(move |res| {
+                        let stream = res.unwrap().unwrap();
+                        assert_eq!(stream.get_ref().local_addr().unwrap(), local);
+                        drop(stream);
+                        stats(&pool2, ep)
+                    })
+            })
+            .then(move |r| {
+                *res2.lock().unwrap() = Some(r);
+                Arbiter::system().do_send(SystemExit(0));
+                future::ok(())
+            }));
+    sys.run();
+
+    let stats = res.lock().unwrap().take().unwrap().unwrap();
+    assert_eq!(stats.created, 1);
+    assert_eq!(stats.reused, 1);
+    assert_eq!(stats.idle, 1);
+    assert_eq!(echo.accepted.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_pool_limit() {
+    let sys = System::new("test");
+    let echo = echo();
+    let pool = configure(PoolConfig::default().max_total(1));
+    let ep = echo.addr.clone();
+
+    let res = Arc::new(Mutex::new(None));
+    let res2 = Arc::clone(&res);
+    let pool2 = pool.clone();
+    Arbiter::handle().spawn(
+        pool.call_fut(Acquire::new(ep.as_str(), Duration::from_secs(1)))
+            .then(move |first| {
+                let first = first.unwrap().unwrap();
+
+                // limit is reached, requests wait for connection
+                let second = pool2.call_fut(Acquire::new(ep.as_str(), Duration::from_secs(1)));
+                let third = pool2.call_fut(Acquire::new(ep.as_str(), Duration::from_millis(50)));
+                let waiting = stats(&pool2, ep.clone());
+
+                // first connection is released after third request timed out
+                let release = delay(150).then(move |_| {
+                    drop(first);
+                    Ok::<_, ()>(())
+                });
+                waiting.join4(third.then(Ok::<_, ()>), release,
+                              second.then(Ok::<_, ()>))
+            })
+            .then(move |r| {
+                *res2.lock().unwrap() = Some(r.unwrap());
+                Arbiter::system().do_send(SystemExit(0));
+                future::ok(())
+            }));
+    sys.run();
+
+    let (waiting, third, _, second) = res.lock().unwrap().take().unwrap();
+    assert_eq!(waiting.waiting, 2);
+    assert_eq!(waiting.active, 1);
+    match third {
+        Ok(Err(ConnectorError::Timeout)) => (),
+        res => panic!("third request should time out: {:?}", res),
+    }
+    assert!(second.unwrap().is_ok());
+    assert_eq!(echo.accepted.load(Ordering::SeqCst), 1);
+}
+
+#[test]
+fn test_pool_idle_ttl() {
+    let sys = System::new("test");
+    let echo = echo();
+    let pool = configure(PoolConfig::default().idle_ttl(Duration::from_millis(50)));
+    let ep = echo.addr.clone();
+
+    let res = Arc::new(Mutex::new(None));
+    let res2 = Arc::clone(&res);
+    let closed = Arc::clone(&echo.closed);
+    let pool2 = pool.clone();
+    Arbiter::handle().spawn(
+        pool.call_fut(Acquire::new(ep.as_str(), Duration::from_secs(1)))
+            .then(move |res| {
+                drop(res.unwrap().unwrap());
+                delay(200)
+            })
+            .then(move |_| stats(&pool2, ep))
+            .then(move |r| {
+                // give echo thread time to observe closed socket
+                delay(50).then(move |_| {
+                    *res2.lock().unwrap() = Some((r.unwrap(), closed.load(Ordering::SeqCst)));
+                    Arbiter::system().do_send(SystemExit(0));
+                    future::ok(())
+                })
+            }));
+    sys.run();
+
+    let (stats, closed) = res.lock().unwrap().take().unwrap();
+    assert_eq!(stats.idle, 0);
+    assert_eq!(stats.active, 0);
+    assert_eq!(closed, 1);
+}
@@ -0,0 +1,121 @@
+extern crate actix;
+
+use std::rc::Rc;
+use std::cell::Cell;
+use std::time::{Duration, Instant};
+use actix::prelude::*;
+
+struct Ball(usize);
+
+impl ResponseType for Ball {
+    type Item = ();
+    type Error = ();
+}
+
+struct Serve(Address<Player>);
+
+impl ResponseType for Serve {
+    type Item = ();
+    type Error = ();
+}
+
+struct Player {
+    peer: Option<Address<Player>>,
+    hits: Rc<Cell<usize>>,
+}
+
+impl Actor for Player {
+    type Context = Context<Self>;
+}
+
+impl Handler<Serve> for Player {
+    type Result = ();
+
+    fn handle(&mut self, msg: Serve, _: &mut Context<Self>) {
+        self.peer = Some(msg.0);
+    }
+}
+
+impl Handler<Ball> for Player {
+    type Result = ();
+
+    fn handle(&mut self, msg: Ball, _: &mut Context<Self>) {
+        self.hits.set(self.hits.get() + 1);
+        if msg.0 == 100 {
+            Arbiter::system().do_send(actix::msgs::SystemExit(7));
+        } else {
+            self.peer.as_ref().unwrap().do_send(Ball(msg.0 + 1));
+        }
+    }
+}
+
+#[test]
+fn test_run_pending_ping_pong() {
+    let mut sys = System::new("test");
+    let hits = Rc::new(Cell::new(0));
+
+    let ping: Address<_> = Player{peer: None, hits: Rc::clone(&hits)}.start();
+    let pong: Address<_> = Player{peer: None, hits: Rc::clone(&hits)}.start();
+    ping.do_send(Serve(pong.clone()));
+    pong.do_send(Serve(ping.clone()));
+
+    let mut messages = 0;
+    let mut exit_code = None;
+    for _ in 0..1000 {
+        let report = sys.run_pending();
+        messages += report.messages;
+        if report.exit_code.is_some() {
+            exit_code = report.exit_code;
+            break
+        }
+        if hits.get() == 0 {
+            ping.do_send(Ball(1));
+        }
+    }
+
+    assert_eq!(exit_code, Some(7));
+    assert_eq!(hits.get(), 100);
+    assert!(messages >= 100);
+
+    // work is done, nothing is pending
+    let report = sys.run_pending();
+    assert_eq!(report.messages, 0);
+    assert_eq!(report.exit_code, Some(7));
+    assert_eq!(sys.run(), 7);
+}
+
+struct Sleeper;
+
+impl Actor for Sleeper {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.run_later(Duration::from_millis(50), |_, _| {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        });
+    }
+}
+
+#[test]
+fn test_run_pending_next_timer() {
+    let mut sys = System::new("test");
+    let start = Instant::now();
+    let _: Address<_> = Sleeper.start();
+
+    let report = sys.run_pending();
+    let deadline = report.next_timer.unwrap();
+    assert!(deadline > start && deadline <= start + Duration::from_millis(100));
+    assert_eq!(report.exit_code, None);
+
+    // embedder sleeps until next timer
+    loop {
+        let now = Instant::now();
+        if deadline > now {
+            std::thread::sleep(deadline - now);
+        }
+        if sys.run_pending().exit_code.is_some() {
+            break
+        }
+    }
+    assert!(start.elapsed() >= Duration::from_millis(50));
+}
@@ -0,0 +1,119 @@
+extern crate actix;
+extern crate futures;
+
+use futures::Future;
+use actix::prelude::*;
+use actix::RestartPolicy;
+use actix::actors::deadletter::{DeadLetterReason, DeadLetters, GetDeadLetters};
+
+const MAX_ATTEMPTS: u32 = 3;
+
+#[derive(Clone, Debug)]
+struct Job(usize);
+
+impl ResponseType for Job {
+    type Item = ();
+    type Error = ();
+}
+
+/// Handler of this message always panics
+#[derive(Clone, Debug)]
+struct Poison;
+
+impl ResponseType for Poison {
+    type Item = ();
+    type Error = ();
+
+    fn render(&self) -> Option<String> {
+        Some(format!("{:?}", self))
+    }
+}
+
+struct GetState;
+
+impl ResponseType for GetState {
+    type Item = (Vec<usize>, usize, usize);
+    type Error = ();
+}
+
+#[derive(Default)]
+struct Worker {
+    done: Vec<usize>,
+    poisoned: usize,
+    restarts: usize,
+}
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+}
+
+impl actix::Supervised for Worker {
+    fn restarting(&mut self, _: &mut Context<Self>) {
+        self.restarts += 1;
+    }
+}
+
+impl Handler<Job> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, msg: Job, _: &mut Context<Self>) {
+        self.done.push(msg.0);
+    }
+}
+
+impl Handler<Poison> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, _: Poison, _: &mut Context<Self>) {
+        self.poisoned += 1;
+        panic!("poisoned message");
+    }
+}
+
+impl Handler<GetState> for Worker {
+    type Result = MessageResult<GetState>;
+
+    fn handle(&mut self, _: GetState, _: &mut Context<Self>) -> Self::Result {
+        Ok((self.done.clone(), self.poisoned, self.restarts))
+    }
+}
+
+#[test]
+fn test_poisoned_message_is_quarantined() {
+    let sys = System::new("test");
+
+    let addr: Address<_> = actix::Supervisor::start(|ctx: &mut Context<Worker>| {
+        ctx.set_restart_policy(RestartPolicy::KeepMailbox{max_attempts: MAX_ATTEMPTS});
+        ctx.redeliver::<Job>();
+        ctx.redeliver::<Poison>();
+        Worker::default()
+    });
+
+    addr.do_send(Job(1));
+    addr.do_send(Poison);
+    addr.do_send(Job(2));
+    addr.do_send(Job(3));
+
+    Arbiter::handle().spawn(
+        addr.call_fut(GetState).then(|res| {
+            let (done, poisoned, restarts) = res.unwrap().unwrap();
+            // messages after poisoned one are handled by recovered actor
+            assert_eq!(done, vec![1, 2, 3]);
+            assert_eq!(poisoned, MAX_ATTEMPTS as usize);
+            assert_eq!(restarts, MAX_ATTEMPTS as usize);
+
+            DeadLetters::from_registry().call_fut(GetDeadLetters)
+        }).then(|res| {
+            let letters = res.unwrap().unwrap();
+            assert_eq!(letters.len(), 1);
+            assert!(letters[0].message_type.ends_with("::Poison"));
+            assert!(letters[0].actor_type.ends_with("::Worker"));
+            assert_eq!(letters[0].rendered, Some("Poison".to_owned()));
+            assert_eq!(letters[0].reason, DeadLetterReason::Poisoned{attempts: MAX_ATTEMPTS});
+
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+}
@@ -0,0 +1,177 @@
+extern crate actix;
+extern crate bytes;
+extern crate futures;
+extern crate tokio_core;
+extern crate tokio_io;
+
+use std::io::{self, Write};
+use std::net::TcpListener;
+use std::sync::{Arc, Mutex};
+use std::thread;
+use std::time::{Duration, Instant};
+use bytes::BytesMut;
+use futures::Future;
+use tokio_core::net::TcpStream;
+use tokio_io::AsyncRead;
+use tokio_io::codec::{Decoder, Encoder};
+use actix::prelude::*;
+use actix::{ReaderControl, FramedReader};
+use actix::msgs::SystemExit;
+use actix::utils::{Decision, RateLimiter};
+
+struct LineCodec;
+
+impl Decoder for LineCodec {
+    type Item = String;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<String>, io::Error> {
+        if let Some(pos) = src.iter().position(|b| *b == b'\n') {
+            let line = src.split_to(pos + 1);
+            Ok(Some(String::from_utf8_lossy(&line[..pos]).into_owned()))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl Encoder for LineCodec {
+    type Item = String;
+    type Error = io::Error;
+
+    fn encode(&mut self, msg: String, dst: &mut BytesMut) -> Result<(), io::Error> {
+        dst.extend(msg.as_bytes());
+        dst.extend(b"\n");
+        Ok(())
+    }
+}
+
+const FRAMES: usize = 110;
+const RATE: u32 = 200;
+const BURST: u32 = 10;
+
+#[derive(Default)]
+struct Report {
+    allowed: Vec<Instant>,
+    pauses: usize,
+    deferred: usize,
+    read_while_paused: usize,
+}
+
+struct Session {
+    limiter: RateLimiter,
+    control: ReaderControl,
+    report: Arc<Mutex<Report>>,
+}
+
+impl Actor for Session {
+    type Context = Context<Self>;
+}
+
+impl StreamHandler<String, FramedError<LineCodec>> for Session {
+    fn handle(&mut self, msg: String, ctx: &mut Context<Self>) {
+        let mut report = self.report.lock().unwrap();
+        if self.control.is_paused() {
+            report.read_while_paused += 1;
+        }
+        match self.limiter.check(ctx) {
+            Decision::Allow => {
+                report.allowed.push(Instant::now());
+                if self.control.is_paused() {
+                    report.pauses += 1;
+                }
+                if report.allowed.len() == FRAMES {
+                    Arbiter::system().do_send(SystemExit(0));
+                }
+            }
+            Decision::Defer(dur) => {
+                report.deferred += 1;
+                drop(report);
+                ctx.run_later(dur, move |act, ctx| StreamHandler::handle(act, msg, ctx));
+            }
+            Decision::Drop => panic!("frame dropped"),
+        }
+    }
+}
+
+#[test]
+fn test_rate_limited_reader() {
+    let sys = System::new("test");
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // peer sends all frames at once
+    thread::spawn(move || {
+        let (mut stream, _) = listener.accept().unwrap();
+        let frames: String = (0..FRAMES).map(|i| format!("{}\n", i)).collect();
+        stream.write_all(frames.as_bytes()).unwrap();
+        thread::sleep(Duration::from_secs(5));
+    });
+
+    let report = Arc::new(Mutex::new(Report::default()));
+    let report2 = Arc::clone(&report);
+    Arbiter::handle().spawn(
+        TcpStream::connect(&addr, Arbiter::handle())
+            .map(move |stream| {
+                let _: () = Session::create(move |ctx| {
+                    let (reader, _) = FramedReader::wrap(stream.framed(LineCodec));
+                    let control = reader.control();
+                    Session::add_stream(reader, ctx);
+                    Session{limiter: RateLimiter::new(RATE, BURST).pause_reader(control.clone()),
+                            control: control,
+                            report: report2}
+                });
+            })
+            .map_err(|err| panic!("can not connect: {}", err)));
+    sys.run();
+
+    let report = report.lock().unwrap();
+    assert_eq!(report.read_while_paused, 0);
+    assert_eq!(report.deferred, 0);
+    // reader gets paused once burst is spent, late timer may refill
+    // an extra token now and then
+    assert!(report.pauses >= (FRAMES - BURST as usize) / 2, "pauses: {}", report.pauses);
+
+    // burst is allowed right away
+    let first = report.allowed[0];
+    assert!(report.allowed[BURST as usize - 1] - first < Duration::from_millis(20));
+
+    // rest of frames are allowed at configured rate
+    let elapsed = report.allowed[FRAMES - 1] - first;
+    let expected = Duration::from_millis(1000 * (FRAMES as u64 - u64::from(BURST)) / u64::from(RATE));
+    assert!(elapsed > expected * 9 / 10 && elapsed < expected * 11 / 10,
+            "elapsed {:?}, expected {:?}", elapsed, expected);
+}
+
+#[test]
+fn test_rate_limiter_decisions() {
+    struct Dummy;
+    impl Actor for Dummy {
+        type Context = Context<Dummy>;
+    }
+
+    let sys = System::new("test");
+    let res = Arc::new(Mutex::new(Vec::new()));
+    let res2 = Arc::clone(&res);
+    let _: () = Dummy::create(move |ctx| {
+        let mut limiter = RateLimiter::new(10, 2).max_delay(Duration::from_millis(150));
+        let mut strict = RateLimiter::new(10, 2).max_delay(Duration::from_millis(50));
+        let mut res = res2.lock().unwrap();
+        for _ in 0..3 {
+            res.push((limiter.available(), limiter.check(ctx), strict.check(ctx)));
+        }
+        Arbiter::system().do_send(SystemExit(0));
+        Dummy
+    });
+    sys.run();
+
+    let res = res.lock().unwrap();
+    assert_eq!(res[0], (2, Decision::Allow, Decision::Allow));
+    assert_eq!(res[1], (1, Decision::Allow, Decision::Allow));
+    // next token is available in 100ms
+    match res[2] {
+        (0, Decision::Defer(dur), Decision::Drop) =>
+            assert!(dur > Duration::from_millis(90) && dur <= Duration::from_millis(100)),
+        ref res => panic!("unexpected {:?}", res),
+    }
+}
@@ -0,0 +1,223 @@
+extern crate actix;
+extern crate bytes;
+extern crate futures;
+extern crate tokio_core;
+extern crate tokio_io;
+
+use std::io;
+use std::net::{SocketAddr, TcpListener as StdTcpListener};
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use bytes::BytesMut;
+use futures::{Future, Stream};
+use tokio_core::net::{TcpListener, TcpStream};
+use tokio_io::AsyncRead;
+use tokio_io::codec::{Decoder, Encoder};
+use tokio_io::io::{read_exact, write_all};
+use actix::prelude::*;
+use actix::{ReconnectingClient, Backoff, Connected, Disconnected, DisconnectReason};
+
+struct LineCodec;
+
+impl Decoder for LineCodec {
+    type Item = String;
+    type Error = io::Error;
+
+    fn decode(&mut self, src: &mut BytesMut) -> Result<Option<String>, io::Error> {
+        if let Some(pos) = src.iter().position(|b| *b == b'\n') {
+            let line = src.split_to(pos + 1);
+            Ok(Some(String::from_utf8_lossy(&line[..pos]).into_owned()))
+        } else {
+            Ok(None)
+        }
+    }
+}
+
+impl Encoder for LineCodec {
+    type Item = String;
+    type Error = io::Error;
+
+    fn encode(&mut self, msg: String, dst: &mut BytesMut) -> Result<(), io::Error> {
+        dst.extend(msg.as_bytes());
+        dst.extend(b"\n");
+        Ok(())
+    }
+}
+
+#[derive(Debug, PartialEq)]
+enum Event {
+    Connected,
+    Frame(String),
+    Disconnected(bool),
+}
+
+struct Client {
+    addr: SocketAddr,
+    buffer: usize,
+    client: Option<ReconnectingClient<TcpStream, LineCodec>>,
+    events: Arc<Mutex<Vec<Event>>>,
+}
+
+impl Actor for Client {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        let addr = self.addr;
+        self.client = Some(
+            ReconnectingClient::build(move || {
+                Box::new(TcpStream::connect(&addr, Arbiter::handle())
+                         .map(|io| io.framed(LineCodec)))
+            })
+            .backoff(Backoff::fixed(Duration::from_millis(10)))
+            .buffer(self.buffer)
+            .start(ctx));
+    }
+}
+
+impl StreamHandler<String, FramedError<LineCodec>> for Client {
+    fn handle(&mut self, line: String, _: &mut Context<Self>) {
+        if line == "two" {
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        }
+        self.events.lock().unwrap().push(Event::Frame(line));
+    }
+}
+
+impl Handler<Connected> for Client {
+    type Result = ();
+
+    fn handle(&mut self, _: Connected, _: &mut Context<Self>) {
+        self.events.lock().unwrap().push(Event::Connected);
+    }
+}
+
+impl Handler<Disconnected<LineCodec>> for Client {
+    type Result = ();
+
+    fn handle(&mut self, msg: Disconnected<LineCodec>, _: &mut Context<Self>) {
+        let closed = match msg.reason {
+            DisconnectReason::Closed => true,
+            DisconnectReason::Error(_) => false,
+        };
+        self.events.lock().unwrap().push(Event::Disconnected(closed));
+
+        // buffered until next connection
+        assert!(self.client.as_mut().unwrap().send("ping".to_owned()).is_ok());
+    }
+}
+
+#[test]
+fn test_reconnect() {
+    let sys = System::new("test");
+    let events = Arc::new(Mutex::new(Vec::new()));
+
+    let addr = "127.0.0.1:0".parse().unwrap();
+    let listener = TcpListener::bind(&addr, Arbiter::handle()).unwrap();
+    let addr = listener.local_addr().unwrap();
+
+    // first connection gets dropped, second one waits for buffered item
+    let mut conn = 0;
+    Arbiter::handle().spawn(
+        listener.incoming().take(2).for_each(move |(sock, _)| {
+            conn += 1;
+            if conn == 1 {
+                Box::new(write_all(sock, b"one\n").map(|_| ()))
+                    as Box<Future<Item=(), Error=io::Error>>
+            } else {
+                Box::new(read_exact(sock, [0u8; 5])
+                         .and_then(|(sock, buf)| {
+                             assert_eq!(&buf, b"ping\n");
+                             write_all(sock, b"two\n")
+                         })
+                         .map(|_| ()))
+            }
+        }).map_err(|_| panic!("server failed")));
+
+    let _: () = Client{addr: addr, buffer: 16, client: None,
+                       events: Arc::clone(&events)}.start();
+    sys.run();
+
+    let events = events.lock().unwrap();
+    assert_eq!(&events[..5],
+               &[Event::Connected,
+                 Event::Frame("one".to_owned()),
+                 Event::Disconnected(true),
+                 Event::Connected,
+                 Event::Frame("two".to_owned())]);
+}
+
+struct Rejecting {
+    addr: SocketAddr,
+    client: Option<ReconnectingClient<TcpStream, LineCodec>>,
+}
+
+impl Actor for Rejecting {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        let addr = self.addr;
+        let mut client = ReconnectingClient::build(move || {
+            Box::new(TcpStream::connect(&addr, Arbiter::handle())
+                     .map(|io| io.framed(LineCodec)))
+        }).backoff(Backoff::fixed(Duration::from_millis(10))).buffer(0).start(ctx);
+
+        match client.send("ping".to_owned()) {
+            Err(SendError::Full(item)) => assert_eq!(item, "ping"),
+            _ => panic!("item should be rejected"),
+        }
+        self.client = Some(client);
+
+        ctx.run_later(Duration::from_millis(50), |act, _| {
+            let client = act.client.as_mut().unwrap();
+            assert!(!client.connected());
+            client.close();
+            match client.send("ping".to_owned()) {
+                Err(SendError::Closed(_)) => (),
+                _ => panic!("client should be closed"),
+            }
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        });
+    }
+}
+
+impl StreamHandler<String, FramedError<LineCodec>> for Rejecting {
+    fn handle(&mut self, _: String, _: &mut Context<Self>) {}
+}
+
+impl Handler<Connected> for Rejecting {
+    type Result = ();
+
+    fn handle(&mut self, _: Connected, _: &mut Context<Self>) {
+        panic!("should not connect");
+    }
+}
+
+impl Handler<Disconnected<LineCodec>> for Rejecting {
+    type Result = ();
+
+    fn handle(&mut self, _: Disconnected<LineCodec>, _: &mut Context<Self>) {}
+}
+
+#[test]
+fn test_reject_while_disconnected() {
+    let sys = System::new("test");
+
+    // reserve port without listening on it
+    let addr = StdTcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+
+    let _: () = Rejecting{addr: addr, client: None}.start();
+    sys.run();
+}
+
+#[test]
+fn test_backoff_delay() {
+    let backoff = Backoff::exponential(Duration::from_millis(10), Duration::from_millis(50));
+    assert_eq!(backoff.delay(0), Duration::from_millis(10));
+    assert_eq!(backoff.delay(1), Duration::from_millis(20));
+    assert_eq!(backoff.delay(2), Duration::from_millis(40));
+    assert_eq!(backoff.delay(3), Duration::from_millis(50));
+    assert_eq!(backoff.delay(100), Duration::from_millis(50));
+
+    let backoff = Backoff::fixed(Duration::from_millis(10));
+    assert_eq!(backoff.delay(5), Duration::from_millis(10));
+}
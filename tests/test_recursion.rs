@@ -57,7 +57,7 @@ impl Handler<Count> for CounterActor {
         // creating sorta async recursion
         let my_address: Address<CounterActor> = ctx.address();
 
-        my_address.send(Count(msg.0 + 1));
+        my_address.do_send(Count(msg.0 + 1));
         Ok(TrackableItem::new())
     }
 }
@@ -69,6 +69,6 @@ impl Handler<Count> for CounterActor {
 fn test_recursion() {
     let system = actix::System::new("test");
     let addr: Address<_> = CounterActor.start();
-    addr.send(Count(0));
+    addr.do_send(Count(0));
     system.run();
 }
@@ -0,0 +1,92 @@
+extern crate actix;
+
+use std::sync::{Arc, Mutex};
+use std::sync::atomic::{AtomicUsize, Ordering};
+use actix::prelude::*;
+use actix::Lazy;
+use actix::msgs::SystemExit;
+
+static PING_STARTED: AtomicUsize = AtomicUsize::new(0);
+static PONG_STARTED: AtomicUsize = AtomicUsize::new(0);
+
+struct Ball(usize, Arc<Mutex<Vec<String>>>);
+
+impl ResponseType for Ball {
+    type Item = ();
+    type Error = ();
+}
+
+#[derive(Default)]
+struct Ping {
+    pong: Lazy<Pong>,
+}
+
+impl Actor for Ping {
+    type Context = Context<Self>;
+}
+
+impl Supervised for Ping {}
+
+impl ArbiterService for Ping {
+    fn service_started(&mut self, _: &mut Context<Self>) {
+        PING_STARTED.fetch_add(1, Ordering::SeqCst);
+        // resolves peer during startup, peer resolves this service as well
+        self.pong.get();
+    }
+}
+
+impl Handler<Ball> for Ping {
+    type Result = ();
+
+    fn handle(&mut self, msg: Ball, _: &mut Context<Self>) {
+        msg.1.lock().unwrap().push(format!("ping {}", msg.0));
+        if msg.0 == 0 {
+            Arbiter::system().do_send(SystemExit(0));
+        } else {
+            self.pong.get().do_send(Ball(msg.0 - 1, msg.1));
+        }
+    }
+}
+
+#[derive(Default)]
+struct Pong {
+    ping: Lazy<Ping>,
+}
+
+impl Actor for Pong {
+    type Context = Context<Self>;
+}
+
+impl Supervised for Pong {}
+
+impl ArbiterService for Pong {
+    fn service_started(&mut self, _: &mut Context<Self>) {
+        PONG_STARTED.fetch_add(1, Ordering::SeqCst);
+        self.ping.get();
+    }
+}
+
+impl Handler<Ball> for Pong {
+    type Result = ();
+
+    fn handle(&mut self, msg: Ball, _: &mut Context<Self>) {
+        msg.1.lock().unwrap().push(format!("pong {}", msg.0));
+        self.ping.get().do_send(Ball(msg.0 - 1, msg.1));
+    }
+}
+
+#[test]
+fn test_lazy_circular() {
+    let sys = System::new("test");
+    let log = Arc::new(Mutex::new(Vec::new()));
+
+    let ping: Lazy<Ping> = Lazy::new();
+    assert!(!ping.is_resolved());
+    ping.get().do_send(Ball(4, Arc::clone(&log)));
+    assert!(ping.is_resolved());
+    sys.run();
+
+    assert_eq!(*log.lock().unwrap(), vec!["ping 4", "pong 3", "ping 2", "pong 1", "ping 0"]);
+    assert_eq!(PING_STARTED.load(Ordering::SeqCst), 1);
+    assert_eq!(PONG_STARTED.load(Ordering::SeqCst), 1);
+}
@@ -0,0 +1,168 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::thread;
+use std::time::{Duration, Instant};
+use futures::{future, Future};
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+
+struct Ping(usize);
+
+impl ResponseType for Ping {
+    type Item = usize;
+    type Error = ();
+}
+
+struct Sleep(u64);
+
+impl ResponseType for Sleep {
+    type Item = ();
+    type Error = ();
+}
+
+struct Echo;
+
+impl Actor for Echo {
+    type Context = Context<Self>;
+}
+
+impl Handler<Ping> for Echo {
+    type Result = MessageResult<Ping>;
+
+    fn handle(&mut self, msg: Ping, _: &mut Context<Echo>) -> Self::Result {
+        Ok(msg.0)
+    }
+}
+
+impl Handler<Sleep> for Echo {
+    type Result = ();
+
+    fn handle(&mut self, msg: Sleep, _: &mut Context<Echo>) {
+        thread::sleep(Duration::from_millis(msg.0));
+    }
+}
+
+fn rendezvous_echo() -> SyncAddress<Echo> {
+    Echo::build()
+        .mailbox_capacity(0)
+        .on_arbiter(&Arbiter::new("echo"))
+        .start(Echo)
+}
+
+fn deadline(secs: u64) {
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_secs(secs), Arbiter::handle()).unwrap()
+            .then(|_| -> Result<(), ()> { panic!("rendezvous mailbox is stuck") }));
+}
+
+fn ping_pong(addr: SyncAddress<Echo>, count: usize) -> Box<Future<Item=(), Error=()>> {
+    Box::new(future::loop_fn(0, move |i| {
+        addr.call_fut(Ping(i)).then(move |res| {
+            assert_eq!(res.unwrap().unwrap(), i);
+            if i + 1 == count {
+                Ok(future::Loop::Break(()))
+            } else {
+                Ok(future::Loop::Continue(i + 1))
+            }
+        })
+    }))
+}
+
+#[test]
+fn test_rendezvous_ping_pong() {
+    let sys = System::new("test");
+    deadline(10);
+
+    let addr = rendezvous_echo();
+    let start = Instant::now();
+    Arbiter::handle().spawn(
+        ping_pong(addr.clone(), 1000).join(ping_pong(addr, 1000))
+            .then(move |_| {
+                // two senders compete for single handoff slot
+                assert!(start.elapsed() < Duration::from_secs(5));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+}
+
+#[test]
+fn test_rendezvous_try_send_busy() {
+    let sys = System::new("test");
+    deadline(10);
+
+    let addr = rendezvous_echo();
+    let addr2 = addr.clone();
+    let addr3 = addr.clone();
+
+    // call waits for handoff, handler keeps actor busy
+    Arbiter::handle().spawn(addr.call_fut(Sleep(300)).then(|res| {
+        assert!(res.is_ok());
+        Ok(())
+    }));
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(100), Arbiter::handle()).unwrap()
+            .then(move |_| {
+                match addr2.try_send(Ping(0)) {
+                    Err(SendError::Full(_)) => (),
+                    _ => panic!("busy actor should reject message"),
+                }
+                Timeout::new(Duration::from_millis(500), Arbiter::handle()).unwrap()
+            })
+            .then(move |_| {
+                // actor waits for messages again
+                assert!(addr3.try_send(Ping(1)).is_ok());
+                assert!(addr3.try_send(Ping(2)).is_err());
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+}
+
+#[test]
+fn test_rendezvous_simultaneous_start() {
+    let sys = System::new("test");
+    deadline(10);
+
+    // senders race with actors that are not started yet
+    let calls: Vec<_> = (0..20)
+        .map(|i| rendezvous_echo().call_fut(Ping(i)))
+        .collect();
+    Arbiter::handle().spawn(
+        future::join_all(calls).then(|res| {
+            let res: Vec<_> = res.unwrap().into_iter().map(|r| r.unwrap()).collect();
+            assert_eq!(res, (0..20).collect::<Vec<_>>());
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+}
+
+#[test]
+fn test_rendezvous_local() {
+    let sys = System::new("test");
+    deadline(10);
+
+    let addr: Address<_> = Echo::build().mailbox_capacity(0).start(Echo);
+
+    // actor is not polled yet
+    assert!(addr.try_send(Ping(0)).is_err());
+
+    let addr2 = addr.clone();
+    Arbiter::handle().spawn(
+        addr.call_fut(Ping(1)).join(addr2.call_fut(Ping(2)))
+            .then(|res| {
+                let (r1, r2) = res.unwrap();
+                assert_eq!((r1.unwrap(), r2.unwrap()), (1, 2));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+}
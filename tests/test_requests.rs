@@ -0,0 +1,143 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::time::Duration;
+use futures::Future;
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+
+/// Callee does not process messages for 100ms
+struct Block;
+
+impl ResponseType for Block {
+    type Item = ();
+    type Error = ();
+}
+
+struct Slow;
+
+impl ResponseType for Slow {
+    type Item = ();
+    type Error = ();
+}
+
+struct GetHandled;
+
+impl ResponseType for GetHandled {
+    type Item = usize;
+    type Error = ();
+}
+
+struct Callee(usize);
+
+impl Actor for Callee {
+    type Context = Context<Self>;
+}
+
+impl Handler<Block> for Callee {
+    type Result = ();
+
+    fn handle(&mut self, _: Block, ctx: &mut Context<Self>) {
+        AsyncContext::wait(ctx,
+            Timeout::new(Duration::from_millis(100), Arbiter::handle()).unwrap()
+                .map_err(|_| ()).into_actor(self));
+    }
+}
+
+impl Handler<Slow> for Callee {
+    type Result = ();
+
+    fn handle(&mut self, _: Slow, _: &mut Context<Self>) {
+        self.0 += 1;
+    }
+}
+
+impl Handler<GetHandled> for Callee {
+    type Result = MessageResult<GetHandled>;
+
+    fn handle(&mut self, _: GetHandled, _: &mut Context<Self>) -> Self::Result {
+        Ok(self.0)
+    }
+}
+
+struct Coordinator {
+    callee: Address<Callee>,
+    continuations: usize,
+}
+
+impl Actor for Coordinator {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        self.callee.do_send(Block);
+        for _ in 0..5 {
+            ctx.track_request(
+                "reconfigure",
+                self.callee.call_fut(Slow).into_actor(self)
+                    .then(|_, act: &mut Coordinator, _| {
+                        act.continuations += 1;
+                        actix::fut::ok(())
+                    }));
+        }
+        assert_eq!(ctx.pending_requests("reconfigure"), 5);
+        assert_eq!(ctx.pending_requests("other"), 0);
+
+        ctx.cancel_requests("reconfigure");
+        assert_eq!(ctx.pending_requests("reconfigure"), 0);
+
+        ctx.run_later(Duration::from_millis(200), |act, ctx| {
+            ctx.spawn(act.callee.call_fut(GetHandled).into_actor(act)
+                .then(|res, act: &mut Coordinator, _| {
+                    // recipient skipped messages with canceled response channel
+                    assert_eq!(res.unwrap().unwrap(), 0);
+                    // continuations of cancelled requests did not run
+                    assert_eq!(act.continuations, 0);
+                    Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                    actix::fut::ok(())
+                }));
+        });
+    }
+}
+
+#[test]
+fn test_cancel_requests() {
+    let sys = System::new("test");
+    let callee: Address<_> = Callee(0).start();
+    let _: Address<_> = Coordinator{callee: callee, continuations: 0}.start();
+    sys.run();
+}
+
+struct Tracker {
+    callee: Address<Callee>,
+    done: usize,
+}
+
+impl Actor for Tracker {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        for _ in 0..3 {
+            ctx.track_request(
+                "calls",
+                self.callee.call_fut(Slow).into_actor(self)
+                    .then(|_, act: &mut Tracker, ctx: &mut Context<Tracker>| {
+                        act.done += 1;
+                        if ctx.pending_requests("calls") == 1 {
+                            // last request is completing
+                            assert_eq!(act.done, 3);
+                            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                        }
+                        actix::fut::ok(())
+                    }));
+        }
+    }
+}
+
+#[test]
+fn test_completed_requests() {
+    let sys = System::new("test");
+    let callee: Address<_> = Callee(0).start();
+    let _: Address<_> = Tracker{callee: callee, done: 0}.start();
+    sys.run();
+}
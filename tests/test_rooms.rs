@@ -0,0 +1,162 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::{Arc, Mutex};
+use std::time::Duration;
+use futures::Future;
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::Subscriber;
+use actix::msgs::SystemExit;
+use actix::utils::Rooms;
+
+#[derive(Clone)]
+struct Text(&'static str);
+
+impl ResponseType for Text {
+    type Item = ();
+    type Error = ();
+}
+
+type Log = Arc<Mutex<Vec<(usize, &'static str)>>>;
+
+struct Member {
+    id: usize,
+    log: Log,
+}
+
+impl Actor for Member {
+    type Context = Context<Self>;
+}
+
+impl Handler<Text> for Member {
+    type Result = ();
+
+    fn handle(&mut self, msg: Text, ctx: &mut Context<Self>) {
+        if msg.0 == "stop" {
+            ctx.stop();
+        } else {
+            self.log.lock().unwrap().push((self.id, msg.0));
+        }
+    }
+}
+
+fn member(id: usize, log: &Log, cap: usize) -> Box<Subscriber<Text>> {
+    let log = Arc::clone(log);
+    let addr: Address<_> = Member::create(move |ctx| {
+        ctx.set_mailbox_capacity(cap);
+        Member{id: id, log: log}
+    });
+    addr.into_subscriber()
+}
+
+fn sorted(mut names: Vec<String>) -> Vec<String> {
+    names.sort();
+    names
+}
+
+fn delay(ms: u64) -> Timeout {
+    Timeout::new(Duration::from_millis(ms), Arbiter::handle()).unwrap()
+}
+
+#[test]
+fn test_rooms_membership() {
+    let sys = System::new("test");
+    let log = Log::default();
+    let mut rooms = Rooms::new();
+
+    assert!(rooms.join("a", 1, member(1, &log, 16)));
+    assert!(rooms.join("a", 2, member(2, &log, 16)));
+    assert!(rooms.join("b", 2, rooms.subscriber(2).unwrap()));
+    assert!(rooms.join("a", 3, member(3, &log, 16)));
+    assert!(!rooms.join("a", 3, rooms.subscriber(3).unwrap()));
+
+    assert_eq!(rooms.len(), 3);
+    assert_eq!(rooms.members("a"), 3);
+    assert_eq!(rooms.members("b"), 1);
+    assert_eq!(sorted(rooms.rooms()), vec!["a", "b"]);
+    assert_eq!(sorted(rooms.rooms_of(2)), vec!["a", "b"]);
+    assert!(rooms.contains("b", 2));
+    assert!(!rooms.contains("b", 1));
+
+    // sender is skipped
+    assert_eq!(rooms.broadcast("a", Text("hello"), Some(1)), 2);
+
+    assert!(rooms.leave("a", 3));
+    assert!(!rooms.leave("a", 3));
+    assert!(rooms.subscriber(3).is_none());
+    assert_eq!(rooms.broadcast("a", Text("after leave"), None), 2);
+    assert_eq!(rooms.broadcast("b", Text("room b"), None), 1);
+
+    assert_eq!(sorted(rooms.leave_all(2)), vec!["a", "b"]);
+    assert_eq!(rooms.rooms(), vec!["a"]);
+    assert_eq!(rooms.broadcast("b", Text("no room"), None), 0);
+    assert!(rooms.leave("a", 1));
+    assert!(rooms.is_empty());
+    assert!(rooms.rooms().is_empty());
+
+    Arbiter::handle().spawn(
+        delay(50)
+            .then(|_| {
+                Arbiter::system().do_send(SystemExit(0));
+                Ok(())
+            }));
+    sys.run();
+
+    let mut log = log.lock().unwrap().clone();
+    log.sort();
+    assert_eq!(log, vec![(1, "after leave"), (2, "after leave"), (2, "hello"), (2, "room b"),
+                         (3, "hello")]);
+}
+
+#[test]
+fn test_rooms_prune() {
+    let sys = System::new("test");
+    let log = Log::default();
+    let rooms = Rc::new(RefCell::new(Rooms::new()));
+    {
+        let mut rooms = rooms.borrow_mut();
+        rooms.join("a", 1, member(1, &log, 16));
+        let second = member(2, &log, 16);
+        rooms.join("a", 2, second.clone());
+        rooms.join("b", 2, second);
+        rooms.join("a", 3, member(3, &log, 1));
+    }
+
+    let (rooms1, rooms2) = (Rc::clone(&rooms), Rc::clone(&rooms));
+    Arbiter::handle().spawn(
+        // members are started
+        delay(20)
+            .then(move |_| {
+                // member 2 stops, member 3 has full mailbox
+                let mut rooms = rooms1.borrow_mut();
+                assert_eq!(rooms.broadcast("b", Text("stop"), None), 1);
+                assert_eq!(rooms.broadcast("a", Text("first"), Some(2)), 2);
+                assert_eq!(rooms.broadcast("a", Text("second"), Some(2)), 1);
+                assert_eq!(rooms.dropped(3), 1);
+                delay(50)
+            })
+            .then(move |_| {
+                // stopped member leaves all rooms
+                let mut rooms = rooms2.borrow_mut();
+                assert_eq!(rooms.members("a"), 3);
+                assert_eq!(rooms.broadcast("a", Text("third"), None), 2);
+                assert_eq!(rooms.members("a"), 2);
+                assert!(rooms.subscriber(2).is_none());
+                assert!(rooms.rooms_of(2).is_empty());
+                assert_eq!(rooms.rooms(), vec!["a"]);
+                delay(50)
+            })
+            .then(|_| {
+                Arbiter::system().do_send(SystemExit(0));
+                Ok(())
+            }));
+    sys.run();
+
+    let mut log = log.lock().unwrap().clone();
+    log.sort();
+    assert_eq!(log, vec![(1, "first"), (1, "second"), (1, "third"), (3, "first"), (3, "third")]);
+}
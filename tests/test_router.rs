@@ -0,0 +1,126 @@
+extern crate actix;
+extern crate futures;
+
+use std::thread;
+use std::rc::Rc;
+use std::cell::RefCell;
+use std::time::Duration;
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::utils::{Router, RoutingStrategy};
+
+struct Job;
+
+impl ResponseType for Job {
+    type Item = usize;
+    type Error = ();
+}
+
+/// Replies with id of the worker that handled job
+struct Worker {
+    id: usize,
+    delay: u64,
+}
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+}
+
+impl Handler<Job> for Worker {
+    type Result = MessageResult<Job>;
+
+    fn handle(&mut self, _: Job, _: &mut Context<Self>) -> Self::Result {
+        thread::sleep(Duration::from_millis(self.delay));
+        Ok(self.id)
+    }
+}
+
+/// Run `lanes` concurrent callers, each sends `count` jobs one after another,
+/// resolves with number of jobs handled by each worker
+fn load(router: Router<Worker>, lanes: usize, count: usize)
+        -> Box<Future<Item=Vec<usize>, Error=()>>
+{
+    let router = Rc::new(RefCell::new(router));
+    let counts = Rc::new(RefCell::new(vec![0; router.borrow().len()]));
+    let lanes: Vec<_> = (0..lanes)
+        .map(|_| {
+            let (router, counts) = (Rc::clone(&router), Rc::clone(&counts));
+            future::loop_fn(0, move |i| {
+                let counts = Rc::clone(&counts);
+                router.borrow_mut().call_fut(Job).then(move |res| {
+                    counts.borrow_mut()[res.unwrap().unwrap()] += 1;
+                    if i + 1 == count {
+                        Ok(future::Loop::Break(()))
+                    } else {
+                        Ok(future::Loop::Continue(i + 1))
+                    }
+                })
+            })
+        })
+        .collect();
+    Box::new(future::join_all(lanes).map(move |_| counts.borrow().clone()))
+}
+
+/// First worker is slow
+fn worker(id: usize) -> Worker {
+    Worker{id: id, delay: if id == 0 { 10 } else { 1 }}
+}
+
+#[test]
+fn test_least_outstanding() {
+    let sys = System::new("test");
+    let round_robin = Router::start(3, worker, RoutingStrategy::RoundRobin);
+    let least = Router::start(3, worker, RoutingStrategy::LeastOutstanding);
+
+    Arbiter::handle().spawn(
+        load(round_robin, 6, 25)
+            .and_then(move |rr| load(least, 6, 25).map(move |lo| (rr, lo)))
+            .then(|res| {
+                let (rr, lo) = res.unwrap();
+                assert_eq!(rr, vec![50, 50, 50]);
+                assert_eq!(lo.iter().sum::<usize>(), 150);
+                // slow worker gets measurably fewer jobs
+                assert!(lo[0] * 2 < rr[0], "{:?}", lo);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+}
+
+#[test]
+fn test_weighted() {
+    let sys = System::new("test");
+    let weighted = Router::start(
+        3, |id| Worker{id: id, delay: 0}, RoutingStrategy::Weighted(vec![1, 2, 3]));
+
+    Arbiter::handle().spawn(
+        load(weighted, 1, 600).then(|res| {
+            assert_eq!(res.unwrap(), vec![100, 200, 300]);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+}
+
+#[test]
+fn test_outstanding_released() {
+    let sys = System::new("test");
+    let mut router = Router::start(2, worker, RoutingStrategy::PowerOfTwoChoices);
+    let req = router.call_fut(Job);
+    let dropped = router.call_fut(Job);
+    assert_eq!(router.outstanding(0) + router.outstanding(1), 2);
+    drop(dropped);
+    assert_eq!(router.outstanding(0) + router.outstanding(1), 1);
+
+    Arbiter::handle().spawn(
+        req.then(move |res| {
+            assert!(res.unwrap().is_ok());
+            assert_eq!(router.outstanding(0) + router.outstanding(1), 0);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+}
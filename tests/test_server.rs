@@ -0,0 +1,119 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::cell::{Cell, RefCell};
+use std::rc::Rc;
+use std::time::Duration;
+use futures::{future, Future};
+use tokio_core::net::TcpStream;
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::{TcpServer, SocketConfig};
+use actix::actors::{Connect, Connector, SetSocketConfig};
+
+fn delay(millis: u64) -> Timeout {
+    Timeout::new(Duration::from_millis(millis), Arbiter::handle()).unwrap()
+}
+
+#[test]
+fn test_tcp_server() {
+    let sys = System::new("test");
+    let accepted = Rc::new(Cell::new(0));
+    let sessions = Rc::new(RefCell::new(Vec::new()));
+    let refused = Rc::new(Cell::new(false));
+
+    let acc = Rc::clone(&accepted);
+    let server = TcpServer::new("127.0.0.1:0".parse().unwrap())
+        .start(move |stream, _| {
+            acc.set(acc.get() + 1);
+            sessions.borrow_mut().push(stream);
+        }).unwrap();
+    let addr = server.local_addr();
+
+    let acc = Rc::clone(&accepted);
+    let refused2 = Rc::clone(&refused);
+    Arbiter::handle().spawn(
+        TcpStream::connect(&addr, Arbiter::handle())
+            .join(TcpStream::connect(&addr, Arbiter::handle()))
+            .map_err(|e| panic!("connect failed: {}", e))
+            .and_then(|clients| delay(100).map(move |_| clients))
+            .and_then(move |clients| {
+                assert_eq!(acc.get(), 2);
+
+                // connection waits in backlog
+                server.pause();
+                assert!(server.paused());
+                TcpStream::connect(&addr, Arbiter::handle())
+                    .map_err(|e| panic!("connect failed: {}", e))
+                    .and_then(|client| delay(100).map(move |_| client))
+                    .and_then(move |client| {
+                        assert_eq!(acc.get(), 2);
+                        server.resume();
+                        delay(100).map(move |_| (server, acc, client, clients))
+                    })
+            })
+            .and_then(|(server, acc, _client, _clients)| {
+                assert_eq!(acc.get(), 3);
+                server.stop();
+                delay(100)
+            })
+            .and_then(move |_| {
+                // port is released
+                TcpStream::connect(&addr, Arbiter::handle()).then(move |res| {
+                    refused2.set(res.is_err());
+                    Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                    future::ok(())
+                })
+            })
+            .map_err(|_| ())
+    );
+
+    sys.run();
+    assert_eq!(accepted.get(), 3);
+    assert!(refused.get());
+}
+
+#[test]
+fn test_socket_config() {
+    let sys = System::new("test");
+    let keepalive = Some(Duration::from_secs(30));
+    let config = SocketConfig::default().nodelay(true).keepalive(keepalive);
+    let options = Rc::new(RefCell::new(Vec::new()));
+
+    let opts = Rc::clone(&options);
+    let server = TcpServer::new("127.0.0.1:0".parse().unwrap())
+        .socket_config(config)
+        .start_configured(move |stream, _, config| {
+            // second connection overrides nodelay
+            if !opts.borrow().is_empty() {
+                config.nodelay(false).apply(&stream).unwrap();
+            }
+            opts.borrow_mut().push(
+                (stream.nodelay().unwrap(), stream.keepalive().unwrap()));
+        }).unwrap();
+    let addr = server.local_addr();
+
+    let connected = Rc::new(Cell::new(None));
+    let conn = Rc::clone(&connected);
+    Connector::from_registry().do_send(SetSocketConfig(config));
+    Arbiter::handle().spawn(
+        Connector::from_registry().call_fut(Connect::host(addr.to_string()))
+            .then(|res| match res {
+                Ok(Ok(stream)) => Ok(stream),
+                _ => Err(()),
+            })
+            .and_then(move |client| {
+                conn.set(Some(client.nodelay().unwrap()));
+                TcpStream::connect(&addr, Arbiter::handle())
+                    .map_err(|e| panic!("connect failed: {}", e))
+                    .and_then(|client2| delay(100).map(move |_| (client, client2)))
+                    .map_err(|_| ())
+            })
+            .map(|_| Arbiter::system().do_send(actix::msgs::SystemExit(0)))
+    );
+
+    sys.run();
+    assert_eq!(connected.get(), Some(true));
+    assert_eq!(*options.borrow(), vec![(true, keepalive), (false, keepalive)]);
+}
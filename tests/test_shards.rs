@@ -0,0 +1,86 @@
+extern crate actix;
+extern crate futures;
+
+use futures::{future, Future};
+use actix::prelude::*;
+use actix::utils::Shards;
+
+struct Keyed(u64);
+
+impl ResponseType for Keyed {
+    type Item = usize;
+    type Error = ();
+}
+
+/// Replies with id of the instance that handled message
+struct Shard(usize);
+
+impl Actor for Shard {
+    type Context = Context<Self>;
+}
+
+impl Handler<Keyed> for Shard {
+    type Result = MessageResult<Keyed>;
+
+    fn handle(&mut self, _: Keyed, _: &mut Context<Self>) -> Self::Result {
+        Ok(self.0)
+    }
+}
+
+fn call_all(shards: &Shards<Shard, Keyed>, keys: Vec<u64>)
+            -> Box<Future<Item=Vec<usize>, Error=MailboxError>>
+{
+    let calls: Vec<_> = keys.into_iter()
+        .map(|key| shards.call_fut(Keyed(key)).then(|res| res.map(|id| id.unwrap())))
+        .collect();
+    Box::new(future::join_all(calls))
+}
+
+#[test]
+fn test_shards_same_key() {
+    let sys = System::new("test");
+    let shards = Shards::start(4, Shard, |msg: &Keyed| msg.0);
+    assert_eq!(shards.len(), 4);
+
+    let keys: Vec<u64> = (0..50).chain(0..50).chain(0..50).collect();
+    Arbiter::handle().spawn(
+        call_all(&shards, keys).then(|res| {
+            let ids = res.unwrap();
+            for key in 0..50 {
+                assert_eq!(ids[key], ids[key + 50]);
+                assert_eq!(ids[key], ids[key + 100]);
+            }
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+}
+
+#[test]
+fn test_shards_distribution() {
+    let sys = System::new("test");
+    let shards = Shards::start(4, Shard, |msg: &Keyed| msg.0);
+
+    // pseudo random keys
+    let mut seed = 0x2545_f491_4f6c_dd1d_u64;
+    let keys: Vec<u64> = (0..4000).map(|_| {
+        seed = seed.wrapping_mul(6_364_136_223_846_793_005).wrapping_add(1_442_695_040_888_963_407);
+        seed
+    }).collect();
+
+    Arbiter::handle().spawn(
+        call_all(&shards, keys).then(|res| {
+            let mut counts = [0; 4];
+            for id in res.unwrap() {
+                counts[id] += 1;
+            }
+            for count in &counts {
+                assert!(*count > 800 && *count < 1200, "uneven distribution: {:?}", counts);
+            }
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+}
@@ -0,0 +1,47 @@
+extern crate actix;
+extern crate futures;
+extern crate shared_messages;
+
+use futures::Future;
+use actix::prelude::*;
+use shared_messages::{Add, Get};
+
+/// Messages are defined in crate that does not depend on the runtime
+struct Counter(usize);
+
+impl Actor for Counter {
+    type Context = Context<Self>;
+}
+
+impl Handler<Add> for Counter {
+    type Result = ();
+
+    fn handle(&mut self, msg: Add, _: &mut Context<Self>) {
+        self.0 += msg.0;
+    }
+}
+
+impl Handler<Get> for Counter {
+    type Result = MessageResult<Get>;
+
+    fn handle(&mut self, _: Get, _: &mut Context<Self>) -> Self::Result {
+        Ok(self.0)
+    }
+}
+
+#[test]
+fn test_shared_messages() {
+    let sys = System::new("test");
+    let addr: Address<_> = Counter(0).start();
+    addr.do_send(Add(2));
+    addr.do_send(Add(3));
+
+    Arbiter::handle().spawn(
+        addr.call_fut(Get).then(|res| {
+            assert_eq!(res.unwrap().unwrap(), 5);
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            Ok(())
+        }));
+
+    sys.run();
+}
@@ -0,0 +1,62 @@
+#![cfg(feature="spill")]
+extern crate actix;
+extern crate futures;
+#[macro_use]
+extern crate serde_derive;
+
+use std::env;
+use std::process;
+use futures::future;
+use actix::prelude::*;
+
+#[derive(Serialize, Deserialize)]
+struct Item(usize);
+
+impl ResponseType for Item {
+    type Item = ();
+    type Error = ();
+}
+
+struct Ingest {
+    next: usize,
+}
+
+impl Actor for Ingest {
+    type Context = Context<Self>;
+}
+
+impl Handler<Item> for Ingest {
+    type Result = ();
+
+    fn handle(&mut self, msg: Item, ctx: &mut Context<Self>) {
+        assert_eq!(msg.0, self.next);
+        self.next += 1;
+        if self.next == 10_000 {
+            ctx.stop();
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+        }
+    }
+}
+
+#[test]
+fn test_spill_to_disk() {
+    let sys = System::new("test");
+    let path = env::temp_dir().join(format!("actix-spill-{}", process::id()));
+    let addr: SyncAddress<_> = Ingest::build()
+        .mailbox_capacity(100)
+        .spill_to_disk(path.clone(), 64 * 1024 * 1024)
+        .start(Ingest{next: 0});
+
+    let spill_path = path.clone();
+    Arbiter::handle().spawn(future::lazy(move || {
+        // actor does not run until all messages are sent
+        for i in 0..10_000 {
+            addr.spill_send(Item(i)).unwrap();
+        }
+        assert!(spill_path.metadata().unwrap().len() > 0);
+        Ok(())
+    }));
+
+    sys.run();
+    assert!(!path.exists());
+}
@@ -0,0 +1,109 @@
+extern crate actix;
+extern crate futures;
+extern crate log;
+extern crate tokio_core;
+
+use std::sync::Mutex;
+use std::time::{Duration, Instant};
+use futures::{task, Async, Future, Poll, Stream};
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+
+struct RecordLogger(Mutex<Vec<String>>);
+
+impl log::Log for RecordLogger {
+    fn enabled(&self, _: &log::Metadata) -> bool {
+        true
+    }
+
+    fn log(&self, record: &log::Record) {
+        self.0.lock().unwrap().push(format!("{}", record.args()));
+    }
+
+    fn flush(&self) {}
+}
+
+/// Stream that wakes up its task without yielding items
+struct HotLoop;
+
+impl Stream for HotLoop {
+    type Item = ();
+    type Error = ();
+
+    fn poll(&mut self) -> Poll<Option<()>, ()> {
+        task::current().notify();
+        Ok(Async::NotReady)
+    }
+}
+
+struct Spinning;
+
+impl Actor for Spinning {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.set_spin_detection(Some(1000));
+        Spinning::add_stream(HotLoop, ctx);
+    }
+}
+
+impl StreamHandler<(), ()> for Spinning {
+    fn handle(&mut self, _: (), _: &mut Context<Self>) {}
+}
+
+struct Healthy(usize);
+
+impl Actor for Healthy {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        ctx.set_spin_detection(Some(1000));
+        ctx.run_interval_at(Instant::now(), Duration::from_millis(5), |act, _| act.0 += 1);
+    }
+}
+
+struct GetStats;
+
+impl ResponseType for GetStats {
+    type Item = actix::PollStats;
+    type Error = ();
+}
+
+impl Handler<GetStats> for Spinning {
+    type Result = MessageResult<GetStats>;
+
+    fn handle(&mut self, _: GetStats, ctx: &mut Context<Self>) -> Self::Result {
+        Ok(ctx.poll_stats())
+    }
+}
+
+#[test]
+fn test_spin_detection() {
+    let logger: &'static RecordLogger = Box::leak(Box::new(RecordLogger(Mutex::new(Vec::new()))));
+    log::set_logger(logger).unwrap();
+    log::set_max_level(log::LevelFilter::Warn);
+
+    let sys = System::new("test");
+    let spinning: Address<_> = Spinning::build().name("spinning").start(Spinning);
+    let _: Address<_> = Healthy::build().name("healthy").start(Healthy(0));
+
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(1500), Arbiter::handle()).unwrap()
+            .then(move |_| spinning.call_fut(GetStats))
+            .then(|res| {
+                let stats = res.unwrap().unwrap();
+                assert!(stats.polls > 1000);
+                assert!(stats.spurious > 1000);
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                Ok(())
+            }));
+
+    sys.run();
+
+    // single reporting interval passed
+    let records = logger.0.lock().unwrap();
+    let spinning: Vec<_> = records.iter().filter(|rec| rec.contains("spinning")).collect();
+    assert_eq!(spinning.len(), 1, "{:?}", spinning);
+    assert!(spinning[0].starts_with("Actor spinning is spinning"));
+    assert!(!records.iter().any(|rec| rec.contains("healthy")));
+}
@@ -2,9 +2,9 @@ extern crate actix;
 extern crate futures;
 extern crate tokio_core;
 
-use std::sync::Arc;
+use std::sync::{Arc, Mutex};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use std::time::Duration;
+use std::time::{Duration, Instant};
 use futures::{future, Future};
 use tokio_core::reactor::Timeout;
 use actix::prelude::*;
@@ -55,13 +55,13 @@ fn test_supervisor_restart() {
 
     let addr: Address<_> = actix::Supervisor::start(
         move |_| MyActor(starts2, restarts2, messages2));
-    addr.send(Die);
-    addr.send(Die);
+    addr.do_send(Die);
+    addr.do_send(Die);
 
     Arbiter::handle().spawn(
         Timeout::new(Duration::new(0, 100_000), Arbiter::handle()).unwrap()
             .then(|_| {
-                Arbiter::system().send(actix::msgs::SystemExit(0));
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
                 future::result(Ok(()))
             })
     );
@@ -71,3 +71,80 @@ fn test_supervisor_restart() {
     assert_eq!(restarts.load(Ordering::Relaxed), 2);
     assert_eq!(messages.load(Ordering::Relaxed), 2);
 }
+
+struct Flaky(Arc<Mutex<Vec<Instant>>>);
+
+impl Actor for Flaky {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _: &mut Context<Flaky>) {
+        self.0.lock().unwrap().push(Instant::now());
+    }
+}
+
+impl actix::Supervised for Flaky {}
+
+impl actix::Handler<Die> for Flaky {
+    type Result = ();
+
+    fn handle(&mut self, _: Die, ctx: &mut actix::Context<Flaky>) {
+        ctx.stop();
+    }
+}
+
+fn exit_after(ms: u64) {
+    Arbiter::handle().spawn(
+        Timeout::new(Duration::from_millis(ms), Arbiter::handle()).unwrap()
+            .then(|_| {
+                Arbiter::system().do_send(actix::msgs::SystemExit(0));
+                future::result(Ok(()))
+            }));
+}
+
+#[test]
+fn test_supervisor_restart_backoff() {
+    let sys = System::new("test");
+    let starts = Arc::new(Mutex::new(Vec::new()));
+    let starts2 = Arc::clone(&starts);
+
+    let strategy = actix::RestartStrategy::exponential(
+        Duration::from_millis(30), Duration::from_secs(1));
+    let addr: Address<_> = actix::Supervisor::start_with_strategy(
+        strategy, move |_| Flaky(starts2));
+    addr.do_send(Die);
+    addr.do_send(Die);
+    exit_after(200);
+    sys.run();
+
+    let starts = starts.lock().unwrap();
+    assert_eq!(starts.len(), 3);
+    assert!(starts[1] - starts[0] >= Duration::from_millis(30));
+    assert!(starts[2] - starts[1] >= Duration::from_millis(60));
+}
+
+#[test]
+fn test_supervisor_max_restarts() {
+    let sys = System::new("test");
+    let starts = Arc::new(AtomicUsize::new(0));
+    let restarts = Arc::new(AtomicUsize::new(0));
+    let messages = Arc::new(AtomicUsize::new(0));
+    let (starts2, restarts2, messages2) =
+        (Arc::clone(&starts), Arc::clone(&restarts), Arc::clone(&messages));
+
+    let addr: Address<_> = MyActor::build()
+        .restart_strategy(actix::RestartStrategy::fixed(Duration::from_millis(5))
+                          .jitter(0.5)
+                          .max_restarts(2, Duration::from_secs(10)))
+        .start_supervised(move |_| MyActor(starts2, restarts2, messages2));
+    for _ in 0..4 {
+        addr.do_send(Die);
+    }
+    exit_after(100);
+    sys.run();
+
+    // third failure stops actor for good
+    assert_eq!(starts.load(Ordering::Relaxed), 3);
+    assert_eq!(restarts.load(Ordering::Relaxed), 2);
+    assert_eq!(messages.load(Ordering::Relaxed), 3);
+    assert!(!addr.connected());
+}
@@ -4,7 +4,7 @@ extern crate tokio_core;
 
 use std::sync::{Arc, Condvar, Mutex};
 use std::sync::atomic::{AtomicUsize, Ordering};
-use futures::future;
+use futures::{future, Future};
 use actix::prelude::*;
 
 
@@ -41,7 +41,7 @@ impl Handler<Fibonacci> for SyncActor {
     fn handle(&mut self, msg: Fibonacci, _: &mut Self::Context) -> Self::Result {
         let old = self.messages.fetch_add(1, Ordering::Relaxed);
         if old == 4 {
-            self.addr.send(actix::msgs::SystemExit(0));
+            self.addr.do_send(actix::msgs::SystemExit(0));
         }
 
         if msg.0 == 0 {
@@ -93,7 +93,7 @@ fn test_sync() {
 
     Arbiter::handle().spawn_fn(move || {
         for n in 5..10 {
-            addr.send(Fibonacci(n));
+            addr.do_send(Fibonacci(n));
         }
         future::result(Ok(()))
     });
@@ -102,3 +102,70 @@ fn test_sync() {
     assert_eq!(counter.load(Ordering::Relaxed), 2, "Not started");
     assert_eq!(messages.load(Ordering::Relaxed), 5, "Wrong number of messages");
 }
+
+struct Waiter(Option<actix::sync::Listener<usize>>);
+
+impl Actor for Waiter {
+    type Context = Context<Self>;
+}
+
+struct Wait;
+
+impl ResponseType for Wait {
+    type Item = usize;
+    type Error = ();
+}
+
+impl Handler<Wait> for Waiter {
+    type Result = ResponseActFuture<Self, Wait>;
+
+    fn handle(&mut self, _: Wait, _: &mut Context<Self>) -> Self::Result {
+        let listener = self.0.take().unwrap();
+        Box::new(listener.into_actor(self)
+                 .map(|value, _, _| value * 2)
+                 .map_err(|_, _, _| ()))
+    }
+}
+
+#[test]
+fn test_oneshot_complete() {
+    let sys = System::new("test");
+    let (completer, listener) = actix::sync::oneshot();
+
+    let addr: Address<_> = Waiter(Some(listener)).start();
+    let subscriber: Box<actix::Subscriber<usize> + Send> = Box::new(completer.clone());
+    let handle = std::thread::spawn(move || {
+        std::thread::sleep(std::time::Duration::from_millis(10));
+        assert!(subscriber.send(21).is_ok());
+        assert!(completer.is_completed());
+        completer.complete(1)
+    });
+
+    Arbiter::handle().spawn(
+        addr.call_fut(Wait).then(|res| {
+            assert_eq!(res.unwrap(), Ok(42));
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            future::ok(())
+        }));
+
+    sys.run();
+    assert_eq!(handle.join().unwrap(), Err(1));
+}
+
+#[test]
+fn test_oneshot_canceled() {
+    let sys = System::new("test");
+    let (completer, listener) = actix::sync::oneshot::<usize>();
+    let completer2 = completer.clone();
+
+    Arbiter::handle().spawn(
+        listener.then(|res| {
+            assert!(res.is_err());
+            Arbiter::system().do_send(actix::msgs::SystemExit(0));
+            future::ok(())
+        }));
+
+    std::thread::spawn(move || drop(completer)).join().unwrap();
+    drop(completer2);
+    sys.run();
+}
@@ -0,0 +1,90 @@
+extern crate actix;
+extern crate futures;
+
+use std::thread;
+use std::sync::{Arc, Mutex};
+use futures::{future, Future};
+use futures::sync::oneshot;
+use actix::dev::*;
+use actix::msgs::SystemExit;
+
+struct Num(usize);
+
+impl ResponseType for Num {
+    type Item = usize;
+    type Error = ();
+}
+
+struct Collector(Arc<Mutex<Vec<usize>>>);
+
+impl Actor for Collector {
+    type Context = Context<Self>;
+}
+
+impl Handler<Num> for Collector {
+    type Result = MessageResult<Num>;
+
+    fn handle(&mut self, msg: Num, _: &mut Context<Self>) -> Self::Result {
+        let mut msgs = self.0.lock().unwrap();
+        msgs.push(msg.0);
+        Ok(msgs.len())
+    }
+}
+
+#[test]
+fn test_create_with_receiver() {
+    let sys = System::new("test");
+    let msgs = Arc::new(Mutex::new(Vec::new()));
+
+    let (tx, rx) = MailboxReceiver::channel(16);
+    let msgs2 = Arc::clone(&msgs);
+    let addr: SyncAddress<_> = Collector::create_with_receiver(rx, move |_| Collector(msgs2));
+
+    // envelopes are pushed from other thread
+    let (res_tx, res_rx) = oneshot::channel();
+    thread::spawn(move || {
+        for i in 0..3 {
+            let env: Envelope<Collector> = RemoteEnvelope::envelope(Num(i), None).into();
+            tx.try_send_envelope(env, false).ok().unwrap();
+        }
+        let env: Envelope<Collector> = RemoteEnvelope::envelope(Num(3), Some(res_tx)).into();
+        tx.try_send_envelope(env, false).ok().unwrap();
+    }).join().unwrap();
+
+    let res = Arc::new(Mutex::new(None));
+    let res2 = Arc::clone(&res);
+    Arbiter::handle().spawn(
+        res_rx.map_err(|_| ())
+            .and_then(move |r| Future::map(addr.call_fut(Num(4)), move |r2| (r, r2)).map_err(|_| ()))
+            .then(move |r| {
+                *res2.lock().unwrap() = Some(r);
+                Arbiter::system().do_send(SystemExit(0));
+                future::ok(())
+            }));
+    sys.run();
+
+    // address of the actor shares the channel
+    assert_eq!(res.lock().unwrap().take(), Some(Ok((Ok(4), Ok(5)))));
+    assert_eq!(*msgs.lock().unwrap(), vec![0, 1, 2, 3, 4]);
+}
+
+#[test]
+fn test_send_envelope_full() {
+    let _sys = System::new("test");
+    let (tx, rx) = MailboxReceiver::<Collector>::channel(1);
+
+    let env: Envelope<Collector> = RemoteEnvelope::envelope(Num(0), None).into();
+    assert!(tx.try_send_envelope(env, false).is_ok());
+    let env: Envelope<Collector> = RemoteEnvelope::envelope(Num(1), None).into();
+    match tx.try_send_envelope(env, false) {
+        Err(SendError::Full(_)) => (),
+        _ => panic!("mailbox should be full"),
+    }
+
+    drop(rx);
+    let env: Envelope<Collector> = RemoteEnvelope::envelope(Num(2), None).into();
+    match tx.try_send_envelope(env, false) {
+        Err(SendError::Closed(_)) => (),
+        _ => panic!("mailbox should be closed"),
+    }
+}
@@ -0,0 +1,169 @@
+extern crate actix;
+extern crate futures;
+extern crate tokio_core;
+
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::time::Duration;
+use futures::{future, Future};
+use tokio_core::reactor::Timeout;
+use actix::prelude::*;
+use actix::msgs::SystemExit;
+
+type Log = Rc<RefCell<Vec<String>>>;
+
+/// Stop worker, or panic if flag is set
+struct Die(bool);
+
+impl ResponseType for Die {
+    type Item = ();
+    type Error = ();
+}
+
+struct Worker {
+    name: &'static str,
+    log: Log,
+}
+
+impl Actor for Worker {
+    type Context = Context<Self>;
+
+    fn started(&mut self, _: &mut Context<Self>) {
+        self.log.borrow_mut().push(format!("{} started", self.name));
+    }
+}
+
+impl Supervised for Worker {
+    fn restarting(&mut self, _: &mut Context<Self>) {
+        self.log.borrow_mut().push(format!("{} restarting", self.name));
+    }
+}
+
+impl Handler<Die> for Worker {
+    type Result = ();
+
+    fn handle(&mut self, msg: Die, ctx: &mut Context<Self>) {
+        if msg.0 {
+            panic!("worker {} panics", self.name);
+        }
+        ctx.stop();
+    }
+}
+
+struct Parent {
+    tree: SupervisorTree<Parent>,
+    names: Vec<&'static str>,
+    workers: Rc<RefCell<Vec<Address<Worker>>>>,
+    log: Log,
+}
+
+impl Actor for Parent {
+    type Context = Context<Self>;
+
+    fn started(&mut self, ctx: &mut Context<Self>) {
+        for name in &self.names {
+            let log = Rc::clone(&self.log);
+            let (_, addr) = self.tree.start_child(ctx, move |_| Worker{name: name, log: log});
+            self.workers.borrow_mut().push(addr);
+        }
+    }
+
+    fn stopped(&mut self, _: &mut Context<Self>) {
+        self.log.borrow_mut().push("parent stopped".to_owned());
+    }
+}
+
+impl Handler<ChildTerminated> for Parent {
+    type Result = ();
+
+    fn handle(&mut self, msg: ChildTerminated, ctx: &mut Context<Self>) {
+        self.log.borrow_mut().push(format!("terminated {:?}", msg.reason));
+        self.tree.handle_terminated(msg, ctx);
+    }
+}
+
+fn delay(ms: u64) -> Timeout {
+    Timeout::new(Duration::from_millis(ms), Arbiter::handle()).unwrap()
+}
+
+/// Start parent with three children, kill children in order, return log
+fn run(tree: SupervisorTree<Parent>, kills: Vec<(usize, bool)>) -> Vec<String> {
+    let sys = System::new("test");
+    let log = Log::default();
+    let workers = Rc::new(RefCell::new(Vec::new()));
+
+    // parent lives as long as its address
+    let parent: Address<_> = Parent {
+        tree: tree,
+        names: vec!["a", "b", "c"],
+        workers: Rc::clone(&workers),
+        log: Rc::clone(&log),
+    }.start();
+
+    let log2 = Rc::clone(&log);
+    Arbiter::handle().spawn(
+        future::loop_fn(kills.into_iter(), move |mut kills| {
+            let workers = Rc::clone(&workers);
+            let log = Rc::clone(&log2);
+            delay(30).map(move |_| {
+                match kills.next() {
+                    Some((idx, panic)) => {
+                        log.borrow_mut().push("--".to_owned());
+                        workers.borrow()[idx].do_send(Die(panic));
+                        future::Loop::Continue(kills)
+                    }
+                    None => future::Loop::Break(()),
+                }
+            })
+        })
+            .then(move |_| {
+                drop(parent);
+                Arbiter::system().do_send(SystemExit(0));
+                Ok(())
+            }));
+    sys.run();
+
+    let log = log.borrow().clone();
+    log
+}
+
+fn strs(log: &[&str]) -> Vec<String> {
+    log.iter().map(|s| s.to_string()).collect()
+}
+
+#[test]
+fn test_tree_one_for_one() {
+    let log = run(SupervisorTree::new(TreeStrategy::OneForOne), vec![(1, false), (2, true)]);
+    assert_eq!(log, strs(&["a started", "b started", "c started",
+                           "--", "terminated Stopped", "b restarting", "b started",
+                           "--", "terminated Panicked", "c restarting", "c started",
+                           "parent stopped"]));
+}
+
+#[test]
+fn test_tree_one_for_all() {
+    let log = run(SupervisorTree::new(TreeStrategy::OneForAll), vec![(1, false)]);
+    assert_eq!(log, strs(&["a started", "b started", "c started",
+                           "--", "terminated Stopped", "a restarting", "a started",
+                           "b restarting", "b started", "c restarting", "c started",
+                           "parent stopped"]));
+}
+
+#[test]
+fn test_tree_rest_for_one() {
+    let log = run(SupervisorTree::new(TreeStrategy::RestForOne), vec![(1, true)]);
+    assert_eq!(log, strs(&["a started", "b started", "c started",
+                           "--", "terminated Panicked",
+                           "b restarting", "b started", "c restarting", "c started",
+                           "parent stopped"]));
+}
+
+#[test]
+fn test_tree_escalation() {
+    let tree = SupervisorTree::new(TreeStrategy::OneForOne).max_restarts(1, Duration::from_secs(10));
+    let log = run(tree, vec![(0, false), (0, false), (1, false)]);
+    assert_eq!(log, strs(&["a started", "b started", "c started",
+                           "--", "terminated Stopped", "a restarting", "a started",
+                           "--", "terminated Stopped", "parent stopped",
+                           "--"]));
+}